@@ -2,8 +2,9 @@ use std::fs::File;
 use std::io::Write;
 use std::sync::Arc;
 
-use criterion::{BenchmarkId, Criterion, criterion_group, criterion_main};
+use criterion::{BenchmarkId, Criterion, Throughput, criterion_group, criterion_main};
 
+use swiftbeaver::chunk::ScanChunk;
 use swiftbeaver::config;
 use swiftbeaver::evidence::RawFileSource;
 use swiftbeaver::metadata::{self, MetadataBackendKind};
@@ -47,7 +48,7 @@ fn run_pipeline(input_path: &std::path::Path, max_files: Option<u64>) -> pipelin
     let sig_scanner = scanner::build_signature_scanner(&cfg, false).expect("scanner");
     let sig_scanner: Arc<dyn swiftbeaver::scanner::SignatureScanner> = Arc::from(sig_scanner);
 
-    let carve_registry = Arc::new(util::build_carve_registry(&cfg).expect("registry"));
+    let carve_registry = Arc::new(util::build_carve_registry(&cfg, false).expect("registry"));
 
     pipeline::run_pipeline(
         &cfg,
@@ -99,5 +100,70 @@ fn bench_throughput(c: &mut Criterion) {
     group.finish();
 }
 
-criterion_group!(benches, bench_throughput);
+/// Pseudo-random data with the byte runs that trip up first-byte scanning:
+/// zero runs and `RIFF` every few KiB.
+fn scan_data(len: usize) -> Vec<u8> {
+    let mut state = 0x9E37_79B9_7F4A_7C15u64;
+    let mut data: Vec<u8> = (0..len)
+        .map(|_| {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            state as u8
+        })
+        .collect();
+    for at in (0..len.saturating_sub(64)).step_by(4096) {
+        data[at..at + 32].fill(0);
+        data[at + 32..at + 36].copy_from_slice(b"RIFF");
+    }
+    data
+}
+
+/// The previous scanner: one `memchr` pass over the chunk per pattern.
+fn per_pattern_scan(patterns: &[Vec<u8>], data: &[u8]) -> usize {
+    let mut hits = 0usize;
+    for pattern in patterns {
+        let mut pos = 0usize;
+        while let Some(found) = memchr::memchr(pattern[0], &data[pos..]) {
+            let idx = pos + found;
+            if data[idx..].starts_with(pattern) {
+                hits += 1;
+            }
+            pos = idx + 1;
+        }
+    }
+    hits
+}
+
+fn bench_signature_scan(c: &mut Criterion) {
+    let cfg = config::load_config(None).expect("config").config;
+    let scanner = scanner::build_signature_scanner(&cfg, false).expect("scanner");
+    let patterns: Vec<Vec<u8>> = scanner::pattern::compile_patterns(&cfg)
+        .expect("patterns")
+        .iter()
+        .map(|pattern| pattern.search_literal().1.to_vec())
+        .collect();
+
+    let size = 64 * 1024 * 1024usize;
+    let data = scan_data(size);
+    let chunk = ScanChunk {
+        id: 0,
+        start: 0,
+        length: size as u64,
+        valid_length: size as u64,
+    };
+
+    let mut group = c.benchmark_group("signature_scan");
+    group.throughput(Throughput::Bytes(size as u64));
+    group.sample_size(10);
+    group.bench_function("aho_corasick", |b| {
+        b.iter(|| scanner.scan_chunk(&chunk, &data).len());
+    });
+    group.bench_function("per_pattern_memchr", |b| {
+        b.iter(|| per_pattern_scan(&patterns, &data));
+    });
+    group.finish();
+}
+
+criterion_group!(benches, bench_throughput, bench_signature_scan);
 criterion_main!(benches);
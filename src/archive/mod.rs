@@ -0,0 +1,88 @@
+//! Member listings of carved archives, for the `archive_entries` metadata.
+//!
+//! Names, sizes, timestamps, CRCs and encryption flags come from the
+//! archive's own directory or member headers, so they are recorded even
+//! when the members cannot be extracted: encrypted, compressed with an
+//! unsupported method or cut off by the carve. Only headers are read;
+//! member data is skipped over.
+
+mod ole;
+mod rar;
+pub(crate) mod sevenz;
+mod tar;
+mod zip;
+
+use std::path::Path;
+
+use crate::evidence::{EvidenceError, EvidenceSource, RawFileSource};
+
+/// Most entries listed for one archive; corrupt directories can claim
+/// far more.
+const MAX_ENTRIES: usize = 1_000_000;
+
+/// One member named in an archive directory.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Entry {
+    /// Member path inside the archive.
+    pub name: String,
+    pub compressed_size: Option<u64>,
+    pub uncompressed_size: Option<u64>,
+    pub modified: Option<chrono::NaiveDateTime>,
+    pub crc32: Option<u32>,
+    pub encrypted: bool,
+    /// Compression method as the format names it (`deflate`, `lzma2`, ...).
+    pub compression_method: Option<String>,
+}
+
+/// Whether `file_type` names an archive whose members can be listed.
+pub fn is_listable(file_type: &str) -> bool {
+    matches!(
+        file_type,
+        "zip"
+            | "docx"
+            | "xlsx"
+            | "pptx"
+            | "odt"
+            | "ods"
+            | "odp"
+            | "epub"
+            | "tar"
+            | "rar"
+            | "7z"
+            | "ole"
+            | "doc"
+            | "xls"
+            | "ppt"
+    )
+}
+
+/// List the members of the carved archive at `path`.
+pub fn list_file(path: &Path, file_type: &str) -> Result<Vec<Entry>, EvidenceError> {
+    if !is_listable(file_type) {
+        return Ok(Vec::new());
+    }
+    let source = RawFileSource::open(path)?;
+    Ok(list_entries(&source, file_type))
+}
+
+/// List the members of an archive starting at offset 0 of `source`.
+pub fn list_entries(source: &dyn EvidenceSource, file_type: &str) -> Vec<Entry> {
+    match file_type {
+        "zip" | "docx" | "xlsx" | "pptx" | "odt" | "ods" | "odp" | "epub" => zip::entries(source),
+        "tar" => tar::entries(source),
+        "rar" => rar::entries(source),
+        "7z" => sevenz::entries(source),
+        "ole" | "doc" | "xls" | "ppt" => ole::entries(source),
+        _ => Vec::new(),
+    }
+}
+
+/// `len` bytes at `offset`, or `None` when the source ends first.
+fn read_exact_at(source: &dyn EvidenceSource, offset: u64, len: usize) -> Option<Vec<u8>> {
+    if offset.checked_add(len as u64)? > source.len() {
+        return None;
+    }
+    let mut buf = vec![0u8; len];
+    let n = source.read_at(offset, &mut buf).ok()?;
+    (n == len).then_some(buf)
+}
@@ -0,0 +1,78 @@
+//! OLE compound file streams.
+
+use super::{Entry, MAX_ENTRIES, read_exact_at};
+use crate::carve::ole::{ENTRY_STORAGE, ENTRY_STREAM, parse_ole_header, read_directory};
+use crate::evidence::EvidenceSource;
+use crate::parsers::time::filetime_to_datetime;
+
+const NO_STREAM: u32 = 0xFFFF_FFFF;
+
+/// Every stream reachable from the root storage, named by its storage
+/// path (`ObjectPool/_1234/Ole10Native`) and sorted by name.
+pub(super) fn entries(source: &dyn EvidenceSource) -> Vec<Entry> {
+    let Some(header) = read_exact_at(source, 0, 512) else {
+        return Vec::new();
+    };
+    let Ok((_, sector_size)) = parse_ole_header(&header) else {
+        return Vec::new();
+    };
+    let Some(directory) = read_directory(source, 0, &header, sector_size, source.len()) else {
+        return Vec::new();
+    };
+    let Some(root) = directory.first() else {
+        return Vec::new();
+    };
+
+    let mut entries = Vec::new();
+    let mut seen = vec![false; directory.len()];
+    let mut stack = vec![(root.child, String::new())];
+    while let Some((id, prefix)) = stack.pop() {
+        if id == NO_STREAM || entries.len() >= MAX_ENTRIES {
+            continue;
+        }
+        // Sibling and child links of a corrupt directory can loop
+        let Some(visited) = seen.get_mut(id as usize) else {
+            continue;
+        };
+        if std::mem::replace(visited, true) {
+            continue;
+        }
+        let record = &directory[id as usize];
+        stack.push((record.left, prefix.clone()));
+        stack.push((record.right, prefix.clone()));
+        let path = format!("{prefix}{}", record.name);
+        match record.entry_type {
+            ENTRY_STORAGE => stack.push((record.child, format!("{path}/"))),
+            ENTRY_STREAM => entries.push(Entry {
+                name: path,
+                uncompressed_size: Some(record.size),
+                modified: filetime_to_datetime(record.modified),
+                ..Entry::default()
+            }),
+            _ => {}
+        }
+    }
+    entries.sort_by(|a, b| a.name.cmp(&b.name));
+    entries
+}
+
+#[cfg(test)]
+mod tests {
+    use super::entries;
+    use crate::evidence::RawFileSource;
+
+    #[test]
+    fn lists_workbook_stream() {
+        let path = std::path::Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/golden_image/samples/documents/file_example_XLS_50.xls");
+        let source = RawFileSource::open(&path).expect("open");
+        let found = entries(&source);
+        let workbook = found
+            .iter()
+            .find(|entry| entry.name == "Workbook")
+            .expect("Workbook stream");
+        assert!(workbook.uncompressed_size.unwrap_or(0) > 0);
+        assert!(!workbook.encrypted);
+        assert!(found.windows(2).all(|pair| pair[0].name <= pair[1].name));
+    }
+}
@@ -0,0 +1,269 @@
+//! RAR 4 and RAR 5 file headers.
+
+use super::{Entry, MAX_ENTRIES, read_exact_at};
+use crate::carve::rar::{
+    MAX_RAR5_HEADER_BYTES, RAR4_FILE_ENCRYPTED, RAR4_HEAD_END, RAR4_HEAD_FILE, RAR4_HEAD_MAIN,
+    RAR4_MAGIC, RAR4_MAIN_ENCRYPTED, RAR5_HEAD_ENCRYPTION, RAR5_HEAD_END, RAR5_HEAD_FILE,
+    RAR5_MAGIC, has_encryption_record, read_varint_buf,
+};
+use crate::evidence::EvidenceSource;
+use crate::parsers::time::{dos_to_datetime, unix_seconds_to_datetime};
+
+const RAR4_FILE_LARGE: u16 = 0x0100;
+const RAR4_FILE_UNICODE: u16 = 0x0200;
+/// Block flag: ADD_SIZE bytes of data follow the header
+const RAR4_LONG_BLOCK: u16 = 0x8000;
+
+/// Method names shared by both versions, by method number.
+const METHODS: [&str; 6] = ["store", "fastest", "fast", "normal", "good", "best"];
+
+/// File headers up to the end-of-archive header. Archives with encrypted
+/// headers list nothing.
+pub(super) fn entries(source: &dyn EvidenceSource) -> Vec<Entry> {
+    let Some(magic) = read_exact_at(source, 0, RAR5_MAGIC.len()) else {
+        return Vec::new();
+    };
+    if magic == RAR5_MAGIC {
+        rar5_entries(source)
+    } else if magic[..RAR4_MAGIC.len()] == RAR4_MAGIC {
+        rar4_entries(source)
+    } else {
+        Vec::new()
+    }
+}
+
+fn rar4_entries(source: &dyn EvidenceSource) -> Vec<Entry> {
+    let mut entries = Vec::new();
+    let mut offset = RAR4_MAGIC.len() as u64;
+    while entries.len() < MAX_ENTRIES {
+        let Some(block) = read_exact_at(source, offset, 7) else {
+            break;
+        };
+        let head_type = block[2];
+        let flags = u16::from_le_bytes([block[3], block[4]]);
+        let head_size = u16::from_le_bytes([block[5], block[6]]) as usize;
+        if head_size < 7
+            || head_type == RAR4_HEAD_END
+            || (head_type == RAR4_HEAD_MAIN && flags & RAR4_MAIN_ENCRYPTED != 0)
+        {
+            break;
+        }
+        let Some(header) = read_exact_at(source, offset, head_size) else {
+            break;
+        };
+        let field32 = |at: usize| {
+            header
+                .get(at..at + 4)
+                .map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+        };
+        let mut data_size = if flags & RAR4_LONG_BLOCK != 0 || head_type == RAR4_HEAD_FILE {
+            field32(7).map_or(0, u64::from)
+        } else {
+            0
+        };
+
+        if head_type == RAR4_HEAD_FILE {
+            let Some(entry) = rar4_file(&header, flags) else {
+                break;
+            };
+            if let Some(size) = entry.compressed_size {
+                data_size = size;
+            }
+            entries.push(entry);
+        }
+        offset = offset
+            .saturating_add(head_size as u64)
+            .saturating_add(data_size);
+    }
+    entries
+}
+
+fn rar4_file(header: &[u8], flags: u16) -> Option<Entry> {
+    let field16 = |at: usize| {
+        header
+            .get(at..at + 2)
+            .map(|b| u16::from_le_bytes([b[0], b[1]]))
+    };
+    let field32 = |at: usize| {
+        header
+            .get(at..at + 4)
+            .map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+    };
+    let mut packed = u64::from(field32(7)?);
+    let mut unpacked = u64::from(field32(11)?);
+    let name_size = field16(26)? as usize;
+    let mut name_start = 32;
+    if flags & RAR4_FILE_LARGE != 0 {
+        packed |= u64::from(field32(32)?) << 32;
+        unpacked |= u64::from(field32(36)?) << 32;
+        name_start = 40;
+    }
+    let mut name = header.get(name_start..name_start + name_size)?;
+    // Unicode names follow the ASCII name after a NUL
+    if flags & RAR4_FILE_UNICODE != 0 {
+        name = name.split(|&b| b == 0).next().unwrap_or_default();
+    }
+    let time = field32(20)?;
+    Some(Entry {
+        name: String::from_utf8_lossy(name).into_owned(),
+        compressed_size: Some(packed),
+        uncompressed_size: Some(unpacked),
+        modified: dos_to_datetime((time >> 16) as u16, time as u16),
+        crc32: field32(16),
+        encrypted: flags & RAR4_FILE_ENCRYPTED != 0,
+        compression_method: Some(method_name(u64::from(header[25].wrapping_sub(0x30)))),
+    })
+}
+
+fn rar5_entries(source: &dyn EvidenceSource) -> Vec<Entry> {
+    let mut entries = Vec::new();
+    let mut offset = RAR5_MAGIC.len() as u64;
+    while entries.len() < MAX_ENTRIES {
+        // CRC32, then the header size as a vint of up to 3 bytes
+        let Some(prefix) = read_exact_at(source, offset, 7) else {
+            break;
+        };
+        let mut idx = 4usize;
+        let Some(header_size) = read_varint_buf(&prefix, &mut idx) else {
+            break;
+        };
+        if header_size == 0 || header_size > MAX_RAR5_HEADER_BYTES {
+            break;
+        }
+        let Some(header) = read_exact_at(source, offset + idx as u64, header_size as usize) else {
+            break;
+        };
+
+        let mut pos = 0usize;
+        let Some(header_type) = read_varint_buf(&header, &mut pos) else {
+            break;
+        };
+        let flags = read_varint_buf(&header, &mut pos).unwrap_or(0);
+        let extra_size = if flags & 0x01 != 0 {
+            read_varint_buf(&header, &mut pos).unwrap_or(0)
+        } else {
+            0
+        };
+        let data_size = if flags & 0x02 != 0 {
+            read_varint_buf(&header, &mut pos).unwrap_or(0)
+        } else {
+            0
+        };
+        if header_type == RAR5_HEAD_END || header_type == RAR5_HEAD_ENCRYPTION {
+            break;
+        }
+        if header_type == RAR5_HEAD_FILE {
+            let extra_start = header.len().saturating_sub(extra_size as usize);
+            let Some(mut entry) = rar5_file(&header[..extra_start], pos) else {
+                break;
+            };
+            entry.compressed_size = Some(data_size);
+            entry.encrypted = has_encryption_record(&header[extra_start..]);
+            entries.push(entry);
+        }
+        offset = offset
+            .saturating_add(idx as u64)
+            .saturating_add(header_size)
+            .saturating_add(data_size);
+    }
+    entries
+}
+
+/// The file-specific fields that follow the common header fields at `pos`.
+fn rar5_file(header: &[u8], mut pos: usize) -> Option<Entry> {
+    let field32 = |at: usize| {
+        header
+            .get(at..at + 4)
+            .map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+    };
+    let file_flags = read_varint_buf(header, &mut pos)?;
+    let unpacked = read_varint_buf(header, &mut pos)?;
+    let _attributes = read_varint_buf(header, &mut pos)?;
+    let mut modified = None;
+    if file_flags & 0x02 != 0 {
+        modified = unix_seconds_to_datetime(i64::from(field32(pos)?));
+        pos += 4;
+    }
+    let mut crc32 = None;
+    if file_flags & 0x04 != 0 {
+        crc32 = Some(field32(pos)?);
+        pos += 4;
+    }
+    let compression = read_varint_buf(header, &mut pos)?;
+    let _host_os = read_varint_buf(header, &mut pos)?;
+    let name_len = read_varint_buf(header, &mut pos)? as usize;
+    let name = header.get(pos..pos.checked_add(name_len)?)?;
+    Some(Entry {
+        name: String::from_utf8_lossy(name).into_owned(),
+        // Flag 0x08: unpacked size unknown
+        uncompressed_size: (file_flags & 0x08 == 0).then_some(unpacked),
+        modified,
+        crc32,
+        compression_method: Some(method_name((compression >> 7) & 0x07)),
+        ..Entry::default()
+    })
+}
+
+fn method_name(method: u64) -> String {
+    METHODS
+        .get(method as usize)
+        .map_or_else(|| format!("method {method}"), |name| name.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::entries;
+    use crate::evidence::{MemorySource, RawFileSource};
+
+    #[test]
+    fn lists_rar5_file_headers() {
+        let path = std::path::Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/golden_image/samples/archives/test.rar");
+        let source = RawFileSource::open(&path).expect("open");
+        let found = entries(&source);
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].name, "tmp/7z_test.txt");
+        assert!(!found[0].encrypted);
+        assert!(found[0].uncompressed_size.is_some());
+        assert!(found[0].crc32.is_some());
+    }
+
+    #[test]
+    fn lists_rar4_file_headers() {
+        let mut data = vec![0x52, 0x61, 0x72, 0x21, 0x1A, 0x07, 0x00];
+        // Main header
+        data.extend_from_slice(&[0, 0, 0x73, 0, 0, 13, 0, 0, 0, 0, 0, 0, 0]);
+        let name = b"secret.doc";
+        let mut file = vec![0, 0, 0x74];
+        file.extend_from_slice(&0x8004u16.to_le_bytes());
+        file.extend_from_slice(&((32 + name.len()) as u16).to_le_bytes());
+        file.extend_from_slice(&5u32.to_le_bytes());
+        file.extend_from_slice(&9u32.to_le_bytes());
+        file.push(2);
+        file.extend_from_slice(&0xDEADBEEFu32.to_le_bytes());
+        // 2020-05-17 05:30:08
+        file.extend_from_slice(
+            &((5u32 << 11 | 30 << 5 | 4) | (40 << 9 | 5 << 5 | 17) << 16).to_le_bytes(),
+        );
+        file.extend_from_slice(&[29, 0x33]);
+        file.extend_from_slice(&(name.len() as u16).to_le_bytes());
+        file.extend_from_slice(&[0x20, 0, 0, 0]);
+        file.extend_from_slice(name);
+        data.extend_from_slice(&file);
+        data.extend_from_slice(b"ABCDE");
+        data.extend_from_slice(&[0x3D, 0x7B, 0x7B, 0x00, 0x40, 0x07, 0x00]);
+
+        let found = entries(&MemorySource::new(data));
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].name, "secret.doc");
+        assert_eq!(found[0].compressed_size, Some(5));
+        assert_eq!(found[0].uncompressed_size, Some(9));
+        assert_eq!(found[0].crc32, Some(0xDEADBEEF));
+        assert!(found[0].encrypted);
+        assert_eq!(found[0].compression_method.as_deref(), Some("normal"));
+        assert_eq!(
+            found[0].modified.map(|time| time.to_string()).as_deref(),
+            Some("2020-05-17 05:30:08")
+        );
+    }
+}
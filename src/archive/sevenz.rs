@@ -0,0 +1,695 @@
+//! 7z headers.
+//!
+//! The end header holds the folders (compressed streams and their coders),
+//! the sizes and CRCs of the files unpacked from each folder, and the file
+//! names and times. 7-Zip usually compresses it too (an "encoded header"):
+//! headers compressed with LZMA or LZMA2 are unpacked here, while headers
+//! encrypted with AES (`-mhe`) cannot be read and list nothing.
+
+use lzma_rs::decompress::{Options, UnpackedSize};
+
+use super::{Entry, MAX_ENTRIES, read_exact_at};
+use crate::carve::Encryption;
+use crate::carve::sevenz::{SEVENZ_HEADER_LEN, SEVENZ_MAGIC};
+use crate::evidence::EvidenceSource;
+use crate::parsers::time::filetime_to_datetime;
+
+const K_END: u8 = 0x00;
+const K_HEADER: u8 = 0x01;
+const K_ARCHIVE_PROPERTIES: u8 = 0x02;
+const K_ADDITIONAL_STREAMS_INFO: u8 = 0x03;
+const K_MAIN_STREAMS_INFO: u8 = 0x04;
+const K_FILES_INFO: u8 = 0x05;
+const K_PACK_INFO: u8 = 0x06;
+const K_UNPACK_INFO: u8 = 0x07;
+const K_SUBSTREAMS_INFO: u8 = 0x08;
+const K_SIZE: u8 = 0x09;
+const K_CRC: u8 = 0x0A;
+const K_FOLDER: u8 = 0x0B;
+const K_CODERS_UNPACK_SIZE: u8 = 0x0C;
+const K_NUM_UNPACK_STREAM: u8 = 0x0D;
+const K_EMPTY_STREAM: u8 = 0x0E;
+const K_NAME: u8 = 0x11;
+const K_MTIME: u8 = 0x14;
+const K_ENCODED_HEADER: u8 = 0x17;
+
+const CODER_LZMA: &[u8] = &[0x03, 0x01, 0x01];
+const CODER_LZMA2: &[u8] = &[0x21];
+const CODER_AES: &[u8] = &[0x06, 0xF1, 0x07, 0x01];
+
+/// Largest end header read or unpacked
+const MAX_HEADER_SIZE: u64 = 64 * 1024 * 1024;
+
+/// Files named in the end header, in header order.
+pub(super) fn entries(source: &dyn EvidenceSource) -> Vec<Entry> {
+    list(source).unwrap_or_default()
+}
+
+/// Whether the archive is encrypted: `headers` when the encoded header is
+/// itself encrypted, `full` when a folder of files is, and `unknown` when
+/// the end header cannot be read.
+pub(crate) fn encryption(source: &dyn EvidenceSource) -> Encryption {
+    let Some(mut header) = end_header(source) else {
+        return Encryption::Unknown;
+    };
+    for _ in 0..4 {
+        let mut reader = Reader::new(&header);
+        match reader.byte() {
+            Some(K_HEADER) => {
+                return match read_header(&mut reader) {
+                    Some(entries) if entries.iter().any(|entry| entry.encrypted) => {
+                        Encryption::Full
+                    }
+                    Some(_) => Encryption::None,
+                    None => Encryption::Unknown,
+                };
+            }
+            Some(K_ENCODED_HEADER) => {
+                let mut peek = Reader::new(&header[1..]);
+                if read_streams_info(&mut peek)
+                    .is_some_and(|info| info.folders.iter().any(Folder::encrypted))
+                {
+                    return Encryption::Headers;
+                }
+                match decode_header(source, &mut reader) {
+                    Some(decoded) => header = decoded,
+                    None => return Encryption::Unknown,
+                }
+            }
+            _ => return Encryption::Unknown,
+        }
+    }
+    Encryption::Unknown
+}
+
+fn list(source: &dyn EvidenceSource) -> Option<Vec<Entry>> {
+    let mut header = end_header(source)?;
+    // An encoded header unpacks to a plain one
+    for _ in 0..4 {
+        let mut reader = Reader::new(&header);
+        match reader.byte()? {
+            K_HEADER => return read_header(&mut reader),
+            K_ENCODED_HEADER => header = decode_header(source, &mut reader)?,
+            _ => return None,
+        }
+    }
+    None
+}
+
+/// The end header named by the start header, as stored.
+fn end_header(source: &dyn EvidenceSource) -> Option<Vec<u8>> {
+    let start = read_exact_at(source, 0, SEVENZ_HEADER_LEN)?;
+    if start[..SEVENZ_MAGIC.len()] != SEVENZ_MAGIC {
+        return None;
+    }
+    let mut reader = Reader::new(&start[12..28]);
+    let offset = reader.u64()?;
+    let size = reader.u64()?;
+    if size == 0 || size > MAX_HEADER_SIZE {
+        return None;
+    }
+    let header_offset = (SEVENZ_HEADER_LEN as u64).checked_add(offset)?;
+    read_exact_at(source, header_offset, size as usize)
+}
+
+fn read_header(reader: &mut Reader) -> Option<Vec<Entry>> {
+    let mut streams = StreamsInfo::default();
+    let mut files = Files::default();
+    loop {
+        match reader.byte()? {
+            K_END => break,
+            K_ARCHIVE_PROPERTIES => loop {
+                if reader.byte()? == K_END {
+                    break;
+                }
+                let len = reader.count()?;
+                reader.bytes(len)?;
+            },
+            K_ADDITIONAL_STREAMS_INFO => {
+                read_streams_info(reader)?;
+            }
+            K_MAIN_STREAMS_INFO => streams = read_streams_info(reader)?,
+            K_FILES_INFO => files = read_files(reader)?,
+            _ => return None,
+        }
+    }
+
+    let mut entries = Vec::with_capacity(files.names.len());
+    let mut stream = 0usize;
+    for (index, name) in files.names.into_iter().enumerate() {
+        let mut entry = Entry {
+            name,
+            modified: files.modified.get(index).copied().flatten(),
+            ..Entry::default()
+        };
+        if files.empty_stream.get(index).copied().unwrap_or(false) {
+            entry.uncompressed_size = Some(0);
+        } else if let Some(&folder_index) = streams.stream_folders.get(stream) {
+            let folder = &streams.folders[folder_index];
+            entry.uncompressed_size = streams.sizes.get(stream).copied();
+            entry.crc32 = streams.crcs.get(stream).copied().flatten();
+            // A file alone in its folder owns the packed streams
+            if streams.streams_per_folder[folder_index] == 1 {
+                entry.compressed_size = streams.packed_size(folder_index);
+            }
+            entry.encrypted = folder.encrypted();
+            entry.compression_method = Some(
+                folder
+                    .coders
+                    .iter()
+                    .map(|coder| coder_name(&coder.id))
+                    .collect::<Vec<_>>()
+                    .join("+"),
+            );
+            stream += 1;
+        }
+        entries.push(entry);
+    }
+    Some(entries)
+}
+
+/// Unpack an encoded header: a streams description of one folder whose
+/// single LZMA or LZMA2 coder yields the real header.
+fn decode_header(source: &dyn EvidenceSource, reader: &mut Reader) -> Option<Vec<u8>> {
+    let info = read_streams_info(reader)?;
+    let [folder] = info.folders.as_slice() else {
+        return None;
+    };
+    let [coder] = folder.coders.as_slice() else {
+        return None;
+    };
+    let packed_size = *info.pack_sizes.first()?;
+    let size = folder.unpack_size()?;
+    if packed_size > MAX_HEADER_SIZE || size > MAX_HEADER_SIZE {
+        return None;
+    }
+    let offset = (SEVENZ_HEADER_LEN as u64).checked_add(info.pack_pos)?;
+    let packed = read_exact_at(source, offset, packed_size as usize)?;
+    let mut out = Vec::with_capacity(size as usize);
+    if coder.id == CODER_LZMA {
+        // The coder properties are the LZMA header without its size field
+        let mut input = coder.properties.clone();
+        input.extend_from_slice(&packed);
+        let options = Options {
+            unpacked_size: UnpackedSize::UseProvided(Some(size)),
+            ..Options::default()
+        };
+        lzma_rs::lzma_decompress_with_options(&mut input.as_slice(), &mut out, &options).ok()?;
+    } else if coder.id == CODER_LZMA2 {
+        lzma_rs::lzma2_decompress(&mut packed.as_slice(), &mut out).ok()?;
+    } else {
+        return None;
+    }
+    Some(out)
+}
+
+struct Coder {
+    id: Vec<u8>,
+    properties: Vec<u8>,
+}
+
+struct Folder {
+    coders: Vec<Coder>,
+    /// Output streams of all coders, and the ones bound to another
+    /// coder's input; the remaining one is the folder's output.
+    out_streams: usize,
+    bound_out: Vec<u64>,
+    packed_streams: usize,
+    unpack_sizes: Vec<u64>,
+    crc: Option<u32>,
+}
+
+impl Folder {
+    fn encrypted(&self) -> bool {
+        self.coders.iter().any(|coder| coder.id == CODER_AES)
+    }
+
+    fn unpack_size(&self) -> Option<u64> {
+        (0..self.out_streams as u64)
+            .find(|index| !self.bound_out.contains(index))
+            .and_then(|index| self.unpack_sizes.get(index as usize).copied())
+    }
+}
+
+#[derive(Default)]
+struct StreamsInfo {
+    pack_pos: u64,
+    pack_sizes: Vec<u64>,
+    folders: Vec<Folder>,
+    streams_per_folder: Vec<u64>,
+    /// Per unpacked stream: its size, CRC and folder.
+    sizes: Vec<u64>,
+    crcs: Vec<Option<u32>>,
+    stream_folders: Vec<usize>,
+}
+
+impl StreamsInfo {
+    /// Total size of the packed streams a folder reads.
+    fn packed_size(&self, folder_index: usize) -> Option<u64> {
+        let first: usize = self.folders[..folder_index]
+            .iter()
+            .map(|folder| folder.packed_streams)
+            .sum();
+        let count = self.folders[folder_index].packed_streams;
+        self.pack_sizes
+            .get(first..first + count)
+            .map(|sizes| sizes.iter().sum())
+    }
+}
+
+fn read_streams_info(reader: &mut Reader) -> Option<StreamsInfo> {
+    let mut info = StreamsInfo::default();
+    let mut substreams = false;
+    loop {
+        match reader.byte()? {
+            K_END => break,
+            K_PACK_INFO => read_pack_info(reader, &mut info)?,
+            K_UNPACK_INFO => read_unpack_info(reader, &mut info)?,
+            K_SUBSTREAMS_INFO => {
+                read_substreams_info(reader, &mut info)?;
+                substreams = true;
+            }
+            _ => return None,
+        }
+    }
+    // Without a substreams description each folder unpacks to one stream
+    if !substreams {
+        info.streams_per_folder = vec![1; info.folders.len()];
+        info.sizes = info
+            .folders
+            .iter()
+            .map(|f| f.unpack_size().unwrap_or(0))
+            .collect();
+        info.crcs = info.folders.iter().map(|folder| folder.crc).collect();
+        info.stream_folders = (0..info.folders.len()).collect();
+    }
+    Some(info)
+}
+
+fn read_pack_info(reader: &mut Reader, info: &mut StreamsInfo) -> Option<()> {
+    info.pack_pos = reader.number()?;
+    let count = reader.count()?;
+    loop {
+        match reader.byte()? {
+            K_END => return Some(()),
+            K_SIZE => {
+                info.pack_sizes = (0..count).map(|_| reader.number()).collect::<Option<_>>()?
+            }
+            K_CRC => {
+                reader.digests(count)?;
+            }
+            _ => return None,
+        }
+    }
+}
+
+fn read_unpack_info(reader: &mut Reader, info: &mut StreamsInfo) -> Option<()> {
+    if reader.byte()? != K_FOLDER {
+        return None;
+    }
+    let count = reader.count()?;
+    // Folders stored in another stream ("external") are not supported
+    if reader.byte()? != 0 {
+        return None;
+    }
+    info.folders = (0..count)
+        .map(|_| read_folder(reader))
+        .collect::<Option<_>>()?;
+    if reader.byte()? != K_CODERS_UNPACK_SIZE {
+        return None;
+    }
+    for folder in &mut info.folders {
+        folder.unpack_sizes = (0..folder.out_streams)
+            .map(|_| reader.number())
+            .collect::<Option<_>>()?;
+    }
+    loop {
+        match reader.byte()? {
+            K_END => return Some(()),
+            K_CRC => {
+                for (folder, crc) in info.folders.iter_mut().zip(reader.digests(count)?) {
+                    folder.crc = crc;
+                }
+            }
+            _ => return None,
+        }
+    }
+}
+
+fn read_folder(reader: &mut Reader) -> Option<Folder> {
+    let coder_count = reader.count()?;
+    let mut coders = Vec::with_capacity(coder_count);
+    let mut in_streams = 0usize;
+    let mut out_streams = 0usize;
+    for _ in 0..coder_count {
+        let flags = reader.byte()?;
+        // Alternative coder lists were never written by 7-Zip
+        if flags & 0x80 != 0 {
+            return None;
+        }
+        let id = reader.bytes((flags & 0x0F) as usize)?.to_vec();
+        if flags & 0x10 != 0 {
+            in_streams += reader.count()?;
+            out_streams += reader.count()?;
+        } else {
+            in_streams += 1;
+            out_streams += 1;
+        }
+        let properties = if flags & 0x20 != 0 {
+            let len = reader.count()?;
+            reader.bytes(len)?.to_vec()
+        } else {
+            Vec::new()
+        };
+        coders.push(Coder { id, properties });
+    }
+    let bind_pairs = out_streams.checked_sub(1)?;
+    let mut bound_out = Vec::with_capacity(bind_pairs);
+    for _ in 0..bind_pairs {
+        let _in_index = reader.number()?;
+        bound_out.push(reader.number()?);
+    }
+    let packed_streams = in_streams.checked_sub(bind_pairs)?;
+    if packed_streams > 1 {
+        for _ in 0..packed_streams {
+            reader.number()?;
+        }
+    }
+    Some(Folder {
+        coders,
+        out_streams,
+        bound_out,
+        packed_streams,
+        unpack_sizes: Vec::new(),
+        crc: None,
+    })
+}
+
+fn read_substreams_info(reader: &mut Reader, info: &mut StreamsInfo) -> Option<()> {
+    let mut counts = vec![1u64; info.folders.len()];
+    let mut id = reader.byte()?;
+    if id == K_NUM_UNPACK_STREAM {
+        for count in &mut counts {
+            *count = reader.number()?;
+        }
+        id = reader.byte()?;
+    }
+    let total: u64 = counts.iter().sum();
+    if total > MAX_ENTRIES as u64 {
+        return None;
+    }
+
+    // Sizes are given for all but the last stream of each folder, which
+    // takes what is left of the folder's size
+    let has_sizes = id == K_SIZE;
+    for (index, (folder, &count)) in info.folders.iter().zip(&counts).enumerate() {
+        if count == 0 {
+            continue;
+        }
+        let mut used = 0u64;
+        for _ in 1..count {
+            let size = if has_sizes { reader.number()? } else { 0 };
+            used = used.saturating_add(size);
+            info.sizes.push(size);
+            info.stream_folders.push(index);
+        }
+        info.sizes
+            .push(folder.unpack_size().unwrap_or(0).saturating_sub(used));
+        info.stream_folders.push(index);
+    }
+    if has_sizes {
+        id = reader.byte()?;
+    }
+
+    // CRCs are given for the streams whose folder CRC does not already
+    // cover them
+    let known = |index: usize, count: u64| count == 1 && info.folders[index].crc.is_some();
+    let mut digests = Vec::new();
+    while id != K_END {
+        if id != K_CRC {
+            return None;
+        }
+        let missing = counts
+            .iter()
+            .enumerate()
+            .filter(|&(index, &count)| !known(index, count))
+            .map(|(_, &count)| count as usize)
+            .sum();
+        digests = reader.digests(missing)?;
+        id = reader.byte()?;
+    }
+    let mut digests = digests.into_iter();
+    for (index, &count) in counts.iter().enumerate() {
+        if known(index, count) {
+            info.crcs.push(info.folders[index].crc);
+        } else {
+            for _ in 0..count {
+                info.crcs.push(digests.next().flatten());
+            }
+        }
+    }
+    info.streams_per_folder = counts;
+    Some(())
+}
+
+#[derive(Default)]
+struct Files {
+    names: Vec<String>,
+    empty_stream: Vec<bool>,
+    modified: Vec<Option<chrono::NaiveDateTime>>,
+}
+
+fn read_files(reader: &mut Reader) -> Option<Files> {
+    let count = reader.count()?;
+    let mut files = Files {
+        names: vec![String::new(); count],
+        ..Files::default()
+    };
+    loop {
+        let kind = reader.number()?;
+        if kind == u64::from(K_END) {
+            break;
+        }
+        let len = reader.count()?;
+        let mut property = Reader::new(reader.bytes(len)?);
+        match u8::try_from(kind) {
+            Ok(K_EMPTY_STREAM) => files.empty_stream = property.bits(count)?,
+            Ok(K_NAME) => {
+                if property.byte()? != 0 {
+                    return None;
+                }
+                let units: Vec<u16> = property.data[property.pos..]
+                    .chunks_exact(2)
+                    .map(|pair| u16::from_le_bytes([pair[0], pair[1]]))
+                    .collect();
+                for (name, raw) in files.names.iter_mut().zip(units.split(|&unit| unit == 0)) {
+                    *name = String::from_utf16_lossy(raw);
+                }
+            }
+            Ok(K_MTIME) => {
+                let defined = property.defined(count)?;
+                if property.byte()? != 0 {
+                    return None;
+                }
+                files.modified = defined
+                    .into_iter()
+                    .map(|set| {
+                        if set {
+                            property.u64().map(filetime_to_datetime)
+                        } else {
+                            Some(None)
+                        }
+                    })
+                    .collect::<Option<_>>()?;
+            }
+            // Attributes, other times, padding
+            _ => {}
+        }
+    }
+    Some(files)
+}
+
+/// Coder names as 7-Zip prints them, hex IDs for the rest.
+fn coder_name(id: &[u8]) -> String {
+    match id {
+        [0x00] => "copy".to_string(),
+        [0x03] => "delta".to_string(),
+        [0x21] => "lzma2".to_string(),
+        [0x03, 0x01, 0x01] => "lzma".to_string(),
+        [0x03, 0x03, 0x01, 0x03] => "bcj".to_string(),
+        [0x03, 0x03, 0x01, 0x1B] => "bcj2".to_string(),
+        [0x03, 0x04, 0x01] => "ppmd".to_string(),
+        [0x04, 0x01, 0x08] => "deflate".to_string(),
+        [0x04, 0x02, 0x02] => "bzip2".to_string(),
+        [0x06, 0xF1, 0x07, 0x01] => "aes".to_string(),
+        other => hex::encode(other),
+    }
+}
+
+/// Cursor over 7z header bytes.
+struct Reader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Self { data, pos: 0 }
+    }
+
+    fn byte(&mut self) -> Option<u8> {
+        let byte = *self.data.get(self.pos)?;
+        self.pos += 1;
+        Some(byte)
+    }
+
+    fn bytes(&mut self, len: usize) -> Option<&'a [u8]> {
+        let bytes = self.data.get(self.pos..self.pos.checked_add(len)?)?;
+        self.pos += len;
+        Some(bytes)
+    }
+
+    fn u32(&mut self) -> Option<u32> {
+        let b = self.bytes(4)?;
+        Some(u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+    }
+
+    fn u64(&mut self) -> Option<u64> {
+        Some(u64::from_le_bytes(self.bytes(8)?.try_into().ok()?))
+    }
+
+    /// A 7z number: each leading one bit of the first byte adds a
+    /// little-endian byte, and the rest of the first byte is the top.
+    fn number(&mut self) -> Option<u64> {
+        let first = self.byte()?;
+        let mut value = 0u64;
+        for extra in 0..8 {
+            let mask = 0x80u8 >> extra;
+            if first & mask == 0 {
+                let high = u64::from(first & mask.wrapping_sub(1));
+                return Some(value | (high << (8 * extra)));
+            }
+            value |= u64::from(self.byte()?) << (8 * extra);
+        }
+        Some(value)
+    }
+
+    /// A number used as an item count or length, bounded so that corrupt
+    /// headers cannot ask for huge allocations.
+    fn count(&mut self) -> Option<usize> {
+        usize::try_from(self.number()?)
+            .ok()
+            .filter(|&count| count <= MAX_ENTRIES.max(self.data.len()))
+    }
+
+    /// `count` flags, most significant bit first.
+    fn bits(&mut self, count: usize) -> Option<Vec<bool>> {
+        let bytes = self.bytes(count.div_ceil(8))?;
+        Some(
+            (0..count)
+                .map(|index| bytes[index / 8] & (0x80 >> (index % 8)) != 0)
+                .collect(),
+        )
+    }
+
+    /// An "all defined" byte, followed by the flags when it is 0.
+    fn defined(&mut self, count: usize) -> Option<Vec<bool>> {
+        if self.byte()? != 0 {
+            Some(vec![true; count])
+        } else {
+            self.bits(count)
+        }
+    }
+
+    /// CRCs of `count` items, `None` for the undefined ones.
+    fn digests(&mut self, count: usize) -> Option<Vec<Option<u32>>> {
+        self.defined(count)?
+            .into_iter()
+            .map(|set| {
+                if set {
+                    self.u32().map(Some)
+                } else {
+                    Some(None)
+                }
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{encryption, entries};
+    use crate::carve::Encryption;
+    use crate::evidence::{MemorySource, RawFileSource};
+
+    #[test]
+    fn lists_plain_header() {
+        let path = std::path::Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/golden_image/samples/archives/test.7z");
+        let source = RawFileSource::open(&path).expect("open");
+        let found = entries(&source);
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].name, "7z_test.txt");
+        assert_eq!(found[0].uncompressed_size, Some(16));
+        assert_eq!(found[0].compressed_size, Some(20));
+        assert_eq!(found[0].crc32, Some(0x6443_B09E));
+        assert_eq!(found[0].compression_method.as_deref(), Some("lzma2"));
+        assert!(!found[0].encrypted);
+        assert!(found[0].modified.is_some());
+        assert_eq!(encryption(&source), Encryption::None);
+    }
+
+    #[test]
+    fn reports_encrypted_header() {
+        // Encoded header packed by AES then LZMA, as written by `7z -mhe`
+        let mut encoded = vec![0x17, 0x06, 0x00, 0x01, 0x09, 0x10, 0x00];
+        encoded.extend_from_slice(&[0x07, 0x0B, 0x01, 0x00, 0x02]);
+        encoded.extend_from_slice(&[0x24, 0x06, 0xF1, 0x07, 0x01, 0x02, 0x00, 0x00]);
+        encoded.extend_from_slice(&[0x23, 0x03, 0x01, 0x01, 0x05, 0x5D, 0, 0, 1, 0]);
+        encoded.extend_from_slice(&[0x01, 0x00, 0x0C, 0x10, 0x20, 0x00, 0x00]);
+
+        let mut archive = vec![0x37, 0x7A, 0xBC, 0xAF, 0x27, 0x1C, 0x00, 0x04, 0, 0, 0, 0];
+        archive.extend_from_slice(&16u64.to_le_bytes());
+        archive.extend_from_slice(&(encoded.len() as u64).to_le_bytes());
+        archive.extend_from_slice(&[0, 0, 0, 0]);
+        archive.extend_from_slice(&[0xAA; 16]);
+        archive.extend_from_slice(&encoded);
+
+        let source = MemorySource::new(archive);
+        assert_eq!(encryption(&source), Encryption::Headers);
+        assert!(entries(&source).is_empty());
+    }
+
+    #[test]
+    fn unpacks_lzma_encoded_header() {
+        // A plain header naming one empty file
+        let mut header = vec![0x01, 0x05, 0x01, 0x0E, 0x01, 0x80, 0x11, 0x0D, 0x00];
+        for unit in "a.txt\0".encode_utf16() {
+            header.extend_from_slice(&unit.to_le_bytes());
+        }
+        header.extend_from_slice(&[0x00, 0x00]);
+
+        let mut lzma = Vec::new();
+        lzma_rs::lzma_compress(&mut header.as_slice(), &mut lzma).expect("compress");
+        // Drop the 8-byte size field of the .lzma header
+        let properties = &lzma[..5];
+        let packed = &lzma[13..];
+
+        let mut encoded = vec![0x17, 0x06, 0x00, 0x01, 0x09, packed.len() as u8, 0x00];
+        encoded.extend_from_slice(&[0x07, 0x0B, 0x01, 0x00, 0x01, 0x23, 0x03, 0x01, 0x01, 0x05]);
+        encoded.extend_from_slice(properties);
+        encoded.extend_from_slice(&[0x0C, header.len() as u8, 0x00, 0x00]);
+
+        let mut archive = vec![0x37, 0x7A, 0xBC, 0xAF, 0x27, 0x1C, 0x00, 0x04, 0, 0, 0, 0];
+        archive.extend_from_slice(&(packed.len() as u64).to_le_bytes());
+        archive.extend_from_slice(&(encoded.len() as u64).to_le_bytes());
+        archive.extend_from_slice(&[0, 0, 0, 0]);
+        archive.extend_from_slice(packed);
+        archive.extend_from_slice(&encoded);
+
+        let found = entries(&MemorySource::new(archive));
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].name, "a.txt");
+        assert_eq!(found[0].uncompressed_size, Some(0));
+    }
+}
@@ -0,0 +1,70 @@
+//! tar members.
+
+use super::{Entry, MAX_ENTRIES, read_exact_at};
+use crate::carve::tar::{header_name, parse_octal, validate_checksum};
+use crate::evidence::EvidenceSource;
+use crate::parsers::time::unix_seconds_to_datetime;
+
+const TAR_BLOCK_SIZE: u64 = 512;
+/// Longest GNU long name read
+const MAX_LONG_NAME: u64 = 64 * 1024;
+
+/// Every entry up to the end-of-archive blocks or the first bad header.
+/// GNU long names (`L` entries) are applied to the entry that follows them;
+/// other metadata entries (pax headers, long link names) are not listed.
+pub(super) fn entries(source: &dyn EvidenceSource) -> Vec<Entry> {
+    let mut entries = Vec::new();
+    let mut offset = 0u64;
+    let mut long_name: Option<String> = None;
+    while entries.len() < MAX_ENTRIES {
+        let Some(header) = read_exact_at(source, offset, TAR_BLOCK_SIZE as usize) else {
+            break;
+        };
+        if header.iter().all(|&b| b == 0) || !validate_checksum(&header).unwrap_or(false) {
+            break;
+        }
+        let Ok(size) = parse_octal(&header[124..136]) else {
+            break;
+        };
+        let data_start = offset + TAR_BLOCK_SIZE;
+        match header[156] {
+            b'L' => {
+                long_name = read_exact_at(source, data_start, size.min(MAX_LONG_NAME) as usize)
+                    .map(|body| {
+                        let name = body.split(|&b| b == 0).next().unwrap_or_default();
+                        String::from_utf8_lossy(name).into_owned()
+                    });
+            }
+            b'K' | b'x' | b'g' => {}
+            _ => entries.push(Entry {
+                name: long_name.take().unwrap_or_else(|| header_name(&header)),
+                uncompressed_size: Some(size),
+                modified: parse_octal(&header[136..148])
+                    .ok()
+                    .and_then(|seconds| unix_seconds_to_datetime(i64::try_from(seconds).ok()?)),
+                ..Entry::default()
+            }),
+        }
+        offset = data_start.saturating_add(size.div_ceil(TAR_BLOCK_SIZE) * TAR_BLOCK_SIZE);
+    }
+    entries
+}
+
+#[cfg(test)]
+mod tests {
+    use super::entries;
+    use crate::evidence::RawFileSource;
+
+    #[test]
+    fn lists_tar_entries() {
+        let path = std::path::Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/golden_image/samples/archives/test.tar");
+        let source = RawFileSource::open(&path).expect("open");
+        let found = entries(&source);
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].name, "tarfile.txt");
+        assert_eq!(found[0].uncompressed_size, Some(29));
+        assert_eq!(found[0].compressed_size, None);
+        assert!(found[0].modified.is_some());
+    }
+}
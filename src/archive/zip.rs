@@ -0,0 +1,129 @@
+//! ZIP central directory, with local headers as the fallback.
+
+use super::{Entry, MAX_ENTRIES, read_exact_at};
+use crate::evidence::EvidenceSource;
+use crate::parsers::time::dos_to_datetime;
+use crate::validate::zip::{
+    DirectoryEntry, EOCD_MIN_LEN, MAX_COMMENT_LEN, find_eocd, read_central_directory,
+    read_local_header,
+};
+
+const LOCAL_HEADER_LEN: usize = 30;
+/// Flag bit 3: sizes and CRC follow the data in a data descriptor
+const FLAG_DATA_DESCRIPTOR: u16 = 0x0008;
+
+/// Entries of the central directory; when it is missing (a carve cut short
+/// of the end of the archive) the local headers are walked from the start
+/// instead. ZIP64 directories are not read.
+pub(super) fn entries(source: &dyn EvidenceSource) -> Vec<Entry> {
+    match central_directory(source) {
+        Some(directory) => directory.iter().map(entry).collect(),
+        None => local_headers(source),
+    }
+}
+
+fn central_directory(source: &dyn EvidenceSource) -> Option<Vec<DirectoryEntry>> {
+    let tail_len = source.len().min((EOCD_MIN_LEN + MAX_COMMENT_LEN) as u64);
+    let tail_start = source.len() - tail_len;
+    let tail = read_exact_at(source, tail_start, tail_len as usize)?;
+    let eocd = find_eocd(&tail)?;
+    let field16 = |at: usize| u16::from_le_bytes([tail[at], tail[at + 1]]);
+    let field32 =
+        |at: usize| u32::from_le_bytes([tail[at], tail[at + 1], tail[at + 2], tail[at + 3]]);
+    let count = field16(eocd + 10);
+    let cd_size = field32(eocd + 12);
+    let cd_offset = field32(eocd + 16);
+    if count == 0 || count == u16::MAX || cd_size == u32::MAX || cd_offset == u32::MAX {
+        return None;
+    }
+    let cd = read_exact_at(source, u64::from(cd_offset), cd_size as usize)?;
+    read_central_directory(&cd).filter(|directory| !directory.is_empty())
+}
+
+fn local_headers(source: &dyn EvidenceSource) -> Vec<Entry> {
+    let mut entries = Vec::new();
+    let mut offset = 0u64;
+    while entries.len() < MAX_ENTRIES {
+        let Some(fixed) = read_exact_at(source, offset, LOCAL_HEADER_LEN) else {
+            break;
+        };
+        let name_len = u16::from_le_bytes([fixed[26], fixed[27]]) as usize;
+        let extra_len = u16::from_le_bytes([fixed[28], fixed[29]]) as usize;
+        let Some(header) = read_exact_at(source, offset, LOCAL_HEADER_LEN + name_len + extra_len)
+        else {
+            break;
+        };
+        let Some((local, header_len)) = read_local_header(&header, 0) else {
+            break;
+        };
+        // The next header cannot be found without the data size
+        let unknown_size = local.flags & FLAG_DATA_DESCRIPTOR != 0 && local.compressed == 0;
+        entries.push(entry(&local));
+        if unknown_size {
+            break;
+        }
+        offset = offset
+            .saturating_add(header_len as u64)
+            .saturating_add(u64::from(local.compressed));
+    }
+    entries
+}
+
+fn entry(record: &DirectoryEntry) -> Entry {
+    // u32::MAX sizes are placeholders for ZIP64 extra fields
+    let size = |value: u32| (value != u32::MAX).then_some(u64::from(value));
+    Entry {
+        name: String::from_utf8_lossy(&record.name).into_owned(),
+        compressed_size: size(record.compressed),
+        uncompressed_size: size(record.uncompressed),
+        modified: dos_to_datetime(record.date, record.time),
+        crc32: Some(record.crc),
+        encrypted: record.flags & 0x0001 != 0,
+        compression_method: Some(method_name(record.method)),
+    }
+}
+
+fn method_name(method: u16) -> String {
+    match method {
+        0 => "stored".to_string(),
+        1 => "shrink".to_string(),
+        6 => "implode".to_string(),
+        8 => "deflate".to_string(),
+        9 => "deflate64".to_string(),
+        12 => "bzip2".to_string(),
+        14 => "lzma".to_string(),
+        93 => "zstd".to_string(),
+        95 => "xz".to_string(),
+        98 => "ppmd".to_string(),
+        99 => "aes".to_string(),
+        other => format!("method {other}"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::entries;
+    use crate::evidence::RawFileSource;
+
+    #[test]
+    fn lists_central_directory_of_docx() {
+        let path = std::path::Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/golden_image/samples/documents/file-sample_100kB.docx");
+        let source = RawFileSource::open(&path).expect("open");
+        let found = entries(&source);
+        assert!(found.len() > 5);
+        let settings = found
+            .iter()
+            .find(|entry| entry.name == "word/settings.xml")
+            .expect("settings entry");
+        assert_eq!(settings.compression_method.as_deref(), Some("deflate"));
+        assert_eq!(settings.compressed_size, Some(186));
+        assert_eq!(settings.uncompressed_size, Some(235));
+        assert_eq!(settings.crc32, Some(0xd3fe467b));
+        assert!(!settings.encrypted);
+        assert_eq!(
+            settings.modified.map(|time| time.to_string()).as_deref(),
+            Some("2017-08-16 12:28:30")
+        );
+    }
+}
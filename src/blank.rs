@@ -0,0 +1,170 @@
+//! # Blank Block Module
+//!
+//! Detects blocks filled with a single byte value (zeroed or wiped space) so
+//! the scan workers can keep them away from the signature, string and entropy
+//! scanners.
+
+use crate::metadata::SkippedRange;
+
+/// Bytes of context kept on each side of non-constant data, so signatures
+/// that begin or end inside a constant block (e.g. MP4 `00 00 00 18 ftyp`)
+/// are still seen by the scanners.
+pub const SEGMENT_MARGIN: usize = 256;
+
+/// A run of constant-filled blocks within a chunk, in chunk-local offsets.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ConstantRun {
+    pub start: usize,
+    pub end: usize,
+    pub fill: u8,
+}
+
+/// Find runs of constant-filled blocks in `data`. Blocks are aligned to
+/// `base` (the global offset of `data[0]`) so runs from neighbouring chunks
+/// line up; partial blocks at either end are never reported.
+pub fn constant_runs(base: u64, data: &[u8], block_size: usize) -> Vec<ConstantRun> {
+    let mut runs: Vec<ConstantRun> = Vec::new();
+    if block_size == 0 {
+        return runs;
+    }
+
+    let mut pos = ((block_size as u64 - base % block_size as u64) % block_size as u64) as usize;
+    while pos + block_size <= data.len() {
+        let block = &data[pos..pos + block_size];
+        let fill = block[0];
+        if block.iter().all(|&byte| byte == fill) {
+            match runs.last_mut() {
+                Some(run) if run.end == pos && run.fill == fill => run.end += block_size,
+                _ => runs.push(ConstantRun {
+                    start: pos,
+                    end: pos + block_size,
+                    fill,
+                }),
+            }
+        }
+        pos += block_size;
+    }
+    runs
+}
+
+/// Ranges of `[0, len)` that still need scanning once `runs` are removed,
+/// each widened by `margin` bytes and merged where they touch.
+pub fn live_segments(len: usize, runs: &[ConstantRun], margin: usize) -> Vec<(usize, usize)> {
+    let mut segments: Vec<(usize, usize)> = Vec::new();
+    let mut cursor = 0usize;
+    let gaps = runs
+        .iter()
+        .map(|run| (run.start, run.end))
+        .chain(std::iter::once((len, len)));
+    for (gap_start, gap_end) in gaps {
+        if gap_start > cursor {
+            let start = cursor.saturating_sub(margin);
+            let end = gap_start.saturating_add(margin).min(len);
+            match segments.last_mut() {
+                Some(last) if last.1 >= start => last.1 = end,
+                _ => segments.push((start, end)),
+            }
+        }
+        cursor = gap_end;
+    }
+    segments
+}
+
+/// Sort skipped ranges and merge those that touch and share a fill byte.
+pub fn merge_ranges(mut ranges: Vec<SkippedRange>) -> Vec<SkippedRange> {
+    ranges.sort_by_key(|range| range.global_start);
+    let mut merged: Vec<SkippedRange> = Vec::with_capacity(ranges.len());
+    for range in ranges {
+        match merged.last_mut() {
+            Some(last) if last.global_end >= range.global_start && last.fill == range.fill => {
+                last.global_end = last.global_end.max(range.global_end);
+            }
+            _ => merged.push(range),
+        }
+    }
+    merged
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_aligned_constant_runs() {
+        let mut data = vec![0u8; 64];
+        data[20] = 1;
+        data[48..].fill(0xFF);
+        // base 4 puts block boundaries at local offsets 12, 28, 44, 60.
+        let runs = constant_runs(4, &data, 16);
+        assert_eq!(
+            runs,
+            vec![ConstantRun {
+                start: 28,
+                end: 44,
+                fill: 0
+            }]
+        );
+
+        let runs = constant_runs(0, &data, 16);
+        assert_eq!(
+            runs,
+            vec![
+                ConstantRun {
+                    start: 0,
+                    end: 16,
+                    fill: 0
+                },
+                ConstantRun {
+                    start: 32,
+                    end: 48,
+                    fill: 0
+                },
+                ConstantRun {
+                    start: 48,
+                    end: 64,
+                    fill: 0xFF
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn live_segments_keep_margin_around_data() {
+        let runs = [
+            ConstantRun {
+                start: 0,
+                end: 32,
+                fill: 0,
+            },
+            ConstantRun {
+                start: 48,
+                end: 96,
+                fill: 0,
+            },
+        ];
+        assert_eq!(live_segments(128, &runs, 4), vec![(28, 52), (92, 128)]);
+        assert_eq!(live_segments(128, &runs, 30), vec![(2, 128)]);
+        assert_eq!(live_segments(128, &[], 4), vec![(0, 128)]);
+        let all = [ConstantRun {
+            start: 0,
+            end: 128,
+            fill: 0,
+        }];
+        assert!(live_segments(128, &all, 4).is_empty());
+    }
+
+    #[test]
+    fn merges_adjacent_ranges_with_same_fill() {
+        let range = |global_start, global_end, fill| SkippedRange {
+            global_start,
+            global_end,
+            fill,
+        };
+        let merged = merge_ranges(vec![
+            range(4096, 8192, 0),
+            range(0, 4096, 0),
+            range(8192, 12288, 0xFF),
+        ]);
+        assert_eq!(merged, vec![range(0, 8192, 0), range(8192, 12288, 0xFF)]);
+    }
+}
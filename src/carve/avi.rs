@@ -105,7 +105,7 @@ impl CarveHandler for AviCarveHandler {
             }
         }
 
-        let (size, md5_hex, sha256_hex) = stream.finish()?;
+        let (size, md5_hex, sha256_hex, fuzzy) = stream.finish()?;
 
         // Check minimum size
         if size < self.min_size {
@@ -137,10 +137,13 @@ impl CarveHandler for AviCarveHandler {
             size,
             md5: Some(md5_hex),
             sha256: Some(sha256_hex),
+            ssdeep: fuzzy.ssdeep,
+            tlsh: fuzzy.tlsh,
             validated,
             truncated,
             errors,
             pattern_id: Some(hit.pattern_id.clone()),
+            ..Default::default()
         }))
     }
 }
@@ -0,0 +1,341 @@
+//! Bifragment gap carving for JPEG, PNG and ZIP.
+//!
+//! A file written to a fragmented file system can end up as two runs of
+//! blocks with unrelated data between them. A linear carve of such a file
+//! runs straight through the gap and produces a corrupt copy. In bifragment
+//! mode the JPEG, PNG and ZIP handlers re-check their output with the deep
+//! validators; when it is broken, the evidence after the hit is searched for
+//! the block-aligned gap whose removal makes the file valid again (see
+//! `find_splice` in `src/validate/`), and the reassembled file replaces the
+//! linear carve. The linear carve ran through the gap, so the search covers
+//! its length plus the largest gap; a budget shared by all handlers caps
+//! the number of searches in a run.
+
+use std::path::Path;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use sha2::{Digest, Sha256};
+use tracing::debug;
+
+use crate::carve::{CarveError, Fragment};
+use crate::evidence::EvidenceSource;
+use crate::fuzzy::{FuzzyDigests, FuzzyHasher};
+use crate::validate::{self, SpliceSearch};
+
+/// Upper bound on the linear carve length searched for a gap; the search
+/// holds this much of the evidence, plus the largest gap, in memory.
+const MAX_SEARCH_LEN: u64 = 64 * 1024 * 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BifragmentFormat {
+    Jpeg,
+    Png,
+    Zip,
+}
+
+/// Bifragment search settings, shared by the JPEG, PNG and ZIP handlers.
+#[derive(Debug, Clone)]
+pub struct BifragmentConfig {
+    /// Fragments start and end on multiples of this many evidence bytes
+    /// (the file system cluster size, or 512 for sector granularity).
+    pub block_size: u64,
+    /// Largest gap between the two fragments that is searched.
+    pub max_gap: u64,
+    /// Gap searches left in the run; clones share the count.
+    pub searches_left: Arc<AtomicU64>,
+}
+
+/// A linear carve replaced by its reassembled bytes.
+#[derive(Debug)]
+pub struct Repaired {
+    pub size: u64,
+    pub md5: String,
+    pub sha256: String,
+    pub fuzzy: FuzzyDigests,
+    pub fragments: Vec<Fragment>,
+}
+
+impl Repaired {
+    /// Evidence offset of the last byte of the file.
+    pub fn global_end(&self) -> u64 {
+        self.fragments
+            .last()
+            .map_or(0, |last| last.start + last.length.max(1) - 1)
+    }
+}
+
+impl BifragmentConfig {
+    pub fn new(block_size: u64, max_gap: u64, max_searches: u64) -> Self {
+        Self {
+            block_size,
+            max_gap,
+            searches_left: Arc::new(AtomicU64::new(max_searches)),
+        }
+    }
+
+    /// Evidence bytes to search for the gap in a linear carve of
+    /// `carved_len` bytes.
+    pub fn search_len(&self, format: BifragmentFormat, carved_len: u64) -> u64 {
+        let carved_len = carved_len.min(MAX_SEARCH_LEN);
+        match format {
+            // The EOCD ends the second fragment, so the linear carve
+            // already spans both fragments and the gap
+            BifragmentFormat::Zip => carved_len,
+            BifragmentFormat::Jpeg | BifragmentFormat::Png => {
+                carved_len.saturating_add(self.max_gap)
+            }
+        }
+    }
+
+    /// Check the carved file at `path` and, when it is broken, search the
+    /// evidence from `start` for a single gap that repairs it. On success
+    /// the file is rewritten with the reassembled bytes. Returns `None`
+    /// when the file is valid as carved, no gap within the limits repairs
+    /// it, or the run's searches are used up.
+    pub fn repair(
+        &self,
+        evidence: &dyn EvidenceSource,
+        start: u64,
+        path: &Path,
+        format: BifragmentFormat,
+    ) -> Result<Option<Repaired>, CarveError> {
+        let carved = std::fs::read(path)?;
+        let intact = match format {
+            BifragmentFormat::Jpeg => validate::jpeg::validate(&carved),
+            BifragmentFormat::Png => validate::png::validate(&carved),
+            BifragmentFormat::Zip => validate::zip::validate(&carved),
+        };
+        let Err(reason) = intact else {
+            return Ok(None);
+        };
+        let search_len = self.search_len(format, carved.len() as u64);
+        drop(carved);
+        if self
+            .searches_left
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |left| {
+                left.checked_sub(1)
+            })
+            .is_err()
+        {
+            debug!("bifragment: search limit reached, file at offset {start} kept as carved");
+            return Ok(None);
+        }
+
+        let len = search_len.min(evidence.len().saturating_sub(start));
+        let data = read_evidence(evidence, start, len)?;
+        let search = SpliceSearch {
+            base: start,
+            block_size: self.block_size.max(1) as usize,
+            max_gap: self.max_gap as usize,
+        };
+        let splice = match format {
+            BifragmentFormat::Jpeg => validate::jpeg::find_splice(&data, &search),
+            BifragmentFormat::Png => validate::png::find_splice(&data, &search),
+            BifragmentFormat::Zip => validate::zip::find_splice(&data, &search),
+        };
+        let Some(splice) = splice else {
+            debug!("bifragment: no gap repairs file at offset {start}: {reason}");
+            return Ok(None);
+        };
+
+        let joined = splice.join(&data);
+        std::fs::write(path, &joined)?;
+        let mut fuzzy = FuzzyHasher::new();
+        fuzzy.update(&joined);
+        let second = splice.split + splice.gap;
+        Ok(Some(Repaired {
+            size: joined.len() as u64,
+            md5: format!("{:x}", md5::compute(&joined)),
+            sha256: hex::encode(Sha256::digest(&joined)),
+            fuzzy: fuzzy.finish(),
+            fragments: vec![
+                Fragment {
+                    start,
+                    length: splice.split as u64,
+                },
+                Fragment {
+                    start: start + second as u64,
+                    length: (splice.len - splice.split) as u64,
+                },
+            ],
+        }))
+    }
+}
+
+fn read_evidence(
+    evidence: &dyn EvidenceSource,
+    start: u64,
+    len: u64,
+) -> Result<Vec<u8>, CarveError> {
+    let mut data = vec![0u8; len as usize];
+    let mut filled = 0usize;
+    while filled < data.len() {
+        let n = evidence
+            .read_at(start + filled as u64, &mut data[filled..])
+            .map_err(|e| CarveError::Evidence(e.to_string()))?;
+        if n == 0 {
+            break;
+        }
+        filled += n;
+    }
+    data.truncate(filled);
+    Ok(data)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::evidence::EvidenceError;
+    use crate::validate::noise;
+    use crate::validate::png::build_chunk;
+    use tempfile::tempdir;
+
+    struct SliceEvidence {
+        data: Vec<u8>,
+    }
+
+    impl EvidenceSource for SliceEvidence {
+        fn len(&self) -> u64 {
+            self.data.len() as u64
+        }
+
+        fn read_at(&self, offset: u64, buf: &mut [u8]) -> Result<usize, EvidenceError> {
+            if offset as usize >= self.data.len() {
+                return Ok(0);
+            }
+            let max = self.data.len() - offset as usize;
+            let to_copy = buf.len().min(max);
+            buf[..to_copy].copy_from_slice(&self.data[offset as usize..offset as usize + to_copy]);
+            Ok(to_copy)
+        }
+    }
+
+    fn sample_png() -> Vec<u8> {
+        let mut image = vec![0x89, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A, 0x0A];
+        image.extend(build_chunk(
+            b"IHDR",
+            &[0, 0, 0, 64, 0, 0, 0, 16, 8, 2, 0, 0, 0],
+        ));
+        image.extend(build_chunk(b"IDAT", &noise(3000)));
+        image.extend(build_chunk(b"IEND", &[]));
+        image
+    }
+
+    /// Unrelated data, the PNG's first 2048 bytes, a 1024-byte gap and the
+    /// rest of the PNG, with the linear carve of it written to `path`.
+    fn split_png(path: &Path) -> (Vec<u8>, SliceEvidence) {
+        let image = sample_png();
+        let mut linear = image[..2048].to_vec();
+        linear.extend(noise(1024).iter().map(|b| b ^ 0x5A));
+        linear.extend_from_slice(&image[2048..]);
+        std::fs::write(path, &linear).expect("write carve");
+        let mut data = noise(1024);
+        data.extend(&linear);
+        data.extend(noise(512));
+        (image, SliceEvidence { data })
+    }
+
+    #[test]
+    fn search_len_covers_the_carve_and_the_gap() {
+        let config = BifragmentConfig::new(512, 8192, 10);
+        assert_eq!(config.search_len(BifragmentFormat::Jpeg, 1000), 9192);
+        assert_eq!(config.search_len(BifragmentFormat::Png, 1000), 9192);
+        assert_eq!(config.search_len(BifragmentFormat::Zip, 1000), 1000);
+        assert_eq!(
+            config.search_len(BifragmentFormat::Png, u64::MAX),
+            MAX_SEARCH_LEN + 8192
+        );
+    }
+
+    #[test]
+    fn repairs_split_png() {
+        let dir = tempdir().expect("tempdir");
+        let path = dir.path().join("split.png");
+        let (image, evidence) = split_png(&path);
+        let config = BifragmentConfig::new(512, 4096, 10);
+
+        let repaired = config
+            .repair(&evidence, 1024, &path, BifragmentFormat::Png)
+            .expect("repair")
+            .expect("gap found");
+        assert_eq!(std::fs::read(&path).expect("read"), image);
+        assert_eq!(repaired.size, image.len() as u64);
+        assert_eq!(repaired.md5, format!("{:x}", md5::compute(&image)));
+        assert_eq!(
+            repaired.fragments,
+            vec![
+                Fragment {
+                    start: 1024,
+                    length: 2048,
+                },
+                Fragment {
+                    start: 1024 + 2048 + 1024,
+                    length: image.len() as u64 - 2048,
+                },
+            ]
+        );
+        assert_eq!(
+            repaired.global_end(),
+            1024 + 2048 + 1024 + image.len() as u64 - 2048 - 1
+        );
+        assert_eq!(config.searches_left.load(Ordering::Relaxed), 9);
+
+        // Valid as carved: nothing to search
+        assert!(
+            config
+                .repair(&evidence, 1024, &path, BifragmentFormat::Png)
+                .expect("repair")
+                .is_none()
+        );
+        assert_eq!(config.searches_left.load(Ordering::Relaxed), 9);
+    }
+
+    #[test]
+    fn stops_searching_when_the_budget_is_spent() {
+        let dir = tempdir().expect("tempdir");
+        let path = dir.path().join("split.png");
+        let (_, evidence) = split_png(&path);
+        let linear = std::fs::read(&path).expect("read");
+        let config = BifragmentConfig::new(512, 4096, 1);
+        // Clones handed to other handlers draw on the same budget
+        let other = config.clone();
+        other.searches_left.store(0, Ordering::Relaxed);
+
+        assert!(
+            config
+                .repair(&evidence, 1024, &path, BifragmentFormat::Png)
+                .expect("repair")
+                .is_none()
+        );
+        assert_eq!(std::fs::read(&path).expect("read"), linear);
+    }
+
+    #[test]
+    fn global_end_is_the_last_byte_of_the_last_fragment() {
+        let repaired = |fragments| Repaired {
+            size: 0,
+            md5: String::new(),
+            sha256: String::new(),
+            fuzzy: FuzzyDigests::default(),
+            fragments,
+        };
+        let two = repaired(vec![
+            Fragment {
+                start: 4096,
+                length: 512,
+            },
+            Fragment {
+                start: 16384,
+                length: 100,
+            },
+        ]);
+        assert_eq!(two.global_end(), 16483);
+        let empty_tail = repaired(vec![Fragment {
+            start: 4096,
+            length: 0,
+        }]);
+        assert_eq!(empty_tail.global_end(), 4096);
+        assert_eq!(repaired(Vec::new()).global_end(), 0);
+    }
+}
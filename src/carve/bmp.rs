@@ -6,6 +6,7 @@ use sha2::{Digest, Sha256};
 use crate::carve::{
     CarveError, CarveHandler, CarvedFile, ExtractionContext, output_path, write_range,
 };
+use crate::fuzzy::FuzzyHasher;
 use crate::scanner::NormalizedHit;
 
 /// BMP file header is 14 bytes
@@ -136,6 +137,7 @@ impl CarveHandler for BmpCarveHandler {
         let mut file = File::create(&full_path)?;
         let mut md5 = md5::Context::new();
         let mut sha256 = Sha256::new();
+        let mut fuzzy = FuzzyHasher::new();
 
         let mut total_end = hit.global_offset + file_size;
         let mut truncated = false;
@@ -154,6 +156,7 @@ impl CarveHandler for BmpCarveHandler {
             &mut file,
             &mut md5,
             &mut sha256,
+            &mut fuzzy,
         )?;
         if eof_truncated {
             truncated = true;
@@ -168,6 +171,7 @@ impl CarveHandler for BmpCarveHandler {
 
         let md5_hex = format!("{:x}", md5.compute());
         let sha256_hex = hex::encode(sha256.finalize());
+        let fuzzy = fuzzy.finish();
         let global_end = if written == 0 {
             hit.global_offset
         } else {
@@ -184,10 +188,13 @@ impl CarveHandler for BmpCarveHandler {
             size: written,
             md5: Some(md5_hex),
             sha256: Some(sha256_hex),
+            ssdeep: fuzzy.ssdeep,
+            tlsh: fuzzy.tlsh,
             validated: !truncated,
             truncated,
             errors,
             pattern_id: Some(hit.pattern_id.clone()),
+            ..Default::default()
         }))
     }
 }
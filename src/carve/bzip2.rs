@@ -9,6 +9,7 @@ use sha2::{Digest, Sha256};
 use crate::carve::{
     CarveError, CarveHandler, CarvedFile, ExtractionContext, output_path, write_range,
 };
+use crate::fuzzy::FuzzyHasher;
 use crate::scanner::NormalizedHit;
 
 const BZIP2_MAGIC: [u8; 3] = [0x42, 0x5A, 0x68];
@@ -62,6 +63,7 @@ impl CarveHandler for Bzip2CarveHandler {
         let mut file = File::create(&full_path)?;
         let mut md5 = md5::Context::new();
         let mut sha256 = Sha256::new();
+        let mut fuzzy = FuzzyHasher::new();
 
         let mut validated = false;
         let mut truncated = false;
@@ -123,6 +125,7 @@ impl CarveHandler for Bzip2CarveHandler {
             &mut file,
             &mut md5,
             &mut sha256,
+            &mut fuzzy,
         )?;
         if eof_truncated {
             truncated = true;
@@ -138,6 +141,7 @@ impl CarveHandler for Bzip2CarveHandler {
 
         let md5_hex = format!("{:x}", md5.compute());
         let sha256_hex = hex::encode(sha256.finalize());
+        let fuzzy = fuzzy.finish();
         let global_end = if written == 0 {
             hit.global_offset
         } else {
@@ -154,10 +158,13 @@ impl CarveHandler for Bzip2CarveHandler {
             size: written,
             md5: Some(md5_hex),
             sha256: Some(sha256_hex),
+            ssdeep: fuzzy.ssdeep,
+            tlsh: fuzzy.tlsh,
             validated,
             truncated,
             errors,
             pattern_id: Some(hit.pattern_id.clone()),
+            ..Default::default()
         }))
     }
 }
@@ -0,0 +1,126 @@
+//! Run-wide deduplication of carved output by SHA-256.
+//!
+//! Every carve worker shares one tracker. The first file carved with a given
+//! SHA-256 becomes the copy the others point to through `duplicate_of`;
+//! which copy that is depends on worker scheduling, not on evidence order.
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// What happens to the on-disk copy of a duplicate.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DuplicateAction {
+    /// Keep every copy (duplicates are only flagged in metadata).
+    Keep,
+    /// Delete duplicates (`--skip-duplicates`).
+    Remove,
+    /// Replace duplicates with a hardlink to the first copy.
+    Hardlink,
+}
+
+impl DuplicateAction {
+    pub fn from_flags(skip_duplicates: bool, hardlink_duplicates: bool) -> Self {
+        match (skip_duplicates, hardlink_duplicates) {
+            (false, _) => DuplicateAction::Keep,
+            (true, false) => DuplicateAction::Remove,
+            (true, true) => DuplicateAction::Hardlink,
+        }
+    }
+}
+
+/// First carved copy of each SHA-256 seen in the run.
+pub struct DedupTracker {
+    action: DuplicateAction,
+    first_copies: Mutex<HashMap<[u8; 32], String>>,
+    duplicates: AtomicU64,
+}
+
+impl DedupTracker {
+    pub fn new(action: DuplicateAction) -> Self {
+        Self {
+            action,
+            first_copies: Mutex::new(HashMap::new()),
+            duplicates: AtomicU64::new(0),
+        }
+    }
+
+    pub fn action(&self) -> DuplicateAction {
+        self.action
+    }
+
+    /// Record `path` as the first copy of `sha256_hex`, or return the path
+    /// of the first copy when the same content was already carved.
+    /// Digests that are not 64 hex characters are never deduplicated.
+    pub fn first_copy(&self, sha256_hex: &str, path: &str) -> Option<String> {
+        let mut key = [0u8; 32];
+        hex::decode_to_slice(sha256_hex, &mut key).ok()?;
+        let mut first_copies = match self.first_copies.lock() {
+            Ok(guard) => guard,
+            Err(poisoned) => poisoned.into_inner(),
+        };
+        match first_copies.get(&key) {
+            Some(first) => {
+                self.duplicates.fetch_add(1, Ordering::Relaxed);
+                Some(first.clone())
+            }
+            None => {
+                first_copies.insert(key, path.to_string());
+                None
+            }
+        }
+    }
+
+    /// Number of carved files found to duplicate an earlier one.
+    pub fn duplicates(&self) -> u64 {
+        self.duplicates.load(Ordering::Relaxed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{DedupTracker, DuplicateAction};
+
+    const SHA_A: &str = "9f86d081884c7d659a2feaa0c55ad015a3bf4f1b2b0b822cd15d6c15b0f00a08";
+    const SHA_B: &str = "60303ae22b998861bce3b28f33eec1be758a213c86c93c076dbe9f558c11c752";
+
+    #[test]
+    fn later_copies_point_at_the_first() {
+        let tracker = DedupTracker::new(DuplicateAction::Keep);
+        assert_eq!(tracker.first_copy(SHA_A, "jpeg/a.jpg"), None);
+        assert_eq!(tracker.first_copy(SHA_B, "jpeg/b.jpg"), None);
+        assert_eq!(
+            tracker.first_copy(SHA_A, "jpeg/c.jpg"),
+            Some("jpeg/a.jpg".to_string())
+        );
+        assert_eq!(
+            tracker.first_copy(SHA_A, "jpeg/d.jpg"),
+            Some("jpeg/a.jpg".to_string())
+        );
+        assert_eq!(tracker.duplicates(), 2);
+    }
+
+    #[test]
+    fn ignores_malformed_digests() {
+        let tracker = DedupTracker::new(DuplicateAction::Remove);
+        assert_eq!(tracker.first_copy("deadbeef", "a"), None);
+        assert_eq!(tracker.first_copy("deadbeef", "b"), None);
+        assert_eq!(tracker.duplicates(), 0);
+    }
+
+    #[test]
+    fn maps_flags_to_action() {
+        assert_eq!(
+            DuplicateAction::from_flags(false, false),
+            DuplicateAction::Keep
+        );
+        assert_eq!(
+            DuplicateAction::from_flags(true, false),
+            DuplicateAction::Remove
+        );
+        assert_eq!(
+            DuplicateAction::from_flags(true, true),
+            DuplicateAction::Hardlink
+        );
+    }
+}
@@ -10,6 +10,7 @@ use sha2::{Digest, Sha256};
 use crate::carve::{
     CarveError, CarveHandler, CarvedFile, ExtractionContext, output_path, write_range,
 };
+use crate::fuzzy::FuzzyHasher;
 use crate::scanner::NormalizedHit;
 
 /// Required header markers for email validation
@@ -166,6 +167,7 @@ impl CarveHandler for EmlCarveHandler {
         let mut file = File::create(&full_path)?;
         let mut md5 = md5::Context::new();
         let mut sha256 = Sha256::new();
+        let mut fuzzy = FuzzyHasher::new();
 
         let (written, eof_truncated) = write_range(
             ctx,
@@ -174,6 +176,7 @@ impl CarveHandler for EmlCarveHandler {
             &mut file,
             &mut md5,
             &mut sha256,
+            &mut fuzzy,
         )?;
 
         if written < self.min_size {
@@ -183,6 +186,7 @@ impl CarveHandler for EmlCarveHandler {
 
         let md5_hex = format!("{:x}", md5.compute());
         let sha256_hex = hex::encode(sha256.finalize());
+        let fuzzy = fuzzy.finish();
         let global_end = if written == 0 {
             hit.global_offset
         } else {
@@ -199,10 +203,13 @@ impl CarveHandler for EmlCarveHandler {
             size: written,
             md5: Some(md5_hex),
             sha256: Some(sha256_hex),
+            ssdeep: fuzzy.ssdeep,
+            tlsh: fuzzy.tlsh,
             validated: !eof_truncated,
             truncated: eof_truncated,
             errors: Vec::new(),
             pattern_id: Some(hit.pattern_id.clone()),
+            ..Default::default()
         }))
     }
 }
@@ -9,6 +9,7 @@ use std::io::{BufWriter, Write};
 use sha2::{Digest, Sha256};
 
 use crate::carve::{CarveError, CarveHandler, CarvedFile, ExtractionContext, output_path};
+use crate::fuzzy::FuzzyHasher;
 use crate::scanner::NormalizedHit;
 
 const FB2_HEADER: &[u8] = b"<?xml";
@@ -85,6 +86,7 @@ impl CarveHandler for Fb2CarveHandler {
         let mut writer = BufWriter::new(file);
         let mut md5 = md5::Context::new();
         let mut sha256 = Sha256::new();
+        let mut fuzzy = FuzzyHasher::new();
 
         let mut validated = false;
         let mut truncated = false;
@@ -138,6 +140,7 @@ impl CarveHandler for Fb2CarveHandler {
                     writer.write_all(slice)?;
                     md5.consume(slice);
                     sha256.update(slice);
+                    fuzzy.update(slice);
                     bytes_written = bytes_written.saturating_add(slice.len() as u64);
                 }
                 validated = true;
@@ -147,6 +150,7 @@ impl CarveHandler for Fb2CarveHandler {
             writer.write_all(&buf)?;
             md5.consume(&buf);
             sha256.update(&buf);
+            fuzzy.update(&buf);
             bytes_written = bytes_written.saturating_add(buf.len() as u64);
             offset = offset.saturating_add(buf.len() as u64);
 
@@ -171,6 +175,7 @@ impl CarveHandler for Fb2CarveHandler {
 
         let md5_hex = format!("{:x}", md5.compute());
         let sha256_hex = hex::encode(sha256.finalize());
+        let fuzzy = fuzzy.finish();
         let global_end = if bytes_written == 0 {
             hit.global_offset
         } else {
@@ -187,10 +192,13 @@ impl CarveHandler for Fb2CarveHandler {
             size: bytes_written,
             md5: Some(md5_hex),
             sha256: Some(sha256_hex),
+            ssdeep: fuzzy.ssdeep,
+            tlsh: fuzzy.tlsh,
             validated,
             truncated,
             errors,
             pattern_id: Some(hit.pattern_id.clone()),
+            ..Default::default()
         }))
     }
 }
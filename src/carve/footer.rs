@@ -4,14 +4,16 @@ use std::io::{BufWriter, Write};
 use sha2::{Digest, Sha256};
 
 use crate::carve::{CarveError, CarveHandler, CarvedFile, ExtractionContext, output_path};
+use crate::fuzzy::FuzzyHasher;
 use crate::scanner::NormalizedHit;
+use crate::scanner::pattern::SignaturePattern;
 
 pub struct FooterCarveHandler {
     file_type: String,
     extension: String,
     min_size: u64,
     max_size: u64,
-    header_patterns: Vec<Vec<u8>>,
+    header_patterns: Vec<SignaturePattern>,
     footer_patterns: Vec<Vec<u8>>,
     max_footer_len: usize,
 }
@@ -22,7 +24,7 @@ impl FooterCarveHandler {
         extension: String,
         min_size: u64,
         max_size: u64,
-        header_patterns: Vec<Vec<u8>>,
+        header_patterns: Vec<SignaturePattern>,
         footer_patterns: Vec<Vec<u8>>,
     ) -> Self {
         let max_footer_len = footer_patterns.iter().map(|p| p.len()).max().unwrap_or(0);
@@ -43,7 +45,7 @@ impl FooterCarveHandler {
         }
         self.header_patterns
             .iter()
-            .any(|pat| !pat.is_empty() && buf.len() >= pat.len() && &buf[..pat.len()] == pat)
+            .any(|pattern| pattern.matches_at(buf))
     }
 }
 
@@ -71,6 +73,7 @@ impl CarveHandler for FooterCarveHandler {
         let mut writer = BufWriter::new(file);
         let mut md5 = md5::Context::new();
         let mut sha256 = Sha256::new();
+        let mut fuzzy = FuzzyHasher::new();
 
         let mut offset = hit.global_offset;
         let mut bytes_written = 0u64;
@@ -127,6 +130,7 @@ impl CarveHandler for FooterCarveHandler {
                     writer.write_all(slice)?;
                     md5.consume(slice);
                     sha256.update(slice);
+                    fuzzy.update(slice);
                     bytes_written = bytes_written.saturating_add(slice.len() as u64);
                 }
                 validated = true;
@@ -136,6 +140,7 @@ impl CarveHandler for FooterCarveHandler {
             writer.write_all(&buf)?;
             md5.consume(&buf);
             sha256.update(&buf);
+            fuzzy.update(&buf);
             bytes_written = bytes_written.saturating_add(buf.len() as u64);
             offset = offset.saturating_add(buf.len() as u64);
 
@@ -160,6 +165,7 @@ impl CarveHandler for FooterCarveHandler {
 
         let md5_hex = format!("{:x}", md5.compute());
         let sha256_hex = hex::encode(sha256.finalize());
+        let fuzzy = fuzzy.finish();
         let global_end = if bytes_written == 0 {
             hit.global_offset
         } else {
@@ -176,10 +182,13 @@ impl CarveHandler for FooterCarveHandler {
             size: bytes_written,
             md5: Some(md5_hex),
             sha256: Some(sha256_hex),
+            ssdeep: fuzzy.ssdeep,
+            tlsh: fuzzy.tlsh,
             validated,
             truncated,
             errors,
             pattern_id: Some(hit.pattern_id.clone()),
+            ..Default::default()
         }))
     }
 }
@@ -220,8 +229,10 @@ fn find_pattern(haystack: &[u8], needle: &[u8]) -> Option<usize> {
 mod tests {
     use super::FooterCarveHandler;
     use crate::carve::{CarveHandler, ExtractionContext};
+    use crate::config::PatternConfig;
     use crate::evidence::{EvidenceError, EvidenceSource};
     use crate::scanner::NormalizedHit;
+    use crate::scanner::pattern::SignaturePattern;
     use tempfile::tempdir;
 
     struct SliceEvidence {
@@ -244,6 +255,18 @@ mod tests {
         }
     }
 
+    fn header_pattern(bytes: &[u8]) -> SignaturePattern {
+        let config = PatternConfig {
+            id: "header".to_string(),
+            hex: hex::encode(bytes),
+            offset: 0,
+            also: Vec::new(),
+        };
+        SignaturePattern::parse(&config, "custom")
+            .expect("parse")
+            .expect("pattern")
+    }
+
     #[test]
     fn carves_until_footer() {
         let header = b"HEAD";
@@ -266,7 +289,7 @@ mod tests {
             "bin".to_string(),
             1,
             0,
-            vec![header.to_vec()],
+            vec![header_pattern(header)],
             vec![footer.to_vec()],
         );
 
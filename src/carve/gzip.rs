@@ -10,6 +10,7 @@ use sha2::{Digest, Sha256};
 use crate::carve::{
     CarveError, CarveHandler, CarvedFile, ExtractionContext, output_path, write_range,
 };
+use crate::fuzzy::FuzzyHasher;
 use crate::scanner::NormalizedHit;
 
 const GZIP_MAGIC: [u8; 3] = [0x1F, 0x8B, 0x08];
@@ -59,6 +60,7 @@ impl CarveHandler for GzipCarveHandler {
         let mut file = File::create(&full_path)?;
         let mut md5 = md5::Context::new();
         let mut sha256 = Sha256::new();
+        let mut fuzzy = FuzzyHasher::new();
 
         let mut validated = false;
         let mut truncated = false;
@@ -129,6 +131,7 @@ impl CarveHandler for GzipCarveHandler {
             &mut file,
             &mut md5,
             &mut sha256,
+            &mut fuzzy,
         )?;
         if eof_truncated {
             truncated = true;
@@ -144,6 +147,7 @@ impl CarveHandler for GzipCarveHandler {
 
         let md5_hex = format!("{:x}", md5.compute());
         let sha256_hex = hex::encode(sha256.finalize());
+        let fuzzy = fuzzy.finish();
         let global_end = if written == 0 {
             hit.global_offset
         } else {
@@ -160,10 +164,13 @@ impl CarveHandler for GzipCarveHandler {
             size: written,
             md5: Some(md5_hex),
             sha256: Some(sha256_hex),
+            ssdeep: fuzzy.ssdeep,
+            tlsh: fuzzy.tlsh,
             validated,
             truncated,
             errors,
             pattern_id: Some(hit.pattern_id.clone()),
+            ..Default::default()
         }))
     }
 }
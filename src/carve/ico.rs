@@ -10,6 +10,7 @@ use sha2::{Digest, Sha256};
 use crate::carve::{
     CarveError, CarveHandler, CarvedFile, ExtractionContext, output_path, write_range,
 };
+use crate::fuzzy::FuzzyHasher;
 use crate::scanner::NormalizedHit;
 
 /// BMP signature at start of image data within ICO
@@ -164,6 +165,7 @@ impl CarveHandler for IcoCarveHandler {
         let mut file = File::create(&full_path)?;
         let mut md5 = md5::Context::new();
         let mut sha256 = Sha256::new();
+        let mut fuzzy = FuzzyHasher::new();
 
         let (written, eof_truncated) = write_range(
             ctx,
@@ -172,6 +174,7 @@ impl CarveHandler for IcoCarveHandler {
             &mut file,
             &mut md5,
             &mut sha256,
+            &mut fuzzy,
         )?;
 
         if written < self.min_size {
@@ -181,6 +184,7 @@ impl CarveHandler for IcoCarveHandler {
 
         let md5_hex = format!("{:x}", md5.compute());
         let sha256_hex = hex::encode(sha256.finalize());
+        let fuzzy = fuzzy.finish();
         let global_end = if written == 0 {
             hit.global_offset
         } else {
@@ -197,10 +201,13 @@ impl CarveHandler for IcoCarveHandler {
             size: written,
             md5: Some(md5_hex),
             sha256: Some(sha256_hex),
+            ssdeep: fuzzy.ssdeep,
+            tlsh: fuzzy.tlsh,
             validated: !eof_truncated,
             truncated: eof_truncated,
             errors: Vec::new(),
             pattern_id: Some(hit.pattern_id.clone()),
+            ..Default::default()
         }))
     }
 }
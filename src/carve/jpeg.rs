@@ -3,21 +3,30 @@ use std::io::{BufWriter, Write};
 
 use sha2::{Digest, Sha256};
 
+use crate::carve::bifragment::{BifragmentConfig, BifragmentFormat};
 use crate::carve::{CarveError, CarveHandler, CarvedFile, ExtractionContext, output_path};
+use crate::fuzzy::FuzzyHasher;
 use crate::scanner::NormalizedHit;
 
 pub struct JpegCarveHandler {
     extension: String,
     min_size: u64,
     max_size: u64,
+    bifragment: Option<BifragmentConfig>,
 }
 
 impl JpegCarveHandler {
-    pub fn new(extension: String, min_size: u64, max_size: u64) -> Self {
+    pub fn new(
+        extension: String,
+        min_size: u64,
+        max_size: u64,
+        bifragment: Option<BifragmentConfig>,
+    ) -> Self {
         Self {
             extension,
             min_size,
             max_size,
+            bifragment,
         }
     }
 }
@@ -46,6 +55,7 @@ impl CarveHandler for JpegCarveHandler {
         let mut writer = BufWriter::new(file);
         let mut md5 = md5::Context::new();
         let mut sha256 = Sha256::new();
+        let mut fuzzy = FuzzyHasher::new();
 
         let mut offset = hit.global_offset;
         let mut bytes_written = 0u64;
@@ -95,6 +105,7 @@ impl CarveHandler for JpegCarveHandler {
             writer.write_all(slice)?;
             md5.consume(slice);
             sha256.update(slice);
+            fuzzy.update(slice);
 
             bytes_written = bytes_written.saturating_add(write_len as u64);
             offset = offset.saturating_add(write_len as u64);
@@ -110,18 +121,38 @@ impl CarveHandler for JpegCarveHandler {
 
         writer.flush()?;
 
-        if bytes_written < self.min_size {
-            let _ = std::fs::remove_file(&full_path);
-            return Ok(None);
-        }
-
-        let md5_hex = format!("{:x}", md5.compute());
-        let sha256_hex = hex::encode(sha256.finalize());
-        let global_end = if bytes_written == 0 {
+        let mut md5_hex = format!("{:x}", md5.compute());
+        let mut sha256_hex = hex::encode(sha256.finalize());
+        let mut fuzzy = fuzzy.finish();
+        let mut global_end = if bytes_written == 0 {
             hit.global_offset
         } else {
             hit.global_offset + bytes_written - 1
         };
+        let mut fragments = Vec::new();
+        if let Some(bifragment) = &self.bifragment
+            && let Some(repaired) = bifragment.repair(
+                ctx.evidence,
+                hit.global_offset,
+                &full_path,
+                BifragmentFormat::Jpeg,
+            )?
+        {
+            global_end = repaired.global_end();
+            bytes_written = repaired.size;
+            md5_hex = repaired.md5;
+            sha256_hex = repaired.sha256;
+            fuzzy = repaired.fuzzy;
+            fragments = repaired.fragments;
+            validated = true;
+            truncated = false;
+            errors.clear();
+        }
+
+        if bytes_written < self.min_size {
+            let _ = std::fs::remove_file(&full_path);
+            return Ok(None);
+        }
 
         Ok(Some(CarvedFile {
             run_id: ctx.run_id.to_string(),
@@ -133,10 +164,14 @@ impl CarveHandler for JpegCarveHandler {
             size: bytes_written,
             md5: Some(md5_hex),
             sha256: Some(sha256_hex),
+            ssdeep: fuzzy.ssdeep,
+            tlsh: fuzzy.tlsh,
             validated,
             truncated,
             errors,
             pattern_id: Some(hit.pattern_id.clone()),
+            fragments,
+            ..Default::default()
         }))
     }
 }
@@ -9,10 +9,10 @@ use sha2::{Digest, Sha256};
 use crate::carve::{
     CarveError, CarveHandler, CarvedFile, ExtractionContext, output_path, write_range,
 };
+use crate::fuzzy::FuzzyHasher;
 use crate::scanner::NormalizedHit;
 
 const PDB_HEADER_LEN: usize = 78;
-const MOBI_OFFSET: u64 = 60;
 const MOBI_MAGIC: &[u8; 8] = b"BOOKMOBI";
 
 pub struct MobiCarveHandler {
@@ -45,14 +45,7 @@ impl CarveHandler for MobiCarveHandler {
         hit: &NormalizedHit,
         ctx: &ExtractionContext,
     ) -> Result<Option<CarvedFile>, CarveError> {
-        let start_offset = if hit.pattern_id == "mobi_pdb" {
-            if hit.global_offset < MOBI_OFFSET {
-                return Ok(None);
-            }
-            hit.global_offset - MOBI_OFFSET
-        } else {
-            hit.global_offset
-        };
+        let start_offset = hit.global_offset;
 
         let header = read_exact_at(ctx, start_offset, PDB_HEADER_LEN)
             .ok_or_else(|| CarveError::Invalid("pdb header too short".to_string()))?;
@@ -115,6 +108,7 @@ impl CarveHandler for MobiCarveHandler {
         let mut file = File::create(&full_path)?;
         let mut md5 = md5::Context::new();
         let mut sha256 = Sha256::new();
+        let mut fuzzy = FuzzyHasher::new();
 
         let (written, eof_truncated) = write_range(
             ctx,
@@ -123,6 +117,7 @@ impl CarveHandler for MobiCarveHandler {
             &mut file,
             &mut md5,
             &mut sha256,
+            &mut fuzzy,
         )?;
 
         if written < self.min_size {
@@ -132,6 +127,7 @@ impl CarveHandler for MobiCarveHandler {
 
         let md5_hex = format!("{:x}", md5.compute());
         let sha256_hex = hex::encode(sha256.finalize());
+        let fuzzy = fuzzy.finish();
         let global_end = if written == 0 {
             start_offset
         } else {
@@ -148,10 +144,13 @@ impl CarveHandler for MobiCarveHandler {
             size: written,
             md5: Some(md5_hex),
             sha256: Some(sha256_hex),
+            ssdeep: fuzzy.ssdeep,
+            tlsh: fuzzy.tlsh,
             validated: !eof_truncated,
             truncated: eof_truncated,
             errors: Vec::new(),
             pattern_id: Some(hit.pattern_id.clone()),
+            ..Default::default()
         }))
     }
 }
@@ -209,7 +208,7 @@ mod tests {
         let evidence = SliceEvidence { data: data.clone() };
         let handler = MobiCarveHandler::new("mobi".to_string(), 0, 0);
         let hit = NormalizedHit {
-            global_offset: 60,
+            global_offset: 0,
             file_type_id: "mobi".to_string(),
             pattern_id: "mobi_pdb".to_string(),
         };
@@ -1,6 +1,8 @@
 pub mod avi;
+pub mod bifragment;
 pub mod bmp;
 pub mod bzip2;
+pub mod dedup;
 pub mod elf;
 pub mod eml;
 pub mod fb2;
@@ -42,6 +44,7 @@ use sha2::{Digest, Sha256};
 use thiserror::Error;
 
 use crate::evidence::EvidenceSource;
+use crate::fuzzy::{FuzzyDigests, FuzzyHasher};
 use crate::scanner::NormalizedHit;
 
 /// Metadata about a carved file.
@@ -58,16 +61,14 @@ use crate::scanner::NormalizedHit;
 ///     global_start: 4096,
 ///     global_end: 8191,
 ///     size: 4096,
-///     md5: None,
 ///     sha256: Some("deadbeef".to_string()),
 ///     validated: true,
-///     truncated: false,
-///     errors: Vec::new(),
 ///     pattern_id: Some("jpeg_soi".to_string()),
+///     ..Default::default()
 /// };
 /// let _ = file;
 /// ```
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Default, Serialize)]
 pub struct CarvedFile {
     pub run_id: String,
     pub file_type: String,
@@ -78,10 +79,109 @@ pub struct CarvedFile {
     pub size: u64,
     pub md5: Option<String>,
     pub sha256: Option<String>,
+    /// Fuzzy hashes of the carved bytes; `tlsh` is `None` for files under
+    /// 50 bytes or with too little variety.
+    pub ssdeep: Option<String>,
+    pub tlsh: Option<String>,
     pub validated: bool,
     pub truncated: bool,
     pub errors: Vec<String>,
     pub pattern_id: Option<String>,
+    /// Post-carve validation verdict (`passed`, `failed`, `unsupported`);
+    /// `None` when `--validate-carved` is off.
+    pub validation_status: Option<String>,
+    pub validation_reason: Option<String>,
+    /// Partition containing `global_start`, when the evidence has a
+    /// partition table. `partition_offset` is relative to the partition start.
+    pub partition_index: Option<u32>,
+    pub partition_type: Option<String>,
+    pub partition_offset: Option<u64>,
+    /// `allocated`, `unallocated` or `partially_allocated` according to the
+    /// file system allocation map; `None` unless `--unallocated-only` is on.
+    pub allocation_status: Option<String>,
+    /// Live file whose data contains `global_start`, and where inside it;
+    /// `None` unless `--attribute-fs-paths` is on.
+    pub containing_fs_path: Option<String>,
+    pub containing_inode: Option<u64>,
+    pub offset_in_file: Option<u64>,
+    /// `known_good` or `known_bad` when the file's hash is in a
+    /// `--known-good`/`--known-bad` set, with the name of that set.
+    pub hash_match: Option<String>,
+    pub hash_set: Option<String>,
+    /// Set when a file with the same SHA-256 was already carved in this
+    /// run; `duplicate_of` is the path of that first copy.
+    pub is_duplicate: bool,
+    pub duplicate_of: Option<String>,
+    /// Evidence ranges the file was joined from, in file order, when
+    /// bifragment carving reassembled it; empty for contiguous files.
+    pub fragments: Vec<Fragment>,
+    /// Container the file was carved from by recursive carving, the member
+    /// of it that held the file (archive entry name, `obj <n>` for a PDF
+    /// stream, MIME part file name) and the file's offset within that
+    /// decoded member. `None` for files carved straight from the evidence,
+    /// whose `depth` is 0; embedded files are one deeper than their parent
+    /// and keep the `global_start`/`global_end` of the outermost container,
+    /// the evidence range they were decoded from.
+    pub parent_path: Option<String>,
+    pub parent_id: Option<String>,
+    pub offset_in_parent: Option<u64>,
+    pub depth: u32,
+    /// `none`, `full` or `headers` (see [`Encryption`]) for the types whose
+    /// handler looks for encryption markers (ZIP, OLE, PDF, RAR, 7z), with
+    /// the cipher when the file names it; `unknown` for other types and
+    /// for files that could not be read far enough to tell.
+    pub encryption: Encryption,
+    pub encryption_algorithm: Option<String>,
+}
+
+impl CarvedFile {
+    /// Whether the handler found any encryption marker.
+    pub fn is_encrypted(&self) -> bool {
+        matches!(self.encryption, Encryption::Full | Encryption::Headers)
+    }
+}
+
+/// How much of a carved file is encrypted, as far as its handler can tell.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Encryption {
+    None,
+    /// Member or stream contents are encrypted; names and structure are
+    /// readable.
+    Full,
+    /// Headers are encrypted as well, hiding member names and sizes.
+    Headers,
+    /// The file type is not checked, or the file could not be read far
+    /// enough to tell.
+    #[default]
+    Unknown,
+}
+
+impl Encryption {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Encryption::None => "none",
+            Encryption::Full => "full",
+            Encryption::Headers => "headers",
+            Encryption::Unknown => "unknown",
+        }
+    }
+}
+
+/// A run of evidence bytes forming part of a carved file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct Fragment {
+    pub start: u64,
+    pub length: u64,
+}
+
+/// `start:length` pairs separated by `; `, for the flat metadata formats.
+pub fn join_fragments(fragments: &[Fragment]) -> String {
+    fragments
+        .iter()
+        .map(|f| format!("{}:{}", f.start, f.length))
+        .collect::<Vec<_>>()
+        .join("; ")
 }
 
 pub struct ExtractionContext<'a> {
@@ -224,6 +324,7 @@ pub fn build_carved_file(
         truncated,
         errors,
         pattern_id: Some(pattern_id.to_string()),
+        ..Default::default()
     }
 }
 
@@ -245,6 +346,7 @@ pub(crate) struct CarveStream<'a> {
     writer: BufWriter<File>,
     md5: md5::Context,
     sha256: Sha256,
+    fuzzy: FuzzyHasher,
 }
 
 impl<'a> CarveStream<'a> {
@@ -262,6 +364,7 @@ impl<'a> CarveStream<'a> {
             writer: BufWriter::new(writer),
             md5: md5::Context::new(),
             sha256: Sha256::new(),
+            fuzzy: FuzzyHasher::new(),
         }
     }
 
@@ -294,16 +397,18 @@ impl<'a> CarveStream<'a> {
         self.writer.write_all(buf)?;
         self.md5.consume(buf);
         self.sha256.update(buf);
+        self.fuzzy.update(buf);
         self.offset = self.offset.saturating_add(buf.len() as u64);
         self.written = self.written.saturating_add(buf.len() as u64);
         Ok(())
     }
 
-    pub(crate) fn finish(mut self) -> Result<(u64, String, String), CarveError> {
+    pub(crate) fn finish(mut self) -> Result<(u64, String, String, FuzzyDigests), CarveError> {
         self.writer.flush()?;
         let md5 = format!("{:x}", self.md5.compute());
         let sha256 = hex::encode(self.sha256.finalize());
-        Ok((self.written, md5, sha256))
+        let fuzzy = self.fuzzy.finish();
+        Ok((self.written, md5, sha256, fuzzy))
     }
 
     /// Get the number of bytes written so far
@@ -319,6 +424,7 @@ pub(crate) fn write_range(
     file: &mut File,
     md5: &mut md5::Context,
     sha256: &mut Sha256,
+    fuzzy: &mut FuzzyHasher,
 ) -> Result<(u64, bool), CarveError> {
     let mut offset = start;
     let mut remaining = end.saturating_sub(start);
@@ -339,6 +445,7 @@ pub(crate) fn write_range(
         file.write_all(&buf)?;
         md5.consume(&buf);
         sha256.update(&buf);
+        fuzzy.update(&buf);
         bytes_written = bytes_written.saturating_add(buf.len() as u64);
         offset = offset.saturating_add(buf.len() as u64);
         remaining = remaining.saturating_sub(buf.len() as u64);
@@ -317,7 +317,7 @@ impl CarveHandler for Mp3CarveHandler {
             return Ok(None);
         }
 
-        let (size, md5_hex, sha256_hex) = stream.finish()?;
+        let (size, md5_hex, sha256_hex, fuzzy) = stream.finish()?;
 
         // Check minimum size
         if size < self.min_size {
@@ -349,10 +349,13 @@ impl CarveHandler for Mp3CarveHandler {
             size,
             md5: Some(md5_hex),
             sha256: Some(sha256_hex),
+            ssdeep: fuzzy.ssdeep,
+            tlsh: fuzzy.tlsh,
             validated,
             truncated,
             errors,
             pattern_id: Some(hit.pattern_id.clone()),
+            ..Default::default()
         }))
     }
 }
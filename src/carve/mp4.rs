@@ -6,6 +6,7 @@ use sha2::{Digest, Sha256};
 use crate::carve::{
     CarveError, CarveHandler, CarvedFile, ExtractionContext, output_path, write_range,
 };
+use crate::fuzzy::FuzzyHasher;
 use crate::scanner::NormalizedHit;
 
 const BOX_HEADER_LEN: usize = 8;
@@ -152,6 +153,7 @@ impl CarveHandler for Mp4CarveHandler {
         let mut file = File::create(&full_path)?;
         let mut md5 = md5::Context::new();
         let mut sha256 = Sha256::new();
+        let mut fuzzy = FuzzyHasher::new();
 
         let mut total_end = last_good;
         if self.max_size > 0 && total_end - hit.global_offset > self.max_size {
@@ -165,6 +167,7 @@ impl CarveHandler for Mp4CarveHandler {
             &mut file,
             &mut md5,
             &mut sha256,
+            &mut fuzzy,
         )?;
         if eof_truncated {
             truncated = true;
@@ -179,6 +182,7 @@ impl CarveHandler for Mp4CarveHandler {
 
         let md5_hex = format!("{:x}", md5.compute());
         let sha256_hex = hex::encode(sha256.finalize());
+        let fuzzy = fuzzy.finish();
         let global_end = if written == 0 {
             hit.global_offset
         } else {
@@ -195,10 +199,13 @@ impl CarveHandler for Mp4CarveHandler {
             size: written,
             md5: Some(md5_hex),
             sha256: Some(sha256_hex),
+            ssdeep: fuzzy.ssdeep,
+            tlsh: fuzzy.tlsh,
             validated: !truncated,
             truncated,
             errors,
             pattern_id: Some(hit.pattern_id.clone()),
+            ..Default::default()
         }))
     }
 }
@@ -248,7 +255,7 @@ mod tests {
         let hit = NormalizedHit {
             global_offset: 0,
             file_type_id: "mp4".to_string(),
-            pattern_id: "mp4_ftyp_18".to_string(),
+            pattern_id: "mp4_ftyp".to_string(),
         };
 
         let carved = handler.process_hit(&hit, &ctx).expect("carve");
@@ -286,7 +293,7 @@ mod tests {
         let hit = NormalizedHit {
             global_offset: 0,
             file_type_id: "mp4".to_string(),
-            pattern_id: "mp4_ftyp_18".to_string(),
+            pattern_id: "mp4_ftyp".to_string(),
         };
 
         let carved = handler.process_hit(&hit, &ctx).expect("carve");
@@ -322,7 +329,7 @@ mod tests {
         let hit = NormalizedHit {
             global_offset: 0,
             file_type_id: "mp4".to_string(),
-            pattern_id: "mp4_ftyp_18".to_string(),
+            pattern_id: "mp4_ftyp".to_string(),
         };
 
         let carved = handler.process_hit(&hit, &ctx).expect("carve");
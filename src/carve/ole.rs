@@ -8,7 +8,7 @@
 use std::fs::File;
 
 use crate::carve::{
-    CarveError, CarveHandler, CarveStream, CarvedFile, ExtractionContext, output_path,
+    CarveError, CarveHandler, CarveStream, CarvedFile, Encryption, ExtractionContext, output_path,
 };
 use crate::evidence::EvidenceSource;
 use crate::scanner::NormalizedHit;
@@ -26,6 +26,21 @@ const SECTOR_SIZE_V3: u64 = 512;
 /// Sector size for version 4
 const SECTOR_SIZE_V4: u64 = 4096;
 
+/// Streams smaller than this are stored in the mini stream
+const MINI_STREAM_CUTOFF: u64 = 4096;
+const MINI_SECTOR_SIZE: u64 = 64;
+/// Bytes read from the start of a stream that carries an encryption header
+const STREAM_PEEK_LEN: u64 = 4096;
+
+/// FIB flags of the WordDocument stream
+const FIB_ENCRYPTED: u16 = 0x0100;
+const FIB_TABLE_1: u16 = 0x0200;
+const FIB_OBFUSCATED: u16 = 0x8000;
+/// BIFF record that follows BOF in an encrypted workbook
+const BIFF_FILEPASS: u16 = 0x002F;
+/// Header token of an encrypted PowerPoint "Current User" atom
+const PPT_ENCRYPTED_TOKEN: u32 = 0xF3D1_C4DF;
+
 pub struct OleCarveHandler {
     extension: String,
     min_size: u64,
@@ -71,7 +86,7 @@ impl OleCarveHandler {
 /// - Bytes 64-67: First DIFAT sector
 /// - Bytes 68-71: Number of DIFAT sectors
 /// - Bytes 72-511: DIFAT array (109 entries, each 4 bytes)
-fn parse_ole_header(header: &[u8]) -> Result<(u64, u64), CarveError> {
+pub(crate) fn parse_ole_header(header: &[u8]) -> Result<(u64, u64), CarveError> {
     if header.len() < 512 {
         return Err(CarveError::Invalid("ole header too short".to_string()));
     }
@@ -297,13 +312,276 @@ fn refine_ole_size(
     Ok(total_size.min(max_size))
 }
 
-fn classify_ole_kind(
+fn classify_ole_kind(directory: &[DirectoryEntry]) -> Option<&'static str> {
+    let has_stream = |names: &[&str]| {
+        directory
+            .iter()
+            .any(|entry| entry.entry_type == ENTRY_STREAM && names.contains(&entry.name.as_str()))
+    };
+    if has_stream(&["WordDocument"]) {
+        Some("doc")
+    } else if has_stream(&["Workbook", "Book"]) {
+        Some("xls")
+    } else if has_stream(&["PowerPoint Document"]) {
+        Some("ppt")
+    } else {
+        None
+    }
+}
+
+/// Encryption of an OLE file: password-protected OOXML packages
+/// (`EncryptedPackage`), and Word, Excel and PowerPoint 97-2003 files
+/// flagged as encrypted in their main stream.
+fn ole_encryption(
+    evidence: &dyn EvidenceSource,
+    base_offset: u64,
+    header: &[u8],
+    sector_size: u64,
+    max_size: u64,
+    directory: &[DirectoryEntry],
+) -> (Encryption, Option<String>) {
+    let Some(streams) = StreamReader::new(
+        evidence,
+        base_offset,
+        header,
+        sector_size,
+        max_size,
+        directory,
+    ) else {
+        return (Encryption::Unknown, None);
+    };
+    let find = |name: &str| {
+        directory
+            .iter()
+            .find(|entry| entry.entry_type == ENTRY_STREAM && entry.name == name)
+    };
+    let read = |name: &str, len: u64| find(name).and_then(|entry| streams.read(entry, len));
+
+    if find("EncryptedPackage").is_some() {
+        let algorithm =
+            read("EncryptionInfo", STREAM_PEEK_LEN).and_then(|info| encryption_info_cipher(&info));
+        return (Encryption::Full, algorithm);
+    }
+    if let Some(fib) = read("WordDocument", 12).filter(|fib| fib.len() == 12) {
+        let flags = u16::from_le_bytes([fib[0x0A], fib[0x0B]]);
+        if flags & FIB_ENCRYPTED == 0 {
+            return (Encryption::None, None);
+        }
+        let algorithm = if flags & FIB_OBFUSCATED != 0 {
+            Some("xor".to_string())
+        } else {
+            let table = if flags & FIB_TABLE_1 != 0 {
+                "1Table"
+            } else {
+                "0Table"
+            };
+            read(table, 4).and_then(|version| rc4_cipher(&version))
+        };
+        return (Encryption::Full, algorithm);
+    }
+    if let Some(workbook) = read("Workbook", 64).or_else(|| read("Book", 64)) {
+        // FilePass, when present, is the record right after BOF
+        let field16 = |at: usize| {
+            workbook
+                .get(at..at + 2)
+                .map(|b| u16::from_le_bytes([b[0], b[1]]))
+        };
+        let next = 4 + field16(2).unwrap_or(0) as usize;
+        if field16(next) != Some(BIFF_FILEPASS) {
+            return (Encryption::None, None);
+        }
+        let algorithm = match field16(next + 4) {
+            Some(0) => Some("xor".to_string()),
+            Some(1) => workbook.get(next + 6..).and_then(rc4_cipher),
+            _ => None,
+        };
+        return (Encryption::Full, algorithm);
+    }
+    if let Some(atom) = read("Current User", 16).filter(|atom| atom.len() == 16) {
+        let token = u32::from_le_bytes([atom[12], atom[13], atom[14], atom[15]]);
+        if token == PPT_ENCRYPTED_TOKEN {
+            return (Encryption::Full, Some("rc4-cryptoapi".to_string()));
+        }
+    }
+    (Encryption::None, None)
+}
+
+/// Cipher named by the `EncryptionInfo` stream of an encrypted OOXML
+/// package: the key data of agile encryption, or the AlgID of standard
+/// encryption.
+fn encryption_info_cipher(info: &[u8]) -> Option<String> {
+    let major = u16::from_le_bytes([*info.first()?, *info.get(1)?]);
+    let minor = u16::from_le_bytes([*info.get(2)?, *info.get(3)?]);
+    if (major, minor) == (4, 4) {
+        let xml = String::from_utf8_lossy(info.get(8..)?);
+        let key_data = &xml[xml.find("<keyData")?..];
+        let key_data = &key_data[..key_data.find('>')?];
+        let attribute = |name: &str| {
+            let start = key_data.find(&format!("{name}=\""))? + name.len() + 2;
+            let len = key_data[start..].find('"')?;
+            Some(key_data[start..start + len].to_ascii_lowercase())
+        };
+        return Some(format!(
+            "{}-{}",
+            attribute("cipherAlgorithm")?,
+            attribute("keyBits")?
+        ));
+    }
+    if minor != 2 {
+        return None;
+    }
+    let alg_id = u32::from_le_bytes(info.get(20..24)?.try_into().ok()?);
+    match alg_id {
+        0x660E => Some("aes-128".to_string()),
+        0x660F => Some("aes-192".to_string()),
+        0x6610 => Some("aes-256".to_string()),
+        0x6801 => Some("rc4".to_string()),
+        _ => None,
+    }
+}
+
+/// Cipher of the RC4 encryption header of Word and Excel 97-2003 files:
+/// version 1.1 is plain RC4, 2.2 to 4.2 RC4 with CryptoAPI.
+fn rc4_cipher(version: &[u8]) -> Option<String> {
+    match u16::from_le_bytes([*version.first()?, *version.get(1)?]) {
+        1 => Some("rc4".to_string()),
+        2..=4 => Some("rc4-cryptoapi".to_string()),
+        _ => None,
+    }
+}
+
+/// Reads the start of streams, through the FAT or, for streams below the
+/// cutoff, the mini FAT and the mini stream held by the root entry.
+struct StreamReader<'a> {
+    evidence: &'a dyn EvidenceSource,
+    base_offset: u64,
+    sector_size: u64,
+    max_size: u64,
+    fat: Vec<u32>,
+    mini_fat: Vec<u32>,
+    /// Sectors of the mini stream, in order
+    mini_stream: Vec<u32>,
+}
+
+impl<'a> StreamReader<'a> {
+    fn new(
+        evidence: &'a dyn EvidenceSource,
+        base_offset: u64,
+        header: &[u8],
+        sector_size: u64,
+        max_size: u64,
+        directory: &[DirectoryEntry],
+    ) -> Option<Self> {
+        let fat = read_fat(evidence, base_offset, header, sector_size, max_size).ok()?;
+        let mut reader = Self {
+            evidence,
+            base_offset,
+            sector_size,
+            max_size,
+            fat,
+            mini_fat: Vec::new(),
+            mini_stream: Vec::new(),
+        };
+        let first_mini_fat = u32::from_le_bytes([header[60], header[61], header[62], header[63]]);
+        let mini_fat_count = u32::from_le_bytes([header[64], header[65], header[66], header[67]]);
+        for sector in follow_chain(&reader.fat, first_mini_fat, mini_fat_count as usize) {
+            let Some(buf) = reader.read_sector(sector, 0, sector_size) else {
+                break;
+            };
+            reader.mini_fat.extend(
+                buf.chunks_exact(4)
+                    .map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]])),
+            );
+        }
+        if let Some(root) = directory.first() {
+            let sectors = root.size.div_ceil(sector_size).min(max_size / sector_size);
+            reader.mini_stream = follow_chain(&reader.fat, root.start_sector, sectors as usize);
+        }
+        Some(reader)
+    }
+
+    /// Up to `limit` bytes from the start of a stream; `None` when they
+    /// cannot all be read.
+    fn read(&self, entry: &DirectoryEntry, limit: u64) -> Option<Vec<u8>> {
+        let len = entry.size.min(limit) as usize;
+        let mut out = Vec::with_capacity(len);
+        if entry.size < MINI_STREAM_CUTOFF {
+            let count = (len as u64).div_ceil(MINI_SECTOR_SIZE) as usize;
+            for mini in follow_chain(&self.mini_fat, entry.start_sector, count) {
+                let pos = u64::from(mini) * MINI_SECTOR_SIZE;
+                let sector = *self.mini_stream.get((pos / self.sector_size) as usize)?;
+                out.extend(self.read_sector(sector, pos % self.sector_size, MINI_SECTOR_SIZE)?);
+            }
+        } else {
+            let count = (len as u64).div_ceil(self.sector_size) as usize;
+            for sector in follow_chain(&self.fat, entry.start_sector, count) {
+                out.extend(self.read_sector(sector, 0, self.sector_size)?);
+            }
+        }
+        if out.len() < len {
+            return None;
+        }
+        out.truncate(len);
+        Some(out)
+    }
+
+    fn read_sector(&self, sector: u32, within: u64, len: u64) -> Option<Vec<u8>> {
+        let offset = 512u64 + u64::from(sector) * self.sector_size + within;
+        if offset + len > self.max_size {
+            return None;
+        }
+        let mut buf = vec![0u8; len as usize];
+        let n = self
+            .evidence
+            .read_at(self.base_offset + offset, &mut buf)
+            .ok()?;
+        (n == buf.len()).then_some(buf)
+    }
+}
+
+/// Sector IDs of a chain, at most `limit` of them so that loops end.
+fn follow_chain(table: &[u32], start: u32, limit: usize) -> Vec<u32> {
+    let mut chain = Vec::new();
+    let mut current = start;
+    while current < 0xFFFFFFFA && chain.len() < limit {
+        chain.push(current);
+        current = table.get(current as usize).copied().unwrap_or(0xFFFFFFFE);
+    }
+    chain
+}
+
+/// Directory entry type of a storage (a folder of streams)
+pub(crate) const ENTRY_STORAGE: u8 = 1;
+/// Directory entry type of a stream
+pub(crate) const ENTRY_STREAM: u8 = 2;
+
+/// One 128-byte record of the OLE directory.
+pub(crate) struct DirectoryEntry {
+    /// Empty for unused records.
+    pub name: String,
+    pub entry_type: u8,
+    /// Stream IDs of the left and right siblings and, for storages, the
+    /// first child; `0xFFFFFFFF` when absent.
+    pub left: u32,
+    pub right: u32,
+    pub child: u32,
+    /// FILETIME, 0 when unset (it always is for streams).
+    pub modified: u64,
+    /// First sector of the stream, a mini sector when it is below the
+    /// cutoff; the root entry's is the mini stream's.
+    pub start_sector: u32,
+    pub size: u64,
+}
+
+/// Read the directory records following the directory sector chain, so
+/// that a record's index is its stream ID.
+pub(crate) fn read_directory(
     evidence: &dyn EvidenceSource,
     base_offset: u64,
     header: &[u8],
     sector_size: u64,
     max_size: u64,
-) -> Option<&'static str> {
+) -> Option<Vec<DirectoryEntry>> {
     if header.len() < 512 {
         return None;
     }
@@ -314,9 +592,7 @@ fn classify_ole_kind(
     let fat = read_fat(evidence, base_offset, header, sector_size, max_size).ok()?;
     let mut current = first_dir_sector;
     let mut visited = 0u32;
-    let mut found_doc = false;
-    let mut found_xls = false;
-    let mut found_ppt = false;
+    let mut entries = Vec::new();
 
     while current < 0xFFFFFFFA && visited < 1024 {
         let offset = base_offset + 512u64 + (current as u64 * sector_size);
@@ -328,26 +604,32 @@ fn classify_ole_kind(
         if n < sector_size as usize {
             break;
         }
-        for entry in buf.chunks(128) {
-            if entry.len() < 128 {
-                continue;
-            }
+        for entry in buf.chunks_exact(128) {
+            let field32 = |at: usize| {
+                u32::from_le_bytes([entry[at], entry[at + 1], entry[at + 2], entry[at + 3]])
+            };
+            let field64 = |at: usize| u64::from(field32(at)) | (u64::from(field32(at + 4)) << 32);
             let name_len = u16::from_le_bytes([entry[64], entry[65]]) as usize;
-            if name_len < 2 || name_len > 64 {
-                continue;
-            }
-            let entry_type = entry[66];
-            if entry_type != 2 {
-                continue;
-            }
-            let name = decode_utf16le(&entry[..name_len.saturating_sub(2)]);
-            if name == "WordDocument" {
-                found_doc = true;
-            } else if name == "Workbook" || name == "Book" {
-                found_xls = true;
-            } else if name == "PowerPoint Document" {
-                found_ppt = true;
+            let name = if (2..=64).contains(&name_len) {
+                decode_utf16le(&entry[..name_len - 2])
+            } else {
+                String::new()
+            };
+            // Version 3 files only use the low 32 bits of the size
+            let mut size = field64(120);
+            if sector_size == SECTOR_SIZE_V3 {
+                size &= 0xFFFF_FFFF;
             }
+            entries.push(DirectoryEntry {
+                name,
+                entry_type: entry[66],
+                left: field32(68),
+                right: field32(72),
+                child: field32(76),
+                modified: field64(108),
+                start_sector: field32(116),
+                size,
+            });
         }
 
         let next = fat.get(current as usize).copied().unwrap_or(0xFFFFFFFE);
@@ -358,15 +640,7 @@ fn classify_ole_kind(
         visited += 1;
     }
 
-    if found_doc {
-        Some("doc")
-    } else if found_xls {
-        Some("xls")
-    } else if found_ppt {
-        Some("ppt")
-    } else {
-        None
-    }
+    Some(entries)
 }
 
 fn read_fat(
@@ -472,6 +746,7 @@ impl CarveHandler for OleCarveHandler {
         let mut errors = Vec::new();
 
         let mut classified_kind: Option<&'static str> = None;
+        let mut encryption = (Encryption::Unknown, None);
 
         let result: Result<u64, CarveError> = (|| {
             // Read OLE header (512 bytes minimum)
@@ -479,13 +754,23 @@ impl CarveHandler for OleCarveHandler {
 
             // Parse and validate header
             let (_estimated_size, sector_size) = parse_ole_header(&header)?;
-            classified_kind = classify_ole_kind(
+            if let Some(directory) = read_directory(
                 ctx.evidence,
                 hit.global_offset,
                 &header,
                 sector_size,
                 effective_max,
-            );
+            ) {
+                classified_kind = classify_ole_kind(&directory);
+                encryption = ole_encryption(
+                    ctx.evidence,
+                    hit.global_offset,
+                    &header,
+                    sector_size,
+                    effective_max,
+                    &directory,
+                );
+            }
 
             // Try to refine size estimate by reading FAT from evidence directly
             let target_size = refine_ole_size(
@@ -531,7 +816,7 @@ impl CarveHandler for OleCarveHandler {
             }
         }
 
-        let (size, md5_hex, sha256_hex) = stream.finish()?;
+        let (size, md5_hex, sha256_hex, fuzzy) = stream.finish()?;
 
         // Check minimum size
         if size < self.min_size {
@@ -588,10 +873,15 @@ impl CarveHandler for OleCarveHandler {
             size,
             md5: Some(md5_hex),
             sha256: Some(sha256_hex),
+            ssdeep: fuzzy.ssdeep,
+            tlsh: fuzzy.tlsh,
             validated,
             truncated,
             errors,
             pattern_id: Some(hit.pattern_id.clone()),
+            encryption: encryption.0,
+            encryption_algorithm: encryption.1,
+            ..Default::default()
         }))
     }
 }
@@ -692,6 +982,111 @@ mod tests {
         ole
     }
 
+    /// A compound file whose directory holds an encrypted OOXML package,
+    /// with `EncryptionInfo` in the mini stream.
+    fn create_encrypted_package_ole(alg_id: u32) -> Vec<u8> {
+        let mut ole = vec![0u8; 512];
+        ole[0..8].copy_from_slice(&OLE_SIGNATURE);
+        ole[24..26].copy_from_slice(&0x003Eu16.to_le_bytes());
+        ole[26..28].copy_from_slice(&VERSION_3.to_le_bytes());
+        ole[28..30].copy_from_slice(&0xFFFEu16.to_le_bytes());
+        ole[30..32].copy_from_slice(&9u16.to_le_bytes());
+        ole[32..34].copy_from_slice(&6u16.to_le_bytes());
+        ole[44..48].copy_from_slice(&1u32.to_le_bytes());
+        // Directory in sector 1, mini stream cutoff, mini FAT in sector 3
+        ole[48..52].copy_from_slice(&1u32.to_le_bytes());
+        ole[56..60].copy_from_slice(&4096u32.to_le_bytes());
+        ole[60..64].copy_from_slice(&3u32.to_le_bytes());
+        ole[64..68].copy_from_slice(&1u32.to_le_bytes());
+        ole[68..72].copy_from_slice(&0xFFFFFFFEu32.to_le_bytes());
+        // FAT in sector 0
+        ole[76..80].copy_from_slice(&0u32.to_le_bytes());
+        for i in 1..109 {
+            let offset = 76 + i * 4;
+            ole[offset..offset + 4].copy_from_slice(&0xFFFFFFFFu32.to_le_bytes());
+        }
+
+        let mut fat = vec![0xFFu8; 512];
+        fat[0..4].copy_from_slice(&0xFFFFFFFDu32.to_le_bytes());
+        for sector in 1..4 {
+            fat[sector * 4..sector * 4 + 4].copy_from_slice(&0xFFFFFFFEu32.to_le_bytes());
+        }
+        ole.extend_from_slice(&fat);
+
+        let mut dir = vec![0u8; 512];
+        let mut record = |index: usize, name: &str, kind: u8, child: u32, start: u32, size: u64| {
+            let entry = &mut dir[index * 128..(index + 1) * 128];
+            for (i, unit) in name.encode_utf16().enumerate() {
+                entry[i * 2..i * 2 + 2].copy_from_slice(&unit.to_le_bytes());
+            }
+            entry[64..66].copy_from_slice(&((name.len() as u16 + 1) * 2).to_le_bytes());
+            entry[66] = kind;
+            entry[68..72].copy_from_slice(&0xFFFFFFFFu32.to_le_bytes());
+            entry[72..76]
+                .copy_from_slice(&(if index == 1 { 2u32 } else { 0xFFFFFFFF }).to_le_bytes());
+            entry[76..80].copy_from_slice(&child.to_le_bytes());
+            entry[116..120].copy_from_slice(&start.to_le_bytes());
+            entry[120..128].copy_from_slice(&size.to_le_bytes());
+        };
+        record(0, "Root Entry", 5, 1, 2, 64);
+        record(1, "EncryptionInfo", ENTRY_STREAM, 0xFFFFFFFF, 0, 64);
+        record(
+            2,
+            "EncryptedPackage",
+            ENTRY_STREAM,
+            0xFFFFFFFF,
+            0xFFFFFFFE,
+            0,
+        );
+        ole.extend_from_slice(&dir);
+
+        // Mini stream: standard encryption 3.2 naming the AlgID
+        let mut mini = vec![0u8; 512];
+        mini[0..4].copy_from_slice(&[3, 0, 2, 0]);
+        mini[20..24].copy_from_slice(&alg_id.to_le_bytes());
+        ole.extend_from_slice(&mini);
+
+        let mut mini_fat = vec![0xFFu8; 512];
+        mini_fat[0..4].copy_from_slice(&0xFFFFFFFEu32.to_le_bytes());
+        ole.extend_from_slice(&mini_fat);
+        ole
+    }
+
+    #[test]
+    fn flags_encrypted_package() {
+        let evidence = SliceEvidence {
+            data: create_encrypted_package_ole(0x6610),
+        };
+        let handler = OleCarveHandler::new("ole".to_string(), 0, 0, None);
+        let hit = NormalizedHit {
+            global_offset: 0,
+            file_type_id: "ole".to_string(),
+            pattern_id: "ole_cfb".to_string(),
+        };
+        let dir = tempdir().expect("tempdir");
+        let ctx = ExtractionContext {
+            run_id: "test",
+            output_root: dir.path(),
+            evidence: &evidence,
+        };
+
+        let carved = handler
+            .process_hit(&hit, &ctx)
+            .expect("process")
+            .expect("carved file");
+        assert_eq!(carved.encryption, Encryption::Full);
+        assert_eq!(carved.encryption_algorithm.as_deref(), Some("aes-256"));
+    }
+
+    #[test]
+    fn reads_agile_key_data() {
+        let mut info = vec![4, 0, 4, 0, 0x40, 0, 0, 0];
+        info.extend_from_slice(
+            br#"<?xml version="1.0"?><encryption><keyData saltSize="16" blockSize="16" keyBits="256" cipherAlgorithm="AES"/></encryption>"#,
+        );
+        assert_eq!(encryption_info_cipher(&info).as_deref(), Some("aes-256"));
+    }
+
     #[test]
     fn parses_ole_header() {
         let ole = create_minimal_ole();
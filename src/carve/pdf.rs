@@ -1,13 +1,20 @@
 use std::fs::File;
-use std::io::{BufWriter, Write};
+use std::io::{BufWriter, Read, Seek, SeekFrom, Write};
+use std::path::Path;
 
 use sha2::{Digest, Sha256};
 
-use crate::carve::{CarveError, CarveHandler, CarvedFile, ExtractionContext, output_path};
+use crate::carve::{
+    CarveError, CarveHandler, CarvedFile, Encryption, ExtractionContext, output_path,
+};
+use crate::fuzzy::FuzzyHasher;
 use crate::scanner::NormalizedHit;
 
 const PDF_HEADER: &[u8] = b"%PDF-";
 const PDF_EOF: &[u8] = b"%%EOF";
+/// Bytes read from each end of a carved PDF when looking for its trailer
+/// and encryption dictionary
+const ENCRYPT_WINDOW: u64 = 64 * 1024;
 
 pub struct PdfCarveHandler {
     extension: String,
@@ -49,6 +56,7 @@ impl CarveHandler for PdfCarveHandler {
         let mut writer = BufWriter::new(file);
         let mut md5 = md5::Context::new();
         let mut sha256 = Sha256::new();
+        let mut fuzzy = FuzzyHasher::new();
 
         let mut offset = hit.global_offset;
         let mut bytes_written = 0u64;
@@ -105,6 +113,7 @@ impl CarveHandler for PdfCarveHandler {
                     writer.write_all(slice)?;
                     md5.consume(slice);
                     sha256.update(slice);
+                    fuzzy.update(slice);
                     bytes_written = bytes_written.saturating_add(slice.len() as u64);
                 }
 
@@ -115,6 +124,7 @@ impl CarveHandler for PdfCarveHandler {
             writer.write_all(&buf)?;
             md5.consume(&buf);
             sha256.update(&buf);
+            fuzzy.update(&buf);
             bytes_written = bytes_written.saturating_add(buf.len() as u64);
             offset = offset.saturating_add(buf.len() as u64);
 
@@ -131,6 +141,7 @@ impl CarveHandler for PdfCarveHandler {
                     writer.write_all(&[next])?;
                     md5.consume(&[next]);
                     sha256.update(&[next]);
+                    fuzzy.update(&[next]);
                     bytes_written = bytes_written.saturating_add(1);
                     if next == b'\r' {
                         if let Some(next2) = read_byte(ctx, hit.global_offset + bytes_written) {
@@ -138,6 +149,7 @@ impl CarveHandler for PdfCarveHandler {
                                 writer.write_all(&[next2])?;
                                 md5.consume(&[next2]);
                                 sha256.update(&[next2]);
+                                fuzzy.update(&[next2]);
                                 bytes_written = bytes_written.saturating_add(1);
                             }
                         }
@@ -153,8 +165,11 @@ impl CarveHandler for PdfCarveHandler {
             return Ok(None);
         }
 
+        let (encryption, encryption_algorithm) = pdf_encryption(&full_path, validated);
+
         let md5_hex = format!("{:x}", md5.compute());
         let sha256_hex = hex::encode(sha256.finalize());
+        let fuzzy = fuzzy.finish();
         let global_end = if bytes_written == 0 {
             hit.global_offset
         } else {
@@ -171,14 +186,124 @@ impl CarveHandler for PdfCarveHandler {
             size: bytes_written,
             md5: Some(md5_hex),
             sha256: Some(sha256_hex),
+            ssdeep: fuzzy.ssdeep,
+            tlsh: fuzzy.tlsh,
             validated,
             truncated,
             errors,
             pattern_id: Some(hit.pattern_id.clone()),
+            encryption,
+            encryption_algorithm,
+            ..Default::default()
         }))
     }
 }
 
+/// Encryption of a carved PDF, from the `/Encrypt` entry of its trailer
+/// (at the end, or at the start of linearized files). Without `/Encrypt`
+/// a carve that stopped before `%%EOF` is `unknown`.
+fn pdf_encryption(path: &Path, complete: bool) -> (Encryption, Option<String>) {
+    let Ok(data) = read_ends(path) else {
+        return (Encryption::Unknown, None);
+    };
+    let Some(pos) = find_key(&data, b"/Encrypt") else {
+        let encryption = if complete {
+            Encryption::None
+        } else {
+            Encryption::Unknown
+        };
+        return (encryption, None);
+    };
+    let value = trim_start(&data[pos + b"/Encrypt".len()..]);
+    // Either an inline dictionary or an indirect reference `12 0 R`
+    let dict = if value.starts_with(b"<<") {
+        Some(value)
+    } else {
+        let mut parts = value
+            .split(|b| b.is_ascii_whitespace())
+            .filter(|part| !part.is_empty());
+        match (parts.next(), parts.next()) {
+            (Some(number), Some(generation)) => {
+                let mut header = b"\n".to_vec();
+                header.extend_from_slice(number);
+                header.push(b' ');
+                header.extend_from_slice(generation);
+                header.extend_from_slice(b" obj");
+                find_pattern(&data, &header).map(|at| &data[at + header.len()..])
+            }
+            _ => None,
+        }
+    };
+    (Encryption::Full, dict.and_then(encrypt_cipher))
+}
+
+/// Cipher of a standard security handler dictionary, from its version
+/// `/V`, key `/Length` and crypt filter method.
+fn encrypt_cipher(dict: &[u8]) -> Option<String> {
+    let end = find_pattern(dict, b"endobj")
+        .unwrap_or(dict.len())
+        .min(4096);
+    let dict = &dict[..end];
+    if find_key(dict, b"/AESV3").is_some() {
+        return Some("aes-256".to_string());
+    }
+    if find_key(dict, b"/AESV2").is_some() {
+        return Some("aes-128".to_string());
+    }
+    match dict_number(dict, b"/V")? {
+        1 => Some("rc4-40".to_string()),
+        2 | 3 => Some(format!(
+            "rc4-{}",
+            dict_number(dict, b"/Length").unwrap_or(40)
+        )),
+        4 => Some("rc4-128".to_string()),
+        5 => Some("aes-256".to_string()),
+        _ => None,
+    }
+}
+
+/// Position of a name that is not the prefix of a longer name.
+fn find_key(data: &[u8], key: &[u8]) -> Option<usize> {
+    let mut start = 0usize;
+    while let Some(pos) = find_pattern(&data[start..], key) {
+        let at = start + pos;
+        match data.get(at + key.len()) {
+            Some(next) if next.is_ascii_alphanumeric() => start = at + 1,
+            _ => return Some(at),
+        }
+    }
+    None
+}
+
+fn dict_number(dict: &[u8], key: &[u8]) -> Option<u64> {
+    let value = trim_start(&dict[find_key(dict, key)? + key.len()..]);
+    let digits = value.iter().take_while(|b| b.is_ascii_digit()).count();
+    std::str::from_utf8(&value[..digits]).ok()?.parse().ok()
+}
+
+fn trim_start(data: &[u8]) -> &[u8] {
+    let skip = data.iter().take_while(|b| b.is_ascii_whitespace()).count();
+    &data[skip..]
+}
+
+/// The first and last [`ENCRYPT_WINDOW`] bytes of a file, or all of it
+/// when it is small.
+fn read_ends(path: &Path) -> std::io::Result<Vec<u8>> {
+    let mut file = File::open(path)?;
+    let len = file.metadata()?.len();
+    if len <= 2 * ENCRYPT_WINDOW {
+        let mut data = Vec::with_capacity(len as usize);
+        file.read_to_end(&mut data)?;
+        return Ok(data);
+    }
+    let mut data = vec![0u8; 2 * ENCRYPT_WINDOW as usize];
+    let (head, tail) = data.split_at_mut(ENCRYPT_WINDOW as usize);
+    file.read_exact(head)?;
+    file.seek(SeekFrom::End(-(ENCRYPT_WINDOW as i64)))?;
+    file.read_exact(tail)?;
+    Ok(data)
+}
+
 fn find_pattern(haystack: &[u8], needle: &[u8]) -> Option<usize> {
     if needle.is_empty() || haystack.len() < needle.len() {
         return None;
@@ -199,3 +324,35 @@ fn read_byte(ctx: &ExtractionContext, offset: u64) -> Option<u8> {
     let n = ctx.evidence.read_at(offset, &mut buf).ok()?;
     if n == 1 { Some(buf[0]) } else { None }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::pdf_encryption;
+    use crate::carve::Encryption;
+
+    #[test]
+    fn reads_referenced_encrypt_dictionary() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let path = dir.path().join("locked.pdf");
+        let pdf = b"%PDF-1.6\n1 0 obj\n<< /Type /Catalog >>\nendobj\n\
+            7 0 obj\n<< /Filter /Standard /V 4 /R 4 /Length 128 \
+            /CF << /StdCF << /CFM /AESV2 /Length 16 >> >> >>\nendobj\n\
+            trailer\n<< /Root 1 0 R /Encrypt 7 0 R >>\n%%EOF\n";
+        std::fs::write(&path, pdf).expect("write");
+
+        let (encryption, algorithm) = pdf_encryption(&path, true);
+        assert_eq!(encryption, Encryption::Full);
+        assert_eq!(algorithm.as_deref(), Some("aes-128"));
+    }
+
+    #[test]
+    fn plain_trailer_is_not_encrypted() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let path = dir.path().join("plain.pdf");
+        let pdf = b"%PDF-1.4\ntrailer\n<< /Root 1 0 R >>\n%%EOF\n";
+        std::fs::write(&path, pdf).expect("write");
+
+        assert_eq!(pdf_encryption(&path, true), (Encryption::None, None));
+        assert_eq!(pdf_encryption(&path, false), (Encryption::Unknown, None));
+    }
+}
@@ -1,5 +1,6 @@
 use std::fs::File;
 
+use crate::carve::bifragment::{BifragmentConfig, BifragmentFormat};
 use crate::carve::{
     CarveError, CarveHandler, CarveStream, CarvedFile, ExtractionContext, output_path,
 };
@@ -11,14 +12,21 @@ pub struct PngCarveHandler {
     extension: String,
     min_size: u64,
     max_size: u64,
+    bifragment: Option<BifragmentConfig>,
 }
 
 impl PngCarveHandler {
-    pub fn new(extension: String, min_size: u64, max_size: u64) -> Self {
+    pub fn new(
+        extension: String,
+        min_size: u64,
+        max_size: u64,
+        bifragment: Option<BifragmentConfig>,
+    ) -> Self {
         Self {
             extension,
             min_size,
             max_size,
+            bifragment,
         }
     }
 }
@@ -81,31 +89,54 @@ impl CarveHandler for PngCarveHandler {
             Ok(())
         })();
 
+        // A chunk walk that runs into garbage may be a fragmented PNG.
+        let mut invalid = false;
         if let Err(err) = result {
             match err {
                 CarveError::Truncated | CarveError::Eof => {
                     truncated = true;
                     errors.push(err.to_string());
                 }
-                CarveError::Invalid(_msg) => {
+                CarveError::Invalid(_msg) if self.bifragment.is_none() => {
                     let _ = std::fs::remove_file(&full_path);
                     return Ok(None);
                 }
+                CarveError::Invalid(_msg) => invalid = true,
                 other => return Err(other),
             }
         }
 
-        let (size, md5_hex, sha256_hex) = stream.finish()?;
-        if size < self.min_size {
-            let _ = std::fs::remove_file(&full_path);
-            return Ok(None);
-        }
-
-        let global_end = if size == 0 {
+        let (mut size, mut md5_hex, mut sha256_hex, mut fuzzy) = stream.finish()?;
+        let mut global_end = if size == 0 {
             hit.global_offset
         } else {
             hit.global_offset + size - 1
         };
+        let mut fragments = Vec::new();
+        if let Some(bifragment) = &self.bifragment
+            && let Some(repaired) = bifragment.repair(
+                ctx.evidence,
+                hit.global_offset,
+                &full_path,
+                BifragmentFormat::Png,
+            )?
+        {
+            global_end = repaired.global_end();
+            size = repaired.size;
+            md5_hex = repaired.md5;
+            sha256_hex = repaired.sha256;
+            fuzzy = repaired.fuzzy;
+            fragments = repaired.fragments;
+            invalid = false;
+            validated = true;
+            truncated = false;
+            errors.clear();
+        }
+
+        if invalid || size < self.min_size {
+            let _ = std::fs::remove_file(&full_path);
+            return Ok(None);
+        }
 
         Ok(Some(CarvedFile {
             run_id: ctx.run_id.to_string(),
@@ -117,10 +148,14 @@ impl CarveHandler for PngCarveHandler {
             size,
             md5: Some(md5_hex),
             sha256: Some(sha256_hex),
+            ssdeep: fuzzy.ssdeep,
+            tlsh: fuzzy.tlsh,
             validated,
             truncated,
             errors,
             pattern_id: Some(hit.pattern_id.clone()),
+            fragments,
+            ..Default::default()
         }))
     }
 }
@@ -4,19 +4,29 @@ use std::io::Write;
 use sha2::{Digest, Sha256};
 
 use crate::carve::{
-    CarveError, CarveHandler, CarvedFile, ExtractionContext, output_path, write_range,
+    CarveError, CarveHandler, CarvedFile, Encryption, ExtractionContext, output_path, write_range,
 };
+use crate::fuzzy::FuzzyHasher;
 use crate::scanner::NormalizedHit;
 
-const RAR4_MAGIC: [u8; 7] = [0x52, 0x61, 0x72, 0x21, 0x1A, 0x07, 0x00];
-const RAR5_MAGIC: [u8; 8] = [0x52, 0x61, 0x72, 0x21, 0x1A, 0x07, 0x01, 0x00];
+pub(crate) const RAR4_MAGIC: [u8; 7] = [0x52, 0x61, 0x72, 0x21, 0x1A, 0x07, 0x00];
+pub(crate) const RAR5_MAGIC: [u8; 8] = [0x52, 0x61, 0x72, 0x21, 0x1A, 0x07, 0x01, 0x00];
 
-const RAR4_HEAD_FILE: u8 = 0x74;
-const RAR4_HEAD_END: u8 = 0x7B;
+pub(crate) const RAR4_HEAD_MAIN: u8 = 0x73;
+pub(crate) const RAR4_HEAD_FILE: u8 = 0x74;
+pub(crate) const RAR4_HEAD_END: u8 = 0x7B;
+/// Main header flag: block headers are encrypted
+pub(crate) const RAR4_MAIN_ENCRYPTED: u16 = 0x0080;
+pub(crate) const RAR4_FILE_ENCRYPTED: u16 = 0x0004;
+/// Unpack version from which RAR 3 and later encrypt with AES-128
+const RAR4_AES_VERSION: u8 = 29;
 
-const RAR5_HEAD_END: u64 = 5;
+pub(crate) const RAR5_HEAD_FILE: u64 = 2;
+pub(crate) const RAR5_HEAD_ENCRYPTION: u64 = 4;
+pub(crate) const RAR5_HEAD_END: u64 = 5;
+pub(crate) const RAR5_EXTRA_ENCRYPTION: u64 = 1;
 
-const MAX_RAR5_HEADER_BYTES: u64 = 1024 * 1024;
+pub(crate) const MAX_RAR5_HEADER_BYTES: u64 = 1024 * 1024;
 
 pub struct RarCarveHandler {
     extension: String,
@@ -64,6 +74,7 @@ impl CarveHandler for RarCarveHandler {
         let mut file = File::create(&full_path)?;
         let mut md5 = md5::Context::new();
         let mut sha256 = Sha256::new();
+        let mut fuzzy = FuzzyHasher::new();
 
         let total_end = hit.global_offset + estimate.end;
         let (written, eof_truncated) = write_range(
@@ -73,6 +84,7 @@ impl CarveHandler for RarCarveHandler {
             &mut file,
             &mut md5,
             &mut sha256,
+            &mut fuzzy,
         )?;
         let truncated = estimate.truncated || eof_truncated;
         if eof_truncated {
@@ -87,6 +99,7 @@ impl CarveHandler for RarCarveHandler {
 
         let md5_hex = format!("{:x}", md5.compute());
         let sha256_hex = hex::encode(sha256.finalize());
+        let fuzzy = fuzzy.finish();
         let global_end = if written == 0 {
             hit.global_offset
         } else {
@@ -103,10 +116,15 @@ impl CarveHandler for RarCarveHandler {
             size: written,
             md5: Some(md5_hex),
             sha256: Some(sha256_hex),
+            ssdeep: fuzzy.ssdeep,
+            tlsh: fuzzy.tlsh,
             validated: !truncated,
             truncated,
             errors,
             pattern_id: Some(hit.pattern_id.clone()),
+            encryption: estimate.encryption,
+            encryption_algorithm: estimate.encryption_algorithm.map(str::to_string),
+            ..Default::default()
         }))
     }
 }
@@ -114,6 +132,19 @@ impl CarveHandler for RarCarveHandler {
 struct RarEstimate {
     end: u64,
     truncated: bool,
+    encryption: Encryption,
+    encryption_algorithm: Option<&'static str>,
+}
+
+/// Block headers past the current one are encrypted, so the end of the
+/// archive cannot be found: carve up to `max_size` when one is set.
+fn encrypted_headers_end(offset: u64, start: u64, max_size: u64, errors: &mut Vec<String>) -> u64 {
+    errors.push("rar headers encrypted; end unknown".to_string());
+    if max_size > 0 {
+        start.saturating_add(max_size)
+    } else {
+        offset
+    }
 }
 
 fn estimate_rar_end(
@@ -140,6 +171,8 @@ fn parse_rar4(
 ) -> Result<RarEstimate, CarveError> {
     let mut offset = start + RAR4_MAGIC.len() as u64;
     let mut truncated = false;
+    let mut encryption = Encryption::None;
+    let mut encryption_algorithm = None;
 
     loop {
         if max_size > 0 && offset - start >= max_size {
@@ -176,7 +209,22 @@ fn parse_rar4(
             break;
         }
 
+        if head_type == RAR4_HEAD_MAIN && flags & RAR4_MAIN_ENCRYPTED != 0 {
+            encryption = Encryption::Headers;
+            encryption_algorithm = Some("aes-128");
+            truncated = true;
+            offset =
+                encrypted_headers_end(offset.saturating_add(head_size), start, max_size, errors);
+            break;
+        }
+
         if head_type == RAR4_HEAD_FILE {
+            if flags & RAR4_FILE_ENCRYPTED != 0 && encryption == Encryption::None {
+                encryption = Encryption::Full;
+                encryption_algorithm = read_exact_at(ctx, offset + 24, 1)
+                    .filter(|version| version[0] >= RAR4_AES_VERSION)
+                    .map(|_| "aes-128");
+            }
             let pack_size = match read_exact_at(ctx, offset + 7, 4) {
                 Some(buf) => u32::from_le_bytes([buf[0], buf[1], buf[2], buf[3]]) as u64,
                 None => {
@@ -215,6 +263,8 @@ fn parse_rar4(
     Ok(RarEstimate {
         end: offset.saturating_sub(start),
         truncated,
+        encryption,
+        encryption_algorithm,
     })
 }
 
@@ -226,6 +276,8 @@ fn parse_rar5(
 ) -> Result<RarEstimate, CarveError> {
     let mut offset = start + RAR5_MAGIC.len() as u64;
     let mut truncated = false;
+    let mut encryption = Encryption::None;
+    let mut encryption_algorithm = None;
 
     loop {
         if max_size > 0 && offset - start >= max_size {
@@ -272,9 +324,11 @@ fn parse_rar5(
         let flags = read_varint_buf(&header_buf, &mut idx)
             .ok_or_else(|| CarveError::Invalid("rar5 header flags missing".to_string()))?;
 
-        if flags & 0x01 != 0 {
-            let _ = read_varint_buf(&header_buf, &mut idx);
-        }
+        let extra_size = if flags & 0x01 != 0 {
+            read_varint_buf(&header_buf, &mut idx).unwrap_or(0)
+        } else {
+            0
+        };
         let data_size = if flags & 0x02 != 0 {
             read_varint_buf(&header_buf, &mut idx).unwrap_or(0)
         } else {
@@ -292,6 +346,20 @@ fn parse_rar5(
 
         offset = offset.saturating_add(block_total);
 
+        if header_type == RAR5_HEAD_ENCRYPTION {
+            encryption = Encryption::Headers;
+            encryption_algorithm = Some("aes-256");
+            truncated = true;
+            offset = encrypted_headers_end(offset, start, max_size, errors);
+            break;
+        }
+        if header_type == RAR5_HEAD_FILE && encryption == Encryption::None {
+            let extra_start = header_buf.len().saturating_sub(extra_size as usize);
+            if has_encryption_record(&header_buf[extra_start..]) {
+                encryption = Encryption::Full;
+                encryption_algorithm = Some("aes-256");
+            }
+        }
         if header_type == RAR5_HEAD_END {
             break;
         }
@@ -300,9 +368,27 @@ fn parse_rar5(
     Ok(RarEstimate {
         end: offset.saturating_sub(start),
         truncated,
+        encryption,
+        encryption_algorithm,
     })
 }
 
+/// Whether the extra area of a RAR 5 file header holds an encryption record.
+pub(crate) fn has_encryption_record(extra: &[u8]) -> bool {
+    let mut pos = 0usize;
+    while pos < extra.len() {
+        let Some(size) = read_varint_buf(extra, &mut pos) else {
+            break;
+        };
+        let record_end = pos.saturating_add(size as usize);
+        if read_varint_buf(extra, &mut pos) == Some(RAR5_EXTRA_ENCRYPTION) {
+            return true;
+        }
+        pos = record_end;
+    }
+    false
+}
+
 fn read_varint_at(ctx: &ExtractionContext, offset: u64) -> Option<(u64, u64)> {
     let mut value = 0u64;
     let mut shift = 0u32;
@@ -319,7 +405,7 @@ fn read_varint_at(ctx: &ExtractionContext, offset: u64) -> Option<(u64, u64)> {
     None
 }
 
-fn read_varint_buf(buf: &[u8], idx: &mut usize) -> Option<u64> {
+pub(crate) fn read_varint_buf(buf: &[u8], idx: &mut usize) -> Option<u64> {
     let mut value = 0u64;
     let mut shift = 0u32;
     let mut read = 0u32;
@@ -351,7 +437,7 @@ fn read_exact_at(ctx: &ExtractionContext, offset: u64, len: usize) -> Option<Vec
 #[cfg(test)]
 mod tests {
     use super::RarCarveHandler;
-    use crate::carve::{CarveHandler, ExtractionContext};
+    use crate::carve::{CarveHandler, Encryption, ExtractionContext};
     use crate::evidence::RawFileSource;
     use crate::scanner::NormalizedHit;
 
@@ -432,5 +518,44 @@ mod tests {
         let carved = carved.expect("carved");
         assert!(carved.validated);
         assert_eq!(carved.size, rar.len() as u64);
+        assert_eq!(carved.encryption, Encryption::None);
+    }
+
+    #[test]
+    fn flags_rar5_encrypted_headers() {
+        let temp_dir = tempfile::tempdir().expect("tempdir");
+        let output_root = temp_dir.path().join("out");
+        std::fs::create_dir_all(&output_root).expect("output root");
+
+        let mut rar = Vec::new();
+        rar.extend_from_slice(&super::RAR5_MAGIC);
+        rar.extend_from_slice(&[0u8; 4]); // crc
+        rar.push(2); // header size
+        rar.push(super::RAR5_HEAD_ENCRYPTION as u8); // type archive encryption
+        rar.push(0); // flags
+        rar.extend_from_slice(&[0xAA; 64]); // encrypted headers
+
+        let input_path = temp_dir.path().join("image.bin");
+        std::fs::write(&input_path, &rar).expect("write rar");
+
+        let evidence = RawFileSource::open(&input_path).expect("evidence");
+        let ctx = ExtractionContext {
+            run_id: "test",
+            output_root: &output_root,
+            evidence: &evidence,
+        };
+        let handler = RarCarveHandler::new("rar".to_string(), 8, 32);
+        let hit = NormalizedHit {
+            global_offset: 0,
+            file_type_id: "rar".to_string(),
+            pattern_id: "rar5_header".to_string(),
+        };
+
+        let carved = handler.process_hit(&hit, &ctx).expect("carve");
+        let carved = carved.expect("carved");
+        assert!(carved.truncated);
+        assert_eq!(carved.size, 32);
+        assert_eq!(carved.encryption, Encryption::Headers);
+        assert_eq!(carved.encryption_algorithm.as_deref(), Some("aes-256"));
     }
 }
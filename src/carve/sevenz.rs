@@ -3,13 +3,18 @@ use std::io::Write;
 
 use sha2::{Digest, Sha256};
 
+use crate::archive;
 use crate::carve::{
-    CarveError, CarveHandler, CarvedFile, ExtractionContext, output_path, write_range,
+    CarveError, CarveHandler, CarvedFile, Encryption, ExtractionContext, output_path, write_range,
 };
+use crate::evidence::RawFileSource;
+use crate::fuzzy::FuzzyHasher;
 use crate::scanner::NormalizedHit;
 
-const SEVENZ_MAGIC: [u8; 6] = [0x37, 0x7A, 0xBC, 0xAF, 0x27, 0x1C];
-const SEVENZ_HEADER_LEN: usize = 32;
+pub(crate) const SEVENZ_MAGIC: [u8; 6] = [0x37, 0x7A, 0xBC, 0xAF, 0x27, 0x1C];
+pub(crate) const SEVENZ_HEADER_LEN: usize = 32;
+/// The only cipher 7-Zip writes
+const SEVENZ_CIPHER: &str = "aes-256";
 
 pub struct SevenZCarveHandler {
     extension: String,
@@ -86,6 +91,7 @@ impl CarveHandler for SevenZCarveHandler {
         let mut file = File::create(&full_path)?;
         let mut md5 = md5::Context::new();
         let mut sha256 = Sha256::new();
+        let mut fuzzy = FuzzyHasher::new();
 
         let total_end = hit.global_offset + total_size;
         let (written, eof_truncated) = write_range(
@@ -95,6 +101,7 @@ impl CarveHandler for SevenZCarveHandler {
             &mut file,
             &mut md5,
             &mut sha256,
+            &mut fuzzy,
         )?;
         if eof_truncated {
             truncated = true;
@@ -107,8 +114,14 @@ impl CarveHandler for SevenZCarveHandler {
             return Ok(None);
         }
 
+        // The end header is read back from the carved file
+        let encryption = RawFileSource::open(&full_path).map_or(Encryption::Unknown, |source| {
+            archive::sevenz::encryption(&source)
+        });
+
         let md5_hex = format!("{:x}", md5.compute());
         let sha256_hex = hex::encode(sha256.finalize());
+        let fuzzy = fuzzy.finish();
         let global_end = if written == 0 {
             hit.global_offset
         } else {
@@ -125,10 +138,16 @@ impl CarveHandler for SevenZCarveHandler {
             size: written,
             md5: Some(md5_hex),
             sha256: Some(sha256_hex),
+            ssdeep: fuzzy.ssdeep,
+            tlsh: fuzzy.tlsh,
             validated: !truncated,
             truncated,
             errors,
             pattern_id: Some(hit.pattern_id.clone()),
+            encryption,
+            encryption_algorithm: matches!(encryption, Encryption::Full | Encryption::Headers)
+                .then(|| SEVENZ_CIPHER.to_string()),
+            ..Default::default()
         }))
     }
 }
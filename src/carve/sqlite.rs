@@ -106,7 +106,7 @@ impl CarveHandler for SqliteCarveHandler {
             }
         }
 
-        let (size, md5_hex, sha256_hex) = stream.finish()?;
+        let (size, md5_hex, sha256_hex, fuzzy) = stream.finish()?;
         if size < self.min_size {
             let _ = std::fs::remove_file(&full_path);
             return Ok(None);
@@ -135,10 +135,13 @@ impl CarveHandler for SqliteCarveHandler {
             size,
             md5: Some(md5_hex),
             sha256: Some(sha256_hex),
+            ssdeep: fuzzy.ssdeep,
+            tlsh: fuzzy.tlsh,
             validated,
             truncated,
             errors,
             pattern_id: Some(hit.pattern_id.clone()),
+            ..Default::default()
         }))
     }
 }
@@ -44,14 +44,9 @@ impl CarveHandler for TarCarveHandler {
         hit: &NormalizedHit,
         ctx: &ExtractionContext,
     ) -> Result<Option<CarvedFile>, CarveError> {
-        let start_offset = if hit.pattern_id == "tar_ustar" {
-            if hit.global_offset < TAR_USTAR_OFFSET as u64 {
-                return Ok(None);
-            }
-            hit.global_offset - TAR_USTAR_OFFSET as u64
-        } else {
-            hit.global_offset
-        };
+        // `tar_ustar` is anchored 257 bytes into the header, so the hit is
+        // already the header start.
+        let start_offset = hit.global_offset;
 
         let (full_path, rel_path) = output_path(
             ctx.output_root,
@@ -116,7 +111,7 @@ impl CarveHandler for TarCarveHandler {
             }
         }
 
-        let (size, md5_hex, sha256_hex) = stream.finish()?;
+        let (size, md5_hex, sha256_hex, fuzzy) = stream.finish()?;
 
         if size < self.min_size {
             let _ = std::fs::remove_file(&full_path);
@@ -146,10 +141,13 @@ impl CarveHandler for TarCarveHandler {
             size,
             md5: Some(md5_hex),
             sha256: Some(sha256_hex),
+            ssdeep: fuzzy.ssdeep,
+            tlsh: fuzzy.tlsh,
             validated,
             truncated,
             errors,
             pattern_id: Some(hit.pattern_id.clone()),
+            ..Default::default()
         }))
     }
 }
@@ -158,7 +156,7 @@ fn is_zero_block(block: &[u8]) -> bool {
     block.iter().all(|b| *b == 0)
 }
 
-fn parse_octal(field: &[u8]) -> Result<u64, CarveError> {
+pub(crate) fn parse_octal(field: &[u8]) -> Result<u64, CarveError> {
     let mut value = 0u64;
     let mut seen = false;
     for &b in field {
@@ -177,7 +175,27 @@ fn parse_octal(field: &[u8]) -> Result<u64, CarveError> {
     Ok(value)
 }
 
-fn validate_checksum(header: &[u8]) -> Result<bool, CarveError> {
+/// Entry name, joined with the ustar prefix field when there is one.
+pub(crate) fn header_name(header: &[u8]) -> String {
+    let field = |range: std::ops::Range<usize>| {
+        let raw = &header[range];
+        let len = raw.iter().position(|&b| b == 0).unwrap_or(raw.len());
+        String::from_utf8_lossy(&raw[..len]).into_owned()
+    };
+    let name = field(0..100);
+    let prefix = if &header[257..262] == b"ustar" {
+        field(345..500)
+    } else {
+        String::new()
+    };
+    if prefix.is_empty() {
+        name
+    } else {
+        format!("{prefix}/{name}")
+    }
+}
+
+pub(crate) fn validate_checksum(header: &[u8]) -> Result<bool, CarveError> {
     if header.len() < TAR_BLOCK_SIZE {
         return Err(CarveError::Invalid("tar header too short".to_string()));
     }
@@ -258,7 +276,7 @@ mod tests {
         };
         let handler = TarCarveHandler::new("tar".to_string(), 0, 0);
         let hit = NormalizedHit {
-            global_offset: 257,
+            global_offset: 0,
             file_type_id: "tar".to_string(),
             pattern_id: "tar_ustar".to_string(),
         };
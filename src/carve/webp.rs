@@ -89,7 +89,7 @@ impl CarveHandler for WebpCarveHandler {
             }
         }
 
-        let (size, md5_hex, sha256_hex) = stream.finish()?;
+        let (size, md5_hex, sha256_hex, fuzzy) = stream.finish()?;
         if size < self.min_size {
             let _ = std::fs::remove_file(&full_path);
             return Ok(None);
@@ -118,10 +118,13 @@ impl CarveHandler for WebpCarveHandler {
             size,
             md5: Some(md5_hex),
             sha256: Some(sha256_hex),
+            ssdeep: fuzzy.ssdeep,
+            tlsh: fuzzy.tlsh,
             validated,
             truncated,
             errors,
             pattern_id: Some(hit.pattern_id.clone()),
+            ..Default::default()
         }))
     }
 }
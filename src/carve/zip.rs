@@ -5,20 +5,30 @@ use std::path::Path;
 
 use sha2::{Digest, Sha256};
 
+use crate::carve::bifragment::{BifragmentConfig, BifragmentFormat};
 use crate::carve::{
-    CarveError, CarveHandler, CarvedFile, ExtractionContext, output_path, write_range,
+    CarveError, CarveHandler, CarvedFile, Encryption, ExtractionContext, output_path, write_range,
 };
+use crate::fuzzy::FuzzyHasher;
 use crate::scanner::NormalizedHit;
 
 const ZIP_HEADER: &[u8] = b"PK\x03\x04";
 const ZIP_EOCD: &[u8] = b"PK\x05\x06";
 
+/// General purpose flags: entry is encrypted, with PKWARE strong encryption
+const FLAG_ENCRYPTED: u16 = 0x0001;
+const FLAG_STRONG_ENCRYPTION: u16 = 0x0040;
+/// WinZip AES: compression method and extra field header ID
+const METHOD_AES: u16 = 99;
+const EXTRA_AES: u16 = 0x9901;
+
 pub struct ZipCarveHandler {
     extension: String,
     min_size: u64,
     max_size: u64,
     require_eocd: bool,
     allowed_kinds: Option<HashSet<String>>,
+    bifragment: Option<BifragmentConfig>,
 }
 
 impl ZipCarveHandler {
@@ -28,6 +38,7 @@ impl ZipCarveHandler {
         max_size: u64,
         require_eocd: bool,
         allowed_kinds: Option<Vec<String>>,
+        bifragment: Option<BifragmentConfig>,
     ) -> Self {
         let allowed_kinds = allowed_kinds.map(|kinds| {
             kinds
@@ -41,6 +52,7 @@ impl ZipCarveHandler {
             max_size,
             require_eocd,
             allowed_kinds,
+            bifragment,
         }
     }
 }
@@ -93,6 +105,7 @@ impl CarveHandler for ZipCarveHandler {
             let mut file = File::create(&full_path)?;
             let mut md5 = md5::Context::new();
             let mut sha256 = Sha256::new();
+            let mut fuzzy = FuzzyHasher::new();
 
             let (written, eof_truncated) = write_range(
                 ctx,
@@ -101,6 +114,7 @@ impl CarveHandler for ZipCarveHandler {
                 &mut file,
                 &mut md5,
                 &mut sha256,
+                &mut fuzzy,
             )?;
             bytes_written = written;
             if eof_truncated {
@@ -109,38 +123,55 @@ impl CarveHandler for ZipCarveHandler {
             }
             file.flush()?;
 
-            if bytes_written < self.min_size {
-                let _ = std::fs::remove_file(&full_path);
-                return Ok(None);
-            }
-
-            let md5_hex = format!("{:x}", md5.compute());
-            let sha256_hex = hex::encode(sha256.finalize());
-            let global_end = if bytes_written == 0 {
+            let mut md5_hex = format!("{:x}", md5.compute());
+            let mut sha256_hex = hex::encode(sha256.finalize());
+            let mut fuzzy = fuzzy.finish();
+            let mut global_end = if bytes_written == 0 {
                 hit.global_offset
             } else {
                 hit.global_offset + bytes_written - 1
             };
+            let mut fragments = Vec::new();
+            if let Some(bifragment) = &self.bifragment
+                && let Some(repaired) = bifragment.repair(
+                    ctx.evidence,
+                    hit.global_offset,
+                    &full_path,
+                    BifragmentFormat::Zip,
+                )?
+            {
+                global_end = repaired.global_end();
+                bytes_written = repaired.size;
+                md5_hex = repaired.md5;
+                sha256_hex = repaired.sha256;
+                fuzzy = repaired.fuzzy;
+                fragments = repaired.fragments;
+            }
+
+            if bytes_written < self.min_size {
+                let _ = std::fs::remove_file(&full_path);
+                return Ok(None);
+            }
 
             let mut file_type = self.file_type().to_string();
             let mut extension = self.extension.clone();
 
-            if let Some(parsed) = &eocd {
-                if let Some(kind) = classify_zip(&full_path, parsed.cd_offset, parsed.cd_size) {
-                    file_type = kind.file_type().to_string();
-                    extension = kind.extension().to_string();
-                    if file_type != self.file_type() {
-                        if let Ok((new_path, new_rel)) =
-                            output_path(ctx.output_root, &file_type, &extension, hit.global_offset)
-                        {
-                            if std::fs::rename(&full_path, &new_path).is_ok() {
-                                rel_path = new_rel;
-                                full_path = new_path;
-                            }
-                        }
-                    }
+            let directory = eocd.as_ref().and_then(|parsed| {
+                read_zip_directory(&full_path, parsed.cd_offset, parsed.cd_size)
+            });
+            if let Some(kind) = directory.as_ref().and_then(|directory| directory.kind) {
+                file_type = kind.file_type().to_string();
+                extension = kind.extension().to_string();
+                if file_type != self.file_type()
+                    && let Ok((new_path, new_rel)) =
+                        output_path(ctx.output_root, &file_type, &extension, hit.global_offset)
+                    && std::fs::rename(&full_path, &new_path).is_ok()
+                {
+                    rel_path = new_rel;
+                    full_path = new_path;
                 }
             }
+            let (encryption, encryption_algorithm) = directory_encryption(directory);
 
             if let Some(allowed) = &self.allowed_kinds {
                 if !allowed.contains(&file_type) {
@@ -159,10 +190,16 @@ impl CarveHandler for ZipCarveHandler {
                 size: bytes_written,
                 md5: Some(md5_hex),
                 sha256: Some(sha256_hex),
+                ssdeep: fuzzy.ssdeep,
+                tlsh: fuzzy.tlsh,
                 validated,
                 truncated,
                 errors,
                 pattern_id: Some(hit.pattern_id.clone()),
+                fragments,
+                encryption,
+                encryption_algorithm,
+                ..Default::default()
             }));
         } else {
             output_path(
@@ -176,6 +213,7 @@ impl CarveHandler for ZipCarveHandler {
         let mut file = File::create(&full_path)?;
         let mut md5 = md5::Context::new();
         let mut sha256 = Sha256::new();
+        let mut fuzzy = FuzzyHasher::new();
 
         let mut offset = hit.global_offset;
         let mut carry: Vec<u8> = Vec::new();
@@ -248,6 +286,7 @@ impl CarveHandler for ZipCarveHandler {
                     file.write_all(slice)?;
                     md5.consume(slice);
                     sha256.update(slice);
+                    fuzzy.update(slice);
                     bytes_written = bytes_written.saturating_add(slice.len() as u64);
                 }
 
@@ -270,6 +309,7 @@ impl CarveHandler for ZipCarveHandler {
                         file.write_all(&extra)?;
                         md5.consume(&extra);
                         sha256.update(&extra);
+                        fuzzy.update(&extra);
                         bytes_written = bytes_written.saturating_add(extra.len() as u64);
                         extra_offset = extra_offset.saturating_add(extra.len() as u64);
                         remaining = remaining.saturating_sub(extra.len() as u64);
@@ -283,6 +323,7 @@ impl CarveHandler for ZipCarveHandler {
             file.write_all(&buf)?;
             md5.consume(&buf);
             sha256.update(&buf);
+            fuzzy.update(&buf);
             bytes_written = bytes_written.saturating_add(buf.len() as u64);
             offset = offset.saturating_add(buf.len() as u64);
 
@@ -302,6 +343,7 @@ impl CarveHandler for ZipCarveHandler {
 
         let md5_hex = format!("{:x}", md5.compute());
         let sha256_hex = hex::encode(sha256.finalize());
+        let fuzzy = fuzzy.finish();
         let global_end = if bytes_written == 0 {
             hit.global_offset
         } else {
@@ -311,23 +353,22 @@ impl CarveHandler for ZipCarveHandler {
         let mut file_type = self.file_type().to_string();
         let mut extension = self.extension.clone();
 
-        if validated {
-            if let Some(parsed) = &eocd {
-                if let Some(kind) = classify_zip(&full_path, parsed.cd_offset, parsed.cd_size) {
-                    file_type = kind.file_type().to_string();
-                    extension = kind.extension().to_string();
-                    if file_type != self.file_type() {
-                        if let Ok((new_path, new_rel)) =
-                            output_path(ctx.output_root, &file_type, &extension, hit.global_offset)
-                        {
-                            if std::fs::rename(&full_path, &new_path).is_ok() {
-                                rel_path = new_rel;
-                            }
-                        }
-                    }
-                }
+        let directory = eocd
+            .as_ref()
+            .filter(|_| validated)
+            .and_then(|parsed| read_zip_directory(&full_path, parsed.cd_offset, parsed.cd_size));
+        if let Some(kind) = directory.as_ref().and_then(|directory| directory.kind) {
+            file_type = kind.file_type().to_string();
+            extension = kind.extension().to_string();
+            if file_type != self.file_type()
+                && let Ok((new_path, new_rel)) =
+                    output_path(ctx.output_root, &file_type, &extension, hit.global_offset)
+                && std::fs::rename(&full_path, &new_path).is_ok()
+            {
+                rel_path = new_rel;
             }
         }
+        let (encryption, encryption_algorithm) = directory_encryption(directory);
 
         Ok(Some(CarvedFile {
             run_id: ctx.run_id.to_string(),
@@ -339,10 +380,15 @@ impl CarveHandler for ZipCarveHandler {
             size: bytes_written,
             md5: Some(md5_hex),
             sha256: Some(sha256_hex),
+            ssdeep: fuzzy.ssdeep,
+            tlsh: fuzzy.tlsh,
             validated,
             truncated,
             errors,
             pattern_id: Some(hit.pattern_id.clone()),
+            encryption,
+            encryption_algorithm,
+            ..Default::default()
         }))
     }
 }
@@ -486,7 +532,23 @@ struct ZipEntryInfo {
     compression_method: u16,
 }
 
-fn classify_zip(path: &Path, cd_offset: u64, cd_size: u64) -> Option<ZipKind> {
+/// What the central directory tells about the archive: the document kind
+/// and whether any entry is encrypted.
+struct ZipDirectory {
+    kind: Option<ZipKind>,
+    encryption: Encryption,
+    encryption_algorithm: Option<String>,
+}
+
+/// Encryption of a carved archive, `unknown` when its central directory
+/// could not be read.
+fn directory_encryption(directory: Option<ZipDirectory>) -> (Encryption, Option<String>) {
+    directory.map_or((Encryption::Unknown, None), |directory| {
+        (directory.encryption, directory.encryption_algorithm)
+    })
+}
+
+fn read_zip_directory(path: &Path, cd_offset: u64, cd_size: u64) -> Option<ZipDirectory> {
     if cd_size == 0 || cd_size > 16 * 1024 * 1024 {
         return None;
     }
@@ -501,12 +563,18 @@ fn classify_zip(path: &Path, cd_offset: u64, cd_size: u64) -> Option<ZipKind> {
         return None;
     }
 
+    let mut directory = ZipDirectory {
+        kind: None,
+        encryption: Encryption::None,
+        encryption_algorithm: None,
+    };
     let mut mimetype_entry: Option<ZipEntryInfo> = None;
     let mut idx = 0usize;
     while idx + 46 <= buf.len() {
         if &buf[idx..idx + 4] != b"PK\x01\x02" {
             break;
         }
+        let flags = u16::from_le_bytes([buf[idx + 8], buf[idx + 9]]);
         let compression = u16::from_le_bytes([buf[idx + 10], buf[idx + 11]]);
         let comp_size =
             u32::from_le_bytes([buf[idx + 20], buf[idx + 21], buf[idx + 22], buf[idx + 23]]) as u64;
@@ -521,14 +589,19 @@ fn classify_zip(path: &Path, cd_offset: u64, cd_size: u64) -> Option<ZipKind> {
             break;
         }
         let name = &buf[name_start..name_end];
-        if name.starts_with(b"word/") {
-            return Some(ZipKind::Docx);
-        }
-        if name.starts_with(b"xl/") {
-            return Some(ZipKind::Xlsx);
+        if flags & FLAG_ENCRYPTED != 0 && directory.encryption == Encryption::None {
+            let extra = buf.get(name_end..name_end + extra_len).unwrap_or_default();
+            directory.encryption = Encryption::Full;
+            directory.encryption_algorithm = Some(entry_cipher(flags, compression, extra));
         }
-        if name.starts_with(b"ppt/") {
-            return Some(ZipKind::Pptx);
+        if directory.kind.is_none() {
+            if name.starts_with(b"word/") {
+                directory.kind = Some(ZipKind::Docx);
+            } else if name.starts_with(b"xl/") {
+                directory.kind = Some(ZipKind::Xlsx);
+            } else if name.starts_with(b"ppt/") {
+                directory.kind = Some(ZipKind::Pptx);
+            }
         }
         if name == b"mimetype" {
             mimetype_entry = Some(ZipEntryInfo {
@@ -540,25 +613,48 @@ fn classify_zip(path: &Path, cd_offset: u64, cd_size: u64) -> Option<ZipKind> {
         idx = name_end + extra_len + comment_len;
     }
 
-    if let Some(entry) = mimetype_entry {
-        if let Some(mime) = read_stored_entry(path, &entry) {
-            let mime = trim_ascii(&mime);
-            if mime == b"application/vnd.oasis.opendocument.text" {
-                return Some(ZipKind::Odt);
-            }
-            if mime == b"application/vnd.oasis.opendocument.spreadsheet" {
-                return Some(ZipKind::Ods);
-            }
-            if mime == b"application/vnd.oasis.opendocument.presentation" {
-                return Some(ZipKind::Odp);
-            }
-            if mime == b"application/epub+zip" {
-                return Some(ZipKind::Epub);
+    if directory.kind.is_none()
+        && let Some(entry) = mimetype_entry
+        && let Some(mime) = read_stored_entry(path, &entry)
+    {
+        directory.kind = match trim_ascii(&mime) {
+            b"application/vnd.oasis.opendocument.text" => Some(ZipKind::Odt),
+            b"application/vnd.oasis.opendocument.spreadsheet" => Some(ZipKind::Ods),
+            b"application/vnd.oasis.opendocument.presentation" => Some(ZipKind::Odp),
+            b"application/epub+zip" => Some(ZipKind::Epub),
+            _ => None,
+        };
+    }
+
+    Some(directory)
+}
+
+/// Cipher of an encrypted entry: WinZip AES (key size from its extra
+/// field), PKWARE strong encryption or the traditional ZipCrypto.
+fn entry_cipher(flags: u16, compression: u16, extra: &[u8]) -> String {
+    if compression == METHOD_AES {
+        let mut pos = 0usize;
+        while pos + 4 <= extra.len() {
+            let id = u16::from_le_bytes([extra[pos], extra[pos + 1]]);
+            let len = u16::from_le_bytes([extra[pos + 2], extra[pos + 3]]) as usize;
+            // Version, vendor ID, then the key strength
+            if id == EXTRA_AES && len >= 5 {
+                return match extra.get(pos + 8) {
+                    Some(1) => "aes-128",
+                    Some(2) => "aes-192",
+                    Some(3) => "aes-256",
+                    _ => "aes",
+                }
+                .to_string();
             }
+            pos += 4 + len;
         }
+        "aes".to_string()
+    } else if flags & FLAG_STRONG_ENCRYPTION != 0 {
+        "pkware-strong".to_string()
+    } else {
+        "zipcrypto".to_string()
     }
-
-    None
 }
 
 fn read_stored_entry(path: &Path, entry: &ZipEntryInfo) -> Option<Vec<u8>> {
@@ -625,7 +721,8 @@ fn find_pattern(haystack: &[u8], needle: &[u8]) -> Option<usize> {
 
 #[cfg(test)]
 mod tests {
-    use super::{ZipCarveHandler, ZipKind, classify_zip};
+    use super::{ZipCarveHandler, ZipKind, read_zip_directory};
+    use crate::carve::Encryption;
     use crate::carve::{CarveHandler, ExtractionContext};
     use crate::evidence::RawFileSource;
     use crate::scanner::NormalizedHit;
@@ -642,8 +739,26 @@ mod tests {
         file.write_all(&data).expect("write");
         drop(file);
 
-        let kind = classify_zip(&path, 48, 63);
-        assert_eq!(kind, Some(ZipKind::Docx));
+        let directory = read_zip_directory(&path, 48, 63).expect("directory");
+        assert_eq!(directory.kind, Some(ZipKind::Docx));
+        assert_eq!(directory.encryption, Encryption::None);
+    }
+
+    #[test]
+    fn reports_zipcrypto_entries() {
+        let dir = tempdir().expect("tempdir");
+        let path = dir.path().join("secret.zip");
+        let mut data = sample_zip_with_entry("secret.txt");
+        let cd_offset = 30 + "secret.txt".len() + 1;
+        // Encrypted flag in the central directory entry
+        data[cd_offset + 8] = 0x01;
+        std::fs::write(&path, &data).expect("write");
+
+        let directory = read_zip_directory(&path, cd_offset as u64, 46 + "secret.txt".len() as u64)
+            .expect("directory");
+        assert_eq!(directory.kind, None);
+        assert_eq!(directory.encryption, Encryption::Full);
+        assert_eq!(directory.encryption_algorithm.as_deref(), Some("zipcrypto"));
     }
 
     #[test]
@@ -656,8 +771,8 @@ mod tests {
         file.write_all(&data).expect("write");
         drop(file);
 
-        let kind = classify_zip(&path, cd_offset, cd_size);
-        assert_eq!(kind, Some(ZipKind::Odt));
+        let directory = read_zip_directory(&path, cd_offset, cd_size).expect("directory");
+        assert_eq!(directory.kind, Some(ZipKind::Odt));
     }
 
     fn sample_zip_with_entry(name: &str) -> Vec<u8> {
@@ -785,7 +900,7 @@ mod tests {
             output_root: dir.path(),
             evidence: &evidence,
         };
-        let handler = ZipCarveHandler::new("zip".to_string(), 0, 1024, true, None);
+        let handler = ZipCarveHandler::new("zip".to_string(), 0, 1024, true, None, None);
         let hit = NormalizedHit {
             global_offset: 0,
             file_type_id: "zip".to_string(),
@@ -824,6 +939,7 @@ mod tests {
             1024,
             true,
             Some(vec!["docx".to_string()]),
+            None,
         );
         let result = handler.process_hit(&hit, &ctx).expect("process");
         let carved = result.expect("carved");
@@ -848,6 +964,7 @@ mod tests {
             1024,
             true,
             Some(vec!["xlsx".to_string()]),
+            None,
         );
         let result = handler.process_hit(&hit, &ctx).expect("process");
         assert!(result.is_none());
@@ -14,6 +14,12 @@ pub struct CheckpointState {
     pub next_offset: u64,
     pub evidence_len: u64,
     pub created_at: String,
+    /// `[start, end)` ranges the run was limited to; empty for a full scan.
+    #[serde(default)]
+    pub scan_ranges: Vec<(u64, u64)>,
+    /// Portions of the scanned ranges finished before the checkpoint.
+    #[serde(default)]
+    pub completed_ranges: Vec<(u64, u64)>,
 }
 
 impl CheckpointState {
@@ -25,13 +31,15 @@ impl CheckpointState {
         evidence_len: u64,
     ) -> Self {
         Self {
-            version: 1,
+            version: 2,
             run_id: run_id.to_string(),
             chunk_size,
             overlap,
             next_offset,
             evidence_len,
             created_at: Utc::now().to_rfc3339(),
+            scan_ranges: Vec::new(),
+            completed_ranges: Vec::new(),
         }
     }
 }
@@ -64,11 +72,29 @@ mod tests {
     fn roundtrip_checkpoint() {
         let dir = tempdir().expect("tempdir");
         let path = dir.path().join("checkpoint.json");
-        let state = CheckpointState::new("run", 1024, 64, 2048, 4096);
+        let mut state = CheckpointState::new("run", 1024, 64, 2048, 4096);
+        state.scan_ranges = vec![(512, 3072)];
+        state.completed_ranges = vec![(512, 2048)];
         save_checkpoint(&path, &state).expect("save");
         let loaded = load_checkpoint(&path).expect("load");
         assert_eq!(loaded.run_id, "run");
         assert_eq!(loaded.next_offset, 2048);
         assert_eq!(loaded.evidence_len, 4096);
+        assert_eq!(loaded.scan_ranges, vec![(512, 3072)]);
+        assert_eq!(loaded.completed_ranges, vec![(512, 2048)]);
+    }
+
+    #[test]
+    fn loads_checkpoint_without_ranges() {
+        let dir = tempdir().expect("tempdir");
+        let path = dir.path().join("checkpoint.json");
+        fs::write(
+            &path,
+            r#"{"version":1,"run_id":"run","chunk_size":1024,"overlap":64,"next_offset":2048,"evidence_len":4096,"created_at":"2024-01-01T00:00:00Z"}"#,
+        )
+        .expect("write");
+        let loaded = load_checkpoint(&path).expect("load");
+        assert!(loaded.scan_ranges.is_empty());
+        assert!(loaded.completed_ranges.is_empty());
     }
 }
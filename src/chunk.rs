@@ -34,6 +34,37 @@ pub fn build_chunks(total_len: u64, chunk_size: u64, overlap: u64) -> Vec<ScanCh
     chunks
 }
 
+/// Build chunks covering only the given `[start, end)` byte ranges. Chunk
+/// ids stay sequential across ranges and overlap never reaches past the end
+/// of a range.
+pub fn build_chunks_for_ranges(
+    ranges: &[(u64, u64)],
+    chunk_size: u64,
+    overlap: u64,
+) -> Vec<ScanChunk> {
+    if chunk_size == 0 {
+        return Vec::new();
+    }
+
+    let mut chunks = Vec::new();
+    let mut id = 0u64;
+    for &(range_start, range_end) in ranges {
+        let mut start = range_start;
+        while start < range_end {
+            let remaining = range_end - start;
+            chunks.push(ScanChunk {
+                id,
+                start,
+                length: remaining.min(chunk_size.saturating_add(overlap)),
+                valid_length: remaining.min(chunk_size),
+            });
+            start = start.saturating_add(chunk_size);
+            id += 1;
+        }
+    }
+    chunks
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -52,4 +83,17 @@ mod tests {
         assert_eq!(chunks[2].length, 20);
         assert_eq!(chunks[2].valid_length, 20);
     }
+
+    #[test]
+    fn builds_chunks_within_ranges() {
+        let chunks = build_chunks_for_ranges(&[(10, 60), (100, 130)], 40, 10);
+        let layout: Vec<(u64, u64, u64, u64)> = chunks
+            .iter()
+            .map(|c| (c.id, c.start, c.length, c.valid_length))
+            .collect();
+        assert_eq!(
+            layout,
+            vec![(0, 10, 50, 40), (1, 50, 10, 10), (2, 100, 30, 30)]
+        );
+    }
 }
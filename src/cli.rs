@@ -1,6 +1,6 @@
 use std::path::PathBuf;
 
-use clap::{Parser, ValueEnum};
+use clap::{Args, Parser, Subcommand, ValueEnum};
 
 #[derive(ValueEnum, Debug, Clone, Copy)]
 pub enum MetadataBackend {
@@ -16,11 +16,14 @@ pub enum LogFormat {
 }
 
 #[derive(Parser, Debug)]
-#[command(author, version, about)]
+#[command(author, version, about, subcommand_negates_reqs = true)]
 pub struct CliOptions {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+
     /// Input image (raw, E01, or device)
-    #[arg(short, long)]
-    pub input: PathBuf,
+    #[arg(short, long, required = true)]
+    pub input: Option<PathBuf>,
 
     /// Output directory for carved files and metadata
     #[arg(short, long, default_value = "./output")]
@@ -90,6 +93,38 @@ pub struct CliOptions {
     #[arg(long, conflicts_with = "scan_phones")]
     pub no_scan_phones: bool,
 
+    /// Enable IP address extraction from string spans
+    #[arg(long, conflicts_with = "no_scan_ips")]
+    pub scan_ips: bool,
+
+    /// Disable IP address extraction from string spans
+    #[arg(long, conflicts_with = "scan_ips")]
+    pub no_scan_ips: bool,
+
+    /// Enable payment card number extraction from string spans
+    #[arg(long, conflicts_with = "no_scan_credit_cards")]
+    pub scan_credit_cards: bool,
+
+    /// Disable payment card number extraction from string spans
+    #[arg(long, conflicts_with = "scan_credit_cards")]
+    pub no_scan_credit_cards: bool,
+
+    /// Enable cryptocurrency wallet address extraction from string spans
+    #[arg(long, conflicts_with = "no_scan_crypto_wallets")]
+    pub scan_crypto_wallets: bool,
+
+    /// Disable cryptocurrency wallet address extraction from string spans
+    #[arg(long, conflicts_with = "scan_crypto_wallets")]
+    pub no_scan_crypto_wallets: bool,
+
+    /// Enable leaked credential (AWS key, JWT, PEM private key) extraction from string spans
+    #[arg(long, conflicts_with = "no_scan_secrets")]
+    pub scan_secrets: bool,
+
+    /// Disable leaked credential (AWS key, JWT, PEM private key) extraction from string spans
+    #[arg(long, conflicts_with = "scan_secrets")]
+    pub no_scan_secrets: bool,
+
     /// Override minimum string length when scanning
     #[arg(long)]
     pub string_min_len: Option<usize>,
@@ -110,6 +145,14 @@ pub struct CliOptions {
     #[arg(long)]
     pub scan_sqlite_pages: bool,
 
+    /// Zero-fill unreadable sectors and keep going (writes a ddrescue mapfile)
+    #[arg(long)]
+    pub tolerant_reads: bool,
+
+    /// Scan constant-filled (blank or wiped) blocks instead of skipping them
+    #[arg(long)]
+    pub no_skip_constant_blocks: bool,
+
     /// Stop after scanning this many bytes (approximate limit)
     #[arg(long)]
     pub max_bytes: Option<u64>,
@@ -146,6 +189,10 @@ pub struct CliOptions {
     #[arg(long)]
     pub compute_evidence_sha256: bool,
 
+    /// Verify MD5/SHA1 stored in an E01 image against its media (extra full pass)
+    #[arg(long)]
+    pub verify_ewf_hashes: bool,
+
     /// Disable ZIP carving (skips zip/docx/xlsx/pptx)
     #[arg(long)]
     pub disable_zip: bool,
@@ -162,13 +209,155 @@ pub struct CliOptions {
     #[arg(long)]
     pub dry_run: bool,
 
-    /// Validate carved files after extraction (runs file magic check)
+    /// Validate carved files after extraction (deep per-format structure check)
     #[arg(long)]
     pub validate_carved: bool,
 
     /// Remove files that fail post-carving validation (requires --validate-carved)
     #[arg(long, requires = "validate_carved")]
     pub remove_invalid: bool,
+
+    /// Scan only these partitions (comma-separated indexes from the MBR/GPT table)
+    #[arg(long, value_delimiter = ',')]
+    pub partitions: Option<Vec<u32>>,
+
+    /// Start scanning at this byte offset
+    #[arg(long)]
+    pub start_offset: Option<u64>,
+
+    /// Stop scanning at this byte offset (exclusive)
+    #[arg(long)]
+    pub end_offset: Option<u64>,
+
+    /// Scan only the ranges listed in this file (CSV `start,length` or ddrescue mapfile)
+    #[arg(long)]
+    pub ranges_file: Option<PathBuf>,
+
+    /// Scan only unallocated clusters of FAT, NTFS and ext2/3/4 volumes
+    #[arg(long)]
+    pub unallocated_only: bool,
+
+    /// Record the live file system path, inode and file offset of carved data
+    #[arg(long)]
+    pub attribute_fs_paths: bool,
+
+    /// Drop header hits not aligned to this many bytes within their partition
+    #[arg(long)]
+    pub sector_size: Option<u64>,
+
+    /// YARA rule file or directory of rule files to evaluate over the evidence
+    #[arg(long)]
+    pub yara_rules: Option<PathBuf>,
+
+    /// Also evaluate the YARA rules over each carved file
+    #[arg(long, requires = "yara_rules")]
+    pub yara_carved: bool,
+
+    /// Keyword file to search for (one term per line, tab-separated `r`/`i` flags)
+    #[arg(long)]
+    pub keywords: Option<PathBuf>,
+
+    /// Bytes of context to record on each side of a keyword hit
+    #[arg(long, requires = "keywords")]
+    pub keyword_context: Option<usize>,
+
+    /// Block hash database to match aligned evidence blocks against
+    #[arg(long)]
+    pub hash_db: Option<PathBuf>,
+
+    /// Hash set of known-good files (NSRL RDS SQLite, MD5/SHA-1/SHA-256 list or HashKeeper CSV); repeatable
+    #[arg(long)]
+    pub known_good: Vec<PathBuf>,
+
+    /// Hash set of known-bad files, alerted on when carved; repeatable
+    #[arg(long)]
+    pub known_bad: Vec<PathBuf>,
+
+    /// Delete carved files that match a known-good hash set
+    #[arg(long, requires = "known_good")]
+    pub delete_known_good: bool,
+
+    /// Keep only the first carved copy of identical files (same SHA-256)
+    #[arg(long)]
+    pub skip_duplicates: bool,
+
+    /// Hardlink duplicates to the first copy instead of deleting them
+    #[arg(long, requires = "skip_duplicates")]
+    pub hardlink_duplicates: bool,
+
+    /// Reference list of ssdeep/TLSH digests to compare carved files against
+    #[arg(long)]
+    pub fuzzy_match: Option<PathBuf>,
+
+    /// Minimum ssdeep similarity score (1-100) to report
+    #[arg(long, requires = "fuzzy_match", value_parser = clap::value_parser!(u32).range(1..=100))]
+    pub fuzzy_threshold: Option<u32>,
+
+    /// Maximum TLSH distance to report
+    #[arg(long, requires = "fuzzy_match")]
+    pub tlsh_max_distance: Option<u32>,
+
+    /// Reassemble JPEG/PNG/ZIP files split into two fragments
+    #[arg(long)]
+    pub bifragment: bool,
+
+    /// Fragment boundary granularity in bytes for --bifragment (default 4096)
+    #[arg(long, requires = "bifragment", value_parser = clap::value_parser!(u64).range(1..))]
+    pub bifragment_block_size: Option<u64>,
+
+    /// Largest gap between fragments searched by --bifragment, in bytes
+    #[arg(long, requires = "bifragment")]
+    pub bifragment_max_gap: Option<u64>,
+
+    /// Most gap searches --bifragment runs per carve run (default 1000)
+    #[arg(long, requires = "bifragment")]
+    pub bifragment_max_searches: Option<u64>,
+
+    /// Carve files embedded in carved containers (ZIP, tar, gzip, bzip2, xz, PDF, EML)
+    #[arg(long)]
+    pub recursive_carving: bool,
+
+    /// Deepest container nesting carved by --recursive-carving (default 3)
+    #[arg(
+        long,
+        requires = "recursive_carving",
+        value_parser = clap::value_parser!(u32).range(1..)
+    )]
+    pub recursive_max_depth: Option<u32>,
+
+    /// Largest container or decoded member handled by --recursive-carving, in bytes
+    #[arg(long, requires = "recursive_carving")]
+    pub recursive_max_size: Option<u64>,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum Command {
+    /// Manage block hash databases for --hash-db
+    Hashdb {
+        #[command(subcommand)]
+        action: HashdbCommand,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum HashdbCommand {
+    /// Hash every block of the files under a directory into a new database
+    Build(HashdbBuildArgs),
+}
+
+#[derive(Args, Debug)]
+pub struct HashdbBuildArgs {
+    /// Directory of reference files
+    #[arg(long)]
+    pub source: PathBuf,
+
+    /// Database file to write (replaced if it exists)
+    #[arg(long)]
+    pub db: PathBuf,
+
+    /// Block size, in bytes
+    #[arg(long, default_value_t = crate::hashdb::DEFAULT_BLOCK_SIZE)]
+    pub block_size: usize,
 }
 
 pub fn parse() -> CliOptions {
@@ -182,7 +371,7 @@ pub fn get_types_filter(opts: &CliOptions) -> Option<&Vec<String>> {
 
 #[cfg(test)]
 mod tests {
-    use super::CliOptions;
+    use super::{CliOptions, Command, HashdbCommand};
     use clap::Parser;
     use std::path::PathBuf;
 
@@ -390,6 +579,38 @@ mod tests {
         assert_eq!(types, vec!["jpeg", "png", "gif"]);
     }
 
+    #[test]
+    fn parses_partitions_list() {
+        let opts = CliOptions::try_parse_from([
+            "SwiftBeaver",
+            "--input",
+            "image.dd",
+            "--partitions",
+            "2,5",
+        ])
+        .expect("parse");
+        assert_eq!(opts.partitions, Some(vec![2, 5]));
+    }
+
+    #[test]
+    fn parses_offset_window_and_ranges_file() {
+        let opts = CliOptions::try_parse_from([
+            "SwiftBeaver",
+            "--input",
+            "image.dd",
+            "--start-offset",
+            "4096",
+            "--end-offset",
+            "1048576",
+            "--ranges-file",
+            "unalloc.csv",
+        ])
+        .expect("parse");
+        assert_eq!(opts.start_offset, Some(4096));
+        assert_eq!(opts.end_offset, Some(1_048_576));
+        assert_eq!(opts.ranges_file, Some(PathBuf::from("unalloc.csv")));
+    }
+
     #[test]
     fn types_and_enable_types_conflict() {
         let result = CliOptions::try_parse_from([
@@ -403,4 +624,191 @@ mod tests {
         ]);
         assert!(result.is_err(), "types and enable-types should conflict");
     }
+
+    #[test]
+    fn input_is_required_without_subcommand() {
+        assert!(CliOptions::try_parse_from(["SwiftBeaver"]).is_err());
+    }
+
+    #[test]
+    fn parses_hashdb_build_subcommand() {
+        let opts = CliOptions::try_parse_from([
+            "SwiftBeaver",
+            "hashdb",
+            "build",
+            "--source",
+            "known_files",
+            "--db",
+            "known.hashdb",
+        ])
+        .expect("parse");
+        assert!(opts.input.is_none());
+        let Some(Command::Hashdb {
+            action: HashdbCommand::Build(args),
+        }) = opts.command
+        else {
+            panic!("expected hashdb build");
+        };
+        assert_eq!(args.source, PathBuf::from("known_files"));
+        assert_eq!(args.db, PathBuf::from("known.hashdb"));
+        assert_eq!(args.block_size, 4096);
+    }
+
+    #[test]
+    fn parses_hash_db_flag() {
+        let opts = CliOptions::try_parse_from([
+            "SwiftBeaver",
+            "--input",
+            "image.dd",
+            "--hash-db",
+            "known.hashdb",
+        ])
+        .expect("parse");
+        assert_eq!(opts.hash_db, Some(PathBuf::from("known.hashdb")));
+    }
+
+    #[test]
+    fn parses_known_hash_sets() {
+        let opts = CliOptions::try_parse_from([
+            "SwiftBeaver",
+            "--input",
+            "image.dd",
+            "--known-good",
+            "NSRLFile.db",
+            "--known-good",
+            "baseline.txt",
+            "--known-bad",
+            "watchlist.csv",
+            "--delete-known-good",
+        ])
+        .expect("parse");
+        assert_eq!(
+            opts.known_good,
+            vec![PathBuf::from("NSRLFile.db"), PathBuf::from("baseline.txt")]
+        );
+        assert_eq!(opts.known_bad, vec![PathBuf::from("watchlist.csv")]);
+        assert!(opts.delete_known_good);
+    }
+
+    #[test]
+    fn parses_duplicate_flags() {
+        let opts = CliOptions::try_parse_from([
+            "SwiftBeaver",
+            "--input",
+            "image.dd",
+            "--skip-duplicates",
+            "--hardlink-duplicates",
+        ])
+        .expect("parse");
+        assert!(opts.skip_duplicates);
+        assert!(opts.hardlink_duplicates);
+
+        let result = CliOptions::try_parse_from([
+            "SwiftBeaver",
+            "--input",
+            "image.dd",
+            "--hardlink-duplicates",
+        ]);
+        assert!(
+            result.is_err(),
+            "hardlink-duplicates requires skip-duplicates"
+        );
+    }
+
+    #[test]
+    fn parses_fuzzy_match() {
+        let opts = CliOptions::try_parse_from([
+            "SwiftBeaver",
+            "--input",
+            "image.dd",
+            "--fuzzy-match",
+            "refs.txt",
+            "--fuzzy-threshold",
+            "70",
+            "--tlsh-max-distance",
+            "30",
+        ])
+        .expect("parse");
+        assert_eq!(opts.fuzzy_match, Some(PathBuf::from("refs.txt")));
+        assert_eq!(opts.fuzzy_threshold, Some(70));
+        assert_eq!(opts.tlsh_max_distance, Some(30));
+
+        let result = CliOptions::try_parse_from([
+            "SwiftBeaver",
+            "--input",
+            "image.dd",
+            "--fuzzy-threshold",
+            "70",
+        ]);
+        assert!(result.is_err(), "fuzzy-threshold requires fuzzy-match");
+    }
+
+    #[test]
+    fn parses_bifragment_options() {
+        let opts = CliOptions::try_parse_from([
+            "SwiftBeaver",
+            "--input",
+            "image.dd",
+            "--bifragment",
+            "--bifragment-block-size",
+            "512",
+            "--bifragment-max-gap",
+            "1048576",
+            "--bifragment-max-searches",
+            "50",
+        ])
+        .expect("parse");
+        assert!(opts.bifragment);
+        assert_eq!(opts.bifragment_block_size, Some(512));
+        assert_eq!(opts.bifragment_max_gap, Some(1_048_576));
+        assert_eq!(opts.bifragment_max_searches, Some(50));
+
+        let result = CliOptions::try_parse_from([
+            "SwiftBeaver",
+            "--input",
+            "image.dd",
+            "--bifragment-max-gap",
+            "4096",
+        ]);
+        assert!(result.is_err(), "bifragment-max-gap requires bifragment");
+    }
+
+    #[test]
+    fn parses_recursive_carving_options() {
+        let opts = CliOptions::try_parse_from([
+            "SwiftBeaver",
+            "--input",
+            "image.dd",
+            "--recursive-carving",
+            "--recursive-max-depth",
+            "2",
+            "--recursive-max-size",
+            "1048576",
+        ])
+        .expect("parse");
+        assert!(opts.recursive_carving);
+        assert_eq!(opts.recursive_max_depth, Some(2));
+        assert_eq!(opts.recursive_max_size, Some(1_048_576));
+
+        let result = CliOptions::try_parse_from([
+            "SwiftBeaver",
+            "--input",
+            "image.dd",
+            "--recursive-max-depth",
+            "0",
+            "--recursive-carving",
+        ]);
+        assert!(result.is_err(), "recursive-max-depth must be at least 1");
+    }
+
+    #[test]
+    fn delete_known_good_requires_known_good() {
+        let result = CliOptions::try_parse_from([
+            "SwiftBeaver",
+            "--input",
+            "image.dd",
+            "--delete-known-good",
+        ]);
+        assert!(result.is_err());
+    }
 }
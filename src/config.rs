@@ -1,9 +1,11 @@
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 use anyhow::Result;
 use serde::Deserialize;
 use sha2::{Digest, Sha256};
 
+use crate::strings::validators::ArtefactValidator;
+
 #[derive(Debug, Deserialize, Clone)]
 pub struct FileTypeConfig {
     pub id: String,
@@ -15,14 +17,59 @@ pub struct FileTypeConfig {
     pub validator: String,
     #[serde(default)]
     pub require_eocd: bool,
+    /// Required alignment of header hits in bytes, relative to the
+    /// containing partition. Overrides `sector_size`; `1` accepts any
+    /// offset (embedded content).
+    #[serde(default)]
+    pub alignment: Option<u64>,
 }
 
 #[derive(Debug, Deserialize, Clone)]
 pub struct PatternConfig {
     pub id: String,
+    /// Hex bytes; `?` is a nibble wildcard (`??` matches any byte) and
+    /// whitespace is ignored.
+    pub hex: String,
+    /// Position of `hex` relative to the file start reported as the hit.
+    #[serde(default)]
+    pub offset: u64,
+    /// Further bytes that must also match, relative to the file start.
+    #[serde(default)]
+    pub also: Vec<PatternCondition>,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct PatternCondition {
+    pub offset: u64,
     pub hex: String,
 }
 
+/// A user-defined regex artefact extractor, reported as
+/// `ArtefactKind::Custom(name)`.
+#[derive(Debug, Deserialize, Clone)]
+pub struct CustomArtefactConfig {
+    pub name: String,
+    pub regex: String,
+    /// Checksum the match must pass to be kept.
+    #[serde(default)]
+    pub validator: Option<ArtefactValidator>,
+    /// String encodings the regex is applied to.
+    #[serde(default)]
+    pub encoding: EncodingScope,
+}
+
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum EncodingScope {
+    #[default]
+    All,
+    Ascii,
+    /// ASCII and UTF-8 spans.
+    Utf8,
+    /// UTF-16LE and UTF-16BE spans.
+    Utf16,
+}
+
 #[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq)]
 #[serde(rename_all = "lowercase")]
 pub enum QuicktimeMode {
@@ -47,6 +94,14 @@ pub struct Config {
     pub enable_email_scan: bool,
     #[serde(default = "default_true")]
     pub enable_phone_scan: bool,
+    #[serde(default = "default_true")]
+    pub enable_ip_scan: bool,
+    #[serde(default = "default_true")]
+    pub enable_credit_card_scan: bool,
+    #[serde(default = "default_true")]
+    pub enable_crypto_wallet_scan: bool,
+    #[serde(default = "default_true")]
+    pub enable_secret_scan: bool,
     #[serde(default)]
     pub string_scan_utf16: bool,
     #[serde(default = "default_string_min_len")]
@@ -65,8 +120,119 @@ pub struct Config {
     pub entropy_window_size: usize,
     #[serde(default = "default_entropy_threshold")]
     pub entropy_threshold: f64,
+    /// Extra regex extractors applied to every string span.
+    #[serde(default)]
+    pub custom_artefacts: Vec<CustomArtefactConfig>,
     #[serde(default)]
     pub enable_sqlite_page_recovery: bool,
+    /// Zero-fill unreadable sectors instead of failing the read; bad ranges
+    /// go to `bad_sectors.map` and the `bad_sectors` metadata table.
+    #[serde(default)]
+    pub tolerant_reads: bool,
+    #[serde(default = "default_true")]
+    pub skip_constant_blocks: bool,
+    #[serde(default = "default_constant_block_size")]
+    pub constant_block_size: usize,
+    #[serde(default)]
+    pub validate_carved: bool,
+    #[serde(default)]
+    pub remove_invalid: bool,
+    /// Partition indexes (as reported by the MBR/GPT parser) to scan; `None`
+    /// scans the whole evidence.
+    #[serde(default)]
+    pub partitions: Option<Vec<u32>>,
+    /// First byte offset to scan (inclusive).
+    #[serde(default)]
+    pub start_offset: Option<u64>,
+    /// Byte offset to stop scanning at (exclusive).
+    #[serde(default)]
+    pub end_offset: Option<u64>,
+    /// File of `start,length` ranges (CSV or ddrescue mapfile) to scan.
+    #[serde(default)]
+    pub ranges_file: Option<PathBuf>,
+    /// Scan only space the FAT/NTFS/ext allocation maps mark as free.
+    #[serde(default)]
+    pub unallocated_only: bool,
+    /// Attribute carved data to the live file containing it (walks the MFT
+    /// and directory trees of FAT/NTFS/ext volumes).
+    #[serde(default)]
+    pub attribute_fs_paths: bool,
+    /// Default alignment of header hits for file types without their own
+    /// `alignment`; `None` keeps hits at any offset.
+    #[serde(default)]
+    pub sector_size: Option<u64>,
+    /// YARA rule file, or directory of `.yar`/`.yara` files, evaluated over
+    /// every scan chunk; matches go to the `yara_matches` table.
+    #[serde(default)]
+    pub yara_rules: Option<PathBuf>,
+    /// Also evaluate the YARA rules over each carved file.
+    #[serde(default)]
+    pub yara_scan_carved: bool,
+    /// Keyword file (one term per line) searched in every encoding; hits go
+    /// to the `keyword_hits` table.
+    #[serde(default)]
+    pub keywords: Option<PathBuf>,
+    /// Bytes of context recorded on each side of a keyword hit.
+    #[serde(default = "default_keyword_context_bytes")]
+    pub keyword_context_bytes: usize,
+    /// Block hash database from `hashdb build`; aligned evidence blocks are
+    /// looked up in it and matches go to the `block_hash_matches` table.
+    #[serde(default)]
+    pub hash_db: Option<PathBuf>,
+    /// Hash sets of known-good files (NSRL RDS SQLite, MD5/SHA-1/SHA-256
+    /// lists or HashKeeper CSV); matching carved files get `hash_match:
+    /// known_good` and the set name.
+    #[serde(default)]
+    pub known_good: Vec<PathBuf>,
+    /// Hash sets of known-bad files; matches are logged as alerts and
+    /// counted in the run summary.
+    #[serde(default)]
+    pub known_bad: Vec<PathBuf>,
+    /// Delete carved files that match a known-good set.
+    #[serde(default)]
+    pub delete_known_good: bool,
+    /// Keep only the first carved copy of each SHA-256 on disk.
+    #[serde(default)]
+    pub skip_duplicates: bool,
+    /// With `skip_duplicates`, hardlink duplicates to the first copy
+    /// instead of deleting them.
+    #[serde(default)]
+    pub hardlink_duplicates: bool,
+    /// Reference list of ssdeep and/or TLSH digests; carved files close to
+    /// one go to the `fuzzy_matches` table.
+    #[serde(default)]
+    pub fuzzy_match: Option<PathBuf>,
+    /// Minimum ssdeep similarity (1-100) reported as a fuzzy match.
+    #[serde(default = "default_fuzzy_threshold")]
+    pub fuzzy_threshold: u32,
+    /// Maximum TLSH distance reported as a fuzzy match.
+    #[serde(default = "default_tlsh_max_distance")]
+    pub tlsh_max_distance: u32,
+    /// Reassemble JPEG, PNG and ZIP files split into two fragments: when a
+    /// carve fails deep validation, a block-aligned gap that repairs it is
+    /// searched for and the joined file is written instead.
+    #[serde(default)]
+    pub bifragment: bool,
+    /// Fragment boundary granularity in bytes (cluster size; 512 for sectors).
+    #[serde(default = "default_bifragment_block_size")]
+    pub bifragment_block_size: u64,
+    /// Largest gap between the two fragments searched, in bytes.
+    #[serde(default = "default_bifragment_max_gap")]
+    pub bifragment_max_gap: u64,
+    /// Gap searches allowed per run; broken carves past this are kept as
+    /// carved.
+    #[serde(default = "default_bifragment_max_searches")]
+    pub bifragment_max_searches: u64,
+    /// Decode the members of carved containers (ZIP, tar, gzip, bzip2, xz,
+    /// PDF streams, MIME parts) and carve the files inside them.
+    #[serde(default)]
+    pub recursive_carving: bool,
+    /// Deepest nesting carved; files carved from the evidence are depth 0.
+    #[serde(default = "default_recursive_max_depth")]
+    pub recursive_max_depth: u32,
+    /// Largest container opened, and largest decoded member, in bytes.
+    #[serde(default = "default_recursive_max_size")]
+    pub recursive_max_size: u64,
     pub opencl_platform_index: Option<usize>,
     pub opencl_device_index: Option<usize>,
     #[serde(default)]
@@ -157,6 +323,42 @@ fn default_entropy_threshold() -> f64 {
     7.5
 }
 
+fn default_constant_block_size() -> usize {
+    4096
+}
+
+fn default_keyword_context_bytes() -> usize {
+    64
+}
+
+fn default_fuzzy_threshold() -> u32 {
+    50
+}
+
+fn default_tlsh_max_distance() -> u32 {
+    50
+}
+
+fn default_bifragment_block_size() -> u64 {
+    4096
+}
+
+fn default_bifragment_max_gap() -> u64 {
+    16 * 1024 * 1024
+}
+
+fn default_bifragment_max_searches() -> u64 {
+    1000
+}
+
+fn default_recursive_max_depth() -> u32 {
+    3
+}
+
+fn default_recursive_max_size() -> u64 {
+    256 * 1024 * 1024
+}
+
 fn default_true() -> bool {
     true
 }
@@ -166,7 +368,15 @@ impl Config {
     /// CLI flags override config file values.
     pub fn merge_cli(&mut self, cli: &crate::cli::CliOptions) {
         // String scanning
-        if cli.scan_strings || cli.scan_utf16 || cli.scan_urls || cli.scan_emails || cli.scan_phones
+        if cli.scan_strings
+            || cli.scan_utf16
+            || cli.scan_urls
+            || cli.scan_emails
+            || cli.scan_phones
+            || cli.scan_ips
+            || cli.scan_credit_cards
+            || cli.scan_crypto_wallets
+            || cli.scan_secrets
         {
             self.enable_string_scan = true;
         }
@@ -198,6 +408,38 @@ impl Config {
             self.enable_phone_scan = false;
         }
 
+        // IP scanning
+        if cli.scan_ips {
+            self.enable_ip_scan = true;
+        }
+        if cli.no_scan_ips {
+            self.enable_ip_scan = false;
+        }
+
+        // Payment card scanning
+        if cli.scan_credit_cards {
+            self.enable_credit_card_scan = true;
+        }
+        if cli.no_scan_credit_cards {
+            self.enable_credit_card_scan = false;
+        }
+
+        // Crypto wallet scanning
+        if cli.scan_crypto_wallets {
+            self.enable_crypto_wallet_scan = true;
+        }
+        if cli.no_scan_crypto_wallets {
+            self.enable_crypto_wallet_scan = false;
+        }
+
+        // Secret scanning
+        if cli.scan_secrets {
+            self.enable_secret_scan = true;
+        }
+        if cli.no_scan_secrets {
+            self.enable_secret_scan = false;
+        }
+
         // String length
         if let Some(min_len) = cli.string_min_len {
             self.string_min_len = min_len;
@@ -230,5 +472,126 @@ impl Config {
         if cli.scan_sqlite_pages {
             self.enable_sqlite_page_recovery = true;
         }
+
+        // Bad-sector-tolerant reads
+        if cli.tolerant_reads {
+            self.tolerant_reads = true;
+        }
+
+        // Constant-block skipping
+        if cli.no_skip_constant_blocks {
+            self.skip_constant_blocks = false;
+        }
+
+        // Post-carve validation
+        if cli.validate_carved {
+            self.validate_carved = true;
+        }
+        if cli.remove_invalid {
+            self.remove_invalid = true;
+        }
+
+        // Partition selection
+        if let Some(partitions) = &cli.partitions {
+            self.partitions = Some(partitions.clone());
+        }
+
+        // Offset window and ranges file
+        if let Some(start) = cli.start_offset {
+            self.start_offset = Some(start);
+        }
+        if let Some(end) = cli.end_offset {
+            self.end_offset = Some(end);
+        }
+        if let Some(path) = &cli.ranges_file {
+            self.ranges_file = Some(path.clone());
+        }
+
+        // Unallocated-space scanning
+        if cli.unallocated_only {
+            self.unallocated_only = true;
+        }
+
+        // Live file attribution
+        if cli.attribute_fs_paths {
+            self.attribute_fs_paths = true;
+        }
+
+        // Hit alignment
+        if let Some(sector_size) = cli.sector_size {
+            self.sector_size = Some(sector_size);
+        }
+
+        // YARA rules
+        if let Some(path) = &cli.yara_rules {
+            self.yara_rules = Some(path.clone());
+        }
+        if cli.yara_carved {
+            self.yara_scan_carved = true;
+        }
+
+        // Keyword search
+        if let Some(path) = &cli.keywords {
+            self.keywords = Some(path.clone());
+        }
+        if let Some(bytes) = cli.keyword_context {
+            self.keyword_context_bytes = bytes;
+        }
+
+        // Block hash matching
+        if let Some(path) = &cli.hash_db {
+            self.hash_db = Some(path.clone());
+        }
+
+        // Known-file hash sets
+        self.known_good.extend(cli.known_good.iter().cloned());
+        self.known_bad.extend(cli.known_bad.iter().cloned());
+        if cli.delete_known_good {
+            self.delete_known_good = true;
+        }
+
+        // Duplicate carved output
+        if cli.skip_duplicates {
+            self.skip_duplicates = true;
+        }
+        if cli.hardlink_duplicates {
+            self.hardlink_duplicates = true;
+        }
+
+        // Fuzzy hash matching
+        if let Some(path) = &cli.fuzzy_match {
+            self.fuzzy_match = Some(path.clone());
+        }
+        if let Some(score) = cli.fuzzy_threshold {
+            self.fuzzy_threshold = score;
+        }
+        if let Some(distance) = cli.tlsh_max_distance {
+            self.tlsh_max_distance = distance;
+        }
+
+        // Bifragment carving
+        if cli.bifragment {
+            self.bifragment = true;
+        }
+        if let Some(size) = cli.bifragment_block_size {
+            self.bifragment_block_size = size;
+        }
+        if let Some(gap) = cli.bifragment_max_gap {
+            self.bifragment_max_gap = gap;
+        }
+        if let Some(searches) = cli.bifragment_max_searches {
+            self.bifragment_max_searches = searches;
+        }
+
+        // Recursive carving
+        if cli.recursive_carving {
+            self.recursive_carving = true;
+        }
+        if let Some(depth) = cli.recursive_max_depth {
+            self.recursive_max_depth = depth;
+        }
+        if let Some(size) = cli.recursive_max_size {
+            self.recursive_max_size = size;
+        }
     }
 }
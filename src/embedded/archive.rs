@@ -0,0 +1,195 @@
+//! ZIP and tar members.
+
+use flate2::read::DeflateDecoder;
+
+use super::{Member, read_limited};
+use crate::carve::tar::{header_name, parse_octal, validate_checksum};
+use crate::validate::read_u32_le;
+use crate::validate::zip::{
+    DirectoryEntry, find_eocd, local_data_start, read_central_directory, read_local_header,
+};
+
+const TAR_BLOCK_SIZE: usize = 512;
+
+/// Stored and deflated entries, from the central directory. Encrypted
+/// entries, directories and ZIP64 entries are skipped.
+pub(super) fn zip_members(data: &[u8], max_size: u64, visit: &mut dyn FnMut(Member)) {
+    for entry in zip_entries(data) {
+        if entry.flags & 1 != 0
+            || entry.name.ends_with(b"/")
+            || entry.compressed == u32::MAX
+            || entry.local_offset == u32::MAX as usize
+        {
+            continue;
+        }
+        let Some(start) = local_data_start(data, entry.local_offset, &entry.name) else {
+            continue;
+        };
+        let end = start
+            .saturating_add(entry.compressed as usize)
+            .min(data.len());
+        let raw = &data[start.min(end)..end];
+        let decoded = match entry.method {
+            0 => raw[..raw.len().min(max_size as usize)].to_vec(),
+            8 => read_limited(DeflateDecoder::new(raw), max_size),
+            _ => continue,
+        };
+        if !decoded.is_empty() {
+            visit(Member {
+                name: String::from_utf8_lossy(&entry.name).into_owned(),
+                data: decoded,
+            });
+        }
+    }
+}
+
+/// Entries of the central directory, or of the local headers when the
+/// archive was carved without one.
+fn zip_entries(data: &[u8]) -> Vec<DirectoryEntry> {
+    let directory = find_eocd(data).and_then(|eocd| {
+        let cd_size = read_u32_le(data, eocd + 12)? as usize;
+        let cd_offset = read_u32_le(data, eocd + 16)? as usize;
+        let cd = data.get(cd_offset..cd_offset.checked_add(cd_size)?)?;
+        read_central_directory(cd)
+    });
+    directory.unwrap_or_else(|| local_entries(data))
+}
+
+/// Walk consecutive local headers from the start of the archive, up to the
+/// first one whose data length is only given by a trailing data descriptor.
+fn local_entries(data: &[u8]) -> Vec<DirectoryEntry> {
+    let mut entries = Vec::new();
+    let mut pos = 0usize;
+    while let Some((entry, header_len)) = read_local_header(data, pos) {
+        if entry.flags & 0x08 != 0 && entry.compressed == 0 {
+            break;
+        }
+        pos += header_len + entry.compressed as usize;
+        entries.push(entry);
+    }
+    entries
+}
+
+/// Regular files of a ustar or v7 tar archive. GNU long names (`L`
+/// entries) are applied to the entry that follows them.
+pub(super) fn tar_members(data: &[u8], max_size: u64, visit: &mut dyn FnMut(Member)) {
+    let mut pos = 0usize;
+    let mut long_name: Option<String> = None;
+    while let Some(header) = data.get(pos..pos + TAR_BLOCK_SIZE) {
+        if header.iter().all(|&b| b == 0) || !validate_checksum(header).unwrap_or(false) {
+            break;
+        }
+        let Ok(size) = parse_octal(&header[124..136]) else {
+            break;
+        };
+        let start = pos + TAR_BLOCK_SIZE;
+        let end = start.saturating_add(size as usize).min(data.len());
+        let body = &data[start..end];
+        match header[156] {
+            b'L' => {
+                let name = body.split(|&b| b == 0).next().unwrap_or_default();
+                long_name = Some(String::from_utf8_lossy(name).into_owned());
+            }
+            b'0' | b'\0' | b'7' => {
+                let name = long_name.take().unwrap_or_else(|| header_name(header));
+                if !body.is_empty() {
+                    visit(Member {
+                        name,
+                        data: body[..body.len().min(max_size as usize)].to_vec(),
+                    });
+                }
+            }
+            _ => long_name = None,
+        }
+        let blocks = (size as usize).div_ceil(TAR_BLOCK_SIZE);
+        pos = start.saturating_add(blocks.saturating_mul(TAR_BLOCK_SIZE));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use super::{tar_members, zip_members};
+    use crate::embedded::Member;
+
+    fn collect(visit: impl Fn(&mut dyn FnMut(Member))) -> Vec<Member> {
+        let mut out = Vec::new();
+        visit(&mut |member| out.push(member));
+        out
+    }
+
+    fn local_header(name: &[u8], method: u16, payload: &[u8], raw_len: usize) -> Vec<u8> {
+        let mut out = b"PK\x03\x04".to_vec();
+        out.extend_from_slice(&[20, 0, 0, 0]);
+        out.extend_from_slice(&method.to_le_bytes());
+        out.extend_from_slice(&[0; 4]);
+        out.extend_from_slice(&crc32fast::hash(payload).to_le_bytes());
+        out.extend_from_slice(&(raw_len as u32).to_le_bytes());
+        out.extend_from_slice(&(payload.len() as u32).to_le_bytes());
+        out.extend_from_slice(&(name.len() as u16).to_le_bytes());
+        out.extend_from_slice(&0u16.to_le_bytes());
+        out.extend_from_slice(name);
+        out
+    }
+
+    #[test]
+    fn reads_stored_and_deflated_zip_entries() {
+        let text = b"deflated member deflated member deflated member".to_vec();
+        let mut encoder =
+            flate2::write::DeflateEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all(&text).expect("write");
+        let deflated = encoder.finish().expect("finish");
+
+        // No central directory: the local headers are walked instead.
+        let mut data = local_header(b"a.bin", 0, b"stored", 6);
+        data.extend_from_slice(b"stored");
+        data.extend(local_header(b"dir/b.txt", 8, &text, deflated.len()));
+        data.extend_from_slice(&deflated);
+
+        let found = collect(|visit| zip_members(&data, 1024, visit));
+        assert_eq!(found.len(), 2);
+        assert_eq!(found[0].name, "a.bin");
+        assert_eq!(found[0].data, b"stored");
+        assert_eq!(found[1].name, "dir/b.txt");
+        assert_eq!(found[1].data, text);
+    }
+
+    fn tar_header(name: &str, typeflag: u8, size: usize) -> Vec<u8> {
+        let mut header = vec![0u8; 512];
+        header[..name.len()].copy_from_slice(name.as_bytes());
+        header[124..135].copy_from_slice(format!("{size:011o}").as_bytes());
+        header[156] = typeflag;
+        header[257..263].copy_from_slice(b"ustar\0");
+        header[148..156].copy_from_slice(b"        ");
+        let sum: u32 = header.iter().map(|&b| b as u32).sum();
+        header[148..155].copy_from_slice(format!("{sum:06o}\0").as_bytes());
+        header
+    }
+
+    fn padded(body: &[u8]) -> Vec<u8> {
+        let mut out = body.to_vec();
+        out.resize(body.len().div_ceil(512) * 512, 0);
+        out
+    }
+
+    #[test]
+    fn reads_tar_entries_with_long_names() {
+        let long = format!("{}/photo.jpg", "d".repeat(120));
+        let mut data = tar_header("short.txt", b'0', 5);
+        data.extend(padded(b"hello"));
+        data.extend(tar_header("dir/", b'5', 0));
+        data.extend(tar_header("././@LongLink", b'L', long.len() + 1));
+        data.extend(padded(format!("{long}\0").as_bytes()));
+        data.extend(tar_header("truncated-name", b'0', 3));
+        data.extend(padded(b"abc"));
+        data.extend(vec![0u8; 1024]);
+
+        let found = collect(|visit| tar_members(&data, 1024, visit));
+        assert_eq!(found.len(), 2);
+        assert_eq!(found[0].name, "short.txt");
+        assert_eq!(found[0].data, b"hello");
+        assert_eq!(found[1].name, long);
+        assert_eq!(found[1].data, b"abc");
+    }
+}
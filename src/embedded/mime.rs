@@ -0,0 +1,161 @@
+//! Base64-encoded MIME parts of an e-mail message.
+
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+
+use super::Member;
+
+/// Decode every part sent with `Content-Transfer-Encoding: base64`. Part
+/// headers start at the top of the message or after a boundary line of an
+/// enclosing multipart, and a part body runs to the next boundary line.
+pub(super) fn base64_parts(data: &[u8], max_size: u64, visit: &mut dyn FnMut(Member)) {
+    let mut lines = data
+        .split(|&b| b == b'\n')
+        .map(|line| line.trim_ascii_end());
+    let mut boundaries: Vec<Vec<u8>> = Vec::new();
+    let is_boundary = |boundaries: &[Vec<u8>], line: &[u8]| {
+        line.strip_prefix(b"--").is_some_and(|rest| {
+            let rest = rest.strip_suffix(b"--").unwrap_or(rest);
+            boundaries.iter().any(|boundary| boundary == rest)
+        })
+    };
+    let mut index = 0usize;
+    let mut in_headers = true;
+    let mut headers: Vec<Vec<u8>> = Vec::new();
+    while let Some(line) = lines.next() {
+        if is_boundary(&boundaries, line) {
+            in_headers = true;
+            headers.clear();
+            continue;
+        }
+        if !in_headers {
+            continue;
+        }
+        if !line.is_empty() {
+            // Folded header lines continue the previous header
+            match headers.last_mut() {
+                Some(last) if line[0] == b' ' || line[0] == b'\t' => last.extend_from_slice(line),
+                _ => headers.push(line.to_vec()),
+            }
+            continue;
+        }
+
+        in_headers = false;
+        boundaries.extend(headers.iter().filter_map(|header| {
+            header_value(header, b"content-type")
+                .and_then(|value| parameter(value, b"boundary"))
+                .map(String::into_bytes)
+        }));
+        let base64 = headers.iter().any(|header| {
+            header_value(header, b"content-transfer-encoding")
+                .is_some_and(|value| value.trim_ascii().eq_ignore_ascii_case(b"base64"))
+        });
+        if !base64 {
+            continue;
+        }
+        index += 1;
+        let mut encoded = Vec::new();
+        for body in lines.by_ref() {
+            if is_boundary(&boundaries, body) {
+                in_headers = true;
+                break;
+            }
+            encoded.extend(body.iter().filter(|b| !b.is_ascii_whitespace()));
+        }
+        let name = headers
+            .iter()
+            .find_map(|header| {
+                parameter(header, b"filename").or_else(|| parameter(header, b"name"))
+            })
+            .unwrap_or_else(|| format!("part {index}"));
+        headers.clear();
+
+        // A part cut off mid-quantum still decodes up to the cut
+        let usable = encoded.len() / 4 * 4;
+        let mut decoded = STANDARD.decode(&encoded[..usable]).unwrap_or_default();
+        decoded.truncate(max_size as usize);
+        if !decoded.is_empty() {
+            visit(Member {
+                name,
+                data: decoded,
+            });
+        }
+    }
+}
+
+/// Value of `header` when its name is `name` (case-insensitive).
+fn header_value<'a>(header: &'a [u8], name: &[u8]) -> Option<&'a [u8]> {
+    let colon = header.iter().position(|&b| b == b':')?;
+    header[..colon]
+        .trim_ascii()
+        .eq_ignore_ascii_case(name)
+        .then(|| &header[colon + 1..])
+}
+
+/// A `name=value` or `name="value"` parameter anywhere in a header.
+fn parameter(header: &[u8], name: &[u8]) -> Option<String> {
+    let lower = header.to_ascii_lowercase();
+    let mut from = 0usize;
+    while let Some(found) = memchr::memmem::find(&lower[from..], name) {
+        let at = from + found;
+        from = at + name.len();
+        let boundary = at == 0 || matches!(lower[at - 1], b';' | b' ' | b'\t');
+        let rest = header[from..].trim_ascii_start();
+        let Some(value) = rest.strip_prefix(b"=") else {
+            continue;
+        };
+        if !boundary {
+            continue;
+        }
+        let value = value.trim_ascii_start();
+        let value = match value.strip_prefix(b"\"") {
+            Some(quoted) => quoted.split(|&b| b == b'"').next().unwrap_or_default(),
+            None => value
+                .split(|&b| b == b';')
+                .next()
+                .unwrap_or_default()
+                .trim_ascii(),
+        };
+        if !value.is_empty() {
+            return Some(String::from_utf8_lossy(value).into_owned());
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::base64_parts;
+    use crate::embedded::Member;
+
+    #[test]
+    fn decodes_base64_attachments() {
+        let message = b"From: a@example.com\r\n\
+Content-Type: multipart/mixed; boundary=\"XYZ\"\r\n\
+\r\n\
+--XYZ\r\n\
+Content-Type: text/plain\r\n\
+\r\n\
+Hello\r\n\
+--XYZ\r\n\
+Content-Type: image/png;\r\n\
+\tname=\"pic.png\"\r\n\
+Content-Transfer-Encoding: BASE64\r\n\
+\r\n\
+aGVsbG8g\r\n\
+YXR0YWNo\r\n\
+bWVudA==\r\n\
+--XYZ\r\n\
+Content-Transfer-Encoding: base64\r\n\
+\r\n\
+c2Vjb25k\r\n\
+--XYZ--\r\n";
+        let mut found: Vec<Member> = Vec::new();
+        base64_parts(message, 1024, &mut |member| found.push(member));
+        assert_eq!(found.len(), 2);
+        assert_eq!(found[0].name, "pic.png");
+        assert_eq!(found[0].data, b"hello attachment");
+        assert_eq!(found[1].name, "part 2");
+        assert_eq!(found[1].data, b"second");
+    }
+}
@@ -0,0 +1,206 @@
+//! Members of carved containers, for recursive carving.
+//!
+//! Content inside a compressed or encoded container (images in a DOCX, a
+//! JPEG in a PDF, files in a `.tar.gz`, e-mail attachments) never appears
+//! as plain bytes in the evidence, so the signature scan cannot see it.
+//! With `--recursive-carving` every carved container is opened again and
+//! its members are decoded here: ZIP entries (stored or deflate), tar
+//! entries, gzip/bzip2/xz streams, PDF FlateDecode streams and base64 MIME
+//! parts. The carve workers scan each member like a small evidence image
+//! and carve what they find.
+
+mod archive;
+mod mime;
+mod pdf;
+
+use std::io::{Read, Write};
+use std::path::Path;
+
+use bzip2::read::MultiBzDecoder;
+use flate2::read::{GzDecoder, MultiGzDecoder};
+
+/// One decoded member of a container.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Member {
+    /// Where the member sits in its container: the archive entry name, the
+    /// gzip original file name, `obj <n>` for a PDF stream or the MIME part
+    /// file name. Recorded as `parent_id` on files carved from it.
+    pub name: String,
+    pub data: Vec<u8>,
+}
+
+/// Whether `file_type` names a container whose members can be decoded.
+pub fn is_container(file_type: &str) -> bool {
+    matches!(
+        file_type,
+        "zip"
+            | "docx"
+            | "xlsx"
+            | "pptx"
+            | "odt"
+            | "ods"
+            | "odp"
+            | "epub"
+            | "tar"
+            | "gzip"
+            | "bzip2"
+            | "xz"
+            | "pdf"
+            | "eml"
+    )
+}
+
+/// Decode the members of the carved container at `path` one at a time.
+/// Containers larger than `max_size` are skipped, and each member is cut
+/// off after `max_size` decoded bytes.
+pub fn visit_file(
+    path: &Path,
+    file_type: &str,
+    max_size: u64,
+    visit: &mut dyn FnMut(Member),
+) -> std::io::Result<()> {
+    if !is_container(file_type) || std::fs::metadata(path)?.len() > max_size {
+        return Ok(());
+    }
+    let data = std::fs::read(path)?;
+    visit_members(&data, file_type, max_size, visit);
+    Ok(())
+}
+
+/// Decode the members of a container held in memory.
+pub fn visit_members(data: &[u8], file_type: &str, max_size: u64, visit: &mut dyn FnMut(Member)) {
+    match file_type {
+        "zip" | "docx" | "xlsx" | "pptx" | "odt" | "ods" | "odp" | "epub" => {
+            archive::zip_members(data, max_size, visit)
+        }
+        "tar" => archive::tar_members(data, max_size, visit),
+        "gzip" => {
+            // The header of the first member; the multi-member decoder
+            // moves on to whatever follows the last member
+            let name = GzDecoder::new(data)
+                .header()
+                .and_then(|header| header.filename())
+                .map(|name| String::from_utf8_lossy(name).into_owned());
+            let decoded = read_limited(MultiGzDecoder::new(data), max_size);
+            visit_decoded(name, decoded, visit);
+        }
+        "bzip2" => visit_decoded(
+            None,
+            read_limited(MultiBzDecoder::new(data), max_size),
+            visit,
+        ),
+        "xz" => {
+            let mut out = LimitedWriter::new(max_size);
+            // The limit and corrupt input both end decoding; what was
+            // decoded up to then is kept
+            let _ = lzma_rs::xz_decompress(&mut std::io::BufReader::new(data), &mut out);
+            visit_decoded(None, out.data, visit);
+        }
+        "pdf" => pdf::stream_members(data, max_size, visit),
+        "eml" => mime::base64_parts(data, max_size, visit),
+        _ => {}
+    }
+}
+
+/// Name given to the content of a single-stream compressor without an
+/// original file name.
+const DECOMPRESSED: &str = "decompressed";
+
+fn visit_decoded(name: Option<String>, data: Vec<u8>, visit: &mut dyn FnMut(Member)) {
+    if !data.is_empty() {
+        visit(Member {
+            name: name.unwrap_or_else(|| DECOMPRESSED.to_string()),
+            data,
+        });
+    }
+}
+
+/// Read up to `max_size` bytes. Corrupt or truncated input still yields
+/// what was decoded before the error.
+pub(crate) fn read_limited(reader: impl Read, max_size: u64) -> Vec<u8> {
+    let mut out = Vec::new();
+    let _ = reader.take(max_size).read_to_end(&mut out);
+    out
+}
+
+/// Collects output up to a size limit and fails writes past it.
+struct LimitedWriter {
+    data: Vec<u8>,
+    limit: usize,
+}
+
+impl LimitedWriter {
+    fn new(limit: u64) -> Self {
+        Self {
+            data: Vec::new(),
+            limit: usize::try_from(limit).unwrap_or(usize::MAX),
+        }
+    }
+}
+
+impl Write for LimitedWriter {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let room = self.limit - self.data.len();
+        if room == 0 && !buf.is_empty() {
+            return Err(std::io::Error::other("member size limit reached"));
+        }
+        let n = buf.len().min(room);
+        self.data.extend_from_slice(&buf[..n]);
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use super::{DECOMPRESSED, Member, visit_members};
+
+    fn members(data: &[u8], file_type: &str, max_size: u64) -> Vec<Member> {
+        let mut out = Vec::new();
+        visit_members(data, file_type, max_size, &mut |member| out.push(member));
+        out
+    }
+
+    #[test]
+    fn decodes_gzip_with_original_name() {
+        let mut encoder = flate2::GzBuilder::new()
+            .filename("notes.txt")
+            .write(Vec::new(), flate2::Compression::default());
+        encoder.write_all(b"hello gzip").expect("write");
+        let mut data = encoder.finish().expect("finish");
+        // Carved without a footer, the stream runs on into unrelated bytes
+        data.extend_from_slice(&[0u8; 64]);
+        let found = members(&data, "gzip", 1024);
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].name, "notes.txt");
+        assert_eq!(found[0].data, b"hello gzip");
+    }
+
+    #[test]
+    fn decodes_bzip2_and_xz_up_to_the_limit() {
+        let payload = vec![b'a'; 4096];
+        let mut bz = bzip2::write::BzEncoder::new(Vec::new(), bzip2::Compression::default());
+        bz.write_all(&payload).expect("write");
+        let bz = bz.finish().expect("finish");
+        let found = members(&bz, "bzip2", 1000);
+        assert_eq!(found[0].name, DECOMPRESSED);
+        assert_eq!(found[0].data, &payload[..1000]);
+
+        let mut xz = Vec::new();
+        lzma_rs::xz_compress(&mut payload.as_slice(), &mut xz).expect("compress");
+        let found = members(&xz, "xz", 1 << 20);
+        assert_eq!(found[0].data, payload);
+        let found = members(&xz, "xz", 100);
+        assert_eq!(found[0].data.len(), 100);
+    }
+
+    #[test]
+    fn ignores_other_file_types() {
+        assert!(members(b"\xFF\xD8\xFF\xE0", "jpeg", 1024).is_empty());
+    }
+}
@@ -0,0 +1,105 @@
+//! PDF FlateDecode streams.
+
+use flate2::read::ZlibDecoder;
+use memchr::memmem;
+
+use super::{Member, read_limited};
+
+/// Inflate every stream whose dictionary names the FlateDecode filter.
+/// Streams are delimited by the `stream`/`endstream` keywords rather than
+/// `/Length`, which is often an indirect reference.
+pub(super) fn stream_members(data: &[u8], max_size: u64, visit: &mut dyn FnMut(Member)) {
+    let mut pos = 0usize;
+    let mut index = 0usize;
+    while let Some(found) = memmem::find(&data[pos..], b"stream") {
+        let keyword = pos + found;
+        pos = keyword + b"stream".len();
+        // Skip the `stream` inside `endstream`
+        if data[..keyword].ends_with(b"end") {
+            continue;
+        }
+        let start = match data.get(pos..pos + 2) {
+            Some([b'\r', b'\n', ..]) => pos + 2,
+            Some([b'\n', ..]) => pos + 1,
+            _ => continue,
+        };
+        let Some(len) = memmem::find(&data[start..], b"endstream") else {
+            break;
+        };
+        let end = start + len;
+        pos = end + b"endstream".len();
+        index += 1;
+
+        let (object, dictionary) = stream_object(&data[..keyword]);
+        if memmem::find(dictionary, b"/FlateDecode").is_none() {
+            continue;
+        }
+        let decoded = read_limited(ZlibDecoder::new(&data[start..end]), max_size);
+        if !decoded.is_empty() {
+            visit(Member {
+                name: object.map_or_else(|| format!("stream {index}"), |n| format!("obj {n}")),
+                data: decoded,
+            });
+        }
+    }
+}
+
+/// Object number and dictionary of the stream whose `stream` keyword ends
+/// `before`: the text after the last `obj` keyword.
+fn stream_object(before: &[u8]) -> (Option<u64>, &[u8]) {
+    let Some(obj) = memmem::rfind(before, b"obj") else {
+        return (None, before);
+    };
+    let dictionary = &before[obj + 3..];
+    // `<number> <generation> obj`
+    let mut fields = before[..obj]
+        .rsplit(|b| b.is_ascii_whitespace())
+        .filter(|field| !field.is_empty());
+    let _generation = fields.next();
+    let number = fields
+        .next()
+        .and_then(|field| std::str::from_utf8(field).ok())
+        .and_then(|field| field.parse().ok());
+    (number, dictionary)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use super::stream_members;
+    use crate::embedded::Member;
+
+    fn zlib(data: &[u8]) -> Vec<u8> {
+        let mut encoder =
+            flate2::write::ZlibEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all(data).expect("write");
+        encoder.finish().expect("finish")
+    }
+
+    #[test]
+    fn inflates_flate_streams_only() {
+        let mut pdf = b"%PDF-1.4\n".to_vec();
+        let image = zlib(b"\xFF\xD8\xFF\xE0 inflated image bytes \xFF\xD9");
+        pdf.extend_from_slice(
+            format!(
+                "4 0 obj\n<< /Length {} /Filter /FlateDecode >>\nstream\r\n",
+                image.len()
+            )
+            .as_bytes(),
+        );
+        pdf.extend_from_slice(&image);
+        pdf.extend_from_slice(b"\r\nendstream\nendobj\n");
+        pdf.extend_from_slice(b"5 0 obj\n<< /Length 4 >>\nstream\nBT/Fendstream\nendobj\n");
+        pdf.extend_from_slice(b"%%EOF\n");
+
+        let mut found: Vec<Member> = Vec::new();
+        stream_members(&pdf, 1024, &mut |member| found.push(member));
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].name, "obj 4");
+        assert_eq!(
+            found[0].data,
+            b"\xFF\xD8\xFF\xE0 inflated image bytes \xFF\xD9"
+        );
+    }
+}
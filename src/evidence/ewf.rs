@@ -0,0 +1,846 @@
+//! Native reader for EWF v1 (EnCase `.E01`) images.
+//!
+//! Segment files are walked section by section; `table` sections (with
+//! `table2` as fallback) give the location of every chunk, and chunks are
+//! decompressed on demand into a sharded LRU cache so that many carve
+//! workers can call `read_at` concurrently.
+
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use hashlink::LruCache;
+use sha1::{Digest, Sha1};
+
+use super::{
+    EvidenceError, EvidenceSource, RawFileSource, read_exact_at, read_u32_le, read_u64_le,
+};
+
+const EVF_SIGNATURE: &[u8; 8] = b"EVF\x09\x0D\x0A\xFF\x00";
+const EVF2_SIGNATURE: &[u8; 8] = b"EVF2\x0D\x0A\x81\x00";
+const FILE_HEADER_LEN: u64 = 13;
+const SECTION_DESCRIPTOR_LEN: u64 = 76;
+const TABLE_HEADER_LEN: usize = 24;
+const VOLUME_MIN_LEN: usize = 24;
+/// Upper bound on a single chunk's stored size, guarding against corrupt tables.
+const MAX_CHUNK_SIZE: u64 = 64 * 1024 * 1024;
+
+/// Number of independently locked cache shards.
+const CACHE_SHARDS: usize = 16;
+/// Decompressed chunks kept per shard (EnCase defaults to 32 KiB chunks).
+const CACHE_CHUNKS_PER_SHARD: usize = 32;
+
+type ChunkCache = LruCache<u64, Arc<Vec<u8>>>;
+
+#[derive(Debug, Clone, Copy)]
+struct ChunkLocation {
+    segment: usize,
+    offset: u64,
+    stored_size: u64,
+    compressed: bool,
+}
+
+/// Hash values stored in the image compared with a full read of the media.
+#[derive(Debug, Clone)]
+pub struct EwfHashVerification {
+    pub stored_md5: Option<String>,
+    pub computed_md5: String,
+    pub stored_sha1: Option<String>,
+    pub computed_sha1: String,
+}
+
+impl EwfHashVerification {
+    /// True when no stored hash disagrees with the computed value.
+    pub fn matches(&self) -> bool {
+        self.stored_md5
+            .as_ref()
+            .is_none_or(|stored| *stored == self.computed_md5)
+            && self
+                .stored_sha1
+                .as_ref()
+                .is_none_or(|stored| *stored == self.computed_sha1)
+    }
+
+    /// True when the image carries at least one hash to compare against.
+    pub fn has_stored_hash(&self) -> bool {
+        self.stored_md5.is_some() || self.stored_sha1.is_some()
+    }
+}
+
+pub struct EwfSource {
+    segments: Vec<RawFileSource>,
+    chunks: Vec<ChunkLocation>,
+    chunk_size: u64,
+    len: u64,
+    stored_md5: Option<[u8; 16]>,
+    stored_sha1: Option<[u8; 20]>,
+    cache: Vec<Mutex<ChunkCache>>,
+}
+
+#[derive(Default)]
+struct Geometry {
+    chunk_count: u32,
+    sectors_per_chunk: u32,
+    bytes_per_sector: u32,
+    sector_count: u64,
+}
+
+#[derive(Default)]
+struct ParseState {
+    geometry: Option<Geometry>,
+    chunks: Vec<ChunkLocation>,
+    /// Error of a `table` section that failed its checksum, until the
+    /// `table2` copy that must follow it replaces it.
+    corrupt_table: Option<String>,
+    /// End of the most recent `sectors` section in the current segment.
+    sectors_end: Option<u64>,
+    stored_md5: Option<[u8; 16]>,
+    stored_sha1: Option<[u8; 20]>,
+}
+
+impl EwfSource {
+    /// Open an EWF image from its first segment (`.E01`); later segments
+    /// (`.E02`..`.E99`, `.EAA`..) are discovered next to it.
+    pub fn open(path: &Path) -> Result<Self, EvidenceError> {
+        let paths = segment_paths(path);
+        let mut segments = Vec::with_capacity(paths.len());
+        let mut state = ParseState::default();
+        let mut done = false;
+
+        for (idx, segment_path) in paths.iter().enumerate() {
+            let segment = RawFileSource::open(segment_path)?;
+            let finished = parse_segment(&segment, idx, &mut state).map_err(|err| match err {
+                EvidenceError::Corrupt(msg) => {
+                    EvidenceError::Corrupt(format!("{}: {msg}", segment_path.display()))
+                }
+                other => other,
+            })?;
+            segments.push(segment);
+            if finished {
+                done = true;
+                break;
+            }
+        }
+        if !done {
+            return Err(EvidenceError::Segment(format!(
+                "EWF image {} ends without a done section (missing segment?)",
+                path.display()
+            )));
+        }
+
+        let geometry = state
+            .geometry
+            .ok_or_else(|| EvidenceError::Corrupt("EWF volume section missing".to_string()))?;
+        let chunk_size = geometry.sectors_per_chunk as u64 * geometry.bytes_per_sector as u64;
+        if chunk_size == 0 || chunk_size > MAX_CHUNK_SIZE {
+            return Err(EvidenceError::Corrupt(format!(
+                "EWF chunk size {chunk_size} invalid"
+            )));
+        }
+        let len = geometry.sector_count * geometry.bytes_per_sector as u64;
+        let needed = len.div_ceil(chunk_size);
+        if (state.chunks.len() as u64) < needed {
+            return Err(EvidenceError::Corrupt(format!(
+                "EWF tables list {} chunks but media needs {needed}",
+                state.chunks.len()
+            )));
+        }
+        if geometry.chunk_count != 0 && (geometry.chunk_count as u64) < needed {
+            return Err(EvidenceError::Corrupt(format!(
+                "EWF volume lists {} chunks but media needs {needed}",
+                geometry.chunk_count
+            )));
+        }
+
+        let cache = (0..CACHE_SHARDS)
+            .map(|_| Mutex::new(LruCache::new(CACHE_CHUNKS_PER_SHARD)))
+            .collect();
+        Ok(Self {
+            segments,
+            chunks: state.chunks,
+            chunk_size,
+            len,
+            stored_md5: state.stored_md5,
+            stored_sha1: state.stored_sha1,
+            cache,
+        })
+    }
+
+    pub fn chunk_size(&self) -> u64 {
+        self.chunk_size
+    }
+
+    pub fn segment_count(&self) -> usize {
+        self.segments.len()
+    }
+
+    /// Read the whole media and compare it with the MD5/SHA1 stored in the
+    /// `hash`/`digest` sections.
+    pub fn verify_hashes(&self) -> Result<EwfHashVerification, EvidenceError> {
+        let mut md5 = md5::Context::new();
+        let mut sha1 = Sha1::new();
+        let mut buf = vec![0u8; (self.chunk_size as usize).max(1024 * 1024)];
+        let mut offset = 0u64;
+        while offset < self.len {
+            let want = (self.len - offset).min(buf.len() as u64) as usize;
+            let n = self.read_at(offset, &mut buf[..want])?;
+            if n == 0 {
+                return Err(EvidenceError::Corrupt(format!(
+                    "EWF media ends early at offset {offset}"
+                )));
+            }
+            md5.consume(&buf[..n]);
+            sha1.update(&buf[..n]);
+            offset += n as u64;
+        }
+        Ok(EwfHashVerification {
+            stored_md5: self.stored_md5.map(hex::encode),
+            computed_md5: format!("{:x}", md5.compute()),
+            stored_sha1: self.stored_sha1.map(hex::encode),
+            computed_sha1: hex::encode(sha1.finalize()),
+        })
+    }
+
+    fn chunk(&self, index: u64) -> Result<Arc<Vec<u8>>, EvidenceError> {
+        let shard = &self.cache[(index % CACHE_SHARDS as u64) as usize];
+        if let Some(data) = lock_cache(shard)?.get(&index) {
+            return Ok(data.clone());
+        }
+
+        // Decompress outside the lock so other readers are not blocked.
+        let data = Arc::new(self.load_chunk(index)?);
+        lock_cache(shard)?.insert(index, data.clone());
+        Ok(data)
+    }
+
+    fn load_chunk(&self, index: u64) -> Result<Vec<u8>, EvidenceError> {
+        let location = self.chunks[index as usize];
+        let expected = self.chunk_size.min(self.len - index * self.chunk_size) as usize;
+        let mut raw = vec![0u8; location.stored_size as usize];
+        read_exact_at(&self.segments[location.segment], location.offset, &mut raw)?;
+
+        if location.compressed {
+            let mut out = Vec::with_capacity(self.chunk_size as usize);
+            flate2::read::ZlibDecoder::new(raw.as_slice())
+                .take(self.chunk_size)
+                .read_to_end(&mut out)
+                .map_err(|err| {
+                    EvidenceError::Corrupt(format!("EWF chunk {index} inflate failed: {err}"))
+                })?;
+            if out.len() < expected {
+                return Err(EvidenceError::Corrupt(format!(
+                    "EWF chunk {index} inflated to {} bytes, expected {expected}",
+                    out.len()
+                )));
+            }
+            out.truncate(expected);
+            return Ok(out);
+        }
+
+        if raw.len() < expected {
+            return Err(EvidenceError::Corrupt(format!(
+                "EWF chunk {index} is {} bytes, expected {expected}",
+                raw.len()
+            )));
+        }
+        if let Some(stored) = read_u32_le(&raw, expected) {
+            let computed = adler32(&raw[..expected]);
+            if stored != computed {
+                return Err(EvidenceError::Corrupt(format!(
+                    "EWF chunk {index} checksum mismatch"
+                )));
+            }
+        }
+        raw.truncate(expected);
+        Ok(raw)
+    }
+}
+
+impl EvidenceSource for EwfSource {
+    fn len(&self) -> u64 {
+        self.len
+    }
+
+    fn read_at(&self, offset: u64, buf: &mut [u8]) -> Result<usize, EvidenceError> {
+        let mut filled = 0usize;
+        while filled < buf.len() {
+            let pos = offset + filled as u64;
+            if pos >= self.len {
+                break;
+            }
+            let index = pos / self.chunk_size;
+            let within = (pos % self.chunk_size) as usize;
+            let chunk = self.chunk(index)?;
+            let available = chunk.len().saturating_sub(within);
+            if available == 0 {
+                break;
+            }
+            let n = available.min(buf.len() - filled);
+            buf[filled..filled + n].copy_from_slice(&chunk[within..within + n]);
+            filled += n;
+        }
+        Ok(filled)
+    }
+}
+
+/// Walk the section chain of one segment file. Returns true when the
+/// segment ends with a `done` section (last segment of the image).
+fn parse_segment(
+    segment: &RawFileSource,
+    index: usize,
+    state: &mut ParseState,
+) -> Result<bool, EvidenceError> {
+    let mut header = [0u8; FILE_HEADER_LEN as usize];
+    read_exact_at(segment, 0, &mut header)?;
+    if &header[..8] == EVF2_SIGNATURE {
+        return Err(EvidenceError::Unsupported(
+            "EWF v2 (Ex01) images are not supported".to_string(),
+        ));
+    }
+    if &header[..8] != EVF_SIGNATURE {
+        return Err(EvidenceError::Unsupported("not an EWF image".to_string()));
+    }
+    let segment_number = u16::from_le_bytes([header[9], header[10]]) as usize;
+    if segment_number != index + 1 {
+        return Err(EvidenceError::Segment(format!(
+            "EWF segment number {segment_number} found where {} was expected",
+            index + 1
+        )));
+    }
+
+    state.sectors_end = None;
+    let mut offset = FILE_HEADER_LEN;
+    loop {
+        let mut descriptor = [0u8; SECTION_DESCRIPTOR_LEN as usize];
+        read_exact_at(segment, offset, &mut descriptor)?;
+        let stored = read_u32_le(&descriptor, 72).unwrap_or(0);
+        if stored != adler32(&descriptor[..72]) {
+            return Err(EvidenceError::Corrupt(format!(
+                "section descriptor checksum mismatch at offset {offset}"
+            )));
+        }
+        let kind_end = descriptor[..16].iter().position(|&b| b == 0).unwrap_or(16);
+        let kind = String::from_utf8_lossy(&descriptor[..kind_end]).to_string();
+        let next = read_u64_le(&descriptor, 16).unwrap_or(0);
+        let size = read_u64_le(&descriptor, 24).unwrap_or(0);
+        let data_offset = offset + SECTION_DESCRIPTOR_LEN;
+        let data_len = size.saturating_sub(SECTION_DESCRIPTOR_LEN);
+
+        // Skipping a corrupt table would shift every later chunk index
+        if kind != "table2"
+            && let Some(err) = state.corrupt_table.take()
+        {
+            return Err(EvidenceError::Corrupt(format!(
+                "chunk table corrupt and not followed by table2: {err}"
+            )));
+        }
+
+        match kind.as_str() {
+            "volume" | "disk" if state.geometry.is_none() => {
+                let data = read_section(segment, data_offset, data_len)?;
+                state.geometry = Some(parse_volume(&data)?);
+            }
+            "sectors" => {
+                state.sectors_end = Some(offset.saturating_add(size));
+            }
+            "table" => {
+                let data = read_section(segment, data_offset, data_len)?;
+                match parse_table(&data, index, offset, offset.saturating_add(size), state) {
+                    Ok(chunks) => state.chunks.extend(chunks),
+                    Err(err) => state.corrupt_table = Some(err.to_string()),
+                }
+            }
+            "table2" if state.corrupt_table.is_some() => {
+                let data = read_section(segment, data_offset, data_len)?;
+                let chunks = parse_table(&data, index, offset, offset.saturating_add(size), state)
+                    .map_err(|err| {
+                        EvidenceError::Corrupt(format!("table and table2 corrupt: {err}"))
+                    })?;
+                state.chunks.extend(chunks);
+                state.corrupt_table = None;
+            }
+            "hash" => {
+                let data = read_section(segment, data_offset, data_len)?;
+                if let Some(md5) = data.get(..16) {
+                    state.stored_md5 = nonzero(md5.try_into().expect("16 bytes"));
+                }
+            }
+            "digest" => {
+                let data = read_section(segment, data_offset, data_len)?;
+                if let Some(md5) = data.get(..16) {
+                    state.stored_md5 = nonzero(md5.try_into().expect("16 bytes"));
+                }
+                if let Some(sha1) = data.get(16..36) {
+                    state.stored_sha1 = nonzero(sha1.try_into().expect("20 bytes"));
+                }
+            }
+            "next" => return Ok(false),
+            "done" => return Ok(true),
+            _ => {}
+        }
+
+        if next <= offset || next >= segment.len() {
+            return Err(EvidenceError::Corrupt(format!(
+                "section '{kind}' at offset {offset} has invalid next offset {next}"
+            )));
+        }
+        offset = next;
+    }
+}
+
+fn parse_volume(data: &[u8]) -> Result<Geometry, EvidenceError> {
+    if data.len() < VOLUME_MIN_LEN {
+        return Err(EvidenceError::Corrupt(
+            "volume section too short".to_string(),
+        ));
+    }
+    let geometry = Geometry {
+        chunk_count: read_u32_le(data, 4).unwrap_or(0),
+        sectors_per_chunk: read_u32_le(data, 8).unwrap_or(0),
+        bytes_per_sector: read_u32_le(data, 12).unwrap_or(0),
+        sector_count: read_u64_le(data, 16).unwrap_or(0),
+    };
+    if geometry.bytes_per_sector == 0 || geometry.sectors_per_chunk == 0 {
+        return Err(EvidenceError::Corrupt(
+            "volume section geometry is zero".to_string(),
+        ));
+    }
+    Ok(geometry)
+}
+
+/// Parse a `table`/`table2` section body into chunk locations. The stored
+/// size of each chunk runs to the next entry; the last one ends at the end of
+/// the preceding `sectors` section (EnCase 2+) or of the table section
+/// itself (EnCase 1, chunks stored after the offsets array).
+fn parse_table(
+    data: &[u8],
+    segment: usize,
+    section_start: u64,
+    section_end: u64,
+    state: &ParseState,
+) -> Result<Vec<ChunkLocation>, EvidenceError> {
+    if data.len() < TABLE_HEADER_LEN {
+        return Err(EvidenceError::Corrupt(
+            "table section too short".to_string(),
+        ));
+    }
+    let stored = read_u32_le(data, 20).unwrap_or(0);
+    if stored != adler32(&data[..20]) {
+        return Err(EvidenceError::Corrupt(
+            "table header checksum mismatch".to_string(),
+        ));
+    }
+    let count = read_u32_le(data, 0).unwrap_or(0) as usize;
+    let base_offset = read_u64_le(data, 8).unwrap_or(0);
+    let entries_end = TABLE_HEADER_LEN + count * 4;
+    let entries = data
+        .get(TABLE_HEADER_LEN..entries_end)
+        .ok_or_else(|| EvidenceError::Corrupt("table entries truncated".to_string()))?;
+
+    let raw: Vec<(u64, bool)> = entries
+        .chunks_exact(4)
+        .map(|entry| {
+            let value = u32::from_le_bytes([entry[0], entry[1], entry[2], entry[3]]);
+            (
+                base_offset + (value & 0x7FFF_FFFF) as u64,
+                value & 0x8000_0000 != 0,
+            )
+        })
+        .collect();
+
+    let mut chunks = Vec::with_capacity(raw.len());
+    for (idx, &(offset, compressed)) in raw.iter().enumerate() {
+        let end = match raw.get(idx + 1) {
+            Some(&(next, _)) => next,
+            None if offset < section_start => state
+                .sectors_end
+                .filter(|&end| end > offset && end <= section_start)
+                .unwrap_or(section_start),
+            None => section_end,
+        };
+        if end <= offset || end - offset > MAX_CHUNK_SIZE {
+            return Err(EvidenceError::Corrupt(format!(
+                "table entry {idx} has invalid chunk bounds {offset}..{end}"
+            )));
+        }
+        chunks.push(ChunkLocation {
+            segment,
+            offset,
+            stored_size: end - offset,
+            compressed,
+        });
+    }
+    Ok(chunks)
+}
+
+fn read_section(segment: &RawFileSource, offset: u64, len: u64) -> Result<Vec<u8>, EvidenceError> {
+    if offset.saturating_add(len) > segment.len() {
+        return Err(EvidenceError::Corrupt(format!(
+            "section at offset {offset} extends past end of segment"
+        )));
+    }
+    let mut data = vec![0u8; len as usize];
+    read_exact_at(segment, offset, &mut data)?;
+    Ok(data)
+}
+
+fn lock_cache(shard: &Mutex<ChunkCache>) -> Result<MutexGuard<'_, ChunkCache>, EvidenceError> {
+    shard
+        .lock()
+        .map_err(|_| EvidenceError::Unsupported("EWF chunk cache lock poisoned".to_string()))
+}
+
+/// Segment files of an image, in order: `.E01`..`.E99`, then `.EAA`..`.EZZ`,
+/// `.FAA`.. (case follows the first segment).
+fn segment_paths(first: &Path) -> Vec<PathBuf> {
+    let mut paths = vec![first.to_path_buf()];
+    let Some(mut ext) = first
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .map(str::to_string)
+    else {
+        return paths;
+    };
+    while let Some(next) = next_segment_extension(&ext) {
+        let path = first.with_extension(&next);
+        if !path.is_file() {
+            break;
+        }
+        paths.push(path);
+        ext = next;
+    }
+    paths
+}
+
+fn next_segment_extension(ext: &str) -> Option<String> {
+    let bytes = ext.as_bytes();
+    if bytes.len() != 3 || !bytes[0].is_ascii_alphabetic() {
+        return None;
+    }
+    let upper = bytes[0].is_ascii_uppercase();
+    let (a, z) = if upper { (b'A', b'Z') } else { (b'a', b'z') };
+    if bytes[1].is_ascii_digit() && bytes[2].is_ascii_digit() {
+        let number = (bytes[1] - b'0') as u32 * 10 + (bytes[2] - b'0') as u32;
+        if number < 99 {
+            return Some(format!("{}{:02}", bytes[0] as char, number + 1));
+        }
+        return Some(format!("{}{}{}", bytes[0] as char, a as char, a as char));
+    }
+    let mut out = bytes.to_vec();
+    for idx in (0..3).rev() {
+        if out[idx] < z {
+            out[idx] += 1;
+            return String::from_utf8(out).ok();
+        }
+        out[idx] = a;
+    }
+    None
+}
+
+fn nonzero<const N: usize>(bytes: [u8; N]) -> Option<[u8; N]> {
+    bytes.iter().any(|&b| b != 0).then_some(bytes)
+}
+
+pub(crate) fn adler32(data: &[u8]) -> u32 {
+    const MOD: u32 = 65_521;
+    let (mut a, mut b) = (1u32, 0u32);
+    for chunk in data.chunks(5552) {
+        for &byte in chunk {
+            a += byte as u32;
+            b += a;
+        }
+        a %= MOD;
+        b %= MOD;
+    }
+    (b << 16) | a
+}
+
+#[cfg(test)]
+pub(crate) mod test_support {
+    //! Writer for small synthetic E01 images used by tests.
+
+    use std::io::Write;
+
+    use super::adler32;
+
+    pub const SECTOR: usize = 512;
+
+    fn descriptor(kind: &str, offset: u64, next: Option<u64>, size: u64) -> Vec<u8> {
+        let mut out = vec![0u8; 76];
+        out[..kind.len()].copy_from_slice(kind.as_bytes());
+        out[16..24].copy_from_slice(&next.unwrap_or(offset).to_le_bytes());
+        out[24..32].copy_from_slice(&size.to_le_bytes());
+        let checksum = adler32(&out[..72]);
+        out[72..76].copy_from_slice(&checksum.to_le_bytes());
+        out
+    }
+
+    fn push_section(out: &mut Vec<u8>, kind: &str, body: &[u8], last: bool) {
+        let offset = out.len() as u64;
+        let size = 76 + body.len() as u64;
+        let next = if last { None } else { Some(offset + size) };
+        out.extend(descriptor(kind, offset, next, size));
+        out.extend_from_slice(body);
+    }
+
+    fn table_body(offsets: &[(u64, bool)]) -> Vec<u8> {
+        let mut body = Vec::new();
+        body.extend_from_slice(&(offsets.len() as u32).to_le_bytes());
+        body.extend_from_slice(&[0u8; 4]);
+        body.extend_from_slice(&0u64.to_le_bytes());
+        body.extend_from_slice(&[0u8; 4]);
+        let checksum = adler32(&body);
+        body.extend_from_slice(&checksum.to_le_bytes());
+        let mut entries = Vec::new();
+        for &(offset, compressed) in offsets {
+            let value = offset as u32 | if compressed { 0x8000_0000 } else { 0 };
+            entries.extend_from_slice(&value.to_le_bytes());
+        }
+        body.extend_from_slice(&entries);
+        body.extend_from_slice(&adler32(&entries).to_le_bytes());
+        body
+    }
+
+    /// Build segment files for `media`. Chunks alternate between compressed
+    /// and uncompressed storage; `chunks_per_segment` controls splitting.
+    pub fn build_e01(
+        media: &[u8],
+        sectors_per_chunk: usize,
+        chunks_per_segment: usize,
+        hashes: Option<([u8; 16], [u8; 20])>,
+    ) -> Vec<Vec<u8>> {
+        assert_eq!(media.len() % SECTOR, 0);
+        let chunk_size = sectors_per_chunk * SECTOR;
+        let chunks: Vec<&[u8]> = media.chunks(chunk_size).collect();
+        let groups: Vec<&[&[u8]]> = chunks.chunks(chunks_per_segment.max(1)).collect();
+        let mut segments = Vec::new();
+
+        for (seg_idx, group) in groups.iter().enumerate() {
+            let mut out = Vec::new();
+            out.extend_from_slice(b"EVF\x09\x0D\x0A\xFF\x00");
+            out.push(1);
+            out.extend_from_slice(&((seg_idx + 1) as u16).to_le_bytes());
+            out.extend_from_slice(&0u16.to_le_bytes());
+
+            if seg_idx == 0 {
+                let mut volume = vec![0u8; 1052];
+                volume[0] = 1;
+                volume[4..8].copy_from_slice(&(chunks.len() as u32).to_le_bytes());
+                volume[8..12].copy_from_slice(&(sectors_per_chunk as u32).to_le_bytes());
+                volume[12..16].copy_from_slice(&(SECTOR as u32).to_le_bytes());
+                volume[16..24].copy_from_slice(&((media.len() / SECTOR) as u64).to_le_bytes());
+                let checksum = adler32(&volume[..1048]);
+                volume[1048..].copy_from_slice(&checksum.to_le_bytes());
+                push_section(&mut out, "volume", &volume, false);
+            }
+
+            let sectors_start = out.len() as u64;
+            let mut body = Vec::new();
+            let mut offsets = Vec::new();
+            for (idx, chunk) in group.iter().enumerate() {
+                let offset = sectors_start + 76 + body.len() as u64;
+                let compressed = idx % 2 == 0;
+                if compressed {
+                    let mut encoder =
+                        flate2::write::ZlibEncoder::new(Vec::new(), flate2::Compression::default());
+                    encoder.write_all(chunk).expect("compress");
+                    body.extend(encoder.finish().expect("compress"));
+                } else {
+                    body.extend_from_slice(chunk);
+                    body.extend_from_slice(&adler32(chunk).to_le_bytes());
+                }
+                offsets.push((offset, compressed));
+            }
+            push_section(&mut out, "sectors", &body, false);
+            let table = table_body(&offsets);
+            push_section(&mut out, "table", &table, false);
+            push_section(&mut out, "table2", &table, false);
+
+            let last = seg_idx + 1 == groups.len();
+            if last {
+                if let Some((md5, sha1)) = hashes {
+                    let mut digest = Vec::new();
+                    digest.extend_from_slice(&md5);
+                    digest.extend_from_slice(&sha1);
+                    digest.extend_from_slice(&[0u8; 40]);
+                    let checksum = adler32(&digest);
+                    digest.extend_from_slice(&checksum.to_le_bytes());
+                    push_section(&mut out, "digest", &digest, false);
+                }
+                push_section(&mut out, "done", &[], true);
+            } else {
+                push_section(&mut out, "next", &[], true);
+            }
+            segments.push(out);
+        }
+        segments
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use sha1::{Digest, Sha1};
+
+    use super::test_support::{SECTOR, build_e01};
+    use super::{EwfSource, adler32, next_segment_extension};
+    use crate::evidence::{EvidenceError, EvidenceSource};
+
+    fn sample_media(sectors: usize) -> Vec<u8> {
+        (0..sectors * SECTOR)
+            .map(|idx| ((idx / 7) % 251) as u8)
+            .collect()
+    }
+
+    fn hashes(media: &[u8]) -> ([u8; 16], [u8; 20]) {
+        (md5::compute(media).0, Sha1::digest(media).into())
+    }
+
+    fn write_segments(dir: &std::path::Path, segments: &[Vec<u8>]) -> std::path::PathBuf {
+        for (idx, data) in segments.iter().enumerate() {
+            std::fs::write(dir.join(format!("image.E{:02}", idx + 1)), data).expect("write");
+        }
+        dir.join("image.E01")
+    }
+
+    #[test]
+    fn segment_extensions_roll_over() {
+        assert_eq!(next_segment_extension("E01").as_deref(), Some("E02"));
+        assert_eq!(next_segment_extension("e99").as_deref(), Some("eaa"));
+        assert_eq!(next_segment_extension("EAZ").as_deref(), Some("EBA"));
+        assert_eq!(next_segment_extension("EZZ").as_deref(), Some("FAA"));
+        assert_eq!(next_segment_extension("ZZZ"), None);
+    }
+
+    #[test]
+    fn reads_compressed_and_raw_chunks() {
+        let tmp = tempfile::tempdir().expect("tempdir");
+        let media = sample_media(5);
+        let path = write_segments(tmp.path(), &build_e01(&media, 2, 16, None));
+
+        let src = EwfSource::open(&path).expect("open");
+        assert_eq!(src.len(), media.len() as u64);
+        assert_eq!(src.chunk_size(), 1024);
+        let mut buf = vec![0u8; media.len()];
+        assert_eq!(src.read_at(0, &mut buf).expect("read"), media.len());
+        assert_eq!(buf, media);
+
+        let mut window = [0u8; 100];
+        assert_eq!(src.read_at(1000, &mut window).expect("read"), 100);
+        assert_eq!(&window[..], &media[1000..1100]);
+    }
+
+    #[test]
+    fn reads_across_segments_concurrently() {
+        let tmp = tempfile::tempdir().expect("tempdir");
+        let media = sample_media(24);
+        let path = write_segments(tmp.path(), &build_e01(&media, 1, 5, None));
+
+        let src = Arc::new(EwfSource::open(&path).expect("open"));
+        assert_eq!(src.segment_count(), 5);
+        let media = Arc::new(media);
+        let handles: Vec<_> = (0..8)
+            .map(|worker| {
+                let src = src.clone();
+                let media = media.clone();
+                std::thread::spawn(move || {
+                    for step in 0..50u64 {
+                        let offset = (worker * 997 + step * 131) % (media.len() as u64 - 700);
+                        let mut buf = vec![0u8; 700];
+                        let n = src.read_at(offset, &mut buf).expect("read");
+                        assert_eq!(n, 700);
+                        assert_eq!(&buf[..], &media[offset as usize..offset as usize + 700]);
+                    }
+                })
+            })
+            .collect();
+        for handle in handles {
+            handle.join().expect("join");
+        }
+    }
+
+    #[test]
+    fn verifies_stored_hashes() {
+        let tmp = tempfile::tempdir().expect("tempdir");
+        let media = sample_media(8);
+        let path = write_segments(tmp.path(), &build_e01(&media, 2, 16, Some(hashes(&media))));
+
+        let verification = EwfSource::open(&path)
+            .expect("open")
+            .verify_hashes()
+            .expect("verify");
+        assert!(verification.has_stored_hash());
+        assert!(verification.matches());
+
+        let mut other = media.clone();
+        other[0] ^= 0xFF;
+        let path = write_segments(tmp.path(), &build_e01(&media, 2, 16, Some(hashes(&other))));
+        let verification = EwfSource::open(&path)
+            .expect("open")
+            .verify_hashes()
+            .expect("verify");
+        assert!(!verification.matches());
+    }
+
+    #[test]
+    fn falls_back_to_table2() {
+        let tmp = tempfile::tempdir().expect("tempdir");
+        let media = sample_media(4);
+        let mut segments = build_e01(&media, 2, 16, None);
+        // Corrupt the first table header checksum; table2 is an intact copy.
+        let data = &mut segments[0];
+        let table_pos = data
+            .windows(6)
+            .position(|w| w == b"table\0")
+            .expect("table section");
+        data[table_pos + 76 + 20] ^= 0xFF;
+        let path = write_segments(tmp.path(), &segments);
+
+        let src = EwfSource::open(&path).expect("open");
+        let mut buf = vec![0u8; media.len()];
+        src.read_at(0, &mut buf).expect("read");
+        assert_eq!(buf, media);
+    }
+
+    #[test]
+    fn corrupt_table_without_table2_is_an_error() {
+        let tmp = tempfile::tempdir().expect("tempdir");
+        let media = sample_media(4);
+        let mut segments = build_e01(&media, 2, 16, None);
+        let data = &mut segments[0];
+        let table_pos = data
+            .windows(6)
+            .position(|w| w == b"table\0")
+            .expect("table section");
+        data[table_pos + 76 + 20] ^= 0xFF;
+        // Turn the table2 copy into a second table of the same segment
+        let table2_pos = data
+            .windows(6)
+            .position(|w| w == b"table2")
+            .expect("table2 section");
+        data[table2_pos + 5] = 0;
+        let checksum = adler32(&data[table2_pos..table2_pos + 72]);
+        data[table2_pos + 72..table2_pos + 76].copy_from_slice(&checksum.to_le_bytes());
+        let path = write_segments(tmp.path(), &segments);
+
+        match EwfSource::open(&path) {
+            Err(EvidenceError::Corrupt(msg)) => assert!(msg.contains("not followed by table2")),
+            Err(other) => panic!("unexpected error: {other:?}"),
+            Ok(_) => panic!("expected corrupt table error"),
+        }
+    }
+
+    #[test]
+    fn missing_segment_is_reported() {
+        let tmp = tempfile::tempdir().expect("tempdir");
+        let media = sample_media(6);
+        let segments = build_e01(&media, 1, 2, None);
+        let path = write_segments(tmp.path(), &segments[..2]);
+        match EwfSource::open(&path) {
+            Err(EvidenceError::Segment(msg)) => assert!(msg.contains("done section")),
+            Err(other) => panic!("unexpected error: {other:?}"),
+            Ok(_) => panic!("expected missing segment error"),
+        }
+    }
+}
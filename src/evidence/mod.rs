@@ -0,0 +1,797 @@
+use std::fs::{File, OpenOptions};
+use std::sync::Arc;
+
+use thiserror::Error;
+
+#[cfg(feature = "ewf")]
+mod ewf;
+
+mod qcow2;
+mod tolerant;
+mod vhd;
+mod vhdx;
+mod vmdk;
+
+#[cfg(feature = "ewf")]
+pub use ewf::{EwfHashVerification, EwfSource};
+pub use qcow2::Qcow2Source;
+pub use tolerant::{TOLERANT_SECTOR_SIZE, TolerantSource, write_ddrescue_mapfile};
+pub use vhd::VhdSource;
+pub use vhdx::VhdxSource;
+pub use vmdk::VmdkSource;
+
+#[derive(Debug, Error)]
+pub enum EvidenceError {
+    #[error("io error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("unsupported evidence type: {0}")]
+    Unsupported(String),
+    #[error("invalid evidence offset: {0}")]
+    InvalidOffset(String),
+    #[error("invalid split image segment: {0}")]
+    Segment(String),
+    #[error("corrupt evidence container: {0}")]
+    Corrupt(String),
+}
+
+/// A read-only evidence source backed by a linear byte space.
+///
+/// # Example
+/// ```rust
+/// use swiftbeaver::evidence::{EvidenceSource, RawFileSource};
+///
+/// let path = std::env::temp_dir().join("SwiftBeaver_evidence_example.bin");
+/// std::fs::write(&path, b"hello").unwrap();
+/// let source = RawFileSource::open(&path).unwrap();
+/// let mut buf = [0u8; 5];
+/// source.read_at(0, &mut buf).unwrap();
+/// assert_eq!(&buf, b"hello");
+/// ```
+pub trait EvidenceSource: Send + Sync {
+    fn len(&self) -> u64;
+    fn read_at(&self, offset: u64, buf: &mut [u8]) -> Result<usize, EvidenceError>;
+
+    /// Sorted, non-overlapping `[start, end)` ranges that could not be read
+    /// and were zero-filled. Only sources that tolerate read errors report
+    /// any.
+    fn unreadable_ranges(&self) -> Arc<[(u64, u64)]> {
+        Arc::default()
+    }
+}
+
+pub struct RawFileSource {
+    file: File,
+    len: u64,
+    #[cfg(not(unix))]
+    lock: std::sync::Mutex<()>,
+}
+
+impl RawFileSource {
+    pub fn open(path: &std::path::Path) -> Result<Self, EvidenceError> {
+        let file = File::open(path)?;
+        let len = file.metadata()?.len();
+        Ok(Self {
+            file,
+            len,
+            #[cfg(not(unix))]
+            lock: std::sync::Mutex::new(()),
+        })
+    }
+}
+
+impl EvidenceSource for RawFileSource {
+    fn len(&self) -> u64 {
+        self.len
+    }
+
+    fn read_at(&self, offset: u64, buf: &mut [u8]) -> Result<usize, EvidenceError> {
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::FileExt;
+            Ok(self.file.read_at(buf, offset)?)
+        }
+        #[cfg(not(unix))]
+        {
+            use std::io::{Read, Seek, SeekFrom};
+            let _guard = self
+                .lock
+                .lock()
+                .map_err(|_| EvidenceError::Unsupported("raw file lock poisoned".to_string()))?;
+            let mut f = &self.file;
+            f.seek(SeekFrom::Start(offset))?;
+            Ok(f.read(buf)?)
+        }
+    }
+}
+
+/// Bytes held in memory, such as a container member decoded for recursive
+/// carving.
+pub struct MemorySource {
+    data: Vec<u8>,
+}
+
+impl MemorySource {
+    pub fn new(data: Vec<u8>) -> Self {
+        Self { data }
+    }
+
+    pub fn data(&self) -> &[u8] {
+        &self.data
+    }
+}
+
+impl EvidenceSource for MemorySource {
+    fn len(&self) -> u64 {
+        self.data.len() as u64
+    }
+
+    fn read_at(&self, offset: u64, buf: &mut [u8]) -> Result<usize, EvidenceError> {
+        let tail = usize::try_from(offset)
+            .ok()
+            .and_then(|start| self.data.get(start..))
+            .unwrap_or_default();
+        let n = buf.len().min(tail.len());
+        buf[..n].copy_from_slice(&tail[..n]);
+        Ok(n)
+    }
+}
+
+/// Split raw image (FTK-style `.001`/`.002`/... or `split(1)`-style
+/// `.aa`/`.ab`/...) exposed as one linear byte space.
+pub struct SplitRawSource {
+    segments: Vec<RawFileSource>,
+    /// Global offset of the first byte of each segment.
+    starts: Vec<u64>,
+    paths: Vec<std::path::PathBuf>,
+    len: u64,
+}
+
+impl SplitRawSource {
+    /// Open a split image starting from its first segment, discovering the
+    /// following sibling segments by incrementing the extension.
+    pub fn open(first: &std::path::Path) -> Result<Self, EvidenceError> {
+        let paths = split_segment_paths(first).ok_or_else(|| {
+            EvidenceError::Segment(format!(
+                "{} is not the first segment of a split image",
+                first.display()
+            ))
+        })?;
+        Self::open_segments(&paths)
+    }
+
+    /// Open an explicit, ordered list of segments.
+    pub fn open_segments(paths: &[std::path::PathBuf]) -> Result<Self, EvidenceError> {
+        if paths.is_empty() {
+            return Err(EvidenceError::Segment("no segments".to_string()));
+        }
+        let mut segments = Vec::with_capacity(paths.len());
+        let mut starts = Vec::with_capacity(paths.len());
+        let mut len = 0u64;
+        for (idx, path) in paths.iter().enumerate() {
+            let segment = RawFileSource::open(path)?;
+            let is_last = idx + 1 == paths.len();
+            if segment.len() == 0 {
+                return Err(EvidenceError::Segment(format!(
+                    "{} is empty",
+                    path.display()
+                )));
+            }
+            if let Some(first) = segments.first().map(|s: &RawFileSource| s.len()) {
+                let size = segment.len();
+                if (!is_last && size != first) || (is_last && size > first) {
+                    return Err(EvidenceError::Segment(format!(
+                        "{} is {size} bytes but segments are {first} bytes",
+                        path.display()
+                    )));
+                }
+            }
+            starts.push(len);
+            len += segment.len();
+            segments.push(segment);
+        }
+        Ok(Self {
+            segments,
+            starts,
+            paths: paths.to_vec(),
+            len,
+        })
+    }
+
+    pub fn segment_paths(&self) -> &[std::path::PathBuf] {
+        &self.paths
+    }
+}
+
+impl EvidenceSource for SplitRawSource {
+    fn len(&self) -> u64 {
+        self.len
+    }
+
+    fn read_at(&self, offset: u64, buf: &mut [u8]) -> Result<usize, EvidenceError> {
+        let mut filled = 0usize;
+        while filled < buf.len() {
+            let pos = offset + filled as u64;
+            if pos >= self.len {
+                break;
+            }
+            // Index of the last segment starting at or before `pos`.
+            let idx = self.starts.partition_point(|&start| start <= pos) - 1;
+            let n = self.segments[idx].read_at(pos - self.starts[idx], &mut buf[filled..])?;
+            if n == 0 {
+                break;
+            }
+            filled += n;
+        }
+        Ok(filled)
+    }
+}
+
+/// Fill `buf` from `source` at `offset`, treating a short read as corruption
+/// of the container being parsed.
+fn read_exact_at(
+    source: &dyn EvidenceSource,
+    offset: u64,
+    buf: &mut [u8],
+) -> Result<(), EvidenceError> {
+    let mut filled = 0usize;
+    while filled < buf.len() {
+        let n = source.read_at(offset + filled as u64, &mut buf[filled..])?;
+        if n == 0 {
+            return Err(EvidenceError::Corrupt(format!(
+                "unexpected end of file at offset {}",
+                offset + filled as u64
+            )));
+        }
+        filled += n;
+    }
+    Ok(())
+}
+
+/// Drive a `read_at` over a virtual disk made of fixed-size blocks. `fill`
+/// receives the block index, the offset within the block and the slice of
+/// `buf` that falls inside that block.
+fn read_blocks(
+    len: u64,
+    block_size: u64,
+    offset: u64,
+    buf: &mut [u8],
+    mut fill: impl FnMut(u64, u64, &mut [u8]) -> Result<(), EvidenceError>,
+) -> Result<usize, EvidenceError> {
+    let end = len.min(offset.saturating_add(buf.len() as u64));
+    let mut pos = offset;
+    while pos < end {
+        let block = pos / block_size;
+        let within = pos % block_size;
+        let n = (block_size - within).min(end - pos) as usize;
+        let start = (pos - offset) as usize;
+        fill(block, within, &mut buf[start..start + n])?;
+        pos += n as u64;
+    }
+    Ok(end.saturating_sub(offset) as usize)
+}
+
+fn read_u16_le(data: &[u8], offset: usize) -> Option<u16> {
+    let bytes = data.get(offset..offset + 2)?;
+    Some(u16::from_le_bytes([bytes[0], bytes[1]]))
+}
+
+fn read_u32_le(data: &[u8], offset: usize) -> Option<u32> {
+    let bytes = data.get(offset..offset + 4)?;
+    Some(u32::from_le_bytes(bytes.try_into().ok()?))
+}
+
+fn read_u64_le(data: &[u8], offset: usize) -> Option<u64> {
+    let bytes = data.get(offset..offset + 8)?;
+    Some(u64::from_le_bytes(bytes.try_into().ok()?))
+}
+
+fn read_u32_be(data: &[u8], offset: usize) -> Option<u32> {
+    let bytes = data.get(offset..offset + 4)?;
+    Some(u32::from_be_bytes(bytes.try_into().ok()?))
+}
+
+fn read_u64_be(data: &[u8], offset: usize) -> Option<u64> {
+    let bytes = data.get(offset..offset + 8)?;
+    Some(u64::from_be_bytes(bytes.try_into().ok()?))
+}
+
+/// Enumerate the segments of a split image when `first` is a first segment
+/// (`.000`/`.001` or `.aa`). Returns `None` for other paths.
+fn split_segment_paths(first: &std::path::Path) -> Option<Vec<std::path::PathBuf>> {
+    let ext = first.extension()?.to_str()?;
+    let numeric = ext.bytes().all(|b| b.is_ascii_digit());
+    let alpha = ext.bytes().all(|b| b == b'a') || ext.bytes().all(|b| b == b'A');
+    if ext.len() < 2 || !(alpha || (numeric && matches!(ext.parse::<u64>(), Ok(0 | 1)))) {
+        return None;
+    }
+
+    let mut paths = vec![first.to_path_buf()];
+    let mut current = ext.to_string();
+    while let Some(candidate) = next_segment_suffix(&current) {
+        let path = first.with_extension(&candidate);
+        if !path.is_file() {
+            break;
+        }
+        paths.push(path);
+        current = candidate;
+    }
+    Some(paths)
+}
+
+/// `001` -> `002`, `aa` -> `ab`, `az` -> `ba`; `None` once the fixed-width
+/// suffix space is exhausted.
+fn next_segment_suffix(current: &str) -> Option<String> {
+    if current.bytes().all(|b| b.is_ascii_digit()) {
+        let width = current.len();
+        let next = format!("{:0width$}", current.parse::<u64>().ok()? + 1);
+        return (next.len() == width).then_some(next);
+    }
+    let upper = current.bytes().any(|b| b.is_ascii_uppercase());
+    let (first, last) = if upper { (b'A', b'Z') } else { (b'a', b'z') };
+    let mut bytes = current.as_bytes().to_vec();
+    for byte in bytes.iter_mut().rev() {
+        if *byte < last {
+            *byte += 1;
+            return String::from_utf8(bytes).ok();
+        }
+        *byte = first;
+    }
+    None
+}
+
+pub struct DeviceSource {
+    file: File,
+    len: u64,
+    #[cfg(not(unix))]
+    lock: std::sync::Mutex<()>,
+}
+
+impl DeviceSource {
+    pub fn open(path: &std::path::Path) -> Result<Self, EvidenceError> {
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::FileTypeExt;
+
+            let metadata = path.metadata()?;
+            if !metadata.file_type().is_block_device() {
+                return Err(EvidenceError::Unsupported(
+                    "path is not a block device".to_string(),
+                ));
+            }
+
+            let file = OpenOptions::new().read(true).open(path)?;
+            let len = device_len(&file, metadata.len())?;
+            return Ok(Self {
+                file,
+                len,
+                #[cfg(not(unix))]
+                lock: std::sync::Mutex::new(()),
+            });
+        }
+        #[cfg(not(unix))]
+        {
+            let _ = path;
+            Err(EvidenceError::Unsupported(
+                "device input is only supported on unix".to_string(),
+            ))
+        }
+    }
+}
+
+impl EvidenceSource for DeviceSource {
+    fn len(&self) -> u64 {
+        self.len
+    }
+
+    fn read_at(&self, offset: u64, buf: &mut [u8]) -> Result<usize, EvidenceError> {
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::FileExt;
+            Ok(self.file.read_at(buf, offset)?)
+        }
+        #[cfg(not(unix))]
+        {
+            use std::io::{Read, Seek, SeekFrom};
+            let _guard = self
+                .lock
+                .lock()
+                .map_err(|_| EvidenceError::Unsupported("device lock poisoned".to_string()))?;
+            let mut f = &self.file;
+            f.seek(SeekFrom::Start(offset))?;
+            Ok(f.read(buf)?)
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn device_len(file: &File, fallback_len: u64) -> Result<u64, EvidenceError> {
+    use std::os::unix::io::AsRawFd;
+
+    const BLKGETSIZE64: libc::c_ulong = 0x80081272;
+    let mut size: u64 = 0;
+    let rc = unsafe { libc::ioctl(file.as_raw_fd(), BLKGETSIZE64, &mut size) };
+    if rc != 0 {
+        if fallback_len > 0 {
+            return Ok(fallback_len);
+        }
+        return Err(EvidenceError::Unsupported(
+            "unable to read block device size".to_string(),
+        ));
+    }
+    Ok(size)
+}
+
+#[cfg(not(target_os = "linux"))]
+fn device_len(_file: &File, fallback_len: u64) -> Result<u64, EvidenceError> {
+    Ok(fallback_len)
+}
+
+use crate::cli::CliOptions;
+
+pub fn open_source(opts: &CliOptions) -> Result<Box<dyn EvidenceSource>, EvidenceError> {
+    let input = opts
+        .input
+        .as_deref()
+        .ok_or_else(|| EvidenceError::Unsupported("no input path given".to_string()))?;
+    if is_ewf_path(input) {
+        #[cfg(feature = "ewf")]
+        {
+            let src = ewf::EwfSource::open(input)?;
+            return Ok(Box::new(src));
+        }
+        #[cfg(not(feature = "ewf"))]
+        {
+            return Err(EvidenceError::Unsupported(
+                "E01 support requires the `ewf` feature".to_string(),
+            ));
+        }
+    }
+
+    if is_block_device(input)? {
+        let src = DeviceSource::open(input)?;
+        return Ok(Box::new(src));
+    }
+
+    if let Some(format) = sniff_disk_image(input)? {
+        return match format {
+            DiskImageFormat::Vmdk => Ok(Box::new(VmdkSource::open(input)?)),
+            DiskImageFormat::Vhd => Ok(Box::new(VhdSource::open(input)?)),
+            DiskImageFormat::Vhdx => Ok(Box::new(VhdxSource::open(input)?)),
+            DiskImageFormat::Qcow2 => Ok(Box::new(Qcow2Source::open(input)?)),
+        };
+    }
+
+    if let Some(paths) = split_segment_paths(input).filter(|paths| paths.len() > 1) {
+        let src = SplitRawSource::open_segments(&paths)?;
+        return Ok(Box::new(src));
+    }
+
+    let src = RawFileSource::open(input)?;
+    Ok(Box::new(src))
+}
+
+/// Re-read an E01 image and compare it with the hashes recorded by the
+/// imaging tool.
+#[cfg(feature = "ewf")]
+pub fn verify_ewf_hashes(path: &std::path::Path) -> Result<EwfHashVerification, EvidenceError> {
+    if !is_ewf_path(path) {
+        return Err(EvidenceError::Unsupported(format!(
+            "{} is not an E01 image",
+            path.display()
+        )));
+    }
+    EwfSource::open(path)?.verify_hashes()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum DiskImageFormat {
+    Vmdk,
+    Vhd,
+    Vhdx,
+    Qcow2,
+}
+
+/// Identify virtual disk containers by magic: VMDK sparse extents and
+/// descriptors, VHDX, QCOW2 and VHD (footer at the end, or its copy at the
+/// start of a dynamic disk).
+fn sniff_disk_image(path: &std::path::Path) -> Result<Option<DiskImageFormat>, EvidenceError> {
+    let file = RawFileSource::open(path)?;
+    let mut head = [0u8; 64];
+    let n = file.read_at(0, &mut head)?;
+    let head = &head[..n];
+    if head.starts_with(vmdk::SPARSE_MAGIC) || head.starts_with(vmdk::DESCRIPTOR_MAGIC) {
+        return Ok(Some(DiskImageFormat::Vmdk));
+    }
+    if head.starts_with(vhdx::FILE_MAGIC) {
+        return Ok(Some(DiskImageFormat::Vhdx));
+    }
+    if head.starts_with(qcow2::MAGIC) {
+        return Ok(Some(DiskImageFormat::Qcow2));
+    }
+    if head.starts_with(vhd::FOOTER_COOKIE) {
+        return Ok(Some(DiskImageFormat::Vhd));
+    }
+    if let Some(footer_at) = file.len().checked_sub(vhd::FOOTER_LEN) {
+        let mut cookie = [0u8; 8];
+        read_exact_at(&file, footer_at, &mut cookie)?;
+        if &cookie == vhd::FOOTER_COOKIE {
+            return Ok(Some(DiskImageFormat::Vhd));
+        }
+    }
+    Ok(None)
+}
+
+fn is_ewf_path(path: &std::path::Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .map(|ext| ext.eq_ignore_ascii_case("e01"))
+        .unwrap_or(false)
+}
+
+fn is_block_device(path: &std::path::Path) -> Result<bool, EvidenceError> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::FileTypeExt;
+
+        let metadata = std::fs::metadata(path)?;
+        Ok(metadata.file_type().is_block_device())
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = path;
+        Ok(false)
+    }
+}
+
+pub fn compute_sha256(
+    evidence: &dyn EvidenceSource,
+    chunk_size: usize,
+) -> Result<String, EvidenceError> {
+    use sha2::{Digest, Sha256};
+
+    let mut hasher = Sha256::new();
+    let total_len = evidence.len();
+    let mut offset = 0u64;
+    let mut buf = vec![0u8; chunk_size.max(1)];
+
+    while offset < total_len {
+        let remaining = total_len - offset;
+        let read_len = remaining.min(buf.len() as u64) as usize;
+        let n = evidence.read_at(offset, &mut buf[..read_len])?;
+        if n == 0 {
+            break;
+        }
+        hasher.update(&buf[..n]);
+        offset = offset.saturating_add(n as u64);
+    }
+
+    Ok(hex::encode(hasher.finalize()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        DiskImageFormat, EvidenceError, EvidenceSource, RawFileSource, SplitRawSource,
+        compute_sha256, is_ewf_path, next_segment_suffix, sniff_disk_image, split_segment_paths,
+    };
+
+    #[test]
+    fn ewf_extension_detection() {
+        assert!(is_ewf_path(std::path::Path::new("case.E01")));
+        assert!(is_ewf_path(std::path::Path::new("case.e01")));
+        assert!(!is_ewf_path(std::path::Path::new("case.dd")));
+    }
+
+    #[test]
+    fn computes_sha256_for_raw_file() {
+        use std::fs;
+
+        let tmp = tempfile::tempdir().expect("tempdir");
+        let path = tmp.path().join("image.bin");
+        fs::write(&path, b"abc").expect("write");
+
+        let src = RawFileSource::open(&path).expect("open");
+        let hash = compute_sha256(&src, 4).expect("hash");
+        assert_eq!(
+            hash,
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+    }
+
+    #[test]
+    fn split_suffix_increments() {
+        assert_eq!(next_segment_suffix("001").as_deref(), Some("002"));
+        assert_eq!(next_segment_suffix("009").as_deref(), Some("010"));
+        assert_eq!(next_segment_suffix("999"), None);
+        assert_eq!(next_segment_suffix("az").as_deref(), Some("ba"));
+        assert_eq!(next_segment_suffix("AZ").as_deref(), Some("BA"));
+        assert_eq!(next_segment_suffix("zz"), None);
+    }
+
+    #[test]
+    fn split_raw_reads_across_segments() {
+        use std::fs;
+
+        let tmp = tempfile::tempdir().expect("tempdir");
+        fs::write(tmp.path().join("image.001"), b"abcd").expect("write");
+        fs::write(tmp.path().join("image.002"), b"efgh").expect("write");
+        fs::write(tmp.path().join("image.003"), b"ij").expect("write");
+
+        let paths = split_segment_paths(&tmp.path().join("image.001")).expect("split");
+        assert_eq!(paths.len(), 3);
+        assert!(split_segment_paths(&tmp.path().join("image.002")).is_none());
+
+        let src = SplitRawSource::open(&tmp.path().join("image.001")).expect("open");
+        assert_eq!(src.len(), 10);
+        let mut buf = [0u8; 6];
+        assert_eq!(src.read_at(2, &mut buf).expect("read"), 6);
+        assert_eq!(&buf, b"cdefgh");
+        let mut tail = [0u8; 8];
+        assert_eq!(src.read_at(7, &mut tail).expect("read"), 3);
+        assert_eq!(&tail[..3], b"hij");
+    }
+
+    #[test]
+    fn split_raw_discovers_alpha_segments() {
+        use std::fs;
+
+        let tmp = tempfile::tempdir().expect("tempdir");
+        fs::write(tmp.path().join("disk.aa"), b"12").expect("write");
+        fs::write(tmp.path().join("disk.ab"), b"34").expect("write");
+        let src = SplitRawSource::open(&tmp.path().join("disk.aa")).expect("open");
+        assert_eq!(src.segment_paths().len(), 2);
+        assert_eq!(compute_sha256(&src, 3).expect("hash"), {
+            let single = tmp.path().join("joined.bin");
+            fs::write(&single, b"1234").expect("write");
+            compute_sha256(&RawFileSource::open(&single).expect("open"), 3).expect("hash")
+        });
+    }
+
+    #[test]
+    fn split_raw_rejects_short_middle_segment() {
+        use std::fs;
+
+        let tmp = tempfile::tempdir().expect("tempdir");
+        fs::write(tmp.path().join("image.001"), b"abcd").expect("write");
+        fs::write(tmp.path().join("image.002"), b"ef").expect("write");
+        fs::write(tmp.path().join("image.003"), b"gh").expect("write");
+        match SplitRawSource::open(&tmp.path().join("image.001")) {
+            Err(EvidenceError::Segment(msg)) => assert!(msg.contains("image.002")),
+            Err(other) => panic!("unexpected error: {other:?}"),
+            Ok(_) => panic!("expected segment size error"),
+        }
+    }
+
+    #[test]
+    fn sniffs_virtual_disk_magic() {
+        use std::fs;
+
+        let tmp = tempfile::tempdir().expect("tempdir");
+        let mut fixed_vhd = vec![0u8; 2048];
+        fixed_vhd[1536..1544].copy_from_slice(b"conectix");
+        let cases: [(&str, Vec<u8>, Option<DiskImageFormat>); 7] = [
+            (
+                "a.vmdk",
+                b"KDMV\x01\0\0\0".to_vec(),
+                Some(DiskImageFormat::Vmdk),
+            ),
+            (
+                "b.vmdk",
+                b"# Disk DescriptorFile\nversion=1\n".to_vec(),
+                Some(DiskImageFormat::Vmdk),
+            ),
+            ("c.vhdx", b"vhdxfile".to_vec(), Some(DiskImageFormat::Vhdx)),
+            (
+                "d.qcow2",
+                b"QFI\xfb\0\0\0\x03".to_vec(),
+                Some(DiskImageFormat::Qcow2),
+            ),
+            ("e.vhd", b"conectix".to_vec(), Some(DiskImageFormat::Vhd)),
+            ("f.vhd", fixed_vhd, Some(DiskImageFormat::Vhd)),
+            ("g.dd", vec![0u8; 4096], None),
+        ];
+        for (name, data, want) in cases {
+            let path = tmp.path().join(name);
+            fs::write(&path, data).expect("write");
+            assert_eq!(sniff_disk_image(&path).expect("sniff"), want, "{name}");
+        }
+    }
+
+    #[cfg(not(feature = "ewf"))]
+    #[test]
+    fn ewf_requires_feature() {
+        use std::fs;
+
+        use crate::cli::{CliOptions, MetadataBackend};
+        let tmp = tempfile::tempdir().expect("tempdir");
+        let path = tmp.path().join("image.E01");
+        fs::write(&path, b"not ewf").expect("write");
+
+        let opts = CliOptions {
+            command: None,
+            input: Some(path),
+            output: tmp.path().to_path_buf(),
+            config_path: None,
+            gpu: false,
+            workers: 1,
+            chunk_size_mib: 1,
+            overlap_kib: None,
+            metadata_backend: MetadataBackend::Jsonl,
+            log_format: crate::cli::LogFormat::Text,
+            progress_interval_secs: 0,
+            scan_strings: false,
+            scan_utf16: false,
+            scan_urls: false,
+            no_scan_urls: false,
+            scan_emails: false,
+            no_scan_emails: false,
+            scan_phones: false,
+            no_scan_phones: false,
+            scan_ips: false,
+            no_scan_ips: false,
+            scan_credit_cards: false,
+            no_scan_credit_cards: false,
+            scan_crypto_wallets: false,
+            no_scan_crypto_wallets: false,
+            scan_secrets: false,
+            no_scan_secrets: false,
+            string_min_len: None,
+            scan_entropy: false,
+            entropy_window_bytes: None,
+            entropy_threshold: None,
+            scan_sqlite_pages: false,
+            tolerant_reads: false,
+            no_skip_constant_blocks: false,
+            start_offset: None,
+            end_offset: None,
+            ranges_file: None,
+            unallocated_only: false,
+            attribute_fs_paths: false,
+            sector_size: None,
+            yara_rules: None,
+            yara_carved: false,
+            keywords: None,
+            keyword_context: None,
+            hash_db: None,
+            known_good: Vec::new(),
+            known_bad: Vec::new(),
+            delete_known_good: false,
+            skip_duplicates: false,
+            hardlink_duplicates: false,
+            fuzzy_match: None,
+            fuzzy_threshold: None,
+            tlsh_max_distance: None,
+            bifragment: false,
+            bifragment_block_size: None,
+            bifragment_max_gap: None,
+            bifragment_max_searches: None,
+            recursive_carving: false,
+            recursive_max_depth: None,
+            recursive_max_size: None,
+            max_bytes: None,
+            max_chunks: None,
+            max_files: None,
+            max_memory_mib: None,
+            max_open_files: None,
+            checkpoint_path: None,
+            resume_from: None,
+            evidence_sha256: None,
+            compute_evidence_sha256: false,
+            verify_ewf_hashes: false,
+            disable_zip: false,
+            types: None,
+            enable_types: None,
+            dry_run: false,
+            validate_carved: false,
+            remove_invalid: false,
+            partitions: None,
+        };
+
+        let result = super::open_source(&opts);
+        match result {
+            Ok(_) => panic!("expected unsupported error"),
+            Err(super::EvidenceError::Unsupported(_)) => {}
+            Err(other) => panic!("unexpected error: {other:?}"),
+        }
+    }
+}
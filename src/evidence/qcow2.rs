@@ -0,0 +1,305 @@
+//! QEMU QCOW2 images (version 2 and 3) with uncompressed and
+//! deflate-compressed clusters. Backing files and encryption are rejected.
+
+use std::io::Read;
+use std::path::Path;
+
+use super::{
+    EvidenceError, EvidenceSource, RawFileSource, read_blocks, read_exact_at, read_u32_be,
+    read_u64_be,
+};
+
+pub(super) const MAGIC: &[u8; 4] = b"QFI\xfb";
+const HEADER_V2_LEN: usize = 72;
+const HEADER_V3_LEN: usize = 104;
+const OFFSET_MASK: u64 = 0x00FF_FFFF_FFFF_FE00;
+const L2_COMPRESSED: u64 = 1 << 62;
+const L2_ZERO: u64 = 1;
+/// Incompatible feature bits that change how clusters must be located or
+/// decoded: external data file, non-zlib compression, extended L2 entries.
+const UNSUPPORTED_INCOMPATIBLE: u64 = (1 << 2) | (1 << 3) | (1 << 4);
+
+/// A QCOW2 image presented as the guest's linear disk. Unallocated clusters
+/// read as zeros.
+pub struct Qcow2Source {
+    file: RawFileSource,
+    len: u64,
+    cluster_bits: u32,
+    l1: Vec<u64>,
+}
+
+impl Qcow2Source {
+    pub fn open(path: &Path) -> Result<Self, EvidenceError> {
+        let file = RawFileSource::open(path)?;
+        let mut header = [0u8; HEADER_V3_LEN];
+        read_exact_at(&file, 0, &mut header[..HEADER_V2_LEN])?;
+        if &header[..4] != MAGIC {
+            return Err(EvidenceError::Unsupported("not a QCOW2 image".to_string()));
+        }
+        let version = read_u32_be(&header, 4).unwrap_or(0);
+        if !matches!(version, 2 | 3) {
+            return Err(EvidenceError::Unsupported(format!(
+                "QCOW version {version} not supported"
+            )));
+        }
+        if version == 3 {
+            read_exact_at(&file, 0, &mut header)?;
+            let incompatible = read_u64_be(&header, 72).unwrap_or(0);
+            if incompatible & UNSUPPORTED_INCOMPATIBLE != 0 {
+                return Err(EvidenceError::Unsupported(format!(
+                    "QCOW2 incompatible features {incompatible:#x} not supported"
+                )));
+            }
+        }
+        if read_u64_be(&header, 8).unwrap_or(0) != 0 {
+            return Err(EvidenceError::Unsupported(
+                "QCOW2 images with a backing file are not supported".to_string(),
+            ));
+        }
+        if read_u32_be(&header, 32).unwrap_or(0) != 0 {
+            return Err(EvidenceError::Unsupported(
+                "encrypted QCOW2 images are not supported".to_string(),
+            ));
+        }
+
+        let cluster_bits = read_u32_be(&header, 20).unwrap_or(0);
+        if !(9..=21).contains(&cluster_bits) {
+            return Err(EvidenceError::Corrupt(format!(
+                "QCOW2 cluster_bits {cluster_bits} invalid"
+            )));
+        }
+        let len = read_u64_be(&header, 24).unwrap_or(0);
+        let l1_size = read_u32_be(&header, 36).unwrap_or(0) as u64;
+        let l1_offset = read_u64_be(&header, 40).unwrap_or(0);
+        let cluster_size = 1u64 << cluster_bits;
+        let l2_entries = cluster_size / 8;
+        if l1_size < len.div_ceil(cluster_size).div_ceil(l2_entries) {
+            return Err(EvidenceError::Corrupt(format!(
+                "QCOW2 L1 table has {l1_size} entries, too few for {len} bytes"
+            )));
+        }
+
+        if l1_size * 8 > file.len() {
+            return Err(EvidenceError::Corrupt(format!(
+                "QCOW2 L1 table of {l1_size} entries does not fit the image"
+            )));
+        }
+
+        let mut raw = vec![0u8; (l1_size * 8) as usize];
+        read_exact_at(&file, l1_offset, &mut raw)?;
+        let l1 = raw
+            .chunks_exact(8)
+            .map(|entry| u64::from_be_bytes(entry.try_into().expect("8-byte entry")))
+            .collect();
+        Ok(Self {
+            file,
+            len,
+            cluster_bits,
+            l1,
+        })
+    }
+
+    pub fn cluster_size(&self) -> u64 {
+        1 << self.cluster_bits
+    }
+
+    fn read_cluster(&self, cluster: u64, within: u64, out: &mut [u8]) -> Result<(), EvidenceError> {
+        let l2_entries = self.cluster_size() / 8;
+        let l2_offset = self.l1[(cluster / l2_entries) as usize] & OFFSET_MASK;
+        if l2_offset == 0 {
+            out.fill(0);
+            return Ok(());
+        }
+        let mut entry = [0u8; 8];
+        read_exact_at(
+            &self.file,
+            l2_offset + (cluster % l2_entries) * 8,
+            &mut entry,
+        )?;
+        let entry = u64::from_be_bytes(entry);
+
+        if entry & L2_COMPRESSED != 0 {
+            return self.read_compressed(cluster, entry, within, out);
+        }
+        let offset = entry & OFFSET_MASK;
+        if offset == 0 || entry & L2_ZERO != 0 {
+            out.fill(0);
+            return Ok(());
+        }
+        read_exact_at(&self.file, offset + within, out)
+    }
+
+    fn read_compressed(
+        &self,
+        cluster: u64,
+        entry: u64,
+        within: u64,
+        out: &mut [u8],
+    ) -> Result<(), EvidenceError> {
+        let cluster_size = self.cluster_size();
+        let shift = 62 - (self.cluster_bits as u64 - 8);
+        let offset = entry & ((1u64 << shift) - 1);
+        let sectors = ((entry >> shift) & ((1u64 << (self.cluster_bits - 8)) - 1)) + 1;
+        let stored = (sectors * 512 - (offset & 511)).min(self.file.len().saturating_sub(offset));
+        let mut compressed = vec![0u8; stored as usize];
+        read_exact_at(&self.file, offset, &mut compressed)?;
+
+        // The stored length is rounded up to sectors, so the stream may be
+        // followed by padding; raw deflate stops at its own end marker.
+        let mut data = Vec::with_capacity(cluster_size as usize);
+        flate2::read::DeflateDecoder::new(compressed.as_slice())
+            .take(cluster_size)
+            .read_to_end(&mut data)
+            .map_err(|err| {
+                EvidenceError::Corrupt(format!("QCOW2 cluster {cluster} inflate failed: {err}"))
+            })?;
+        let start = within as usize;
+        if data.len() < start + out.len() {
+            return Err(EvidenceError::Corrupt(format!(
+                "QCOW2 cluster {cluster} inflated to {} bytes",
+                data.len()
+            )));
+        }
+        out.copy_from_slice(&data[start..start + out.len()]);
+        Ok(())
+    }
+}
+
+impl EvidenceSource for Qcow2Source {
+    fn len(&self) -> u64 {
+        self.len
+    }
+
+    fn read_at(&self, offset: u64, buf: &mut [u8]) -> Result<usize, EvidenceError> {
+        read_blocks(
+            self.len,
+            self.cluster_size(),
+            offset,
+            buf,
+            |cluster, within, out| self.read_cluster(cluster, within, out),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use super::{L2_COMPRESSED, Qcow2Source};
+    use crate::evidence::{EvidenceError, EvidenceSource};
+
+    const CLUSTER_BITS: u32 = 12;
+    const CLUSTER: usize = 1 << CLUSTER_BITS;
+
+    enum Cluster {
+        Hole,
+        Raw(u8),
+        Compressed(u8),
+    }
+
+    fn cluster_data(fill: u8) -> Vec<u8> {
+        (0..CLUSTER).map(|idx| fill ^ (idx % 239) as u8).collect()
+    }
+
+    fn build_qcow2(clusters: &[Cluster], backing: bool) -> Vec<u8> {
+        // Layout: header | L1 | L2 | data clusters, each one cluster long.
+        let mut out = vec![0u8; 3 * CLUSTER];
+        out[..4].copy_from_slice(b"QFI\xfb");
+        out[4..8].copy_from_slice(&3u32.to_be_bytes());
+        if backing {
+            out[8..16].copy_from_slice(&512u64.to_be_bytes());
+        }
+        out[20..24].copy_from_slice(&CLUSTER_BITS.to_be_bytes());
+        out[24..32].copy_from_slice(&((clusters.len() * CLUSTER) as u64).to_be_bytes());
+        out[36..40].copy_from_slice(&1u32.to_be_bytes());
+        out[40..48].copy_from_slice(&(CLUSTER as u64).to_be_bytes());
+        out[96..100].copy_from_slice(&4u32.to_be_bytes());
+        out[100..104].copy_from_slice(&104u32.to_be_bytes());
+        let l2 = 2 * CLUSTER as u64;
+        out[CLUSTER..CLUSTER + 8].copy_from_slice(&((1u64 << 63) | l2).to_be_bytes());
+
+        for (idx, cluster) in clusters.iter().enumerate() {
+            let entry = match cluster {
+                Cluster::Hole => 0,
+                Cluster::Raw(fill) => {
+                    let offset = out.len() as u64;
+                    out.extend(cluster_data(*fill));
+                    (1u64 << 63) | offset
+                }
+                Cluster::Compressed(fill) => {
+                    let offset = out.len() as u64;
+                    let mut encoder = flate2::write::DeflateEncoder::new(
+                        Vec::new(),
+                        flate2::Compression::default(),
+                    );
+                    encoder.write_all(&cluster_data(*fill)).expect("compress");
+                    let body = encoder.finish().expect("compress");
+                    let sectors = (body.len() as u64).div_ceil(512);
+                    out.extend(body);
+                    out.resize(out.len().div_ceil(CLUSTER) * CLUSTER, 0);
+                    let shift = 62 - (CLUSTER_BITS as u64 - 8);
+                    L2_COMPRESSED | ((sectors - 1) << shift) | offset
+                }
+            };
+            let at = 2 * CLUSTER + idx * 8;
+            out[at..at + 8].copy_from_slice(&entry.to_be_bytes());
+        }
+        out
+    }
+
+    #[test]
+    fn reads_raw_compressed_and_unallocated_clusters() {
+        let tmp = tempfile::tempdir().expect("tempdir");
+        let clusters = [
+            Cluster::Raw(1),
+            Cluster::Hole,
+            Cluster::Compressed(2),
+            Cluster::Raw(3),
+        ];
+        let path = tmp.path().join("disk.qcow2");
+        std::fs::write(&path, build_qcow2(&clusters, false)).expect("write");
+
+        let src = Qcow2Source::open(&path).expect("open");
+        let want: Vec<u8> = clusters
+            .iter()
+            .flat_map(|cluster| match cluster {
+                Cluster::Hole => vec![0u8; CLUSTER],
+                Cluster::Raw(fill) | Cluster::Compressed(fill) => cluster_data(*fill),
+            })
+            .collect();
+        assert_eq!(src.len(), want.len() as u64);
+        let mut buf = vec![0xAAu8; want.len()];
+        assert_eq!(src.read_at(0, &mut buf).expect("read"), want.len());
+        assert_eq!(buf, want);
+
+        let mut window = [0u8; 500];
+        src.read_at(2 * CLUSTER as u64 + 3800, &mut window)
+            .expect("read");
+        assert_eq!(&window[..], &want[2 * CLUSTER + 3800..2 * CLUSTER + 4300]);
+    }
+
+    #[test]
+    fn oversized_l1_table_is_rejected() {
+        let tmp = tempfile::tempdir().expect("tempdir");
+        let path = tmp.path().join("truncated.qcow2");
+        let mut image = build_qcow2(&[Cluster::Raw(1)], false);
+        image[36..40].copy_from_slice(&u32::MAX.to_be_bytes());
+        image.truncate(CLUSTER);
+        std::fs::write(&path, image).expect("write");
+        assert!(matches!(
+            Qcow2Source::open(&path),
+            Err(EvidenceError::Corrupt(_))
+        ));
+    }
+
+    #[test]
+    fn backing_file_is_rejected() {
+        let tmp = tempfile::tempdir().expect("tempdir");
+        let path = tmp.path().join("overlay.qcow2");
+        std::fs::write(&path, build_qcow2(&[Cluster::Hole], true)).expect("write");
+        assert!(matches!(
+            Qcow2Source::open(&path),
+            Err(EvidenceError::Unsupported(_))
+        ));
+    }
+}
@@ -0,0 +1,210 @@
+//! Bad-sector-tolerant reads for failing media. Failed reads are retried
+//! with a shrinking block size down to single sectors; sectors that still
+//! cannot be read are zero-filled and remembered.
+
+use std::io::Write;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+use super::{EvidenceError, EvidenceSource, read_exact_at};
+
+/// Granularity at which unreadable data is given up on.
+pub const TOLERANT_SECTOR_SIZE: u64 = 512;
+
+/// Wraps another source so read errors zero-fill the affected sectors
+/// instead of failing the read.
+pub struct TolerantSource {
+    inner: Box<dyn EvidenceSource>,
+    sector_size: u64,
+    /// Unreadable `[start, end)` ranges, kept sorted and merged. Replaced
+    /// as a whole on every new range so readers can share a snapshot.
+    bad: Mutex<Arc<[(u64, u64)]>>,
+}
+
+impl TolerantSource {
+    pub fn new(inner: Box<dyn EvidenceSource>) -> Self {
+        Self {
+            inner,
+            sector_size: TOLERANT_SECTOR_SIZE,
+            bad: Mutex::new(Arc::default()),
+        }
+    }
+
+    /// Read `buf` at `offset`, bisecting on sector boundaries when the
+    /// read fails until single sectors are reached.
+    fn read_region(&self, offset: u64, buf: &mut [u8]) {
+        if read_exact_at(self.inner.as_ref(), offset, buf).is_ok() {
+            return;
+        }
+        let len = buf.len() as u64;
+        let to_boundary = self.sector_size - offset % self.sector_size;
+        if len <= to_boundary {
+            buf.fill(0);
+            self.mark_bad(offset, offset + len);
+            return;
+        }
+
+        let middle = (offset + len / 2) / self.sector_size * self.sector_size;
+        let split = if middle > offset {
+            middle - offset
+        } else {
+            to_boundary
+        };
+        let (head, tail) = buf.split_at_mut(split as usize);
+        self.read_region(offset, head);
+        self.read_region(offset + split, tail);
+    }
+
+    fn mark_bad(&self, start: u64, end: u64) {
+        let Ok(mut bad) = self.bad.lock() else {
+            return;
+        };
+        let at = bad.partition_point(|range| range.0 < start);
+        let mut ranges = bad.to_vec();
+        ranges.insert(at, (start, end));
+        // Merge with the neighbours the new range touches.
+        let mut merged: Vec<(u64, u64)> = Vec::with_capacity(ranges.len());
+        for &(s, e) in &ranges {
+            match merged.last_mut() {
+                Some(last) if last.1 >= s => last.1 = last.1.max(e),
+                _ => merged.push((s, e)),
+            }
+        }
+        *bad = merged.into();
+    }
+}
+
+impl EvidenceSource for TolerantSource {
+    fn len(&self) -> u64 {
+        self.inner.len()
+    }
+
+    fn read_at(&self, offset: u64, buf: &mut [u8]) -> Result<usize, EvidenceError> {
+        let len = self.len();
+        if offset >= len {
+            return Ok(0);
+        }
+        let n = (len - offset).min(buf.len() as u64) as usize;
+        self.read_region(offset, &mut buf[..n]);
+        Ok(n)
+    }
+
+    fn unreadable_ranges(&self) -> Arc<[(u64, u64)]> {
+        self.bad.lock().map(|bad| bad.clone()).unwrap_or_default()
+    }
+}
+
+/// Write a GNU ddrescue mapfile: `bad` ranges are marked `-`, everything
+/// else below `rescued_until` `+` and the rest `?` (not tried).
+pub fn write_ddrescue_mapfile(
+    path: &Path,
+    len: u64,
+    rescued_until: u64,
+    bad: &[(u64, u64)],
+) -> std::io::Result<()> {
+    let rescued_until = rescued_until.min(len);
+    let mut blocks: Vec<(u64, u64, char)> = Vec::new();
+    let mut push = |start: u64, end: u64, status: char| {
+        if end > start {
+            blocks.push((start, end - start, status));
+        }
+    };
+    let mut cursor = 0u64;
+    for &(start, end) in bad {
+        let start = start.clamp(cursor, len);
+        let end = end.min(len);
+        if end <= start {
+            continue;
+        }
+        push(cursor, start.min(rescued_until), '+');
+        push(cursor.max(rescued_until), start, '?');
+        push(start, end, '-');
+        cursor = end;
+    }
+    push(cursor, rescued_until, '+');
+    push(cursor.max(rescued_until), len, '?');
+
+    let mut out = std::io::BufWriter::new(std::fs::File::create(path)?);
+    writeln!(
+        out,
+        "# Mapfile. Created by SwiftBeaver {}",
+        env!("CARGO_PKG_VERSION")
+    )?;
+    writeln!(out, "# current_pos  current_status  current_pass")?;
+    writeln!(out, "0x{rescued_until:08X}     +               1")?;
+    writeln!(out, "#      pos        size  status")?;
+    for (pos, size, status) in blocks {
+        writeln!(out, "0x{pos:08X}  0x{size:08X}  {status}")?;
+    }
+    out.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{TolerantSource, write_ddrescue_mapfile};
+    use crate::evidence::{EvidenceError, EvidenceSource};
+
+    /// In-memory source whose reads fail when they touch a bad byte range.
+    struct FlakySource {
+        data: Vec<u8>,
+        bad: (u64, u64),
+    }
+
+    impl EvidenceSource for FlakySource {
+        fn len(&self) -> u64 {
+            self.data.len() as u64
+        }
+
+        fn read_at(&self, offset: u64, buf: &mut [u8]) -> Result<usize, EvidenceError> {
+            let end = offset + buf.len() as u64;
+            if offset < self.bad.1 && end > self.bad.0 {
+                return Err(EvidenceError::Io(std::io::Error::other("medium error")));
+            }
+            let start = offset as usize;
+            let n = buf.len().min(self.data.len().saturating_sub(start));
+            buf[..n].copy_from_slice(&self.data[start..start + n]);
+            Ok(n)
+        }
+    }
+
+    #[test]
+    fn zero_fills_only_unreadable_sectors() {
+        let data: Vec<u8> = (0..16 * 512).map(|idx| (idx % 251) as u8 + 1).collect();
+        let src = TolerantSource::new(Box::new(FlakySource {
+            data: data.clone(),
+            bad: (5 * 512 + 100, 6 * 512 + 10),
+        }));
+
+        let mut buf = vec![0xAAu8; data.len()];
+        assert_eq!(src.read_at(0, &mut buf).expect("read"), data.len());
+        assert_eq!(&buf[..5 * 512], &data[..5 * 512]);
+        assert!(buf[5 * 512..7 * 512].iter().all(|&byte| byte == 0));
+        assert_eq!(&buf[7 * 512..], &data[7 * 512..]);
+        assert_eq!(*src.unreadable_ranges(), [(5 * 512, 7 * 512)]);
+
+        // Unaligned reads stop at the requested bounds.
+        let mut small = [0xAAu8; 300];
+        src.read_at(5 * 512 + 400, &mut small).expect("read");
+        assert!(small.iter().all(|&byte| byte == 0));
+        assert_eq!(*src.unreadable_ranges(), [(5 * 512, 7 * 512)]);
+    }
+
+    #[test]
+    fn mapfile_marks_bad_rescued_and_untried_blocks() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let path = dir.path().join("bad.map");
+        write_ddrescue_mapfile(&path, 0x4000, 0x3000, &[(0x1000, 0x1200)]).expect("mapfile");
+        let text = std::fs::read_to_string(&path).expect("read");
+        let blocks: Vec<&str> = text.lines().filter(|line| !line.starts_with('#')).collect();
+        assert_eq!(
+            blocks,
+            vec![
+                "0x00003000     +               1",
+                "0x00000000  0x00001000  +",
+                "0x00001000  0x00000200  -",
+                "0x00001200  0x00001E00  +",
+                "0x00003000  0x00001000  ?",
+            ]
+        );
+    }
+}
@@ -0,0 +1,306 @@
+//! Microsoft VHD (Virtual PC) fixed and dynamic disks.
+
+use std::path::Path;
+
+use super::{
+    EvidenceError, EvidenceSource, RawFileSource, read_blocks, read_exact_at, read_u32_be,
+    read_u64_be,
+};
+
+pub(super) const FOOTER_COOKIE: &[u8; 8] = b"conectix";
+const DYNAMIC_COOKIE: &[u8; 8] = b"cxsparse";
+pub(super) const FOOTER_LEN: u64 = 512;
+const DYNAMIC_HEADER_LEN: usize = 1024;
+const SECTOR: u64 = 512;
+const DISK_TYPE_FIXED: u32 = 2;
+const DISK_TYPE_DYNAMIC: u32 = 3;
+const DISK_TYPE_DIFFERENCING: u32 = 4;
+const BAT_UNUSED: u32 = u32::MAX;
+
+enum Layout {
+    Fixed,
+    Dynamic {
+        block_size: u64,
+        /// Bytes of sector bitmap in front of each data block.
+        bitmap_size: u64,
+        bat: Vec<u32>,
+    },
+}
+
+/// A VHD disk presented as the guest's linear disk. Unallocated blocks of a
+/// dynamic disk read as zeros.
+pub struct VhdSource {
+    file: RawFileSource,
+    len: u64,
+    layout: Layout,
+}
+
+impl VhdSource {
+    pub fn open(path: &Path) -> Result<Self, EvidenceError> {
+        let file = RawFileSource::open(path)?;
+        let footer = read_footer(&file)?;
+        let disk_type = read_u32_be(&footer, 60).unwrap_or(0);
+        let len = read_u64_be(&footer, 48).unwrap_or(0);
+
+        let layout = match disk_type {
+            DISK_TYPE_FIXED => {
+                if file.len() < len + FOOTER_LEN {
+                    return Err(EvidenceError::Corrupt(format!(
+                        "fixed VHD is {} bytes but declares {len} bytes of data",
+                        file.len()
+                    )));
+                }
+                Layout::Fixed
+            }
+            DISK_TYPE_DYNAMIC => {
+                let header_offset = read_u64_be(&footer, 16).unwrap_or(0);
+                open_dynamic(&file, header_offset, len)?
+            }
+            DISK_TYPE_DIFFERENCING => {
+                return Err(EvidenceError::Unsupported(
+                    "differencing VHD disks are not supported".to_string(),
+                ));
+            }
+            other => {
+                return Err(EvidenceError::Corrupt(format!(
+                    "VHD disk type {other} unknown"
+                )));
+            }
+        };
+        Ok(Self { file, len, layout })
+    }
+
+    pub fn is_dynamic(&self) -> bool {
+        matches!(self.layout, Layout::Dynamic { .. })
+    }
+}
+
+impl EvidenceSource for VhdSource {
+    fn len(&self) -> u64 {
+        self.len
+    }
+
+    fn read_at(&self, offset: u64, buf: &mut [u8]) -> Result<usize, EvidenceError> {
+        match &self.layout {
+            Layout::Fixed => {
+                let end = self.len.min(offset.saturating_add(buf.len() as u64));
+                let n = end.saturating_sub(offset) as usize;
+                read_exact_at(&self.file, offset, &mut buf[..n])?;
+                Ok(n)
+            }
+            Layout::Dynamic {
+                block_size,
+                bitmap_size,
+                bat,
+            } => read_blocks(
+                self.len,
+                *block_size,
+                offset,
+                buf,
+                |block, within, out| match bat.get(block as usize).copied() {
+                    Some(sector) if sector != BAT_UNUSED => {
+                        let at = sector as u64 * SECTOR + bitmap_size + within;
+                        read_exact_at(&self.file, at, out)
+                    }
+                    _ => {
+                        out.fill(0);
+                        Ok(())
+                    }
+                },
+            ),
+        }
+    }
+}
+
+/// The footer lives in the last 512 bytes; dynamic disks also keep a copy at
+/// offset 0, which is used when the trailing one is damaged.
+fn read_footer(file: &RawFileSource) -> Result<[u8; FOOTER_LEN as usize], EvidenceError> {
+    let mut footer = [0u8; FOOTER_LEN as usize];
+    if let Some(at) = file.len().checked_sub(FOOTER_LEN) {
+        read_exact_at(file, at, &mut footer)?;
+        if footer_valid(&footer) {
+            return Ok(footer);
+        }
+    }
+    if file.len() >= FOOTER_LEN {
+        read_exact_at(file, 0, &mut footer)?;
+        if footer_valid(&footer) {
+            return Ok(footer);
+        }
+    }
+    Err(EvidenceError::Corrupt(
+        "VHD footer missing or checksum mismatch".to_string(),
+    ))
+}
+
+fn footer_valid(footer: &[u8]) -> bool {
+    &footer[..8] == FOOTER_COOKIE && read_u32_be(footer, 64) == Some(checksum(footer, 64..68))
+}
+
+/// One's complement of the byte sum, skipping the checksum field itself.
+fn checksum(data: &[u8], skip: std::ops::Range<usize>) -> u32 {
+    let sum = data
+        .iter()
+        .enumerate()
+        .filter(|(idx, _)| !skip.contains(idx))
+        .fold(0u32, |acc, (_, &byte)| acc.wrapping_add(byte as u32));
+    !sum
+}
+
+fn open_dynamic(
+    file: &RawFileSource,
+    header_offset: u64,
+    len: u64,
+) -> Result<Layout, EvidenceError> {
+    let mut header = [0u8; DYNAMIC_HEADER_LEN];
+    read_exact_at(file, header_offset, &mut header)?;
+    if &header[..8] != DYNAMIC_COOKIE {
+        return Err(EvidenceError::Corrupt(
+            "VHD dynamic header cookie missing".to_string(),
+        ));
+    }
+    if read_u32_be(&header, 36) != Some(checksum(&header, 36..40)) {
+        return Err(EvidenceError::Corrupt(
+            "VHD dynamic header checksum mismatch".to_string(),
+        ));
+    }
+    let table_offset = read_u64_be(&header, 16).unwrap_or(0);
+    let entries = read_u32_be(&header, 28).unwrap_or(0) as u64;
+    let block_size = read_u32_be(&header, 32).unwrap_or(0) as u64;
+    if block_size == 0 || !block_size.is_multiple_of(SECTOR) {
+        return Err(EvidenceError::Corrupt(format!(
+            "VHD block size {block_size} invalid"
+        )));
+    }
+    if entries < len.div_ceil(block_size) {
+        return Err(EvidenceError::Corrupt(format!(
+            "VHD block table has {entries} entries, too few for {len} bytes"
+        )));
+    }
+
+    let mut raw = vec![0u8; (entries * 4) as usize];
+    read_exact_at(file, table_offset, &mut raw)?;
+    let bat = raw
+        .chunks_exact(4)
+        .map(|entry| u32::from_be_bytes([entry[0], entry[1], entry[2], entry[3]]))
+        .collect();
+    let bitmap_bytes = (block_size / SECTOR).div_ceil(8);
+    Ok(Layout::Dynamic {
+        block_size,
+        bitmap_size: bitmap_bytes.div_ceil(SECTOR) * SECTOR,
+        bat,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        DISK_TYPE_DIFFERENCING, DISK_TYPE_DYNAMIC, DISK_TYPE_FIXED, FOOTER_LEN, SECTOR, VhdSource,
+        checksum,
+    };
+    use crate::evidence::{EvidenceError, EvidenceSource};
+
+    const BLOCK: u64 = 4096;
+
+    fn footer(disk_type: u32, size: u64, data_offset: u64) -> Vec<u8> {
+        let mut out = vec![0u8; FOOTER_LEN as usize];
+        out[..8].copy_from_slice(b"conectix");
+        out[8..12].copy_from_slice(&2u32.to_be_bytes());
+        out[12..16].copy_from_slice(&0x0001_0000u32.to_be_bytes());
+        out[16..24].copy_from_slice(&data_offset.to_be_bytes());
+        out[40..48].copy_from_slice(&size.to_be_bytes());
+        out[48..56].copy_from_slice(&size.to_be_bytes());
+        out[60..64].copy_from_slice(&disk_type.to_be_bytes());
+        let sum = checksum(&out, 64..68);
+        out[64..68].copy_from_slice(&sum.to_be_bytes());
+        out
+    }
+
+    fn dynamic_vhd(blocks: &[Option<Vec<u8>>]) -> Vec<u8> {
+        let size = blocks.len() as u64 * BLOCK;
+        let mut out = footer(DISK_TYPE_DYNAMIC, size, FOOTER_LEN);
+        let table_offset = FOOTER_LEN + 1024;
+        let mut header = vec![0u8; 1024];
+        header[..8].copy_from_slice(b"cxsparse");
+        header[8..16].copy_from_slice(&u64::MAX.to_be_bytes());
+        header[16..24].copy_from_slice(&table_offset.to_be_bytes());
+        header[24..28].copy_from_slice(&0x0001_0000u32.to_be_bytes());
+        header[28..32].copy_from_slice(&(blocks.len() as u32).to_be_bytes());
+        header[32..36].copy_from_slice(&(BLOCK as u32).to_be_bytes());
+        let sum = checksum(&header, 36..40);
+        header[36..40].copy_from_slice(&sum.to_be_bytes());
+        out.extend_from_slice(&header);
+
+        let bat_at = out.len();
+        out.resize(bat_at + SECTOR as usize, 0xFF);
+        for (idx, block) in blocks.iter().enumerate() {
+            let Some(block) = block else {
+                continue;
+            };
+            let sector = out.len() as u64 / SECTOR;
+            out.extend_from_slice(&[0xFF; SECTOR as usize]);
+            out.extend_from_slice(block);
+            out[bat_at + idx * 4..bat_at + idx * 4 + 4]
+                .copy_from_slice(&(sector as u32).to_be_bytes());
+        }
+        out.extend(footer(DISK_TYPE_DYNAMIC, size, FOOTER_LEN));
+        out
+    }
+
+    fn block(fill: u8) -> Vec<u8> {
+        (0..BLOCK).map(|idx| fill ^ (idx % 249) as u8).collect()
+    }
+
+    #[test]
+    fn fixed_vhd_strips_footer() {
+        let tmp = tempfile::tempdir().expect("tempdir");
+        let data = block(5);
+        let mut image = data.clone();
+        image.extend(footer(DISK_TYPE_FIXED, data.len() as u64, u64::MAX));
+        let path = tmp.path().join("fixed.vhd");
+        std::fs::write(&path, image).expect("write");
+
+        let src = VhdSource::open(&path).expect("open");
+        assert!(!src.is_dynamic());
+        assert_eq!(src.len(), data.len() as u64);
+        let mut buf = vec![0u8; data.len() + 100];
+        assert_eq!(src.read_at(0, &mut buf).expect("read"), data.len());
+        assert_eq!(&buf[..data.len()], &data[..]);
+    }
+
+    #[test]
+    fn dynamic_vhd_reads_blocks_and_holes() {
+        let tmp = tempfile::tempdir().expect("tempdir");
+        let blocks = vec![Some(block(1)), None, Some(block(2))];
+        let path = tmp.path().join("dynamic.vhd");
+        std::fs::write(&path, dynamic_vhd(&blocks)).expect("write");
+
+        let src = VhdSource::open(&path).expect("open");
+        assert!(src.is_dynamic());
+        let want: Vec<u8> = blocks
+            .iter()
+            .flat_map(|b| b.clone().unwrap_or_else(|| vec![0u8; BLOCK as usize]))
+            .collect();
+        let mut buf = vec![0xAAu8; want.len()];
+        assert_eq!(src.read_at(0, &mut buf).expect("read"), want.len());
+        assert_eq!(buf, want);
+
+        let mut window = [0u8; 200];
+        src.read_at(BLOCK * 2 - 100, &mut window).expect("read");
+        assert_eq!(
+            &window[..],
+            &want[(BLOCK * 2 - 100) as usize..(BLOCK * 2 + 100) as usize]
+        );
+    }
+
+    #[test]
+    fn differencing_vhd_is_rejected() {
+        let tmp = tempfile::tempdir().expect("tempdir");
+        let path = tmp.path().join("child.vhd");
+        std::fs::write(&path, footer(DISK_TYPE_DIFFERENCING, 4096, 512)).expect("write");
+        assert!(matches!(
+            VhdSource::open(&path),
+            Err(EvidenceError::Unsupported(_))
+        ));
+    }
+}
@@ -0,0 +1,406 @@
+//! Microsoft VHDX disks (fixed and dynamic, no differencing).
+//!
+//! The log is not replayed: images that were not closed cleanly are read as
+//! they are on disk, which is what the carver wants for unallocated space
+//! anyway.
+
+use std::path::Path;
+
+use super::{
+    EvidenceError, EvidenceSource, RawFileSource, read_blocks, read_exact_at, read_u16_le,
+    read_u32_le, read_u64_le,
+};
+
+pub(super) const FILE_MAGIC: &[u8; 8] = b"vhdxfile";
+const KIB_64: u64 = 64 * 1024;
+const MIB: u64 = 1024 * 1024;
+const HEADER_OFFSETS: [u64; 2] = [KIB_64, 2 * KIB_64];
+const HEADER_LEN: usize = 4096;
+const REGION_TABLE_OFFSETS: [u64; 2] = [3 * KIB_64, 4 * KIB_64];
+const REGION_TABLE_LEN: usize = KIB_64 as usize;
+
+const BAT_REGION: [u8; 16] = guid(
+    0x2DC2_7766,
+    0xF623,
+    0x4200,
+    *b"\x9D\x64\x11\x5E\x9B\xFD\x4A\x08",
+);
+const METADATA_REGION: [u8; 16] = guid(
+    0x8B7C_A206,
+    0x4790,
+    0x4B9A,
+    *b"\xB8\xFE\x57\x5F\x05\x0F\x88\x6E",
+);
+const FILE_PARAMETERS: [u8; 16] = guid(
+    0xCAA1_6737,
+    0xFA36,
+    0x4D43,
+    *b"\xB3\xB6\x33\xF0\xAA\x44\xE7\x6B",
+);
+const VIRTUAL_DISK_SIZE: [u8; 16] = guid(
+    0x2FA5_4224,
+    0xCD1B,
+    0x4876,
+    *b"\xB2\x11\x5D\xBE\xD8\x3B\xF4\xB8",
+);
+const LOGICAL_SECTOR_SIZE: [u8; 16] = guid(
+    0x8141_BF1D,
+    0xA96F,
+    0x4709,
+    *b"\xBA\x47\xF2\x33\xA8\xFA\xAB\x5F",
+);
+
+const PAYLOAD_NOT_PRESENT: u64 = 0;
+const PAYLOAD_UNDEFINED: u64 = 1;
+const PAYLOAD_ZERO: u64 = 2;
+const PAYLOAD_UNMAPPED: u64 = 3;
+const PAYLOAD_FULLY_PRESENT: u64 = 6;
+const FILE_PARAMETERS_HAS_PARENT: u32 = 0x2;
+
+/// GUIDs are stored with the first three fields little-endian.
+const fn guid(a: u32, b: u16, c: u16, d: [u8; 8]) -> [u8; 16] {
+    let a = a.to_le_bytes();
+    let b = b.to_le_bytes();
+    let c = c.to_le_bytes();
+    [
+        a[0], a[1], a[2], a[3], b[0], b[1], c[0], c[1], d[0], d[1], d[2], d[3], d[4], d[5], d[6],
+        d[7],
+    ]
+}
+
+/// A VHDX disk presented as the guest's linear disk. Blocks that are not
+/// present read as zeros.
+pub struct VhdxSource {
+    file: RawFileSource,
+    len: u64,
+    block_size: u64,
+    chunk_ratio: u64,
+    bat: Vec<u64>,
+}
+
+impl VhdxSource {
+    pub fn open(path: &Path) -> Result<Self, EvidenceError> {
+        let file = RawFileSource::open(path)?;
+        let mut magic = [0u8; 8];
+        read_exact_at(&file, 0, &mut magic)?;
+        if &magic != FILE_MAGIC {
+            return Err(EvidenceError::Unsupported("not a VHDX image".to_string()));
+        }
+        check_header(&file)?;
+
+        let regions = read_region_table(&file)?;
+        let (bat_offset, bat_len) = find_region(&regions, &BAT_REGION)?;
+        let (meta_offset, meta_len) = find_region(&regions, &METADATA_REGION)?;
+        let metadata = read_metadata(&file, meta_offset, meta_len)?;
+
+        let block_size = metadata.block_size as u64;
+        if !(MIB..=256 * MIB).contains(&block_size) || !block_size.is_power_of_two() {
+            return Err(EvidenceError::Corrupt(format!(
+                "VHDX block size {block_size} invalid"
+            )));
+        }
+        if !matches!(metadata.logical_sector_size, 512 | 4096) {
+            return Err(EvidenceError::Corrupt(format!(
+                "VHDX logical sector size {} invalid",
+                metadata.logical_sector_size
+            )));
+        }
+        if metadata.has_parent {
+            return Err(EvidenceError::Unsupported(
+                "differencing VHDX disks are not supported".to_string(),
+            ));
+        }
+        let chunk_ratio = (1u64 << 23) * metadata.logical_sector_size as u64 / block_size;
+
+        let mut raw = vec![0u8; bat_len as usize];
+        read_exact_at(&file, bat_offset, &mut raw)?;
+        let bat: Vec<u64> = raw
+            .chunks_exact(8)
+            .map(|entry| u64::from_le_bytes(entry.try_into().expect("8-byte entry")))
+            .collect();
+        let blocks = metadata.disk_size.div_ceil(block_size);
+        let needed = blocks + blocks.saturating_sub(1) / chunk_ratio;
+        if (bat.len() as u64) < needed {
+            return Err(EvidenceError::Corrupt(format!(
+                "VHDX block table has {} entries, {needed} needed",
+                bat.len()
+            )));
+        }
+
+        Ok(Self {
+            file,
+            len: metadata.disk_size,
+            block_size,
+            chunk_ratio,
+            bat,
+        })
+    }
+
+    pub fn block_size(&self) -> u64 {
+        self.block_size
+    }
+
+    fn read_block(&self, block: u64, within: u64, out: &mut [u8]) -> Result<(), EvidenceError> {
+        // Sector bitmap entries are interleaved after every `chunk_ratio`
+        // payload entries.
+        let entry = self.bat[(block + block / self.chunk_ratio) as usize];
+        match entry & 0x7 {
+            PAYLOAD_NOT_PRESENT | PAYLOAD_UNDEFINED | PAYLOAD_ZERO | PAYLOAD_UNMAPPED => {
+                out.fill(0);
+                Ok(())
+            }
+            PAYLOAD_FULLY_PRESENT => {
+                let offset = (entry >> 20) * MIB;
+                read_exact_at(&self.file, offset + within, out)
+            }
+            state => Err(EvidenceError::Corrupt(format!(
+                "VHDX block {block} has unexpected state {state}"
+            ))),
+        }
+    }
+}
+
+impl EvidenceSource for VhdxSource {
+    fn len(&self) -> u64 {
+        self.len
+    }
+
+    fn read_at(&self, offset: u64, buf: &mut [u8]) -> Result<usize, EvidenceError> {
+        read_blocks(
+            self.len,
+            self.block_size,
+            offset,
+            buf,
+            |block, within, out| self.read_block(block, within, out),
+        )
+    }
+}
+
+/// At least one of the two headers must carry a valid checksum.
+fn check_header(file: &RawFileSource) -> Result<(), EvidenceError> {
+    for offset in HEADER_OFFSETS {
+        let mut header = vec![0u8; HEADER_LEN];
+        read_exact_at(file, offset, &mut header)?;
+        if &header[..4] == b"head" && checksum_valid(&mut header) {
+            return Ok(());
+        }
+    }
+    Err(EvidenceError::Corrupt(
+        "VHDX headers missing or checksum mismatch".to_string(),
+    ))
+}
+
+fn read_region_table(file: &RawFileSource) -> Result<Vec<([u8; 16], u64, u32)>, EvidenceError> {
+    for offset in REGION_TABLE_OFFSETS {
+        let mut table = vec![0u8; REGION_TABLE_LEN];
+        read_exact_at(file, offset, &mut table)?;
+        if &table[..4] != b"regi" || !checksum_valid(&mut table) {
+            continue;
+        }
+        let count = read_u32_le(&table, 8).unwrap_or(0) as usize;
+        let entries = (0..count.min(2047))
+            .map(|idx| {
+                let at = 16 + idx * 32;
+                let id: [u8; 16] = table[at..at + 16].try_into().expect("16-byte guid");
+                let file_offset = read_u64_le(&table, at + 16).unwrap_or(0);
+                let len = read_u32_le(&table, at + 24).unwrap_or(0);
+                (id, file_offset, len)
+            })
+            .collect();
+        return Ok(entries);
+    }
+    Err(EvidenceError::Corrupt(
+        "VHDX region tables missing or checksum mismatch".to_string(),
+    ))
+}
+
+fn find_region(
+    regions: &[([u8; 16], u64, u32)],
+    id: &[u8; 16],
+) -> Result<(u64, u64), EvidenceError> {
+    regions
+        .iter()
+        .find(|(region, _, _)| region == id)
+        .map(|&(_, offset, len)| (offset, len as u64))
+        .ok_or_else(|| EvidenceError::Corrupt("VHDX required region missing".to_string()))
+}
+
+struct Metadata {
+    block_size: u32,
+    has_parent: bool,
+    disk_size: u64,
+    logical_sector_size: u32,
+}
+
+fn read_metadata(file: &RawFileSource, offset: u64, len: u64) -> Result<Metadata, EvidenceError> {
+    let mut region = vec![0u8; len as usize];
+    read_exact_at(file, offset, &mut region)?;
+    if region.get(..8) != Some(b"metadata".as_slice()) {
+        return Err(EvidenceError::Corrupt(
+            "VHDX metadata table signature missing".to_string(),
+        ));
+    }
+    let count = read_u16_le(&region, 10).unwrap_or(0) as usize;
+    let item = |id: &[u8; 16], size: usize| -> Result<&[u8], EvidenceError> {
+        (0..count)
+            .map(|idx| 32 + idx * 32)
+            .find(|&at| region.get(at..at + 16) == Some(id.as_slice()))
+            .and_then(|at| {
+                let item_offset = read_u32_le(&region, at + 16)? as usize;
+                region.get(item_offset..item_offset + size)
+            })
+            .ok_or_else(|| EvidenceError::Corrupt("VHDX metadata item missing".to_string()))
+    };
+
+    let params = item(&FILE_PARAMETERS, 8)?;
+    Ok(Metadata {
+        block_size: read_u32_le(params, 0).unwrap_or(0),
+        has_parent: read_u32_le(params, 4).unwrap_or(0) & FILE_PARAMETERS_HAS_PARENT != 0,
+        disk_size: read_u64_le(item(&VIRTUAL_DISK_SIZE, 8)?, 0).unwrap_or(0),
+        logical_sector_size: read_u32_le(item(&LOGICAL_SECTOR_SIZE, 4)?, 0).unwrap_or(0),
+    })
+}
+
+/// Verify the CRC-32C stored at bytes 4..8 (computed with that field zeroed).
+fn checksum_valid(data: &mut [u8]) -> bool {
+    let stored = read_u32_le(data, 4).unwrap_or(0);
+    data[4..8].fill(0);
+    let valid = crc32c(data) == stored;
+    data[4..8].copy_from_slice(&stored.to_le_bytes());
+    valid
+}
+
+fn crc32c(data: &[u8]) -> u32 {
+    const POLY: u32 = 0x82F6_3B78;
+    let mut crc = !0u32;
+    for &byte in data {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 {
+                (crc >> 1) ^ POLY
+            } else {
+                crc >> 1
+            };
+        }
+    }
+    !crc
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        BAT_REGION, FILE_PARAMETERS, LOGICAL_SECTOR_SIZE, METADATA_REGION, MIB, VIRTUAL_DISK_SIZE,
+        VhdxSource, crc32c,
+    };
+    use crate::evidence::{EvidenceError, EvidenceSource};
+
+    fn with_checksum(mut data: Vec<u8>) -> Vec<u8> {
+        let crc = crc32c(&data);
+        data[4..8].copy_from_slice(&crc.to_le_bytes());
+        data
+    }
+
+    /// Build a VHDX with 1 MiB blocks where `blocks[i]` gives the fill byte of
+    /// a present block, `None` leaves it not present.
+    fn build_vhdx(blocks: &[Option<u8>], has_parent: bool) -> Vec<u8> {
+        let mut out = vec![0u8; 3 * MIB as usize];
+        out[..8].copy_from_slice(b"vhdxfile");
+
+        let mut header = vec![0u8; 4096];
+        header[..4].copy_from_slice(b"head");
+        header[8..16].copy_from_slice(&1u64.to_le_bytes());
+        header[66..68].copy_from_slice(&1u16.to_le_bytes());
+        let header = with_checksum(header);
+        out[64 * 1024..64 * 1024 + 4096].copy_from_slice(&header);
+
+        let bat_offset = MIB;
+        let meta_offset = 2 * MIB;
+        let mut regions = vec![0u8; 64 * 1024];
+        regions[..4].copy_from_slice(b"regi");
+        regions[8..12].copy_from_slice(&2u32.to_le_bytes());
+        for (idx, (id, offset)) in [(BAT_REGION, bat_offset), (METADATA_REGION, meta_offset)]
+            .into_iter()
+            .enumerate()
+        {
+            let at = 16 + idx * 32;
+            regions[at..at + 16].copy_from_slice(&id);
+            regions[at + 16..at + 24].copy_from_slice(&offset.to_le_bytes());
+            regions[at + 24..at + 28].copy_from_slice(&(MIB as u32).to_le_bytes());
+            regions[at + 28..at + 32].copy_from_slice(&1u32.to_le_bytes());
+        }
+        let regions = with_checksum(regions);
+        out[192 * 1024..256 * 1024].copy_from_slice(&regions);
+
+        let meta = meta_offset as usize;
+        out[meta..meta + 8].copy_from_slice(b"metadata");
+        out[meta + 10..meta + 12].copy_from_slice(&3u16.to_le_bytes());
+        let items: [([u8; 16], Vec<u8>); 3] = [
+            (FILE_PARAMETERS, {
+                let mut v = (MIB as u32).to_le_bytes().to_vec();
+                v.extend_from_slice(&(if has_parent { 2u32 } else { 0 }).to_le_bytes());
+                v
+            }),
+            (
+                VIRTUAL_DISK_SIZE,
+                (blocks.len() as u64 * MIB).to_le_bytes().to_vec(),
+            ),
+            (LOGICAL_SECTOR_SIZE, 512u32.to_le_bytes().to_vec()),
+        ];
+        for (idx, (id, value)) in items.iter().enumerate() {
+            let at = meta + 32 + idx * 32;
+            let item_offset = 64 * 1024 + idx * 8;
+            out[at..at + 16].copy_from_slice(id);
+            out[at + 16..at + 20].copy_from_slice(&(item_offset as u32).to_le_bytes());
+            out[at + 20..at + 24].copy_from_slice(&(value.len() as u32).to_le_bytes());
+            out[meta + item_offset..meta + item_offset + value.len()].copy_from_slice(value);
+        }
+
+        for (idx, block) in blocks.iter().enumerate() {
+            let entry = match block {
+                Some(fill) => {
+                    let offset_mb = out.len() as u64 / MIB;
+                    out.extend(std::iter::repeat_n(*fill, MIB as usize));
+                    (offset_mb << 20) | 6
+                }
+                None => 0,
+            };
+            let at = bat_offset as usize + idx * 8;
+            out[at..at + 8].copy_from_slice(&entry.to_le_bytes());
+        }
+        out
+    }
+
+    #[test]
+    fn reads_present_and_missing_blocks() {
+        let tmp = tempfile::tempdir().expect("tempdir");
+        let path = tmp.path().join("disk.vhdx");
+        std::fs::write(&path, build_vhdx(&[Some(0x11), None, Some(0x33)], false)).expect("write");
+
+        let src = VhdxSource::open(&path).expect("open");
+        assert_eq!(src.len(), 3 * MIB);
+        assert_eq!(src.block_size(), MIB);
+        let mut buf = vec![0xAAu8; 64];
+        src.read_at(MIB - 32, &mut buf).expect("read");
+        assert_eq!(&buf[..32], &[0x11; 32]);
+        assert_eq!(&buf[32..], &[0u8; 32]);
+        src.read_at(3 * MIB - 64, &mut buf).expect("read");
+        assert_eq!(buf, vec![0x33; 64]);
+        assert_eq!(src.read_at(3 * MIB, &mut buf).expect("read"), 0);
+    }
+
+    #[test]
+    fn differencing_vhdx_is_rejected() {
+        let tmp = tempfile::tempdir().expect("tempdir");
+        let path = tmp.path().join("child.vhdx");
+        std::fs::write(&path, build_vhdx(&[None], true)).expect("write");
+        assert!(matches!(
+            VhdxSource::open(&path),
+            Err(EvidenceError::Unsupported(_))
+        ));
+    }
+
+    #[test]
+    fn crc32c_matches_reference() {
+        assert_eq!(crc32c(b"123456789"), 0xE306_9283);
+    }
+}
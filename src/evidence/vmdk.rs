@@ -0,0 +1,524 @@
+//! VMware VMDK virtual disks: hosted sparse extents (monolithic, split and
+//! stream-optimized) plus flat/zero extents listed by a text descriptor.
+
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+use super::{
+    EvidenceError, EvidenceSource, RawFileSource, read_blocks, read_exact_at, read_u32_le,
+    read_u64_le,
+};
+
+pub(super) const SPARSE_MAGIC: &[u8; 4] = b"KDMV";
+pub(super) const DESCRIPTOR_MAGIC: &[u8] = b"# Disk DescriptorFile";
+const SECTOR: u64 = 512;
+const HEADER_LEN: usize = 512;
+const GD_AT_END: u64 = u64::MAX;
+const FLAG_COMPRESSED: u32 = 1 << 16;
+/// Descriptors are small text files; anything larger is not one.
+const MAX_DESCRIPTOR_LEN: u64 = 1024 * 1024;
+
+struct SparseExtent {
+    file: RawFileSource,
+    grain_size: u64,
+    gtes_per_gt: u64,
+    grain_directory: Vec<u32>,
+    compressed: bool,
+}
+
+enum ExtentKind {
+    Sparse(SparseExtent),
+    Flat { file: RawFileSource, offset: u64 },
+    Zero,
+}
+
+struct Extent {
+    len: u64,
+    kind: ExtentKind,
+}
+
+/// A VMDK virtual disk presented as the guest's linear disk. Unallocated
+/// grains read as zeros.
+pub struct VmdkSource {
+    extents: Vec<Extent>,
+    /// Virtual offset of the first byte of each extent.
+    starts: Vec<u64>,
+    len: u64,
+}
+
+impl VmdkSource {
+    /// Open either a sparse extent file (`KDMV` magic) or a text descriptor
+    /// whose extents are resolved relative to the descriptor's directory.
+    pub fn open(path: &Path) -> Result<Self, EvidenceError> {
+        let file = RawFileSource::open(path)?;
+        let mut magic = [0u8; 4];
+        read_exact_at(&file, 0, &mut magic)?;
+        if &magic == SPARSE_MAGIC {
+            let (extent, capacity, descriptor) = SparseExtent::open(file)?;
+            if let Some(text) = descriptor {
+                check_no_parent(&text)?;
+            }
+            return Ok(Self::from_extents(vec![Extent {
+                len: capacity,
+                kind: ExtentKind::Sparse(extent),
+            }]));
+        }
+
+        if file.len() > MAX_DESCRIPTOR_LEN {
+            return Err(EvidenceError::Unsupported(
+                "VMDK descriptor file too large".to_string(),
+            ));
+        }
+        let mut data = vec![0u8; file.len() as usize];
+        read_exact_at(&file, 0, &mut data)?;
+        let text = String::from_utf8_lossy(&data);
+        let base = path.parent().unwrap_or_else(|| Path::new("."));
+        Self::open_descriptor(&text, base)
+    }
+
+    fn open_descriptor(text: &str, base: &Path) -> Result<Self, EvidenceError> {
+        check_no_parent(text)?;
+        let mut extents = Vec::new();
+        for line in text.lines() {
+            let Some(entry) = parse_extent_line(line) else {
+                continue;
+            };
+            let len = entry.sectors * SECTOR;
+            let kind = match entry.kind.as_str() {
+                "SPARSE" => {
+                    let path = extent_path(base, entry.file.as_deref())?;
+                    let (extent, _, _) = SparseExtent::open(RawFileSource::open(&path)?)?;
+                    ExtentKind::Sparse(extent)
+                }
+                "FLAT" | "VMFS" => {
+                    let path = extent_path(base, entry.file.as_deref())?;
+                    ExtentKind::Flat {
+                        file: RawFileSource::open(&path)?,
+                        offset: entry.offset * SECTOR,
+                    }
+                }
+                "ZERO" => ExtentKind::Zero,
+                other => {
+                    return Err(EvidenceError::Unsupported(format!(
+                        "VMDK extent type {other} not supported"
+                    )));
+                }
+            };
+            extents.push(Extent { len, kind });
+        }
+        if extents.is_empty() {
+            return Err(EvidenceError::Corrupt(
+                "VMDK descriptor lists no extents".to_string(),
+            ));
+        }
+        Ok(Self::from_extents(extents))
+    }
+
+    fn from_extents(extents: Vec<Extent>) -> Self {
+        let mut starts = Vec::with_capacity(extents.len());
+        let mut len = 0u64;
+        for extent in &extents {
+            starts.push(len);
+            len += extent.len;
+        }
+        Self {
+            extents,
+            starts,
+            len,
+        }
+    }
+
+    pub fn extent_count(&self) -> usize {
+        self.extents.len()
+    }
+}
+
+impl EvidenceSource for VmdkSource {
+    fn len(&self) -> u64 {
+        self.len
+    }
+
+    fn read_at(&self, offset: u64, buf: &mut [u8]) -> Result<usize, EvidenceError> {
+        let end = self.len.min(offset.saturating_add(buf.len() as u64));
+        let mut pos = offset;
+        while pos < end {
+            let idx = self.starts.partition_point(|&start| start <= pos) - 1;
+            let extent = &self.extents[idx];
+            let local = pos - self.starts[idx];
+            let n = (extent.len - local).min(end - pos) as usize;
+            let start = (pos - offset) as usize;
+            let out = &mut buf[start..start + n];
+            match &extent.kind {
+                ExtentKind::Sparse(sparse) => {
+                    read_blocks(
+                        extent.len,
+                        sparse.grain_size,
+                        local,
+                        out,
+                        |grain, within, out| sparse.read_grain(grain, within, out),
+                    )?;
+                }
+                ExtentKind::Flat { file, offset } => read_exact_at(file, offset + local, out)?,
+                ExtentKind::Zero => out.fill(0),
+            }
+            pos += n as u64;
+        }
+        Ok(end.saturating_sub(offset) as usize)
+    }
+}
+
+impl SparseExtent {
+    /// Parse a hosted sparse extent header. Returns the extent, its capacity
+    /// in bytes and the embedded descriptor text, if any.
+    fn open(file: RawFileSource) -> Result<(Self, u64, Option<String>), EvidenceError> {
+        let mut header = [0u8; HEADER_LEN];
+        read_exact_at(&file, 0, &mut header)?;
+        if &header[..4] != SPARSE_MAGIC {
+            return Err(EvidenceError::Corrupt(
+                "VMDK sparse extent magic missing".to_string(),
+            ));
+        }
+        // Stream-optimized extents keep the real header in a footer just
+        // before the end-of-stream marker.
+        if read_u64_le(&header, 56) == Some(GD_AT_END) {
+            let footer_at = file.len().checked_sub(2 * SECTOR).ok_or_else(|| {
+                EvidenceError::Corrupt("VMDK stream-optimized footer missing".to_string())
+            })?;
+            read_exact_at(&file, footer_at, &mut header)?;
+            if &header[..4] != SPARSE_MAGIC || read_u64_le(&header, 56) == Some(GD_AT_END) {
+                return Err(EvidenceError::Corrupt(
+                    "VMDK stream-optimized footer invalid".to_string(),
+                ));
+            }
+        }
+
+        let flags = read_u32_le(&header, 8).unwrap_or(0);
+        let capacity = read_u64_le(&header, 12).unwrap_or(0);
+        let grain_sectors = read_u64_le(&header, 20).unwrap_or(0);
+        let descriptor_offset = read_u64_le(&header, 28).unwrap_or(0);
+        let descriptor_size = read_u64_le(&header, 36).unwrap_or(0);
+        let gtes_per_gt = read_u32_le(&header, 44).unwrap_or(0) as u64;
+        let gd_offset = read_u64_le(&header, 56).unwrap_or(0);
+        let compress_algorithm = u16::from_le_bytes([header[77], header[78]]);
+
+        if grain_sectors == 0 || !grain_sectors.is_power_of_two() || gtes_per_gt == 0 {
+            return Err(EvidenceError::Corrupt(format!(
+                "VMDK grain geometry invalid (grain {grain_sectors} sectors, {gtes_per_gt} entries per table)"
+            )));
+        }
+        let compressed = flags & FLAG_COMPRESSED != 0;
+        if compressed && compress_algorithm != 1 {
+            return Err(EvidenceError::Unsupported(format!(
+                "VMDK compression algorithm {compress_algorithm} not supported"
+            )));
+        }
+
+        let grain_size = grain_sectors * SECTOR;
+        let grains = capacity.div_ceil(grain_sectors);
+        let tables = grains.div_ceil(gtes_per_gt);
+        let directory_len = tables
+            .checked_mul(4)
+            .filter(|&len| len <= file.len())
+            .ok_or_else(|| {
+                EvidenceError::Corrupt(format!(
+                    "VMDK grain directory of {tables} tables does not fit the extent"
+                ))
+            })?;
+        let mut raw = vec![0u8; directory_len as usize];
+        read_exact_at(&file, gd_offset * SECTOR, &mut raw)?;
+        let grain_directory = raw
+            .chunks_exact(4)
+            .map(|entry| u32::from_le_bytes([entry[0], entry[1], entry[2], entry[3]]))
+            .collect();
+
+        let descriptor = if descriptor_offset != 0 && descriptor_size != 0 {
+            let len = (descriptor_size * SECTOR).min(MAX_DESCRIPTOR_LEN) as usize;
+            let mut text = vec![0u8; len];
+            read_exact_at(&file, descriptor_offset * SECTOR, &mut text)?;
+            let end = text.iter().position(|&b| b == 0).unwrap_or(text.len());
+            Some(String::from_utf8_lossy(&text[..end]).to_string())
+        } else {
+            None
+        };
+
+        Ok((
+            Self {
+                file,
+                grain_size,
+                gtes_per_gt,
+                grain_directory,
+                compressed,
+            },
+            capacity * SECTOR,
+            descriptor,
+        ))
+    }
+
+    fn read_grain(&self, grain: u64, within: u64, out: &mut [u8]) -> Result<(), EvidenceError> {
+        let table = self
+            .grain_directory
+            .get((grain / self.gtes_per_gt) as usize)
+            .copied()
+            .unwrap_or(0);
+        if table == 0 {
+            out.fill(0);
+            return Ok(());
+        }
+        let mut entry = [0u8; 4];
+        let entry_at = table as u64 * SECTOR + (grain % self.gtes_per_gt) * 4;
+        read_exact_at(&self.file, entry_at, &mut entry)?;
+        let sector = u32::from_le_bytes(entry) as u64;
+        // 0 = never written, 1 = explicitly zeroed grain.
+        if sector <= 1 {
+            out.fill(0);
+            return Ok(());
+        }
+
+        if !self.compressed {
+            return read_exact_at(&self.file, sector * SECTOR + within, out);
+        }
+        // Compressed grain: u64 LBA, u32 size, then zlib data.
+        let mut marker = [0u8; 12];
+        read_exact_at(&self.file, sector * SECTOR, &mut marker)?;
+        let size = u32::from_le_bytes([marker[8], marker[9], marker[10], marker[11]]) as u64;
+        if size == 0 || size > self.grain_size * 2 {
+            return Err(EvidenceError::Corrupt(format!(
+                "VMDK grain {grain} has invalid compressed size {size}"
+            )));
+        }
+        let mut compressed = vec![0u8; size as usize];
+        read_exact_at(&self.file, sector * SECTOR + 12, &mut compressed)?;
+        let mut data = Vec::with_capacity(self.grain_size as usize);
+        flate2::read::ZlibDecoder::new(compressed.as_slice())
+            .take(self.grain_size)
+            .read_to_end(&mut data)
+            .map_err(|err| {
+                EvidenceError::Corrupt(format!("VMDK grain {grain} inflate failed: {err}"))
+            })?;
+        data.resize(self.grain_size as usize, 0);
+        let start = within as usize;
+        out.copy_from_slice(&data[start..start + out.len()]);
+        Ok(())
+    }
+}
+
+struct ExtentLine {
+    sectors: u64,
+    kind: String,
+    file: Option<String>,
+    offset: u64,
+}
+
+/// Parse `RW 4192256 SPARSE "disk-s001.vmdk"` / `RW 2048 FLAT "disk-flat.vmdk" 0`.
+fn parse_extent_line(line: &str) -> Option<ExtentLine> {
+    let line = line.trim();
+    let mut tokens = line.split_whitespace();
+    if !matches!(tokens.next()?, "RW" | "RDONLY" | "NOACCESS") {
+        return None;
+    }
+    let sectors = tokens.next()?.parse().ok()?;
+    let kind = tokens.next()?.to_ascii_uppercase();
+    let (file, rest) = match (line.find('"'), line.rfind('"')) {
+        (Some(open), Some(close)) if close > open => (
+            Some(line[open + 1..close].to_string()),
+            line[close + 1..].trim(),
+        ),
+        _ => (None, ""),
+    };
+    let offset = rest
+        .split_whitespace()
+        .next()
+        .and_then(|value| value.parse().ok())
+        .unwrap_or(0);
+    Some(ExtentLine {
+        sectors,
+        kind,
+        file,
+        offset,
+    })
+}
+
+fn extent_path(base: &Path, file: Option<&str>) -> Result<PathBuf, EvidenceError> {
+    let file = file.ok_or_else(|| {
+        EvidenceError::Corrupt("VMDK extent line without a file name".to_string())
+    })?;
+    Ok(base.join(file))
+}
+
+/// Delta disks need their parent to resolve unallocated grains.
+fn check_no_parent(descriptor: &str) -> Result<(), EvidenceError> {
+    let has_parent = descriptor.lines().any(|line| {
+        line.trim()
+            .strip_prefix("parentCID")
+            .and_then(|rest| rest.trim_start().strip_prefix('='))
+            .is_some_and(|value| !value.trim().eq_ignore_ascii_case("ffffffff"))
+    });
+    if has_parent {
+        return Err(EvidenceError::Unsupported(
+            "VMDK delta disks with a parent are not supported".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use super::{SECTOR, VmdkSource};
+    use crate::evidence::{EvidenceError, EvidenceSource};
+
+    const GRAIN_SECTORS: u64 = 8;
+    const GTES: u64 = 4;
+
+    /// Hosted sparse extent with `grains` grains where `data[i]` is `Some` for
+    /// allocated grains.
+    fn sparse_extent(data: &[Option<Vec<u8>>], compressed: bool, descriptor: &str) -> Vec<u8> {
+        let grain_size = (GRAIN_SECTORS * SECTOR) as usize;
+        let capacity = data.len() as u64 * GRAIN_SECTORS;
+        let tables = (data.len() as u64).div_ceil(GTES);
+        let descriptor_sector = 1u64;
+        let descriptor_sectors = 2u64;
+        let gd_sector = descriptor_sector + descriptor_sectors;
+        let gt_sector = gd_sector + 1;
+        // One sector per grain table keeps every table sector-aligned.
+        let first_grain_sector = gt_sector + tables;
+
+        let mut out = vec![0u8; (first_grain_sector * SECTOR) as usize];
+        out[..4].copy_from_slice(b"KDMV");
+        out[4..8].copy_from_slice(&1u32.to_le_bytes());
+        let flags: u32 = if compressed { (1 << 16) | (1 << 17) } else { 0 };
+        out[8..12].copy_from_slice(&flags.to_le_bytes());
+        out[12..20].copy_from_slice(&capacity.to_le_bytes());
+        out[20..28].copy_from_slice(&GRAIN_SECTORS.to_le_bytes());
+        out[28..36].copy_from_slice(&descriptor_sector.to_le_bytes());
+        out[36..44].copy_from_slice(&descriptor_sectors.to_le_bytes());
+        out[44..48].copy_from_slice(&(GTES as u32).to_le_bytes());
+        out[56..64].copy_from_slice(&gd_sector.to_le_bytes());
+        out[77..79].copy_from_slice(&(compressed as u16).to_le_bytes());
+        let desc_at = (descriptor_sector * SECTOR) as usize;
+        out[desc_at..desc_at + descriptor.len()].copy_from_slice(descriptor.as_bytes());
+
+        for table in 0..tables {
+            let at = (gd_sector * SECTOR + table * 4) as usize;
+            out[at..at + 4].copy_from_slice(&((gt_sector + table) as u32).to_le_bytes());
+        }
+
+        for (idx, grain) in data.iter().enumerate() {
+            let Some(grain) = grain else {
+                continue;
+            };
+            assert_eq!(grain.len(), grain_size);
+            let sector = out.len() as u64 / SECTOR;
+            if compressed {
+                let mut encoder =
+                    flate2::write::ZlibEncoder::new(Vec::new(), flate2::Compression::default());
+                encoder.write_all(grain).expect("compress");
+                let body = encoder.finish().expect("compress");
+                out.extend_from_slice(&(idx as u64 * GRAIN_SECTORS).to_le_bytes());
+                out.extend_from_slice(&(body.len() as u32).to_le_bytes());
+                out.extend_from_slice(&body);
+                out.resize(out.len().div_ceil(SECTOR as usize) * SECTOR as usize, 0);
+            } else {
+                out.extend_from_slice(grain);
+            }
+            let table = gt_sector + idx as u64 / GTES;
+            let at = (table * SECTOR) as usize + (idx as u64 % GTES) as usize * 4;
+            out[at..at + 4].copy_from_slice(&(sector as u32).to_le_bytes());
+        }
+        out
+    }
+
+    fn grain(fill: u8) -> Vec<u8> {
+        (0..GRAIN_SECTORS * SECTOR)
+            .map(|idx| fill ^ (idx % 253) as u8)
+            .collect()
+    }
+
+    fn expected(data: &[Option<Vec<u8>>]) -> Vec<u8> {
+        data.iter()
+            .flat_map(|grain| {
+                grain
+                    .clone()
+                    .unwrap_or_else(|| vec![0u8; (GRAIN_SECTORS * SECTOR) as usize])
+            })
+            .collect()
+    }
+
+    #[test]
+    fn monolithic_sparse_reads_grains_and_holes() {
+        let tmp = tempfile::tempdir().expect("tempdir");
+        for compressed in [false, true] {
+            let data = vec![Some(grain(1)), None, Some(grain(7)), None, Some(grain(9))];
+            let path = tmp.path().join(format!("disk-{compressed}.vmdk"));
+            std::fs::write(&path, sparse_extent(&data, compressed, "")).expect("write");
+
+            let src = VmdkSource::open(&path).expect("open");
+            let want = expected(&data);
+            assert_eq!(src.len(), want.len() as u64);
+            let mut buf = vec![0xAAu8; want.len()];
+            assert_eq!(src.read_at(0, &mut buf).expect("read"), want.len());
+            assert_eq!(buf, want);
+
+            let mut window = [0u8; 300];
+            src.read_at(4000, &mut window).expect("read");
+            assert_eq!(&window[..], &want[4000..4300]);
+        }
+    }
+
+    #[test]
+    fn descriptor_joins_sparse_flat_and_zero_extents() {
+        let tmp = tempfile::tempdir().expect("tempdir");
+        let sparse = vec![None, Some(grain(3))];
+        std::fs::write(
+            tmp.path().join("disk-s001.vmdk"),
+            sparse_extent(&sparse, false, ""),
+        )
+        .expect("write");
+        let flat: Vec<u8> = (0..2048u32).map(|v| (v % 241) as u8).collect();
+        std::fs::write(tmp.path().join("disk-flat.vmdk"), &flat).expect("write");
+        let descriptor = "# Disk DescriptorFile\nversion=1\nCID=fffffffe\nparentCID=ffffffff\n\n\
+            # Extent description\n\
+            RW 16 SPARSE \"disk-s001.vmdk\"\n\
+            RW 2 FLAT \"disk-flat.vmdk\" 1\n\
+            RW 4 ZERO\n";
+        let path = tmp.path().join("disk.vmdk");
+        std::fs::write(&path, descriptor).expect("write");
+
+        let src = VmdkSource::open(&path).expect("open");
+        assert_eq!(src.extent_count(), 3);
+        let mut want = expected(&sparse);
+        want.extend_from_slice(&flat[512..1536]);
+        want.extend_from_slice(&[0u8; 2048]);
+        assert_eq!(src.len(), want.len() as u64);
+        let mut buf = vec![0xAAu8; want.len()];
+        src.read_at(0, &mut buf).expect("read");
+        assert_eq!(buf, want);
+    }
+
+    #[test]
+    fn oversized_grain_directory_is_rejected() {
+        let tmp = tempfile::tempdir().expect("tempdir");
+        let path = tmp.path().join("truncated.vmdk");
+        let mut image = sparse_extent(&[Some(grain(1))], false, "");
+        image[12..20].copy_from_slice(&(1u64 << 50).to_le_bytes());
+        image.truncate(SECTOR as usize);
+        std::fs::write(&path, image).expect("write");
+        assert!(matches!(
+            VmdkSource::open(&path),
+            Err(EvidenceError::Corrupt(_))
+        ));
+    }
+
+    #[test]
+    fn delta_disk_is_rejected() {
+        let tmp = tempfile::tempdir().expect("tempdir");
+        let path = tmp.path().join("child.vmdk");
+        let descriptor = "# Disk DescriptorFile\nparentCID=12345678\n";
+        std::fs::write(&path, sparse_extent(&[Some(grain(1))], false, descriptor)).expect("write");
+        assert!(matches!(
+            VmdkSource::open(&path),
+            Err(EvidenceError::Unsupported(_))
+        ));
+    }
+}
@@ -0,0 +1,545 @@
+//! ext2/3/4 allocation from the per-group block bitmaps. Groups flagged
+//! `BLOCK_UNINIT` have no bitmap on disk and are treated as free. The file
+//! index walks directories from the root inode and maps extent trees (ext4)
+//! or direct/indirect block maps (ext2/3).
+
+use std::collections::{HashSet, VecDeque};
+
+use super::{
+    FileIndex, FilesystemKind, Volume, bitmap_ranges, join_path, le_u16, le_u32, push_range,
+    read_full, read_vec,
+};
+use crate::evidence::{EvidenceError, EvidenceSource};
+
+const SUPERBLOCK_OFFSET: u64 = 1024;
+const SUPERBLOCK_SIZE: usize = 1024;
+const EXT_MAGIC: u16 = 0xEF53;
+const INCOMPAT_64BIT: u32 = 0x80;
+const BG_BLOCK_UNINIT: u16 = 0x2;
+const MIN_DESC_SIZE: usize = 32;
+const ROOT_INODE: u64 = 2;
+const GOOD_OLD_INODE_SIZE: u64 = 128;
+const MODE_TYPE_MASK: u16 = 0xF000;
+const MODE_DIR: u16 = 0x4000;
+const MODE_FILE: u16 = 0x8000;
+const INODE_FLAG_EXTENTS: u32 = 0x80000;
+const EXTENT_MAGIC: u16 = 0xF30A;
+/// Extent lengths above this mark uninitialized (preallocated) extents.
+const EXTENT_INIT_MAX_LEN: u64 = 32768;
+/// Guards against extent-tree and indirect-block loops.
+const MAX_TREE_DEPTH: usize = 8;
+/// Directories larger than this are not read into memory.
+const MAX_DIR_BYTES: u64 = 64 * 1024 * 1024;
+
+/// Geometry from the ext superblock.
+struct Superblock {
+    block_size: u64,
+    first_data_block: u64,
+    blocks_per_group: u64,
+    blocks_count: u64,
+    desc_size: usize,
+    inodes_per_group: u64,
+    inode_size: u64,
+}
+
+impl Superblock {
+    fn read(
+        evidence: &dyn EvidenceSource,
+        start: u64,
+        len: u64,
+    ) -> Result<Option<Self>, EvidenceError> {
+        let mut sb = [0u8; SUPERBLOCK_SIZE];
+        if read_full(evidence, start + SUPERBLOCK_OFFSET, &mut sb)? < SUPERBLOCK_SIZE
+            || le_u16(&sb[56..58]) != EXT_MAGIC
+        {
+            return Ok(None);
+        }
+
+        let log_block_size = le_u32(&sb[24..28]);
+        if log_block_size > 6 {
+            return Ok(None);
+        }
+        let block_size = 1024u64 << log_block_size;
+        let first_data_block = le_u32(&sb[20..24]) as u64;
+        let blocks_per_group = le_u32(&sb[32..36]) as u64;
+        let is_64bit = le_u32(&sb[96..100]) & INCOMPAT_64BIT != 0;
+        let mut blocks_count = le_u32(&sb[4..8]) as u64;
+        if is_64bit {
+            blocks_count |= (le_u32(&sb[336..340]) as u64) << 32;
+        }
+        let desc_size = if is_64bit {
+            (le_u16(&sb[254..256]) as usize).max(MIN_DESC_SIZE)
+        } else {
+            MIN_DESC_SIZE
+        };
+        let inode_size = if le_u32(&sb[76..80]) == 0 {
+            GOOD_OLD_INODE_SIZE
+        } else {
+            le_u16(&sb[88..90]) as u64
+        };
+        blocks_count = blocks_count.min(len / block_size);
+        if blocks_per_group == 0
+            || blocks_per_group > block_size * 8
+            || first_data_block >= blocks_count
+        {
+            return Ok(None);
+        }
+        Ok(Some(Self {
+            block_size,
+            first_data_block,
+            blocks_per_group,
+            blocks_count,
+            desc_size,
+            inodes_per_group: le_u32(&sb[40..44]) as u64,
+            inode_size,
+        }))
+    }
+
+    fn group_count(&self) -> u64 {
+        (self.blocks_count - self.first_data_block).div_ceil(self.blocks_per_group)
+    }
+
+    fn read_descriptors(
+        &self,
+        evidence: &dyn EvidenceSource,
+        start: u64,
+    ) -> Result<Vec<u8>, EvidenceError> {
+        read_vec(
+            evidence,
+            start + (self.first_data_block + 1) * self.block_size,
+            self.group_count() as usize * self.desc_size,
+        )
+    }
+
+    fn descriptor<'a>(&self, descriptors: &'a [u8], group: u64) -> &'a [u8] {
+        let at = group as usize * self.desc_size;
+        &descriptors[at..at + self.desc_size]
+    }
+}
+
+pub(super) fn probe(
+    evidence: &dyn EvidenceSource,
+    start: u64,
+    len: u64,
+) -> Result<Option<Volume>, EvidenceError> {
+    let Some(sb) = Superblock::read(evidence, start, len)? else {
+        return Ok(None);
+    };
+    let descriptors = sb.read_descriptors(evidence, start)?;
+
+    let mut allocated = Vec::new();
+    // Boot block ahead of the first group (1 KiB block file systems).
+    push_range(
+        &mut allocated,
+        start,
+        start + sb.first_data_block * sb.block_size,
+    );
+    for group in 0..sb.group_count() {
+        let desc = sb.descriptor(&descriptors, group);
+        let flags = le_u16(&desc[18..20]);
+        if flags & BG_BLOCK_UNINIT != 0 {
+            continue;
+        }
+        let mut bitmap_block = le_u32(&desc[0..4]) as u64;
+        if sb.desc_size >= 64 {
+            bitmap_block |= (le_u32(&desc[32..36]) as u64) << 32;
+        }
+        if bitmap_block >= sb.blocks_count {
+            return Err(EvidenceError::Corrupt(format!(
+                "ext group {group} block bitmap {bitmap_block} beyond volume"
+            )));
+        }
+        let group_first = sb.first_data_block + group * sb.blocks_per_group;
+        let group_blocks = sb.blocks_per_group.min(sb.blocks_count - group_first);
+        let bitmap = read_vec(
+            evidence,
+            start + bitmap_block * sb.block_size,
+            group_blocks.div_ceil(8) as usize,
+        )?;
+        bitmap_ranges(
+            &bitmap,
+            group_blocks,
+            sb.block_size,
+            start + group_first * sb.block_size,
+            &mut allocated,
+        );
+    }
+
+    Ok(Some(Volume {
+        kind: FilesystemKind::Ext,
+        start,
+        len: sb.blocks_count * sb.block_size,
+        cluster_size: sb.block_size,
+        allocated,
+    }))
+}
+
+/// Index every regular file reachable from the root directory. Returns
+/// `false` when the volume is not ext.
+pub(super) fn index(
+    evidence: &dyn EvidenceSource,
+    start: u64,
+    len: u64,
+    index: &mut FileIndex,
+) -> Result<bool, EvidenceError> {
+    let Some(sb) = Superblock::read(evidence, start, len)? else {
+        return Ok(false);
+    };
+    if sb.inodes_per_group == 0 || sb.inode_size < GOOD_OLD_INODE_SIZE {
+        return Ok(false);
+    }
+    let reader = InodeReader {
+        evidence,
+        start,
+        descriptors: sb.read_descriptors(evidence, start)?,
+        sb,
+    };
+
+    let mut visited = HashSet::from([ROOT_INODE]);
+    let mut pending = VecDeque::from([(String::new(), ROOT_INODE)]);
+    while let Some((dir_path, dir_inode)) = pending.pop_front() {
+        let inode = reader.inode(dir_inode)?;
+        if inode.size > MAX_DIR_BYTES {
+            continue;
+        }
+        let mut contents = Vec::new();
+        for (file_block, block, count) in reader.block_runs(&inode)? {
+            let Some(want) = inode
+                .size
+                .checked_sub(file_block * reader.sb.block_size)
+                .filter(|want| *want > 0)
+            else {
+                continue;
+            };
+            let bytes = (count * reader.sb.block_size).min(want);
+            let at = file_block * reader.sb.block_size;
+            if contents.len() < (at + bytes) as usize {
+                contents.resize((at + bytes) as usize, 0);
+            }
+            let dest = &mut contents[at as usize..(at + bytes) as usize];
+            read_full(evidence, start + block * reader.sb.block_size, dest)?;
+        }
+
+        for (child, name) in dir_entries(&contents) {
+            if name == "." || name == ".." || !visited.insert(child) {
+                continue;
+            }
+            let path = join_path(&dir_path, &name);
+            let Ok(inode) = reader.inode(child) else {
+                continue;
+            };
+            match inode.mode & MODE_TYPE_MASK {
+                MODE_DIR => pending.push_back((path, child)),
+                MODE_FILE => {
+                    let file = index.add_file(path, child);
+                    let Ok(runs) = reader.block_runs(&inode) else {
+                        continue;
+                    };
+                    for (file_block, block, count) in runs {
+                        let file_offset = file_block * reader.sb.block_size;
+                        let bytes = (count * reader.sb.block_size)
+                            .min(inode.size.saturating_sub(file_offset));
+                        let at = start + block * reader.sb.block_size;
+                        index.add_extent(file, at, at + bytes, file_offset);
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+    Ok(true)
+}
+
+struct Inode {
+    mode: u16,
+    size: u64,
+    flags: u32,
+    block: [u8; 60],
+}
+
+struct InodeReader<'a> {
+    evidence: &'a dyn EvidenceSource,
+    start: u64,
+    sb: Superblock,
+    descriptors: Vec<u8>,
+}
+
+impl InodeReader<'_> {
+    fn inode(&self, number: u64) -> Result<Inode, EvidenceError> {
+        let Some(group) = number
+            .checked_sub(1)
+            .map(|idx| idx / self.sb.inodes_per_group)
+            .filter(|group| *group < self.sb.group_count())
+        else {
+            return Err(corrupt(format!("inode {number} out of range")));
+        };
+        let desc = self.sb.descriptor(&self.descriptors, group);
+        let mut table = le_u32(&desc[8..12]) as u64;
+        if self.sb.desc_size >= 64 {
+            table |= (le_u32(&desc[40..44]) as u64) << 32;
+        }
+        let slot = (number - 1) % self.sb.inodes_per_group;
+        let raw = read_vec(
+            self.evidence,
+            self.start + table * self.sb.block_size + slot * self.sb.inode_size,
+            GOOD_OLD_INODE_SIZE as usize,
+        )?;
+        let mut block = [0u8; 60];
+        block.copy_from_slice(&raw[40..100]);
+        Ok(Inode {
+            mode: le_u16(&raw[0..2]),
+            size: le_u32(&raw[4..8]) as u64 | (le_u32(&raw[108..112]) as u64) << 32,
+            flags: le_u32(&raw[32..36]),
+            block,
+        })
+    }
+
+    /// `(file_block, volume_block, block_count)` runs holding the inode's
+    /// data; holes and uninitialized extents are left out.
+    fn block_runs(&self, inode: &Inode) -> Result<Vec<(u64, u64, u64)>, EvidenceError> {
+        let mut runs = Vec::new();
+        if inode.flags & INODE_FLAG_EXTENTS != 0 {
+            self.extent_node(&inode.block, 0, &mut runs)?;
+        } else {
+            let mut file_block = 0u64;
+            for idx in 0..12 {
+                let block = le_u32(&inode.block[idx * 4..idx * 4 + 4]) as u64;
+                self.push_block(&mut runs, file_block, block);
+                file_block += 1;
+            }
+            for (idx, depth) in [(12usize, 1usize), (13, 2), (14, 3)] {
+                let block = le_u32(&inode.block[idx * 4..idx * 4 + 4]) as u64;
+                self.indirect(block, depth, &mut file_block, &mut runs)?;
+            }
+        }
+        Ok(runs)
+    }
+
+    fn extent_node(
+        &self,
+        node: &[u8],
+        depth: usize,
+        runs: &mut Vec<(u64, u64, u64)>,
+    ) -> Result<(), EvidenceError> {
+        if node.len() < 12 || le_u16(&node[0..2]) != EXTENT_MAGIC || depth > MAX_TREE_DEPTH {
+            return Err(corrupt("bad extent header".to_string()));
+        }
+        let entries = le_u16(&node[2..4]) as usize;
+        let leaf = le_u16(&node[6..8]) == 0;
+        for entry in node[12..].chunks_exact(12).take(entries) {
+            let file_block = le_u32(&entry[0..4]) as u64;
+            if leaf {
+                let len = le_u16(&entry[4..6]) as u64;
+                if len > EXTENT_INIT_MAX_LEN {
+                    continue;
+                }
+                let block = (le_u16(&entry[6..8]) as u64) << 32 | le_u32(&entry[8..12]) as u64;
+                if block + len <= self.sb.blocks_count {
+                    runs.push((file_block, block, len));
+                }
+            } else {
+                let block = (le_u16(&entry[8..10]) as u64) << 32 | le_u32(&entry[4..8]) as u64;
+                if block >= self.sb.blocks_count {
+                    continue;
+                }
+                let child = read_vec(
+                    self.evidence,
+                    self.start + block * self.sb.block_size,
+                    self.sb.block_size as usize,
+                )?;
+                self.extent_node(&child, depth + 1, runs)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn indirect(
+        &self,
+        block: u64,
+        depth: usize,
+        file_block: &mut u64,
+        runs: &mut Vec<(u64, u64, u64)>,
+    ) -> Result<(), EvidenceError> {
+        let per_block = self.sb.block_size / 4;
+        if block == 0 || block >= self.sb.blocks_count {
+            *file_block += per_block.pow(depth as u32);
+            return Ok(());
+        }
+        let table = read_vec(
+            self.evidence,
+            self.start + block * self.sb.block_size,
+            self.sb.block_size as usize,
+        )?;
+        for pointer in table.chunks_exact(4) {
+            let pointer = le_u32(pointer) as u64;
+            if depth == 1 {
+                self.push_block(runs, *file_block, pointer);
+                *file_block += 1;
+            } else {
+                self.indirect(pointer, depth - 1, file_block, runs)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Append one mapped block, extending the previous run when both the
+    /// file and volume positions follow on.
+    fn push_block(&self, runs: &mut Vec<(u64, u64, u64)>, file_block: u64, block: u64) {
+        if block == 0 || block >= self.sb.blocks_count {
+            return;
+        }
+        match runs.last_mut() {
+            Some(last) if last.0 + last.2 == file_block && last.1 + last.2 == block => last.2 += 1,
+            _ => runs.push((file_block, block, 1)),
+        }
+    }
+}
+
+/// `(inode, name)` of each live entry in a directory's data.
+fn dir_entries(data: &[u8]) -> Vec<(u64, String)> {
+    let mut out = Vec::new();
+    let mut at = 0usize;
+    while at + 8 <= data.len() {
+        let inode = le_u32(&data[at..at + 4]) as u64;
+        let rec_len = le_u16(&data[at + 4..at + 6]) as usize;
+        let name_len = data[at + 6] as usize;
+        if rec_len < 8 || at + rec_len > data.len() {
+            break;
+        }
+        if inode != 0
+            && let Some(name) = data.get(at + 8..at + 8 + name_len)
+        {
+            out.push((inode, String::from_utf8_lossy(name).into_owned()));
+        }
+        at += rec_len;
+    }
+    out
+}
+
+fn corrupt(what: String) -> EvidenceError {
+    EvidenceError::Corrupt(format!("ext: {what}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct SliceEvidence {
+        data: Vec<u8>,
+    }
+
+    impl EvidenceSource for SliceEvidence {
+        fn len(&self) -> u64 {
+            self.data.len() as u64
+        }
+
+        fn read_at(&self, offset: u64, buf: &mut [u8]) -> Result<usize, EvidenceError> {
+            let start = (offset as usize).min(self.data.len());
+            let n = buf.len().min(self.data.len() - start);
+            buf[..n].copy_from_slice(&self.data[start..start + n]);
+            Ok(n)
+        }
+    }
+
+    #[test]
+    fn reads_block_bitmaps() {
+        // 1 KiB blocks, 64 blocks, two groups of 32 starting at block 1.
+        // Group 0's bitmap is in block 5; group 1 is BLOCK_UNINIT.
+        let mut image = vec![0u8; 64 * 1024];
+        let sb = 1024;
+        image[sb + 4..sb + 8].copy_from_slice(&64u32.to_le_bytes());
+        image[sb + 20..sb + 24].copy_from_slice(&1u32.to_le_bytes());
+        image[sb + 32..sb + 36].copy_from_slice(&32u32.to_le_bytes());
+        image[sb + 56..sb + 58].copy_from_slice(&EXT_MAGIC.to_le_bytes());
+        let gdt = 2 * 1024;
+        image[gdt..gdt + 4].copy_from_slice(&5u32.to_le_bytes());
+        image[gdt + 32 + 18..gdt + 32 + 20].copy_from_slice(&BG_BLOCK_UNINIT.to_le_bytes());
+        // Group 0: blocks 1-8 and 20 in use.
+        image[5 * 1024] = 0xFF;
+        image[5 * 1024 + 2] = 0b0000_1000;
+
+        let evidence = SliceEvidence { data: image };
+        let volume = probe(&evidence, 0, evidence.len())
+            .expect("probe")
+            .expect("ext volume");
+        assert_eq!(volume.kind, FilesystemKind::Ext);
+        assert_eq!(volume.cluster_size, 1024);
+        assert_eq!(
+            volume.allocated,
+            vec![(0, 9 * 1024), (20 * 1024, 21 * 1024)]
+        );
+    }
+
+    fn extent_inode(
+        image: &mut [u8],
+        at: usize,
+        mode: u16,
+        size: u32,
+        extents: &[(u32, u16, u32)],
+    ) {
+        image[at..at + 2].copy_from_slice(&mode.to_le_bytes());
+        image[at + 4..at + 8].copy_from_slice(&size.to_le_bytes());
+        image[at + 32..at + 36].copy_from_slice(&INODE_FLAG_EXTENTS.to_le_bytes());
+        let node = at + 40;
+        image[node..node + 2].copy_from_slice(&EXTENT_MAGIC.to_le_bytes());
+        image[node + 2..node + 4].copy_from_slice(&(extents.len() as u16).to_le_bytes());
+        image[node + 4..node + 6].copy_from_slice(&4u16.to_le_bytes());
+        for (idx, (file_block, len, block)) in extents.iter().enumerate() {
+            let entry = node + 12 + idx * 12;
+            image[entry..entry + 4].copy_from_slice(&file_block.to_le_bytes());
+            image[entry + 4..entry + 6].copy_from_slice(&len.to_le_bytes());
+            image[entry + 8..entry + 12].copy_from_slice(&block.to_le_bytes());
+        }
+    }
+
+    #[test]
+    fn indexes_files_from_extent_trees() {
+        // 1 KiB blocks, one group of 64 blocks, 16 inodes of 128 bytes in a
+        // table at block 10. The root directory (block 20) holds `a.bin`,
+        // inode 12, whose 3000 bytes sit in blocks 30-31 and 40.
+        let mut image = vec![0u8; 64 * 1024];
+        let sb = 1024;
+        image[sb + 4..sb + 8].copy_from_slice(&64u32.to_le_bytes());
+        image[sb + 20..sb + 24].copy_from_slice(&1u32.to_le_bytes());
+        image[sb + 32..sb + 36].copy_from_slice(&64u32.to_le_bytes());
+        image[sb + 40..sb + 44].copy_from_slice(&16u32.to_le_bytes());
+        image[sb + 56..sb + 58].copy_from_slice(&EXT_MAGIC.to_le_bytes());
+        image[sb + 76..sb + 80].copy_from_slice(&1u32.to_le_bytes());
+        image[sb + 88..sb + 90].copy_from_slice(&128u16.to_le_bytes());
+        let gdt = 2 * 1024;
+        image[gdt + 8..gdt + 12].copy_from_slice(&10u32.to_le_bytes());
+
+        let table = 10 * 1024;
+        extent_inode(&mut image, table + 128, 0x41ED, 1024, &[(0, 1, 20)]);
+        extent_inode(
+            &mut image,
+            table + 11 * 128,
+            0x81A4,
+            3000,
+            &[(0, 2, 30), (2, 1, 40)],
+        );
+        let dir = 20 * 1024;
+        for (at, inode, rec_len, name) in [
+            (0usize, 2u32, 12u16, &b"."[..]),
+            (12, 2, 12, b".."),
+            (24, 12, 1000, b"a.bin"),
+        ] {
+            let entry = dir + at;
+            image[entry..entry + 4].copy_from_slice(&inode.to_le_bytes());
+            image[entry + 4..entry + 6].copy_from_slice(&rec_len.to_le_bytes());
+            image[entry + 6] = name.len() as u8;
+            image[entry + 8..entry + 8 + name.len()].copy_from_slice(name);
+        }
+
+        let evidence = SliceEvidence { data: image };
+        let mut index = FileIndex::default();
+        assert!(super::index(&evidence, 0, evidence.len(), &mut index).expect("index"));
+        assert_eq!(index.file_count(), 1);
+        let hit = index.locate(40 * 1024 + 100).expect("third block");
+        assert_eq!(hit.path, "/a.bin");
+        assert_eq!(hit.inode, 12);
+        assert_eq!(hit.offset_in_file, 2148);
+        // Past the 3000-byte size.
+        assert!(index.locate(40 * 1024 + 1000).is_none());
+    }
+}
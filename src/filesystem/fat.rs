@@ -0,0 +1,475 @@
+//! FAT12/16/32 allocation: the reserved area, FATs and (FAT12/16) root
+//! directory are always in use; data clusters are in use unless their FAT
+//! entry is zero. The file index walks the directory tree from the root and
+//! follows each file's cluster chain.
+
+use std::collections::HashSet;
+
+use super::{
+    FileIndex, FilesystemKind, Volume, join_path, le_u16, le_u32, push_range, read_full, read_vec,
+};
+use crate::evidence::{EvidenceError, EvidenceSource};
+
+const BOOT_SECTOR_SIZE: usize = 512;
+/// Cluster-count limits from the FAT specification.
+const FAT12_MAX_CLUSTERS: u64 = 4085;
+const FAT16_MAX_CLUSTERS: u64 = 65525;
+const DIR_ENTRY_SIZE: usize = 32;
+const ATTR_VOLUME_ID: u8 = 0x08;
+const ATTR_DIRECTORY: u8 = 0x10;
+const ATTR_LONG_NAME: u8 = 0x0F;
+const ENTRY_FREE: u8 = 0xE5;
+const ENTRY_END: u8 = 0x00;
+/// Guards against directory loops on damaged volumes.
+const MAX_DIR_DEPTH: usize = 64;
+
+pub(super) fn probe(
+    evidence: &dyn EvidenceSource,
+    start: u64,
+    len: u64,
+) -> Result<Option<Volume>, EvidenceError> {
+    let mut boot = [0u8; BOOT_SECTOR_SIZE];
+    if read_full(evidence, start, &mut boot)? < BOOT_SECTOR_SIZE {
+        return Ok(None);
+    }
+    let Some(bpb) = Bpb::parse(&boot) else {
+        return Ok(None);
+    };
+    if bpb.total_sectors * bpb.bytes_per_sector > len {
+        return Ok(None);
+    }
+
+    // Only the entries covering real clusters are read; the FAT may be
+    // larger than needed.
+    let kind = bpb.kind();
+    let entries = bpb.cluster_count() + 2;
+    let needed = match kind {
+        FilesystemKind::Fat12 => entries * 3 / 2 + 1,
+        FilesystemKind::Fat16 => entries * 2,
+        _ => entries * 4,
+    };
+    let fat = read_vec(
+        evidence,
+        start + bpb.reserved_sectors * bpb.bytes_per_sector,
+        needed.min(bpb.fat_sectors * bpb.bytes_per_sector) as usize,
+    )?;
+    let cluster_size = bpb.sectors_per_cluster * bpb.bytes_per_sector;
+    let data_start = start + bpb.data_start_sector() * bpb.bytes_per_sector;
+
+    let mut allocated = Vec::new();
+    push_range(&mut allocated, start, data_start);
+    for cluster in 2..bpb.cluster_count() + 2 {
+        let Some(entry) = fat_entry(&fat, kind, cluster) else {
+            break;
+        };
+        if entry != 0 {
+            let at = data_start + (cluster - 2) * cluster_size;
+            push_range(&mut allocated, at, at + cluster_size);
+        }
+    }
+
+    Ok(Some(Volume {
+        kind,
+        start,
+        len: bpb.total_sectors * bpb.bytes_per_sector,
+        cluster_size,
+        allocated,
+    }))
+}
+
+/// Index every file reachable from the root directory. Returns `false` when
+/// the volume is not FAT.
+pub(super) fn index(
+    evidence: &dyn EvidenceSource,
+    start: u64,
+    len: u64,
+    index: &mut FileIndex,
+) -> Result<bool, EvidenceError> {
+    let mut boot = [0u8; BOOT_SECTOR_SIZE];
+    if read_full(evidence, start, &mut boot)? < BOOT_SECTOR_SIZE {
+        return Ok(false);
+    }
+    let Some(bpb) = Bpb::parse(&boot) else {
+        return Ok(false);
+    };
+    if bpb.total_sectors * bpb.bytes_per_sector > len {
+        return Ok(false);
+    }
+
+    let kind = bpb.kind();
+    let fat = read_vec(
+        evidence,
+        start + bpb.reserved_sectors * bpb.bytes_per_sector,
+        (bpb.fat_sectors * bpb.bytes_per_sector) as usize,
+    )?;
+    let walker = Walker {
+        evidence,
+        fat: &fat,
+        kind,
+        cluster_count: bpb.cluster_count(),
+        cluster_size: bpb.sectors_per_cluster * bpb.bytes_per_sector,
+        data_start: start + bpb.data_start_sector() * bpb.bytes_per_sector,
+    };
+
+    let root = if kind == FilesystemKind::Fat32 {
+        walker.read_chain(le_u32(&boot[44..48]) as u64)?
+    } else {
+        let root_start = bpb.reserved_sectors + bpb.num_fats * bpb.fat_sectors;
+        read_vec(
+            evidence,
+            start + root_start * bpb.bytes_per_sector,
+            (bpb.root_entries * DIR_ENTRY_SIZE as u64) as usize,
+        )?
+    };
+    let mut visited = HashSet::new();
+    let mut pending = vec![(String::new(), root, 0usize)];
+    while let Some((dir_path, entries, depth)) = pending.pop() {
+        for entry in dir_entries(&entries) {
+            let path = join_path(&dir_path, &entry.name);
+            if entry.is_dir {
+                if depth < MAX_DIR_DEPTH && visited.insert(entry.first_cluster) {
+                    let contents = walker.read_chain(entry.first_cluster)?;
+                    pending.push((path, contents, depth + 1));
+                }
+                continue;
+            }
+            if entry.first_cluster < 2 || entry.size == 0 {
+                continue;
+            }
+            let file = index.add_file(path, entry.first_cluster);
+            let mut file_offset = 0u64;
+            for (at, end) in walker.chain_ranges(entry.first_cluster) {
+                let end = end.min(at + (entry.size as u64 - file_offset));
+                index.add_extent(file, at, end, file_offset);
+                file_offset += end - at;
+                if file_offset >= entry.size as u64 {
+                    break;
+                }
+            }
+        }
+    }
+    Ok(true)
+}
+
+struct Walker<'a> {
+    evidence: &'a dyn EvidenceSource,
+    fat: &'a [u8],
+    kind: FilesystemKind,
+    cluster_count: u64,
+    cluster_size: u64,
+    data_start: u64,
+}
+
+impl Walker<'_> {
+    /// Byte ranges of a cluster chain, consecutive clusters merged. The
+    /// chain stops at end-of-chain, bad or free entries, out-of-range
+    /// clusters and loops.
+    fn chain_ranges(&self, first: u64) -> Vec<(u64, u64)> {
+        let mut ranges = Vec::new();
+        let mut cluster = first;
+        for _ in 0..self.cluster_count {
+            if !(2..self.cluster_count + 2).contains(&cluster) {
+                break;
+            }
+            let at = self.data_start + (cluster - 2) * self.cluster_size;
+            push_range(&mut ranges, at, at + self.cluster_size);
+            let Some(next) = fat_entry(self.fat, self.kind, cluster) else {
+                break;
+            };
+            cluster = next as u64;
+        }
+        ranges
+    }
+
+    fn read_chain(&self, first: u64) -> Result<Vec<u8>, EvidenceError> {
+        let mut data = Vec::new();
+        for (at, end) in self.chain_ranges(first) {
+            data.extend(read_vec(self.evidence, at, (end - at) as usize)?);
+        }
+        Ok(data)
+    }
+}
+
+struct DirEntry {
+    name: String,
+    is_dir: bool,
+    first_cluster: u64,
+    size: u32,
+}
+
+/// Live entries of a directory, with long names where they check out.
+fn dir_entries(data: &[u8]) -> Vec<DirEntry> {
+    let mut out = Vec::new();
+    let mut long_name: Vec<(u8, [u16; 13])> = Vec::new();
+    for raw in data.chunks_exact(DIR_ENTRY_SIZE) {
+        match raw[0] {
+            ENTRY_END => break,
+            ENTRY_FREE => {
+                long_name.clear();
+                continue;
+            }
+            _ => {}
+        }
+        let attr = raw[11];
+        if attr == ATTR_LONG_NAME {
+            let mut units = [0u16; 13];
+            for (idx, at) in [1, 3, 5, 7, 9, 14, 16, 18, 20, 22, 24, 28, 30]
+                .into_iter()
+                .enumerate()
+            {
+                units[idx] = le_u16(&raw[at..at + 2]);
+            }
+            long_name.push((raw[13], units));
+            continue;
+        }
+        let lfn = std::mem::take(&mut long_name);
+        if attr & ATTR_VOLUME_ID != 0 || raw[0] == b'.' {
+            continue;
+        }
+        let checksum = raw[..11]
+            .iter()
+            .fold(0u8, |sum, byte| sum.rotate_right(1).wrapping_add(*byte));
+        let name = if !lfn.is_empty() && lfn.iter().all(|part| part.0 == checksum) {
+            // Long-name parts are stored last part first.
+            let units: Vec<u16> = lfn
+                .iter()
+                .rev()
+                .flat_map(|part| part.1)
+                .take_while(|unit| *unit != 0 && *unit != 0xFFFF)
+                .collect();
+            String::from_utf16_lossy(&units)
+        } else {
+            short_name(&raw[..11])
+        };
+        out.push(DirEntry {
+            name,
+            is_dir: attr & ATTR_DIRECTORY != 0,
+            first_cluster: ((le_u16(&raw[20..22]) as u64) << 16) | le_u16(&raw[26..28]) as u64,
+            size: le_u32(&raw[28..32]),
+        });
+    }
+    out
+}
+
+/// `NAME.EXT` from a space-padded 8.3 name.
+fn short_name(raw: &[u8]) -> String {
+    let mut base = raw[..8].to_vec();
+    // 0x05 stands for a leading 0xE5 byte.
+    if base[0] == 0x05 {
+        base[0] = ENTRY_FREE;
+    }
+    let base = String::from_utf8_lossy(&base).trim_end().to_string();
+    let ext = String::from_utf8_lossy(&raw[8..11]).trim_end().to_string();
+    if ext.is_empty() {
+        base
+    } else {
+        format!("{base}.{ext}")
+    }
+}
+
+struct Bpb {
+    bytes_per_sector: u64,
+    sectors_per_cluster: u64,
+    reserved_sectors: u64,
+    num_fats: u64,
+    root_entries: u64,
+    total_sectors: u64,
+    fat_sectors: u64,
+}
+
+impl Bpb {
+    fn parse(boot: &[u8]) -> Option<Self> {
+        if boot[510..512] != [0x55, 0xAA] || !matches!(boot[0], 0xEB | 0xE9) {
+            return None;
+        }
+        let bytes_per_sector = le_u16(&boot[11..13]) as u64;
+        let sectors_per_cluster = boot[13] as u64;
+        let total16 = le_u16(&boot[19..21]) as u64;
+        let fat16 = le_u16(&boot[22..24]) as u64;
+        let bpb = Self {
+            bytes_per_sector,
+            sectors_per_cluster,
+            reserved_sectors: le_u16(&boot[14..16]) as u64,
+            num_fats: boot[16] as u64,
+            root_entries: le_u16(&boot[17..19]) as u64,
+            total_sectors: if total16 != 0 {
+                total16
+            } else {
+                le_u32(&boot[32..36]) as u64
+            },
+            fat_sectors: if fat16 != 0 {
+                fat16
+            } else {
+                le_u32(&boot[36..40]) as u64
+            },
+        };
+        let valid = matches!(bytes_per_sector, 512 | 1024 | 2048 | 4096)
+            && sectors_per_cluster.is_power_of_two()
+            && sectors_per_cluster <= 128
+            && bpb.reserved_sectors > 0
+            && (1..=2).contains(&bpb.num_fats)
+            && bpb.fat_sectors > 0
+            && bpb.data_start_sector() < bpb.total_sectors;
+        valid.then_some(bpb)
+    }
+
+    fn root_dir_sectors(&self) -> u64 {
+        (self.root_entries * 32).div_ceil(self.bytes_per_sector)
+    }
+
+    fn data_start_sector(&self) -> u64 {
+        self.reserved_sectors + self.num_fats * self.fat_sectors + self.root_dir_sectors()
+    }
+
+    fn cluster_count(&self) -> u64 {
+        (self.total_sectors - self.data_start_sector()) / self.sectors_per_cluster
+    }
+
+    fn kind(&self) -> FilesystemKind {
+        let clusters = self.cluster_count();
+        if clusters < FAT12_MAX_CLUSTERS {
+            FilesystemKind::Fat12
+        } else if clusters < FAT16_MAX_CLUSTERS {
+            FilesystemKind::Fat16
+        } else {
+            FilesystemKind::Fat32
+        }
+    }
+}
+
+/// FAT entry for `cluster`, or `None` past the end of the table.
+fn fat_entry(fat: &[u8], kind: FilesystemKind, cluster: u64) -> Option<u32> {
+    let cluster = cluster as usize;
+    match kind {
+        FilesystemKind::Fat12 => {
+            let at = cluster + cluster / 2;
+            let raw = le_u16(fat.get(at..at + 2)?) as u32;
+            Some(if cluster % 2 == 1 {
+                raw >> 4
+            } else {
+                raw & 0x0FFF
+            })
+        }
+        FilesystemKind::Fat16 => Some(le_u16(fat.get(cluster * 2..cluster * 2 + 2)?) as u32),
+        _ => Some(le_u32(fat.get(cluster * 4..cluster * 4 + 4)?) & 0x0FFF_FFFF),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct SliceEvidence {
+        data: Vec<u8>,
+    }
+
+    impl EvidenceSource for SliceEvidence {
+        fn len(&self) -> u64 {
+            self.data.len() as u64
+        }
+
+        fn read_at(&self, offset: u64, buf: &mut [u8]) -> Result<usize, EvidenceError> {
+            let start = (offset as usize).min(self.data.len());
+            let n = buf.len().min(self.data.len() - start);
+            buf[..n].copy_from_slice(&self.data[start..start + n]);
+            Ok(n)
+        }
+    }
+
+    /// 1 reserved sector, 1 FAT sector, 16 root entries (1 sector),
+    /// 1 sector per cluster, 64 sectors in total.
+    fn fat12_image() -> Vec<u8> {
+        let mut image = vec![0u8; 64 * 512];
+        image[0] = 0xEB;
+        image[11..13].copy_from_slice(&512u16.to_le_bytes());
+        image[13] = 1;
+        image[14..16].copy_from_slice(&1u16.to_le_bytes());
+        image[16] = 1;
+        image[17..19].copy_from_slice(&16u16.to_le_bytes());
+        image[19..21].copy_from_slice(&64u16.to_le_bytes());
+        image[22..24].copy_from_slice(&1u16.to_le_bytes());
+        image[510] = 0x55;
+        image[511] = 0xAA;
+        // FAT12 entries: 0/1 reserved, cluster 2 -> 3, 3 = EOC, 4 free,
+        // 5 = EOC.
+        image[512..521].copy_from_slice(&[0xF8, 0xFF, 0xFF, 0x03, 0xF0, 0xFF, 0x00, 0xF0, 0xFF]);
+        image
+    }
+
+    fn dir_entry(name: &[u8; 11], attr: u8, cluster: u16, size: u32) -> [u8; 32] {
+        let mut entry = [0u8; 32];
+        entry[..11].copy_from_slice(name);
+        entry[11] = attr;
+        entry[26..28].copy_from_slice(&cluster.to_le_bytes());
+        entry[28..32].copy_from_slice(&size.to_le_bytes());
+        entry
+    }
+
+    #[test]
+    fn reads_fat12_allocation() {
+        let evidence = SliceEvidence {
+            data: fat12_image(),
+        };
+        let volume = probe(&evidence, 0, evidence.len())
+            .expect("probe")
+            .expect("fat volume");
+        assert_eq!(volume.kind, FilesystemKind::Fat12);
+        assert_eq!(volume.cluster_size, 512);
+        // System area (3 sectors) plus clusters 2-3 and 5.
+        assert_eq!(volume.allocated, vec![(0, 5 * 512), (6 * 512, 7 * 512)]);
+    }
+
+    #[test]
+    fn rejects_non_fat_boot_sector() {
+        let evidence = SliceEvidence {
+            data: vec![0u8; 4096],
+        };
+        assert!(probe(&evidence, 0, 4096).expect("probe").is_none());
+    }
+
+    #[test]
+    fn indexes_files_through_directories_and_chains() {
+        let mut image = fat12_image();
+        // Root (sector 2): a volume label, a deleted file and `DIR` in
+        // cluster 5. `DIR` holds `photo one.jpg` (long name) in clusters 2-3.
+        let root = 2 * 512;
+        image[root..root + 32].copy_from_slice(&dir_entry(b"LABEL      ", ATTR_VOLUME_ID, 0, 0));
+        let mut deleted = dir_entry(b"OLD     TXT", 0x20, 4, 10);
+        deleted[0] = ENTRY_FREE;
+        image[root + 32..root + 64].copy_from_slice(&deleted);
+        image[root + 64..root + 96].copy_from_slice(&dir_entry(
+            b"DIR        ",
+            ATTR_DIRECTORY,
+            5,
+            0,
+        ));
+
+        let dir = (3 + 3) * 512;
+        let short = *b"PHOTOO~1JPG";
+        let checksum = short
+            .iter()
+            .fold(0u8, |sum, byte| sum.rotate_right(1).wrapping_add(*byte));
+        let mut lfn = [0u8; 32];
+        lfn[0] = 0x41;
+        lfn[11] = ATTR_LONG_NAME;
+        lfn[13] = checksum;
+        let offsets = [1, 3, 5, 7, 9, 14, 16, 18, 20, 22, 24, 28, 30];
+        for (at, unit) in offsets.into_iter().zip("photo one.jpg".encode_utf16()) {
+            lfn[at..at + 2].copy_from_slice(&unit.to_le_bytes());
+        }
+        image[dir..dir + 32].copy_from_slice(&dir_entry(b".          ", ATTR_DIRECTORY, 5, 0));
+        image[dir + 32..dir + 64].copy_from_slice(&lfn);
+        image[dir + 64..dir + 96].copy_from_slice(&dir_entry(&short, 0x20, 2, 700));
+
+        let evidence = SliceEvidence { data: image };
+        let mut index = FileIndex::default();
+        assert!(super::index(&evidence, 0, evidence.len(), &mut index).expect("index"));
+        assert_eq!(index.file_count(), 1);
+        let hit = index.locate(3 * 512 + 600).expect("located");
+        assert_eq!(hit.path, "/DIR/photo one.jpg");
+        assert_eq!(hit.inode, 2);
+        assert_eq!(hit.offset_in_file, 600);
+        // Past the 700-byte file size, in the chain's slack.
+        assert!(index.locate(3 * 512 + 700).is_none());
+    }
+}
@@ -0,0 +1,423 @@
+//! # Filesystem Module
+//!
+//! Allocation maps and offset-to-file indexes for FAT12/16/32, NTFS and
+//! ext2/3/4 volumes. Allocation maps drive `--unallocated-only` scanning and
+//! mark carved files as allocated, unallocated or partially allocated; the
+//! file index attributes carved data to the live file that contains it.
+
+mod ext;
+mod fat;
+mod ntfs;
+
+use tracing::warn;
+
+use crate::evidence::{EvidenceError, EvidenceSource};
+use crate::partition::Partition;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FilesystemKind {
+    Fat12,
+    Fat16,
+    Fat32,
+    Ntfs,
+    Ext,
+}
+
+impl FilesystemKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            FilesystemKind::Fat12 => "fat12",
+            FilesystemKind::Fat16 => "fat16",
+            FilesystemKind::Fat32 => "fat32",
+            FilesystemKind::Ntfs => "ntfs",
+            FilesystemKind::Ext => "ext",
+        }
+    }
+}
+
+/// A recognised volume and the byte ranges its allocation structures mark
+/// as in use (absolute evidence offsets, sorted and merged).
+#[derive(Debug, Clone)]
+pub struct Volume {
+    pub kind: FilesystemKind,
+    pub start: u64,
+    pub len: u64,
+    /// Cluster (FAT/NTFS) or block (ext) size in bytes.
+    pub cluster_size: u64,
+    pub allocated: Vec<(u64, u64)>,
+}
+
+impl Volume {
+    pub fn allocated_bytes(&self) -> u64 {
+        self.allocated.iter().map(|(start, end)| end - start).sum()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AllocationStatus {
+    Allocated,
+    Unallocated,
+    PartiallyAllocated,
+}
+
+impl AllocationStatus {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            AllocationStatus::Allocated => "allocated",
+            AllocationStatus::Unallocated => "unallocated",
+            AllocationStatus::PartiallyAllocated => "partially_allocated",
+        }
+    }
+}
+
+/// Allocated space across all recognised volumes. Anything outside them
+/// (unpartitioned gaps, unrecognised or damaged volumes) counts as
+/// unallocated, so it is still scanned.
+#[derive(Debug, Clone, Default)]
+pub struct AllocationMap {
+    pub volumes: Vec<Volume>,
+    allocated: Vec<(u64, u64)>,
+}
+
+impl AllocationMap {
+    pub fn new(volumes: Vec<Volume>) -> Self {
+        let ranges = volumes
+            .iter()
+            .flat_map(|volume| volume.allocated.iter().copied())
+            .collect();
+        Self {
+            allocated: crate::ranges::merge_ranges(ranges),
+            volumes,
+        }
+    }
+
+    pub fn allocated_ranges(&self) -> &[(u64, u64)] {
+        &self.allocated
+    }
+
+    /// Complement of the allocated ranges within `[0, total_len)`.
+    pub fn unallocated_ranges(&self, total_len: u64) -> Vec<(u64, u64)> {
+        let mut free = Vec::new();
+        let mut cursor = 0u64;
+        for &(start, end) in &self.allocated {
+            if start >= total_len {
+                break;
+            }
+            if start > cursor {
+                free.push((cursor, start));
+            }
+            cursor = cursor.max(end);
+        }
+        if cursor < total_len {
+            free.push((cursor, total_len));
+        }
+        free
+    }
+
+    /// Allocation status of the `[start, end)` byte range.
+    pub fn status(&self, start: u64, end: u64) -> AllocationStatus {
+        let first = self.allocated.partition_point(|range| range.1 <= start);
+        let covered: u64 = self.allocated[first..]
+            .iter()
+            .take_while(|range| range.0 < end)
+            .map(|&(s, e)| e.min(end) - s.max(start))
+            .sum();
+        if covered == 0 {
+            AllocationStatus::Unallocated
+        } else if covered >= end.saturating_sub(start) {
+            AllocationStatus::Allocated
+        } else {
+            AllocationStatus::PartiallyAllocated
+        }
+    }
+}
+
+/// Probe every partition (or the whole evidence when there is no partition
+/// table) for a FAT, NTFS or ext file system and read its allocation map.
+/// Volumes that fail to parse are logged and left out.
+pub fn read_allocation(evidence: &dyn EvidenceSource, partitions: &[Partition]) -> AllocationMap {
+    let mut volumes = Vec::new();
+    for (start, len) in volume_candidates(evidence, partitions) {
+        match probe_volume(evidence, start, len) {
+            Ok(Some(volume)) => volumes.push(volume),
+            Ok(None) => {}
+            Err(err) => warn!("failed to read file system at offset {start}: {err}"),
+        }
+    }
+    AllocationMap::new(volumes)
+}
+
+/// A live file found by walking a volume's metadata.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IndexedFile {
+    /// Path within its volume, `/`-separated.
+    pub path: String,
+    /// MFT record number (NTFS), inode number (ext) or first cluster (FAT).
+    pub inode: u64,
+}
+
+/// Where an evidence offset falls inside a live file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FileLocation<'a> {
+    pub path: &'a str,
+    pub inode: u64,
+    pub offset_in_file: u64,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct FileExtent {
+    start: u64,
+    end: u64,
+    file_offset: u64,
+    file: usize,
+}
+
+/// Maps evidence byte ranges back to the live files whose data occupy them.
+#[derive(Debug, Clone, Default)]
+pub struct FileIndex {
+    files: Vec<IndexedFile>,
+    extents: Vec<FileExtent>,
+}
+
+impl FileIndex {
+    pub fn file_count(&self) -> usize {
+        self.files.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.extents.is_empty()
+    }
+
+    /// The file whose data contains the absolute evidence `offset`.
+    pub fn locate(&self, offset: u64) -> Option<FileLocation<'_>> {
+        let idx = self
+            .extents
+            .partition_point(|extent| extent.start <= offset);
+        let extent = self.extents.get(idx.checked_sub(1)?)?;
+        if offset >= extent.end {
+            return None;
+        }
+        let file = &self.files[extent.file];
+        Some(FileLocation {
+            path: &file.path,
+            inode: file.inode,
+            offset_in_file: extent.file_offset + (offset - extent.start),
+        })
+    }
+
+    fn add_file(&mut self, path: String, inode: u64) -> usize {
+        self.files.push(IndexedFile { path, inode });
+        self.files.len() - 1
+    }
+
+    /// Record that `[start, end)` holds the file's bytes from `file_offset`.
+    fn add_extent(&mut self, file: usize, start: u64, end: u64, file_offset: u64) {
+        if end > start {
+            self.extents.push(FileExtent {
+                start,
+                end,
+                file_offset,
+                file,
+            });
+        }
+    }
+}
+
+/// Walk every FAT, NTFS or ext volume (see [`read_allocation`]) and index
+/// where each live file's data sits. Volumes that fail to parse are logged
+/// and left out.
+pub fn build_file_index(evidence: &dyn EvidenceSource, partitions: &[Partition]) -> FileIndex {
+    let mut index = FileIndex::default();
+    for (start, len) in volume_candidates(evidence, partitions) {
+        if let Err(err) = index_volume(evidence, start, len, &mut index) {
+            warn!("failed to index file system at offset {start}: {err}");
+        }
+    }
+    index.extents.sort_unstable_by_key(|extent| extent.start);
+    index
+}
+
+fn index_volume(
+    evidence: &dyn EvidenceSource,
+    start: u64,
+    len: u64,
+    index: &mut FileIndex,
+) -> Result<(), EvidenceError> {
+    if ntfs::index(evidence, start, len, index)? {
+        return Ok(());
+    }
+    if fat::index(evidence, start, len, index)? {
+        return Ok(());
+    }
+    ext::index(evidence, start, len, index)?;
+    Ok(())
+}
+
+fn volume_candidates(evidence: &dyn EvidenceSource, partitions: &[Partition]) -> Vec<(u64, u64)> {
+    if partitions.is_empty() {
+        vec![(0, evidence.len())]
+    } else {
+        partitions
+            .iter()
+            .map(|part| (part.start, part.len))
+            .collect()
+    }
+}
+
+fn probe_volume(
+    evidence: &dyn EvidenceSource,
+    start: u64,
+    len: u64,
+) -> Result<Option<Volume>, EvidenceError> {
+    if let Some(volume) = ntfs::probe(evidence, start, len)? {
+        return Ok(Some(volume));
+    }
+    if let Some(volume) = fat::probe(evidence, start, len)? {
+        return Ok(Some(volume));
+    }
+    ext::probe(evidence, start, len)
+}
+
+/// Append `[start, end)` to a sorted range list, extending the last range
+/// when they touch.
+fn push_range(ranges: &mut Vec<(u64, u64)>, start: u64, end: u64) {
+    if end <= start {
+        return;
+    }
+    match ranges.last_mut() {
+        Some(last) if last.1 >= start => last.1 = last.1.max(end),
+        _ => ranges.push((start, end)),
+    }
+}
+
+/// Turn an LSB-first allocation bitmap into byte ranges: bit `i` covers
+/// `[base + i * unit, base + (i + 1) * unit)`. Only the first `count` bits
+/// are used.
+fn bitmap_ranges(bitmap: &[u8], count: u64, unit: u64, base: u64, out: &mut Vec<(u64, u64)>) {
+    let count = count.min(bitmap.len() as u64 * 8);
+    let mut bit = 0u64;
+    while bit < count {
+        let byte = bitmap[(bit / 8) as usize];
+        // Skip whole bytes quickly.
+        if bit.is_multiple_of(8) && count - bit >= 8 && (byte == 0 || byte == 0xFF) {
+            if byte == 0xFF {
+                push_range(out, base + bit * unit, base + (bit + 8) * unit);
+            }
+            bit += 8;
+            continue;
+        }
+        if byte & (1 << (bit % 8)) != 0 {
+            push_range(out, base + bit * unit, base + (bit + 1) * unit);
+        }
+        bit += 1;
+    }
+}
+
+fn read_full(
+    evidence: &dyn EvidenceSource,
+    offset: u64,
+    buf: &mut [u8],
+) -> Result<usize, EvidenceError> {
+    let mut filled = 0usize;
+    while filled < buf.len() {
+        let n = evidence.read_at(offset + filled as u64, &mut buf[filled..])?;
+        if n == 0 {
+            break;
+        }
+        filled += n;
+    }
+    Ok(filled)
+}
+
+/// Read exactly `len` bytes, failing on a short read.
+fn read_vec(
+    evidence: &dyn EvidenceSource,
+    offset: u64,
+    len: usize,
+) -> Result<Vec<u8>, EvidenceError> {
+    let mut buf = vec![0u8; len];
+    if read_full(evidence, offset, &mut buf)? < len {
+        return Err(EvidenceError::Corrupt(format!(
+            "short read of {len} bytes at offset {offset}"
+        )));
+    }
+    Ok(buf)
+}
+
+/// Join a directory path and an entry name.
+fn join_path(dir: &str, name: &str) -> String {
+    format!("{dir}/{name}")
+}
+
+fn le_u16(bytes: &[u8]) -> u16 {
+    u16::from_le_bytes([bytes[0], bytes[1]])
+}
+
+fn le_u32(bytes: &[u8]) -> u32 {
+    u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]])
+}
+
+fn le_u64(bytes: &[u8]) -> u64 {
+    u64::from_le_bytes(bytes[..8].try_into().expect("8 bytes"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn volume(allocated: Vec<(u64, u64)>) -> Volume {
+        Volume {
+            kind: FilesystemKind::Fat16,
+            start: 0,
+            len: 1000,
+            cluster_size: 10,
+            allocated,
+        }
+    }
+
+    #[test]
+    fn classifies_ranges_against_allocation() {
+        let map = AllocationMap::new(vec![volume(vec![(100, 200), (300, 400)])]);
+        assert_eq!(
+            map.unallocated_ranges(1000),
+            vec![(0, 100), (200, 300), (400, 1000)]
+        );
+        assert_eq!(map.status(120, 180), AllocationStatus::Allocated);
+        assert_eq!(map.status(200, 300), AllocationStatus::Unallocated);
+        assert_eq!(map.status(150, 350), AllocationStatus::PartiallyAllocated);
+        assert_eq!(map.status(500, 600), AllocationStatus::Unallocated);
+    }
+
+    #[test]
+    fn converts_bitmaps_to_ranges() {
+        let mut out = Vec::new();
+        // Bits 0-1, 8-15 and 17 set; only 18 bits are valid.
+        bitmap_ranges(&[0b0000_0011, 0xFF, 0b0000_0110], 18, 4, 1000, &mut out);
+        assert_eq!(out, vec![(1000, 1008), (1032, 1064), (1068, 1072)]);
+    }
+
+    #[test]
+    fn locates_offsets_in_indexed_files() {
+        let mut index = FileIndex::default();
+        let a = index.add_file("/a.jpg".to_string(), 7);
+        let b = index.add_file("/dir/b.bin".to_string(), 9);
+        index.add_extent(a, 300, 400, 100);
+        index.add_extent(a, 100, 200, 0);
+        index.add_extent(b, 200, 250, 0);
+        index.extents.sort_unstable_by_key(|extent| extent.start);
+
+        let hit = index.locate(350).expect("in a.jpg");
+        assert_eq!(
+            (hit.path, hit.inode, hit.offset_in_file),
+            ("/a.jpg", 7, 150)
+        );
+        let hit = index.locate(200).expect("in b.bin");
+        assert_eq!(
+            (hit.path, hit.inode, hit.offset_in_file),
+            ("/dir/b.bin", 9, 0)
+        );
+        assert!(index.locate(260).is_none());
+        assert!(index.locate(50).is_none());
+        assert!(index.locate(400).is_none());
+    }
+}
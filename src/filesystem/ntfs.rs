@@ -0,0 +1,672 @@
+//! NTFS allocation from the `$Bitmap` metadata file (MFT record 6): one bit
+//! per cluster, set when the cluster is in use. The file index walks every
+//! MFT record and maps the data runs of each unnamed `$DATA` attribute.
+
+use std::collections::HashMap;
+
+use super::{
+    FileIndex, FilesystemKind, Volume, bitmap_ranges, join_path, le_u16, le_u32, le_u64, read_full,
+    read_vec,
+};
+use crate::evidence::{EvidenceError, EvidenceSource};
+
+const BOOT_SECTOR_SIZE: usize = 512;
+const NTFS_OEM_ID: &[u8; 8] = b"NTFS    ";
+const MFT_RECORD: u64 = 0;
+const BITMAP_RECORD: u64 = 6;
+const ROOT_RECORD: u64 = 5;
+const ATTR_FILE_NAME: u32 = 0x30;
+const ATTR_DATA: u32 = 0x80;
+const ATTR_END: u32 = 0xFFFF_FFFF;
+const RECORD_IN_USE: u16 = 0x01;
+const NAMESPACE_DOS: u8 = 2;
+/// Upper bound on `$Bitmap` size (one bit per cluster), 512 MiB covers
+/// volumes far beyond anything we expect to see.
+const MAX_BITMAP_BYTES: u64 = 512 * 1024 * 1024;
+/// MFT bytes read per request while walking records.
+const MFT_READ_SIZE: u64 = 1024 * 1024;
+/// Guards against parent-reference loops when building paths.
+const MAX_PATH_DEPTH: usize = 256;
+const ORPHAN_DIR: &str = "/$Orphan";
+
+/// Geometry from the NTFS boot sector.
+struct Boot {
+    bytes_per_sector: u64,
+    cluster_size: u64,
+    mft_lcn: u64,
+    record_size: u64,
+    volume_len: u64,
+    cluster_count: u64,
+}
+
+impl Boot {
+    fn read(
+        evidence: &dyn EvidenceSource,
+        start: u64,
+        len: u64,
+    ) -> Result<Option<Self>, EvidenceError> {
+        let mut boot = [0u8; BOOT_SECTOR_SIZE];
+        if read_full(evidence, start, &mut boot)? < BOOT_SECTOR_SIZE || &boot[3..11] != NTFS_OEM_ID
+        {
+            return Ok(None);
+        }
+
+        let bytes_per_sector = le_u16(&boot[11..13]) as u64;
+        let cluster_size = match boot[13] {
+            0 => return Ok(None),
+            raw if raw < 0x80 => raw as u64 * bytes_per_sector,
+            // Large clusters are stored as a negative power of two.
+            raw if raw >= 0xE0 => 1u64 << (256 - raw as u32),
+            _ => return Ok(None),
+        };
+        if !matches!(bytes_per_sector, 512 | 1024 | 2048 | 4096) {
+            return Ok(None);
+        }
+        let total_sectors = le_u64(&boot[40..48]);
+        let mft_lcn = le_u64(&boot[48..56]);
+        let record_size = match boot[64] as i8 {
+            raw if raw > 0 => raw as u64 * cluster_size,
+            raw if raw > -32 => 1u64 << (-(raw as i32)) as u32,
+            _ => return Ok(None),
+        };
+        let volume_len = total_sectors.saturating_mul(bytes_per_sector).min(len);
+        let cluster_count = volume_len / cluster_size;
+        if !(256..=64 * 1024).contains(&record_size) || mft_lcn >= cluster_count {
+            return Ok(None);
+        }
+        Ok(Some(Self {
+            bytes_per_sector,
+            cluster_size,
+            mft_lcn,
+            record_size,
+            volume_len,
+            cluster_count,
+        }))
+    }
+
+    /// Read one of the first MFT records, which live in the `$MFT`'s first
+    /// run at `mft_lcn`.
+    fn read_record(
+        &self,
+        evidence: &dyn EvidenceSource,
+        start: u64,
+        number: u64,
+    ) -> Result<Vec<u8>, EvidenceError> {
+        let offset = start + self.mft_lcn * self.cluster_size + number * self.record_size;
+        let mut record = read_vec(evidence, offset, self.record_size as usize)?;
+        apply_fixups(&mut record, self.bytes_per_sector as usize)?;
+        Ok(record)
+    }
+}
+
+pub(super) fn probe(
+    evidence: &dyn EvidenceSource,
+    start: u64,
+    len: u64,
+) -> Result<Option<Volume>, EvidenceError> {
+    let Some(boot) = Boot::read(evidence, start, len)? else {
+        return Ok(None);
+    };
+    let record = boot.read_record(evidence, start, BITMAP_RECORD)?;
+    let bitmap = match find_unnamed_data(&record)? {
+        Some(DataAttr::Resident(data)) => data,
+        Some(DataAttr::NonResident(attr)) => read_runs(evidence, start, boot.cluster_size, &attr)?,
+        None => return Err(corrupt("$Bitmap has no unnamed $DATA attribute")),
+    };
+
+    let mut allocated = Vec::new();
+    bitmap_ranges(
+        &bitmap,
+        boot.cluster_count,
+        boot.cluster_size,
+        start,
+        &mut allocated,
+    );
+    Ok(Some(Volume {
+        kind: FilesystemKind::Ntfs,
+        start,
+        len: boot.volume_len,
+        cluster_size: boot.cluster_size,
+        allocated,
+    }))
+}
+
+/// Index every in-use MFT record with non-resident data. Returns `false`
+/// when the volume is not NTFS.
+pub(super) fn index(
+    evidence: &dyn EvidenceSource,
+    start: u64,
+    len: u64,
+    index: &mut FileIndex,
+) -> Result<bool, EvidenceError> {
+    let Some(boot) = Boot::read(evidence, start, len)? else {
+        return Ok(false);
+    };
+    let mft = match find_unnamed_data(&boot.read_record(evidence, start, MFT_RECORD)?)? {
+        Some(DataAttr::NonResident(attr)) => attr,
+        _ => return Err(corrupt("$MFT has no non-resident $DATA attribute")),
+    };
+
+    let mut names: HashMap<u64, (u64, String)> = HashMap::new();
+    let mut pieces: Vec<(u64, NonResident)> = Vec::new();
+    let record_count = mft.real_size / boot.record_size;
+    let mut number = 0u64;
+    for (lcn, clusters) in mft.runs.iter().copied() {
+        let run_bytes = clusters.saturating_mul(boot.cluster_size);
+        let Some(lcn) = lcn else {
+            number += run_bytes / boot.record_size;
+            continue;
+        };
+        let run_start = start.saturating_add(lcn.saturating_mul(boot.cluster_size));
+        let mut done = 0u64;
+        while done < run_bytes && number < record_count {
+            let want = MFT_READ_SIZE
+                .min(run_bytes - done)
+                .min((record_count - number) * boot.record_size);
+            let mut block = vec![0u8; want as usize];
+            let got = read_full(evidence, run_start + done, &mut block)? as u64;
+            if got < boot.record_size {
+                break;
+            }
+            for raw in block[..got as usize].chunks_exact(boot.record_size as usize) {
+                let mut record = raw.to_vec();
+                collect_record(
+                    &mut record,
+                    number,
+                    boot.bytes_per_sector as usize,
+                    &mut names,
+                    &mut pieces,
+                );
+                number += 1;
+            }
+            done += want;
+        }
+    }
+
+    // The first (VCN 0) piece carries the real size of the stream.
+    let mut sizes: HashMap<u64, u64> = HashMap::new();
+    for (base, attr) in &pieces {
+        if attr.start_vcn == 0 {
+            sizes.insert(*base, attr.real_size);
+        }
+    }
+    let mut files: HashMap<u64, usize> = HashMap::new();
+    let mut paths: HashMap<u64, String> = HashMap::new();
+    for (base, attr) in pieces {
+        let file = match files.get(&base) {
+            Some(&file) => file,
+            None => {
+                let path = resolve_path(base, &names, &mut paths);
+                let file = index.add_file(path, base);
+                files.insert(base, file);
+                file
+            }
+        };
+        let size = sizes.get(&base).copied().unwrap_or(u64::MAX);
+        let mut file_offset = attr.start_vcn.saturating_mul(boot.cluster_size);
+        for (lcn, clusters) in attr.runs {
+            let bytes = clusters
+                .saturating_mul(boot.cluster_size)
+                .min(size.saturating_sub(file_offset));
+            if let Some(lcn) = lcn {
+                let at = start.saturating_add(lcn.saturating_mul(boot.cluster_size));
+                index.add_extent(file, at, at.saturating_add(bytes), file_offset);
+            }
+            file_offset = file_offset.saturating_add(bytes);
+        }
+    }
+    Ok(true)
+}
+
+/// Pull the name and data runs out of one raw MFT record; damaged or unused
+/// records are skipped.
+fn collect_record(
+    record: &mut [u8],
+    number: u64,
+    sector_size: usize,
+    names: &mut HashMap<u64, (u64, String)>,
+    pieces: &mut Vec<(u64, NonResident)>,
+) {
+    if apply_fixups(record, sector_size).is_err() || le_u16(&record[22..24]) & RECORD_IN_USE == 0 {
+        return;
+    }
+    // Extension records hold attributes of their base record.
+    let base = match le_u64(&record[32..40]) & 0x0000_FFFF_FFFF_FFFF {
+        0 => number,
+        base => base,
+    };
+    if let Some((parent, name, namespace)) = file_name(record)
+        && (!names.contains_key(&base) || namespace != NAMESPACE_DOS)
+    {
+        names.insert(base, (parent, name));
+    }
+    if let Ok(Some(DataAttr::NonResident(attr))) = find_unnamed_data(record) {
+        pieces.push((base, attr));
+    }
+}
+
+/// Path of `record` built from `$FILE_NAME` parent references.
+fn resolve_path(
+    record: u64,
+    names: &HashMap<u64, (u64, String)>,
+    cache: &mut HashMap<u64, String>,
+) -> String {
+    let mut chain = Vec::new();
+    let mut current = record;
+    let mut prefix = String::new();
+    loop {
+        if current == ROOT_RECORD {
+            break;
+        }
+        if let Some(path) = cache.get(&current) {
+            prefix = path.clone();
+            break;
+        }
+        let Some((parent, name)) = names.get(&current) else {
+            prefix = ORPHAN_DIR.to_string();
+            break;
+        };
+        if chain.len() >= MAX_PATH_DEPTH {
+            prefix = ORPHAN_DIR.to_string();
+            break;
+        }
+        chain.push((current, name.as_str()));
+        current = *parent;
+    }
+    for (number, name) in chain.into_iter().rev() {
+        prefix = join_path(&prefix, name);
+        cache.insert(number, prefix.clone());
+    }
+    prefix
+}
+
+/// First `$FILE_NAME` attribute, preferring a non-DOS name: `(parent
+/// record, name, namespace)`.
+fn file_name(record: &[u8]) -> Option<(u64, String, u8)> {
+    let mut best: Option<(u64, String, u8)> = None;
+    for (attr_type, attr) in attributes(record) {
+        if attr_type != ATTR_FILE_NAME || attr[8] != 0 {
+            continue;
+        }
+        let offset = le_u16(&attr[20..22]) as usize;
+        let content = attr.get(offset..)?;
+        if content.len() < 66 {
+            continue;
+        }
+        let parent = le_u64(&content[0..8]) & 0x0000_FFFF_FFFF_FFFF;
+        let name_len = content[64] as usize;
+        let namespace = content[65];
+        let Some(raw) = content.get(66..66 + name_len * 2) else {
+            continue;
+        };
+        let units: Vec<u16> = raw
+            .chunks_exact(2)
+            .map(|pair| u16::from_le_bytes([pair[0], pair[1]]))
+            .collect();
+        let name = String::from_utf16_lossy(&units);
+        let better = match &best {
+            None => true,
+            Some(current) => current.2 == NAMESPACE_DOS && namespace != NAMESPACE_DOS,
+        };
+        if better {
+            best = Some((parent, name, namespace));
+        }
+    }
+    best
+}
+
+/// `(type, bytes)` of each attribute in a fixed-up MFT record.
+fn attributes(record: &[u8]) -> Vec<(u32, &[u8])> {
+    let mut out = Vec::new();
+    let mut at = le_u16(&record[20..22]) as usize;
+    while at + 16 <= record.len() {
+        let attr_type = le_u32(&record[at..at + 4]);
+        if attr_type == ATTR_END {
+            break;
+        }
+        let attr_len = le_u32(&record[at + 4..at + 8]) as usize;
+        if attr_len < 16 || at + attr_len > record.len() {
+            break;
+        }
+        out.push((attr_type, &record[at..at + attr_len]));
+        at += attr_len;
+    }
+    out
+}
+
+enum DataAttr {
+    Resident(Vec<u8>),
+    NonResident(NonResident),
+}
+
+struct NonResident {
+    start_vcn: u64,
+    real_size: u64,
+    runs: Vec<(Option<u64>, u64)>,
+}
+
+/// The unnamed `$DATA` attribute of an MFT record, if any.
+fn find_unnamed_data(record: &[u8]) -> Result<Option<DataAttr>, EvidenceError> {
+    for (attr_type, attr) in attributes(record) {
+        if attr_type != ATTR_DATA || attr[9] != 0 {
+            continue;
+        }
+        if attr[8] == 0 {
+            let size = le_u32(&attr[16..20]) as usize;
+            let offset = le_u16(&attr[20..22]) as usize;
+            return attr
+                .get(offset..offset + size)
+                .map(|data| Some(DataAttr::Resident(data.to_vec())))
+                .ok_or_else(|| corrupt("resident data out of bounds"));
+        }
+        if attr.len() < 64 {
+            return Err(corrupt("non-resident header truncated"));
+        }
+        let runs_offset = le_u16(&attr[32..34]) as usize;
+        return Ok(Some(DataAttr::NonResident(NonResident {
+            start_vcn: le_u64(&attr[16..24]),
+            real_size: le_u64(&attr[48..56]),
+            runs: parse_runs(attr.get(runs_offset..).unwrap_or(&[]))?,
+        })));
+    }
+    Ok(None)
+}
+
+/// Read a (small) non-resident stream into memory.
+fn read_runs(
+    evidence: &dyn EvidenceSource,
+    volume_start: u64,
+    cluster_size: u64,
+    attr: &NonResident,
+) -> Result<Vec<u8>, EvidenceError> {
+    if attr.real_size > MAX_BITMAP_BYTES {
+        return Err(corrupt("stream too large"));
+    }
+    let real_size = attr.real_size;
+    let mut data = vec![0u8; real_size as usize];
+    let mut filled = 0u64;
+    for &(lcn, clusters) in &attr.runs {
+        if filled >= real_size {
+            break;
+        }
+        let bytes = clusters
+            .saturating_mul(cluster_size)
+            .min(real_size - filled);
+        let dest = &mut data[filled as usize..(filled + bytes) as usize];
+        filled += bytes;
+        // Sparse runs stay zero.
+        let Some(lcn) = lcn else {
+            continue;
+        };
+        let at = volume_start.saturating_add(lcn.saturating_mul(cluster_size));
+        if read_full(evidence, at, dest)? < dest.len() {
+            return Err(corrupt("data run past end of evidence"));
+        }
+    }
+    Ok(data)
+}
+
+fn corrupt(what: &str) -> EvidenceError {
+    EvidenceError::Corrupt(format!("NTFS: {what}"))
+}
+
+/// Restore the last two bytes of every sector from the update sequence array.
+fn apply_fixups(record: &mut [u8], sector_size: usize) -> Result<(), EvidenceError> {
+    if record.len() < 48 || &record[0..4] != b"FILE" {
+        return Err(corrupt("MFT record missing FILE magic"));
+    }
+    let usa_offset = le_u16(&record[4..6]) as usize;
+    let usa_count = le_u16(&record[6..8]) as usize;
+    if usa_count == 0 || usa_offset + usa_count * 2 > record.len() {
+        return Err(corrupt("MFT record update sequence out of bounds"));
+    }
+    let check = [record[usa_offset], record[usa_offset + 1]];
+    for idx in 1..usa_count {
+        let end = idx * sector_size;
+        if end > record.len() {
+            break;
+        }
+        if record[end - 2..end] != check {
+            return Err(corrupt("MFT record torn write"));
+        }
+        let fix = usa_offset + idx * 2;
+        record[end - 2] = record[fix];
+        record[end - 1] = record[fix + 1];
+    }
+    Ok(())
+}
+
+/// Decode a data-run list into `(lcn, cluster_count)` pairs; `lcn` is
+/// `None` for sparse runs.
+fn parse_runs(runs: &[u8]) -> Result<Vec<(Option<u64>, u64)>, EvidenceError> {
+    let mut out = Vec::new();
+    let mut at = 0usize;
+    let mut lcn = 0i64;
+    while at < runs.len() && runs[at] != 0 {
+        let header = runs[at];
+        let len_size = (header & 0x0F) as usize;
+        let off_size = (header >> 4) as usize;
+        at += 1;
+        if len_size == 0 || len_size > 8 || off_size > 8 || at + len_size + off_size > runs.len() {
+            return Err(corrupt("malformed data run"));
+        }
+        let mut length = 0u64;
+        for (idx, byte) in runs[at..at + len_size].iter().enumerate() {
+            length |= (*byte as u64) << (idx * 8);
+        }
+        at += len_size;
+        if off_size == 0 {
+            out.push((None, length));
+            continue;
+        }
+        let mut delta = 0i64;
+        for (idx, byte) in runs[at..at + off_size].iter().enumerate() {
+            delta |= (*byte as i64) << (idx * 8);
+        }
+        // Sign-extend the relative offset.
+        let shift = 64 - off_size * 8;
+        if shift > 0 {
+            delta = (delta << shift) >> shift;
+        }
+        at += off_size;
+        lcn = lcn.saturating_add(delta);
+        if lcn < 0 {
+            return Err(corrupt("negative cluster number"));
+        }
+        out.push((Some(lcn as u64), length));
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct SliceEvidence {
+        data: Vec<u8>,
+    }
+
+    impl EvidenceSource for SliceEvidence {
+        fn len(&self) -> u64 {
+            self.data.len() as u64
+        }
+
+        fn read_at(&self, offset: u64, buf: &mut [u8]) -> Result<usize, EvidenceError> {
+            let start = (offset as usize).min(self.data.len());
+            let n = buf.len().min(self.data.len() - start);
+            buf[..n].copy_from_slice(&self.data[start..start + n]);
+            Ok(n)
+        }
+    }
+
+    #[test]
+    fn decodes_data_runs() {
+        // 2 clusters at LCN 0x20, then 1 sparse cluster, then 3 clusters
+        // 0x10 before the first run.
+        let runs = [0x11, 0x02, 0x20, 0x01, 0x01, 0x11, 0x03, 0xF0, 0x00];
+        let decoded = parse_runs(&runs).expect("runs");
+        assert_eq!(decoded, vec![(Some(0x20), 2), (None, 1), (Some(0x10), 3)]);
+    }
+
+    #[test]
+    fn reads_bitmap_from_mft_record() {
+        // 4 KiB clusters, 64 clusters, MFT at cluster 4, 1 KiB records,
+        // $Bitmap data in cluster 10.
+        let cluster = 4096usize;
+        let mut image = vec![0u8; 64 * cluster];
+        image[3..11].copy_from_slice(NTFS_OEM_ID);
+        image[11..13].copy_from_slice(&512u16.to_le_bytes());
+        image[13] = 8;
+        image[40..48].copy_from_slice(&(64u64 * 8).to_le_bytes());
+        image[48..56].copy_from_slice(&4u64.to_le_bytes());
+        image[64] = (-10i8) as u8;
+
+        let rec = 4 * cluster + 6 * 1024;
+        image[rec..rec + 4].copy_from_slice(b"FILE");
+        image[rec + 4..rec + 6].copy_from_slice(&48u16.to_le_bytes());
+        image[rec + 6..rec + 8].copy_from_slice(&3u16.to_le_bytes());
+        image[rec + 48..rec + 50].copy_from_slice(&[0x01, 0x00]);
+        for sector_end in [512, 1024] {
+            image[rec + sector_end - 2..rec + sector_end].copy_from_slice(&[0x01, 0x00]);
+        }
+        image[rec + 20..rec + 22].copy_from_slice(&56u16.to_le_bytes());
+        let attr = rec + 56;
+        image[attr..attr + 4].copy_from_slice(&ATTR_DATA.to_le_bytes());
+        image[attr + 4..attr + 8].copy_from_slice(&72u32.to_le_bytes());
+        image[attr + 8] = 1;
+        image[attr + 32..attr + 34].copy_from_slice(&64u16.to_le_bytes());
+        image[attr + 48..attr + 56].copy_from_slice(&8u64.to_le_bytes());
+        image[attr + 64..attr + 67].copy_from_slice(&[0x11, 0x01, 0x0A]);
+        image[attr + 72..attr + 76].copy_from_slice(&ATTR_END.to_le_bytes());
+        // Clusters 0-7 and 10 in use.
+        image[10 * cluster] = 0xFF;
+        image[10 * cluster + 1] = 0b0000_0100;
+
+        let evidence = SliceEvidence { data: image };
+        let volume = probe(&evidence, 0, evidence.len())
+            .expect("probe")
+            .expect("ntfs volume");
+        assert_eq!(volume.kind, FilesystemKind::Ntfs);
+        assert_eq!(volume.cluster_size, 4096);
+        assert_eq!(
+            volume.allocated,
+            vec![(0, 8 * 4096), (10 * 4096, 11 * 4096)]
+        );
+    }
+
+    fn non_resident_data(start_vcn: u64, real_size: u64, runs: &[u8]) -> Vec<u8> {
+        let mut attr = vec![0u8; 64 + runs.len().div_ceil(8) * 8];
+        let len = attr.len() as u32;
+        attr[0..4].copy_from_slice(&ATTR_DATA.to_le_bytes());
+        attr[4..8].copy_from_slice(&len.to_le_bytes());
+        attr[8] = 1;
+        attr[16..24].copy_from_slice(&start_vcn.to_le_bytes());
+        attr[32..34].copy_from_slice(&64u16.to_le_bytes());
+        attr[48..56].copy_from_slice(&real_size.to_le_bytes());
+        attr[64..64 + runs.len()].copy_from_slice(runs);
+        attr
+    }
+
+    fn file_name_attr(parent: u64, name: &str, namespace: u8) -> Vec<u8> {
+        let units: Vec<u16> = name.encode_utf16().collect();
+        let content_len = 66 + units.len() * 2;
+        let mut attr = vec![0u8; (24 + content_len).div_ceil(8) * 8];
+        let len = attr.len() as u32;
+        attr[0..4].copy_from_slice(&ATTR_FILE_NAME.to_le_bytes());
+        attr[4..8].copy_from_slice(&len.to_le_bytes());
+        attr[16..20].copy_from_slice(&(content_len as u32).to_le_bytes());
+        attr[20..22].copy_from_slice(&24u16.to_le_bytes());
+        attr[24..32].copy_from_slice(&parent.to_le_bytes());
+        attr[24 + 64] = units.len() as u8;
+        attr[24 + 65] = namespace;
+        for (idx, unit) in units.iter().enumerate() {
+            attr[24 + 66 + idx * 2..24 + 68 + idx * 2].copy_from_slice(&unit.to_le_bytes());
+        }
+        attr
+    }
+
+    /// A 1 KiB MFT record with update sequence protection applied.
+    fn mft_record(attrs: &[Vec<u8>], in_use: bool) -> Vec<u8> {
+        let mut record = vec![0u8; 1024];
+        record[0..4].copy_from_slice(b"FILE");
+        record[4..6].copy_from_slice(&48u16.to_le_bytes());
+        record[6..8].copy_from_slice(&3u16.to_le_bytes());
+        record[20..22].copy_from_slice(&56u16.to_le_bytes());
+        record[22] = in_use as u8;
+        let mut at = 56;
+        for attr in attrs {
+            record[at..at + attr.len()].copy_from_slice(attr);
+            at += attr.len();
+        }
+        record[at..at + 4].copy_from_slice(&ATTR_END.to_le_bytes());
+        record[48..50].copy_from_slice(&[0x07, 0x00]);
+        for sector_end in [512, 1024] {
+            let saved = [record[sector_end - 2], record[sector_end - 1]];
+            let slot = 48 + sector_end / 512 * 2;
+            record[slot..slot + 2].copy_from_slice(&saved);
+            record[sector_end - 2..sector_end].copy_from_slice(&[0x07, 0x00]);
+        }
+        record
+    }
+
+    #[test]
+    fn indexes_files_from_mft_records() {
+        // 4 KiB clusters, 64 clusters, 16 MFT records in clusters 4-7.
+        // Record 12 is `/Users/doc.txt` (Users is record 11): 5000 bytes in
+        // clusters 20 and 30. Record 13 is not in use.
+        let cluster = 4096usize;
+        let mut image = vec![0u8; 64 * cluster];
+        image[3..11].copy_from_slice(NTFS_OEM_ID);
+        image[11..13].copy_from_slice(&512u16.to_le_bytes());
+        image[13] = 8;
+        image[40..48].copy_from_slice(&(64u64 * 8).to_le_bytes());
+        image[48..56].copy_from_slice(&4u64.to_le_bytes());
+        image[64] = (-10i8) as u8;
+
+        let records = [
+            (
+                0,
+                mft_record(
+                    &[non_resident_data(0, 16 * 1024, &[0x11, 0x04, 0x04])],
+                    true,
+                ),
+            ),
+            (
+                11,
+                mft_record(&[file_name_attr(ROOT_RECORD, "Users", 1)], true),
+            ),
+            (
+                12,
+                mft_record(
+                    &[
+                        file_name_attr(11, "DOC~1.TXT", NAMESPACE_DOS),
+                        file_name_attr(11, "doc.txt", 1),
+                        non_resident_data(0, 5000, &[0x11, 0x01, 0x14, 0x11, 0x01, 0x0A]),
+                    ],
+                    true,
+                ),
+            ),
+            (
+                13,
+                mft_record(&[non_resident_data(0, 4096, &[0x11, 0x01, 0x28])], false),
+            ),
+        ];
+        for (number, record) in records {
+            let at = 4 * cluster + number * 1024;
+            image[at..at + 1024].copy_from_slice(&record);
+        }
+
+        let evidence = SliceEvidence { data: image };
+        let mut index = FileIndex::default();
+        assert!(super::index(&evidence, 0, evidence.len(), &mut index).expect("index"));
+        // $MFT itself and doc.txt.
+        assert_eq!(index.file_count(), 2);
+        let hit = index.locate(30 * 4096 + 10).expect("second cluster");
+        assert_eq!(hit.path, "/Users/doc.txt");
+        assert_eq!(hit.inode, 12);
+        assert_eq!(hit.offset_in_file, 4106);
+        // Beyond the 5000-byte size, and in the unused record's cluster.
+        assert!(index.locate(30 * 4096 + 1000).is_none());
+        assert!(index.locate(40 * 4096).is_none());
+    }
+}
@@ -0,0 +1,218 @@
+//! Fuzzy hashes of carved files and `--fuzzy-match`.
+//!
+//! Every carved file gets an ssdeep and a TLSH digest, computed while it is
+//! written. A reference list loaded with `--fuzzy-match` holds digests of
+//! either kind, one per line, optionally followed by a name: ssdeep's own
+//! CSV output (`digest,"name"`) and `tlsh` output (`digest<TAB>name`) both
+//! work. Carved files scoring at least the ssdeep threshold, or within the
+//! TLSH distance, against a reference are reported.
+
+pub mod ssdeep;
+pub mod tlsh;
+
+use std::path::Path;
+
+use anyhow::{Context, Result, anyhow};
+
+use ssdeep::Ssdeep;
+use tlsh::Tlsh;
+
+/// ssdeep and TLSH digests of one carved file. TLSH is `None` for inputs
+/// under 50 bytes or with too little variety.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FuzzyDigests {
+    pub ssdeep: Option<String>,
+    pub tlsh: Option<String>,
+}
+
+/// Streams data into both fuzzy hashes, next to the MD5/SHA-256 contexts.
+#[derive(Default)]
+pub struct FuzzyHasher {
+    ssdeep: Ssdeep,
+    tlsh: Tlsh,
+}
+
+impl FuzzyHasher {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn update(&mut self, data: &[u8]) {
+        self.ssdeep.update(data);
+        self.tlsh.update(data);
+    }
+
+    pub fn finish(&self) -> FuzzyDigests {
+        FuzzyDigests {
+            ssdeep: Some(self.ssdeep.finish()),
+            tlsh: self.tlsh.finish(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FuzzyAlgorithm {
+    Ssdeep,
+    Tlsh,
+}
+
+impl FuzzyAlgorithm {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            FuzzyAlgorithm::Ssdeep => "ssdeep",
+            FuzzyAlgorithm::Tlsh => "tlsh",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FuzzyReference {
+    pub name: String,
+    pub algorithm: FuzzyAlgorithm,
+    pub digest: String,
+}
+
+/// A reference a carved file is similar to. `score` is the ssdeep
+/// similarity (0-100, higher is closer) or the TLSH distance (0 is
+/// identical, lower is closer).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FuzzyScore<'a> {
+    pub reference: &'a FuzzyReference,
+    pub score: u32,
+}
+
+/// Reference digests and thresholds for `--fuzzy-match`.
+#[derive(Debug)]
+pub struct FuzzyMatcher {
+    references: Vec<FuzzyReference>,
+    min_ssdeep_score: u32,
+    max_tlsh_distance: u32,
+}
+
+impl FuzzyMatcher {
+    pub fn load(path: &Path, min_ssdeep_score: u32, max_tlsh_distance: u32) -> Result<Self> {
+        let text = std::fs::read_to_string(path)
+            .with_context(|| format!("reading fuzzy hash list {}", path.display()))?;
+        let references = parse_references(&text)
+            .with_context(|| format!("parsing fuzzy hash list {}", path.display()))?;
+        Ok(Self {
+            references,
+            min_ssdeep_score,
+            max_tlsh_distance,
+        })
+    }
+
+    pub fn len(&self) -> usize {
+        self.references.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.references.is_empty()
+    }
+
+    /// References within the thresholds of a carved file's digests.
+    pub fn matches(&self, digests: &FuzzyDigests) -> Vec<FuzzyScore<'_>> {
+        self.references
+            .iter()
+            .filter_map(|reference| {
+                let score = match reference.algorithm {
+                    FuzzyAlgorithm::Ssdeep => {
+                        let score = ssdeep::compare(digests.ssdeep.as_deref()?, &reference.digest);
+                        (score > 0 && score >= self.min_ssdeep_score).then_some(score)?
+                    }
+                    FuzzyAlgorithm::Tlsh => {
+                        let distance = tlsh::distance(digests.tlsh.as_deref()?, &reference.digest)?;
+                        (distance <= self.max_tlsh_distance).then_some(distance)?
+                    }
+                };
+                Some(FuzzyScore { reference, score })
+            })
+            .collect()
+    }
+}
+
+fn parse_references(text: &str) -> Result<Vec<FuzzyReference>> {
+    let mut references = Vec::new();
+    for (index, line) in text.lines().enumerate() {
+        let line = line.trim();
+        // `ssdeep -s` output starts with a format header
+        if line.is_empty() || line.starts_with('#') || line.starts_with("ssdeep,") {
+            continue;
+        }
+        let (digest, name) = match line.split_once(',') {
+            Some((digest, name)) if ssdeep::parse(digest).is_some() => (digest, name),
+            _ => line.split_once(char::is_whitespace).unwrap_or((line, "")),
+        };
+        let algorithm = if ssdeep::parse(digest).is_some() {
+            FuzzyAlgorithm::Ssdeep
+        } else if tlsh::is_digest(digest) {
+            FuzzyAlgorithm::Tlsh
+        } else {
+            return Err(anyhow!(
+                "line {}: {digest:?} is neither an ssdeep nor a TLSH digest",
+                index + 1
+            ));
+        };
+        let name = name.trim().trim_matches('"');
+        references.push(FuzzyReference {
+            name: if name.is_empty() { digest } else { name }.to_string(),
+            algorithm,
+            digest: digest.to_string(),
+        });
+    }
+    Ok(references)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{FuzzyAlgorithm, FuzzyDigests, FuzzyMatcher, parse_references};
+
+    const SSDEEP_A: &str = "3:AXGBicFlgVNhBGcL6wCrFQEv:AXGHsNhxLsr2C";
+    const SSDEEP_B: &str = "3:AXGBicFlIHBGcL6wCrFQEv:AXGH6xLsr2C";
+    const TLSH: &str = "T1A0B012123456789ABCDEF0123456789ABCDEF0123456789ABCDEF0123456789ABCDEF0";
+
+    #[test]
+    fn parses_ssdeep_and_tlsh_lists() {
+        let text = format!(
+            "ssdeep,1.1--blocksize:hash:hash,filename\n\
+             {SSDEEP_A},\"/ref/report.doc\"\n\
+             # comment\n\
+             {TLSH}\tdropper.exe\n\
+             {SSDEEP_B}\n"
+        );
+        let refs = parse_references(&text).expect("parse");
+        assert_eq!(refs.len(), 3);
+        assert_eq!(refs[0].name, "/ref/report.doc");
+        assert_eq!(refs[0].algorithm, FuzzyAlgorithm::Ssdeep);
+        assert_eq!(refs[1].name, "dropper.exe");
+        assert_eq!(refs[1].algorithm, FuzzyAlgorithm::Tlsh);
+        assert_eq!(refs[2].name, SSDEEP_B);
+        assert!(parse_references("3:abc\n").is_err());
+    }
+
+    #[test]
+    fn applies_thresholds() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let path = dir.path().join("refs.txt");
+        std::fs::write(&path, format!("{SSDEEP_B},similar\n{TLSH}\tother\n")).unwrap();
+        let digests = FuzzyDigests {
+            ssdeep: Some(SSDEEP_A.to_string()),
+            tlsh: Some(TLSH.to_string()),
+        };
+
+        let loose = FuzzyMatcher::load(&path, 20, 0).expect("load");
+        let found = loose.matches(&digests);
+        assert_eq!(found.len(), 2);
+        assert_eq!(
+            (found[0].reference.name.as_str(), found[0].score),
+            ("similar", 22)
+        );
+        assert_eq!(
+            (found[1].reference.name.as_str(), found[1].score),
+            ("other", 0)
+        );
+
+        let strict = FuzzyMatcher::load(&path, 50, 0).expect("load");
+        assert_eq!(strict.matches(&digests).len(), 1);
+    }
+}
@@ -0,0 +1,349 @@
+//! Streaming ssdeep (context-triggered piecewise hashing), compatible with
+//! libfuzzy 2.14 `fuzzy_digest` without flags, and `fuzzy_compare`.
+
+const ROLLING_WINDOW: usize = 7;
+const MIN_BLOCKSIZE: u64 = 3;
+const HASH_PRIME: u32 = 0x0100_0193;
+const HASH_INIT: u8 = 0x27;
+const NUM_BLOCKHASHES: usize = 31;
+const SPAMSUM_LENGTH: usize = 64;
+const B64: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+fn block_size(index: usize) -> u64 {
+    MIN_BLOCKSIZE << index
+}
+
+/// FNV-style piece hash; only the low six bits are ever used.
+fn sum_hash(c: u8, h: u8) -> u8 {
+    ((u32::from(h).wrapping_mul(HASH_PRIME) ^ u32::from(c)) & 0x3f) as u8
+}
+
+#[derive(Default)]
+struct RollState {
+    window: [u8; ROLLING_WINDOW],
+    h1: u32,
+    h2: u32,
+    h3: u32,
+    n: usize,
+}
+
+impl RollState {
+    fn hash(&mut self, c: u8) {
+        let c32 = u32::from(c);
+        self.h2 = self.h2.wrapping_sub(self.h1);
+        self.h2 = self.h2.wrapping_add(ROLLING_WINDOW as u32 * c32);
+        self.h1 = self.h1.wrapping_add(c32);
+        self.h1 = self
+            .h1
+            .wrapping_sub(u32::from(self.window[self.n % ROLLING_WINDOW]));
+        self.window[self.n % ROLLING_WINDOW] = c;
+        self.n = self.n.wrapping_add(1);
+        self.h3 = (self.h3 << 5) ^ c32;
+    }
+
+    fn sum(&self) -> u32 {
+        self.h1.wrapping_add(self.h2).wrapping_add(self.h3)
+    }
+}
+
+#[derive(Clone, Copy)]
+struct BlockHash {
+    digest: [u8; SPAMSUM_LENGTH],
+    dindex: usize,
+    half_digest: u8,
+    h: u8,
+    half_h: u8,
+}
+
+impl BlockHash {
+    fn new() -> Self {
+        Self {
+            digest: [0; SPAMSUM_LENGTH],
+            dindex: 0,
+            half_digest: 0,
+            h: HASH_INIT,
+            half_h: HASH_INIT,
+        }
+    }
+}
+
+/// Incremental ssdeep state. Every candidate block size is tracked at once,
+/// so the input is never buffered; block sizes that can no longer be chosen
+/// are dropped as the input grows.
+pub struct Ssdeep {
+    total_size: u64,
+    bh_start: usize,
+    bh_end: usize,
+    bh: [BlockHash; NUM_BLOCKHASHES],
+    roll: RollState,
+    last_h: u8,
+    need_last_h: bool,
+}
+
+impl Default for Ssdeep {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Ssdeep {
+    pub fn new() -> Self {
+        Self {
+            total_size: 0,
+            bh_start: 0,
+            bh_end: 1,
+            bh: [BlockHash::new(); NUM_BLOCKHASHES],
+            roll: RollState::default(),
+            last_h: HASH_INIT,
+            need_last_h: false,
+        }
+    }
+
+    pub fn update(&mut self, data: &[u8]) {
+        self.total_size = self.total_size.saturating_add(data.len() as u64);
+        for &c in data {
+            self.step(c);
+        }
+    }
+
+    fn step(&mut self, c: u8) {
+        self.roll.hash(c);
+        let trigger = u64::from(self.roll.sum()) + 1;
+        for bh in &mut self.bh[self.bh_start..self.bh_end] {
+            bh.h = sum_hash(c, bh.h);
+            bh.half_h = sum_hash(c, bh.half_h);
+        }
+        if self.need_last_h {
+            self.last_h = sum_hash(c, self.last_h);
+        }
+
+        // A reset point for block size b is where the rolling sum is b - 1;
+        // block sizes double, so the larger ones can only trigger if the
+        // smaller ones did.
+        let mut i = self.bh_start;
+        while i < self.bh_end && trigger.is_multiple_of(block_size(i)) {
+            if self.bh[i].dindex == 0 {
+                self.try_fork();
+            }
+            let bh = &mut self.bh[i];
+            bh.digest[bh.dindex] = B64[usize::from(bh.h)];
+            bh.half_digest = B64[usize::from(bh.half_h)];
+            if bh.dindex < SPAMSUM_LENGTH - 1 {
+                // Only reset while there is room, so the tail of a long
+                // input folds into the last digest character.
+                bh.dindex += 1;
+                bh.digest[bh.dindex] = 0;
+                bh.h = HASH_INIT;
+                if bh.dindex < SPAMSUM_LENGTH / 2 {
+                    bh.half_h = HASH_INIT;
+                    bh.half_digest = 0;
+                }
+            } else {
+                self.try_reduce();
+            }
+            i += 1;
+        }
+    }
+
+    fn try_fork(&mut self) {
+        let last = self.bh[self.bh_end - 1];
+        if self.bh_end < NUM_BLOCKHASHES {
+            let next = &mut self.bh[self.bh_end];
+            *next = BlockHash::new();
+            next.h = last.h;
+            next.half_h = last.half_h;
+            self.bh_end += 1;
+        } else if !self.need_last_h {
+            self.need_last_h = true;
+            self.last_h = last.h;
+        }
+    }
+
+    fn try_reduce(&mut self) {
+        if self.bh_end - self.bh_start < 2
+            || block_size(self.bh_start) * SPAMSUM_LENGTH as u64 >= self.total_size
+            || self.bh[self.bh_start + 1].dindex < SPAMSUM_LENGTH / 2
+        {
+            return;
+        }
+        self.bh_start += 1;
+    }
+
+    /// `blocksize:digest:double_blocksize_digest`.
+    pub fn finish(&self) -> String {
+        let roll_sum = self.roll.sum();
+        let mut bi = self.bh_start;
+        while bi < NUM_BLOCKHASHES - 1 && block_size(bi) * (SPAMSUM_LENGTH as u64) < self.total_size
+        {
+            bi += 1;
+        }
+        while bi >= self.bh_end {
+            bi -= 1;
+        }
+        while bi > self.bh_start && self.bh[bi].dindex < SPAMSUM_LENGTH / 2 {
+            bi -= 1;
+        }
+
+        let mut out = format!("{}:", block_size(bi));
+        let bh = &self.bh[bi];
+        out.extend(bh.digest[..bh.dindex].iter().map(|&b| b as char));
+        if roll_sum != 0 {
+            out.push(B64[usize::from(bh.h)] as char);
+        } else if bh.digest[bh.dindex] != 0 {
+            out.push(bh.digest[bh.dindex] as char);
+        }
+        out.push(':');
+
+        if bi < self.bh_end - 1 {
+            let bh = &self.bh[bi + 1];
+            let len = bh.dindex.min(SPAMSUM_LENGTH / 2 - 1);
+            out.extend(bh.digest[..len].iter().map(|&b| b as char));
+            if roll_sum != 0 {
+                out.push(B64[usize::from(bh.half_h)] as char);
+            } else if bh.half_digest != 0 {
+                out.push(bh.half_digest as char);
+            }
+        } else if roll_sum != 0 {
+            let h = if bi == 0 { self.bh[bi].h } else { self.last_h };
+            out.push(B64[usize::from(h)] as char);
+        }
+        out
+    }
+}
+
+/// Parse `blocksize:digest1:digest2`.
+pub fn parse(digest: &str) -> Option<(u64, &str, &str)> {
+    let mut parts = digest.splitn(3, ':');
+    let block_size = parts.next()?.parse().ok()?;
+    let first = parts.next()?;
+    let second = parts.next()?;
+    let valid = |s: &str| s.len() <= SPAMSUM_LENGTH && s.bytes().all(|b| B64.contains(&b));
+    (valid(first) && valid(second)).then_some((block_size, first, second))
+}
+
+/// Similarity of two ssdeep digests from 0 (unrelated) to 100, as computed
+/// by `fuzzy_compare`. Malformed digests score 0.
+pub fn compare(a: &str, b: &str) -> u32 {
+    let (Some((bs_a, a1, a2)), Some((bs_b, b1, b2))) = (parse(a), parse(b)) else {
+        return 0;
+    };
+    if bs_a != bs_b && bs_a != bs_b.saturating_mul(2) && bs_b != bs_a.saturating_mul(2) {
+        return 0;
+    }
+    let (a1, a2) = (eliminate_sequences(a1), eliminate_sequences(a2));
+    let (b1, b2) = (eliminate_sequences(b1), eliminate_sequences(b2));
+    if bs_a == bs_b && a1 == b1 {
+        return 100;
+    }
+    if bs_a == bs_b {
+        score_strings(&a1, &b1, bs_a).max(score_strings(&a2, &b2, bs_a * 2))
+    } else if bs_a == bs_b * 2 {
+        score_strings(&a1, &b2, bs_a)
+    } else {
+        score_strings(&a2, &b1, bs_b)
+    }
+}
+
+/// Collapse runs of more than three identical characters to three.
+fn eliminate_sequences(s: &str) -> Vec<u8> {
+    let mut out: Vec<u8> = Vec::with_capacity(s.len());
+    for b in s.bytes() {
+        if out.len() < 3 || out[out.len() - 3..].iter().any(|&prev| prev != b) {
+            out.push(b);
+        }
+    }
+    out
+}
+
+fn score_strings(a: &[u8], b: &[u8], block_size: u64) -> u32 {
+    if a.len() > SPAMSUM_LENGTH || b.len() > SPAMSUM_LENGTH || !has_common_substring(a, b) {
+        return 0;
+    }
+    let distance = edit_distance(a, b) as u64;
+    let scaled = distance * SPAMSUM_LENGTH as u64 / (a.len() + b.len()) as u64;
+    let scaled = 100 * scaled / SPAMSUM_LENGTH as u64;
+    if scaled >= 100 {
+        return 0;
+    }
+    let mut score = 100 - scaled;
+    // Small block sizes cannot claim high similarity on short digests.
+    let cap_limit = (99 + ROLLING_WINDOW as u64) / ROLLING_WINDOW as u64 * MIN_BLOCKSIZE;
+    if block_size < cap_limit {
+        let cap = block_size / MIN_BLOCKSIZE * a.len().min(b.len()) as u64;
+        score = score.min(cap);
+    }
+    score as u32
+}
+
+fn has_common_substring(a: &[u8], b: &[u8]) -> bool {
+    if a.len() < ROLLING_WINDOW || b.len() < ROLLING_WINDOW {
+        return false;
+    }
+    a.windows(ROLLING_WINDOW)
+        .any(|wa| b.windows(ROLLING_WINDOW).any(|wb| wa == wb))
+}
+
+/// Levenshtein distance with insert/delete cost 1 and substitution cost 2.
+fn edit_distance(a: &[u8], b: &[u8]) -> usize {
+    let mut prev: Vec<usize> = (0..=b.len()).collect();
+    let mut cur = vec![0usize; b.len() + 1];
+    for (i, &ca) in a.iter().enumerate() {
+        cur[0] = i + 1;
+        for (j, &cb) in b.iter().enumerate() {
+            let replace = prev[j] + if ca == cb { 0 } else { 2 };
+            cur[j + 1] = replace.min(prev[j + 1] + 1).min(cur[j] + 1);
+        }
+        std::mem::swap(&mut prev, &mut cur);
+    }
+    prev[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Ssdeep, compare};
+
+    fn hash(data: &[u8]) -> String {
+        let mut state = Ssdeep::new();
+        state.update(data);
+        state.finish()
+    }
+
+    #[test]
+    fn matches_reference_digests() {
+        assert_eq!(hash(b""), "3::");
+        assert_eq!(
+            hash(b"Also called fuzzy hashes, Ctph can match inputs that have homologies."),
+            "3:AXGBicFlgVNhBGcL6wCrFQEv:AXGHsNhxLsr2C"
+        );
+        assert_eq!(
+            hash(b"Also called fuzzy hashes, CTPH can match inputs that have homologies."),
+            "3:AXGBicFlIHBGcL6wCrFQEv:AXGH6xLsr2C"
+        );
+    }
+
+    #[test]
+    fn compares_like_libfuzzy() {
+        let a = "3:AXGBicFlgVNhBGcL6wCrFQEv:AXGHsNhxLsr2C";
+        let b = "3:AXGBicFlIHBGcL6wCrFQEv:AXGH6xLsr2C";
+        assert_eq!(compare(a, b), 22);
+        assert_eq!(compare(a, a), 100);
+        assert_eq!(compare(a, "96:AXGBicFlgVNhBGcL6wCrFQEv:AXGHsNhxLsr2C"), 0);
+        assert_eq!(compare(a, "not a digest"), 0);
+    }
+
+    #[test]
+    fn streaming_matches_one_shot() {
+        let data: Vec<u8> = (0..200_000u32)
+            .map(|i| (i.wrapping_mul(2_654_435_761) >> 13) as u8)
+            .collect();
+        let mut state = Ssdeep::new();
+        for chunk in data.chunks(4093) {
+            state.update(chunk);
+        }
+        assert_eq!(state.finish(), hash(&data));
+
+        let mut edited = data.clone();
+        edited[100_000..100_400].fill(0);
+        assert!(compare(&hash(&data), &hash(&edited)) > 80);
+    }
+}
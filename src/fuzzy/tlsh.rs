@@ -0,0 +1,262 @@
+//! Streaming TLSH (128 buckets, 1-byte checksum), producing the `T1` hex
+//! digests of the reference implementation and its distance score.
+
+const WINDOW: usize = 5;
+const BUCKETS: usize = 128;
+const CODE_SIZE: usize = 32;
+/// Inputs shorter than this get no digest.
+pub const MIN_DATA_LENGTH: u64 = 50;
+
+/// Pearson permutation used by the reference implementation.
+const V_TABLE: [u8; 256] = [
+    1, 87, 49, 12, 176, 178, 102, 166, 121, 193, 6, 84, 249, 230, 44, 163, 14, 197, 213, 181, 161,
+    85, 218, 80, 64, 239, 24, 226, 236, 142, 38, 200, 110, 177, 104, 103, 141, 253, 255, 50, 77,
+    101, 81, 18, 45, 96, 31, 222, 25, 107, 190, 70, 86, 237, 240, 34, 72, 242, 20, 214, 244, 227,
+    149, 235, 97, 234, 57, 22, 60, 250, 82, 175, 208, 5, 127, 199, 111, 62, 135, 248, 174, 169,
+    211, 58, 66, 154, 106, 195, 245, 171, 17, 187, 182, 179, 0, 243, 132, 56, 148, 75, 128, 133,
+    158, 100, 130, 126, 91, 13, 153, 246, 216, 219, 119, 68, 223, 78, 83, 88, 201, 99, 122, 11, 92,
+    32, 136, 114, 52, 10, 138, 30, 48, 183, 156, 35, 61, 26, 143, 74, 251, 94, 129, 162, 63, 152,
+    170, 7, 115, 167, 241, 206, 3, 150, 55, 59, 151, 220, 90, 53, 23, 131, 125, 173, 15, 238, 79,
+    95, 89, 16, 105, 137, 225, 224, 217, 160, 37, 123, 118, 73, 2, 157, 46, 116, 9, 145, 134, 228,
+    207, 212, 202, 215, 69, 229, 27, 188, 67, 124, 168, 252, 42, 4, 29, 108, 21, 247, 19, 205, 39,
+    203, 233, 40, 186, 147, 198, 192, 155, 33, 164, 191, 98, 204, 165, 180, 117, 76, 140, 36, 210,
+    172, 41, 54, 159, 8, 185, 232, 113, 196, 231, 47, 146, 120, 51, 65, 28, 144, 254, 221, 93, 189,
+    194, 139, 112, 43, 71, 109, 184, 209,
+];
+
+fn b_mapping(salt: u8, i: u8, j: u8, k: u8) -> u8 {
+    let h = V_TABLE[usize::from(salt)];
+    let h = V_TABLE[usize::from(h ^ i)];
+    let h = V_TABLE[usize::from(h ^ j)];
+    V_TABLE[usize::from(h ^ k)]
+}
+
+/// Incremental TLSH state: a five-byte sliding window feeding the bucket
+/// counts and the checksum.
+pub struct Tlsh {
+    buckets: [u64; 256],
+    window: [u8; WINDOW],
+    checksum: u8,
+    len: u64,
+}
+
+impl Default for Tlsh {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Tlsh {
+    pub fn new() -> Self {
+        Self {
+            buckets: [0; 256],
+            window: [0; WINDOW],
+            checksum: 0,
+            len: 0,
+        }
+    }
+
+    pub fn update(&mut self, data: &[u8]) {
+        for &byte in data {
+            let j = (self.len % WINDOW as u64) as usize;
+            self.window[j] = byte;
+            if self.len >= WINDOW as u64 - 1 {
+                let w = |back: usize| self.window[(j + WINDOW - back) % WINDOW];
+                let (w0, w1, w2, w3, w4) = (w(0), w(1), w(2), w(3), w(4));
+                self.checksum = b_mapping(0, w0, w1, self.checksum);
+                for (salt, a, b) in [
+                    (2, w1, w2),
+                    (3, w1, w3),
+                    (5, w2, w3),
+                    (7, w2, w4),
+                    (11, w1, w4),
+                    (13, w3, w4),
+                ] {
+                    self.buckets[usize::from(b_mapping(salt, w0, a, b))] += 1;
+                }
+            }
+            self.len += 1;
+        }
+    }
+
+    /// `T1` followed by 70 hex digits, or `None` when the input is shorter
+    /// than [`MIN_DATA_LENGTH`] or too uniform to fill half the buckets.
+    pub fn finish(&self) -> Option<String> {
+        if self.len < MIN_DATA_LENGTH {
+            return None;
+        }
+        let used = &self.buckets[..BUCKETS];
+        if used.iter().filter(|&&count| count > 0).count() <= BUCKETS / 2 {
+            return None;
+        }
+        let mut sorted = used.to_vec();
+        sorted.sort_unstable();
+        let (q1, q2, q3) = (sorted[31], sorted[63], sorted[95]);
+
+        let mut code = [0u8; CODE_SIZE];
+        for (i, byte) in code.iter_mut().enumerate() {
+            for j in 0..4 {
+                let count = used[4 * i + j];
+                let quartile = if q3 < count {
+                    3
+                } else if q2 < count {
+                    2
+                } else if q1 < count {
+                    1
+                } else {
+                    0
+                };
+                *byte += quartile << (j * 2);
+            }
+        }
+        let q1_ratio = ((q1 * 100) as f32 / q3 as f32) as u64 % 16;
+        let q2_ratio = ((q2 * 100) as f32 / q3 as f32) as u64 % 16;
+
+        let mut digest = Vec::with_capacity(3 + CODE_SIZE);
+        digest.push(swap_nibbles(self.checksum));
+        digest.push(swap_nibbles(l_capturing(self.len)));
+        digest.push(((q1_ratio << 4) | q2_ratio) as u8);
+        digest.extend(code.iter().rev());
+        Some(format!("T1{}", hex::encode_upper(digest)))
+    }
+}
+
+fn swap_nibbles(byte: u8) -> u8 {
+    byte.rotate_left(4)
+}
+
+/// Logarithmic length bucket.
+fn l_capturing(len: u64) -> u8 {
+    let log = (len as f64).ln();
+    let value = if len <= 656 {
+        (log / 0.405_465_1).floor()
+    } else if len <= 3199 {
+        (log / 0.262_364_26 - 8.727_77).floor()
+    } else {
+        (log / 0.095_310_18 - 62.547_2).floor()
+    };
+    (value as i64 & 0xff) as u8
+}
+
+struct Parsed {
+    checksum: u8,
+    l_value: u8,
+    q1_ratio: u8,
+    q2_ratio: u8,
+    code: [u8; CODE_SIZE],
+}
+
+fn parse(digest: &str) -> Option<Parsed> {
+    let hex_part = digest.strip_prefix("T1").unwrap_or(digest);
+    let mut bytes = [0u8; 3 + CODE_SIZE];
+    hex::decode_to_slice(hex_part, &mut bytes).ok()?;
+    let mut code = [0u8; CODE_SIZE];
+    for (i, byte) in bytes[3..].iter().rev().enumerate() {
+        code[i] = *byte;
+    }
+    Some(Parsed {
+        checksum: swap_nibbles(bytes[0]),
+        l_value: swap_nibbles(bytes[1]),
+        q1_ratio: bytes[2] >> 4,
+        q2_ratio: bytes[2] & 0x0f,
+        code,
+    })
+}
+
+/// Whether `digest` is a TLSH digest this module can compare.
+pub fn is_digest(digest: &str) -> bool {
+    parse(digest).is_some()
+}
+
+fn mod_diff(x: u8, y: u8, range: u32) -> u32 {
+    let dl = u32::from(x.abs_diff(y));
+    dl.min(range - dl)
+}
+
+/// TLSH distance including the length component: 0 for identical digests,
+/// growing as inputs differ. `None` if either digest is malformed.
+pub fn distance(a: &str, b: &str) -> Option<u32> {
+    let (a, b) = (parse(a)?, parse(b)?);
+    let mut diff = match mod_diff(a.l_value, b.l_value, 256) {
+        0 => 0,
+        1 => 1,
+        ldiff => ldiff * 12,
+    };
+    for (x, y) in [(a.q1_ratio, b.q1_ratio), (a.q2_ratio, b.q2_ratio)] {
+        let qdiff = mod_diff(x, y, 16);
+        diff += if qdiff <= 1 { qdiff } else { (qdiff - 1) * 12 };
+    }
+    if a.checksum != b.checksum {
+        diff += 1;
+    }
+    for (x, y) in a.code.iter().zip(b.code.iter()) {
+        for shift in (0..8).step_by(2) {
+            let d = ((x >> shift) & 3).abs_diff((y >> shift) & 3);
+            diff += if d == 3 { 6 } else { u32::from(d) };
+        }
+    }
+    Some(diff)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Tlsh, V_TABLE, distance};
+
+    fn hash(data: &[u8]) -> Option<String> {
+        let mut state = Tlsh::new();
+        state.update(data);
+        state.finish()
+    }
+
+    fn sample(len: usize) -> Vec<u8> {
+        (0..len as u32)
+            .map(|i| (i.wrapping_mul(2_654_435_761) >> 11) as u8)
+            .collect()
+    }
+
+    #[test]
+    fn pearson_table_is_a_permutation() {
+        let mut seen = [false; 256];
+        for &v in &V_TABLE {
+            assert!(!seen[usize::from(v)]);
+            seen[usize::from(v)] = true;
+        }
+    }
+
+    #[test]
+    fn short_or_uniform_input_has_no_digest() {
+        assert_eq!(hash(&sample(49)), None);
+        assert_eq!(hash(&[0u8; 10_000]), None);
+    }
+
+    #[test]
+    fn similar_inputs_are_close() {
+        let data = sample(20_000);
+        let digest = hash(&data).expect("digest");
+        assert_eq!(digest.len(), 72);
+        assert!(digest.starts_with("T1"));
+
+        let mut state = Tlsh::new();
+        for chunk in data.chunks(777) {
+            state.update(chunk);
+        }
+        assert_eq!(state.finish().as_deref(), Some(digest.as_str()));
+        assert_eq!(distance(&digest, &digest), Some(0));
+
+        let mut edited = data.clone();
+        edited[5_000..5_200].fill(0x41);
+        let close = distance(&digest, &hash(&edited).unwrap()).unwrap();
+        let far = distance(
+            &digest,
+            &hash(
+                b"completely different text, repeated "
+                    .repeat(500)
+                    .as_slice(),
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        assert!(close < far, "close={close} far={far}");
+        assert_eq!(distance(&digest, "T1XYZ"), None);
+    }
+}
@@ -0,0 +1,579 @@
+//! Block hash database for `--hash-db`.
+//!
+//! `swiftbeaver hashdb build` splits every file under a reference directory
+//! into fixed-size blocks and stores the MD5 of each full block in an SQLite
+//! database. During a run each aligned block of the evidence is hashed and
+//! looked up, so fragments of reference files are found even when the files
+//! cannot be carved whole. Blocks filled with a single byte value match far
+//! too often to prove anything and are left out on both sides.
+
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result, anyhow};
+use rusqlite::{Connection, OpenFlags, params};
+
+use crate::metadata::BlockHashMatch;
+use crate::partition::Partition;
+
+/// Block size used by `hashdb build` unless overridden.
+pub const DEFAULT_BLOCK_SIZE: usize = 4096;
+
+/// Counts reported by [`build`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct BuildSummary {
+    pub files: u64,
+    pub blocks: u64,
+    /// Constant-filled blocks left out of the database.
+    pub skipped_blocks: u64,
+}
+
+/// Hash the files under `source` into a new database at `db_path`,
+/// replacing any existing file.
+pub fn build(source: &Path, db_path: &Path, block_size: usize) -> Result<BuildSummary> {
+    if block_size == 0 {
+        return Err(anyhow!("block size must be greater than zero"));
+    }
+    let mut files = Vec::new();
+    collect_files(source, &mut files)
+        .with_context(|| format!("listing reference files under {}", source.display()))?;
+    files.sort();
+
+    if db_path.exists() {
+        std::fs::remove_file(db_path)
+            .with_context(|| format!("replacing {}", db_path.display()))?;
+    }
+    let mut conn = Connection::open(db_path)
+        .with_context(|| format!("creating hash database {}", db_path.display()))?;
+    conn.execute_batch(
+        "CREATE TABLE meta (key TEXT PRIMARY KEY, value TEXT NOT NULL);
+         CREATE TABLE files (id INTEGER PRIMARY KEY, path TEXT NOT NULL, size INTEGER NOT NULL);
+         CREATE TABLE blocks (hash BLOB NOT NULL, file_id INTEGER NOT NULL, block_index INTEGER NOT NULL);",
+    )?;
+
+    let mut summary = BuildSummary::default();
+    let tx = conn.transaction()?;
+    {
+        let mut meta = tx.prepare("INSERT INTO meta (key, value) VALUES (?1, ?2)")?;
+        meta.execute(params!["block_size", block_size.to_string()])?;
+        meta.execute(params!["hash", "md5"])?;
+        meta.execute(params!["tool_version", env!("CARGO_PKG_VERSION")])?;
+
+        let mut insert_file =
+            tx.prepare("INSERT INTO files (id, path, size) VALUES (?1, ?2, ?3)")?;
+        let mut insert_block =
+            tx.prepare("INSERT INTO blocks (hash, file_id, block_index) VALUES (?1, ?2, ?3)")?;
+        let mut block = vec![0u8; block_size];
+        for (id, path) in files.iter().enumerate() {
+            let mut file =
+                File::open(path).with_context(|| format!("opening {}", path.display()))?;
+            let size = file.metadata()?.len();
+            let relative = path.strip_prefix(source).unwrap_or(path);
+            insert_file.execute(params![id as i64, relative.to_string_lossy(), size as i64])?;
+            summary.files += 1;
+
+            let mut index = 0i64;
+            // A trailing partial block is never hashed
+            while read_block(&mut file, &mut block)
+                .with_context(|| format!("reading {}", path.display()))?
+            {
+                if is_constant(&block) {
+                    summary.skipped_blocks += 1;
+                } else {
+                    let digest = md5::compute(&block);
+                    insert_block.execute(params![&digest.0[..], id as i64, index])?;
+                    summary.blocks += 1;
+                }
+                index += 1;
+            }
+        }
+    }
+    tx.commit()?;
+    Ok(summary)
+}
+
+fn collect_files(dir: &Path, out: &mut Vec<PathBuf>) -> std::io::Result<()> {
+    for entry in std::fs::read_dir(dir)? {
+        let entry = entry?;
+        let file_type = entry.file_type()?;
+        if file_type.is_dir() {
+            collect_files(&entry.path(), out)?;
+        } else if file_type.is_file() {
+            out.push(entry.path());
+        }
+    }
+    Ok(())
+}
+
+/// Fill `buf` completely; `false` at end of file or on a short final block.
+fn read_block(file: &mut File, buf: &mut [u8]) -> std::io::Result<bool> {
+    let mut filled = 0;
+    while filled < buf.len() {
+        let n = file.read(&mut buf[filled..])?;
+        if n == 0 {
+            return Ok(false);
+        }
+        filled += n;
+    }
+    Ok(true)
+}
+
+fn is_constant(block: &[u8]) -> bool {
+    block.iter().all(|&b| b == block[0])
+}
+
+/// A run of consecutive evidence blocks matching consecutive blocks of one
+/// reference file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BlockRun {
+    pub file_id: u32,
+    pub global_start: u64,
+    pub block_index: u64,
+    pub block_count: u64,
+}
+
+/// Block hashes loaded into memory for lookups.
+pub struct HashDb {
+    block_size: usize,
+    files: Vec<String>,
+    blocks: HashMap<[u8; 16], Vec<(u32, u64)>>,
+}
+
+impl std::fmt::Debug for HashDb {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("HashDb")
+            .field("block_size", &self.block_size)
+            .field("files", &self.files.len())
+            .field("hashes", &self.blocks.len())
+            .finish()
+    }
+}
+
+impl HashDb {
+    /// Load a database written by [`build`].
+    pub fn load(path: &Path) -> Result<Self> {
+        let conn = Connection::open_with_flags(path, OpenFlags::SQLITE_OPEN_READ_ONLY)
+            .with_context(|| format!("opening hash database {}", path.display()))?;
+        let hash: String = conn
+            .query_row("SELECT value FROM meta WHERE key = 'hash'", [], |row| {
+                row.get(0)
+            })
+            .context("reading hash database metadata")?;
+        if hash != "md5" {
+            return Err(anyhow!("unsupported block hash `{hash}`"));
+        }
+        let block_size: String = conn.query_row(
+            "SELECT value FROM meta WHERE key = 'block_size'",
+            [],
+            |row| row.get(0),
+        )?;
+        let block_size: usize = block_size
+            .parse()
+            .ok()
+            .filter(|&size| size > 0)
+            .ok_or_else(|| anyhow!("invalid block size `{block_size}`"))?;
+
+        let mut files = Vec::new();
+        let mut stmt = conn.prepare("SELECT id, path FROM files ORDER BY id")?;
+        let rows = stmt.query_map([], |row| {
+            Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?))
+        })?;
+        for row in rows {
+            let (id, path) = row?;
+            if id as usize != files.len() {
+                return Err(anyhow!("hash database file ids are not contiguous"));
+            }
+            files.push(path);
+        }
+
+        let mut blocks: HashMap<[u8; 16], Vec<(u32, u64)>> = HashMap::new();
+        let mut stmt = conn.prepare("SELECT hash, file_id, block_index FROM blocks")?;
+        let mut rows = stmt.query([])?;
+        while let Some(row) = rows.next()? {
+            let hash: Vec<u8> = row.get(0)?;
+            let hash: [u8; 16] = hash
+                .try_into()
+                .map_err(|_| anyhow!("hash database holds a non-MD5 hash"))?;
+            let file_id: i64 = row.get(1)?;
+            let block_index: i64 = row.get(2)?;
+            if file_id < 0 || file_id as usize >= files.len() {
+                return Err(anyhow!("block refers to unknown file id {file_id}"));
+            }
+            blocks
+                .entry(hash)
+                .or_default()
+                .push((file_id as u32, block_index as u64));
+        }
+
+        Ok(Self {
+            block_size,
+            files,
+            blocks,
+        })
+    }
+
+    pub fn block_size(&self) -> usize {
+        self.block_size
+    }
+
+    pub fn file_count(&self) -> usize {
+        self.files.len()
+    }
+
+    pub fn hash_count(&self) -> usize {
+        self.blocks.len()
+    }
+
+    /// Reference path (relative to the build directory) of a file id.
+    pub fn file_path(&self, file_id: u32) -> &str {
+        &self.files[file_id as usize]
+    }
+
+    /// Hash the blocks of `data` (evidence bytes from `start`) that begin
+    /// before `valid_end`, aligned to the start of their partition or of
+    /// the evidence, and return the matches as merged runs.
+    pub fn match_blocks(
+        &self,
+        start: u64,
+        data: &[u8],
+        valid_end: u64,
+        partitions: &[Partition],
+    ) -> Vec<BlockRun> {
+        let size = self.block_size as u64;
+        let data_end = start + data.len() as u64;
+        let mut runs = Vec::new();
+        let mut pos = start;
+        while pos < valid_end {
+            // Blocks never cross a partition boundary
+            let (base, region_end) = match partitions.iter().find(|p| p.contains(pos)) {
+                Some(partition) => (partition.start, partition.end()),
+                None => (
+                    0,
+                    partitions
+                        .iter()
+                        .map(|p| p.start)
+                        .filter(|&s| s > pos)
+                        .min()
+                        .unwrap_or(u64::MAX),
+                ),
+            };
+            let mut block = base + (pos - base).div_ceil(size) * size;
+            while block < valid_end.min(region_end) {
+                let block_end = block + size;
+                if block_end > region_end || block_end > data_end {
+                    break;
+                }
+                let bytes = &data[(block - start) as usize..(block_end - start) as usize];
+                if !is_constant(bytes)
+                    && let Some(entries) = self.blocks.get(&md5::compute(bytes).0)
+                {
+                    for &(file_id, block_index) in entries {
+                        runs.push(BlockRun {
+                            file_id,
+                            global_start: block,
+                            block_index,
+                            block_count: 1,
+                        });
+                    }
+                }
+                block = block_end;
+            }
+            pos = region_end.min(valid_end);
+        }
+        merge_block_runs(runs, size)
+    }
+}
+
+/// Join runs that continue each other in both the evidence and the same
+/// reference file.
+pub fn merge_block_runs(mut runs: Vec<BlockRun>, block_size: u64) -> Vec<BlockRun> {
+    runs.sort_by_key(|run| (run.file_id, run.global_start, run.block_index));
+    let mut out: Vec<BlockRun> = Vec::with_capacity(runs.len());
+    // (file, next evidence offset, next block index) -> run awaiting it
+    let mut open: HashMap<(u32, u64, u64), usize> = HashMap::new();
+    for run in runs {
+        let key = (run.file_id, run.global_start, run.block_index);
+        let idx = match open.remove(&key) {
+            Some(idx) => {
+                out[idx].block_count += run.block_count;
+                idx
+            }
+            None => {
+                out.push(run);
+                out.len() - 1
+            }
+        };
+        let merged = out[idx];
+        open.insert(
+            (
+                merged.file_id,
+                merged.global_start + merged.block_count * block_size,
+                merged.block_index + merged.block_count,
+            ),
+            idx,
+        );
+    }
+    out
+}
+
+/// A run found in one chunk, with whether it reaches the edges of the
+/// chunk's valid region and so may continue in the neighbouring chunk.
+#[derive(Debug, Clone)]
+pub struct ChunkRun {
+    pub record: BlockHashMatch,
+    pub open_before: bool,
+    pub open_after: bool,
+}
+
+/// Joins the per-chunk records of a run into whole ranges as they arrive.
+/// Runs inside their chunk are complete when found; only those reaching a
+/// chunk edge wait here for the piece that continues them.
+#[derive(Default)]
+pub struct RunJoiner {
+    open: HashMap<u64, ChunkRun>,
+    next_id: u64,
+    /// `(source, global_start, block_index)` of runs open before.
+    by_start: HashMap<(String, u64, u64), u64>,
+    /// `(source, next evidence offset, next block index)` of runs open after.
+    by_end: HashMap<(String, u64, u64), u64>,
+}
+
+impl RunJoiner {
+    /// Add a chunk's run; returns it, joined with any waiting neighbours,
+    /// once both of its ends are closed.
+    pub fn push(&mut self, mut run: ChunkRun) -> Option<BlockHashMatch> {
+        if run.open_before
+            && let Some(id) = self.by_end.remove(&start_key(&run.record))
+            && let Some(prev) = self.take(id)
+        {
+            run = join(prev, run);
+        }
+        if run.open_after
+            && let Some(id) = self.by_start.remove(&end_key(&run.record))
+            && let Some(next) = self.take(id)
+        {
+            run = join(run, next);
+        }
+        if !run.open_before && !run.open_after {
+            return Some(run.record);
+        }
+        let id = self.next_id;
+        self.next_id += 1;
+        if run.open_before {
+            self.by_start.insert(start_key(&run.record), id);
+        }
+        if run.open_after {
+            self.by_end.insert(end_key(&run.record), id);
+        }
+        self.open.insert(id, run);
+        None
+    }
+
+    /// Runs still waiting at the end of the evidence, by offset.
+    pub fn finish(self) -> Vec<BlockHashMatch> {
+        let mut runs: Vec<BlockHashMatch> = self.open.into_values().map(|run| run.record).collect();
+        runs.sort_by(|a, b| {
+            (a.global_start, &a.source_file).cmp(&(b.global_start, &b.source_file))
+        });
+        runs
+    }
+
+    fn take(&mut self, id: u64) -> Option<ChunkRun> {
+        let run = self.open.remove(&id)?;
+        if run.open_before {
+            self.by_start.remove(&start_key(&run.record));
+        }
+        if run.open_after {
+            self.by_end.remove(&end_key(&run.record));
+        }
+        Some(run)
+    }
+}
+
+fn start_key(m: &BlockHashMatch) -> (String, u64, u64) {
+    (m.source_file.clone(), m.global_start, m.block_index)
+}
+
+fn end_key(m: &BlockHashMatch) -> (String, u64, u64) {
+    (
+        m.source_file.clone(),
+        m.global_start + m.length,
+        m.block_index + m.block_count,
+    )
+}
+
+fn join(first: ChunkRun, second: ChunkRun) -> ChunkRun {
+    let mut record = first.record;
+    record.length += second.record.length;
+    record.block_count += second.record.block_count;
+    ChunkRun {
+        record,
+        open_before: first.open_before,
+        open_after: second.open_after,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{BlockRun, ChunkRun, HashDb, RunJoiner, build, merge_block_runs};
+    use crate::metadata::BlockHashMatch;
+    use crate::partition::{Partition, PartitionScheme};
+
+    fn block(seed: u8) -> Vec<u8> {
+        (0..512u32)
+            .map(|i| (i as u8).wrapping_mul(seed) ^ seed)
+            .collect()
+    }
+
+    fn reference_db() -> (tempfile::TempDir, HashDb) {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let source = dir.path().join("refs");
+        std::fs::create_dir_all(source.join("nested")).expect("mkdir");
+        let mut doc = Vec::new();
+        for seed in 1..=4 {
+            doc.extend(block(seed));
+        }
+        doc.extend(vec![0u8; 512]);
+        doc.extend(&block(9)[..100]);
+        std::fs::write(source.join("doc.bin"), &doc).expect("write");
+        std::fs::write(source.join("nested").join("other.bin"), block(7)).expect("write");
+
+        let db_path = dir.path().join("known.hashdb");
+        let summary = build(&source, &db_path, 512).expect("build");
+        assert_eq!(summary.files, 2);
+        assert_eq!(summary.blocks, 5);
+        assert_eq!(summary.skipped_blocks, 1);
+        let db = HashDb::load(&db_path).expect("load");
+        (dir, db)
+    }
+
+    #[test]
+    fn builds_and_matches_merged_runs() {
+        let (_dir, db) = reference_db();
+        assert_eq!(db.block_size(), 512);
+        assert_eq!(db.file_count(), 2);
+
+        // Blocks 1..=3 of doc.bin, then other.bin, then doc.bin block 0
+        // out of sequence
+        let mut evidence = vec![0xAAu8; 1024];
+        for seed in [2, 3, 4, 7, 1] {
+            evidence.extend(block(seed));
+        }
+        let runs = db.match_blocks(0, &evidence, evidence.len() as u64, &[]);
+        let found: Vec<(&str, u64, u64, u64)> = runs
+            .iter()
+            .map(|r| {
+                (
+                    db.file_path(r.file_id),
+                    r.global_start,
+                    r.block_index,
+                    r.block_count,
+                )
+            })
+            .collect();
+        assert_eq!(
+            found,
+            vec![
+                ("doc.bin", 1024, 1, 3),
+                ("doc.bin", 3072, 0, 1),
+                ("nested/other.bin", 2560, 0, 1),
+            ]
+        );
+    }
+
+    #[test]
+    fn aligns_blocks_to_partition_start() {
+        let (_dir, db) = reference_db();
+        let partition = Partition {
+            index: 1,
+            scheme: PartitionScheme::Mbr,
+            type_id: "0x07".to_string(),
+            start: 63 * 512 + 100,
+            len: 4096,
+            name: None,
+        };
+        let mut evidence = vec![0x11u8; partition.start as usize + 512];
+        evidence.extend(block(3));
+        let runs = db.match_blocks(0, &evidence, evidence.len() as u64, &[partition.clone()]);
+        assert_eq!(runs.len(), 1);
+        assert_eq!(runs[0].global_start, partition.start + 512);
+        // Off-grid copies are not looked up
+        assert!(
+            db.match_blocks(0, &evidence, evidence.len() as u64, &[])
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn merges_runs_across_chunks() {
+        let run = |global_start, block_index| BlockRun {
+            file_id: 0,
+            global_start,
+            block_index,
+            block_count: 2,
+        };
+        let merged = merge_block_runs(vec![run(1024, 2), run(0, 0), run(4096, 4)], 512);
+        assert_eq!(
+            merged,
+            vec![
+                BlockRun {
+                    file_id: 0,
+                    global_start: 0,
+                    block_index: 0,
+                    block_count: 4,
+                },
+                run(4096, 4),
+            ]
+        );
+    }
+
+    #[test]
+    fn joins_chunk_runs_as_they_arrive() {
+        let piece = |source: &str, global_start, block_index, open_before, open_after| ChunkRun {
+            record: BlockHashMatch {
+                run_id: "run".to_string(),
+                source_file: source.to_string(),
+                global_start,
+                length: 1024,
+                block_index,
+                block_count: 2,
+            },
+            open_before,
+            open_after,
+        };
+        let mut joiner = RunJoiner::default();
+        // Complete inside its chunk
+        let whole = joiner.push(piece("a.bin", 100_000, 7, false, false));
+        assert_eq!(whole.map(|m| m.global_start), Some(100_000));
+        // Three chunks' pieces of one run, middle first
+        assert!(joiner.push(piece("a.bin", 1024, 2, true, true)).is_none());
+        assert!(joiner.push(piece("a.bin", 2048, 4, true, false)).is_none());
+        // Same offsets, other reference file: never joined with a.bin
+        assert!(joiner.push(piece("b.bin", 0, 0, false, true)).is_none());
+        let joined = joiner
+            .push(piece("a.bin", 0, 0, false, true))
+            .expect("run closed");
+        assert_eq!(
+            (
+                joined.global_start,
+                joined.length,
+                joined.block_index,
+                joined.block_count
+            ),
+            (0, 3072, 0, 6)
+        );
+        // A piece at a chunk edge that nothing continues
+        assert!(joiner.push(piece("a.bin", 8192, 9, true, false)).is_none());
+        let rest: Vec<(String, u64)> = joiner
+            .finish()
+            .into_iter()
+            .map(|m| (m.source_file, m.global_start))
+            .collect();
+        assert_eq!(
+            rest,
+            vec![("b.bin".to_string(), 0), ("a.bin".to_string(), 8192)]
+        );
+    }
+}
@@ -0,0 +1,415 @@
+//! Known-file hash sets for `--known-good` and `--known-bad`.
+//!
+//! A set is loaded from an NSRL RDS v3 SQLite database, a plain list of
+//! MD5/SHA-1/SHA-256 hex digests (one per line, anything after the first
+//! token ignored, so `md5sum` output works), or a CSV file whose header
+//! names `md5`, `sha1`/`sha-1`, `sha256`/`sha-256` or `hash` columns
+//! (HashKeeper's `hash` column holds MD5, as do the legacy NSRL text files'
+//! `MD5` column).
+
+use std::collections::HashSet;
+use std::fs::File;
+use std::io::{BufRead, BufReader, Read};
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result, anyhow};
+use rusqlite::{Connection, OpenFlags};
+use sha1::{Digest, Sha1};
+use tracing::{debug, info};
+
+/// Which kind of set a carved file was found in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HashMatch {
+    KnownGood,
+    KnownBad,
+}
+
+impl HashMatch {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            HashMatch::KnownGood => "known_good",
+            HashMatch::KnownBad => "known_bad",
+        }
+    }
+}
+
+/// Digests of one carved file; any of them may be unknown.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct FileHashes {
+    pub md5: Option<[u8; 16]>,
+    pub sha1: Option<[u8; 20]>,
+    pub sha256: Option<[u8; 32]>,
+}
+
+impl FileHashes {
+    /// Decode the hex digests recorded on a carved file.
+    pub fn from_hex(md5: Option<&str>, sha256: Option<&str>) -> Self {
+        Self {
+            md5: md5.and_then(decode),
+            sha1: None,
+            sha256: sha256.and_then(decode),
+        }
+    }
+}
+
+fn decode<const N: usize>(hex_digest: &str) -> Option<[u8; N]> {
+    let bytes = hex::decode(hex_digest.trim()).ok()?;
+    bytes.try_into().ok()
+}
+
+/// One loaded hash set, named after its file stem.
+#[derive(Debug, Default)]
+pub struct HashSetFile {
+    name: String,
+    md5: HashSet<[u8; 16]>,
+    sha1: HashSet<[u8; 20]>,
+    sha256: HashSet<[u8; 32]>,
+}
+
+impl HashSetFile {
+    pub fn load(path: &Path) -> Result<Self> {
+        let name = path
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().to_string())
+            .unwrap_or_else(|| path.display().to_string());
+        let mut set = Self {
+            name,
+            ..Self::default()
+        };
+
+        let mut magic = Vec::new();
+        File::open(path)
+            .and_then(|file| file.take(16).read_to_end(&mut magic))
+            .with_context(|| format!("reading hash set {}", path.display()))?;
+        if magic == b"SQLite format 3\0" {
+            set.load_rds(path)?;
+        } else {
+            set.load_text(path)
+                .with_context(|| format!("reading hash set {}", path.display()))?;
+        }
+        if set.is_empty() {
+            return Err(anyhow!("no hashes found in {}", path.display()));
+        }
+        Ok(set)
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn len(&self) -> usize {
+        self.md5.len() + self.sha1.len() + self.sha256.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn contains(&self, hashes: &FileHashes) -> bool {
+        hashes.md5.is_some_and(|h| self.md5.contains(&h))
+            || hashes.sha1.is_some_and(|h| self.sha1.contains(&h))
+            || hashes.sha256.is_some_and(|h| self.sha256.contains(&h))
+    }
+
+    /// Insert a digest, picking the algorithm from its length.
+    fn insert_hex(&mut self, hex_digest: &str) -> bool {
+        let Ok(bytes) = hex::decode(hex_digest) else {
+            return false;
+        };
+        match bytes.len() {
+            16 => self.md5.insert(bytes.try_into().expect("16 bytes")),
+            20 => self.sha1.insert(bytes.try_into().expect("20 bytes")),
+            32 => self.sha256.insert(bytes.try_into().expect("32 bytes")),
+            _ => return false,
+        };
+        true
+    }
+
+    /// NSRL RDS v3: the `DISTINCT_HASH` view, or the `FILE` table of older
+    /// v3 releases.
+    fn load_rds(&mut self, path: &Path) -> Result<()> {
+        let conn = Connection::open_with_flags(path, OpenFlags::SQLITE_OPEN_READ_ONLY)
+            .with_context(|| format!("opening hash set {}", path.display()))?;
+        let mut table = None;
+        for name in ["DISTINCT_HASH", "FILE"] {
+            let found: i64 = conn.query_row(
+                "SELECT COUNT(*) FROM sqlite_master WHERE type IN ('table', 'view') AND name = ?1",
+                [name],
+                |row| row.get(0),
+            )?;
+            if found > 0 {
+                table = Some(name);
+                break;
+            }
+        }
+        let table =
+            table.ok_or_else(|| anyhow!("{} is not an NSRL RDS database", path.display()))?;
+
+        let mut columns = Vec::new();
+        let mut stmt = conn.prepare(&format!("PRAGMA table_info({table})"))?;
+        let names = stmt.query_map([], |row| row.get::<_, String>(1))?;
+        for name in names {
+            let name = name?;
+            if ["md5", "sha1", "sha256"].contains(&name.to_ascii_lowercase().as_str()) {
+                columns.push(name);
+            }
+        }
+        if columns.is_empty() {
+            return Err(anyhow!("{table} in {} has no hash columns", path.display()));
+        }
+
+        let mut stmt = conn.prepare(&format!("SELECT {} FROM {table}", columns.join(", ")))?;
+        let mut rows = stmt.query([])?;
+        while let Some(row) = rows.next()? {
+            for idx in 0..columns.len() {
+                if let Some(value) = row.get::<_, Option<String>>(idx)? {
+                    self.insert_hex(value.trim());
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// A hash list when the first entry is a digest, else CSV with a header.
+    fn load_text(&mut self, path: &Path) -> Result<()> {
+        let mut lines = BufReader::new(File::open(path)?).lines();
+        let mut first = None;
+        for line in lines.by_ref() {
+            let line = line?;
+            let line = line.trim();
+            if !line.is_empty() && !line.starts_with('#') {
+                first = Some(line.to_string());
+                break;
+            }
+        }
+        let Some(first) = first else {
+            return Ok(());
+        };
+        if !self.insert_hex(first_token(&first)) {
+            return self.load_csv(path);
+        }
+        let mut skipped = 0u64;
+        for line in lines {
+            let line = line?;
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            if !self.insert_hex(first_token(line)) {
+                skipped += 1;
+            }
+        }
+        if skipped > 0 {
+            debug!("hash set {}: skipped {skipped} unparsable lines", self.name);
+        }
+        Ok(())
+    }
+
+    fn load_csv(&mut self, path: &Path) -> Result<()> {
+        let mut reader = csv::ReaderBuilder::new()
+            .flexible(true)
+            .comment(Some(b'#'))
+            .from_path(path)?;
+        let headers = reader.headers().context("reading hash set header")?.clone();
+        let columns: Vec<usize> = headers
+            .iter()
+            .enumerate()
+            .filter(|(_, name)| {
+                let name = name.trim().to_ascii_lowercase().replace('-', "");
+                matches!(name.as_str(), "md5" | "sha1" | "sha256" | "hash")
+            })
+            .map(|(idx, _)| idx)
+            .collect();
+        if columns.is_empty() {
+            return Err(anyhow!(
+                "unrecognised hash set format (no hash list and no md5/sha1/sha256/hash column)"
+            ));
+        }
+        for record in reader.records() {
+            let record = record.context("reading hash set row")?;
+            for &idx in &columns {
+                if let Some(value) = record.get(idx) {
+                    self.insert_hex(value.trim());
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+fn first_token(line: &str) -> &str {
+    line.split(|c: char| c.is_whitespace() || c == ',')
+        .next()
+        .unwrap_or("")
+        .trim_matches('"')
+}
+
+/// All `--known-good` and `--known-bad` sets of a run.
+#[derive(Debug, Default)]
+pub struct KnownFiles {
+    good: Vec<HashSetFile>,
+    bad: Vec<HashSetFile>,
+}
+
+impl KnownFiles {
+    pub fn load(known_good: &[PathBuf], known_bad: &[PathBuf]) -> Result<Self> {
+        let load_all = |paths: &[PathBuf], kind: HashMatch| -> Result<Vec<HashSetFile>> {
+            paths
+                .iter()
+                .map(|path| {
+                    let set = HashSetFile::load(path)
+                        .with_context(|| format!("loading hash set {}", path.display()))?;
+                    info!(
+                        "{} hash set {}: {} md5, {} sha1, {} sha256",
+                        kind.as_str(),
+                        set.name,
+                        set.md5.len(),
+                        set.sha1.len(),
+                        set.sha256.len()
+                    );
+                    Ok(set)
+                })
+                .collect()
+        };
+        Ok(Self {
+            good: load_all(known_good, HashMatch::KnownGood)?,
+            bad: load_all(known_bad, HashMatch::KnownBad)?,
+        })
+    }
+
+    /// Whether any set lists SHA-1 digests, which carving does not compute.
+    pub fn needs_sha1(&self) -> bool {
+        self.good
+            .iter()
+            .chain(&self.bad)
+            .any(|set| !set.sha1.is_empty())
+    }
+
+    /// The first set holding the file; known-bad sets are checked first.
+    pub fn lookup(&self, hashes: &FileHashes) -> Option<(HashMatch, &str)> {
+        if let Some(set) = self.bad.iter().find(|set| set.contains(hashes)) {
+            return Some((HashMatch::KnownBad, set.name()));
+        }
+        self.good
+            .iter()
+            .find(|set| set.contains(hashes))
+            .map(|set| (HashMatch::KnownGood, set.name()))
+    }
+}
+
+/// SHA-1 of a file on disk.
+pub fn sha1_file(path: &Path) -> std::io::Result<[u8; 20]> {
+    let mut file = File::open(path)?;
+    let mut hasher = Sha1::new();
+    let mut buf = vec![0u8; 64 * 1024];
+    loop {
+        let n = file.read(&mut buf)?;
+        if n == 0 {
+            break;
+        }
+        hasher.update(&buf[..n]);
+    }
+    Ok(hasher.finalize().into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{FileHashes, HashMatch, KnownFiles, sha1_file};
+
+    const ABC_MD5: &str = "900150983cd24fb0d6963f7d28e17f72";
+    const ABC_SHA1: &str = "a9993e364706816aba3e25717850c26c9cd0d89d";
+    const ABC_SHA256: &str = "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad";
+
+    fn abc(sha1: bool) -> FileHashes {
+        let mut hashes = FileHashes::from_hex(Some(ABC_MD5), Some(ABC_SHA256));
+        if sha1 {
+            hashes.sha1 = Some(hex::decode(ABC_SHA1).unwrap().try_into().unwrap());
+        }
+        hashes
+    }
+
+    #[test]
+    fn loads_plain_lists_and_hashkeeper_csv() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let list = dir.path().join("good.txt");
+        std::fs::write(
+            &list,
+            format!(
+                "# md5sum output\n{}  abc.txt\nnot-a-hash\n",
+                ABC_MD5.to_uppercase()
+            ),
+        )
+        .expect("write");
+        let hashkeeper = dir.path().join("bad.hke");
+        std::fs::write(
+            &hashkeeper,
+            format!(
+                "\"file_id\",\"hashset_id\",\"file_name\",\"directory\",\"hash\",\"file_size\"\n1,7,\"abc.txt\",\"C:\\\\\",\"{ABC_MD5}\",3\n"
+            ),
+        )
+        .expect("write");
+
+        let known = KnownFiles::load(std::slice::from_ref(&list), &[]).expect("load");
+        assert_eq!(
+            known.lookup(&abc(false)),
+            Some((HashMatch::KnownGood, "good"))
+        );
+        assert!(!known.needs_sha1());
+
+        // Known-bad wins when a file is in both
+        let known = KnownFiles::load(&[list], &[hashkeeper]).expect("load");
+        assert_eq!(
+            known.lookup(&abc(false)),
+            Some((HashMatch::KnownBad, "bad"))
+        );
+        assert_eq!(known.lookup(&FileHashes::default()), None);
+    }
+
+    #[test]
+    fn loads_nsrl_rds_sqlite() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let db = dir.path().join("RDS_modern_minimal.db");
+        let conn = rusqlite::Connection::open(&db).expect("open");
+        conn.execute_batch(
+            "CREATE TABLE FILE (sha256 TEXT, sha1 TEXT, md5 TEXT, file_name TEXT);
+             CREATE VIEW DISTINCT_HASH AS SELECT DISTINCT sha256, sha1, md5 FROM FILE;",
+        )
+        .expect("schema");
+        conn.execute(
+            "INSERT INTO FILE VALUES (?1, ?2, ?3, 'abc.txt')",
+            [
+                ABC_SHA256.to_uppercase(),
+                ABC_SHA1.to_uppercase(),
+                "00000000000000000000000000000000".to_string(),
+            ],
+        )
+        .expect("insert");
+        drop(conn);
+
+        let known = KnownFiles::load(&[db], &[]).expect("load");
+        assert!(known.needs_sha1());
+        let mut sha1_only = abc(true);
+        sha1_only.md5 = None;
+        sha1_only.sha256 = None;
+        assert_eq!(
+            known.lookup(&sha1_only),
+            Some((HashMatch::KnownGood, "RDS_modern_minimal"))
+        );
+    }
+
+    #[test]
+    fn rejects_files_without_hashes() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let path = dir.path().join("names.csv");
+        std::fs::write(&path, "name,size\nabc.txt,3\n").expect("write");
+        assert!(KnownFiles::load(&[path], &[]).is_err());
+    }
+
+    #[test]
+    fn hashes_files_with_sha1() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let path = dir.path().join("abc.txt");
+        std::fs::write(&path, b"abc").expect("write");
+        assert_eq!(hex::encode(sha1_file(&path).expect("sha1")), ABC_SHA1);
+    }
+}
@@ -0,0 +1,456 @@
+//! Keyword search for `--keywords`.
+//!
+//! A keyword file holds one term per line. Blank lines and lines starting
+//! with `#` are skipped. A term may be followed by a tab and flag letters:
+//! `r` treats it as a regular expression ([regex crate] syntax) and `i`
+//! makes it case-insensitive. Every term is searched as ASCII/UTF-8 and as
+//! UTF-16LE and UTF-16BE; regex terms are translated code unit by code unit,
+//! so classes and `.` match UTF-16 text too.
+//!
+//! Each encoding's terms are compiled into one [`RegexSet`], so a buffer
+//! is scanned once per encoding to find which terms occur in it; only
+//! those terms are then searched again for their offsets.
+//!
+//! [regex crate]: https://docs.rs/regex
+
+use std::path::Path;
+
+use anyhow::{Context, Result, anyhow};
+use regex::bytes::{Regex, RegexSet};
+use regex_syntax::hir::{Class, ClassBytes, ClassBytesRange, Hir, HirKind, Look, Repetition};
+
+/// One keyword occurrence in a scanned buffer.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeywordMatch {
+    /// Index of the term in [`KeywordList::terms`].
+    pub term: usize,
+    pub encoding: &'static str,
+    pub start: usize,
+    pub end: usize,
+}
+
+/// Compiled keyword list, shared by all scan workers.
+#[derive(Debug)]
+pub struct KeywordList {
+    terms: Vec<String>,
+    /// One entry per [`Encoding`], in declaration order.
+    searches: Vec<EncodingSearch>,
+}
+
+/// Every term translated to one encoding.
+#[derive(Debug)]
+struct EncodingSearch {
+    encoding: Encoding,
+    /// Which terms occur in a buffer, in a single pass.
+    set: RegexSet,
+    /// Per-term regexes, indexed like [`KeywordList::terms`], to locate
+    /// the occurrences of terms the set reported.
+    regexes: Vec<Regex>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Encoding {
+    /// ASCII and UTF-8 share their bytes; hits are labelled by content.
+    Utf8,
+    Utf16Le,
+    Utf16Be,
+}
+
+impl KeywordList {
+    /// Load and compile a keyword file.
+    pub fn load(path: &Path) -> Result<Self> {
+        let text = std::fs::read_to_string(path)
+            .with_context(|| format!("reading keyword file {}", path.display()))?;
+        Self::parse(&text)
+    }
+
+    /// Compile a keyword list from the contents of a keyword file.
+    pub fn parse(text: &str) -> Result<Self> {
+        let mut terms = Vec::new();
+        let mut regexes: [Vec<Regex>; 3] = Default::default();
+        for (idx, line) in text.lines().enumerate() {
+            let line = line.trim_end_matches('\r');
+            if line.trim().is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (term, flags) = line.rsplit_once('\t').unwrap_or((line, ""));
+            let compiled = compile_term(term, flags)
+                .with_context(|| format!("keyword line {}: `{term}`", idx + 1))?;
+            for (regexes, regex) in regexes.iter_mut().zip(compiled) {
+                regexes.push(regex);
+            }
+            terms.push(term.to_string());
+        }
+        if terms.is_empty() {
+            return Err(anyhow!("keyword list contains no terms"));
+        }
+        let searches = [Encoding::Utf8, Encoding::Utf16Le, Encoding::Utf16Be]
+            .into_iter()
+            .zip(regexes)
+            .map(|(encoding, regexes)| {
+                let set = RegexSet::new(regexes.iter().map(Regex::as_str))
+                    .map_err(|err| anyhow!("compiling keyword list: {err}"))?;
+                Ok(EncodingSearch {
+                    encoding,
+                    set,
+                    regexes,
+                })
+            })
+            .collect::<Result<_>>()?;
+        Ok(Self { terms, searches })
+    }
+
+    /// Terms in file order, as written.
+    pub fn terms(&self) -> &[String] {
+        &self.terms
+    }
+
+    /// Every non-overlapping match of every term and encoding, ordered by
+    /// offset.
+    pub fn scan(&self, data: &[u8]) -> Vec<KeywordMatch> {
+        let mut out = Vec::new();
+        for search in &self.searches {
+            for term in search.set.matches(data).iter() {
+                for found in search.regexes[term].find_iter(data) {
+                    let encoding = match search.encoding {
+                        Encoding::Utf8 if found.as_bytes().is_ascii() => "ascii",
+                        Encoding::Utf8 => "utf-8",
+                        Encoding::Utf16Le => "utf-16le",
+                        Encoding::Utf16Be => "utf-16be",
+                    };
+                    out.push(KeywordMatch {
+                        term,
+                        encoding,
+                        start: found.start(),
+                        end: found.end(),
+                    });
+                }
+            }
+        }
+        out.sort_by_key(|m| (m.start, m.term));
+        out
+    }
+}
+
+/// Compile one keyword line's term into its UTF-8, UTF-16LE and UTF-16BE
+/// regexes.
+fn compile_term(term: &str, flags: &str) -> Result<[Regex; 3]> {
+    let mut is_regex = false;
+    let mut nocase = false;
+    for flag in flags.trim().chars() {
+        match flag {
+            'r' => is_regex = true,
+            'i' => nocase = true,
+            _ => return Err(anyhow!("unknown keyword flag `{flag}`")),
+        }
+    }
+    if term.is_empty() {
+        return Err(anyhow!("empty keyword"));
+    }
+    let pattern = if is_regex {
+        term.to_string()
+    } else {
+        regex::escape(term)
+    };
+    let hir = regex_syntax::ParserBuilder::new()
+        .case_insensitive(nocase)
+        .build()
+        .parse(&pattern)
+        .map_err(|err| anyhow!("{err}"))?;
+    if hir.properties().minimum_len() == Some(0) {
+        return Err(anyhow!("keyword can match the empty string"));
+    }
+
+    Ok([
+        compile(&hir)?,
+        compile(&to_utf16(&hir, false)?)?,
+        compile(&to_utf16(&hir, true)?)?,
+    ])
+}
+
+/// Decode hit context in the hit's encoding for display, showing control
+/// characters as `.`.
+pub fn render_context(bytes: &[u8], encoding: &str) -> String {
+    let decode_wide = |to_unit: fn([u8; 2]) -> u16| {
+        char::decode_utf16(
+            bytes
+                .chunks_exact(2)
+                .map(|pair| to_unit([pair[0], pair[1]])),
+        )
+        .map(|c| c.unwrap_or(char::REPLACEMENT_CHARACTER))
+        .collect::<String>()
+    };
+    let text = match encoding {
+        "utf-16le" => decode_wide(u16::from_le_bytes),
+        "utf-16be" => decode_wide(u16::from_be_bytes),
+        _ => String::from_utf8_lossy(bytes).into_owned(),
+    };
+    text.chars()
+        .map(|c| if c.is_control() { '.' } else { c })
+        .collect()
+}
+
+fn compile(hir: &Hir) -> Result<Regex> {
+    Regex::new(&hir.to_string()).map_err(|err| anyhow!("{err}"))
+}
+
+/// Rewrite a UTF-8 pattern so it matches the same text encoded as UTF-16.
+fn to_utf16(hir: &Hir, big_endian: bool) -> Result<Hir> {
+    Ok(match hir.kind() {
+        HirKind::Empty => Hir::empty(),
+        HirKind::Literal(lit) => {
+            let text = std::str::from_utf8(&lit.0)
+                .map_err(|_| anyhow!("byte literals cannot be searched as UTF-16"))?;
+            let mut bytes = Vec::with_capacity(text.len() * 2);
+            for unit in text.encode_utf16() {
+                bytes.extend_from_slice(&unit_bytes(unit, big_endian));
+            }
+            Hir::literal(bytes)
+        }
+        HirKind::Class(Class::Unicode(cls)) => {
+            let mut alternatives = Vec::new();
+            for range in cls.iter() {
+                let (start, end) = (u32::from(range.start()), u32::from(range.end()));
+                if start <= 0xFFFF {
+                    for seq in unit_ranges(start as u16, end.min(0xFFFF) as u16) {
+                        alternatives.push(unit_hir(seq, big_endian));
+                    }
+                }
+                if end >= 0x1_0000 {
+                    alternatives.extend(astral_ranges(start.max(0x1_0000), end, big_endian));
+                }
+            }
+            Hir::alternation(alternatives)
+        }
+        HirKind::Class(Class::Bytes(cls)) => {
+            let low = Hir::class(Class::Bytes(cls.clone()));
+            let high = Hir::literal([0u8]);
+            if big_endian {
+                Hir::concat(vec![high, low])
+            } else {
+                Hir::concat(vec![low, high])
+            }
+        }
+        HirKind::Look(look) => match look {
+            Look::Start | Look::End => Hir::look(*look),
+            _ => {
+                return Err(anyhow!(
+                    "only `^` and `$` assertions can be searched as UTF-16"
+                ));
+            }
+        },
+        HirKind::Repetition(rep) => Hir::repetition(Repetition {
+            min: rep.min,
+            max: rep.max,
+            greedy: rep.greedy,
+            sub: Box::new(to_utf16(&rep.sub, big_endian)?),
+        }),
+        HirKind::Capture(cap) => to_utf16(&cap.sub, big_endian)?,
+        HirKind::Concat(subs) => Hir::concat(
+            subs.iter()
+                .map(|sub| to_utf16(sub, big_endian))
+                .collect::<Result<_>>()?,
+        ),
+        HirKind::Alternation(subs) => Hir::alternation(
+            subs.iter()
+                .map(|sub| to_utf16(sub, big_endian))
+                .collect::<Result<_>>()?,
+        ),
+    })
+}
+
+fn unit_bytes(unit: u16, big_endian: bool) -> [u8; 2] {
+    if big_endian {
+        unit.to_be_bytes()
+    } else {
+        unit.to_le_bytes()
+    }
+}
+
+/// A range of code units as (high byte range, low byte range) pairs.
+type UnitSeq = ((u8, u8), (u8, u8));
+
+/// Split `[lo, hi]` into runs whose code units share a byte-range shape.
+fn unit_ranges(lo: u16, hi: u16) -> Vec<UnitSeq> {
+    let [lo_hi, lo_lo] = lo.to_be_bytes();
+    let [hi_hi, hi_lo] = hi.to_be_bytes();
+    if lo_hi == hi_hi {
+        return vec![((lo_hi, lo_hi), (lo_lo, hi_lo))];
+    }
+    let mut out = Vec::new();
+    let mut first_full = lo_hi;
+    if lo_lo != 0 {
+        out.push(((lo_hi, lo_hi), (lo_lo, 0xFF)));
+        first_full += 1;
+    }
+    let mut last_full = hi_hi;
+    let tail = (hi_lo != 0xFF).then_some(((hi_hi, hi_hi), (0, hi_lo)));
+    if tail.is_some() {
+        last_full -= 1;
+    }
+    if first_full <= last_full {
+        out.push(((first_full, last_full), (0, 0xFF)));
+    }
+    out.extend(tail);
+    out
+}
+
+fn unit_hir(((hi_a, hi_b), (lo_a, lo_b)): UnitSeq, big_endian: bool) -> Hir {
+    let high = byte_class(hi_a, hi_b);
+    let low = byte_class(lo_a, lo_b);
+    if big_endian {
+        Hir::concat(vec![high, low])
+    } else {
+        Hir::concat(vec![low, high])
+    }
+}
+
+fn byte_class(start: u8, end: u8) -> Hir {
+    if start == end {
+        Hir::literal([start])
+    } else {
+        Hir::class(Class::Bytes(ClassBytes::new([ClassBytesRange::new(
+            start, end,
+        )])))
+    }
+}
+
+/// Surrogate-pair alternatives for the supplementary-plane range
+/// `[start, end]`.
+fn astral_ranges(start: u32, end: u32, big_endian: bool) -> Vec<Hir> {
+    let (start, end) = (start - 0x1_0000, end - 0x1_0000);
+    let mut out = Vec::new();
+    let (lead_lo, lead_hi) = (start >> 10, end >> 10);
+    let mut push = |lead_a: u32, lead_b: u32, trail_a: u32, trail_b: u32| {
+        let leads = unit_ranges(0xD800 + lead_a as u16, 0xD800 + lead_b as u16);
+        let trails = unit_ranges(0xDC00 + trail_a as u16, 0xDC00 + trail_b as u16);
+        for lead in &leads {
+            for trail in &trails {
+                out.push(Hir::concat(vec![
+                    unit_hir(*lead, big_endian),
+                    unit_hir(*trail, big_endian),
+                ]));
+            }
+        }
+    };
+    if lead_lo == lead_hi {
+        push(lead_lo, lead_lo, start & 0x3FF, end & 0x3FF);
+        return out;
+    }
+    push(lead_lo, lead_lo, start & 0x3FF, 0x3FF);
+    if lead_hi > lead_lo + 1 {
+        push(lead_lo + 1, lead_hi - 1, 0, 0x3FF);
+    }
+    push(lead_hi, lead_hi, 0, end & 0x3FF);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{KeywordList, render_context};
+
+    fn utf16(text: &str, big_endian: bool) -> Vec<u8> {
+        text.encode_utf16()
+            .flat_map(|unit| {
+                if big_endian {
+                    unit.to_be_bytes()
+                } else {
+                    unit.to_le_bytes()
+                }
+            })
+            .collect()
+    }
+
+    fn found(list: &KeywordList, data: &[u8]) -> Vec<(String, &'static str, usize)> {
+        list.scan(data)
+            .into_iter()
+            .map(|m| (list.terms()[m.term].clone(), m.encoding, m.start))
+            .collect()
+    }
+
+    #[test]
+    fn finds_literal_in_every_encoding() {
+        let list = KeywordList::parse("secret\n").expect("parse");
+        let mut data = b"xx secret ".to_vec();
+        data.extend(utf16("secret", false));
+        data.push(b' ');
+        data.extend(utf16("secret", true));
+        data.extend(b" SECRET");
+        assert_eq!(
+            found(&list, &data),
+            vec![
+                ("secret".to_string(), "ascii", 3),
+                ("secret".to_string(), "utf-16le", 10),
+                ("secret".to_string(), "utf-16be", 23),
+            ]
+        );
+    }
+
+    #[test]
+    fn applies_flags_and_skips_comments() {
+        let text = "# case list\n\nmüller\ti\nacct-[0-9]{4}\tr\n";
+        let list = KeywordList::parse(text).expect("parse");
+        assert_eq!(list.terms(), ["müller", "acct-[0-9]{4}"]);
+
+        let mut data = "MÜLLER acct-1234 acct-x ".as_bytes().to_vec();
+        data.extend(utf16("Müller", true));
+        data.extend(utf16("acct-9876", false));
+        let hits = found(&list, &data);
+        assert_eq!(
+            hits,
+            vec![
+                ("müller".to_string(), "utf-8", 0),
+                ("acct-[0-9]{4}".to_string(), "ascii", 8),
+                ("müller".to_string(), "utf-16be", 25),
+                ("acct-[0-9]{4}".to_string(), "utf-16le", 37),
+            ]
+        );
+    }
+
+    #[test]
+    fn reports_overlapping_terms_separately() {
+        let list = KeywordList::parse("secret\ncre\nabsent\n").expect("parse");
+        assert_eq!(
+            found(&list, b"a secret, secret"),
+            vec![
+                ("secret".to_string(), "ascii", 2),
+                ("cre".to_string(), "ascii", 4),
+                ("secret".to_string(), "ascii", 10),
+                ("cre".to_string(), "ascii", 12),
+            ]
+        );
+    }
+
+    #[test]
+    fn matches_wide_classes_and_astral_characters() {
+        let list = KeywordList::parse("pay.\tr\n[😀-😂]\tr\n").expect("parse");
+        let mut data = utf16("payé", false);
+        data.extend(utf16("😁", true));
+        let hits = found(&list, &data);
+        assert_eq!(
+            hits,
+            vec![
+                ("pay.".to_string(), "utf-16le", 0),
+                ("[😀-😂]".to_string(), "utf-16be", 8),
+            ]
+        );
+    }
+
+    #[test]
+    fn renders_context_in_hit_encoding() {
+        assert_eq!(render_context(b"a\x00b\nc", "ascii"), "a.b.c");
+        assert_eq!(
+            render_context(&utf16("héllo\r", false), "utf-16le"),
+            "héllo."
+        );
+        assert_eq!(render_context(&utf16("hi", true), "utf-16be"), "hi");
+    }
+
+    #[test]
+    fn rejects_bad_terms() {
+        assert!(KeywordList::parse("a*\tr\n").is_err());
+        assert!(KeywordList::parse("word\tx\n").is_err());
+        assert!(KeywordList::parse("\\bword\tr\n").is_err());
+        assert!(KeywordList::parse("# only comments\n").is_err());
+    }
+}
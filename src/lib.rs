@@ -5,19 +5,31 @@
 //! This crate provides tools for extracting files and forensic artefacts from
 //! disk images and raw evidence sources.
 
+pub mod archive;
+pub mod blank;
 pub mod carve;
 pub mod checkpoint;
 pub mod chunk;
 pub mod cli;
 pub mod config;
 pub mod constants;
+pub mod embedded;
 pub mod entropy;
 pub mod error;
 pub mod evidence;
+pub mod filesystem;
+pub mod fuzzy;
+pub mod hashdb;
+pub mod hashset;
+pub mod keywords;
 pub mod logging;
 pub mod metadata;
 pub mod parsers;
+pub mod partition;
 pub mod pipeline;
+pub mod ranges;
 pub mod scanner;
 pub mod strings;
 pub mod util;
+pub mod validate;
+pub mod yara;
@@ -6,8 +6,8 @@ use anyhow::{Context, Result, bail};
 use tracing::{info, warn};
 
 use swiftbeaver::{
-    checkpoint, cli, config, constants::MIB, evidence, logging, metadata, pipeline, scanner,
-    strings, util,
+    checkpoint, cli, config, constants::MIB, evidence, hashdb, logging, metadata, pipeline,
+    scanner, strings, util,
 };
 
 struct LoggingProgressReporter;
@@ -19,7 +19,7 @@ impl pipeline::ProgressReporter for LoggingProgressReporter {
             .map(|s| format!("{:.0}s", s))
             .unwrap_or_else(|| "N/A".to_string());
         info!(
-            "progress {:.1}% scanned={}/{} hits={} files={} rate={:.2}MiB/s eta={} errs=[carve:{} meta:{} sql:{}]",
+            "progress {:.1}% scanned={}/{} hits={} files={} rate={:.2}MiB/s eta={} valid=[pass:{} fail:{}] errs=[carve:{} meta:{} sql:{}]",
             snapshot.completion_pct,
             snapshot.bytes_scanned,
             snapshot.total_bytes,
@@ -27,6 +27,8 @@ impl pipeline::ProgressReporter for LoggingProgressReporter {
             snapshot.files_carved,
             snapshot.throughput_mib,
             eta_str,
+            snapshot.validation_pass,
+            snapshot.validation_fail,
             snapshot.carve_errors,
             snapshot.metadata_errors,
             snapshot.sqlite_errors
@@ -37,6 +39,12 @@ impl pipeline::ProgressReporter for LoggingProgressReporter {
 fn main() -> Result<()> {
     let cli_opts = cli::parse();
     logging::init_logging_with_format(cli_opts.log_format);
+    if let Some(command) = &cli_opts.command {
+        return run_command(command);
+    }
+    let Some(input) = cli_opts.input.clone() else {
+        bail!("--input is required");
+    };
     let loaded = config::load_config(cli_opts.config_path.as_deref())?;
     let mut cfg = loaded.config;
 
@@ -62,13 +70,22 @@ fn main() -> Result<()> {
     if cli_opts.dry_run {
         info!("dry-run mode enabled: no files will be written");
     }
-    if cli_opts.validate_carved {
-        info!("post-carving validation enabled");
+    if cfg.validate_carved {
+        if cfg.remove_invalid {
+            info!("post-carving validation enabled; invalid files will be removed");
+        } else {
+            info!("post-carving validation enabled");
+        }
     }
     if cfg.enable_string_scan
         && !cfg.enable_url_scan
         && !cfg.enable_email_scan
         && !cfg.enable_phone_scan
+        && !cfg.enable_ip_scan
+        && !cfg.enable_credit_card_scan
+        && !cfg.enable_crypto_wallet_scan
+        && !cfg.enable_secret_scan
+        && cfg.custom_artefacts.is_empty()
     {
         warn!("string scanning enabled but all artefact types are disabled");
     }
@@ -85,24 +102,33 @@ fn main() -> Result<()> {
     }
 
     let tool_version = env!("CARGO_PKG_VERSION");
-    let evidence_path = cli_opts.input.clone();
+    let evidence_path = input.clone();
 
     info!(
         "starting run_id={} input={} output={} workers={} chunk_mib={}",
         cfg.run_id,
-        cli_opts.input.display(),
+        input.display(),
         run_output_dir.display(),
         cli_opts.workers,
         cli_opts.chunk_size_mib
     );
 
     let evidence_source = evidence::open_source(&cli_opts)?;
-    let evidence_source: Arc<dyn evidence::EvidenceSource> = Arc::from(evidence_source);
+    let evidence_source: Arc<dyn evidence::EvidenceSource> = if cfg.tolerant_reads {
+        info!("tolerant reads enabled: unreadable sectors will be zero-filled");
+        Arc::new(evidence::TolerantSource::new(evidence_source))
+    } else {
+        Arc::from(evidence_source)
+    };
 
     if cli_opts.evidence_sha256.is_some() && cli_opts.compute_evidence_sha256 {
         bail!("set either --evidence-sha256 or --compute-evidence-sha256, not both");
     }
 
+    if cli_opts.verify_ewf_hashes {
+        verify_ewf_hashes(&input)?;
+    }
+
     let evidence_sha256 = if let Some(hash) = cli_opts.evidence_sha256.as_ref() {
         hash.trim().to_string()
     } else if cli_opts.compute_evidence_sha256 {
@@ -202,3 +228,50 @@ fn main() -> Result<()> {
     info!("SwiftBeaver run finished");
     Ok(())
 }
+
+fn run_command(command: &cli::Command) -> Result<()> {
+    match command {
+        cli::Command::Hashdb {
+            action: cli::HashdbCommand::Build(args),
+        } => {
+            info!(
+                "building hash db {} from {} (block size {})",
+                args.db.display(),
+                args.source.display(),
+                args.block_size
+            );
+            let summary = hashdb::build(&args.source, &args.db, args.block_size)?;
+            info!(
+                "hash db written: {} files, {} blocks, {} constant blocks skipped",
+                summary.files, summary.blocks, summary.skipped_blocks
+            );
+            Ok(())
+        }
+    }
+}
+
+#[cfg(feature = "ewf")]
+fn verify_ewf_hashes(input: &std::path::Path) -> Result<()> {
+    info!("verifying E01 stored hashes (full pass)");
+    let verification = evidence::verify_ewf_hashes(input)?;
+    if !verification.has_stored_hash() {
+        warn!("E01 image has no stored MD5/SHA1 to verify");
+        return Ok(());
+    }
+    info!(
+        "ewf_hashes md5 stored={} computed={} sha1 stored={} computed={}",
+        verification.stored_md5.as_deref().unwrap_or("-"),
+        verification.computed_md5,
+        verification.stored_sha1.as_deref().unwrap_or("-"),
+        verification.computed_sha1
+    );
+    if !verification.matches() {
+        bail!("E01 stored hashes do not match media contents");
+    }
+    Ok(())
+}
+
+#[cfg(not(feature = "ewf"))]
+fn verify_ewf_hashes(_input: &std::path::Path) -> Result<()> {
+    bail!("--verify-ewf-hashes requires the `ewf` feature")
+}
@@ -1,11 +1,15 @@
-use std::fs::File;
-use std::path::Path;
+use std::borrow::Cow;
+use std::fs::{File, OpenOptions};
+use std::path::{Path, PathBuf};
 use std::sync::Mutex;
 
 use serde::Serialize;
 
-use crate::carve::CarvedFile;
-use crate::metadata::{EntropyRegion, MetadataError, MetadataSink, RunSummary};
+use crate::carve::{CarvedFile, join_fragments};
+use crate::metadata::{
+    ArchiveEntry, BadSectorRange, BlockHashMatch, EntropyRegion, FuzzyMatch, KeywordHit,
+    MetadataError, MetadataSink, RunSummary, YaraMatch, format_skipped_ranges, format_type_counts,
+};
 use crate::parsers::browser::{BrowserCookieRecord, BrowserDownloadRecord};
 use crate::strings::artifacts::{ArtefactKind, StringArtefact};
 
@@ -16,11 +20,22 @@ pub struct CsvSink {
     evidence_sha256: String,
     files_writer: Mutex<csv::Writer<File>>,
     strings_writer: Mutex<csv::Writer<File>>,
+    ips_writer: Mutex<csv::Writer<File>>,
+    cards_writer: Mutex<csv::Writer<File>>,
+    crypto_writer: Mutex<csv::Writer<File>>,
+    secrets_writer: Mutex<csv::Writer<File>>,
     history_writer: Mutex<csv::Writer<File>>,
     cookies_writer: Mutex<csv::Writer<File>>,
     downloads_writer: Mutex<csv::Writer<File>>,
     run_writer: Mutex<csv::Writer<File>>,
     entropy_writer: Mutex<csv::Writer<File>>,
+    bad_sectors_writer: Mutex<csv::Writer<File>>,
+    yara_writer: Mutex<csv::Writer<File>>,
+    keywords_writer: Mutex<csv::Writer<File>>,
+    keywords_path: PathBuf,
+    block_hash_writer: Mutex<csv::Writer<File>>,
+    fuzzy_writer: Mutex<csv::Writer<File>>,
+    archive_entries_writer: Mutex<csv::Writer<File>>,
 }
 
 #[derive(Serialize)]
@@ -34,10 +49,32 @@ struct CarvedFileCsv<'a> {
     size: u64,
     md5: Option<&'a str>,
     sha256: Option<&'a str>,
+    ssdeep: Option<&'a str>,
+    tlsh: Option<&'a str>,
     validated: bool,
     truncated: bool,
     errors: String,
     pattern_id: Option<&'a str>,
+    validation_status: Option<&'a str>,
+    validation_reason: Option<&'a str>,
+    partition_index: Option<u32>,
+    partition_type: Option<&'a str>,
+    partition_offset: Option<u64>,
+    allocation_status: Option<&'a str>,
+    containing_fs_path: Option<&'a str>,
+    containing_inode: Option<u64>,
+    offset_in_file: Option<u64>,
+    hash_match: Option<&'a str>,
+    hash_set: Option<&'a str>,
+    is_duplicate: bool,
+    duplicate_of: Option<&'a str>,
+    fragments: String,
+    parent_path: Option<&'a str>,
+    parent_id: Option<&'a str>,
+    offset_in_parent: Option<u64>,
+    depth: u32,
+    encryption: &'a str,
+    encryption_algorithm: Option<&'a str>,
     tool_version: &'a str,
     config_hash: &'a str,
     evidence_path: &'a str,
@@ -47,11 +84,14 @@ struct CarvedFileCsv<'a> {
 #[derive(Serialize)]
 struct StringArtefactCsv<'a> {
     run_id: &'a str,
-    artefact_kind: &'a str,
+    artefact_kind: Cow<'a, str>,
     content: &'a str,
     encoding: &'a str,
     global_start: u64,
     global_end: u64,
+    containing_fs_path: Option<&'a str>,
+    containing_inode: Option<u64>,
+    offset_in_file: Option<u64>,
     tool_version: &'a str,
     config_hash: &'a str,
     evidence_path: &'a str,
@@ -68,6 +108,9 @@ struct BrowserHistoryCsv<'a> {
     visit_time: Option<String>,
     visit_source: Option<&'a str>,
     source_file: String,
+    containing_fs_path: Option<&'a str>,
+    containing_inode: Option<u64>,
+    offset_in_file: Option<u64>,
     tool_version: &'a str,
     config_hash: &'a str,
     evidence_path: &'a str,
@@ -89,6 +132,9 @@ struct BrowserCookieCsv<'a> {
     is_secure: Option<bool>,
     is_http_only: Option<bool>,
     source_file: String,
+    containing_fs_path: Option<&'a str>,
+    containing_inode: Option<u64>,
+    offset_in_file: Option<u64>,
     tool_version: &'a str,
     config_hash: &'a str,
     evidence_path: &'a str,
@@ -107,6 +153,9 @@ struct BrowserDownloadCsv<'a> {
     total_bytes: Option<i64>,
     state: Option<&'a str>,
     source_file: String,
+    containing_fs_path: Option<&'a str>,
+    containing_inode: Option<u64>,
+    offset_in_file: Option<u64>,
     tool_version: &'a str,
     config_hash: &'a str,
     evidence_path: &'a str,
@@ -122,6 +171,12 @@ struct RunSummaryCsv<'a> {
     files_carved: u64,
     string_spans: u64,
     artefacts_extracted: u64,
+    bytes_skipped: u64,
+    skipped_ranges: String,
+    unaligned_hits: String,
+    known_good_files: u64,
+    known_bad_files: u64,
+    encrypted_files: u64,
     tool_version: &'a str,
     config_hash: &'a str,
     evidence_path: &'a str,
@@ -141,6 +196,116 @@ struct EntropyRegionCsv<'a> {
     evidence_sha256: &'a str,
 }
 
+#[derive(Serialize)]
+struct BadSectorCsv<'a> {
+    run_id: &'a str,
+    global_start: u64,
+    length: u64,
+    tool_version: &'a str,
+    config_hash: &'a str,
+    evidence_path: &'a str,
+    evidence_sha256: &'a str,
+}
+
+/// Row of the per-kind artefact files (IPs, cards, wallets, secrets).
+#[derive(Serialize)]
+struct TypedArtefactCsv<'a> {
+    run_id: &'a str,
+    artefact_kind: Cow<'a, str>,
+    subtype: &'a str,
+    content: &'a str,
+    encoding: &'a str,
+    global_start: u64,
+    global_end: u64,
+    containing_fs_path: Option<&'a str>,
+    containing_inode: Option<u64>,
+    offset_in_file: Option<u64>,
+    tool_version: &'a str,
+    config_hash: &'a str,
+    evidence_path: &'a str,
+    evidence_sha256: &'a str,
+}
+
+#[derive(Serialize)]
+struct YaraMatchCsv<'a> {
+    run_id: &'a str,
+    rule: &'a str,
+    tags: String,
+    global_offset: u64,
+    string_id: &'a str,
+    carved_path: &'a str,
+    tool_version: &'a str,
+    config_hash: &'a str,
+    evidence_path: &'a str,
+    evidence_sha256: &'a str,
+}
+
+#[derive(Serialize)]
+struct BlockHashMatchCsv<'a> {
+    run_id: &'a str,
+    source_file: &'a str,
+    global_start: u64,
+    length: u64,
+    block_index: u64,
+    block_count: u64,
+    tool_version: &'a str,
+    config_hash: &'a str,
+    evidence_path: &'a str,
+    evidence_sha256: &'a str,
+}
+
+#[derive(Serialize)]
+struct FuzzyMatchCsv<'a> {
+    run_id: &'a str,
+    carved_path: &'a str,
+    file_type: &'a str,
+    global_start: u64,
+    algorithm: &'a str,
+    score: u32,
+    reference_name: &'a str,
+    reference_digest: &'a str,
+    tool_version: &'a str,
+    config_hash: &'a str,
+    evidence_path: &'a str,
+    evidence_sha256: &'a str,
+}
+
+#[derive(Serialize)]
+struct ArchiveEntryCsv<'a> {
+    run_id: &'a str,
+    carved_path: &'a str,
+    file_type: &'a str,
+    index: u64,
+    name: &'a str,
+    compressed_size: Option<u64>,
+    uncompressed_size: Option<u64>,
+    modified: Option<String>,
+    crc32: Option<u32>,
+    encrypted: bool,
+    compression_method: Option<&'a str>,
+    tool_version: &'a str,
+    config_hash: &'a str,
+    evidence_path: &'a str,
+    evidence_sha256: &'a str,
+}
+
+#[derive(Serialize)]
+struct KeywordHitCsv<'a> {
+    run_id: &'a str,
+    term: &'a str,
+    encoding: &'a str,
+    global_offset: u64,
+    length: u64,
+    context_start: u64,
+    context_hex: &'a str,
+    context_text: &'a str,
+    carved_path: &'a str,
+    tool_version: &'a str,
+    config_hash: &'a str,
+    evidence_path: &'a str,
+    evidence_sha256: &'a str,
+}
+
 impl CsvSink {
     pub fn new(
         _run_id: &str,
@@ -155,11 +320,22 @@ impl CsvSink {
 
         let files_file = File::create(meta_dir.join("carved_files.csv"))?;
         let strings_file = File::create(meta_dir.join("string_artefacts.csv"))?;
+        let ips_writer = typed_artefact_writer(&meta_dir.join("artefacts_ips.csv"))?;
+        let cards_writer = typed_artefact_writer(&meta_dir.join("artefacts_credit_cards.csv"))?;
+        let crypto_writer = typed_artefact_writer(&meta_dir.join("artefacts_crypto_wallets.csv"))?;
+        let secrets_writer = typed_artefact_writer(&meta_dir.join("artefacts_secrets.csv"))?;
         let history_file = File::create(meta_dir.join("browser_history.csv"))?;
         let cookies_file = File::create(meta_dir.join("browser_cookies.csv"))?;
         let downloads_file = File::create(meta_dir.join("browser_downloads.csv"))?;
         let run_file = File::create(meta_dir.join("run_summary.csv"))?;
         let entropy_file = File::create(meta_dir.join("entropy_regions.csv"))?;
+        let bad_sectors_file = File::create(meta_dir.join("bad_sectors.csv"))?;
+        let yara_file = File::create(meta_dir.join("yara_matches.csv"))?;
+        let keywords_path = meta_dir.join("keyword_hits.csv");
+        let keywords_file = File::create(&keywords_path)?;
+        let block_hash_file = File::create(meta_dir.join("block_hash_matches.csv"))?;
+        let fuzzy_file = File::create(meta_dir.join("fuzzy_matches.csv"))?;
+        let archive_entries_file = File::create(meta_dir.join("archive_entries.csv"))?;
 
         let mut files_writer = csv::WriterBuilder::new()
             .has_headers(false)
@@ -182,6 +358,24 @@ impl CsvSink {
         let mut entropy_writer = csv::WriterBuilder::new()
             .has_headers(false)
             .from_writer(entropy_file);
+        let mut bad_sectors_writer = csv::WriterBuilder::new()
+            .has_headers(false)
+            .from_writer(bad_sectors_file);
+        let mut yara_writer = csv::WriterBuilder::new()
+            .has_headers(false)
+            .from_writer(yara_file);
+        let mut keywords_writer = csv::WriterBuilder::new()
+            .has_headers(false)
+            .from_writer(keywords_file);
+        let mut block_hash_writer = csv::WriterBuilder::new()
+            .has_headers(false)
+            .from_writer(block_hash_file);
+        let mut fuzzy_writer = csv::WriterBuilder::new()
+            .has_headers(false)
+            .from_writer(fuzzy_file);
+        let mut archive_entries_writer = csv::WriterBuilder::new()
+            .has_headers(false)
+            .from_writer(archive_entries_file);
 
         files_writer.write_record(&[
             "run_id",
@@ -193,10 +387,32 @@ impl CsvSink {
             "size",
             "md5",
             "sha256",
+            "ssdeep",
+            "tlsh",
             "validated",
             "truncated",
             "errors",
             "pattern_id",
+            "validation_status",
+            "validation_reason",
+            "partition_index",
+            "partition_type",
+            "partition_offset",
+            "allocation_status",
+            "containing_fs_path",
+            "containing_inode",
+            "offset_in_file",
+            "hash_match",
+            "hash_set",
+            "is_duplicate",
+            "duplicate_of",
+            "fragments",
+            "parent_path",
+            "parent_id",
+            "offset_in_parent",
+            "depth",
+            "encryption",
+            "encryption_algorithm",
             "tool_version",
             "config_hash",
             "evidence_path",
@@ -210,6 +426,9 @@ impl CsvSink {
             "encoding",
             "global_start",
             "global_end",
+            "containing_fs_path",
+            "containing_inode",
+            "offset_in_file",
             "tool_version",
             "config_hash",
             "evidence_path",
@@ -225,6 +444,9 @@ impl CsvSink {
             "visit_time",
             "visit_source",
             "source_file",
+            "containing_fs_path",
+            "containing_inode",
+            "offset_in_file",
             "tool_version",
             "config_hash",
             "evidence_path",
@@ -245,6 +467,9 @@ impl CsvSink {
             "is_secure",
             "is_http_only",
             "source_file",
+            "containing_fs_path",
+            "containing_inode",
+            "offset_in_file",
             "tool_version",
             "config_hash",
             "evidence_path",
@@ -262,6 +487,9 @@ impl CsvSink {
             "total_bytes",
             "state",
             "source_file",
+            "containing_fs_path",
+            "containing_inode",
+            "offset_in_file",
             "tool_version",
             "config_hash",
             "evidence_path",
@@ -276,6 +504,12 @@ impl CsvSink {
             "files_carved",
             "string_spans",
             "artefacts_extracted",
+            "bytes_skipped",
+            "skipped_ranges",
+            "unaligned_hits",
+            "known_good_files",
+            "known_bad_files",
+            "encrypted_files",
             "tool_version",
             "config_hash",
             "evidence_path",
@@ -294,6 +528,91 @@ impl CsvSink {
             "evidence_sha256",
         ])?;
 
+        bad_sectors_writer.write_record([
+            "run_id",
+            "global_start",
+            "length",
+            "tool_version",
+            "config_hash",
+            "evidence_path",
+            "evidence_sha256",
+        ])?;
+
+        yara_writer.write_record([
+            "run_id",
+            "rule",
+            "tags",
+            "global_offset",
+            "string_id",
+            "carved_path",
+            "tool_version",
+            "config_hash",
+            "evidence_path",
+            "evidence_sha256",
+        ])?;
+
+        block_hash_writer.write_record([
+            "run_id",
+            "source_file",
+            "global_start",
+            "length",
+            "block_index",
+            "block_count",
+            "tool_version",
+            "config_hash",
+            "evidence_path",
+            "evidence_sha256",
+        ])?;
+
+        fuzzy_writer.write_record([
+            "run_id",
+            "carved_path",
+            "file_type",
+            "global_start",
+            "algorithm",
+            "score",
+            "reference_name",
+            "reference_digest",
+            "tool_version",
+            "config_hash",
+            "evidence_path",
+            "evidence_sha256",
+        ])?;
+
+        archive_entries_writer.write_record([
+            "run_id",
+            "carved_path",
+            "file_type",
+            "index",
+            "name",
+            "compressed_size",
+            "uncompressed_size",
+            "modified",
+            "crc32",
+            "encrypted",
+            "compression_method",
+            "tool_version",
+            "config_hash",
+            "evidence_path",
+            "evidence_sha256",
+        ])?;
+
+        keywords_writer.write_record([
+            "run_id",
+            "term",
+            "encoding",
+            "global_offset",
+            "length",
+            "context_start",
+            "context_hex",
+            "context_text",
+            "carved_path",
+            "tool_version",
+            "config_hash",
+            "evidence_path",
+            "evidence_sha256",
+        ])?;
+
         Ok(Self {
             tool_version: tool_version.to_string(),
             config_hash: config_hash.to_string(),
@@ -301,11 +620,22 @@ impl CsvSink {
             evidence_sha256: evidence_sha256.to_string(),
             files_writer: Mutex::new(files_writer),
             strings_writer: Mutex::new(strings_writer),
+            ips_writer: Mutex::new(ips_writer),
+            cards_writer: Mutex::new(cards_writer),
+            crypto_writer: Mutex::new(crypto_writer),
+            secrets_writer: Mutex::new(secrets_writer),
             history_writer: Mutex::new(history_writer),
             cookies_writer: Mutex::new(cookies_writer),
             downloads_writer: Mutex::new(downloads_writer),
             run_writer: Mutex::new(run_writer),
             entropy_writer: Mutex::new(entropy_writer),
+            bad_sectors_writer: Mutex::new(bad_sectors_writer),
+            yara_writer: Mutex::new(yara_writer),
+            keywords_writer: Mutex::new(keywords_writer),
+            keywords_path,
+            block_hash_writer: Mutex::new(block_hash_writer),
+            fuzzy_writer: Mutex::new(fuzzy_writer),
+            archive_entries_writer: Mutex::new(archive_entries_writer),
         })
     }
 }
@@ -322,10 +652,32 @@ impl MetadataSink for CsvSink {
             size: file.size,
             md5: file.md5.as_deref(),
             sha256: file.sha256.as_deref(),
+            ssdeep: file.ssdeep.as_deref(),
+            tlsh: file.tlsh.as_deref(),
             validated: file.validated,
             truncated: file.truncated,
             errors: file.errors.join("; "),
             pattern_id: file.pattern_id.as_deref(),
+            validation_status: file.validation_status.as_deref(),
+            validation_reason: file.validation_reason.as_deref(),
+            partition_index: file.partition_index,
+            partition_type: file.partition_type.as_deref(),
+            partition_offset: file.partition_offset,
+            allocation_status: file.allocation_status.as_deref(),
+            containing_fs_path: file.containing_fs_path.as_deref(),
+            containing_inode: file.containing_inode,
+            offset_in_file: file.offset_in_file,
+            hash_match: file.hash_match.as_deref(),
+            hash_set: file.hash_set.as_deref(),
+            is_duplicate: file.is_duplicate,
+            duplicate_of: file.duplicate_of.as_deref(),
+            fragments: join_fragments(&file.fragments),
+            parent_path: file.parent_path.as_deref(),
+            parent_id: file.parent_id.as_deref(),
+            offset_in_parent: file.offset_in_parent,
+            depth: file.depth,
+            encryption: file.encryption.as_str(),
+            encryption_algorithm: file.encryption_algorithm.as_deref(),
             tool_version: &self.tool_version,
             config_hash: &self.config_hash,
             evidence_path: &self.evidence_path,
@@ -340,6 +692,37 @@ impl MetadataSink for CsvSink {
     }
 
     fn record_string(&self, artefact: &StringArtefact) -> Result<(), MetadataError> {
+        let typed = match artefact.artefact_kind {
+            ArtefactKind::Ip => Some((&self.ips_writer, "ips")),
+            ArtefactKind::CreditCard => Some((&self.cards_writer, "credit cards")),
+            ArtefactKind::CryptoWallet => Some((&self.crypto_writer, "crypto wallets")),
+            ArtefactKind::Secret => Some((&self.secrets_writer, "secrets")),
+            _ => None,
+        };
+        if let Some((writer, name)) = typed {
+            let record = TypedArtefactCsv {
+                run_id: &artefact.run_id,
+                artefact_kind: artefact_kind_label(&artefact.artefact_kind),
+                subtype: artefact.subtype.as_deref().unwrap_or(""),
+                content: &artefact.content,
+                encoding: &artefact.encoding,
+                global_start: artefact.global_start,
+                global_end: artefact.global_end,
+                containing_fs_path: artefact.containing_fs_path.as_deref(),
+                containing_inode: artefact.containing_inode,
+                offset_in_file: artefact.offset_in_file,
+                tool_version: &self.tool_version,
+                config_hash: &self.config_hash,
+                evidence_path: &self.evidence_path,
+                evidence_sha256: &self.evidence_sha256,
+            };
+            let mut guard = writer
+                .lock()
+                .map_err(|_| MetadataError::Other(format!("{name} writer lock poisoned")))?;
+            guard.serialize(record)?;
+            return Ok(());
+        }
+
         let record = StringArtefactCsv {
             run_id: &artefact.run_id,
             artefact_kind: artefact_kind_label(&artefact.artefact_kind),
@@ -347,6 +730,9 @@ impl MetadataSink for CsvSink {
             encoding: &artefact.encoding,
             global_start: artefact.global_start,
             global_end: artefact.global_end,
+            containing_fs_path: artefact.containing_fs_path.as_deref(),
+            containing_inode: artefact.containing_inode,
+            offset_in_file: artefact.offset_in_file,
             tool_version: &self.tool_version,
             config_hash: &self.config_hash,
             evidence_path: &self.evidence_path,
@@ -373,6 +759,9 @@ impl MetadataSink for CsvSink {
             visit_time: record.visit_time.map(|t| t.to_string()),
             visit_source: record.visit_source.as_deref(),
             source_file: record.source_file.to_string_lossy().to_string(),
+            containing_fs_path: record.containing_fs_path.as_deref(),
+            containing_inode: record.containing_inode,
+            offset_in_file: record.offset_in_file,
             tool_version: &self.tool_version,
             config_hash: &self.config_hash,
             evidence_path: &self.evidence_path,
@@ -401,6 +790,9 @@ impl MetadataSink for CsvSink {
             is_secure: record.is_secure,
             is_http_only: record.is_http_only,
             source_file: record.source_file.to_string_lossy().to_string(),
+            containing_fs_path: record.containing_fs_path.as_deref(),
+            containing_inode: record.containing_inode,
+            offset_in_file: record.offset_in_file,
             tool_version: &self.tool_version,
             config_hash: &self.config_hash,
             evidence_path: &self.evidence_path,
@@ -426,6 +818,9 @@ impl MetadataSink for CsvSink {
             total_bytes: record.total_bytes,
             state: record.state.as_deref(),
             source_file: record.source_file.to_string_lossy().to_string(),
+            containing_fs_path: record.containing_fs_path.as_deref(),
+            containing_inode: record.containing_inode,
+            offset_in_file: record.offset_in_file,
             tool_version: &self.tool_version,
             config_hash: &self.config_hash,
             evidence_path: &self.evidence_path,
@@ -448,6 +843,12 @@ impl MetadataSink for CsvSink {
             files_carved: summary.files_carved,
             string_spans: summary.string_spans,
             artefacts_extracted: summary.artefacts_extracted,
+            bytes_skipped: summary.bytes_skipped,
+            skipped_ranges: format_skipped_ranges(&summary.skipped_ranges),
+            unaligned_hits: format_type_counts(&summary.unaligned_hits),
+            known_good_files: summary.known_good_files,
+            known_bad_files: summary.known_bad_files,
+            encrypted_files: summary.encrypted_files,
             tool_version: &self.tool_version,
             config_hash: &self.config_hash,
             evidence_path: &self.evidence_path,
@@ -481,6 +882,179 @@ impl MetadataSink for CsvSink {
         Ok(())
     }
 
+    fn record_bad_sector(&self, range: &BadSectorRange) -> Result<(), MetadataError> {
+        let record = BadSectorCsv {
+            run_id: &range.run_id,
+            global_start: range.global_start,
+            length: range.length,
+            tool_version: &self.tool_version,
+            config_hash: &self.config_hash,
+            evidence_path: &self.evidence_path,
+            evidence_sha256: &self.evidence_sha256,
+        };
+        let mut guard = self
+            .bad_sectors_writer
+            .lock()
+            .map_err(|_| MetadataError::Other("bad sectors writer lock poisoned".into()))?;
+        guard.serialize(record)?;
+        Ok(())
+    }
+
+    fn record_yara_match(&self, m: &YaraMatch) -> Result<(), MetadataError> {
+        let record = YaraMatchCsv {
+            run_id: &m.run_id,
+            rule: &m.rule,
+            tags: m.tags.join(";"),
+            global_offset: m.global_offset,
+            string_id: m.string_id.as_deref().unwrap_or(""),
+            carved_path: m.carved_path.as_deref().unwrap_or(""),
+            tool_version: &self.tool_version,
+            config_hash: &self.config_hash,
+            evidence_path: &self.evidence_path,
+            evidence_sha256: &self.evidence_sha256,
+        };
+        let mut guard = self
+            .yara_writer
+            .lock()
+            .map_err(|_| MetadataError::Other("yara writer lock poisoned".into()))?;
+        guard.serialize(record)?;
+        Ok(())
+    }
+
+    fn record_keyword_hit(&self, hit: &KeywordHit) -> Result<(), MetadataError> {
+        let record = KeywordHitCsv {
+            run_id: &hit.run_id,
+            term: &hit.term,
+            encoding: &hit.encoding,
+            global_offset: hit.global_offset,
+            length: hit.length,
+            context_start: hit.context_start,
+            context_hex: &hit.context_hex,
+            context_text: &hit.context_text,
+            carved_path: hit.carved_path.as_deref().unwrap_or(""),
+            tool_version: &self.tool_version,
+            config_hash: &self.config_hash,
+            evidence_path: &self.evidence_path,
+            evidence_sha256: &self.evidence_sha256,
+        };
+        let mut guard = self
+            .keywords_writer
+            .lock()
+            .map_err(|_| MetadataError::Other("keywords writer lock poisoned".into()))?;
+        guard.serialize(record)?;
+        Ok(())
+    }
+
+    fn attribute_keyword_hits(
+        &self,
+        carved_path: &dyn Fn(u64) -> Option<String>,
+    ) -> Result<(), MetadataError> {
+        let mut guard = self
+            .keywords_writer
+            .lock()
+            .map_err(|_| MetadataError::Other("keywords writer lock poisoned".into()))?;
+        guard.flush()?;
+        let mut reader = csv::Reader::from_path(&self.keywords_path)?;
+        let headers = reader.headers()?.clone();
+        let column = |name: &str| {
+            headers.iter().position(|h| h == name).ok_or_else(|| {
+                MetadataError::Other(format!("keyword_hits.csv has no {name} column"))
+            })
+        };
+        let offset_col = column("global_offset")?;
+        let path_col = column("carved_path")?;
+        let tmp_path = self.keywords_path.with_extension("csv.tmp");
+        let mut out = csv::Writer::from_path(&tmp_path)?;
+        out.write_record(&headers)?;
+        for record in reader.records() {
+            let record = record?;
+            let offset: u64 = record[offset_col].parse().map_err(|err| {
+                MetadataError::Other(format!("bad keyword hit offset in csv: {err}"))
+            })?;
+            let path = carved_path(offset).unwrap_or_default();
+            out.write_record(record.iter().enumerate().map(|(i, field)| {
+                if i == path_col { path.as_str() } else { field }
+            }))?;
+        }
+        out.flush()?;
+        drop(out);
+        std::fs::rename(&tmp_path, &self.keywords_path)?;
+        *guard = csv::WriterBuilder::new()
+            .has_headers(false)
+            .from_writer(OpenOptions::new().append(true).open(&self.keywords_path)?);
+        Ok(())
+    }
+
+    fn record_block_hash_match(&self, m: &BlockHashMatch) -> Result<(), MetadataError> {
+        let record = BlockHashMatchCsv {
+            run_id: &m.run_id,
+            source_file: &m.source_file,
+            global_start: m.global_start,
+            length: m.length,
+            block_index: m.block_index,
+            block_count: m.block_count,
+            tool_version: &self.tool_version,
+            config_hash: &self.config_hash,
+            evidence_path: &self.evidence_path,
+            evidence_sha256: &self.evidence_sha256,
+        };
+        let mut guard = self
+            .block_hash_writer
+            .lock()
+            .map_err(|_| MetadataError::Other("block hash writer lock poisoned".into()))?;
+        guard.serialize(record)?;
+        Ok(())
+    }
+
+    fn record_fuzzy_match(&self, m: &FuzzyMatch) -> Result<(), MetadataError> {
+        let record = FuzzyMatchCsv {
+            run_id: &m.run_id,
+            carved_path: &m.carved_path,
+            file_type: &m.file_type,
+            global_start: m.global_start,
+            algorithm: &m.algorithm,
+            score: m.score,
+            reference_name: &m.reference_name,
+            reference_digest: &m.reference_digest,
+            tool_version: &self.tool_version,
+            config_hash: &self.config_hash,
+            evidence_path: &self.evidence_path,
+            evidence_sha256: &self.evidence_sha256,
+        };
+        let mut guard = self
+            .fuzzy_writer
+            .lock()
+            .map_err(|_| MetadataError::Other("fuzzy writer lock poisoned".into()))?;
+        guard.serialize(record)?;
+        Ok(())
+    }
+
+    fn record_archive_entry(&self, entry: &ArchiveEntry) -> Result<(), MetadataError> {
+        let record = ArchiveEntryCsv {
+            run_id: &entry.run_id,
+            carved_path: &entry.carved_path,
+            file_type: &entry.file_type,
+            index: entry.index,
+            name: &entry.name,
+            compressed_size: entry.compressed_size,
+            uncompressed_size: entry.uncompressed_size,
+            modified: entry.modified.map(|dt| dt.to_string()),
+            crc32: entry.crc32,
+            encrypted: entry.encrypted,
+            compression_method: entry.compression_method.as_deref(),
+            tool_version: &self.tool_version,
+            config_hash: &self.config_hash,
+            evidence_path: &self.evidence_path,
+            evidence_sha256: &self.evidence_sha256,
+        };
+        let mut guard = self
+            .archive_entries_writer
+            .lock()
+            .map_err(|_| MetadataError::Other("archive entries writer lock poisoned".into()))?;
+        guard.serialize(record)?;
+        Ok(())
+    }
+
     fn flush(&self) -> Result<(), MetadataError> {
         let mut files = self
             .files_writer
@@ -490,6 +1064,22 @@ impl MetadataSink for CsvSink {
             .strings_writer
             .lock()
             .map_err(|_| MetadataError::Other("strings writer lock poisoned".into()))?;
+        let mut ips = self
+            .ips_writer
+            .lock()
+            .map_err(|_| MetadataError::Other("ips writer lock poisoned".into()))?;
+        let mut cards = self
+            .cards_writer
+            .lock()
+            .map_err(|_| MetadataError::Other("credit cards writer lock poisoned".into()))?;
+        let mut crypto = self
+            .crypto_writer
+            .lock()
+            .map_err(|_| MetadataError::Other("crypto wallets writer lock poisoned".into()))?;
+        let mut secrets = self
+            .secrets_writer
+            .lock()
+            .map_err(|_| MetadataError::Other("secrets writer lock poisoned".into()))?;
         let mut history = self
             .history_writer
             .lock()
@@ -510,23 +1100,85 @@ impl MetadataSink for CsvSink {
             .entropy_writer
             .lock()
             .map_err(|_| MetadataError::Other("entropy writer lock poisoned".into()))?;
+        let mut bad_sectors = self
+            .bad_sectors_writer
+            .lock()
+            .map_err(|_| MetadataError::Other("bad sectors writer lock poisoned".into()))?;
+        let mut yara = self
+            .yara_writer
+            .lock()
+            .map_err(|_| MetadataError::Other("yara writer lock poisoned".into()))?;
+        let mut keywords = self
+            .keywords_writer
+            .lock()
+            .map_err(|_| MetadataError::Other("keywords writer lock poisoned".into()))?;
+        let mut block_hash = self
+            .block_hash_writer
+            .lock()
+            .map_err(|_| MetadataError::Other("block hash writer lock poisoned".into()))?;
+        let mut fuzzy = self
+            .fuzzy_writer
+            .lock()
+            .map_err(|_| MetadataError::Other("fuzzy writer lock poisoned".into()))?;
+        let mut archive_entries = self
+            .archive_entries_writer
+            .lock()
+            .map_err(|_| MetadataError::Other("archive entries writer lock poisoned".into()))?;
         files.flush()?;
         strings.flush()?;
+        ips.flush()?;
+        cards.flush()?;
+        crypto.flush()?;
+        secrets.flush()?;
         history.flush()?;
         cookies.flush()?;
         downloads.flush()?;
         run.flush()?;
         entropy.flush()?;
+        bad_sectors.flush()?;
+        yara.flush()?;
+        keywords.flush()?;
+        block_hash.flush()?;
+        fuzzy.flush()?;
+        archive_entries.flush()?;
         Ok(())
     }
 }
 
-fn artefact_kind_label(kind: &ArtefactKind) -> &'static str {
+fn typed_artefact_writer(path: &Path) -> Result<csv::Writer<File>, MetadataError> {
+    let mut writer = csv::WriterBuilder::new()
+        .has_headers(false)
+        .from_writer(File::create(path)?);
+    writer.write_record([
+        "run_id",
+        "artefact_kind",
+        "subtype",
+        "content",
+        "encoding",
+        "global_start",
+        "global_end",
+        "containing_fs_path",
+        "containing_inode",
+        "offset_in_file",
+        "tool_version",
+        "config_hash",
+        "evidence_path",
+        "evidence_sha256",
+    ])?;
+    Ok(writer)
+}
+
+fn artefact_kind_label(kind: &ArtefactKind) -> Cow<'_, str> {
     match kind {
-        ArtefactKind::Url => "url",
-        ArtefactKind::Email => "email",
-        ArtefactKind::Phone => "phone",
-        ArtefactKind::GenericString => "string",
+        ArtefactKind::Url => Cow::Borrowed("url"),
+        ArtefactKind::Email => Cow::Borrowed("email"),
+        ArtefactKind::Phone => Cow::Borrowed("phone"),
+        ArtefactKind::Ip => Cow::Borrowed("ip"),
+        ArtefactKind::CreditCard => Cow::Borrowed("credit_card"),
+        ArtefactKind::CryptoWallet => Cow::Borrowed("crypto_wallet"),
+        ArtefactKind::Secret => Cow::Borrowed("secret"),
+        ArtefactKind::GenericString => Cow::Borrowed("string"),
+        ArtefactKind::Custom(name) => Cow::Owned(format!("custom:{name}")),
     }
 }
 
@@ -563,6 +1215,7 @@ mod tests {
             truncated: false,
             errors: Vec::new(),
             pattern_id: Some("jpeg_soi".to_string()),
+            ..Default::default()
         };
         sink.record_file(&file).expect("record file");
 
@@ -571,8 +1224,12 @@ mod tests {
             artefact_kind: ArtefactKind::Url,
             content: "https://example.com".to_string(),
             encoding: "ascii".to_string(),
+            subtype: None,
             global_start: 100,
             global_end: 120,
+            containing_fs_path: None,
+            containing_inode: None,
+            offset_in_file: None,
         };
         sink.record_string(&artefact).expect("record string");
 
@@ -585,6 +1242,9 @@ mod tests {
             visit_time: None,
             visit_source: None,
             source_file: "sqlite/history.sqlite".into(),
+            containing_fs_path: None,
+            containing_inode: None,
+            offset_in_file: None,
         };
         sink.record_history(&history).expect("record history");
 
@@ -602,6 +1262,9 @@ mod tests {
             is_secure: Some(true),
             is_http_only: Some(true),
             source_file: "sqlite/Cookies".into(),
+            containing_fs_path: None,
+            containing_inode: None,
+            offset_in_file: None,
         };
         sink.record_cookie(&cookie).expect("record cookie");
 
@@ -616,6 +1279,9 @@ mod tests {
             total_bytes: Some(123),
             state: Some("1".to_string()),
             source_file: "sqlite/History".into(),
+            containing_fs_path: None,
+            containing_inode: None,
+            offset_in_file: None,
         };
         sink.record_download(&download).expect("record download");
         let summary = RunSummary {
@@ -626,6 +1292,12 @@ mod tests {
             files_carved: 1,
             string_spans: 3,
             artefacts_extracted: 4,
+            bytes_skipped: 0,
+            skipped_ranges: Vec::new(),
+            unaligned_hits: Default::default(),
+            known_good_files: 0,
+            known_bad_files: 0,
+            encrypted_files: 0,
         };
         sink.record_run_summary(&summary).expect("record summary");
         let region = EntropyRegion {
@@ -677,4 +1349,45 @@ mod tests {
                 .exists()
         );
     }
+
+    #[test]
+    fn attributes_keyword_hits_after_the_run() {
+        let dir = tempdir().expect("tempdir");
+        let sink = CsvSink::new(
+            "run1",
+            "0.1.0",
+            "hash",
+            Path::new("/evidence.dd"),
+            "",
+            dir.path(),
+        )
+        .expect("csv sink");
+        let hit = |offset: u64| KeywordHit {
+            run_id: "run1".to_string(),
+            term: "secret".to_string(),
+            encoding: "ascii".to_string(),
+            global_offset: offset,
+            length: 6,
+            context_start: offset,
+            context_hex: "736563726574".to_string(),
+            context_text: "secret, \"quoted\"".to_string(),
+            carved_path: None,
+        };
+        sink.record_keyword_hit(&hit(10)).expect("record hit");
+        sink.record_keyword_hit(&hit(500)).expect("record hit");
+        sink.attribute_keyword_hits(&|offset| (offset < 100).then(|| "jpeg/a.jpg".to_string()))
+            .expect("attribute hits");
+        sink.flush().expect("flush");
+
+        let mut reader =
+            csv::Reader::from_path(dir.path().join("metadata").join("keyword_hits.csv"))
+                .expect("read csv");
+        let rows: Vec<csv::StringRecord> = reader.records().map(|r| r.expect("row")).collect();
+        assert_eq!(rows.len(), 2);
+        assert_eq!(&rows[0][7], "secret, \"quoted\"");
+        assert_eq!(&rows[0][8], "jpeg/a.jpg");
+        assert_eq!(&rows[1][3], "500");
+        assert_eq!(&rows[1][8], "");
+        assert_eq!(&rows[1][9], "0.1.0");
+    }
 }
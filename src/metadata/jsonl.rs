@@ -1,16 +1,19 @@
-use std::fs::File;
-use std::io::{BufWriter, Write};
-use std::path::Path;
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
 use std::sync::Mutex;
 
 use serde::Serialize;
 
 use crate::carve::CarvedFile;
-use crate::metadata::{EntropyRegion, MetadataError, MetadataSink, RunSummary};
+use crate::metadata::{
+    ArchiveEntry, BadSectorRange, BlockHashMatch, EntropyRegion, FuzzyMatch, KeywordHit,
+    MetadataError, MetadataSink, RunSummary, YaraMatch,
+};
 use crate::parsers::browser::{
     BrowserCookieRecord as CookieRecord, BrowserDownloadRecord as DownloadRecord,
 };
-use crate::strings::artifacts::StringArtefact;
+use crate::strings::artifacts::{ArtefactKind, StringArtefact};
 
 pub struct JsonlSink {
     tool_version: String,
@@ -19,11 +22,22 @@ pub struct JsonlSink {
     evidence_sha256: String,
     files_writer: Mutex<BufWriter<File>>,
     strings_writer: Mutex<BufWriter<File>>,
+    ips_writer: Mutex<BufWriter<File>>,
+    cards_writer: Mutex<BufWriter<File>>,
+    crypto_writer: Mutex<BufWriter<File>>,
+    secrets_writer: Mutex<BufWriter<File>>,
     history_writer: Mutex<BufWriter<File>>,
     cookies_writer: Mutex<BufWriter<File>>,
     downloads_writer: Mutex<BufWriter<File>>,
     run_writer: Mutex<BufWriter<File>>,
     entropy_writer: Mutex<BufWriter<File>>,
+    bad_sectors_writer: Mutex<BufWriter<File>>,
+    yara_writer: Mutex<BufWriter<File>>,
+    keywords_writer: Mutex<BufWriter<File>>,
+    keywords_path: PathBuf,
+    block_hash_writer: Mutex<BufWriter<File>>,
+    fuzzy_writer: Mutex<BufWriter<File>>,
+    archive_entries_writer: Mutex<BufWriter<File>>,
 }
 
 #[derive(Serialize)]
@@ -96,6 +110,66 @@ struct EntropyRegionRecord<'a> {
     evidence_sha256: &'a str,
 }
 
+#[derive(Serialize)]
+struct BadSectorRecord<'a> {
+    #[serde(flatten)]
+    range: &'a BadSectorRange,
+    tool_version: &'a str,
+    config_hash: &'a str,
+    evidence_path: &'a str,
+    evidence_sha256: &'a str,
+}
+
+#[derive(Serialize)]
+struct YaraMatchRecord<'a> {
+    #[serde(flatten)]
+    yara_match: &'a YaraMatch,
+    tool_version: &'a str,
+    config_hash: &'a str,
+    evidence_path: &'a str,
+    evidence_sha256: &'a str,
+}
+
+#[derive(Serialize)]
+struct KeywordHitRecord<'a> {
+    #[serde(flatten)]
+    hit: &'a KeywordHit,
+    tool_version: &'a str,
+    config_hash: &'a str,
+    evidence_path: &'a str,
+    evidence_sha256: &'a str,
+}
+
+#[derive(Serialize)]
+struct BlockHashMatchRecord<'a> {
+    #[serde(flatten)]
+    block_match: &'a BlockHashMatch,
+    tool_version: &'a str,
+    config_hash: &'a str,
+    evidence_path: &'a str,
+    evidence_sha256: &'a str,
+}
+
+#[derive(Serialize)]
+struct FuzzyMatchRecord<'a> {
+    #[serde(flatten)]
+    fuzzy_match: &'a FuzzyMatch,
+    tool_version: &'a str,
+    config_hash: &'a str,
+    evidence_path: &'a str,
+    evidence_sha256: &'a str,
+}
+
+#[derive(Serialize)]
+struct ArchiveEntryRecord<'a> {
+    #[serde(flatten)]
+    entry: &'a ArchiveEntry,
+    tool_version: &'a str,
+    config_hash: &'a str,
+    evidence_path: &'a str,
+    evidence_sha256: &'a str,
+}
+
 impl JsonlSink {
     pub fn new(
         _run_id: &str,
@@ -109,18 +183,38 @@ impl JsonlSink {
         std::fs::create_dir_all(&meta_dir)?;
         let files_path = meta_dir.join("carved_files.jsonl");
         let strings_path = meta_dir.join("string_artefacts.jsonl");
+        let ips_path = meta_dir.join("artefacts_ips.jsonl");
+        let cards_path = meta_dir.join("artefacts_credit_cards.jsonl");
+        let crypto_path = meta_dir.join("artefacts_crypto_wallets.jsonl");
+        let secrets_path = meta_dir.join("artefacts_secrets.jsonl");
         let history_path = meta_dir.join("browser_history.jsonl");
         let cookies_path = meta_dir.join("browser_cookies.jsonl");
         let downloads_path = meta_dir.join("browser_downloads.jsonl");
         let run_path = meta_dir.join("run_summary.jsonl");
         let entropy_path = meta_dir.join("entropy_regions.jsonl");
+        let bad_sectors_path = meta_dir.join("bad_sectors.jsonl");
+        let yara_path = meta_dir.join("yara_matches.jsonl");
+        let keywords_path = meta_dir.join("keyword_hits.jsonl");
+        let block_hash_path = meta_dir.join("block_hash_matches.jsonl");
+        let fuzzy_path = meta_dir.join("fuzzy_matches.jsonl");
+        let archive_entries_path = meta_dir.join("archive_entries.jsonl");
         let files_file = File::create(files_path)?;
         let strings_file = File::create(strings_path)?;
+        let ips_file = File::create(ips_path)?;
+        let cards_file = File::create(cards_path)?;
+        let crypto_file = File::create(crypto_path)?;
+        let secrets_file = File::create(secrets_path)?;
         let history_file = File::create(history_path)?;
         let cookies_file = File::create(cookies_path)?;
         let downloads_file = File::create(downloads_path)?;
         let run_file = File::create(run_path)?;
         let entropy_file = File::create(entropy_path)?;
+        let bad_sectors_file = File::create(bad_sectors_path)?;
+        let yara_file = File::create(yara_path)?;
+        let keywords_file = File::create(&keywords_path)?;
+        let block_hash_file = File::create(block_hash_path)?;
+        let fuzzy_file = File::create(fuzzy_path)?;
+        let archive_entries_file = File::create(archive_entries_path)?;
         Ok(Self {
             tool_version: tool_version.to_string(),
             config_hash: config_hash.to_string(),
@@ -128,11 +222,22 @@ impl JsonlSink {
             evidence_sha256: evidence_sha256.to_string(),
             files_writer: Mutex::new(BufWriter::new(files_file)),
             strings_writer: Mutex::new(BufWriter::new(strings_file)),
+            ips_writer: Mutex::new(BufWriter::new(ips_file)),
+            cards_writer: Mutex::new(BufWriter::new(cards_file)),
+            crypto_writer: Mutex::new(BufWriter::new(crypto_file)),
+            secrets_writer: Mutex::new(BufWriter::new(secrets_file)),
             history_writer: Mutex::new(BufWriter::new(history_file)),
             cookies_writer: Mutex::new(BufWriter::new(cookies_file)),
             downloads_writer: Mutex::new(BufWriter::new(downloads_file)),
             run_writer: Mutex::new(BufWriter::new(run_file)),
             entropy_writer: Mutex::new(BufWriter::new(entropy_file)),
+            bad_sectors_writer: Mutex::new(BufWriter::new(bad_sectors_file)),
+            yara_writer: Mutex::new(BufWriter::new(yara_file)),
+            keywords_writer: Mutex::new(BufWriter::new(keywords_file)),
+            keywords_path,
+            block_hash_writer: Mutex::new(BufWriter::new(block_hash_file)),
+            fuzzy_writer: Mutex::new(BufWriter::new(fuzzy_file)),
+            archive_entries_writer: Mutex::new(BufWriter::new(archive_entries_file)),
         })
     }
 }
@@ -163,10 +268,17 @@ impl MetadataSink for JsonlSink {
             evidence_path: &self.evidence_path,
             evidence_sha256: &self.evidence_sha256,
         };
-        let mut guard = self
-            .strings_writer
+        // Typed artefacts get their own files; the rest share string_artefacts
+        let (writer, name) = match artefact.artefact_kind {
+            ArtefactKind::Ip => (&self.ips_writer, "ips"),
+            ArtefactKind::CreditCard => (&self.cards_writer, "credit cards"),
+            ArtefactKind::CryptoWallet => (&self.crypto_writer, "crypto wallets"),
+            ArtefactKind::Secret => (&self.secrets_writer, "secrets"),
+            _ => (&self.strings_writer, "strings"),
+        };
+        let mut guard = writer
             .lock()
-            .map_err(|_| MetadataError::Other("strings writer lock poisoned".into()))?;
+            .map_err(|_| MetadataError::Other(format!("{name} writer lock poisoned")))?;
         serde_json::to_writer(&mut *guard, &record)?;
         guard.write_all(b"\n")?;
         Ok(())
@@ -260,6 +372,139 @@ impl MetadataSink for JsonlSink {
         Ok(())
     }
 
+    fn record_bad_sector(&self, range: &BadSectorRange) -> Result<(), MetadataError> {
+        let record = BadSectorRecord {
+            range,
+            tool_version: &self.tool_version,
+            config_hash: &self.config_hash,
+            evidence_path: &self.evidence_path,
+            evidence_sha256: &self.evidence_sha256,
+        };
+        let mut guard = self
+            .bad_sectors_writer
+            .lock()
+            .map_err(|_| MetadataError::Other("bad sectors writer lock poisoned".into()))?;
+        serde_json::to_writer(&mut *guard, &record)?;
+        guard.write_all(b"\n")?;
+        Ok(())
+    }
+
+    fn record_yara_match(&self, m: &YaraMatch) -> Result<(), MetadataError> {
+        let record = YaraMatchRecord {
+            yara_match: m,
+            tool_version: &self.tool_version,
+            config_hash: &self.config_hash,
+            evidence_path: &self.evidence_path,
+            evidence_sha256: &self.evidence_sha256,
+        };
+        let mut guard = self
+            .yara_writer
+            .lock()
+            .map_err(|_| MetadataError::Other("yara writer lock poisoned".into()))?;
+        serde_json::to_writer(&mut *guard, &record)?;
+        guard.write_all(b"\n")?;
+        Ok(())
+    }
+
+    fn record_keyword_hit(&self, hit: &KeywordHit) -> Result<(), MetadataError> {
+        let record = KeywordHitRecord {
+            hit,
+            tool_version: &self.tool_version,
+            config_hash: &self.config_hash,
+            evidence_path: &self.evidence_path,
+            evidence_sha256: &self.evidence_sha256,
+        };
+        let mut guard = self
+            .keywords_writer
+            .lock()
+            .map_err(|_| MetadataError::Other("keywords writer lock poisoned".into()))?;
+        serde_json::to_writer(&mut *guard, &record)?;
+        guard.write_all(b"\n")?;
+        Ok(())
+    }
+
+    fn attribute_keyword_hits(
+        &self,
+        carved_path: &dyn Fn(u64) -> Option<String>,
+    ) -> Result<(), MetadataError> {
+        let mut guard = self
+            .keywords_writer
+            .lock()
+            .map_err(|_| MetadataError::Other("keywords writer lock poisoned".into()))?;
+        guard.flush()?;
+        let tmp_path = self.keywords_path.with_extension("jsonl.tmp");
+        let mut out = BufWriter::new(File::create(&tmp_path)?);
+        for line in BufReader::new(File::open(&self.keywords_path)?).lines() {
+            let mut hit: KeywordHit = serde_json::from_str(&line?)?;
+            hit.carved_path = carved_path(hit.global_offset);
+            let record = KeywordHitRecord {
+                hit: &hit,
+                tool_version: &self.tool_version,
+                config_hash: &self.config_hash,
+                evidence_path: &self.evidence_path,
+                evidence_sha256: &self.evidence_sha256,
+            };
+            serde_json::to_writer(&mut out, &record)?;
+            out.write_all(b"\n")?;
+        }
+        out.flush()?;
+        drop(out);
+        std::fs::rename(&tmp_path, &self.keywords_path)?;
+        *guard = BufWriter::new(OpenOptions::new().append(true).open(&self.keywords_path)?);
+        Ok(())
+    }
+
+    fn record_block_hash_match(&self, m: &BlockHashMatch) -> Result<(), MetadataError> {
+        let record = BlockHashMatchRecord {
+            block_match: m,
+            tool_version: &self.tool_version,
+            config_hash: &self.config_hash,
+            evidence_path: &self.evidence_path,
+            evidence_sha256: &self.evidence_sha256,
+        };
+        let mut guard = self
+            .block_hash_writer
+            .lock()
+            .map_err(|_| MetadataError::Other("block hash writer lock poisoned".into()))?;
+        serde_json::to_writer(&mut *guard, &record)?;
+        guard.write_all(b"\n")?;
+        Ok(())
+    }
+
+    fn record_fuzzy_match(&self, m: &FuzzyMatch) -> Result<(), MetadataError> {
+        let record = FuzzyMatchRecord {
+            fuzzy_match: m,
+            tool_version: &self.tool_version,
+            config_hash: &self.config_hash,
+            evidence_path: &self.evidence_path,
+            evidence_sha256: &self.evidence_sha256,
+        };
+        let mut guard = self
+            .fuzzy_writer
+            .lock()
+            .map_err(|_| MetadataError::Other("fuzzy writer lock poisoned".into()))?;
+        serde_json::to_writer(&mut *guard, &record)?;
+        guard.write_all(b"\n")?;
+        Ok(())
+    }
+
+    fn record_archive_entry(&self, entry: &ArchiveEntry) -> Result<(), MetadataError> {
+        let record = ArchiveEntryRecord {
+            entry,
+            tool_version: &self.tool_version,
+            config_hash: &self.config_hash,
+            evidence_path: &self.evidence_path,
+            evidence_sha256: &self.evidence_sha256,
+        };
+        let mut guard = self
+            .archive_entries_writer
+            .lock()
+            .map_err(|_| MetadataError::Other("archive entries writer lock poisoned".into()))?;
+        serde_json::to_writer(&mut *guard, &record)?;
+        guard.write_all(b"\n")?;
+        Ok(())
+    }
+
     fn flush(&self) -> Result<(), MetadataError> {
         let mut files = self
             .files_writer
@@ -269,6 +514,22 @@ impl MetadataSink for JsonlSink {
             .strings_writer
             .lock()
             .map_err(|_| MetadataError::Other("strings writer lock poisoned".into()))?;
+        let mut ips = self
+            .ips_writer
+            .lock()
+            .map_err(|_| MetadataError::Other("ips writer lock poisoned".into()))?;
+        let mut cards = self
+            .cards_writer
+            .lock()
+            .map_err(|_| MetadataError::Other("credit cards writer lock poisoned".into()))?;
+        let mut crypto = self
+            .crypto_writer
+            .lock()
+            .map_err(|_| MetadataError::Other("crypto wallets writer lock poisoned".into()))?;
+        let mut secrets = self
+            .secrets_writer
+            .lock()
+            .map_err(|_| MetadataError::Other("secrets writer lock poisoned".into()))?;
         let mut history = self
             .history_writer
             .lock()
@@ -289,13 +550,47 @@ impl MetadataSink for JsonlSink {
             .entropy_writer
             .lock()
             .map_err(|_| MetadataError::Other("entropy writer lock poisoned".into()))?;
+        let mut bad_sectors = self
+            .bad_sectors_writer
+            .lock()
+            .map_err(|_| MetadataError::Other("bad sectors writer lock poisoned".into()))?;
+        let mut yara = self
+            .yara_writer
+            .lock()
+            .map_err(|_| MetadataError::Other("yara writer lock poisoned".into()))?;
+        let mut keywords = self
+            .keywords_writer
+            .lock()
+            .map_err(|_| MetadataError::Other("keywords writer lock poisoned".into()))?;
+        let mut block_hash = self
+            .block_hash_writer
+            .lock()
+            .map_err(|_| MetadataError::Other("block hash writer lock poisoned".into()))?;
+        let mut fuzzy = self
+            .fuzzy_writer
+            .lock()
+            .map_err(|_| MetadataError::Other("fuzzy writer lock poisoned".into()))?;
+        let mut archive_entries = self
+            .archive_entries_writer
+            .lock()
+            .map_err(|_| MetadataError::Other("archive entries writer lock poisoned".into()))?;
         files.flush()?;
         strings.flush()?;
+        ips.flush()?;
+        cards.flush()?;
+        crypto.flush()?;
+        secrets.flush()?;
         history.flush()?;
         cookies.flush()?;
         downloads.flush()?;
         run.flush()?;
         entropy.flush()?;
+        bad_sectors.flush()?;
+        yara.flush()?;
+        keywords.flush()?;
+        block_hash.flush()?;
+        fuzzy.flush()?;
+        archive_entries.flush()?;
         Ok(())
     }
 }
@@ -2,6 +2,7 @@ pub mod csv;
 pub mod jsonl;
 pub mod parquet;
 
+use std::collections::BTreeMap;
 use std::path::Path;
 
 use thiserror::Error;
@@ -19,6 +20,48 @@ pub struct RunSummary {
     pub files_carved: u64,
     pub string_spans: u64,
     pub artefacts_extracted: u64,
+    /// Bytes of constant-filled blocks kept away from the scanners.
+    pub bytes_skipped: u64,
+    pub skipped_ranges: Vec<SkippedRange>,
+    /// Header hits dropped for violating an `alignment`/`sector_size` rule,
+    /// by file type.
+    pub unaligned_hits: BTreeMap<String, u64>,
+    /// Carved files found in a `--known-good` / `--known-bad` hash set.
+    pub known_good_files: u64,
+    pub known_bad_files: u64,
+    /// Carved files whose `encryption` is `full` or `headers`.
+    pub encrypted_files: u64,
+}
+
+/// A run of blocks filled with a single byte value (blank or wiped space).
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+pub struct SkippedRange {
+    pub global_start: u64,
+    pub global_end: u64,
+    pub fill: u8,
+}
+
+/// Flat `type:count;...` form of per-type counters for tabular backends.
+pub(crate) fn format_type_counts(counts: &BTreeMap<String, u64>) -> String {
+    counts
+        .iter()
+        .map(|(file_type, count)| format!("{file_type}:{count}"))
+        .collect::<Vec<_>>()
+        .join(";")
+}
+
+/// Flat `start-end:0xNN;...` form of skipped ranges for tabular backends.
+pub(crate) fn format_skipped_ranges(ranges: &[SkippedRange]) -> String {
+    ranges
+        .iter()
+        .map(|range| {
+            format!(
+                "{}-{}:0x{:02x}",
+                range.global_start, range.global_end, range.fill
+            )
+        })
+        .collect::<Vec<_>>()
+        .join(";")
 }
 
 #[derive(Debug, Clone, serde::Serialize)]
@@ -30,6 +73,103 @@ pub struct EntropyRegion {
     pub window_size: u64,
 }
 
+/// A range of the evidence that could not be read and was zero-filled.
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct BadSectorRange {
+    pub run_id: String,
+    pub global_start: u64,
+    pub length: u64,
+}
+
+/// A YARA rule that matched an evidence chunk or a carved file.
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct YaraMatch {
+    pub run_id: String,
+    pub rule: String,
+    pub tags: Vec<String>,
+    /// Evidence offset of the first string match, or of the carved file
+    /// start for rules matched by condition alone.
+    pub global_offset: u64,
+    /// Identifier of the first matching string (`$a`), if any.
+    pub string_id: Option<String>,
+    /// Carved file the rule matched, relative to the run output directory.
+    pub carved_path: Option<String>,
+}
+
+/// A run of evidence blocks whose hashes match consecutive blocks of a
+/// `--hash-db` reference file.
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct BlockHashMatch {
+    pub run_id: String,
+    /// Reference file path, relative to the directory the database was
+    /// built from.
+    pub source_file: String,
+    pub global_start: u64,
+    pub length: u64,
+    /// Index of the first matching block within the reference file.
+    pub block_index: u64,
+    pub block_count: u64,
+}
+
+/// A carved file whose fuzzy hash is close to a `--fuzzy-match` reference.
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct FuzzyMatch {
+    pub run_id: String,
+    /// Carved file, as in the `path` of its `carved_files` record.
+    pub carved_path: String,
+    pub file_type: String,
+    pub global_start: u64,
+    /// `ssdeep` or `tlsh`.
+    pub algorithm: String,
+    /// ssdeep similarity (0-100, higher is closer) or TLSH distance (0 for
+    /// identical digests, lower is closer).
+    pub score: u32,
+    pub reference_name: String,
+    pub reference_digest: String,
+}
+
+/// One member named in the directory or headers of a carved archive.
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct ArchiveEntry {
+    pub run_id: String,
+    /// Carved archive, as in the `path` of its `carved_files` record.
+    pub carved_path: String,
+    pub file_type: String,
+    /// Position of the member in the listing.
+    pub index: u64,
+    /// Member path inside the archive.
+    pub name: String,
+    pub compressed_size: Option<u64>,
+    pub uncompressed_size: Option<u64>,
+    /// As stored in the archive: MS-DOS times (ZIP, RAR 4) are local
+    /// times of the writing system, the others UTC.
+    pub modified: Option<chrono::NaiveDateTime>,
+    pub crc32: Option<u32>,
+    pub encrypted: bool,
+    pub compression_method: Option<String>,
+}
+
+/// A `--keywords` term found in the evidence.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct KeywordHit {
+    pub run_id: String,
+    /// Term as written in the keyword file.
+    pub term: String,
+    /// `ascii`, `utf-8`, `utf-16le` or `utf-16be`.
+    pub encoding: String,
+    pub global_offset: u64,
+    pub length: u64,
+    /// Evidence offset of the first context byte.
+    pub context_start: u64,
+    /// Hit plus surrounding bytes, hex-encoded.
+    pub context_hex: String,
+    /// The same bytes decoded in the hit's encoding, with control
+    /// characters shown as `.`.
+    pub context_text: String,
+    /// `path` of the smallest carved file whose byte range holds the hit.
+    pub carved_path: Option<String>,
+}
+
 #[derive(Debug, Clone, Copy)]
 pub enum MetadataBackendKind {
     Jsonl,
@@ -81,6 +221,12 @@ pub enum MetadataError {
 ///     files_carved: 0,
 ///     string_spans: 0,
 ///     artefacts_extracted: 0,
+///     bytes_skipped: 0,
+///     skipped_ranges: Vec::new(),
+///     unaligned_hits: Default::default(),
+///     known_good_files: 0,
+///     known_bad_files: 0,
+///     encrypted_files: 0,
 /// };
 /// sink.record_run_summary(&summary).unwrap();
 /// sink.flush().unwrap();
@@ -93,6 +239,19 @@ pub trait MetadataSink: Send + Sync {
     fn record_download(&self, record: &BrowserDownloadRecord) -> Result<(), MetadataError>;
     fn record_run_summary(&self, summary: &RunSummary) -> Result<(), MetadataError>;
     fn record_entropy(&self, region: &EntropyRegion) -> Result<(), MetadataError>;
+    fn record_bad_sector(&self, range: &BadSectorRange) -> Result<(), MetadataError>;
+    fn record_yara_match(&self, m: &YaraMatch) -> Result<(), MetadataError>;
+    fn record_keyword_hit(&self, hit: &KeywordHit) -> Result<(), MetadataError>;
+    fn record_block_hash_match(&self, m: &BlockHashMatch) -> Result<(), MetadataError>;
+    fn record_fuzzy_match(&self, m: &FuzzyMatch) -> Result<(), MetadataError>;
+    fn record_archive_entry(&self, entry: &ArchiveEntry) -> Result<(), MetadataError>;
+    /// Fill in the `carved_path` of the keyword hits written so far by
+    /// rewriting their table, once every carved file is known. Called at
+    /// most once, after the last keyword hit.
+    fn attribute_keyword_hits(
+        &self,
+        carved_path: &dyn Fn(u64) -> Option<String>,
+    ) -> Result<(), MetadataError>;
     fn flush(&self) -> Result<(), MetadataError>;
 }
 
@@ -121,6 +280,30 @@ impl MetadataSink for DryRunSink {
     fn record_entropy(&self, _region: &EntropyRegion) -> Result<(), MetadataError> {
         Ok(())
     }
+    fn record_bad_sector(&self, _range: &BadSectorRange) -> Result<(), MetadataError> {
+        Ok(())
+    }
+    fn record_yara_match(&self, _m: &YaraMatch) -> Result<(), MetadataError> {
+        Ok(())
+    }
+    fn record_keyword_hit(&self, _hit: &KeywordHit) -> Result<(), MetadataError> {
+        Ok(())
+    }
+    fn record_block_hash_match(&self, _m: &BlockHashMatch) -> Result<(), MetadataError> {
+        Ok(())
+    }
+    fn record_fuzzy_match(&self, _m: &FuzzyMatch) -> Result<(), MetadataError> {
+        Ok(())
+    }
+    fn record_archive_entry(&self, _entry: &ArchiveEntry) -> Result<(), MetadataError> {
+        Ok(())
+    }
+    fn attribute_keyword_hits(
+        &self,
+        _carved_path: &dyn Fn(u64) -> Option<String>,
+    ) -> Result<(), MetadataError> {
+        Ok(())
+    }
     fn flush(&self) -> Result<(), MetadataError> {
         Ok(())
     }
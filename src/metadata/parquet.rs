@@ -6,14 +6,18 @@ use arrow_array::builder::{
     BinaryBuilder, BooleanBuilder, Int32Builder, Int64Builder, StringBuilder,
     TimestampMicrosecondBuilder,
 };
-use arrow_array::{ArrayRef, RecordBatch};
+use arrow_array::{Array, ArrayRef, Int64Array, RecordBatch};
 use arrow_schema::{DataType, Field, Schema, SchemaRef, TimeUnit};
 use parquet::arrow::ArrowWriter;
+use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
 use parquet::file::properties::WriterProperties;
 
-use crate::carve::CarvedFile;
+use crate::carve::{CarvedFile, join_fragments};
 use crate::config::Config;
-use crate::metadata::{MetadataError, MetadataSink, RunSummary};
+use crate::metadata::{
+    ArchiveEntry, BadSectorRange, BlockHashMatch, FuzzyMatch, KeywordHit, MetadataError,
+    MetadataSink, RunSummary, YaraMatch, format_skipped_ranges, format_type_counts,
+};
 use crate::parsers::browser::{BrowserCookieRecord, BrowserDownloadRecord, BrowserHistoryRecord};
 use crate::strings::artifacts::{ArtefactKind, StringArtefact};
 
@@ -39,10 +43,21 @@ enum ParquetCategory {
     ArtefactsUrls,
     ArtefactsEmails,
     ArtefactsPhones,
+    ArtefactsCustom,
+    ArtefactsIps,
+    ArtefactsCreditCards,
+    ArtefactsCryptoWallets,
+    ArtefactsSecrets,
     BrowserHistory,
     BrowserCookies,
     BrowserDownloads,
     EntropyRegions,
+    BadSectors,
+    YaraMatches,
+    KeywordHits,
+    BlockHashMatches,
+    FuzzyMatches,
+    ArchiveEntries,
     RunSummary,
 }
 
@@ -60,10 +75,21 @@ impl ParquetCategory {
             ParquetCategory::ArtefactsUrls => "artefacts_urls.parquet",
             ParquetCategory::ArtefactsEmails => "artefacts_emails.parquet",
             ParquetCategory::ArtefactsPhones => "artefacts_phones.parquet",
+            ParquetCategory::ArtefactsCustom => "artefacts_custom.parquet",
+            ParquetCategory::ArtefactsIps => "artefacts_ips.parquet",
+            ParquetCategory::ArtefactsCreditCards => "artefacts_credit_cards.parquet",
+            ParquetCategory::ArtefactsCryptoWallets => "artefacts_crypto_wallets.parquet",
+            ParquetCategory::ArtefactsSecrets => "artefacts_secrets.parquet",
             ParquetCategory::BrowserHistory => "browser_history.parquet",
             ParquetCategory::BrowserCookies => "browser_cookies.parquet",
             ParquetCategory::BrowserDownloads => "browser_downloads.parquet",
             ParquetCategory::EntropyRegions => "entropy_regions.parquet",
+            ParquetCategory::BadSectors => "bad_sectors.parquet",
+            ParquetCategory::YaraMatches => "yara_matches.parquet",
+            ParquetCategory::KeywordHits => "keyword_hits.parquet",
+            ParquetCategory::BlockHashMatches => "block_hash_matches.parquet",
+            ParquetCategory::FuzzyMatches => "fuzzy_matches.parquet",
+            ParquetCategory::ArchiveEntries => "archive_entries.parquet",
             ParquetCategory::RunSummary => "run_summary.parquet",
         }
     }
@@ -93,11 +119,33 @@ struct FileRow {
     size: i64,
     md5: Option<String>,
     sha256: Option<String>,
+    ssdeep: Option<String>,
+    tlsh: Option<String>,
     pattern_id: Option<String>,
     magic_bytes: Option<Vec<u8>>,
     validated: bool,
     truncated: bool,
     error: Option<String>,
+    validation_status: Option<String>,
+    validation_reason: Option<String>,
+    partition_index: Option<i64>,
+    partition_type: Option<String>,
+    partition_offset: Option<i64>,
+    allocation_status: Option<String>,
+    containing_fs_path: Option<String>,
+    containing_inode: Option<i64>,
+    offset_in_file: Option<i64>,
+    hash_match: Option<String>,
+    hash_set: Option<String>,
+    is_duplicate: bool,
+    duplicate_of: Option<String>,
+    fragments: Option<String>,
+    parent_path: Option<String>,
+    parent_id: Option<String>,
+    offset_in_parent: Option<i64>,
+    depth: i64,
+    encryption: String,
+    encryption_algorithm: Option<String>,
 }
 
 #[derive(Debug, Clone)]
@@ -114,6 +162,9 @@ struct UrlArtefactRow {
     source_kind: String,
     source_detail: String,
     certainty: f64,
+    containing_fs_path: Option<String>,
+    containing_inode: Option<i64>,
+    offset_in_file: Option<i64>,
 }
 
 #[derive(Debug, Clone)]
@@ -126,6 +177,9 @@ struct EmailArtefactRow {
     source_kind: String,
     source_detail: String,
     certainty: f64,
+    containing_fs_path: Option<String>,
+    containing_inode: Option<i64>,
+    offset_in_file: Option<i64>,
 }
 
 #[derive(Debug, Clone)]
@@ -138,6 +192,35 @@ struct PhoneArtefactRow {
     source_kind: String,
     source_detail: String,
     certainty: f64,
+    containing_fs_path: Option<String>,
+    containing_inode: Option<i64>,
+    offset_in_file: Option<i64>,
+}
+
+#[derive(Debug, Clone)]
+struct CustomArtefactRow {
+    global_start: i64,
+    global_end: i64,
+    name: String,
+    content: String,
+    encoding: String,
+    containing_fs_path: Option<String>,
+    containing_inode: Option<i64>,
+    offset_in_file: Option<i64>,
+}
+
+/// Row shared by the IP, payment card, crypto wallet and secret tables; only
+/// the column names of `value` and `subtype` differ between them.
+#[derive(Debug, Clone)]
+struct TypedArtefactRow {
+    global_start: i64,
+    global_end: i64,
+    value: String,
+    subtype: Option<String>,
+    encoding: String,
+    containing_fs_path: Option<String>,
+    containing_inode: Option<i64>,
+    offset_in_file: Option<i64>,
 }
 
 #[derive(Debug, Clone)]
@@ -151,6 +234,9 @@ struct BrowserHistoryRow {
     visit_source: Option<String>,
     row_id: Option<i64>,
     table_name: Option<String>,
+    containing_fs_path: Option<String>,
+    containing_inode: Option<i64>,
+    offset_in_file: Option<i64>,
 }
 
 #[derive(Debug, Clone)]
@@ -167,6 +253,9 @@ struct BrowserCookieRow {
     creation_utc: Option<i64>,
     is_secure: Option<bool>,
     is_http_only: Option<bool>,
+    containing_fs_path: Option<String>,
+    containing_inode: Option<i64>,
+    offset_in_file: Option<i64>,
 }
 
 #[derive(Debug, Clone)]
@@ -180,6 +269,9 @@ struct BrowserDownloadRow {
     end_time_utc: Option<i64>,
     total_bytes: Option<i64>,
     state: Option<String>,
+    containing_fs_path: Option<String>,
+    containing_inode: Option<i64>,
+    offset_in_file: Option<i64>,
 }
 
 #[derive(Debug, Clone)]
@@ -190,6 +282,67 @@ struct EntropyRegionRow {
     window_size: i64,
 }
 
+#[derive(Debug, Clone)]
+struct BadSectorRow {
+    global_start: i64,
+    length: i64,
+}
+
+#[derive(Debug, Clone)]
+struct YaraMatchRow {
+    rule: String,
+    tags: String,
+    global_offset: i64,
+    string_id: Option<String>,
+    carved_path: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+struct KeywordHitRow {
+    term: String,
+    encoding: String,
+    global_offset: i64,
+    length: i64,
+    context_start: i64,
+    context_hex: String,
+    context_text: String,
+    carved_path: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+struct BlockHashMatchRow {
+    source_file: String,
+    global_start: i64,
+    length: i64,
+    block_index: i64,
+    block_count: i64,
+}
+
+#[derive(Debug, Clone)]
+struct FuzzyMatchRow {
+    carved_path: String,
+    file_type: String,
+    global_start: i64,
+    algorithm: String,
+    score: i64,
+    reference_name: String,
+    reference_digest: String,
+}
+
+#[derive(Debug, Clone)]
+struct ArchiveEntryRow {
+    carved_path: String,
+    file_type: String,
+    index: i64,
+    name: String,
+    compressed_size: Option<i64>,
+    uncompressed_size: Option<i64>,
+    modified: Option<i64>,
+    crc32: Option<i64>,
+    encrypted: bool,
+    compression_method: Option<String>,
+}
+
 #[derive(Debug, Clone)]
 struct RunSummaryRow {
     bytes_scanned: i64,
@@ -198,6 +351,12 @@ struct RunSummaryRow {
     files_carved: i64,
     string_spans: i64,
     artefacts_extracted: i64,
+    bytes_skipped: i64,
+    skipped_ranges: String,
+    unaligned_hits: String,
+    known_good_files: i64,
+    known_bad_files: i64,
+    encrypted_files: i64,
 }
 
 enum CategoryBuffer {
@@ -205,10 +364,18 @@ enum CategoryBuffer {
     Urls(Vec<UrlArtefactRow>),
     Emails(Vec<EmailArtefactRow>),
     Phones(Vec<PhoneArtefactRow>),
+    Custom(Vec<CustomArtefactRow>),
+    Typed(Vec<TypedArtefactRow>),
     History(Vec<BrowserHistoryRow>),
     Cookies(Vec<BrowserCookieRow>),
     Downloads(Vec<BrowserDownloadRow>),
     Entropy(Vec<EntropyRegionRow>),
+    BadSectors(Vec<BadSectorRow>),
+    YaraMatches(Vec<YaraMatchRow>),
+    KeywordHits(Vec<KeywordHitRow>),
+    BlockHashMatches(Vec<BlockHashMatchRow>),
+    FuzzyMatches(Vec<FuzzyMatchRow>),
+    ArchiveEntries(Vec<ArchiveEntryRow>),
     Summary(Vec<RunSummaryRow>),
 }
 
@@ -239,10 +406,21 @@ impl CategoryWriter {
             ParquetCategory::ArtefactsUrls => CategoryBuffer::Urls(Vec::new()),
             ParquetCategory::ArtefactsEmails => CategoryBuffer::Emails(Vec::new()),
             ParquetCategory::ArtefactsPhones => CategoryBuffer::Phones(Vec::new()),
+            ParquetCategory::ArtefactsCustom => CategoryBuffer::Custom(Vec::new()),
+            ParquetCategory::ArtefactsIps
+            | ParquetCategory::ArtefactsCreditCards
+            | ParquetCategory::ArtefactsCryptoWallets
+            | ParquetCategory::ArtefactsSecrets => CategoryBuffer::Typed(Vec::new()),
             ParquetCategory::BrowserHistory => CategoryBuffer::History(Vec::new()),
             ParquetCategory::BrowserCookies => CategoryBuffer::Cookies(Vec::new()),
             ParquetCategory::BrowserDownloads => CategoryBuffer::Downloads(Vec::new()),
             ParquetCategory::EntropyRegions => CategoryBuffer::Entropy(Vec::new()),
+            ParquetCategory::BadSectors => CategoryBuffer::BadSectors(Vec::new()),
+            ParquetCategory::YaraMatches => CategoryBuffer::YaraMatches(Vec::new()),
+            ParquetCategory::KeywordHits => CategoryBuffer::KeywordHits(Vec::new()),
+            ParquetCategory::BlockHashMatches => CategoryBuffer::BlockHashMatches(Vec::new()),
+            ParquetCategory::FuzzyMatches => CategoryBuffer::FuzzyMatches(Vec::new()),
+            ParquetCategory::ArchiveEntries => CategoryBuffer::ArchiveEntries(Vec::new()),
             ParquetCategory::RunSummary => CategoryBuffer::Summary(Vec::new()),
             _ => CategoryBuffer::Files(Vec::new()),
         };
@@ -316,6 +494,36 @@ impl CategoryWriter {
         }
     }
 
+    fn append_custom(&mut self, row: CustomArtefactRow) -> Result<(), MetadataError> {
+        match &mut self.buffer {
+            CategoryBuffer::Custom(rows) => {
+                rows.push(row);
+                if rows.len() >= self.row_group_size {
+                    self.flush_buffer()?;
+                }
+                Ok(())
+            }
+            _ => Err(MetadataError::Other(
+                "custom artefact row on non-custom category".to_string(),
+            )),
+        }
+    }
+
+    fn append_typed(&mut self, row: TypedArtefactRow) -> Result<(), MetadataError> {
+        match &mut self.buffer {
+            CategoryBuffer::Typed(rows) => {
+                rows.push(row);
+                if rows.len() >= self.row_group_size {
+                    self.flush_buffer()?;
+                }
+                Ok(())
+            }
+            _ => Err(MetadataError::Other(
+                "typed artefact row on non-typed category".to_string(),
+            )),
+        }
+    }
+
     fn append_history(&mut self, row: BrowserHistoryRow) -> Result<(), MetadataError> {
         match &mut self.buffer {
             CategoryBuffer::History(rows) => {
@@ -376,6 +584,96 @@ impl CategoryWriter {
         }
     }
 
+    fn append_bad_sector(&mut self, row: BadSectorRow) -> Result<(), MetadataError> {
+        match &mut self.buffer {
+            CategoryBuffer::BadSectors(rows) => {
+                rows.push(row);
+                if rows.len() >= self.row_group_size {
+                    self.flush_buffer()?;
+                }
+                Ok(())
+            }
+            _ => Err(MetadataError::Other(
+                "bad sector row on non-bad-sector category".to_string(),
+            )),
+        }
+    }
+
+    fn append_yara_match(&mut self, row: YaraMatchRow) -> Result<(), MetadataError> {
+        match &mut self.buffer {
+            CategoryBuffer::YaraMatches(rows) => {
+                rows.push(row);
+                if rows.len() >= self.row_group_size {
+                    self.flush_buffer()?;
+                }
+                Ok(())
+            }
+            _ => Err(MetadataError::Other(
+                "yara match row on non-yara category".to_string(),
+            )),
+        }
+    }
+
+    fn append_keyword_hit(&mut self, row: KeywordHitRow) -> Result<(), MetadataError> {
+        match &mut self.buffer {
+            CategoryBuffer::KeywordHits(rows) => {
+                rows.push(row);
+                if rows.len() >= self.row_group_size {
+                    self.flush_buffer()?;
+                }
+                Ok(())
+            }
+            _ => Err(MetadataError::Other(
+                "keyword hit row on non-keyword category".to_string(),
+            )),
+        }
+    }
+
+    fn append_block_hash_match(&mut self, row: BlockHashMatchRow) -> Result<(), MetadataError> {
+        match &mut self.buffer {
+            CategoryBuffer::BlockHashMatches(rows) => {
+                rows.push(row);
+                if rows.len() >= self.row_group_size {
+                    self.flush_buffer()?;
+                }
+                Ok(())
+            }
+            _ => Err(MetadataError::Other(
+                "block hash row on non-block-hash category".to_string(),
+            )),
+        }
+    }
+
+    fn append_fuzzy_match(&mut self, row: FuzzyMatchRow) -> Result<(), MetadataError> {
+        match &mut self.buffer {
+            CategoryBuffer::FuzzyMatches(rows) => {
+                rows.push(row);
+                if rows.len() >= self.row_group_size {
+                    self.flush_buffer()?;
+                }
+                Ok(())
+            }
+            _ => Err(MetadataError::Other(
+                "fuzzy match row on non-fuzzy category".to_string(),
+            )),
+        }
+    }
+
+    fn append_archive_entry(&mut self, row: ArchiveEntryRow) -> Result<(), MetadataError> {
+        match &mut self.buffer {
+            CategoryBuffer::ArchiveEntries(rows) => {
+                rows.push(row);
+                if rows.len() >= self.row_group_size {
+                    self.flush_buffer()?;
+                }
+                Ok(())
+            }
+            _ => Err(MetadataError::Other(
+                "archive entry row on non-archive category".to_string(),
+            )),
+        }
+    }
+
     fn append_summary(&mut self, row: RunSummaryRow) -> Result<(), MetadataError> {
         match &mut self.buffer {
             CategoryBuffer::Summary(rows) => {
@@ -416,6 +714,16 @@ impl CategoryWriter {
                 rows.clear();
                 batch
             }
+            CategoryBuffer::Custom(rows) => {
+                let batch = build_custom_batch(&self.context, rows, &self.schema)?;
+                rows.clear();
+                batch
+            }
+            CategoryBuffer::Typed(rows) => {
+                let batch = build_typed_batch(&self.context, rows, &self.schema)?;
+                rows.clear();
+                batch
+            }
             CategoryBuffer::History(rows) => {
                 let batch = build_history_batch(&self.context, rows, &self.schema)?;
                 rows.clear();
@@ -436,6 +744,36 @@ impl CategoryWriter {
                 rows.clear();
                 batch
             }
+            CategoryBuffer::BadSectors(rows) => {
+                let batch = build_bad_sectors_batch(&self.context, rows, &self.schema)?;
+                rows.clear();
+                batch
+            }
+            CategoryBuffer::YaraMatches(rows) => {
+                let batch = build_yara_matches_batch(&self.context, rows, &self.schema)?;
+                rows.clear();
+                batch
+            }
+            CategoryBuffer::KeywordHits(rows) => {
+                let batch = build_keyword_hits_batch(&self.context, rows, &self.schema)?;
+                rows.clear();
+                batch
+            }
+            CategoryBuffer::BlockHashMatches(rows) => {
+                let batch = build_block_hash_matches_batch(&self.context, rows, &self.schema)?;
+                rows.clear();
+                batch
+            }
+            CategoryBuffer::FuzzyMatches(rows) => {
+                let batch = build_fuzzy_matches_batch(&self.context, rows, &self.schema)?;
+                rows.clear();
+                batch
+            }
+            CategoryBuffer::ArchiveEntries(rows) => {
+                let batch = build_archive_entries_batch(&self.context, rows, &self.schema)?;
+                rows.clear();
+                batch
+            }
             CategoryBuffer::Summary(rows) => {
                 let batch = build_summary_batch(&self.context, rows, &self.schema)?;
                 rows.clear();
@@ -466,10 +804,18 @@ impl CategoryWriter {
             CategoryBuffer::Urls(rows) => rows.len(),
             CategoryBuffer::Emails(rows) => rows.len(),
             CategoryBuffer::Phones(rows) => rows.len(),
+            CategoryBuffer::Custom(rows) => rows.len(),
+            CategoryBuffer::Typed(rows) => rows.len(),
             CategoryBuffer::History(rows) => rows.len(),
             CategoryBuffer::Cookies(rows) => rows.len(),
             CategoryBuffer::Downloads(rows) => rows.len(),
             CategoryBuffer::Entropy(rows) => rows.len(),
+            CategoryBuffer::BadSectors(rows) => rows.len(),
+            CategoryBuffer::YaraMatches(rows) => rows.len(),
+            CategoryBuffer::KeywordHits(rows) => rows.len(),
+            CategoryBuffer::BlockHashMatches(rows) => rows.len(),
+            CategoryBuffer::FuzzyMatches(rows) => rows.len(),
+            CategoryBuffer::ArchiveEntries(rows) => rows.len(),
             CategoryBuffer::Summary(rows) => rows.len(),
         }
     }
@@ -490,10 +836,21 @@ struct ParquetSinkInner {
     artefacts_urls: Option<CategoryWriter>,
     artefacts_emails: Option<CategoryWriter>,
     artefacts_phones: Option<CategoryWriter>,
+    artefacts_custom: Option<CategoryWriter>,
+    artefacts_ips: Option<CategoryWriter>,
+    artefacts_credit_cards: Option<CategoryWriter>,
+    artefacts_crypto_wallets: Option<CategoryWriter>,
+    artefacts_secrets: Option<CategoryWriter>,
     browser_history: Option<CategoryWriter>,
     browser_cookies: Option<CategoryWriter>,
     browser_downloads: Option<CategoryWriter>,
     entropy_regions: Option<CategoryWriter>,
+    bad_sectors: Option<CategoryWriter>,
+    yara_matches: Option<CategoryWriter>,
+    keyword_hits: Option<CategoryWriter>,
+    block_hash_matches: Option<CategoryWriter>,
+    fuzzy_matches: Option<CategoryWriter>,
+    archive_entries: Option<CategoryWriter>,
     run_summary: Option<CategoryWriter>,
 }
 
@@ -514,10 +871,21 @@ impl ParquetSinkInner {
             ParquetCategory::ArtefactsUrls => &mut self.artefacts_urls,
             ParquetCategory::ArtefactsEmails => &mut self.artefacts_emails,
             ParquetCategory::ArtefactsPhones => &mut self.artefacts_phones,
+            ParquetCategory::ArtefactsCustom => &mut self.artefacts_custom,
+            ParquetCategory::ArtefactsIps => &mut self.artefacts_ips,
+            ParquetCategory::ArtefactsCreditCards => &mut self.artefacts_credit_cards,
+            ParquetCategory::ArtefactsCryptoWallets => &mut self.artefacts_crypto_wallets,
+            ParquetCategory::ArtefactsSecrets => &mut self.artefacts_secrets,
             ParquetCategory::BrowserHistory => &mut self.browser_history,
             ParquetCategory::BrowserCookies => &mut self.browser_cookies,
             ParquetCategory::BrowserDownloads => &mut self.browser_downloads,
             ParquetCategory::EntropyRegions => &mut self.entropy_regions,
+            ParquetCategory::BadSectors => &mut self.bad_sectors,
+            ParquetCategory::YaraMatches => &mut self.yara_matches,
+            ParquetCategory::KeywordHits => &mut self.keyword_hits,
+            ParquetCategory::BlockHashMatches => &mut self.block_hash_matches,
+            ParquetCategory::FuzzyMatches => &mut self.fuzzy_matches,
+            ParquetCategory::ArchiveEntries => &mut self.archive_entries,
             ParquetCategory::RunSummary => &mut self.run_summary,
         };
 
@@ -571,6 +939,21 @@ impl ParquetSinkInner {
         if let Some(writer) = &mut self.artefacts_phones {
             writer.finish()?;
         }
+        if let Some(writer) = &mut self.artefacts_custom {
+            writer.finish()?;
+        }
+        if let Some(writer) = &mut self.artefacts_ips {
+            writer.finish()?;
+        }
+        if let Some(writer) = &mut self.artefacts_credit_cards {
+            writer.finish()?;
+        }
+        if let Some(writer) = &mut self.artefacts_crypto_wallets {
+            writer.finish()?;
+        }
+        if let Some(writer) = &mut self.artefacts_secrets {
+            writer.finish()?;
+        }
         if let Some(writer) = &mut self.browser_history {
             writer.finish()?;
         }
@@ -583,6 +966,24 @@ impl ParquetSinkInner {
         if let Some(writer) = &mut self.entropy_regions {
             writer.finish()?;
         }
+        if let Some(writer) = &mut self.bad_sectors {
+            writer.finish()?;
+        }
+        if let Some(writer) = &mut self.yara_matches {
+            writer.finish()?;
+        }
+        if let Some(writer) = &mut self.keyword_hits {
+            writer.finish()?;
+        }
+        if let Some(writer) = &mut self.block_hash_matches {
+            writer.finish()?;
+        }
+        if let Some(writer) = &mut self.fuzzy_matches {
+            writer.finish()?;
+        }
+        if let Some(writer) = &mut self.archive_entries {
+            writer.finish()?;
+        }
         if let Some(writer) = &mut self.run_summary {
             writer.finish()?;
         }
@@ -624,6 +1025,21 @@ impl ParquetSinkInner {
         if let Some(writer) = &mut self.artefacts_phones {
             writer.flush_buffer()?;
         }
+        if let Some(writer) = &mut self.artefacts_custom {
+            writer.flush_buffer()?;
+        }
+        if let Some(writer) = &mut self.artefacts_ips {
+            writer.flush_buffer()?;
+        }
+        if let Some(writer) = &mut self.artefacts_credit_cards {
+            writer.flush_buffer()?;
+        }
+        if let Some(writer) = &mut self.artefacts_crypto_wallets {
+            writer.flush_buffer()?;
+        }
+        if let Some(writer) = &mut self.artefacts_secrets {
+            writer.flush_buffer()?;
+        }
         if let Some(writer) = &mut self.browser_history {
             writer.flush_buffer()?;
         }
@@ -636,6 +1052,24 @@ impl ParquetSinkInner {
         if let Some(writer) = &mut self.entropy_regions {
             writer.flush_buffer()?;
         }
+        if let Some(writer) = &mut self.bad_sectors {
+            writer.flush_buffer()?;
+        }
+        if let Some(writer) = &mut self.yara_matches {
+            writer.flush_buffer()?;
+        }
+        if let Some(writer) = &mut self.keyword_hits {
+            writer.flush_buffer()?;
+        }
+        if let Some(writer) = &mut self.block_hash_matches {
+            writer.flush_buffer()?;
+        }
+        if let Some(writer) = &mut self.fuzzy_matches {
+            writer.flush_buffer()?;
+        }
+        if let Some(writer) = &mut self.archive_entries {
+            writer.flush_buffer()?;
+        }
         if let Some(writer) = &mut self.run_summary {
             writer.flush_buffer()?;
         }
@@ -683,10 +1117,21 @@ impl ParquetSink {
                 artefacts_urls: None,
                 artefacts_emails: None,
                 artefacts_phones: None,
+                artefacts_custom: None,
+                artefacts_ips: None,
+                artefacts_credit_cards: None,
+                artefacts_crypto_wallets: None,
+                artefacts_secrets: None,
                 browser_history: None,
                 browser_cookies: None,
                 browser_downloads: None,
                 entropy_regions: None,
+                bad_sectors: None,
+                yara_matches: None,
+                keyword_hits: None,
+                block_hash_matches: None,
+                fuzzy_matches: None,
+                archive_entries: None,
                 run_summary: None,
             }),
         })
@@ -711,11 +1156,33 @@ impl MetadataSink for ParquetSink {
             size: to_i64(file.size)?,
             md5: file.md5.clone(),
             sha256: file.sha256.clone(),
+            ssdeep: file.ssdeep.clone(),
+            tlsh: file.tlsh.clone(),
             pattern_id: file.pattern_id.clone(),
             magic_bytes: None,
             validated: file.validated,
             truncated: file.truncated,
             error: join_errors(&file.errors),
+            validation_status: file.validation_status.clone(),
+            validation_reason: file.validation_reason.clone(),
+            partition_index: file.partition_index.map(i64::from),
+            partition_type: file.partition_type.clone(),
+            partition_offset: file.partition_offset.map(to_i64).transpose()?,
+            allocation_status: file.allocation_status.clone(),
+            containing_fs_path: file.containing_fs_path.clone(),
+            containing_inode: file.containing_inode.map(to_i64).transpose()?,
+            offset_in_file: file.offset_in_file.map(to_i64).transpose()?,
+            hash_match: file.hash_match.clone(),
+            hash_set: file.hash_set.clone(),
+            is_duplicate: file.is_duplicate,
+            duplicate_of: file.duplicate_of.clone(),
+            fragments: (!file.fragments.is_empty()).then(|| join_fragments(&file.fragments)),
+            parent_path: file.parent_path.clone(),
+            parent_id: file.parent_id.clone(),
+            offset_in_parent: file.offset_in_parent.map(to_i64).transpose()?,
+            depth: i64::from(file.depth),
+            encryption: file.encryption.as_str().to_string(),
+            encryption_algorithm: file.encryption_algorithm.clone(),
         };
 
         let mut inner = self.lock_inner()?;
@@ -725,7 +1192,7 @@ impl MetadataSink for ParquetSink {
 
     fn record_string(&self, artefact: &StringArtefact) -> Result<(), MetadataError> {
         let mut inner = self.lock_inner()?;
-        match artefact.artefact_kind {
+        match &artefact.artefact_kind {
             ArtefactKind::Url => {
                 let row = map_url_artefact(artefact)?;
                 let writer = inner.get_or_create_writer(ParquetCategory::ArtefactsUrls)?;
@@ -741,6 +1208,31 @@ impl MetadataSink for ParquetSink {
                 let writer = inner.get_or_create_writer(ParquetCategory::ArtefactsPhones)?;
                 writer.append_phone(row)
             }
+            ArtefactKind::Custom(name) => {
+                let row = map_custom_artefact(artefact, name)?;
+                let writer = inner.get_or_create_writer(ParquetCategory::ArtefactsCustom)?;
+                writer.append_custom(row)
+            }
+            ArtefactKind::Ip => {
+                let row = map_typed_artefact(artefact)?;
+                let writer = inner.get_or_create_writer(ParquetCategory::ArtefactsIps)?;
+                writer.append_typed(row)
+            }
+            ArtefactKind::CreditCard => {
+                let row = map_typed_artefact(artefact)?;
+                let writer = inner.get_or_create_writer(ParquetCategory::ArtefactsCreditCards)?;
+                writer.append_typed(row)
+            }
+            ArtefactKind::CryptoWallet => {
+                let row = map_typed_artefact(artefact)?;
+                let writer = inner.get_or_create_writer(ParquetCategory::ArtefactsCryptoWallets)?;
+                writer.append_typed(row)
+            }
+            ArtefactKind::Secret => {
+                let row = map_typed_artefact(artefact)?;
+                let writer = inner.get_or_create_writer(ParquetCategory::ArtefactsSecrets)?;
+                writer.append_typed(row)
+            }
             ArtefactKind::GenericString => Ok(()),
         }
     }
@@ -756,6 +1248,9 @@ impl MetadataSink for ParquetSink {
             visit_source: record.visit_source.clone(),
             row_id: None,
             table_name: None,
+            containing_fs_path: record.containing_fs_path.clone(),
+            containing_inode: record.containing_inode.map(to_i64).transpose()?,
+            offset_in_file: record.offset_in_file.map(to_i64).transpose()?,
         };
 
         let mut inner = self.lock_inner()?;
@@ -777,6 +1272,9 @@ impl MetadataSink for ParquetSink {
             creation_utc: record.creation_utc.map(to_micros),
             is_secure: record.is_secure,
             is_http_only: record.is_http_only,
+            containing_fs_path: record.containing_fs_path.clone(),
+            containing_inode: record.containing_inode.map(to_i64).transpose()?,
+            offset_in_file: record.offset_in_file.map(to_i64).transpose()?,
         };
 
         let mut inner = self.lock_inner()?;
@@ -795,6 +1293,9 @@ impl MetadataSink for ParquetSink {
             end_time_utc: record.end_time.map(to_micros),
             total_bytes: record.total_bytes,
             state: record.state.clone(),
+            containing_fs_path: record.containing_fs_path.clone(),
+            containing_inode: record.containing_inode.map(to_i64).transpose()?,
+            offset_in_file: record.offset_in_file.map(to_i64).transpose()?,
         };
 
         let mut inner = self.lock_inner()?;
@@ -810,6 +1311,12 @@ impl MetadataSink for ParquetSink {
             files_carved: to_i64(summary.files_carved)?,
             string_spans: to_i64(summary.string_spans)?,
             artefacts_extracted: to_i64(summary.artefacts_extracted)?,
+            bytes_skipped: to_i64(summary.bytes_skipped)?,
+            skipped_ranges: format_skipped_ranges(&summary.skipped_ranges),
+            unaligned_hits: format_type_counts(&summary.unaligned_hits),
+            known_good_files: to_i64(summary.known_good_files)?,
+            known_bad_files: to_i64(summary.known_bad_files)?,
+            encrypted_files: to_i64(summary.encrypted_files)?,
         };
         let mut inner = self.lock_inner()?;
         let writer = inner.get_or_create_writer(ParquetCategory::RunSummary)?;
@@ -828,6 +1335,144 @@ impl MetadataSink for ParquetSink {
         writer.append_entropy(row)
     }
 
+    fn record_bad_sector(&self, range: &BadSectorRange) -> Result<(), MetadataError> {
+        let row = BadSectorRow {
+            global_start: to_i64(range.global_start)?,
+            length: to_i64(range.length)?,
+        };
+        let mut inner = self.lock_inner()?;
+        let writer = inner.get_or_create_writer(ParquetCategory::BadSectors)?;
+        writer.append_bad_sector(row)
+    }
+
+    fn record_yara_match(&self, m: &YaraMatch) -> Result<(), MetadataError> {
+        let row = YaraMatchRow {
+            rule: m.rule.clone(),
+            tags: m.tags.join(";"),
+            global_offset: to_i64(m.global_offset)?,
+            string_id: m.string_id.clone(),
+            carved_path: m.carved_path.clone(),
+        };
+        let mut inner = self.lock_inner()?;
+        let writer = inner.get_or_create_writer(ParquetCategory::YaraMatches)?;
+        writer.append_yara_match(row)
+    }
+
+    fn record_keyword_hit(&self, hit: &KeywordHit) -> Result<(), MetadataError> {
+        let row = KeywordHitRow {
+            term: hit.term.clone(),
+            encoding: hit.encoding.clone(),
+            global_offset: to_i64(hit.global_offset)?,
+            length: to_i64(hit.length)?,
+            context_start: to_i64(hit.context_start)?,
+            context_hex: hit.context_hex.clone(),
+            context_text: hit.context_text.clone(),
+            carved_path: hit.carved_path.clone(),
+        };
+        let mut inner = self.lock_inner()?;
+        let writer = inner.get_or_create_writer(ParquetCategory::KeywordHits)?;
+        writer.append_keyword_hit(row)
+    }
+
+    fn attribute_keyword_hits(
+        &self,
+        carved_path: &dyn Fn(u64) -> Option<String>,
+    ) -> Result<(), MetadataError> {
+        let mut inner = self.lock_inner()?;
+        let Some(writer) = &mut inner.keyword_hits else {
+            return Ok(());
+        };
+        // The table is closed here and rewritten with the paths filled in
+        writer.finish()?;
+        let path = inner
+            .parquet_dir
+            .join(ParquetCategory::KeywordHits.filename());
+        let tmp_path = path.with_extension("parquet.tmp");
+        let schema = schema_for_category(ParquetCategory::KeywordHits);
+        let path_col = schema
+            .index_of("carved_path")
+            .map_err(|err| MetadataError::Other(format!("parquet schema error: {err}")))?;
+        let reader = ParquetRecordBatchReaderBuilder::try_new(File::open(&path)?)
+            .and_then(|builder| builder.build())
+            .map_err(|err| MetadataError::Other(format!("parquet read error: {err}")))?;
+        let props = WriterProperties::builder()
+            .set_max_row_group_size(inner.row_group_size)
+            .build();
+        let mut out =
+            ArrowWriter::try_new(File::create(&tmp_path)?, schema.clone(), Some(props))
+                .map_err(|err| MetadataError::Other(format!("parquet writer error: {err}")))?;
+        for batch in reader {
+            let batch =
+                batch.map_err(|err| MetadataError::Other(format!("parquet read error: {err}")))?;
+            let offsets = batch
+                .column_by_name("global_offset")
+                .and_then(|column| column.as_any().downcast_ref::<Int64Array>())
+                .ok_or_else(|| {
+                    MetadataError::Other("keyword hits table has no global_offset".to_string())
+                })?;
+            let mut paths = StringBuilder::new();
+            for offset in offsets.values() {
+                paths.append_option(carved_path(*offset as u64));
+            }
+            let mut columns = batch.columns().to_vec();
+            columns[path_col] = Arc::new(paths.finish());
+            let batch = RecordBatch::try_new(schema.clone(), columns)
+                .map_err(|err| MetadataError::Other(format!("parquet batch error: {err}")))?;
+            out.write(&batch)
+                .map_err(|err| MetadataError::Other(format!("parquet write error: {err}")))?;
+        }
+        out.close()
+            .map_err(|err| MetadataError::Other(format!("parquet finish error: {err}")))?;
+        std::fs::rename(&tmp_path, &path)?;
+        Ok(())
+    }
+
+    fn record_block_hash_match(&self, m: &BlockHashMatch) -> Result<(), MetadataError> {
+        let row = BlockHashMatchRow {
+            source_file: m.source_file.clone(),
+            global_start: to_i64(m.global_start)?,
+            length: to_i64(m.length)?,
+            block_index: to_i64(m.block_index)?,
+            block_count: to_i64(m.block_count)?,
+        };
+        let mut inner = self.lock_inner()?;
+        let writer = inner.get_or_create_writer(ParquetCategory::BlockHashMatches)?;
+        writer.append_block_hash_match(row)
+    }
+
+    fn record_fuzzy_match(&self, m: &FuzzyMatch) -> Result<(), MetadataError> {
+        let row = FuzzyMatchRow {
+            carved_path: m.carved_path.clone(),
+            file_type: m.file_type.clone(),
+            global_start: to_i64(m.global_start)?,
+            algorithm: m.algorithm.clone(),
+            score: i64::from(m.score),
+            reference_name: m.reference_name.clone(),
+            reference_digest: m.reference_digest.clone(),
+        };
+        let mut inner = self.lock_inner()?;
+        let writer = inner.get_or_create_writer(ParquetCategory::FuzzyMatches)?;
+        writer.append_fuzzy_match(row)
+    }
+
+    fn record_archive_entry(&self, entry: &ArchiveEntry) -> Result<(), MetadataError> {
+        let row = ArchiveEntryRow {
+            carved_path: entry.carved_path.clone(),
+            file_type: entry.file_type.clone(),
+            index: to_i64(entry.index)?,
+            name: entry.name.clone(),
+            compressed_size: entry.compressed_size.map(to_i64).transpose()?,
+            uncompressed_size: entry.uncompressed_size.map(to_i64).transpose()?,
+            modified: entry.modified.map(to_micros),
+            crc32: entry.crc32.map(i64::from),
+            encrypted: entry.encrypted,
+            compression_method: entry.compression_method.clone(),
+        };
+        let mut inner = self.lock_inner()?;
+        let writer = inner.get_or_create_writer(ParquetCategory::ArchiveEntries)?;
+        writer.append_archive_entry(row)
+    }
+
     fn flush(&self) -> Result<(), MetadataError> {
         // Flush all buffers to ensure data is written to disk
         // This allows recovery of data if the process is interrupted
@@ -904,11 +1549,33 @@ fn schema_for_category(category: ParquetCategory) -> SchemaRef {
             Field::new("size", DataType::Int64, false),
             Field::new("md5", DataType::Utf8, true),
             Field::new("sha256", DataType::Utf8, true),
+            Field::new("ssdeep", DataType::Utf8, true),
+            Field::new("tlsh", DataType::Utf8, true),
             Field::new("pattern_id", DataType::Utf8, true),
             Field::new("magic_bytes", DataType::Binary, true),
             Field::new("validated", DataType::Boolean, false),
             Field::new("truncated", DataType::Boolean, false),
             Field::new("error", DataType::Utf8, true),
+            Field::new("validation_status", DataType::Utf8, true),
+            Field::new("validation_reason", DataType::Utf8, true),
+            Field::new("partition_index", DataType::Int64, true),
+            Field::new("partition_type", DataType::Utf8, true),
+            Field::new("partition_offset", DataType::Int64, true),
+            Field::new("allocation_status", DataType::Utf8, true),
+            Field::new("containing_fs_path", DataType::Utf8, true),
+            Field::new("containing_inode", DataType::Int64, true),
+            Field::new("offset_in_file", DataType::Int64, true),
+            Field::new("hash_match", DataType::Utf8, true),
+            Field::new("hash_set", DataType::Utf8, true),
+            Field::new("is_duplicate", DataType::Boolean, false),
+            Field::new("duplicate_of", DataType::Utf8, true),
+            Field::new("fragments", DataType::Utf8, true),
+            Field::new("parent_path", DataType::Utf8, true),
+            Field::new("parent_id", DataType::Utf8, true),
+            Field::new("offset_in_parent", DataType::Int64, true),
+            Field::new("depth", DataType::Int64, false),
+            Field::new("encryption", DataType::Utf8, false),
+            Field::new("encryption_algorithm", DataType::Utf8, true),
         ]));
     }
 
@@ -931,6 +1598,9 @@ fn schema_for_category(category: ParquetCategory) -> SchemaRef {
             Field::new("source_kind", DataType::Utf8, false),
             Field::new("source_detail", DataType::Utf8, false),
             Field::new("certainty", DataType::Float64, false),
+            Field::new("containing_fs_path", DataType::Utf8, true),
+            Field::new("containing_inode", DataType::Int64, true),
+            Field::new("offset_in_file", DataType::Int64, true),
         ])),
         ParquetCategory::ArtefactsEmails => Arc::new(Schema::new(vec![
             Field::new("run_id", DataType::Utf8, false),
@@ -946,6 +1616,9 @@ fn schema_for_category(category: ParquetCategory) -> SchemaRef {
             Field::new("source_kind", DataType::Utf8, false),
             Field::new("source_detail", DataType::Utf8, false),
             Field::new("certainty", DataType::Float64, false),
+            Field::new("containing_fs_path", DataType::Utf8, true),
+            Field::new("containing_inode", DataType::Int64, true),
+            Field::new("offset_in_file", DataType::Int64, true),
         ])),
         ParquetCategory::ArtefactsPhones => Arc::new(Schema::new(vec![
             Field::new("run_id", DataType::Utf8, false),
@@ -961,7 +1634,29 @@ fn schema_for_category(category: ParquetCategory) -> SchemaRef {
             Field::new("source_kind", DataType::Utf8, false),
             Field::new("source_detail", DataType::Utf8, false),
             Field::new("certainty", DataType::Float64, false),
+            Field::new("containing_fs_path", DataType::Utf8, true),
+            Field::new("containing_inode", DataType::Int64, true),
+            Field::new("offset_in_file", DataType::Int64, true),
+        ])),
+        ParquetCategory::ArtefactsCustom => Arc::new(Schema::new(vec![
+            Field::new("run_id", DataType::Utf8, false),
+            Field::new("tool_version", DataType::Utf8, false),
+            Field::new("config_hash", DataType::Utf8, false),
+            Field::new("evidence_path", DataType::Utf8, false),
+            Field::new("evidence_sha256", DataType::Utf8, false),
+            Field::new("global_start", DataType::Int64, false),
+            Field::new("global_end", DataType::Int64, false),
+            Field::new("name", DataType::Utf8, false),
+            Field::new("content", DataType::Utf8, false),
+            Field::new("encoding", DataType::Utf8, false),
+            Field::new("containing_fs_path", DataType::Utf8, true),
+            Field::new("containing_inode", DataType::Int64, true),
+            Field::new("offset_in_file", DataType::Int64, true),
         ])),
+        ParquetCategory::ArtefactsIps => typed_artefact_schema("ip", "ip_version"),
+        ParquetCategory::ArtefactsCreditCards => typed_artefact_schema("card_number", "brand"),
+        ParquetCategory::ArtefactsCryptoWallets => typed_artefact_schema("address", "currency"),
+        ParquetCategory::ArtefactsSecrets => typed_artefact_schema("secret", "secret_type"),
         ParquetCategory::BrowserHistory => Arc::new(Schema::new(vec![
             Field::new("run_id", DataType::Utf8, false),
             Field::new("tool_version", DataType::Utf8, false),
@@ -981,6 +1676,9 @@ fn schema_for_category(category: ParquetCategory) -> SchemaRef {
             Field::new("visit_source", DataType::Utf8, true),
             Field::new("row_id", DataType::Int64, true),
             Field::new("table_name", DataType::Utf8, true),
+            Field::new("containing_fs_path", DataType::Utf8, true),
+            Field::new("containing_inode", DataType::Int64, true),
+            Field::new("offset_in_file", DataType::Int64, true),
         ])),
         ParquetCategory::BrowserCookies => Arc::new(Schema::new(vec![
             Field::new("run_id", DataType::Utf8, false),
@@ -1012,6 +1710,9 @@ fn schema_for_category(category: ParquetCategory) -> SchemaRef {
             ),
             Field::new("is_secure", DataType::Boolean, true),
             Field::new("is_http_only", DataType::Boolean, true),
+            Field::new("containing_fs_path", DataType::Utf8, true),
+            Field::new("containing_inode", DataType::Int64, true),
+            Field::new("offset_in_file", DataType::Int64, true),
         ])),
         ParquetCategory::BrowserDownloads => Arc::new(Schema::new(vec![
             Field::new("run_id", DataType::Utf8, false),
@@ -1036,6 +1737,9 @@ fn schema_for_category(category: ParquetCategory) -> SchemaRef {
             ),
             Field::new("total_bytes", DataType::Int64, true),
             Field::new("state", DataType::Utf8, true),
+            Field::new("containing_fs_path", DataType::Utf8, true),
+            Field::new("containing_inode", DataType::Int64, true),
+            Field::new("offset_in_file", DataType::Int64, true),
         ])),
         ParquetCategory::EntropyRegions => Arc::new(Schema::new(vec![
             Field::new("run_id", DataType::Utf8, false),
@@ -1048,18 +1752,107 @@ fn schema_for_category(category: ParquetCategory) -> SchemaRef {
             Field::new("entropy", DataType::Float64, false),
             Field::new("window_size", DataType::Int64, false),
         ])),
-        ParquetCategory::RunSummary => Arc::new(Schema::new(vec![
+        ParquetCategory::BadSectors => Arc::new(Schema::new(vec![
             Field::new("run_id", DataType::Utf8, false),
             Field::new("tool_version", DataType::Utf8, false),
             Field::new("config_hash", DataType::Utf8, false),
             Field::new("evidence_path", DataType::Utf8, false),
             Field::new("evidence_sha256", DataType::Utf8, false),
-            Field::new("bytes_scanned", DataType::Int64, false),
-            Field::new("chunks_processed", DataType::Int64, false),
-            Field::new("hits_found", DataType::Int64, false),
+            Field::new("global_start", DataType::Int64, false),
+            Field::new("length", DataType::Int64, false),
+        ])),
+        ParquetCategory::YaraMatches => Arc::new(Schema::new(vec![
+            Field::new("run_id", DataType::Utf8, false),
+            Field::new("tool_version", DataType::Utf8, false),
+            Field::new("config_hash", DataType::Utf8, false),
+            Field::new("evidence_path", DataType::Utf8, false),
+            Field::new("evidence_sha256", DataType::Utf8, false),
+            Field::new("rule", DataType::Utf8, false),
+            Field::new("tags", DataType::Utf8, false),
+            Field::new("global_offset", DataType::Int64, false),
+            Field::new("string_id", DataType::Utf8, true),
+            Field::new("carved_path", DataType::Utf8, true),
+        ])),
+        ParquetCategory::KeywordHits => Arc::new(Schema::new(vec![
+            Field::new("run_id", DataType::Utf8, false),
+            Field::new("tool_version", DataType::Utf8, false),
+            Field::new("config_hash", DataType::Utf8, false),
+            Field::new("evidence_path", DataType::Utf8, false),
+            Field::new("evidence_sha256", DataType::Utf8, false),
+            Field::new("term", DataType::Utf8, false),
+            Field::new("encoding", DataType::Utf8, false),
+            Field::new("global_offset", DataType::Int64, false),
+            Field::new("length", DataType::Int64, false),
+            Field::new("context_start", DataType::Int64, false),
+            Field::new("context_hex", DataType::Utf8, false),
+            Field::new("context_text", DataType::Utf8, false),
+            Field::new("carved_path", DataType::Utf8, true),
+        ])),
+        ParquetCategory::BlockHashMatches => Arc::new(Schema::new(vec![
+            Field::new("run_id", DataType::Utf8, false),
+            Field::new("tool_version", DataType::Utf8, false),
+            Field::new("config_hash", DataType::Utf8, false),
+            Field::new("evidence_path", DataType::Utf8, false),
+            Field::new("evidence_sha256", DataType::Utf8, false),
+            Field::new("source_file", DataType::Utf8, false),
+            Field::new("global_start", DataType::Int64, false),
+            Field::new("length", DataType::Int64, false),
+            Field::new("block_index", DataType::Int64, false),
+            Field::new("block_count", DataType::Int64, false),
+        ])),
+        ParquetCategory::FuzzyMatches => Arc::new(Schema::new(vec![
+            Field::new("run_id", DataType::Utf8, false),
+            Field::new("tool_version", DataType::Utf8, false),
+            Field::new("config_hash", DataType::Utf8, false),
+            Field::new("evidence_path", DataType::Utf8, false),
+            Field::new("evidence_sha256", DataType::Utf8, false),
+            Field::new("carved_path", DataType::Utf8, false),
+            Field::new("file_type", DataType::Utf8, false),
+            Field::new("global_start", DataType::Int64, false),
+            Field::new("algorithm", DataType::Utf8, false),
+            Field::new("score", DataType::Int64, false),
+            Field::new("reference_name", DataType::Utf8, false),
+            Field::new("reference_digest", DataType::Utf8, false),
+        ])),
+        ParquetCategory::ArchiveEntries => Arc::new(Schema::new(vec![
+            Field::new("run_id", DataType::Utf8, false),
+            Field::new("tool_version", DataType::Utf8, false),
+            Field::new("config_hash", DataType::Utf8, false),
+            Field::new("evidence_path", DataType::Utf8, false),
+            Field::new("evidence_sha256", DataType::Utf8, false),
+            Field::new("carved_path", DataType::Utf8, false),
+            Field::new("file_type", DataType::Utf8, false),
+            Field::new("index", DataType::Int64, false),
+            Field::new("name", DataType::Utf8, false),
+            Field::new("compressed_size", DataType::Int64, true),
+            Field::new("uncompressed_size", DataType::Int64, true),
+            Field::new(
+                "modified",
+                DataType::Timestamp(TimeUnit::Microsecond, None),
+                true,
+            ),
+            Field::new("crc32", DataType::Int64, true),
+            Field::new("encrypted", DataType::Boolean, false),
+            Field::new("compression_method", DataType::Utf8, true),
+        ])),
+        ParquetCategory::RunSummary => Arc::new(Schema::new(vec![
+            Field::new("run_id", DataType::Utf8, false),
+            Field::new("tool_version", DataType::Utf8, false),
+            Field::new("config_hash", DataType::Utf8, false),
+            Field::new("evidence_path", DataType::Utf8, false),
+            Field::new("evidence_sha256", DataType::Utf8, false),
+            Field::new("bytes_scanned", DataType::Int64, false),
+            Field::new("chunks_processed", DataType::Int64, false),
+            Field::new("hits_found", DataType::Int64, false),
             Field::new("files_carved", DataType::Int64, false),
             Field::new("string_spans", DataType::Int64, false),
             Field::new("artefacts_extracted", DataType::Int64, false),
+            Field::new("bytes_skipped", DataType::Int64, false),
+            Field::new("skipped_ranges", DataType::Utf8, false),
+            Field::new("unaligned_hits", DataType::Utf8, false),
+            Field::new("known_good_files", DataType::Int64, false),
+            Field::new("known_bad_files", DataType::Int64, false),
+            Field::new("encrypted_files", DataType::Int64, false),
         ])),
         _ => Arc::new(Schema::empty()),
     }
@@ -1083,11 +1876,33 @@ fn build_files_batch(
     let mut size = Int64Builder::new();
     let mut md5 = StringBuilder::new();
     let mut sha256 = StringBuilder::new();
+    let mut ssdeep = StringBuilder::new();
+    let mut tlsh = StringBuilder::new();
     let mut pattern_id = StringBuilder::new();
     let mut magic_bytes = BinaryBuilder::new();
     let mut validated = BooleanBuilder::new();
     let mut truncated = BooleanBuilder::new();
     let mut error = StringBuilder::new();
+    let mut validation_status = StringBuilder::new();
+    let mut validation_reason = StringBuilder::new();
+    let mut partition_index = Int64Builder::new();
+    let mut partition_type = StringBuilder::new();
+    let mut partition_offset = Int64Builder::new();
+    let mut allocation_status = StringBuilder::new();
+    let mut containing_fs_path = StringBuilder::new();
+    let mut containing_inode = Int64Builder::new();
+    let mut offset_in_file = Int64Builder::new();
+    let mut hash_match = StringBuilder::new();
+    let mut hash_set = StringBuilder::new();
+    let mut is_duplicate = BooleanBuilder::new();
+    let mut duplicate_of = StringBuilder::new();
+    let mut fragments = StringBuilder::new();
+    let mut parent_path = StringBuilder::new();
+    let mut parent_id = StringBuilder::new();
+    let mut offset_in_parent = Int64Builder::new();
+    let mut depth = Int64Builder::new();
+    let mut encryption = StringBuilder::new();
+    let mut encryption_algorithm = StringBuilder::new();
 
     for row in rows {
         run_id.append_value(&ctx.run_id);
@@ -1103,11 +1918,33 @@ fn build_files_batch(
         size.append_value(row.size);
         md5.append_option(row.md5.as_deref());
         sha256.append_option(row.sha256.as_deref());
+        ssdeep.append_option(row.ssdeep.as_deref());
+        tlsh.append_option(row.tlsh.as_deref());
         pattern_id.append_option(row.pattern_id.as_deref());
         magic_bytes.append_option(row.magic_bytes.as_deref());
         validated.append_value(row.validated);
         truncated.append_value(row.truncated);
         error.append_option(row.error.as_deref());
+        validation_status.append_option(row.validation_status.as_deref());
+        validation_reason.append_option(row.validation_reason.as_deref());
+        partition_index.append_option(row.partition_index);
+        partition_type.append_option(row.partition_type.as_deref());
+        partition_offset.append_option(row.partition_offset);
+        allocation_status.append_option(row.allocation_status.as_deref());
+        containing_fs_path.append_option(row.containing_fs_path.as_deref());
+        containing_inode.append_option(row.containing_inode);
+        offset_in_file.append_option(row.offset_in_file);
+        hash_match.append_option(row.hash_match.as_deref());
+        hash_set.append_option(row.hash_set.as_deref());
+        is_duplicate.append_value(row.is_duplicate);
+        duplicate_of.append_option(row.duplicate_of.as_deref());
+        fragments.append_option(row.fragments.as_deref());
+        parent_path.append_option(row.parent_path.as_deref());
+        parent_id.append_option(row.parent_id.as_deref());
+        offset_in_parent.append_option(row.offset_in_parent);
+        depth.append_value(row.depth);
+        encryption.append_value(&row.encryption);
+        encryption_algorithm.append_option(row.encryption_algorithm.as_deref());
     }
 
     let arrays: Vec<ArrayRef> = vec![
@@ -1124,11 +1961,33 @@ fn build_files_batch(
         Arc::new(size.finish()),
         Arc::new(md5.finish()),
         Arc::new(sha256.finish()),
+        Arc::new(ssdeep.finish()),
+        Arc::new(tlsh.finish()),
         Arc::new(pattern_id.finish()),
         Arc::new(magic_bytes.finish()),
         Arc::new(validated.finish()),
         Arc::new(truncated.finish()),
         Arc::new(error.finish()),
+        Arc::new(validation_status.finish()),
+        Arc::new(validation_reason.finish()),
+        Arc::new(partition_index.finish()),
+        Arc::new(partition_type.finish()),
+        Arc::new(partition_offset.finish()),
+        Arc::new(allocation_status.finish()),
+        Arc::new(containing_fs_path.finish()),
+        Arc::new(containing_inode.finish()),
+        Arc::new(offset_in_file.finish()),
+        Arc::new(hash_match.finish()),
+        Arc::new(hash_set.finish()),
+        Arc::new(is_duplicate.finish()),
+        Arc::new(duplicate_of.finish()),
+        Arc::new(fragments.finish()),
+        Arc::new(parent_path.finish()),
+        Arc::new(parent_id.finish()),
+        Arc::new(offset_in_parent.finish()),
+        Arc::new(depth.finish()),
+        Arc::new(encryption.finish()),
+        Arc::new(encryption_algorithm.finish()),
     ];
 
     RecordBatch::try_new(Arc::clone(schema), arrays)
@@ -1157,6 +2016,9 @@ fn build_urls_batch(
     let mut source_kind = StringBuilder::new();
     let mut source_detail = StringBuilder::new();
     let mut certainty = arrow_array::builder::Float64Builder::new();
+    let mut containing_fs_path = StringBuilder::new();
+    let mut containing_inode = Int64Builder::new();
+    let mut offset_in_file = Int64Builder::new();
 
     for row in rows {
         run_id.append_value(&ctx.run_id);
@@ -1176,6 +2038,9 @@ fn build_urls_batch(
         source_kind.append_value(&row.source_kind);
         source_detail.append_value(&row.source_detail);
         certainty.append_value(row.certainty);
+        containing_fs_path.append_option(row.containing_fs_path.as_deref());
+        containing_inode.append_option(row.containing_inode);
+        offset_in_file.append_option(row.offset_in_file);
     }
 
     let arrays: Vec<ArrayRef> = vec![
@@ -1196,6 +2061,9 @@ fn build_urls_batch(
         Arc::new(source_kind.finish()),
         Arc::new(source_detail.finish()),
         Arc::new(certainty.finish()),
+        Arc::new(containing_fs_path.finish()),
+        Arc::new(containing_inode.finish()),
+        Arc::new(offset_in_file.finish()),
     ];
 
     RecordBatch::try_new(Arc::clone(schema), arrays)
@@ -1220,6 +2088,9 @@ fn build_emails_batch(
     let mut source_kind = StringBuilder::new();
     let mut source_detail = StringBuilder::new();
     let mut certainty = arrow_array::builder::Float64Builder::new();
+    let mut containing_fs_path = StringBuilder::new();
+    let mut containing_inode = Int64Builder::new();
+    let mut offset_in_file = Int64Builder::new();
 
     for row in rows {
         run_id.append_value(&ctx.run_id);
@@ -1235,6 +2106,9 @@ fn build_emails_batch(
         source_kind.append_value(&row.source_kind);
         source_detail.append_value(&row.source_detail);
         certainty.append_value(row.certainty);
+        containing_fs_path.append_option(row.containing_fs_path.as_deref());
+        containing_inode.append_option(row.containing_inode);
+        offset_in_file.append_option(row.offset_in_file);
     }
 
     let arrays: Vec<ArrayRef> = vec![
@@ -1251,6 +2125,9 @@ fn build_emails_batch(
         Arc::new(source_kind.finish()),
         Arc::new(source_detail.finish()),
         Arc::new(certainty.finish()),
+        Arc::new(containing_fs_path.finish()),
+        Arc::new(containing_inode.finish()),
+        Arc::new(offset_in_file.finish()),
     ];
 
     RecordBatch::try_new(Arc::clone(schema), arrays)
@@ -1275,6 +2152,9 @@ fn build_phones_batch(
     let mut source_kind = StringBuilder::new();
     let mut source_detail = StringBuilder::new();
     let mut certainty = arrow_array::builder::Float64Builder::new();
+    let mut containing_fs_path = StringBuilder::new();
+    let mut containing_inode = Int64Builder::new();
+    let mut offset_in_file = Int64Builder::new();
 
     for row in rows {
         run_id.append_value(&ctx.run_id);
@@ -1290,6 +2170,9 @@ fn build_phones_batch(
         source_kind.append_value(&row.source_kind);
         source_detail.append_value(&row.source_detail);
         certainty.append_value(row.certainty);
+        containing_fs_path.append_option(row.containing_fs_path.as_deref());
+        containing_inode.append_option(row.containing_inode);
+        offset_in_file.append_option(row.offset_in_file);
     }
 
     let arrays: Vec<ArrayRef> = vec![
@@ -1306,6 +2189,137 @@ fn build_phones_batch(
         Arc::new(source_kind.finish()),
         Arc::new(source_detail.finish()),
         Arc::new(certainty.finish()),
+        Arc::new(containing_fs_path.finish()),
+        Arc::new(containing_inode.finish()),
+        Arc::new(offset_in_file.finish()),
+    ];
+
+    RecordBatch::try_new(Arc::clone(schema), arrays)
+        .map_err(|err| MetadataError::Other(format!("parquet batch error: {err}")))
+}
+
+fn build_custom_batch(
+    ctx: &ParquetContext,
+    rows: &[CustomArtefactRow],
+    schema: &SchemaRef,
+) -> Result<RecordBatch, MetadataError> {
+    let mut run_id = StringBuilder::new();
+    let mut tool_version = StringBuilder::new();
+    let mut config_hash = StringBuilder::new();
+    let mut evidence_path = StringBuilder::new();
+    let mut evidence_sha256 = StringBuilder::new();
+    let mut global_start = Int64Builder::new();
+    let mut global_end = Int64Builder::new();
+    let mut name = StringBuilder::new();
+    let mut content = StringBuilder::new();
+    let mut encoding = StringBuilder::new();
+    let mut containing_fs_path = StringBuilder::new();
+    let mut containing_inode = Int64Builder::new();
+    let mut offset_in_file = Int64Builder::new();
+
+    for row in rows {
+        run_id.append_value(&ctx.run_id);
+        tool_version.append_value(&ctx.tool_version);
+        config_hash.append_value(&ctx.config_hash);
+        evidence_path.append_value(&ctx.evidence_path);
+        evidence_sha256.append_value(&ctx.evidence_sha256);
+        global_start.append_value(row.global_start);
+        global_end.append_value(row.global_end);
+        name.append_value(&row.name);
+        content.append_value(&row.content);
+        encoding.append_value(&row.encoding);
+        containing_fs_path.append_option(row.containing_fs_path.as_deref());
+        containing_inode.append_option(row.containing_inode);
+        offset_in_file.append_option(row.offset_in_file);
+    }
+
+    let arrays: Vec<ArrayRef> = vec![
+        Arc::new(run_id.finish()),
+        Arc::new(tool_version.finish()),
+        Arc::new(config_hash.finish()),
+        Arc::new(evidence_path.finish()),
+        Arc::new(evidence_sha256.finish()),
+        Arc::new(global_start.finish()),
+        Arc::new(global_end.finish()),
+        Arc::new(name.finish()),
+        Arc::new(content.finish()),
+        Arc::new(encoding.finish()),
+        Arc::new(containing_fs_path.finish()),
+        Arc::new(containing_inode.finish()),
+        Arc::new(offset_in_file.finish()),
+    ];
+
+    RecordBatch::try_new(Arc::clone(schema), arrays)
+        .map_err(|err| MetadataError::Other(format!("parquet batch error: {err}")))
+}
+
+fn typed_artefact_schema(value_column: &str, subtype_column: &str) -> SchemaRef {
+    Arc::new(Schema::new(vec![
+        Field::new("run_id", DataType::Utf8, false),
+        Field::new("tool_version", DataType::Utf8, false),
+        Field::new("config_hash", DataType::Utf8, false),
+        Field::new("evidence_path", DataType::Utf8, false),
+        Field::new("evidence_sha256", DataType::Utf8, false),
+        Field::new("global_start", DataType::Int64, false),
+        Field::new("global_end", DataType::Int64, false),
+        Field::new(value_column, DataType::Utf8, false),
+        Field::new(subtype_column, DataType::Utf8, true),
+        Field::new("encoding", DataType::Utf8, false),
+        Field::new("containing_fs_path", DataType::Utf8, true),
+        Field::new("containing_inode", DataType::Int64, true),
+        Field::new("offset_in_file", DataType::Int64, true),
+    ]))
+}
+
+fn build_typed_batch(
+    ctx: &ParquetContext,
+    rows: &[TypedArtefactRow],
+    schema: &SchemaRef,
+) -> Result<RecordBatch, MetadataError> {
+    let mut run_id = StringBuilder::new();
+    let mut tool_version = StringBuilder::new();
+    let mut config_hash = StringBuilder::new();
+    let mut evidence_path = StringBuilder::new();
+    let mut evidence_sha256 = StringBuilder::new();
+    let mut global_start = Int64Builder::new();
+    let mut global_end = Int64Builder::new();
+    let mut value = StringBuilder::new();
+    let mut subtype = StringBuilder::new();
+    let mut encoding = StringBuilder::new();
+    let mut containing_fs_path = StringBuilder::new();
+    let mut containing_inode = Int64Builder::new();
+    let mut offset_in_file = Int64Builder::new();
+
+    for row in rows {
+        run_id.append_value(&ctx.run_id);
+        tool_version.append_value(&ctx.tool_version);
+        config_hash.append_value(&ctx.config_hash);
+        evidence_path.append_value(&ctx.evidence_path);
+        evidence_sha256.append_value(&ctx.evidence_sha256);
+        global_start.append_value(row.global_start);
+        global_end.append_value(row.global_end);
+        value.append_value(&row.value);
+        subtype.append_option(row.subtype.as_deref());
+        encoding.append_value(&row.encoding);
+        containing_fs_path.append_option(row.containing_fs_path.as_deref());
+        containing_inode.append_option(row.containing_inode);
+        offset_in_file.append_option(row.offset_in_file);
+    }
+
+    let arrays: Vec<ArrayRef> = vec![
+        Arc::new(run_id.finish()),
+        Arc::new(tool_version.finish()),
+        Arc::new(config_hash.finish()),
+        Arc::new(evidence_path.finish()),
+        Arc::new(evidence_sha256.finish()),
+        Arc::new(global_start.finish()),
+        Arc::new(global_end.finish()),
+        Arc::new(value.finish()),
+        Arc::new(subtype.finish()),
+        Arc::new(encoding.finish()),
+        Arc::new(containing_fs_path.finish()),
+        Arc::new(containing_inode.finish()),
+        Arc::new(offset_in_file.finish()),
     ];
 
     RecordBatch::try_new(Arc::clone(schema), arrays)
@@ -1331,6 +2345,9 @@ fn build_history_batch(
     let mut visit_source = StringBuilder::new();
     let mut row_id = Int64Builder::new();
     let mut table_name = StringBuilder::new();
+    let mut containing_fs_path = StringBuilder::new();
+    let mut containing_inode = Int64Builder::new();
+    let mut offset_in_file = Int64Builder::new();
 
     for row in rows {
         run_id.append_value(&ctx.run_id);
@@ -1347,6 +2364,9 @@ fn build_history_batch(
         visit_source.append_option(row.visit_source.as_deref());
         row_id.append_option(row.row_id);
         table_name.append_option(row.table_name.as_deref());
+        containing_fs_path.append_option(row.containing_fs_path.as_deref());
+        containing_inode.append_option(row.containing_inode);
+        offset_in_file.append_option(row.offset_in_file);
     }
 
     let arrays: Vec<ArrayRef> = vec![
@@ -1364,6 +2384,9 @@ fn build_history_batch(
         Arc::new(visit_source.finish()),
         Arc::new(row_id.finish()),
         Arc::new(table_name.finish()),
+        Arc::new(containing_fs_path.finish()),
+        Arc::new(containing_inode.finish()),
+        Arc::new(offset_in_file.finish()),
     ];
 
     RecordBatch::try_new(Arc::clone(schema), arrays)
@@ -1392,6 +2415,9 @@ fn build_cookies_batch(
     let mut creation = TimestampMicrosecondBuilder::new();
     let mut is_secure = BooleanBuilder::new();
     let mut is_http_only = BooleanBuilder::new();
+    let mut containing_fs_path = StringBuilder::new();
+    let mut containing_inode = Int64Builder::new();
+    let mut offset_in_file = Int64Builder::new();
 
     for row in rows {
         run_id.append_value(&ctx.run_id);
@@ -1411,6 +2437,9 @@ fn build_cookies_batch(
         creation.append_option(row.creation_utc);
         is_secure.append_option(row.is_secure);
         is_http_only.append_option(row.is_http_only);
+        containing_fs_path.append_option(row.containing_fs_path.as_deref());
+        containing_inode.append_option(row.containing_inode);
+        offset_in_file.append_option(row.offset_in_file);
     }
 
     let arrays: Vec<ArrayRef> = vec![
@@ -1431,6 +2460,9 @@ fn build_cookies_batch(
         Arc::new(creation.finish()),
         Arc::new(is_secure.finish()),
         Arc::new(is_http_only.finish()),
+        Arc::new(containing_fs_path.finish()),
+        Arc::new(containing_inode.finish()),
+        Arc::new(offset_in_file.finish()),
     ];
 
     RecordBatch::try_new(Arc::clone(schema), arrays)
@@ -1456,6 +2488,9 @@ fn build_downloads_batch(
     let mut end_time = TimestampMicrosecondBuilder::new();
     let mut total_bytes = Int64Builder::new();
     let mut state = StringBuilder::new();
+    let mut containing_fs_path = StringBuilder::new();
+    let mut containing_inode = Int64Builder::new();
+    let mut offset_in_file = Int64Builder::new();
 
     for row in rows {
         run_id.append_value(&ctx.run_id);
@@ -1472,6 +2507,9 @@ fn build_downloads_batch(
         end_time.append_option(row.end_time_utc);
         total_bytes.append_option(row.total_bytes);
         state.append_option(row.state.as_deref());
+        containing_fs_path.append_option(row.containing_fs_path.as_deref());
+        containing_inode.append_option(row.containing_inode);
+        offset_in_file.append_option(row.offset_in_file);
     }
 
     let arrays: Vec<ArrayRef> = vec![
@@ -1489,6 +2527,9 @@ fn build_downloads_batch(
         Arc::new(end_time.finish()),
         Arc::new(total_bytes.finish()),
         Arc::new(state.finish()),
+        Arc::new(containing_fs_path.finish()),
+        Arc::new(containing_inode.finish()),
+        Arc::new(offset_in_file.finish()),
     ];
 
     RecordBatch::try_new(Arc::clone(schema), arrays)
@@ -1538,6 +2579,190 @@ fn build_entropy_batch(
         .map_err(|err| MetadataError::Other(format!("parquet batch error: {err}")))
 }
 
+fn build_bad_sectors_batch(
+    ctx: &ParquetContext,
+    rows: &[BadSectorRow],
+    schema: &SchemaRef,
+) -> Result<RecordBatch, MetadataError> {
+    let mut run_id = StringBuilder::new();
+    let mut tool_version = StringBuilder::new();
+    let mut config_hash = StringBuilder::new();
+    let mut evidence_path = StringBuilder::new();
+    let mut evidence_sha256 = StringBuilder::new();
+    let mut global_start = Int64Builder::new();
+    let mut length = Int64Builder::new();
+
+    for row in rows {
+        run_id.append_value(&ctx.run_id);
+        tool_version.append_value(&ctx.tool_version);
+        config_hash.append_value(&ctx.config_hash);
+        evidence_path.append_value(&ctx.evidence_path);
+        evidence_sha256.append_value(&ctx.evidence_sha256);
+        global_start.append_value(row.global_start);
+        length.append_value(row.length);
+    }
+
+    let arrays: Vec<ArrayRef> = vec![
+        Arc::new(run_id.finish()),
+        Arc::new(tool_version.finish()),
+        Arc::new(config_hash.finish()),
+        Arc::new(evidence_path.finish()),
+        Arc::new(evidence_sha256.finish()),
+        Arc::new(global_start.finish()),
+        Arc::new(length.finish()),
+    ];
+
+    RecordBatch::try_new(Arc::clone(schema), arrays)
+        .map_err(|err| MetadataError::Other(format!("parquet batch error: {err}")))
+}
+
+fn build_yara_matches_batch(
+    ctx: &ParquetContext,
+    rows: &[YaraMatchRow],
+    schema: &SchemaRef,
+) -> Result<RecordBatch, MetadataError> {
+    let mut run_id = StringBuilder::new();
+    let mut tool_version = StringBuilder::new();
+    let mut config_hash = StringBuilder::new();
+    let mut evidence_path = StringBuilder::new();
+    let mut evidence_sha256 = StringBuilder::new();
+    let mut rule = StringBuilder::new();
+    let mut tags = StringBuilder::new();
+    let mut global_offset = Int64Builder::new();
+    let mut string_id = StringBuilder::new();
+    let mut carved_path = StringBuilder::new();
+
+    for row in rows {
+        run_id.append_value(&ctx.run_id);
+        tool_version.append_value(&ctx.tool_version);
+        config_hash.append_value(&ctx.config_hash);
+        evidence_path.append_value(&ctx.evidence_path);
+        evidence_sha256.append_value(&ctx.evidence_sha256);
+        rule.append_value(&row.rule);
+        tags.append_value(&row.tags);
+        global_offset.append_value(row.global_offset);
+        string_id.append_option(row.string_id.as_deref());
+        carved_path.append_option(row.carved_path.as_deref());
+    }
+
+    let arrays: Vec<ArrayRef> = vec![
+        Arc::new(run_id.finish()),
+        Arc::new(tool_version.finish()),
+        Arc::new(config_hash.finish()),
+        Arc::new(evidence_path.finish()),
+        Arc::new(evidence_sha256.finish()),
+        Arc::new(rule.finish()),
+        Arc::new(tags.finish()),
+        Arc::new(global_offset.finish()),
+        Arc::new(string_id.finish()),
+        Arc::new(carved_path.finish()),
+    ];
+
+    RecordBatch::try_new(Arc::clone(schema), arrays)
+        .map_err(|err| MetadataError::Other(format!("parquet batch error: {err}")))
+}
+
+fn build_keyword_hits_batch(
+    ctx: &ParquetContext,
+    rows: &[KeywordHitRow],
+    schema: &SchemaRef,
+) -> Result<RecordBatch, MetadataError> {
+    let mut run_id = StringBuilder::new();
+    let mut tool_version = StringBuilder::new();
+    let mut config_hash = StringBuilder::new();
+    let mut evidence_path = StringBuilder::new();
+    let mut evidence_sha256 = StringBuilder::new();
+    let mut term = StringBuilder::new();
+    let mut encoding = StringBuilder::new();
+    let mut global_offset = Int64Builder::new();
+    let mut length = Int64Builder::new();
+    let mut context_start = Int64Builder::new();
+    let mut context_hex = StringBuilder::new();
+    let mut context_text = StringBuilder::new();
+    let mut carved_path = StringBuilder::new();
+
+    for row in rows {
+        run_id.append_value(&ctx.run_id);
+        tool_version.append_value(&ctx.tool_version);
+        config_hash.append_value(&ctx.config_hash);
+        evidence_path.append_value(&ctx.evidence_path);
+        evidence_sha256.append_value(&ctx.evidence_sha256);
+        term.append_value(&row.term);
+        encoding.append_value(&row.encoding);
+        global_offset.append_value(row.global_offset);
+        length.append_value(row.length);
+        context_start.append_value(row.context_start);
+        context_hex.append_value(&row.context_hex);
+        context_text.append_value(&row.context_text);
+        carved_path.append_option(row.carved_path.as_deref());
+    }
+
+    let arrays: Vec<ArrayRef> = vec![
+        Arc::new(run_id.finish()),
+        Arc::new(tool_version.finish()),
+        Arc::new(config_hash.finish()),
+        Arc::new(evidence_path.finish()),
+        Arc::new(evidence_sha256.finish()),
+        Arc::new(term.finish()),
+        Arc::new(encoding.finish()),
+        Arc::new(global_offset.finish()),
+        Arc::new(length.finish()),
+        Arc::new(context_start.finish()),
+        Arc::new(context_hex.finish()),
+        Arc::new(context_text.finish()),
+        Arc::new(carved_path.finish()),
+    ];
+
+    RecordBatch::try_new(Arc::clone(schema), arrays)
+        .map_err(|err| MetadataError::Other(format!("parquet batch error: {err}")))
+}
+
+fn build_block_hash_matches_batch(
+    ctx: &ParquetContext,
+    rows: &[BlockHashMatchRow],
+    schema: &SchemaRef,
+) -> Result<RecordBatch, MetadataError> {
+    let mut run_id = StringBuilder::new();
+    let mut tool_version = StringBuilder::new();
+    let mut config_hash = StringBuilder::new();
+    let mut evidence_path = StringBuilder::new();
+    let mut evidence_sha256 = StringBuilder::new();
+    let mut source_file = StringBuilder::new();
+    let mut global_start = Int64Builder::new();
+    let mut length = Int64Builder::new();
+    let mut block_index = Int64Builder::new();
+    let mut block_count = Int64Builder::new();
+
+    for row in rows {
+        run_id.append_value(&ctx.run_id);
+        tool_version.append_value(&ctx.tool_version);
+        config_hash.append_value(&ctx.config_hash);
+        evidence_path.append_value(&ctx.evidence_path);
+        evidence_sha256.append_value(&ctx.evidence_sha256);
+        source_file.append_value(&row.source_file);
+        global_start.append_value(row.global_start);
+        length.append_value(row.length);
+        block_index.append_value(row.block_index);
+        block_count.append_value(row.block_count);
+    }
+
+    let arrays: Vec<ArrayRef> = vec![
+        Arc::new(run_id.finish()),
+        Arc::new(tool_version.finish()),
+        Arc::new(config_hash.finish()),
+        Arc::new(evidence_path.finish()),
+        Arc::new(evidence_sha256.finish()),
+        Arc::new(source_file.finish()),
+        Arc::new(global_start.finish()),
+        Arc::new(length.finish()),
+        Arc::new(block_index.finish()),
+        Arc::new(block_count.finish()),
+    ];
+
+    RecordBatch::try_new(Arc::clone(schema), arrays)
+        .map_err(|err| MetadataError::Other(format!("parquet batch error: {err}")))
+}
+
 fn build_summary_batch(
     ctx: &ParquetContext,
     rows: &[RunSummaryRow],
@@ -1554,6 +2779,12 @@ fn build_summary_batch(
     let mut files_carved = Int64Builder::new();
     let mut string_spans = Int64Builder::new();
     let mut artefacts_extracted = Int64Builder::new();
+    let mut bytes_skipped = Int64Builder::new();
+    let mut skipped_ranges = StringBuilder::new();
+    let mut unaligned_hits = StringBuilder::new();
+    let mut known_good_files = Int64Builder::new();
+    let mut known_bad_files = Int64Builder::new();
+    let mut encrypted_files = Int64Builder::new();
 
     for row in rows {
         run_id.append_value(&ctx.run_id);
@@ -1567,6 +2798,12 @@ fn build_summary_batch(
         files_carved.append_value(row.files_carved);
         string_spans.append_value(row.string_spans);
         artefacts_extracted.append_value(row.artefacts_extracted);
+        bytes_skipped.append_value(row.bytes_skipped);
+        skipped_ranges.append_value(&row.skipped_ranges);
+        unaligned_hits.append_value(&row.unaligned_hits);
+        known_good_files.append_value(row.known_good_files);
+        known_bad_files.append_value(row.known_bad_files);
+        encrypted_files.append_value(row.encrypted_files);
     }
 
     let arrays: Vec<ArrayRef> = vec![
@@ -1581,6 +2818,12 @@ fn build_summary_batch(
         Arc::new(files_carved.finish()),
         Arc::new(string_spans.finish()),
         Arc::new(artefacts_extracted.finish()),
+        Arc::new(bytes_skipped.finish()),
+        Arc::new(skipped_ranges.finish()),
+        Arc::new(unaligned_hits.finish()),
+        Arc::new(known_good_files.finish()),
+        Arc::new(known_bad_files.finish()),
+        Arc::new(encrypted_files.finish()),
     ];
 
     RecordBatch::try_new(Arc::clone(schema), arrays)
@@ -1602,6 +2845,9 @@ fn map_url_artefact(artefact: &StringArtefact) -> Result<UrlArtefactRow, Metadat
         source_kind: "string_span".to_string(),
         source_detail: "strings_artefacts".to_string(),
         certainty: 1.0,
+        containing_fs_path: artefact.containing_fs_path.clone(),
+        containing_inode: artefact.containing_inode.map(to_i64).transpose()?,
+        offset_in_file: artefact.offset_in_file.map(to_i64).transpose()?,
     })
 }
 
@@ -1616,6 +2862,9 @@ fn map_email_artefact(artefact: &StringArtefact) -> Result<EmailArtefactRow, Met
         source_kind: "string_span".to_string(),
         source_detail: "strings_artefacts".to_string(),
         certainty: 1.0,
+        containing_fs_path: artefact.containing_fs_path.clone(),
+        containing_inode: artefact.containing_inode.map(to_i64).transpose()?,
+        offset_in_file: artefact.offset_in_file.map(to_i64).transpose()?,
     })
 }
 
@@ -1629,6 +2878,38 @@ fn map_phone_artefact(artefact: &StringArtefact) -> Result<PhoneArtefactRow, Met
         source_kind: "string_span".to_string(),
         source_detail: "strings_artefacts".to_string(),
         certainty: 1.0,
+        containing_fs_path: artefact.containing_fs_path.clone(),
+        containing_inode: artefact.containing_inode.map(to_i64).transpose()?,
+        offset_in_file: artefact.offset_in_file.map(to_i64).transpose()?,
+    })
+}
+
+fn map_custom_artefact(
+    artefact: &StringArtefact,
+    name: &str,
+) -> Result<CustomArtefactRow, MetadataError> {
+    Ok(CustomArtefactRow {
+        global_start: to_i64(artefact.global_start)?,
+        global_end: to_i64(artefact.global_end)?,
+        name: name.to_string(),
+        content: artefact.content.clone(),
+        encoding: artefact.encoding.clone(),
+        containing_fs_path: artefact.containing_fs_path.clone(),
+        containing_inode: artefact.containing_inode.map(to_i64).transpose()?,
+        offset_in_file: artefact.offset_in_file.map(to_i64).transpose()?,
+    })
+}
+
+fn map_typed_artefact(artefact: &StringArtefact) -> Result<TypedArtefactRow, MetadataError> {
+    Ok(TypedArtefactRow {
+        global_start: to_i64(artefact.global_start)?,
+        global_end: to_i64(artefact.global_end)?,
+        value: artefact.content.clone(),
+        subtype: artefact.subtype.clone(),
+        encoding: artefact.encoding.clone(),
+        containing_fs_path: artefact.containing_fs_path.clone(),
+        containing_inode: artefact.containing_inode.map(to_i64).transpose()?,
+        offset_in_file: artefact.offset_in_file.map(to_i64).transpose()?,
     })
 }
 
@@ -1716,3 +2997,116 @@ fn to_micros(value: chrono::NaiveDateTime) -> i64 {
     let micros = i64::from(utc.timestamp_subsec_micros());
     seconds.saturating_mul(1_000_000).saturating_add(micros)
 }
+
+fn build_fuzzy_matches_batch(
+    ctx: &ParquetContext,
+    rows: &[FuzzyMatchRow],
+    schema: &SchemaRef,
+) -> Result<RecordBatch, MetadataError> {
+    let mut run_id = StringBuilder::new();
+    let mut tool_version = StringBuilder::new();
+    let mut config_hash = StringBuilder::new();
+    let mut evidence_path = StringBuilder::new();
+    let mut evidence_sha256 = StringBuilder::new();
+    let mut carved_path = StringBuilder::new();
+    let mut file_type = StringBuilder::new();
+    let mut global_start = Int64Builder::new();
+    let mut algorithm = StringBuilder::new();
+    let mut score = Int64Builder::new();
+    let mut reference_name = StringBuilder::new();
+    let mut reference_digest = StringBuilder::new();
+
+    for row in rows {
+        run_id.append_value(&ctx.run_id);
+        tool_version.append_value(&ctx.tool_version);
+        config_hash.append_value(&ctx.config_hash);
+        evidence_path.append_value(&ctx.evidence_path);
+        evidence_sha256.append_value(&ctx.evidence_sha256);
+        carved_path.append_value(&row.carved_path);
+        file_type.append_value(&row.file_type);
+        global_start.append_value(row.global_start);
+        algorithm.append_value(&row.algorithm);
+        score.append_value(row.score);
+        reference_name.append_value(&row.reference_name);
+        reference_digest.append_value(&row.reference_digest);
+    }
+
+    let arrays: Vec<ArrayRef> = vec![
+        Arc::new(run_id.finish()),
+        Arc::new(tool_version.finish()),
+        Arc::new(config_hash.finish()),
+        Arc::new(evidence_path.finish()),
+        Arc::new(evidence_sha256.finish()),
+        Arc::new(carved_path.finish()),
+        Arc::new(file_type.finish()),
+        Arc::new(global_start.finish()),
+        Arc::new(algorithm.finish()),
+        Arc::new(score.finish()),
+        Arc::new(reference_name.finish()),
+        Arc::new(reference_digest.finish()),
+    ];
+
+    RecordBatch::try_new(Arc::clone(schema), arrays)
+        .map_err(|err| MetadataError::Other(format!("parquet batch error: {err}")))
+}
+
+fn build_archive_entries_batch(
+    ctx: &ParquetContext,
+    rows: &[ArchiveEntryRow],
+    schema: &SchemaRef,
+) -> Result<RecordBatch, MetadataError> {
+    let mut run_id = StringBuilder::new();
+    let mut tool_version = StringBuilder::new();
+    let mut config_hash = StringBuilder::new();
+    let mut evidence_path = StringBuilder::new();
+    let mut evidence_sha256 = StringBuilder::new();
+    let mut carved_path = StringBuilder::new();
+    let mut file_type = StringBuilder::new();
+    let mut index = Int64Builder::new();
+    let mut name = StringBuilder::new();
+    let mut compressed_size = Int64Builder::new();
+    let mut uncompressed_size = Int64Builder::new();
+    let mut modified = TimestampMicrosecondBuilder::new();
+    let mut crc32 = Int64Builder::new();
+    let mut encrypted = BooleanBuilder::new();
+    let mut compression_method = StringBuilder::new();
+
+    for row in rows {
+        run_id.append_value(&ctx.run_id);
+        tool_version.append_value(&ctx.tool_version);
+        config_hash.append_value(&ctx.config_hash);
+        evidence_path.append_value(&ctx.evidence_path);
+        evidence_sha256.append_value(&ctx.evidence_sha256);
+        carved_path.append_value(&row.carved_path);
+        file_type.append_value(&row.file_type);
+        index.append_value(row.index);
+        name.append_value(&row.name);
+        compressed_size.append_option(row.compressed_size);
+        uncompressed_size.append_option(row.uncompressed_size);
+        modified.append_option(row.modified);
+        crc32.append_option(row.crc32);
+        encrypted.append_value(row.encrypted);
+        compression_method.append_option(row.compression_method.as_deref());
+    }
+
+    let arrays: Vec<ArrayRef> = vec![
+        Arc::new(run_id.finish()),
+        Arc::new(tool_version.finish()),
+        Arc::new(config_hash.finish()),
+        Arc::new(evidence_path.finish()),
+        Arc::new(evidence_sha256.finish()),
+        Arc::new(carved_path.finish()),
+        Arc::new(file_type.finish()),
+        Arc::new(index.finish()),
+        Arc::new(name.finish()),
+        Arc::new(compressed_size.finish()),
+        Arc::new(uncompressed_size.finish()),
+        Arc::new(modified.finish()),
+        Arc::new(crc32.finish()),
+        Arc::new(encrypted.finish()),
+        Arc::new(compression_method.finish()),
+    ];
+
+    RecordBatch::try_new(Arc::clone(schema), arrays)
+        .map_err(|err| MetadataError::Other(format!("parquet batch error: {err}")))
+}
@@ -10,6 +10,11 @@ pub struct BrowserHistoryRecord {
     pub visit_time: Option<chrono::NaiveDateTime>,
     pub visit_source: Option<String>,
     pub source_file: std::path::PathBuf,
+    /// Live file holding the carved database, and the database's offset
+    /// within it; `None` unless `--attribute-fs-paths` is on.
+    pub containing_fs_path: Option<String>,
+    pub containing_inode: Option<u64>,
+    pub offset_in_file: Option<u64>,
 }
 
 #[derive(Debug, Clone, Serialize)]
@@ -27,6 +32,10 @@ pub struct BrowserCookieRecord {
     pub is_secure: Option<bool>,
     pub is_http_only: Option<bool>,
     pub source_file: std::path::PathBuf,
+    /// See [`BrowserHistoryRecord::containing_fs_path`].
+    pub containing_fs_path: Option<String>,
+    pub containing_inode: Option<u64>,
+    pub offset_in_file: Option<u64>,
 }
 
 #[derive(Debug, Clone, Serialize)]
@@ -41,4 +50,8 @@ pub struct BrowserDownloadRecord {
     pub total_bytes: Option<i64>,
     pub state: Option<String>,
     pub source_file: std::path::PathBuf,
+    /// See [`BrowserHistoryRecord::containing_fs_path`].
+    pub containing_fs_path: Option<String>,
+    pub containing_inode: Option<u64>,
+    pub offset_in_file: Option<u64>,
 }
@@ -161,6 +161,9 @@ fn extract_chrome_history(
             visit_time,
             visit_source: None,
             source_file: source_relative.into(),
+            containing_fs_path: None,
+            containing_inode: None,
+            offset_in_file: None,
         });
     }
 
@@ -205,6 +208,9 @@ fn extract_chrome_visits(
             visit_time,
             visit_source,
             source_file: source_relative.into(),
+            containing_fs_path: None,
+            containing_inode: None,
+            offset_in_file: None,
         });
     }
 
@@ -237,6 +243,9 @@ fn extract_firefox_history(
             visit_time,
             visit_source: None,
             source_file: source_relative.into(),
+            containing_fs_path: None,
+            containing_inode: None,
+            offset_in_file: None,
         });
     }
 
@@ -274,6 +283,9 @@ fn extract_firefox_visits(
             visit_time,
             visit_source,
             source_file: source_relative.into(),
+            containing_fs_path: None,
+            containing_inode: None,
+            offset_in_file: None,
         });
     }
 
@@ -367,6 +379,9 @@ fn extract_chrome_cookies(
             is_secure: is_secure.map(|v| v != 0),
             is_http_only: is_http_only.map(|v| v != 0),
             source_file: source_relative.into(),
+            containing_fs_path: None,
+            containing_inode: None,
+            offset_in_file: None,
         });
     }
 
@@ -425,6 +440,9 @@ fn extract_firefox_cookies(
             is_secure: is_secure.map(|v| v != 0),
             is_http_only: is_http_only.map(|v| v != 0),
             source_file: source_relative.into(),
+            containing_fs_path: None,
+            containing_inode: None,
+            offset_in_file: None,
         });
     }
 
@@ -528,6 +546,9 @@ fn extract_chrome_downloads(
             total_bytes,
             state: state.map(|v| v.to_string()),
             source_file: source_relative.into(),
+            containing_fs_path: None,
+            containing_inode: None,
+            offset_in_file: None,
         });
     }
 
@@ -615,6 +636,9 @@ fn extract_firefox_downloads(
             total_bytes,
             state: state.map(|v| v.to_string()),
             source_file: source_relative.into(),
+            containing_fs_path: None,
+            containing_inode: None,
+            offset_in_file: None,
         });
     }
 
@@ -118,6 +118,9 @@ pub fn extract_history_from_pages(
                             visit_time,
                             visit_source: Some("page_scan".to_string()),
                             source_file: source_relative.into(),
+                            containing_fs_path: None,
+                            containing_inode: None,
+                            offset_in_file: None,
                         });
                 }
             }
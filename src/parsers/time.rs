@@ -19,3 +19,29 @@ pub fn unix_micro_to_datetime(microseconds: i64) -> Option<chrono::NaiveDateTime
     let nsecs = ((microseconds % 1_000_000).abs() as u32) * 1000;
     chrono::DateTime::<chrono::Utc>::from_timestamp(secs, nsecs).map(|dt| dt.naive_utc())
 }
+
+/// Windows FILETIME: 100 ns ticks since 1601-01-01.
+pub fn filetime_to_datetime(ticks: u64) -> Option<chrono::NaiveDateTime> {
+    webkit_timestamp_to_datetime(i64::try_from(ticks / 10).ok()?)
+}
+
+pub fn unix_seconds_to_datetime(seconds: i64) -> Option<chrono::NaiveDateTime> {
+    if seconds <= 0 {
+        return None;
+    }
+    chrono::DateTime::<chrono::Utc>::from_timestamp(seconds, 0).map(|dt| dt.naive_utc())
+}
+
+/// MS-DOS date and time fields, local time of the writing system.
+pub fn dos_to_datetime(date: u16, time: u16) -> Option<chrono::NaiveDateTime> {
+    let day = chrono::NaiveDate::from_ymd_opt(
+        1980 + i32::from(date >> 9),
+        u32::from((date >> 5) & 0x0F),
+        u32::from(date & 0x1F),
+    )?;
+    day.and_hms_opt(
+        u32::from(time >> 11),
+        u32::from((time >> 5) & 0x3F),
+        u32::from(time & 0x1F) * 2,
+    )
+}
@@ -0,0 +1,456 @@
+//! # Partition Module
+//!
+//! MBR (primary, extended and logical) and GPT partition table parsing, used
+//! to attribute carved files to partitions and to restrict scanning to a
+//! chosen set of partitions.
+
+use crate::evidence::{EvidenceError, EvidenceSource};
+
+pub const SECTOR_SIZE: u64 = 512;
+const MBR_SIGNATURE: [u8; 2] = [0x55, 0xAA];
+const MBR_TABLE_OFFSET: usize = 446;
+const MBR_PROTECTIVE: u8 = 0xEE;
+const MBR_EXTENDED: [u8; 3] = [0x05, 0x0F, 0x85];
+const GPT_SIGNATURE: &[u8; 8] = b"EFI PART";
+/// GPT headers are probed at LBA 1 for these logical sector sizes.
+const GPT_SECTOR_SIZES: [u64; 2] = [512, 4096];
+const MAX_GPT_ENTRIES: u32 = 1024;
+const MAX_GPT_ENTRY_SIZE: u32 = 4096;
+/// Guards against EBR chains that loop back on themselves.
+const MAX_LOGICAL_PARTITIONS: u32 = 128;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PartitionScheme {
+    Mbr,
+    Gpt,
+}
+
+impl PartitionScheme {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            PartitionScheme::Mbr => "mbr",
+            PartitionScheme::Gpt => "gpt",
+        }
+    }
+}
+
+/// A partition located on the evidence.
+///
+/// `index` follows the usual numbering: GPT entry slot + 1, MBR primaries
+/// 1-4 and logical partitions from 5. Extended containers are not listed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Partition {
+    pub index: u32,
+    pub scheme: PartitionScheme,
+    /// GPT type GUID, or the MBR type byte as `0x07`.
+    pub type_id: String,
+    pub start: u64,
+    pub len: u64,
+    pub name: Option<String>,
+}
+
+impl Partition {
+    pub fn end(&self) -> u64 {
+        self.start.saturating_add(self.len)
+    }
+
+    pub fn contains(&self, offset: u64) -> bool {
+        offset >= self.start && offset < self.end()
+    }
+}
+
+/// Parse the partition table at the start of `evidence`. Returns an empty
+/// list when there is no MBR signature (e.g. a bare filesystem image).
+pub fn read_partitions(evidence: &dyn EvidenceSource) -> Result<Vec<Partition>, EvidenceError> {
+    let mut mbr = [0u8; SECTOR_SIZE as usize];
+    if read_full(evidence, 0, &mut mbr)? < mbr.len() || mbr[510..512] != MBR_SIGNATURE {
+        return Ok(Vec::new());
+    }
+
+    let entries = mbr_entries(&mbr);
+    let gpt = if entries.iter().any(|entry| entry.kind == MBR_PROTECTIVE) {
+        read_gpt(evidence)?
+    } else {
+        None
+    };
+    if let Some(partitions) = gpt {
+        return Ok(clamp_to_evidence(partitions, evidence.len()));
+    }
+
+    let mut partitions = Vec::new();
+    for (slot, entry) in entries.iter().enumerate() {
+        if entry.kind == 0 || entry.sectors == 0 || entry.kind == MBR_PROTECTIVE {
+            continue;
+        }
+        if MBR_EXTENDED.contains(&entry.kind) {
+            read_logical(evidence, entry.lba as u64, &mut partitions)?;
+            continue;
+        }
+        partitions.push(Partition {
+            index: slot as u32 + 1,
+            scheme: PartitionScheme::Mbr,
+            type_id: format!("0x{:02x}", entry.kind),
+            start: entry.lba as u64 * SECTOR_SIZE,
+            len: entry.sectors as u64 * SECTOR_SIZE,
+            name: None,
+        });
+    }
+    partitions.sort_by_key(|partition| partition.index);
+    Ok(clamp_to_evidence(partitions, evidence.len()))
+}
+
+/// Partition containing `offset`, if any.
+pub fn partition_for_offset(partitions: &[Partition], offset: u64) -> Option<&Partition> {
+    partitions
+        .iter()
+        .find(|partition| partition.contains(offset))
+}
+
+#[derive(Debug, Clone, Copy)]
+struct MbrEntry {
+    kind: u8,
+    lba: u32,
+    sectors: u32,
+}
+
+fn mbr_entries(sector: &[u8]) -> [MbrEntry; 4] {
+    std::array::from_fn(|slot| {
+        let at = MBR_TABLE_OFFSET + slot * 16;
+        MbrEntry {
+            kind: sector[at + 4],
+            lba: le_u32(&sector[at + 8..at + 12]),
+            sectors: le_u32(&sector[at + 12..at + 16]),
+        }
+    })
+}
+
+/// Walk the EBR chain of an extended partition. Logical partition starts are
+/// relative to their EBR; next-EBR links are relative to the extended start.
+fn read_logical(
+    evidence: &dyn EvidenceSource,
+    extended_lba: u64,
+    partitions: &mut Vec<Partition>,
+) -> Result<(), EvidenceError> {
+    let mut ebr_lba = extended_lba;
+    let mut index = 5u32;
+    while index < 5 + MAX_LOGICAL_PARTITIONS {
+        let mut ebr = [0u8; SECTOR_SIZE as usize];
+        let read = read_full(evidence, ebr_lba * SECTOR_SIZE, &mut ebr)?;
+        if read < ebr.len() || ebr[510..512] != MBR_SIGNATURE {
+            break;
+        }
+        let [logical, next, _, _] = mbr_entries(&ebr);
+        if logical.kind != 0 && logical.sectors != 0 {
+            partitions.push(Partition {
+                index,
+                scheme: PartitionScheme::Mbr,
+                type_id: format!("0x{:02x}", logical.kind),
+                start: (ebr_lba + logical.lba as u64) * SECTOR_SIZE,
+                len: logical.sectors as u64 * SECTOR_SIZE,
+                name: None,
+            });
+            index += 1;
+        }
+        if !MBR_EXTENDED.contains(&next.kind) || next.lba == 0 {
+            break;
+        }
+        let next_lba = extended_lba + next.lba as u64;
+        if next_lba <= ebr_lba {
+            break;
+        }
+        ebr_lba = next_lba;
+    }
+    Ok(())
+}
+
+/// Read the primary GPT, falling back to the backup header at the last LBA.
+fn read_gpt(evidence: &dyn EvidenceSource) -> Result<Option<Vec<Partition>>, EvidenceError> {
+    for sector_size in GPT_SECTOR_SIZES {
+        if let Some(partitions) = read_gpt_header(evidence, sector_size, 1)? {
+            return Ok(Some(partitions));
+        }
+        let last_lba = (evidence.len() / sector_size).saturating_sub(1);
+        let backup = if last_lba > 1 {
+            read_gpt_header(evidence, sector_size, last_lba)?
+        } else {
+            None
+        };
+        if backup.is_some() {
+            return Ok(backup);
+        }
+    }
+    Ok(None)
+}
+
+fn read_gpt_header(
+    evidence: &dyn EvidenceSource,
+    sector_size: u64,
+    lba: u64,
+) -> Result<Option<Vec<Partition>>, EvidenceError> {
+    let mut header = vec![0u8; sector_size as usize];
+    if read_full(evidence, lba * sector_size, &mut header)? < header.len()
+        || &header[..8] != GPT_SIGNATURE
+    {
+        return Ok(None);
+    }
+    let header_size = le_u32(&header[12..16]) as usize;
+    if !(92..=header.len()).contains(&header_size) {
+        return Ok(None);
+    }
+    let stored_crc = le_u32(&header[16..20]);
+    let mut check = header[..header_size].to_vec();
+    check[16..20].fill(0);
+    if crc32fast::hash(&check) != stored_crc {
+        return Ok(None);
+    }
+
+    let entries_lba = le_u64(&header[72..80]);
+    let count = le_u32(&header[80..84]);
+    let entry_size = le_u32(&header[84..88]);
+    let entries_crc = le_u32(&header[88..92]);
+    if count > MAX_GPT_ENTRIES
+        || !(128..=MAX_GPT_ENTRY_SIZE).contains(&entry_size)
+        || !entry_size.is_multiple_of(8)
+    {
+        return Ok(None);
+    }
+    let mut entries = vec![0u8; count as usize * entry_size as usize];
+    if read_full(evidence, entries_lba * sector_size, &mut entries)? < entries.len()
+        || crc32fast::hash(&entries) != entries_crc
+    {
+        return Ok(None);
+    }
+
+    let mut partitions = Vec::new();
+    for (slot, entry) in entries.chunks_exact(entry_size as usize).enumerate() {
+        let type_guid = &entry[..16];
+        if type_guid.iter().all(|&b| b == 0) {
+            continue;
+        }
+        let first = le_u64(&entry[32..40]);
+        let last = le_u64(&entry[40..48]);
+        if last < first {
+            continue;
+        }
+        let name_units: Vec<u16> = entry[56..128]
+            .chunks_exact(2)
+            .map(|unit| u16::from_le_bytes([unit[0], unit[1]]))
+            .take_while(|&unit| unit != 0)
+            .collect();
+        let name = String::from_utf16_lossy(&name_units);
+        partitions.push(Partition {
+            index: slot as u32 + 1,
+            scheme: PartitionScheme::Gpt,
+            type_id: format_guid(type_guid),
+            start: first * sector_size,
+            len: (last - first + 1) * sector_size,
+            name: (!name.is_empty()).then_some(name),
+        });
+    }
+    Ok(Some(partitions))
+}
+
+/// Drop partitions starting past the end of the evidence and trim the rest.
+fn clamp_to_evidence(partitions: Vec<Partition>, evidence_len: u64) -> Vec<Partition> {
+    partitions
+        .into_iter()
+        .filter(|partition| partition.start < evidence_len)
+        .map(|mut partition| {
+            partition.len = partition.len.min(evidence_len - partition.start);
+            partition
+        })
+        .collect()
+}
+
+/// GUIDs are stored with the first three fields little-endian.
+fn format_guid(bytes: &[u8]) -> String {
+    format!(
+        "{:08X}-{:04X}-{:04X}-{}-{}",
+        le_u32(&bytes[0..4]),
+        u16::from_le_bytes([bytes[4], bytes[5]]),
+        u16::from_le_bytes([bytes[6], bytes[7]]),
+        hex::encode_upper(&bytes[8..10]),
+        hex::encode_upper(&bytes[10..16])
+    )
+}
+
+fn read_full(
+    evidence: &dyn EvidenceSource,
+    offset: u64,
+    buf: &mut [u8],
+) -> Result<usize, EvidenceError> {
+    let mut filled = 0usize;
+    while filled < buf.len() {
+        let n = evidence.read_at(offset + filled as u64, &mut buf[filled..])?;
+        if n == 0 {
+            break;
+        }
+        filled += n;
+    }
+    Ok(filled)
+}
+
+fn le_u32(bytes: &[u8]) -> u32 {
+    u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]])
+}
+
+fn le_u64(bytes: &[u8]) -> u64 {
+    u64::from_le_bytes(bytes[..8].try_into().expect("8 bytes"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct SliceEvidence {
+        data: Vec<u8>,
+    }
+
+    impl EvidenceSource for SliceEvidence {
+        fn len(&self) -> u64 {
+            self.data.len() as u64
+        }
+
+        fn read_at(&self, offset: u64, buf: &mut [u8]) -> Result<usize, EvidenceError> {
+            if offset as usize >= self.data.len() {
+                return Ok(0);
+            }
+            let max = self.data.len() - offset as usize;
+            let to_copy = buf.len().min(max);
+            buf[..to_copy].copy_from_slice(&self.data[offset as usize..offset as usize + to_copy]);
+            Ok(to_copy)
+        }
+    }
+
+    fn set_entry(sector: &mut [u8], slot: usize, kind: u8, lba: u32, sectors: u32) {
+        let at = MBR_TABLE_OFFSET + slot * 16;
+        sector[at + 4] = kind;
+        sector[at + 8..at + 12].copy_from_slice(&lba.to_le_bytes());
+        sector[at + 12..at + 16].copy_from_slice(&sectors.to_le_bytes());
+        sector[510..512].copy_from_slice(&MBR_SIGNATURE);
+    }
+
+    fn sector(image: &mut [u8], lba: u64) -> &mut [u8] {
+        let at = (lba * SECTOR_SIZE) as usize;
+        &mut image[at..at + SECTOR_SIZE as usize]
+    }
+
+    #[test]
+    fn parses_mbr_with_logical_partitions() {
+        let mut image = vec![0u8; 400 * SECTOR_SIZE as usize];
+        set_entry(sector(&mut image, 0), 0, 0x07, 2, 50);
+        set_entry(sector(&mut image, 0), 1, 0x0F, 100, 200);
+        // First EBR at 100: logical at +1, next EBR at extended+100.
+        set_entry(sector(&mut image, 100), 0, 0x83, 1, 40);
+        set_entry(sector(&mut image, 100), 1, 0x05, 100, 60);
+        set_entry(sector(&mut image, 200), 0, 0x0b, 2, 30);
+
+        let partitions = read_partitions(&SliceEvidence { data: image }).expect("partitions");
+        let summary: Vec<(u32, &str, u64, u64)> = partitions
+            .iter()
+            .map(|p| {
+                (
+                    p.index,
+                    p.type_id.as_str(),
+                    p.start / SECTOR_SIZE,
+                    p.len / SECTOR_SIZE,
+                )
+            })
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                (1, "0x07", 2, 50),
+                (5, "0x83", 101, 40),
+                (6, "0x0b", 202, 30)
+            ]
+        );
+        assert_eq!(
+            partition_for_offset(&partitions, 102 * SECTOR_SIZE).map(|p| p.index),
+            Some(5)
+        );
+        assert!(partition_for_offset(&partitions, 60 * SECTOR_SIZE).is_none());
+    }
+
+    fn build_gpt(total_sectors: u64) -> Vec<u8> {
+        let mut image = vec![0u8; (total_sectors * SECTOR_SIZE) as usize];
+        set_entry(
+            sector(&mut image, 0),
+            0,
+            MBR_PROTECTIVE,
+            1,
+            total_sectors as u32 - 1,
+        );
+
+        let mut entries = vec![0u8; 4 * 128];
+        // Microsoft basic data, LBA 34..=63, named "DATA".
+        entries[..16].copy_from_slice(&[
+            0xA2, 0xA0, 0xD0, 0xEB, 0xE5, 0xB9, 0x33, 0x44, 0x87, 0xC0, 0x68, 0xB6, 0xB7, 0x26,
+            0x99, 0xC7,
+        ]);
+        entries[32..40].copy_from_slice(&34u64.to_le_bytes());
+        entries[40..48].copy_from_slice(&63u64.to_le_bytes());
+        for (idx, unit) in "DATA".encode_utf16().enumerate() {
+            entries[56 + idx * 2..58 + idx * 2].copy_from_slice(&unit.to_le_bytes());
+        }
+        // Slot 3 (index 3): Linux filesystem, LBA 64..=79.
+        entries[256..272].copy_from_slice(&[
+            0xAF, 0x3D, 0xC6, 0x0F, 0x83, 0x84, 0x72, 0x47, 0x8E, 0x79, 0x3D, 0x69, 0xD8, 0x47,
+            0x7D, 0xE4,
+        ]);
+        entries[288..296].copy_from_slice(&64u64.to_le_bytes());
+        entries[296..304].copy_from_slice(&79u64.to_le_bytes());
+        sector(&mut image, 2)[..entries.len()].copy_from_slice(&entries);
+
+        let header = sector(&mut image, 1);
+        header[..8].copy_from_slice(GPT_SIGNATURE);
+        header[12..16].copy_from_slice(&92u32.to_le_bytes());
+        header[72..80].copy_from_slice(&2u64.to_le_bytes());
+        header[80..84].copy_from_slice(&4u32.to_le_bytes());
+        header[84..88].copy_from_slice(&128u32.to_le_bytes());
+        header[88..92].copy_from_slice(&crc32fast::hash(&entries).to_le_bytes());
+        let crc = crc32fast::hash(&header[..92]);
+        header[16..20].copy_from_slice(&crc.to_le_bytes());
+        image
+    }
+
+    #[test]
+    fn parses_gpt_entries() {
+        let partitions = read_partitions(&SliceEvidence {
+            data: build_gpt(100),
+        })
+        .expect("partitions");
+        assert_eq!(partitions.len(), 2);
+        assert_eq!(partitions[0].index, 1);
+        assert_eq!(partitions[0].scheme, PartitionScheme::Gpt);
+        assert_eq!(
+            partitions[0].type_id,
+            "EBD0A0A2-B9E5-4433-87C0-68B6B72699C7"
+        );
+        assert_eq!(partitions[0].start, 34 * SECTOR_SIZE);
+        assert_eq!(partitions[0].len, 30 * SECTOR_SIZE);
+        assert_eq!(partitions[0].name.as_deref(), Some("DATA"));
+        assert_eq!(partitions[1].index, 3);
+        assert_eq!(
+            partitions[1].type_id,
+            "0FC63DAF-8483-4772-8E79-3D69D8477DE4"
+        );
+    }
+
+    #[test]
+    fn corrupt_gpt_header_is_ignored() {
+        let mut image = build_gpt(100);
+        image[SECTOR_SIZE as usize + 40] ^= 0xFF;
+        let partitions = read_partitions(&SliceEvidence { data: image }).expect("partitions");
+        assert!(partitions.is_empty());
+    }
+
+    #[test]
+    fn no_signature_means_no_partitions() {
+        let partitions = read_partitions(&SliceEvidence {
+            data: vec![0u8; 4096],
+        })
+        .expect("partitions");
+        assert!(partitions.is_empty());
+    }
+}
@@ -3,15 +3,19 @@
 //! Events that flow through the pipeline for metadata recording.
 
 use crate::carve::CarvedFile;
-use crate::metadata::{EntropyRegion, RunSummary};
+use crate::hashdb::ChunkRun;
+use crate::metadata::{
+    ArchiveEntry, BadSectorRange, EntropyRegion, FuzzyMatch, KeywordHit, RunSummary, YaraMatch,
+};
 use crate::parsers::browser::{BrowserCookieRecord, BrowserDownloadRecord, BrowserHistoryRecord};
 use crate::strings::artifacts::StringArtefact;
 
 /// Events sent to the metadata recording thread
 #[derive(Debug)]
 pub enum MetadataEvent {
-    /// A carved file was successfully extracted
-    File(CarvedFile),
+    /// A carved file was successfully extracted (boxed, it is by far the
+    /// largest event)
+    File(Box<CarvedFile>),
     /// A string artefact (URL, email, phone) was found
     String(StringArtefact),
     /// A browser history record was parsed
@@ -24,6 +28,20 @@ pub enum MetadataEvent {
     RunSummary(RunSummary),
     /// High entropy region detected
     Entropy(EntropyRegion),
+    /// Unreadable evidence range that was zero-filled
+    BadSector(BadSectorRange),
+    /// A YARA rule matched a chunk or a carved file
+    YaraMatch(YaraMatch),
+    /// A `--keywords` term was found; it is recorded at once and its carved
+    /// path filled in by the sink once all files are known
+    KeywordHit(KeywordHit),
+    /// Evidence blocks matched a `--hash-db` reference file; runs split
+    /// across chunks are joined by the metadata thread
+    BlockHashMatch(ChunkRun),
+    /// A carved file is similar to a `--fuzzy-match` reference
+    FuzzyMatch(FuzzyMatch),
+    /// A member listed from a carved archive's directory
+    ArchiveEntry(ArchiveEntry),
     /// Flush buffered data to disk
     Flush,
 }
@@ -6,25 +6,36 @@
 pub mod events;
 pub mod workers;
 
+use std::collections::{BTreeMap, HashMap};
 use std::path::{Path, PathBuf};
-use std::sync::Arc;
 use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
 use std::time::{Duration, Instant};
 
 use anyhow::{Context, Result};
 use crossbeam_channel::bounded;
 use tracing::{info, warn};
 
+use crate::blank;
 use crate::carve::CarveRegistry;
+use crate::carve::dedup::{DedupTracker, DuplicateAction};
 use crate::checkpoint::{CheckpointState, save_checkpoint};
-use crate::chunk::{ScanChunk, build_chunks};
+use crate::chunk::{ScanChunk, build_chunks, build_chunks_for_ranges};
 use crate::config::Config;
 use crate::constants::{CHANNEL_CAPACITY_MULTIPLIER, MIN_CHANNEL_CAPACITY};
-use crate::evidence::EvidenceSource;
-use crate::metadata::{MetadataSink, RunSummary};
+use crate::evidence::{self, EvidenceSource};
+use crate::filesystem::{self, AllocationMap, FileIndex};
+use crate::fuzzy::FuzzyMatcher;
+use crate::hashdb::HashDb;
+use crate::hashset::KnownFiles;
+use crate::keywords::KeywordList;
+use crate::metadata::{BadSectorRange, MetadataSink, RunSummary, SkippedRange};
+use crate::partition::{self, Partition};
+use crate::ranges;
 use crate::scanner::SignatureScanner;
 use crate::strings::StringScanner;
-use crate::strings::artifacts::ArtefactScanConfig;
+use crate::strings::artifacts::{self, ArtefactScanConfig};
+use crate::yara::YaraRules;
 
 use events::MetadataEvent;
 use workers::{ScanJob, StringJob};
@@ -36,6 +47,119 @@ pub struct EntropyConfig {
     pub threshold: f64,
 }
 
+/// Keyword list and hit context window for `--keywords`
+pub struct KeywordSearch {
+    pub keywords: KeywordList,
+    /// Bytes recorded on each side of a hit.
+    pub context_bytes: usize,
+    /// Source of context bytes that fall outside the scanned chunk.
+    pub evidence: Arc<dyn EvidenceSource>,
+}
+
+/// Configuration for constant-block skipping during scanning
+#[derive(Debug, Clone, Copy)]
+pub struct BlankSkipConfig {
+    pub block_size: usize,
+}
+
+/// Alignment rules applied to header hits before carving
+#[derive(Debug, Clone)]
+pub struct AlignmentConfig {
+    /// Required alignment in bytes by file type; types without an entry
+    /// keep hits at any offset.
+    pub by_type: HashMap<String, u64>,
+}
+
+impl AlignmentConfig {
+    /// Rules from `sector_size` and per-type `alignment`, or `None` when no
+    /// type is constrained.
+    pub fn from_config(cfg: &Config) -> Option<Self> {
+        let by_type: HashMap<String, u64> = cfg
+            .file_types
+            .iter()
+            .filter_map(|file_type| {
+                let alignment = file_type.alignment.or(cfg.sector_size)?;
+                (alignment > 1).then(|| (file_type.id.clone(), alignment))
+            })
+            .collect();
+        (!by_type.is_empty()).then_some(Self { by_type })
+    }
+
+    /// Whether a hit at `offset` may start a `file_type` file. Offsets are
+    /// measured from the start of the containing partition, if any.
+    pub fn allows(&self, file_type: &str, offset: u64, partitions: &[Partition]) -> bool {
+        let Some(&alignment) = self.by_type.get(file_type) else {
+            return true;
+        };
+        let base = partition::partition_for_offset(partitions, offset)
+            .map(|partition| partition.start)
+            .unwrap_or(0);
+        (offset - base).is_multiple_of(alignment)
+    }
+}
+
+/// Configuration for post-carve validation
+#[derive(Debug, Clone, Copy)]
+pub struct ValidationConfig {
+    /// Delete carved files that fail validation.
+    pub remove_invalid: bool,
+}
+
+/// Hash sets for `--known-good` / `--known-bad`
+pub struct KnownFileConfig {
+    pub sets: KnownFiles,
+    /// Delete carved files that match a known-good set.
+    pub delete_known_good: bool,
+    pub known_good_files: Arc<AtomicU64>,
+    pub known_bad_files: Arc<AtomicU64>,
+}
+
+/// Member scanning and limits for `--recursive-carving`
+pub struct RecursionConfig {
+    /// Signature scanner run over each decoded member.
+    pub scanner: Arc<dyn SignatureScanner>,
+    pub max_depth: u32,
+    /// Largest container opened, and largest decoded member, in bytes.
+    pub max_size: u64,
+}
+
+/// Per-run settings shared by the scan workers, and the tallies they add to
+pub struct ScanContext {
+    pub run_id: String,
+    pub entropy: Option<EntropyConfig>,
+    pub blank: Option<BlankSkipConfig>,
+    pub alignment: Option<Arc<AlignmentConfig>>,
+    pub partitions: Arc<Vec<Partition>>,
+    pub yara: Option<Arc<YaraRules>>,
+    pub keywords: Option<Arc<KeywordSearch>>,
+    pub hash_db: Option<Arc<HashDb>>,
+    pub bytes_skipped: Arc<AtomicU64>,
+    pub skipped_ranges: Arc<Mutex<Vec<SkippedRange>>>,
+    /// Hits dropped by the alignment rules, by file type.
+    pub unaligned_hits: Arc<Mutex<BTreeMap<String, u64>>>,
+}
+
+/// Per-run settings shared by the carve workers, and the tallies they add to
+pub struct CarveContext {
+    pub run_id: String,
+    pub run_output_dir: PathBuf,
+    pub enable_sqlite_page_recovery: bool,
+    pub max_files: Option<u64>,
+    pub validation: Option<ValidationConfig>,
+    pub partitions: Arc<Vec<Partition>>,
+    pub allocation: Option<Arc<AllocationMap>>,
+    pub file_index: Option<Arc<FileIndex>>,
+    /// Rules evaluated over each carved file, for `yara_scan_carved`.
+    pub yara: Option<Arc<YaraRules>>,
+    pub known_files: Option<Arc<KnownFileConfig>>,
+    pub dedup: Arc<DedupTracker>,
+    pub fuzzy: Option<Arc<FuzzyMatcher>>,
+    pub recursion: Option<Arc<RecursionConfig>>,
+    pub validation_pass: Arc<AtomicU64>,
+    pub validation_fail: Arc<AtomicU64>,
+    pub encrypted_files: Arc<AtomicU64>,
+}
+
 /// Pipeline statistics collected during a run
 #[derive(Debug, Clone)]
 pub struct PipelineStats {
@@ -45,6 +169,9 @@ pub struct PipelineStats {
     pub files_carved: u64,
     pub string_spans: u64,
     pub artefacts_extracted: u64,
+    pub validation_pass: u64,
+    pub validation_fail: u64,
+    pub bytes_skipped: u64,
 }
 
 /// Progress snapshot reported during a run.
@@ -224,18 +351,185 @@ fn run_pipeline_inner(
         }
     }
     let resume_offset = resume_state.as_ref().map(|s| s.next_offset).unwrap_or(0);
-    let resume_chunks = if chunk_size > 0 {
-        resume_offset / chunk_size
+
+    let partitions = match partition::read_partitions(evidence.as_ref()) {
+        Ok(partitions) => partitions,
+        Err(err) => {
+            warn!("failed to read partition table: {err}");
+            Vec::new()
+        }
+    };
+    for part in &partitions {
+        info!(
+            "partition index={} scheme={} type={} start={} len={}",
+            part.index,
+            part.scheme.as_str(),
+            part.type_id,
+            part.start,
+            part.len
+        );
+    }
+
+    let allocation = if cfg.unallocated_only {
+        let map = filesystem::read_allocation(evidence.as_ref(), &partitions);
+        for volume in &map.volumes {
+            info!(
+                "volume fs={} start={} len={} cluster_size={} allocated_bytes={}",
+                volume.kind.as_str(),
+                volume.start,
+                volume.len,
+                volume.cluster_size,
+                volume.allocated_bytes()
+            );
+        }
+        if map.volumes.is_empty() {
+            warn!("no FAT, NTFS or ext volume recognised; all space is treated as unallocated");
+        }
+        Some(Arc::new(map))
+    } else {
+        None
+    };
+
+    let file_index = if cfg.attribute_fs_paths {
+        let index = filesystem::build_file_index(evidence.as_ref(), &partitions);
+        info!("file index: {} live files", index.file_count());
+        if index.is_empty() {
+            warn!("no live file data indexed; carved data will not be attributed to paths");
+        }
+        Some(Arc::new(index))
+    } else {
+        None
+    };
+
+    let custom_artefacts = artifacts::compile_custom(&cfg.custom_artefacts)?;
+    if !custom_artefacts.is_empty() && string_scanner.is_none() {
+        warn!("custom_artefacts configured but string scanning is disabled; they will not run");
+    }
+
+    let yara_rules = match &cfg.yara_rules {
+        Some(path) => {
+            let rules = YaraRules::load(path)
+                .with_context(|| format!("loading yara rules from {}", path.display()))?;
+            let whole_file: Vec<&str> = rules.whole_file_rules().collect();
+            if !whole_file.is_empty() && !cfg.yara_scan_carved {
+                anyhow::bail!(
+                    "yara rules {} use filesize, offsets, integer reads or match counts, \
+                     or match without any string; they only apply to files, so enable \
+                     yara_scan_carved",
+                    whole_file.join(", ")
+                );
+            }
+            info!(
+                "yara: {} rules loaded from {}",
+                rules.rule_count(),
+                path.display()
+            );
+            Some(Arc::new(rules))
+        }
+        None => None,
+    };
+
+    let keyword_search = match &cfg.keywords {
+        Some(path) => {
+            let keywords = KeywordList::load(path)
+                .with_context(|| format!("loading keywords from {}", path.display()))?;
+            info!(
+                "keywords: {} terms loaded from {}",
+                keywords.terms().len(),
+                path.display()
+            );
+            Some(Arc::new(KeywordSearch {
+                keywords,
+                context_bytes: cfg.keyword_context_bytes,
+                evidence: evidence.clone(),
+            }))
+        }
+        None => None,
+    };
+
+    let hash_db = match &cfg.hash_db {
+        Some(path) => {
+            let db = HashDb::load(path)
+                .with_context(|| format!("loading hash database {}", path.display()))?;
+            info!(
+                "hash db: {} block hashes from {} files, block size {}",
+                db.hash_count(),
+                db.file_count(),
+                db.block_size()
+            );
+            Some(Arc::new(db))
+        }
+        None => None,
+    };
+
+    let known_files = if cfg.known_good.is_empty() && cfg.known_bad.is_empty() {
+        None
     } else {
-        0
+        Some(Arc::new(KnownFileConfig {
+            sets: KnownFiles::load(&cfg.known_good, &cfg.known_bad)?,
+            delete_known_good: cfg.delete_known_good,
+            known_good_files: Arc::new(AtomicU64::new(0)),
+            known_bad_files: Arc::new(AtomicU64::new(0)),
+        }))
+    };
+
+    let dedup = Arc::new(DedupTracker::new(DuplicateAction::from_flags(
+        cfg.skip_duplicates,
+        cfg.hardlink_duplicates,
+    )));
+
+    let fuzzy = match &cfg.fuzzy_match {
+        Some(path) => {
+            let matcher = FuzzyMatcher::load(path, cfg.fuzzy_threshold, cfg.tlsh_max_distance)?;
+            info!("fuzzy match: {} reference digests", matcher.len());
+            Some(Arc::new(matcher))
+        }
+        None => None,
+    };
+
+    let recursion = cfg.recursive_carving.then(|| {
+        Arc::new(RecursionConfig {
+            scanner: sig_scanner.clone(),
+            max_depth: cfg.recursive_max_depth,
+            max_size: cfg.recursive_max_size,
+        })
+    });
+
+    let scan_ranges = scan_ranges(cfg, &partitions, allocation.as_deref(), total_bytes)?;
+    if let Some(state) = &resume_state {
+        let requested = scan_ranges.clone().unwrap_or_default();
+        if state.scan_ranges != requested {
+            return Err(anyhow::anyhow!(
+                "checkpoint scan ranges {:?} do not match requested {:?}",
+                state.scan_ranges,
+                requested
+            ));
+        }
+    }
+    let limited_scan = scan_ranges.is_some();
+    let chunks = match &scan_ranges {
+        Some(ranges) => {
+            for (start, end) in ranges {
+                info!("scan_range start={} end={}", start, end);
+            }
+            build_chunks_for_ranges(ranges, chunk_size, overlap)
+        }
+        None => build_chunks(total_bytes, chunk_size, overlap),
     };
-    let chunks = build_chunks(total_bytes, chunk_size, overlap);
+    let scan_total_bytes: u64 = chunks.iter().map(|chunk| chunk.valid_length).sum();
+    let resumed: Vec<&ScanChunk> = chunks
+        .iter()
+        .filter(|chunk| chunk.start < resume_offset)
+        .collect();
+    let resume_chunks = resumed.len() as u64;
+    let resume_bytes: u64 = resumed.iter().map(|chunk| chunk.valid_length).sum();
     info!(
         "chunk_count={} chunk_size={} overlap={}",
         chunks.len(),
         chunk_size,
         overlap
     );
+    let partitions = Arc::new(partitions);
 
     // Create channels
     let channel_cap = workers
@@ -257,11 +551,17 @@ fn run_pipeline_inner(
     let chunks_processed = Arc::new(AtomicU64::new(0));
     let hits_found = Arc::new(AtomicU64::new(0));
     let files_carved = Arc::new(AtomicU64::new(0));
+    let encrypted_files = Arc::new(AtomicU64::new(0));
     let string_spans = Arc::new(AtomicU64::new(0));
     let artefacts_found = Arc::new(AtomicU64::new(0));
     let carve_errors = Arc::new(AtomicU64::new(0));
     let metadata_errors = Arc::new(AtomicU64::new(0));
     let sqlite_errors = Arc::new(AtomicU64::new(0));
+    let validation_pass = Arc::new(AtomicU64::new(0));
+    let validation_fail = Arc::new(AtomicU64::new(0));
+    let bytes_skipped = Arc::new(AtomicU64::new(0));
+    let skipped_ranges: Arc<Mutex<Vec<SkippedRange>>> = Arc::new(Mutex::new(Vec::new()));
+    let unaligned_hits: Arc<Mutex<BTreeMap<String, u64>>> = Arc::new(Mutex::new(BTreeMap::new()));
 
     // Start metadata recording thread
     let meta_handle = workers::spawn_metadata_thread(meta_sink, meta_rx, metadata_errors.clone());
@@ -276,7 +576,41 @@ fn run_pipeline_inner(
         None
     };
 
+    // Build constant-block skipping config if enabled
+    let blank_cfg = if cfg.skip_constant_blocks && cfg.constant_block_size > 0 {
+        Some(BlankSkipConfig {
+            block_size: cfg.constant_block_size,
+        })
+    } else {
+        None
+    };
+
+    // Build hit alignment rules if any file type is constrained
+    let alignment_cfg = AlignmentConfig::from_config(cfg).map(Arc::new);
+
+    // Build validation config if enabled
+    let validation_cfg = if cfg.validate_carved {
+        Some(ValidationConfig {
+            remove_invalid: cfg.remove_invalid,
+        })
+    } else {
+        None
+    };
+
     // Spawn worker threads
+    let scan_ctx = Arc::new(ScanContext {
+        run_id: cfg.run_id.clone(),
+        entropy: entropy_cfg,
+        blank: blank_cfg,
+        alignment: alignment_cfg,
+        partitions: partitions.clone(),
+        yara: yara_rules.clone(),
+        keywords: keyword_search,
+        hash_db,
+        bytes_skipped: bytes_skipped.clone(),
+        skipped_ranges: skipped_ranges.clone(),
+        unaligned_hits: unaligned_hits.clone(),
+    });
     let scan_handles = workers::spawn_scan_workers(
         workers,
         sig_scanner,
@@ -285,23 +619,37 @@ fn run_pipeline_inner(
         hit_tx.clone(),
         string_tx.clone(),
         meta_tx.clone(),
-        cfg.run_id.clone(),
-        entropy_cfg,
+        scan_ctx,
         hits_found.clone(),
         string_spans.clone(),
     );
 
+    let carve_ctx = Arc::new(CarveContext {
+        run_id: cfg.run_id.clone(),
+        run_output_dir: run_output_dir.to_path_buf(),
+        enable_sqlite_page_recovery: cfg.enable_sqlite_page_recovery,
+        max_files: cfg.max_files,
+        validation: validation_cfg,
+        partitions: partitions.clone(),
+        allocation: allocation.clone(),
+        file_index: file_index.clone(),
+        yara: yara_rules.filter(|_| cfg.yara_scan_carved),
+        known_files: known_files.clone(),
+        dedup: dedup.clone(),
+        fuzzy,
+        recursion,
+        validation_pass: validation_pass.clone(),
+        validation_fail: validation_fail.clone(),
+        encrypted_files: encrypted_files.clone(),
+    });
     let carve_handles = workers::spawn_carve_workers(
         workers,
         carve_registry,
         evidence.clone(),
-        cfg.run_id.clone(),
-        run_output_dir.to_path_buf(),
         hit_rx,
         meta_tx.clone(),
+        carve_ctx,
         files_carved.clone(),
-        cfg.enable_sqlite_page_recovery,
-        cfg.max_files,
         carve_errors.clone(),
         sqlite_errors.clone(),
     );
@@ -311,6 +659,11 @@ fn run_pipeline_inner(
             urls: cfg.enable_url_scan,
             emails: cfg.enable_email_scan,
             phones: cfg.enable_phone_scan,
+            ips: cfg.enable_ip_scan,
+            credit_cards: cfg.enable_credit_card_scan,
+            crypto_wallets: cfg.enable_crypto_wallet_scan,
+            secrets: cfg.enable_secret_scan,
+            custom: Arc::from(custom_artefacts),
         };
         workers::spawn_string_workers(
             workers,
@@ -319,6 +672,7 @@ fn run_pipeline_inner(
             meta_tx.clone(),
             artefacts_found.clone(),
             scan_cfg,
+            file_index.clone(),
         )
     } else {
         Vec::new()
@@ -328,7 +682,7 @@ fn run_pipeline_inner(
     let max_bytes = max_bytes.unwrap_or(u64::MAX);
     let max_chunks = max_chunks.unwrap_or(u64::MAX);
     let mut chunks_seen = 0u64;
-    let mut hit_max_bytes = resume_offset >= max_bytes;
+    let mut hit_max_bytes = resume_bytes >= max_bytes;
     let mut hit_max_chunks = resume_chunks >= max_chunks;
     let mut hit_max_files = false;
     let mut cancelled = false;
@@ -362,7 +716,7 @@ fn run_pipeline_inner(
         }
         let scanned_total = bytes_scanned
             .load(Ordering::Relaxed)
-            .saturating_add(resume_offset);
+            .saturating_add(resume_bytes);
         if scanned_total >= max_bytes {
             hit_max_bytes = true;
             break;
@@ -386,8 +740,8 @@ fn run_pipeline_inner(
         if let Some(progress) = &progress {
             if progress.interval.is_zero() || last_progress.elapsed() >= progress.interval {
                 let snapshot = build_progress_snapshot(
-                    total_bytes,
-                    resume_offset,
+                    scan_total_bytes,
+                    resume_bytes,
                     &start_time,
                     &bytes_scanned,
                     &chunks_processed,
@@ -398,6 +752,8 @@ fn run_pipeline_inner(
                     &carve_errors,
                     &metadata_errors,
                     &sqlite_errors,
+                    &validation_pass,
+                    &validation_fail,
                 );
                 progress.reporter.on_progress(&snapshot);
                 last_progress = Instant::now();
@@ -408,7 +764,7 @@ fn run_pipeline_inner(
         }
         let scanned_total = bytes_scanned
             .load(Ordering::Relaxed)
-            .saturating_add(resume_offset);
+            .saturating_add(resume_bytes);
         if scanned_total >= max_bytes {
             hit_max_bytes = true;
             break;
@@ -430,10 +786,41 @@ fn run_pipeline_inner(
         let _ = handle.join();
     }
 
+    // Record unreadable ranges found by a tolerant evidence source
+    let bad_ranges = evidence.unreadable_ranges();
+    if !bad_ranges.is_empty() {
+        let bad_bytes: u64 = bad_ranges.iter().map(|(start, end)| end - start).sum();
+        warn!(
+            "unreadable ranges={} bytes={} (zero-filled)",
+            bad_ranges.len(),
+            bad_bytes
+        );
+    }
+    for &(start, end) in bad_ranges.iter() {
+        let range = BadSectorRange {
+            run_id: cfg.run_id.clone(),
+            global_start: start,
+            length: end - start,
+        };
+        if let Err(err) = meta_tx.send(MetadataEvent::BadSector(range)) {
+            warn!("metadata channel closed while sending bad sector range: {err}");
+            break;
+        }
+    }
+    if cfg.tolerant_reads && run_output_dir.is_dir() {
+        let map_path = run_output_dir.join("bad_sectors.map");
+        let rescued_until = next_offset.min(total_bytes);
+        if let Err(err) =
+            evidence::write_ddrescue_mapfile(&map_path, total_bytes, rescued_until, &bad_ranges)
+        {
+            warn!("failed to write mapfile {}: {err}", map_path.display());
+        }
+    }
+
     // Send run summary
     let bytes_scanned_total = bytes_scanned
         .load(Ordering::Relaxed)
-        .saturating_add(resume_offset);
+        .saturating_add(resume_bytes);
     let chunks_processed_total = chunks_processed
         .load(Ordering::Relaxed)
         .saturating_add(resume_chunks);
@@ -445,7 +832,26 @@ fn run_pipeline_inner(
         files_carved: files_carved.load(Ordering::Relaxed),
         string_spans: string_spans.load(Ordering::Relaxed),
         artefacts_extracted: artefacts_found.load(Ordering::Relaxed),
+        bytes_skipped: bytes_skipped.load(Ordering::Relaxed),
+        skipped_ranges: blank::merge_ranges(
+            skipped_ranges
+                .lock()
+                .map(|mut guard| std::mem::take(&mut *guard))
+                .unwrap_or_default(),
+        ),
+        unaligned_hits: unaligned_hits
+            .lock()
+            .map(|mut guard| std::mem::take(&mut *guard))
+            .unwrap_or_default(),
+        known_good_files: known_files
+            .as_ref()
+            .map_or(0, |known| known.known_good_files.load(Ordering::Relaxed)),
+        known_bad_files: known_files
+            .as_ref()
+            .map_or(0, |known| known.known_bad_files.load(Ordering::Relaxed)),
+        encrypted_files: encrypted_files.load(Ordering::Relaxed),
     };
+    let known_counts = (summary.known_good_files, summary.known_bad_files);
     if let Err(err) = meta_tx.send(MetadataEvent::RunSummary(summary)) {
         warn!("metadata channel closed while sending run summary: {err}");
     }
@@ -455,8 +861,8 @@ fn run_pipeline_inner(
 
     if let Some(progress) = &progress {
         let snapshot = build_progress_snapshot(
-            total_bytes,
-            resume_offset,
+            scan_total_bytes,
+            resume_bytes,
             &start_time,
             &bytes_scanned,
             &chunks_processed,
@@ -467,6 +873,8 @@ fn run_pipeline_inner(
             &carve_errors,
             &metadata_errors,
             &sqlite_errors,
+            &validation_pass,
+            &validation_fail,
         );
         progress.reporter.on_progress(&snapshot);
     }
@@ -491,27 +899,53 @@ fn run_pipeline_inner(
         files_carved: files_carved.load(Ordering::Relaxed),
         string_spans: string_spans.load(Ordering::Relaxed),
         artefacts_extracted: artefacts_found.load(Ordering::Relaxed),
+        validation_pass: validation_pass.load(Ordering::Relaxed),
+        validation_fail: validation_fail.load(Ordering::Relaxed),
+        bytes_skipped: bytes_skipped.load(Ordering::Relaxed),
     };
 
     info!(
-        "run_summary bytes_scanned={} chunks_processed={} hits_found={} files_carved={} string_spans={} artefacts_extracted={}",
+        "run_summary bytes_scanned={} chunks_processed={} hits_found={} files_carved={} string_spans={} artefacts_extracted={} bytes_skipped={}",
         stats.bytes_scanned,
         stats.chunks_processed,
         stats.hits_found,
         stats.files_carved,
         stats.string_spans,
-        stats.artefacts_extracted
+        stats.artefacts_extracted,
+        stats.bytes_skipped
+    );
+    if validation_cfg.is_some() {
+        info!(
+            "validation_summary passed={} failed={}",
+            stats.validation_pass, stats.validation_fail
+        );
+    }
+    info!("dedup_summary duplicates={}", dedup.duplicates());
+    info!(
+        "encryption_summary encrypted_files={}",
+        encrypted_files.load(Ordering::Relaxed)
     );
+    if known_files.is_some() {
+        let (known_good, known_bad) = known_counts;
+        info!("hash_set_summary known_good={known_good} known_bad={known_bad}");
+        if known_bad > 0 {
+            warn!(
+                alert = "known_bad",
+                "{known_bad} carved files matched a known-bad hash set"
+            );
+        }
+    }
 
     if cancelled || hit_max_bytes || hit_max_chunks || hit_max_files {
         if let Some(path) = checkpoint_path {
-            let state = CheckpointState::new(
-                &cfg.run_id,
-                chunk_size,
-                overlap,
-                next_offset.min(total_bytes),
-                total_bytes,
-            );
+            let next_offset = next_offset.min(total_bytes);
+            let mut state =
+                CheckpointState::new(&cfg.run_id, chunk_size, overlap, next_offset, total_bytes);
+            let covered = scan_ranges.unwrap_or_else(|| vec![(0, total_bytes)]);
+            state.completed_ranges = ranges::completed_ranges(&covered, next_offset);
+            if limited_scan {
+                state.scan_ranges = covered;
+            }
             if let Err(err) = save_checkpoint(&path, &state) {
                 warn!("failed to write checkpoint {}: {err}", path.display());
             } else {
@@ -523,6 +957,82 @@ fn run_pipeline_inner(
     Ok(stats)
 }
 
+/// Byte ranges to scan after applying `partitions`, the offset window,
+/// `ranges_file` and `unallocated_only`; `None` means the whole evidence.
+fn scan_ranges(
+    cfg: &Config,
+    partitions: &[Partition],
+    allocation: Option<&AllocationMap>,
+    total_bytes: u64,
+) -> Result<Option<Vec<(u64, u64)>>> {
+    let limited = cfg.partitions.is_some()
+        || cfg.start_offset.is_some()
+        || cfg.end_offset.is_some()
+        || cfg.ranges_file.is_some()
+        || allocation.is_some();
+    if !limited {
+        return Ok(None);
+    }
+
+    let mut selected = match &cfg.partitions {
+        Some(indexes) => selected_partition_ranges(partitions, indexes)?,
+        None => vec![(0, total_bytes)],
+    };
+    let start = cfg.start_offset.unwrap_or(0);
+    let end = cfg.end_offset.unwrap_or(total_bytes).min(total_bytes);
+    if start >= end {
+        return Err(anyhow::anyhow!(
+            "empty scan window: start offset {start} is not below end offset {end} (evidence size {total_bytes})"
+        ));
+    }
+    selected = ranges::intersect_ranges(&ranges::merge_ranges(selected), &[(start, end)]);
+    if let Some(path) = &cfg.ranges_file {
+        let listed = ranges::load_ranges_file(path)
+            .with_context(|| format!("failed to read ranges file {}", path.display()))?;
+        selected = ranges::intersect_ranges(&selected, &listed);
+    }
+    if let Some(map) = allocation {
+        selected = ranges::intersect_ranges(&selected, &map.unallocated_ranges(total_bytes));
+    }
+    if selected.is_empty() {
+        return Err(anyhow::anyhow!(
+            "no bytes left to scan after applying scan ranges"
+        ));
+    }
+    Ok(Some(selected))
+}
+
+/// Byte ranges of the selected partitions, sorted by start offset.
+fn selected_partition_ranges(
+    partitions: &[Partition],
+    selected: &[u32],
+) -> Result<Vec<(u64, u64)>> {
+    let mut ranges = Vec::with_capacity(selected.len());
+    for index in selected {
+        let part = partitions
+            .iter()
+            .find(|part| part.index == *index)
+            .ok_or_else(|| {
+                let available: Vec<String> = partitions
+                    .iter()
+                    .map(|part| part.index.to_string())
+                    .collect();
+                anyhow::anyhow!(
+                    "partition {index} not found (available: {})",
+                    if available.is_empty() {
+                        "none".to_string()
+                    } else {
+                        available.join(", ")
+                    }
+                )
+            })?;
+        ranges.push((part.start, part.end()));
+    }
+    ranges.sort_unstable();
+    ranges.dedup();
+    Ok(ranges)
+}
+
 fn build_progress_snapshot(
     total_bytes: u64,
     baseline_bytes: u64,
@@ -536,6 +1046,8 @@ fn build_progress_snapshot(
     carve_errors: &AtomicU64,
     metadata_errors: &AtomicU64,
     sqlite_errors: &AtomicU64,
+    validation_pass: &AtomicU64,
+    validation_fail: &AtomicU64,
 ) -> ProgressSnapshot {
     let elapsed_seconds = start_time.elapsed().as_secs_f64();
     let scanned = bytes_scanned.load(Ordering::Relaxed);
@@ -577,8 +1089,8 @@ fn build_progress_snapshot(
         throughput_mib,
         eta_seconds,
         completion_pct,
-        validation_pass: 0, // To be populated when validation is enabled
-        validation_fail: 0, // To be populated when validation is enabled
+        validation_pass: validation_pass.load(Ordering::Relaxed),
+        validation_fail: validation_fail.load(Ordering::Relaxed),
     }
 }
 
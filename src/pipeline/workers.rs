@@ -2,25 +2,42 @@
 //!
 //! Worker thread spawning and management for the processing pipeline.
 
-use std::path::PathBuf;
-use std::sync::Arc;
+use std::collections::{BTreeMap, VecDeque};
+use std::path::{Path, PathBuf};
 use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
 use std::thread;
 
 use crossbeam_channel::{Receiver, Sender};
 use tracing::{debug, warn};
 
-use crate::carve::{CarveRegistry, ExtractionContext};
+use crate::archive;
+use crate::blank;
+use crate::carve::dedup::{DedupTracker, DuplicateAction};
+use crate::carve::{CarveRegistry, CarvedFile, ExtractionContext};
 use crate::chunk::ScanChunk;
+use crate::embedded::{self, Member};
 use crate::entropy;
-use crate::evidence::EvidenceSource;
-use crate::metadata::MetadataSink;
+use crate::evidence::{EvidenceSource, MemorySource};
+use crate::filesystem::{AllocationMap, FileIndex, FileLocation};
+use crate::fuzzy::{FuzzyDigests, FuzzyMatcher};
+use crate::hashdb::{ChunkRun, HashDb, RunJoiner};
+use crate::hashset::{self, FileHashes, HashMatch};
+use crate::keywords;
+use crate::metadata::{
+    ArchiveEntry, BlockHashMatch, FuzzyMatch, KeywordHit, MetadataSink, SkippedRange, YaraMatch,
+};
+use crate::partition::{self, Partition};
 use crate::scanner::{NormalizedHit, SignatureScanner};
 use crate::strings::artifacts::ArtefactScanConfig;
 use crate::strings::{self, StringScanner, StringSpan};
+use crate::validate::{self, ValidationOutcome};
+use crate::yara::YaraRules;
 
-use super::EntropyConfig;
 use super::events::MetadataEvent;
+use super::{
+    CarveContext, KeywordSearch, KnownFileConfig, RecursionConfig, ScanContext, ValidationConfig,
+};
 
 /// Job containing a chunk of data to scan
 pub struct ScanJob {
@@ -42,6 +59,9 @@ pub fn spawn_metadata_thread(
     error_count: Arc<AtomicU64>,
 ) -> thread::JoinHandle<()> {
     thread::spawn(move || {
+        let mut carved = CarvedRanges::default();
+        let mut keyword_hits = false;
+        let mut block_runs = RunJoiner::default();
         for event in rx {
             match event {
                 MetadataEvent::File(file) => {
@@ -49,6 +69,7 @@ pub fn spawn_metadata_thread(
                         error_count.fetch_add(1, Ordering::Relaxed);
                         warn!("metadata record error: {err}");
                     }
+                    carved.push(&file);
                 }
                 MetadataEvent::String(artefact) => {
                     if let Err(err) = sink.record_string(&artefact) {
@@ -86,6 +107,45 @@ pub fn spawn_metadata_thread(
                         warn!("metadata record error: {err}");
                     }
                 }
+                MetadataEvent::BadSector(range) => {
+                    if let Err(err) = sink.record_bad_sector(&range) {
+                        error_count.fetch_add(1, Ordering::Relaxed);
+                        warn!("metadata record error: {err}");
+                    }
+                }
+                MetadataEvent::YaraMatch(m) => {
+                    if let Err(err) = sink.record_yara_match(&m) {
+                        error_count.fetch_add(1, Ordering::Relaxed);
+                        warn!("metadata record error: {err}");
+                    }
+                }
+                MetadataEvent::FuzzyMatch(m) => {
+                    if let Err(err) = sink.record_fuzzy_match(&m) {
+                        error_count.fetch_add(1, Ordering::Relaxed);
+                        warn!("metadata record error: {err}");
+                    }
+                }
+                MetadataEvent::ArchiveEntry(entry) => {
+                    if let Err(err) = sink.record_archive_entry(&entry) {
+                        error_count.fetch_add(1, Ordering::Relaxed);
+                        warn!("metadata record error: {err}");
+                    }
+                }
+                MetadataEvent::KeywordHit(hit) => {
+                    keyword_hits = true;
+                    if let Err(err) = sink.record_keyword_hit(&hit) {
+                        error_count.fetch_add(1, Ordering::Relaxed);
+                        warn!("metadata record error: {err}");
+                    }
+                }
+                MetadataEvent::BlockHashMatch(run) => {
+                    if let Some(m) = block_runs.push(run)
+                        && let Err(err) = sink.record_block_hash_match(&m)
+                    {
+                        error_count.fetch_add(1, Ordering::Relaxed);
+                        warn!("metadata record error: {err}");
+                    }
+                }
                 MetadataEvent::Flush => {
                     if let Err(err) = sink.flush() {
                         error_count.fetch_add(1, Ordering::Relaxed);
@@ -94,6 +154,21 @@ pub fn spawn_metadata_thread(
                 }
             }
         }
+        // Carving has finished, so every hit can be placed in its file
+        if keyword_hits {
+            carved.sort();
+            if let Err(err) = sink.attribute_keyword_hits(&|offset| carved.containing(offset)) {
+                error_count.fetch_add(1, Ordering::Relaxed);
+                warn!("keyword hit attribution error: {err}");
+            }
+        }
+        // Runs reaching the end of the evidence have no piece left to wait for
+        for m in block_runs.finish() {
+            if let Err(err) = sink.record_block_hash_match(&m) {
+                error_count.fetch_add(1, Ordering::Relaxed);
+                warn!("metadata record error: {err}");
+            }
+        }
         // Final flush when channel closes
         if let Err(err) = sink.flush() {
             error_count.fetch_add(1, Ordering::Relaxed);
@@ -102,6 +177,43 @@ pub fn spawn_metadata_thread(
     })
 }
 
+/// Byte ranges of the carved files, for placing keyword hits.
+#[derive(Default)]
+struct CarvedRanges {
+    /// `(global_start, global_end, path)`, end inclusive.
+    files: Vec<(u64, u64, String)>,
+    max_len: u64,
+}
+
+impl CarvedRanges {
+    fn push(&mut self, file: &CarvedFile) {
+        // Embedded files share their outermost container's range
+        if file.size == 0 || file.depth > 0 {
+            return;
+        }
+        self.max_len = self.max_len.max(file.size);
+        self.files
+            .push((file.global_start, file.global_end, file.path.clone()));
+    }
+
+    fn sort(&mut self) {
+        self.files.sort_by_key(|(start, _, _)| *start);
+    }
+
+    /// Path of the smallest file holding `offset`.
+    fn containing(&self, offset: u64) -> Option<String> {
+        let first = self
+            .files
+            .partition_point(|(start, _, _)| *start < offset.saturating_sub(self.max_len));
+        let last = self.files.partition_point(|(start, _, _)| *start <= offset);
+        self.files[first..last]
+            .iter()
+            .filter(|(_, end, _)| *end >= offset)
+            .min_by_key(|(start, end, _)| end - start)
+            .map(|(_, _, path)| path.clone())
+    }
+}
+
 /// Spawn signature scanning worker threads
 pub fn spawn_scan_workers(
     workers: usize,
@@ -111,8 +223,7 @@ pub fn spawn_scan_workers(
     hit_tx: Sender<NormalizedHit>,
     string_tx: Option<Sender<StringJob>>,
     meta_tx: Sender<MetadataEvent>,
-    run_id: String,
-    entropy_cfg: Option<EntropyConfig>,
+    ctx: Arc<ScanContext>,
     hits_found: Arc<AtomicU64>,
     string_spans: Arc<AtomicU64>,
 ) -> Vec<thread::JoinHandle<()>> {
@@ -121,6 +232,7 @@ pub fn spawn_scan_workers(
 
     for _ in 0..worker_count {
         let scanner = scanner.clone();
+        let ctx = ctx.clone();
         let rx = rx.clone();
         let hit_tx = hit_tx.clone();
         let string_scanner = string_scanner.clone();
@@ -128,75 +240,147 @@ pub fn spawn_scan_workers(
         let hits_found = hits_found.clone();
         let string_spans = string_spans.clone();
         let meta_tx = meta_tx.clone();
-        let run_id = run_id.clone();
-        let entropy_cfg = entropy_cfg;
 
         handles.push(thread::spawn(move || {
             for job in rx {
                 let effective_valid = job.chunk.valid_length.min(job.data.len() as u64);
                 let valid_len = effective_valid as usize;
 
-                // Scan for file signatures
-                for hit in scanner.scan_chunk(&job.chunk, &job.data) {
-                    if hit.local_offset >= effective_valid {
-                        continue;
+                // Keep constant-filled blocks away from the scanners
+                let segments = match ctx.blank {
+                    Some(cfg) => {
+                        let runs =
+                            blank::constant_runs(job.chunk.start, &job.data, cfg.block_size);
+                        record_skipped(
+                            &runs,
+                            job.chunk.start,
+                            valid_len,
+                            &ctx.bytes_skipped,
+                            &ctx.skipped_ranges,
+                        );
+                        blank::live_segments(job.data.len(), &runs, blank::SEGMENT_MARGIN)
                     }
-                    hits_found.fetch_add(1, Ordering::Relaxed);
-                    let global_offset = job.chunk.start + hit.local_offset;
-                    let normalized = NormalizedHit {
-                        global_offset,
-                        file_type_id: hit.file_type_id,
-                        pattern_id: hit.pattern_id,
+                    None => vec![(0, job.data.len())],
+                };
+
+                let mut chunk_spans: Vec<StringSpan> = Vec::new();
+                let mut chunk_unaligned: BTreeMap<String, u64> = BTreeMap::new();
+                for (seg_start, seg_end) in segments {
+                    let seg_offset = seg_start as u64;
+                    let seg_chunk = ScanChunk {
+                        id: job.chunk.id,
+                        start: job.chunk.start + seg_offset,
+                        length: (seg_end - seg_start) as u64,
+                        valid_length: effective_valid
+                            .saturating_sub(seg_offset)
+                            .min((seg_end - seg_start) as u64),
                     };
-                    if let Err(err) = hit_tx.send(normalized) {
-                        warn!("hit channel closed while sending hit: {err}");
-                        break;
-                    }
-                }
+                    let seg_data = &job.data[seg_start..seg_end];
 
-                // Scan for strings if enabled
-                if let (Some(scanner), Some(tx)) = (&string_scanner, &string_tx) {
-                    let spans = scanner.scan_chunk(&job.chunk, &job.data);
-                    if !spans.is_empty() {
-                        let filtered: Vec<StringSpan> = spans
-                            .into_iter()
-                            .filter(|span| span.local_start < effective_valid)
-                            .collect();
-                        if !filtered.is_empty() {
-                            string_spans.fetch_add(filtered.len() as u64, Ordering::Relaxed);
-                            let string_job = StringJob {
-                                chunk: job.chunk.clone(),
-                                data: Arc::clone(&job.data),
-                                spans: filtered,
-                            };
-                            if let Err(err) = tx.send(string_job) {
-                                warn!("string channel closed while sending spans: {err}");
-                                break;
-                            }
+                    // Scan for file signatures
+                    for hit in scanner.scan_chunk(&seg_chunk, seg_data) {
+                        let local_offset = seg_offset + hit.local_offset;
+                        if local_offset >= effective_valid {
+                            continue;
+                        }
+                        let global_offset = job.chunk.start + local_offset;
+                        if let Some(alignment) = &ctx.alignment
+                            && !alignment.allows(&hit.file_type_id, global_offset, &ctx.partitions)
+                        {
+                            *chunk_unaligned.entry(hit.file_type_id).or_default() += 1;
+                            continue;
+                        }
+                        hits_found.fetch_add(1, Ordering::Relaxed);
+                        let normalized = NormalizedHit {
+                            global_offset,
+                            file_type_id: hit.file_type_id,
+                            pattern_id: hit.pattern_id,
+                        };
+                        if let Err(err) = hit_tx.send(normalized) {
+                            warn!("hit channel closed while sending hit: {err}");
+                            break;
                         }
                     }
-                }
 
-                // Detect high entropy regions if enabled
-                if let Some(cfg) = entropy_cfg {
-                    if valid_len >= cfg.window_size {
-                        let regions = entropy::detect_entropy_regions(
-                            &run_id,
-                            job.chunk.start,
-                            &job.data[..valid_len],
-                            cfg.window_size,
-                            cfg.threshold,
+                    // Scan for strings if enabled
+                    if let Some(scanner) = &string_scanner {
+                        chunk_spans.extend(
+                            scanner
+                                .scan_chunk(&seg_chunk, seg_data)
+                                .into_iter()
+                                .map(|mut span| {
+                                    span.local_start += seg_offset;
+                                    span
+                                })
+                                .filter(|span| span.local_start < effective_valid),
                         );
-                        for region in regions {
-                            if let Err(err) = meta_tx.send(MetadataEvent::Entropy(region)) {
-                                warn!(
-                                    "metadata channel closed while sending entropy region: {err}"
-                                );
-                                break;
+                    }
+
+                    // Detect high entropy regions if enabled
+                    if let Some(cfg) = ctx.entropy {
+                        let seg_valid_end = seg_end.min(valid_len);
+                        if seg_valid_end >= seg_start + cfg.window_size {
+                            let regions = entropy::detect_entropy_regions(
+                                &ctx.run_id,
+                                seg_chunk.start,
+                                &job.data[seg_start..seg_valid_end],
+                                cfg.window_size,
+                                cfg.threshold,
+                            );
+                            for region in regions {
+                                if let Err(err) = meta_tx.send(MetadataEvent::Entropy(region)) {
+                                    warn!(
+                                        "metadata channel closed while sending entropy region: {err}"
+                                    );
+                                    break;
+                                }
                             }
                         }
                     }
                 }
+
+                if let Some(rules) = &ctx.yara {
+                    scan_chunk_yara(rules, &job, effective_valid, &ctx.run_id, &meta_tx);
+                }
+
+                if let Some(search) = &ctx.keywords {
+                    scan_chunk_keywords(search, &job, effective_valid, &ctx.run_id, &meta_tx);
+                }
+
+                if let Some(db) = &ctx.hash_db {
+                    scan_chunk_blocks(
+                        db,
+                        &job,
+                        effective_valid,
+                        &ctx.partitions,
+                        &ctx.run_id,
+                        &meta_tx,
+                    );
+                }
+
+                if !chunk_unaligned.is_empty()
+                    && let Ok(mut guard) = ctx.unaligned_hits.lock()
+                {
+                    for (file_type, count) in chunk_unaligned {
+                        *guard.entry(file_type).or_default() += count;
+                    }
+                }
+
+                if chunk_spans.is_empty() {
+                    continue;
+                }
+                if let Some(tx) = &string_tx {
+                    string_spans.fetch_add(chunk_spans.len() as u64, Ordering::Relaxed);
+                    let string_job = StringJob {
+                        chunk: job.chunk.clone(),
+                        data: Arc::clone(&job.data),
+                        spans: chunk_spans,
+                    };
+                    if let Err(err) = tx.send(string_job) {
+                        warn!("string channel closed while sending spans: {err}");
+                        break;
+                    }
+                }
             }
         }));
     }
@@ -204,18 +388,273 @@ pub fn spawn_scan_workers(
     handles
 }
 
+/// Evaluate YARA rules over a whole chunk. A rule is reported by the chunk
+/// whose valid region holds its first string match, so matches in the
+/// overlap tail are left to the next chunk; rules that depend on the whole
+/// file are only reported for carved files.
+fn scan_chunk_yara(
+    rules: &YaraRules,
+    job: &ScanJob,
+    effective_valid: u64,
+    run_id: &str,
+    meta_tx: &Sender<MetadataEvent>,
+) {
+    for rule_match in rules.scan_chunk(&job.data) {
+        let Some((string_id, offset)) = rule_match.first_string else {
+            continue;
+        };
+        if offset as u64 >= effective_valid {
+            continue;
+        }
+        let record = YaraMatch {
+            run_id: run_id.to_string(),
+            rule: rule_match.rule,
+            tags: rule_match.tags,
+            global_offset: job.chunk.start + offset as u64,
+            string_id: Some(string_id),
+            carved_path: None,
+        };
+        if let Err(err) = meta_tx.send(MetadataEvent::YaraMatch(record)) {
+            warn!("metadata channel closed while sending yara match: {err}");
+            break;
+        }
+    }
+}
+
+/// Search a whole chunk for the keyword list. Hits starting in the overlap
+/// tail are left to the next chunk.
+fn scan_chunk_keywords(
+    search: &KeywordSearch,
+    job: &ScanJob,
+    effective_valid: u64,
+    run_id: &str,
+    meta_tx: &Sender<MetadataEvent>,
+) {
+    let context = search.context_bytes as u64;
+    for found in search.keywords.scan(&job.data) {
+        if found.start as u64 >= effective_valid {
+            continue;
+        }
+        let global_offset = job.chunk.start + found.start as u64;
+        let length = (found.end - found.start) as u64;
+        // Keep UTF-16 context aligned with the hit's code units
+        let mut before = context.min(global_offset);
+        if found.encoding.starts_with("utf-16") {
+            before -= before % 2;
+        }
+        let end = (global_offset + length + context).min(search.evidence.len());
+        let (context_start, bytes) = keyword_context(search, job, global_offset - before, end);
+        let hit = KeywordHit {
+            run_id: run_id.to_string(),
+            term: search.keywords.terms()[found.term].clone(),
+            encoding: found.encoding.to_string(),
+            global_offset,
+            length,
+            context_start,
+            context_hex: hex::encode(&bytes),
+            context_text: keywords::render_context(&bytes, found.encoding),
+            carved_path: None,
+        };
+        if let Err(err) = meta_tx.send(MetadataEvent::KeywordHit(hit)) {
+            warn!("metadata channel closed while sending keyword hit: {err}");
+            break;
+        }
+    }
+}
+
+/// Look up the aligned blocks starting in the chunk's valid region in the
+/// hash database.
+fn scan_chunk_blocks(
+    db: &HashDb,
+    job: &ScanJob,
+    effective_valid: u64,
+    partitions: &[Partition],
+    run_id: &str,
+    meta_tx: &Sender<MetadataEvent>,
+) {
+    let valid_end = job.chunk.start + effective_valid;
+    let block_size = db.block_size() as u64;
+    for run in db.match_blocks(job.chunk.start, &job.data, valid_end, partitions) {
+        let length = run.block_count * block_size;
+        let record = BlockHashMatch {
+            run_id: run_id.to_string(),
+            source_file: db.file_path(run.file_id).to_string(),
+            global_start: run.global_start,
+            length,
+            block_index: run.block_index,
+            block_count: run.block_count,
+        };
+        // The blocks either side of the run are looked up by the previous
+        // and next chunks when they fall outside this one's valid region
+        let chunk_run = ChunkRun {
+            record,
+            open_before: job.chunk.start > 0 && run.global_start < job.chunk.start + block_size,
+            open_after: run.global_start + length >= valid_end,
+        };
+        if let Err(err) = meta_tx.send(MetadataEvent::BlockHashMatch(chunk_run)) {
+            warn!("metadata channel closed while sending block hash match: {err}");
+            break;
+        }
+    }
+}
+
+/// Bytes `[start, end)` from the chunk when it holds them, else from the
+/// evidence; falls back to the part inside the chunk if that read fails.
+fn keyword_context(search: &KeywordSearch, job: &ScanJob, start: u64, end: u64) -> (u64, Vec<u8>) {
+    let chunk_end = job.chunk.start + job.data.len() as u64;
+    if start < job.chunk.start || end > chunk_end {
+        let mut buf = vec![0u8; (end - start) as usize];
+        match search.evidence.read_at(start, &mut buf) {
+            Ok(n) => {
+                buf.truncate(n);
+                return (start, buf);
+            }
+            Err(err) => debug!("keyword context read at {start} failed: {err}"),
+        }
+    }
+    let start = start.max(job.chunk.start);
+    let end = end.min(chunk_end);
+    let local = (start - job.chunk.start) as usize..(end - job.chunk.start) as usize;
+    (start, job.data[local].to_vec())
+}
+
+/// Evaluate YARA rules over a carved file on disk.
+fn scan_carved_yara(
+    rules: &YaraRules,
+    file: &CarvedFile,
+    path: &std::path::Path,
+    meta_tx: &Sender<MetadataEvent>,
+) {
+    let data = match std::fs::read(path) {
+        Ok(data) => data,
+        Err(err) => {
+            debug!("yara: cannot read {}: {err}", path.display());
+            return;
+        }
+    };
+    for rule_match in rules.scan(&data) {
+        let (string_id, offset) = match rule_match.first_string {
+            Some((id, offset)) => (Some(id), offset as u64),
+            None => (None, 0),
+        };
+        let record = YaraMatch {
+            run_id: file.run_id.clone(),
+            rule: rule_match.rule,
+            tags: rule_match.tags,
+            // Offsets inside an embedded file do not map to the evidence
+            global_offset: if file.depth == 0 {
+                file.global_start + offset
+            } else {
+                file.global_start
+            },
+            string_id,
+            carved_path: Some(file.path.clone()),
+        };
+        if let Err(err) = meta_tx.send(MetadataEvent::YaraMatch(record)) {
+            warn!("metadata channel closed while sending yara match: {err}");
+            break;
+        }
+    }
+}
+
+/// Report the `--fuzzy-match` references the carved file is similar to.
+fn match_fuzzy(matcher: &FuzzyMatcher, file: &CarvedFile, meta_tx: &Sender<MetadataEvent>) {
+    let digests = FuzzyDigests {
+        ssdeep: file.ssdeep.clone(),
+        tlsh: file.tlsh.clone(),
+    };
+    for found in matcher.matches(&digests) {
+        let record = FuzzyMatch {
+            run_id: file.run_id.clone(),
+            carved_path: file.path.clone(),
+            file_type: file.file_type.clone(),
+            global_start: file.global_start,
+            algorithm: found.reference.algorithm.as_str().to_string(),
+            score: found.score,
+            reference_name: found.reference.name.clone(),
+            reference_digest: found.reference.digest.clone(),
+        };
+        if let Err(err) = meta_tx.send(MetadataEvent::FuzzyMatch(record)) {
+            warn!("metadata channel closed while sending fuzzy match: {err}");
+            break;
+        }
+    }
+}
+
+/// Report the members named in a carved archive's directory or headers.
+fn list_archive_entries(file: &CarvedFile, path: &Path, meta_tx: &Sender<MetadataEvent>) {
+    if !archive::is_listable(&file.file_type) {
+        return;
+    }
+    let entries = match archive::list_file(path, &file.file_type) {
+        Ok(entries) => entries,
+        Err(err) => {
+            debug!("archive listing failed for {}: {err}", path.display());
+            return;
+        }
+    };
+    for (index, entry) in entries.into_iter().enumerate() {
+        let record = ArchiveEntry {
+            run_id: file.run_id.clone(),
+            carved_path: file.path.clone(),
+            file_type: file.file_type.clone(),
+            index: index as u64,
+            name: entry.name,
+            compressed_size: entry.compressed_size,
+            uncompressed_size: entry.uncompressed_size,
+            modified: entry.modified,
+            crc32: entry.crc32,
+            encrypted: entry.encrypted,
+            compression_method: entry.compression_method,
+        };
+        if let Err(err) = meta_tx.send(MetadataEvent::ArchiveEntry(record)) {
+            warn!("metadata channel closed while sending archive entry: {err}");
+            break;
+        }
+    }
+}
+
+/// Count and record the constant runs that fall inside the chunk's valid
+/// region; the overlap tail belongs to the next chunk.
+fn record_skipped(
+    runs: &[blank::ConstantRun],
+    chunk_start: u64,
+    valid_len: usize,
+    bytes_skipped: &AtomicU64,
+    skipped_ranges: &Mutex<Vec<SkippedRange>>,
+) {
+    let ranges: Vec<SkippedRange> = runs
+        .iter()
+        .filter(|run| run.start < valid_len)
+        .map(|run| SkippedRange {
+            global_start: chunk_start + run.start as u64,
+            global_end: chunk_start + run.end.min(valid_len) as u64,
+            fill: run.fill,
+        })
+        .collect();
+    if ranges.is_empty() {
+        return;
+    }
+    let total: u64 = ranges
+        .iter()
+        .map(|range| range.global_end - range.global_start)
+        .sum();
+    bytes_skipped.fetch_add(total, Ordering::Relaxed);
+    match skipped_ranges.lock() {
+        Ok(mut guard) => guard.extend(ranges),
+        Err(_) => warn!("skipped range list lock poisoned"),
+    }
+}
+
 /// Spawn file carving worker threads
 pub fn spawn_carve_workers(
     workers: usize,
     registry: Arc<CarveRegistry>,
     evidence: Arc<dyn EvidenceSource>,
-    run_id: String,
-    run_output_dir: PathBuf,
     rx: Receiver<NormalizedHit>,
     meta_tx: Sender<MetadataEvent>,
+    ctx: Arc<CarveContext>,
     files_carved: Arc<AtomicU64>,
-    enable_sqlite_page_recovery: bool,
-    max_files: Option<u64>,
     carve_errors: Arc<AtomicU64>,
     sqlite_errors: Arc<AtomicU64>,
 ) -> Vec<thread::JoinHandle<()>> {
@@ -225,28 +664,26 @@ pub fn spawn_carve_workers(
     for _ in 0..worker_count {
         let registry = registry.clone();
         let evidence = evidence.clone();
-        let run_id = run_id.clone();
-        let run_output_dir = run_output_dir.clone();
+        let ctx = ctx.clone();
         let rx = rx.clone();
         let meta_tx = meta_tx.clone();
         let files_carved = files_carved.clone();
-        let max_files = max_files;
         let carve_errors = carve_errors.clone();
         let sqlite_errors = sqlite_errors.clone();
 
         handles.push(thread::spawn(move || {
-            let carved_root = run_output_dir.join("carved");
-            let ctx = ExtractionContext {
-                run_id: &run_id,
+            let carved_root = ctx.run_output_dir.join("carved");
+            let extraction = ExtractionContext {
+                run_id: &ctx.run_id,
                 output_root: &carved_root,
                 evidence: evidence.as_ref(),
             };
 
-            for hit in rx {
-                if let Some(limit) = max_files {
-                    if files_carved.load(Ordering::Relaxed) >= limit {
-                        break;
-                    }
+            'hits: for hit in rx {
+                if let Some(limit) = ctx.max_files
+                    && files_carved.load(Ordering::Relaxed) >= limit
+                {
+                    break;
                 }
                 let handler = match registry.get(&hit.file_type_id) {
                     Some(handler) => handler,
@@ -256,30 +693,101 @@ pub fn spawn_carve_workers(
                     }
                 };
 
-                match handler.process_hit(&hit, &ctx) {
-                    Ok(Some(file)) => {
-                        let new_total = files_carved.fetch_add(1, Ordering::Relaxed) + 1;
-                        let path = carved_root.join(&file.path);
-                        let file_type = file.file_type.clone();
-                        let rel_path = file.path.clone();
-                        if let Err(err) = meta_tx.send(MetadataEvent::File(file)) {
-                            warn!("metadata channel closed while sending carved file: {err}");
+                match handler.process_hit(&hit, &extraction) {
+                    Ok(Some(mut file)) => {
+                        attribute_partition(&mut file, &ctx.partitions);
+                        if let Some(map) = &ctx.allocation {
+                            attribute_allocation(&mut file, map);
                         }
+                        let origin = ctx
+                            .file_index
+                            .as_deref()
+                            .and_then(|index| index.locate(file.global_start));
+                        set_fs_location(
+                            origin,
+                            &mut file.containing_fs_path,
+                            &mut file.containing_inode,
+                            &mut file.offset_in_file,
+                        );
+                        flag_unreadable(&mut file, &evidence.unreadable_ranges());
 
-                        // Process SQLite files for browser artifacts
-                        if file_type == "sqlite" {
-                            process_sqlite_artifacts(
-                                &path,
-                                &run_id,
-                                &rel_path,
-                                &meta_tx,
-                                enable_sqlite_page_recovery,
-                                &sqlite_errors,
-                            );
-                        }
-                        if let Some(limit) = max_files {
-                            if new_total >= limit {
-                                break;
+                        // Files carved from inside a container queue up behind it
+                        let mut pending = VecDeque::from([file]);
+                        while let Some(mut file) = pending.pop_front() {
+                            let new_total = files_carved.fetch_add(1, Ordering::Relaxed) + 1;
+                            let path = carved_root.join(&file.path);
+                            let mut removed = false;
+                            if let Some(validation) = ctx.validation {
+                                removed = validate_carved_file(
+                                    &mut file,
+                                    &path,
+                                    validation,
+                                    &ctx.validation_pass,
+                                    &ctx.validation_fail,
+                                );
+                            }
+                            if let Some(known) = &ctx.known_files {
+                                removed |= match_known_files(&mut file, &path, removed, known);
+                            }
+                            if !removed {
+                                removed =
+                                    mark_duplicate(&mut file, &carved_root, &path, &ctx.dedup);
+                            }
+                            if let Some(rules) = &ctx.yara
+                                && !removed
+                            {
+                                scan_carved_yara(rules, &file, &path, &meta_tx);
+                            }
+                            if let Some(matcher) = &ctx.fuzzy {
+                                match_fuzzy(matcher, &file, &meta_tx);
+                            }
+                            if !removed && file.is_encrypted() {
+                                ctx.encrypted_files.fetch_add(1, Ordering::Relaxed);
+                            }
+                            // A duplicate's members were listed and carved
+                            // from the first copy
+                            if !removed && !file.is_duplicate {
+                                list_archive_entries(&file, &path, &meta_tx);
+                            }
+                            if let Some(recursion) = &ctx.recursion
+                                && !removed
+                                && !file.is_duplicate
+                            {
+                                pending.extend(carve_embedded(
+                                    &file,
+                                    &path,
+                                    recursion,
+                                    &registry,
+                                    &ctx.run_id,
+                                    &carved_root,
+                                ));
+                            }
+                            let file_type = file.file_type.clone();
+                            let rel_path = file.path.clone();
+                            if let Err(err) = meta_tx.send(MetadataEvent::File(Box::new(file))) {
+                                warn!("metadata channel closed while sending carved file: {err}");
+                            }
+
+                            // Process SQLite files for browser artifacts
+                            if file_type == "sqlite" && !removed {
+                                process_sqlite_artifacts(
+                                    &path,
+                                    &ctx.run_id,
+                                    &rel_path,
+                                    &meta_tx,
+                                    ctx.enable_sqlite_page_recovery,
+                                    &sqlite_errors,
+                                    origin,
+                                );
+                            }
+                            if let Some(limit) = ctx.max_files
+                                && new_total >= limit
+                            {
+                                // Embedded files past the limit are not kept
+                                for file in pending {
+                                    let _ = std::fs::remove_file(carved_root.join(&file.path));
+                                }
+                                break 'hits;
                             }
                         }
                     }
@@ -296,6 +804,272 @@ pub fn spawn_carve_workers(
     handles
 }
 
+/// Decode the members of a carved container and carve the files found in
+/// them, for `--recursive-carving`. Each member is signature-scanned as a
+/// small evidence image of its own and its files are written under
+/// `<container path>.d/<member number>/`. Embedded files take the evidence
+/// location fields of the container.
+fn carve_embedded(
+    parent: &CarvedFile,
+    path: &Path,
+    recursion: &RecursionConfig,
+    registry: &CarveRegistry,
+    run_id: &str,
+    carved_root: &Path,
+) -> Vec<CarvedFile> {
+    let mut children = Vec::new();
+    if parent.depth >= recursion.max_depth || !embedded::is_container(&parent.file_type) {
+        return children;
+    }
+    let mut number = 0usize;
+    let mut visit = |member: Member| {
+        number += 1;
+        let len = member.data.len() as u64;
+        let chunk = ScanChunk {
+            id: 0,
+            start: 0,
+            length: len,
+            valid_length: len,
+        };
+        let hits = recursion.scanner.scan_chunk(&chunk, &member.data);
+        if hits.is_empty() {
+            return;
+        }
+        let prefix = PathBuf::from(format!("{}.d", parent.path)).join(number.to_string());
+        let output_root = carved_root.join(&prefix);
+        let source = MemorySource::new(member.data);
+        let ctx = ExtractionContext {
+            run_id,
+            output_root: &output_root,
+            evidence: &source,
+        };
+        for hit in hits {
+            let Some(handler) = registry.get(&hit.file_type_id) else {
+                continue;
+            };
+            let hit = NormalizedHit {
+                global_offset: hit.local_offset,
+                file_type_id: hit.file_type_id,
+                pattern_id: hit.pattern_id,
+            };
+            match handler.process_hit(&hit, &ctx) {
+                Ok(Some(mut child)) => {
+                    // The handler saw the member as its evidence; the
+                    // evidence-based fields are inherited from the parent
+                    // rather than attributed from member offsets
+                    child.path = prefix.join(&child.path).to_string_lossy().into_owned();
+                    child.parent_path = Some(parent.path.clone());
+                    child.parent_id = Some(member.name.clone());
+                    child.offset_in_parent = Some(child.global_start);
+                    child.depth = parent.depth + 1;
+                    child.global_start = parent.global_start;
+                    child.global_end = parent.global_end;
+                    child.partition_index = parent.partition_index;
+                    child.partition_type = parent.partition_type.clone();
+                    child.partition_offset = parent.partition_offset;
+                    child.allocation_status = parent.allocation_status.clone();
+                    child.containing_fs_path = parent.containing_fs_path.clone();
+                    child.containing_inode = parent.containing_inode;
+                    child.offset_in_file = parent.offset_in_file;
+                    children.push(child);
+                }
+                Ok(None) => {}
+                Err(err) => debug!(
+                    "carve error at offset {} of {} member {}: {err}",
+                    hit.global_offset, parent.path, member.name
+                ),
+            }
+        }
+    };
+    if let Err(err) = embedded::visit_file(path, &parent.file_type, recursion.max_size, &mut visit)
+    {
+        debug!("recursive carving skipped {}: {err}", parent.path);
+    }
+    children
+}
+
+/// Record which partition the carved file starts in, if any.
+fn attribute_partition(file: &mut CarvedFile, partitions: &[Partition]) {
+    if let Some(found) = partition::partition_for_offset(partitions, file.global_start) {
+        file.partition_index = Some(found.index);
+        file.partition_type = Some(found.type_id.clone());
+        file.partition_offset = Some(file.global_start - found.start);
+    }
+}
+
+fn attribute_allocation(file: &mut CarvedFile, allocation: &AllocationMap) {
+    let end = file.global_end.max(file.global_start).saturating_add(1);
+    let status = allocation.status(file.global_start, end);
+    file.allocation_status = Some(status.as_str().to_string());
+}
+
+/// Fill the `containing_*` fields of a record from its live-file location.
+fn set_fs_location(
+    location: Option<FileLocation<'_>>,
+    path: &mut Option<String>,
+    inode: &mut Option<u64>,
+    offset_in_file: &mut Option<u64>,
+) {
+    if let Some(location) = location {
+        *path = Some(location.path.to_string());
+        *inode = Some(location.inode);
+        *offset_in_file = Some(location.offset_in_file);
+    }
+}
+
+/// Note on the carved file how much of it came from zero-filled sectors.
+fn flag_unreadable(file: &mut CarvedFile, ranges: &[(u64, u64)]) {
+    if file.size == 0 {
+        return;
+    }
+    let end = file.global_end.saturating_add(1);
+    // Ranges are sorted and disjoint: skip those ending before the file.
+    let first = ranges.partition_point(|&(_, stop)| stop <= file.global_start);
+    let overlap: u64 = ranges[first..]
+        .iter()
+        .take_while(|&&(start, _)| start < end)
+        .map(|&(start, stop)| stop.min(end) - start.max(file.global_start))
+        .sum();
+    if overlap > 0 {
+        file.errors.push(format!(
+            "{overlap} bytes overlap unreadable sectors (zero-filled)"
+        ));
+    }
+}
+
+/// Run deep validation on a carved file and record the verdict on it.
+/// Returns true when the file was deleted because it failed validation.
+fn validate_carved_file(
+    file: &mut CarvedFile,
+    path: &std::path::Path,
+    cfg: ValidationConfig,
+    validation_pass: &AtomicU64,
+    validation_fail: &AtomicU64,
+) -> bool {
+    let outcome = validate::validate_file(path, &file.file_type);
+    match &outcome {
+        ValidationOutcome::Passed => {
+            validation_pass.fetch_add(1, Ordering::Relaxed);
+        }
+        ValidationOutcome::Failed(reason) => {
+            validation_fail.fetch_add(1, Ordering::Relaxed);
+            debug!("validation failed for {}: {reason}", file.path);
+        }
+        ValidationOutcome::Unsupported => {}
+    }
+    file.validation_status = Some(outcome.status().to_string());
+    file.validation_reason = outcome.reason().map(str::to_string);
+
+    if !cfg.remove_invalid || !matches!(outcome, ValidationOutcome::Failed(_)) {
+        return false;
+    }
+    match std::fs::remove_file(path) {
+        Ok(()) => {
+            file.errors
+                .push("removed after failed validation".to_string());
+            true
+        }
+        Err(err) => {
+            warn!("failed to remove invalid file {}: {err}", path.display());
+            false
+        }
+    }
+}
+
+/// Look the carved file up in the known-good/known-bad hash sets. Known-bad
+/// matches are logged as alerts; known-good files are deleted when
+/// `delete_known_good` is set. Returns whether the file was removed.
+fn match_known_files(
+    file: &mut CarvedFile,
+    path: &std::path::Path,
+    removed: bool,
+    known: &KnownFileConfig,
+) -> bool {
+    let mut hashes = FileHashes::from_hex(file.md5.as_deref(), file.sha256.as_deref());
+    if known.sets.needs_sha1() && !removed {
+        match hashset::sha1_file(path) {
+            Ok(digest) => hashes.sha1 = Some(digest),
+            Err(err) => debug!("sha1 of {} failed: {err}", path.display()),
+        }
+    }
+    let Some((status, set)) = known.sets.lookup(&hashes) else {
+        return false;
+    };
+    file.hash_match = Some(status.as_str().to_string());
+    file.hash_set = Some(set.to_string());
+
+    match status {
+        HashMatch::KnownBad => {
+            known.known_bad_files.fetch_add(1, Ordering::Relaxed);
+            warn!(
+                alert = "known_bad",
+                "known-bad file carved: {} at offset {} matches hash set {set}",
+                file.path,
+                file.global_start
+            );
+            false
+        }
+        HashMatch::KnownGood => {
+            known.known_good_files.fetch_add(1, Ordering::Relaxed);
+            if !known.delete_known_good || removed {
+                return false;
+            }
+            match std::fs::remove_file(path) {
+                Ok(()) => {
+                    file.errors.push("removed as known-good".to_string());
+                    true
+                }
+                Err(err) => {
+                    warn!("failed to remove known-good file {}: {err}", path.display());
+                    false
+                }
+            }
+        }
+    }
+}
+
+/// Flag the carved file when the same content was already carved in this
+/// run, then delete it or hardlink it to the first copy as configured.
+/// Returns whether the file was removed.
+fn mark_duplicate(
+    file: &mut CarvedFile,
+    carved_root: &std::path::Path,
+    path: &std::path::Path,
+    dedup: &DedupTracker,
+) -> bool {
+    let Some(first) = file
+        .sha256
+        .as_deref()
+        .and_then(|sha256| dedup.first_copy(sha256, &file.path))
+    else {
+        return false;
+    };
+    let first_path = carved_root.join(&first);
+    file.is_duplicate = true;
+    file.duplicate_of = Some(first);
+
+    let action = dedup.action();
+    if action == DuplicateAction::Keep {
+        return false;
+    }
+    if let Err(err) = std::fs::remove_file(path) {
+        warn!("failed to remove duplicate file {}: {err}", path.display());
+        return false;
+    }
+    if action == DuplicateAction::Hardlink {
+        match std::fs::hard_link(&first_path, path) {
+            Ok(()) => return false,
+            Err(err) => warn!(
+                "failed to hardlink {} to {}: {err}",
+                path.display(),
+                first_path.display()
+            ),
+        }
+    }
+    file.errors.push("removed as duplicate".to_string());
+    true
+}
+
 /// Process SQLite files for browser artifacts (history, cookies, downloads)
 fn process_sqlite_artifacts(
     path: &std::path::Path,
@@ -304,6 +1078,7 @@ fn process_sqlite_artifacts(
     meta_tx: &Sender<MetadataEvent>,
     enable_page_recovery: bool,
     sqlite_errors: &Arc<AtomicU64>,
+    origin: Option<FileLocation<'_>>,
 ) {
     // Extract browser history
     let mut records =
@@ -327,7 +1102,13 @@ fn process_sqlite_artifacts(
         }
     }
 
-    for record in records {
+    for mut record in records {
+        set_fs_location(
+            origin,
+            &mut record.containing_fs_path,
+            &mut record.containing_inode,
+            &mut record.offset_in_file,
+        );
         if let Err(err) = meta_tx.send(MetadataEvent::History(record)) {
             warn!("metadata channel closed while sending history record: {err}");
             return;
@@ -337,7 +1118,13 @@ fn process_sqlite_artifacts(
     // Extract browser cookies
     match crate::parsers::sqlite_db::extract_browser_cookies(path, run_id, rel_path) {
         Ok(records) => {
-            for record in records {
+            for mut record in records {
+                set_fs_location(
+                    origin,
+                    &mut record.containing_fs_path,
+                    &mut record.containing_inode,
+                    &mut record.offset_in_file,
+                );
                 if let Err(err) = meta_tx.send(MetadataEvent::Cookie(record)) {
                     warn!("metadata channel closed while sending cookie record: {err}");
                     return;
@@ -353,7 +1140,13 @@ fn process_sqlite_artifacts(
     // Extract browser downloads
     match crate::parsers::sqlite_db::extract_browser_downloads(path, run_id, rel_path) {
         Ok(records) => {
-            for record in records {
+            for mut record in records {
+                set_fs_location(
+                    origin,
+                    &mut record.containing_fs_path,
+                    &mut record.containing_inode,
+                    &mut record.offset_in_file,
+                );
                 if let Err(err) = meta_tx.send(MetadataEvent::Download(record)) {
                     warn!("metadata channel closed while sending download record: {err}");
                     return;
@@ -375,6 +1168,7 @@ pub fn spawn_string_workers(
     meta_tx: Sender<MetadataEvent>,
     artefacts_found: Arc<AtomicU64>,
     scan_cfg: ArtefactScanConfig,
+    file_index: Option<Arc<FileIndex>>,
 ) -> Vec<thread::JoinHandle<()>> {
     let mut handles = Vec::new();
     let worker_count = workers.max(1);
@@ -384,6 +1178,8 @@ pub fn spawn_string_workers(
         let meta_tx = meta_tx.clone();
         let run_id = run_id.clone();
         let artefacts_found = artefacts_found.clone();
+        let file_index = file_index.clone();
+        let scan_cfg = scan_cfg.clone();
 
         handles.push(thread::spawn(move || {
             for job in rx {
@@ -400,10 +1196,18 @@ pub fn spawn_string_workers(
                         span.local_start,
                         span.flags,
                         slice,
-                        scan_cfg,
+                        &scan_cfg,
                     );
                     artefacts_found.fetch_add(artefacts.len() as u64, Ordering::Relaxed);
-                    for artefact in artefacts {
+                    for mut artefact in artefacts {
+                        if let Some(index) = &file_index {
+                            set_fs_location(
+                                index.locate(artefact.global_start),
+                                &mut artefact.containing_fs_path,
+                                &mut artefact.containing_inode,
+                                &mut artefact.offset_in_file,
+                            );
+                        }
                         if let Err(err) = meta_tx.send(MetadataEvent::String(artefact)) {
                             warn!("metadata channel closed while sending string artefact: {err}");
                             break;
@@ -0,0 +1,199 @@
+//! # Scan Ranges Module
+//!
+//! Byte-range selection for partial scans: an offset window, a ranges file
+//! (CSV of `start,length` or a GNU ddrescue mapfile) and the helpers that
+//! combine them into the sorted `[start, end)` list the chunk scheduler uses.
+
+use std::fs;
+use std::path::Path;
+
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum RangesError {
+    #[error("io error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("line {line}: {message}")]
+    Parse { line: usize, message: String },
+}
+
+/// Load `[start, end)` ranges from a file. Two formats are accepted:
+///
+/// - CSV lines of `start,length` (decimal or `0x` hex, an optional header
+///   and `#` comments are ignored);
+/// - a GNU ddrescue mapfile, of which only finished (`+`) blocks are used.
+///
+/// The result is sorted and merged.
+pub fn load_ranges_file(path: &Path) -> Result<Vec<(u64, u64)>, RangesError> {
+    let contents = fs::read_to_string(path)?;
+    parse_ranges(&contents)
+}
+
+/// Parse the contents of a ranges file; see [`load_ranges_file`].
+pub fn parse_ranges(contents: &str) -> Result<Vec<(u64, u64)>, RangesError> {
+    let mut ranges = Vec::new();
+    let mut seen_data = false;
+    for (idx, raw) in contents.lines().enumerate() {
+        let line_no = idx + 1;
+        let line = raw.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let fields: Vec<&str> = if line.contains(',') {
+            line.split(',').map(str::trim).collect()
+        } else {
+            line.split_whitespace().collect()
+        };
+        let parse_err = |message: String| RangesError::Parse {
+            line: line_no,
+            message,
+        };
+
+        // ddrescue block line: `pos size status`
+        if fields.len() == 3 && is_ddrescue_status(fields[2]) {
+            seen_data = true;
+            if fields[2] != "+" {
+                continue;
+            }
+            let start = parse_number(fields[0]).map_err(parse_err)?;
+            let size = parse_number(fields[1]).map_err(parse_err)?;
+            push_range(&mut ranges, start, size, line_no)?;
+            continue;
+        }
+        // ddrescue current_pos line: `pos status [pass]`
+        if (2..=3).contains(&fields.len()) && is_ddrescue_status(fields[1]) {
+            continue;
+        }
+        if fields.len() != 2 {
+            return Err(parse_err(format!(
+                "expected `start,length`, got {} fields",
+                fields.len()
+            )));
+        }
+        let start = match parse_number(fields[0]) {
+            Ok(start) => start,
+            // A header row before the first data line.
+            Err(_) if !seen_data => {
+                seen_data = true;
+                continue;
+            }
+            Err(message) => return Err(parse_err(message)),
+        };
+        seen_data = true;
+        let length = parse_number(fields[1]).map_err(parse_err)?;
+        push_range(&mut ranges, start, length, line_no)?;
+    }
+    Ok(merge_ranges(ranges))
+}
+
+/// Sort `[start, end)` ranges, drop empty ones and merge those that touch.
+pub fn merge_ranges(mut ranges: Vec<(u64, u64)>) -> Vec<(u64, u64)> {
+    ranges.retain(|&(start, end)| end > start);
+    ranges.sort_unstable();
+    let mut merged: Vec<(u64, u64)> = Vec::with_capacity(ranges.len());
+    for (start, end) in ranges {
+        match merged.last_mut() {
+            Some(last) if last.1 >= start => last.1 = last.1.max(end),
+            _ => merged.push((start, end)),
+        }
+    }
+    merged
+}
+
+/// Intersection of two sorted, merged range lists.
+pub fn intersect_ranges(a: &[(u64, u64)], b: &[(u64, u64)]) -> Vec<(u64, u64)> {
+    let mut out = Vec::new();
+    let (mut i, mut j) = (0usize, 0usize);
+    while i < a.len() && j < b.len() {
+        let start = a[i].0.max(b[j].0);
+        let end = a[i].1.min(b[j].1);
+        if end > start {
+            out.push((start, end));
+        }
+        if a[i].1 < b[j].1 {
+            i += 1;
+        } else {
+            j += 1;
+        }
+    }
+    out
+}
+
+/// The part of `ranges` that lies before `next_offset`, i.e. what a scan
+/// that stopped there has covered.
+pub fn completed_ranges(ranges: &[(u64, u64)], next_offset: u64) -> Vec<(u64, u64)> {
+    ranges
+        .iter()
+        .filter(|range| range.0 < next_offset)
+        .map(|&(start, end)| (start, end.min(next_offset)))
+        .collect()
+}
+
+fn is_ddrescue_status(field: &str) -> bool {
+    matches!(field, "?" | "*" | "/" | "-" | "+" | "F" | "G")
+}
+
+fn parse_number(field: &str) -> Result<u64, String> {
+    let parsed = match field
+        .strip_prefix("0x")
+        .or_else(|| field.strip_prefix("0X"))
+    {
+        Some(hex) => u64::from_str_radix(hex, 16),
+        None => field.parse::<u64>(),
+    };
+    parsed.map_err(|_| format!("invalid number `{field}`"))
+}
+
+fn push_range(
+    ranges: &mut Vec<(u64, u64)>,
+    start: u64,
+    length: u64,
+    line: usize,
+) -> Result<(), RangesError> {
+    let end = start
+        .checked_add(length)
+        .ok_or_else(|| RangesError::Parse {
+            line,
+            message: format!("range {start}+{length} overflows"),
+        })?;
+    ranges.push((start, end));
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_csv_with_header_and_hex() {
+        let ranges =
+            parse_ranges("start,length\n0x1000,512\n# note\n0,100\n80,40\n").expect("parse");
+        assert_eq!(ranges, vec![(0, 120), (4096, 4608)]);
+
+        let err = parse_ranges("0,100\nabc,5\n").expect_err("bad number");
+        assert!(err.to_string().starts_with("line 2:"));
+    }
+
+    #[test]
+    fn parses_ddrescue_mapfile_finished_blocks() {
+        let map = "# Mapfile. Created by GNU ddrescue\n\
+                   # current_pos  current_status  current_pass\n\
+                   0x00003000     +               1\n\
+                   #      pos        size  status\n\
+                   0x00000000  0x00001000  +\n\
+                   0x00001000  0x00000200  -\n\
+                   0x00001200  0x00001E00  +\n\
+                   0x00003000  0x00001000  ?\n";
+        let ranges = parse_ranges(map).expect("parse");
+        assert_eq!(ranges, vec![(0, 0x1000), (0x1200, 0x3000)]);
+    }
+
+    #[test]
+    fn intersects_and_tracks_completed_ranges() {
+        let ranges = intersect_ranges(&[(0, 100), (200, 300)], &[(50, 250)]);
+        assert_eq!(ranges, vec![(50, 100), (200, 250)]);
+        assert_eq!(completed_ranges(&ranges, 220), vec![(50, 100), (200, 220)]);
+        assert_eq!(completed_ranges(&ranges, 10), Vec::new());
+    }
+}
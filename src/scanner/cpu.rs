@@ -1,70 +1,208 @@
+use std::collections::HashMap;
+
+use aho_corasick::{AhoCorasick, AhoCorasickKind, MatchKind};
 use anyhow::{Result, anyhow};
-use memchr::memchr;
 
 use crate::chunk::ScanChunk;
 use crate::config::Config;
+use crate::scanner::pattern::{SignaturePattern, compile_patterns};
 use crate::scanner::{Hit, SignatureScanner};
 
-#[derive(Debug, Clone)]
-struct Pattern {
-    id: String,
-    file_type_id: String,
-    bytes: Vec<u8>,
-}
-
+/// Single-pass CPU scanner: the exact bytes of every header pattern are
+/// compiled into one Aho-Corasick automaton, so each chunk is read once
+/// however many patterns are configured. Wildcards, anchor offsets and
+/// secondary conditions are verified at each candidate.
 pub struct CpuScanner {
-    patterns: Vec<Pattern>,
+    automaton: Option<AhoCorasick>,
+    patterns: Vec<SignaturePattern>,
+    /// `(pattern index, offset from hit)` for every pattern searching for
+    /// the same bytes, indexed by automaton pattern id.
+    slots: Vec<Vec<(usize, u64)>>,
 }
 
 impl CpuScanner {
     pub fn new(cfg: &Config) -> Result<Self> {
-        let mut patterns = Vec::new();
-        for file_type in &cfg.file_types {
-            for pat in &file_type.header_patterns {
-                let bytes = hex::decode(pat.hex.trim())
-                    .map_err(|e| anyhow!("invalid hex pattern {}: {e}", pat.id))?;
-                if bytes.is_empty() {
-                    continue;
-                }
-                patterns.push(Pattern {
-                    id: pat.id.clone(),
-                    file_type_id: file_type.id.clone(),
-                    bytes,
-                });
-            }
+        Self::from_patterns(compile_patterns(cfg)?)
+    }
+
+    pub fn from_patterns(patterns: Vec<SignaturePattern>) -> Result<Self> {
+        let mut literals: Vec<Vec<u8>> = Vec::new();
+        let mut slots: Vec<Vec<(usize, u64)>> = Vec::new();
+        let mut by_bytes: HashMap<Vec<u8>, usize> = HashMap::new();
+        for (idx, pattern) in patterns.iter().enumerate() {
+            let (offset, bytes) = pattern.search_literal();
+            let slot = *by_bytes.entry(bytes.to_vec()).or_insert_with(|| {
+                literals.push(bytes.to_vec());
+                slots.push(Vec::new());
+                literals.len() - 1
+            });
+            slots[slot].push((idx, offset));
         }
-        Ok(Self { patterns })
+
+        let automaton = if literals.is_empty() {
+            None
+        } else {
+            // Standard semantics are required for overlapping search, which
+            // reports every pattern at every offset (`00 00` and a longer
+            // pattern starting with it both match).
+            Some(
+                AhoCorasick::builder()
+                    .match_kind(MatchKind::Standard)
+                    .kind(Some(AhoCorasickKind::DFA))
+                    .build(&literals)
+                    .map_err(|e| anyhow!("failed to build signature automaton: {e}"))?,
+            )
+        };
+        Ok(Self {
+            automaton,
+            patterns,
+            slots,
+        })
     }
 }
 
 impl SignatureScanner for CpuScanner {
     fn scan_chunk(&self, chunk: &ScanChunk, data: &[u8]) -> Vec<Hit> {
         let mut hits = Vec::new();
-        for pattern in &self.patterns {
-            if pattern.bytes.is_empty() {
-                continue;
-            }
-            let first = pattern.bytes[0];
-            let mut pos = 0usize;
-            while pos < data.len() {
-                let found = memchr(first, &data[pos..]);
-                let idx = match found {
-                    Some(i) => pos + i,
-                    None => break,
+        let Some(automaton) = &self.automaton else {
+            return hits;
+        };
+        for found in automaton.find_overlapping_iter(data) {
+            for &(idx, offset) in &self.slots[found.pattern().as_usize()] {
+                // Anchors found before `offset` bytes into the chunk belong
+                // to a file starting in the previous chunk, which sees them
+                // through its overlap.
+                let Some(start) = (found.start() as u64).checked_sub(offset) else {
+                    continue;
                 };
-                if idx + pattern.bytes.len() <= data.len()
-                    && data[idx..idx + pattern.bytes.len()] == pattern.bytes[..]
-                {
-                    hits.push(Hit {
-                        chunk_id: chunk.id,
-                        local_offset: idx as u64,
-                        pattern_id: pattern.id.clone(),
-                        file_type_id: pattern.file_type_id.clone(),
-                    });
+                let pattern = &self.patterns[idx];
+                if !pattern.is_literal() && !pattern.matches_at(&data[start as usize..]) {
+                    continue;
                 }
-                pos = idx + 1;
+                hits.push(Hit {
+                    chunk_id: chunk.id,
+                    local_offset: start,
+                    pattern_id: pattern.id.clone(),
+                    file_type_id: pattern.file_type_id.clone(),
+                });
             }
         }
         hits
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{self, PatternCondition, PatternConfig};
+
+    /// Reference result: every offset where a pattern matches, found naively.
+    fn naive_hits(patterns: &[SignaturePattern], data: &[u8]) -> Vec<(u64, String)> {
+        let mut out = Vec::new();
+        for pattern in patterns {
+            for idx in 0..data.len() {
+                if pattern.matches_at(&data[idx..]) {
+                    out.push((idx as u64, pattern.id.clone()));
+                }
+            }
+        }
+        out.sort();
+        out
+    }
+
+    /// Shortest buffer matching `pattern`, wildcard bits left zero.
+    fn sample(pattern: &SignaturePattern) -> Vec<u8> {
+        let mut out = vec![0u8; pattern.span() as usize];
+        let parts = pattern
+            .also
+            .iter()
+            .map(|(offset, bytes)| (*offset, bytes))
+            .chain([(pattern.offset, &pattern.anchor)]);
+        for (offset, bytes) in parts {
+            let offset = offset as usize;
+            out[offset..offset + bytes.len()].copy_from_slice(bytes.bytes());
+        }
+        out
+    }
+
+    fn scan(scanner: &CpuScanner, data: &[u8]) -> Vec<(u64, String)> {
+        let chunk = ScanChunk {
+            id: 3,
+            start: 0,
+            length: data.len() as u64,
+            valid_length: data.len() as u64,
+        };
+        let hits = scanner.scan_chunk(&chunk, data);
+        assert!(hits.iter().all(|hit| hit.chunk_id == 3));
+        let mut found: Vec<(u64, String)> = hits
+            .into_iter()
+            .map(|hit| (hit.local_offset, hit.pattern_id))
+            .collect();
+        found.sort();
+        found
+    }
+
+    fn pattern(id: &str, hex: &str, offset: u64, also: &[(u64, &str)]) -> PatternConfig {
+        PatternConfig {
+            id: id.to_string(),
+            hex: hex.to_string(),
+            offset,
+            also: also
+                .iter()
+                .map(|(offset, hex)| PatternCondition {
+                    offset: *offset,
+                    hex: hex.to_string(),
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn matches_every_configured_pattern_in_one_pass() {
+        let cfg = config::load_config(None).expect("config").config;
+        let scanner = CpuScanner::new(&cfg).expect("scanner");
+        let patterns = compile_patterns(&cfg).expect("patterns");
+
+        // Every header pattern, back to back and overlapping with runs of
+        // zeros and `RIFF`, with the last pattern ending at the chunk end.
+        let mut data = vec![0u8; 64];
+        for pattern in &patterns {
+            data.extend(sample(pattern));
+            data.extend_from_slice(b"RIFF\0\0\0\0");
+        }
+
+        assert_eq!(scan(&scanner, &data), naive_hits(&patterns, &data));
+    }
+
+    #[test]
+    fn honours_wildcards_offsets_and_secondary_conditions() {
+        let mut cfg = config::load_config(None).expect("config").config;
+        cfg.file_types.truncate(1);
+        cfg.file_types[0].header_patterns = vec![
+            pattern("mp4", "00 00 00 ?? 66 74 79 70", 0, &[]),
+            pattern("tar", "7573746172", 257, &[]),
+            pattern("wav", "52494646", 0, &[(8, "57415645")]),
+        ];
+        let scanner = CpuScanner::new(&cfg).expect("scanner");
+
+        let mut data = vec![0xAAu8; 2048];
+        // `ustar` too close to the chunk start to have a header before it.
+        data[100..105].copy_from_slice(b"ustar");
+        data[600..608].copy_from_slice(b"\0\0\0\x20ftyp");
+        data[700..708].copy_from_slice(b"\0\0\x01\x20ftyp");
+        data[1000 + 257..1000 + 262].copy_from_slice(b"ustar");
+        data[1400..1412].copy_from_slice(b"RIFF\x24\0\0\0WAVE");
+        data[1500..1512].copy_from_slice(b"RIFF\x24\0\0\0AVI ");
+        // Condition would run past the end of the chunk.
+        data[2040..2048].copy_from_slice(b"RIFF\x24\0\0\0");
+
+        assert_eq!(
+            scan(&scanner, &data),
+            vec![
+                (600, "mp4".to_string()),
+                (1000, "tar".to_string()),
+                (1400, "wav".to_string()),
+            ]
+        );
+    }
+}
@@ -3,6 +3,7 @@ pub mod cpu;
 pub mod cuda;
 #[cfg(feature = "gpu-opencl")]
 pub mod opencl;
+pub mod pattern;
 
 use crate::chunk::ScanChunk;
 
@@ -17,6 +17,7 @@ use tracing::warn;
 use crate::chunk::ScanChunk;
 use crate::config::Config;
 use crate::scanner::cpu::CpuScanner;
+use crate::scanner::pattern::{SignaturePattern, compile_patterns};
 use crate::scanner::{Hit, SignatureScanner};
 
 const KERNEL_SRC: &str = r#"
@@ -65,6 +66,7 @@ struct Pattern {
     id: String,
     file_type_id: String,
     bytes: Vec<u8>,
+    source: SignaturePattern,
 }
 
 pub struct OpenClScanner {
@@ -77,17 +79,27 @@ pub struct OpenClScanner {
     pattern_offsets: Buffer<cl_uint>,
     pattern_lengths: Buffer<cl_uint>,
     max_hits_per_chunk: u32,
+    /// CPU scanner over the same literal patterns, used when a GPU step
+    /// fails.
     cpu_fallback: CpuScanner,
+    /// Patterns the kernel cannot express (wildcards, anchor offsets,
+    /// secondary conditions), always scanned on the CPU.
+    cpu_patterns: CpuScanner,
 }
 
 impl OpenClScanner {
     pub fn new(cfg: &Config) -> Result<Self> {
-        let patterns = parse_patterns(cfg)?;
-        let cpu_fallback = CpuScanner::new(cfg)?;
+        let (patterns, cpu_patterns) = parse_patterns(cfg)?;
 
         if patterns.is_empty() {
-            return Err(anyhow!("no patterns configured"));
+            return Err(anyhow!("no literal patterns configured"));
         }
+        let cpu_fallback = CpuScanner::from_patterns(
+            patterns
+                .iter()
+                .map(|pattern| pattern.source.clone())
+                .collect(),
+        )?;
 
         let (pattern_bytes, pattern_offsets, pattern_lengths) = build_pattern_buffers(&patterns)?;
         let pattern_count = patterns.len() as u32;
@@ -140,12 +152,21 @@ impl OpenClScanner {
             pattern_lengths: pattern_lengths_buffer,
             max_hits_per_chunk: max_hits,
             cpu_fallback,
+            cpu_patterns,
         })
     }
 }
 
 impl SignatureScanner for OpenClScanner {
     fn scan_chunk(&self, chunk: &ScanChunk, data: &[u8]) -> Vec<Hit> {
+        let mut hits = self.scan_literals(chunk, data);
+        hits.extend(self.cpu_patterns.scan_chunk(chunk, data));
+        hits
+    }
+}
+
+impl OpenClScanner {
+    fn scan_literals(&self, chunk: &ScanChunk, data: &[u8]) -> Vec<Hit> {
         if data.is_empty() {
             return Vec::new();
         }
@@ -327,23 +348,24 @@ impl SignatureScanner for OpenClScanner {
     }
 }
 
-fn parse_patterns(cfg: &Config) -> Result<Vec<Pattern>> {
+/// Split the configured patterns into plain byte strings for the kernel and
+/// a CPU scanner for everything else.
+fn parse_patterns(cfg: &Config) -> Result<(Vec<Pattern>, CpuScanner)> {
     let mut patterns = Vec::new();
-    for file_type in &cfg.file_types {
-        for pat in &file_type.header_patterns {
-            let bytes = hex::decode(pat.hex.trim())
-                .map_err(|e| anyhow!("invalid hex pattern {}: {e}", pat.id))?;
-            if bytes.is_empty() {
-                continue;
-            }
+    let mut masked = Vec::new();
+    for pattern in compile_patterns(cfg)? {
+        if pattern.is_literal() {
             patterns.push(Pattern {
-                id: pat.id.clone(),
-                file_type_id: file_type.id.clone(),
-                bytes,
+                id: pattern.id.clone(),
+                file_type_id: pattern.file_type_id.clone(),
+                bytes: pattern.anchor.bytes().to_vec(),
+                source: pattern,
             });
+        } else {
+            masked.push(pattern);
         }
     }
-    Ok(patterns)
+    Ok((patterns, CpuScanner::from_patterns(masked)?))
 }
 
 fn build_pattern_buffers(patterns: &[Pattern]) -> Result<(Vec<u8>, Vec<cl_uint>, Vec<cl_uint>)> {
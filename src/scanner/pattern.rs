@@ -0,0 +1,247 @@
+//! Header signatures compiled from `header_patterns`: masked hex bytes at an
+//! offset from the file start, plus optional secondary conditions.
+
+use anyhow::{Result, anyhow};
+
+use crate::config::{Config, PatternConfig};
+
+/// Bytes with a per-bit mask; a zero mask bit matches anything.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MaskedBytes {
+    bytes: Vec<u8>,
+    mask: Vec<u8>,
+}
+
+impl MaskedBytes {
+    /// Parse hex such as `00 00 00 ?? 66 74 79 70`; `?` stands for one
+    /// nibble.
+    pub fn parse(hex: &str) -> Result<Self, String> {
+        let digits: Vec<char> = hex.chars().filter(|c| !c.is_whitespace()).collect();
+        if !digits.len().is_multiple_of(2) {
+            return Err(format!("odd number of hex digits in `{hex}`"));
+        }
+        let mut bytes = Vec::with_capacity(digits.len() / 2);
+        let mut mask = Vec::with_capacity(digits.len() / 2);
+        for pair in digits.chunks_exact(2) {
+            let mut value = 0u8;
+            let mut bits = 0u8;
+            for &digit in pair {
+                value <<= 4;
+                bits <<= 4;
+                if digit == '?' {
+                    continue;
+                }
+                let nibble = digit
+                    .to_digit(16)
+                    .ok_or_else(|| format!("invalid hex digit `{digit}` in `{hex}`"))?;
+                value |= nibble as u8;
+                bits |= 0x0F;
+            }
+            bytes.push(value);
+            mask.push(bits);
+        }
+        Ok(Self { bytes, mask })
+    }
+
+    pub fn len(&self) -> usize {
+        self.bytes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.bytes.is_empty()
+    }
+
+    /// True when no byte has a wildcard.
+    pub fn is_exact(&self) -> bool {
+        self.mask.iter().all(|&bits| bits == 0xFF)
+    }
+
+    /// The pattern bytes; wildcard bits are zero.
+    pub fn bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+
+    pub fn matches(&self, data: &[u8]) -> bool {
+        data.len() >= self.bytes.len()
+            && self
+                .bytes
+                .iter()
+                .zip(&self.mask)
+                .zip(data)
+                .all(|((&byte, &bits), &value)| value & bits == byte)
+    }
+
+    /// Longest run of fully specified bytes, as `(start, len)`.
+    fn longest_exact_run(&self) -> (usize, usize) {
+        let mut best = (0, 0);
+        let mut start = 0;
+        for (idx, &bits) in self.mask.iter().enumerate() {
+            if bits != 0xFF {
+                start = idx + 1;
+            } else if idx + 1 - start > best.1 {
+                best = (start, idx + 1 - start);
+            }
+        }
+        best
+    }
+}
+
+/// One header signature, ready for matching.
+#[derive(Debug, Clone)]
+pub struct SignaturePattern {
+    pub id: String,
+    pub file_type_id: String,
+    /// Offset of `anchor` from the hit (file start).
+    pub offset: u64,
+    pub anchor: MaskedBytes,
+    /// `(offset, bytes)` conditions relative to the hit.
+    pub also: Vec<(u64, MaskedBytes)>,
+}
+
+impl SignaturePattern {
+    pub fn parse(pattern: &PatternConfig, file_type_id: &str) -> Result<Option<Self>> {
+        let invalid = |err: String| anyhow!("invalid hex pattern {}: {err}", pattern.id);
+        let anchor = MaskedBytes::parse(&pattern.hex).map_err(invalid)?;
+        if anchor.is_empty() {
+            return Ok(None);
+        }
+        if anchor.longest_exact_run().1 == 0 {
+            return Err(anyhow!(
+                "pattern {} needs at least one byte without wildcards",
+                pattern.id
+            ));
+        }
+        let mut also = Vec::with_capacity(pattern.also.len());
+        for condition in &pattern.also {
+            let bytes = MaskedBytes::parse(&condition.hex).map_err(invalid)?;
+            if !bytes.is_empty() {
+                also.push((condition.offset, bytes));
+            }
+        }
+        Ok(Some(Self {
+            id: pattern.id.clone(),
+            file_type_id: file_type_id.to_string(),
+            offset: pattern.offset,
+            anchor,
+            also,
+        }))
+    }
+
+    /// Contiguous exact bytes with no offset or extra conditions, the only
+    /// form the GPU kernels can match.
+    pub fn is_literal(&self) -> bool {
+        self.offset == 0 && self.also.is_empty() && self.anchor.is_exact()
+    }
+
+    /// Exact bytes to search for and their offset from the hit; every match
+    /// still has to pass [`SignaturePattern::matches_at`].
+    pub fn search_literal(&self) -> (u64, &[u8]) {
+        let (start, len) = self.anchor.longest_exact_run();
+        (
+            self.offset + start as u64,
+            &self.anchor.bytes()[start..start + len],
+        )
+    }
+
+    /// Bytes from the hit that matching needs to see.
+    pub fn span(&self) -> u64 {
+        self.also
+            .iter()
+            .map(|(offset, bytes)| offset + bytes.len() as u64)
+            .chain([self.offset + self.anchor.len() as u64])
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// Whether a file starting at `data[0]` matches the anchor and every
+    /// condition. Conditions past the end of `data` do not match.
+    pub fn matches_at(&self, data: &[u8]) -> bool {
+        let part_matches = |offset: u64, bytes: &MaskedBytes| {
+            usize::try_from(offset)
+                .ok()
+                .and_then(|offset| data.get(offset..))
+                .is_some_and(|rest| bytes.matches(rest))
+        };
+        part_matches(self.offset, &self.anchor)
+            && self
+                .also
+                .iter()
+                .all(|(offset, bytes)| part_matches(*offset, bytes))
+    }
+}
+
+/// Compile every header pattern of every configured file type.
+pub fn compile_patterns(cfg: &Config) -> Result<Vec<SignaturePattern>> {
+    let mut patterns = Vec::new();
+    for file_type in &cfg.file_types {
+        for pat in &file_type.header_patterns {
+            if let Some(pattern) = SignaturePattern::parse(pat, &file_type.id)? {
+                patterns.push(pattern);
+            }
+        }
+    }
+    Ok(patterns)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::PatternCondition;
+
+    #[test]
+    fn parses_nibble_and_byte_wildcards() {
+        let bytes = MaskedBytes::parse("00 00 00 ?? 66 74 79 7?").expect("parse");
+        assert!(!bytes.is_exact());
+        assert!(bytes.matches(b"\x00\x00\x00\x18ftyp"));
+        assert!(bytes.matches(b"\x00\x00\x00\x20ftyq"));
+        assert!(!bytes.matches(b"\x00\x00\x01\x18ftyp"));
+        assert!(!bytes.matches(b"\x00\x00\x00\x18fty"));
+        assert_eq!(bytes.longest_exact_run(), (0, 3));
+        assert!(MaskedBytes::parse("ABC").is_err());
+        assert!(MaskedBytes::parse("G0").is_err());
+    }
+
+    #[test]
+    fn anchors_at_offset_with_secondary_conditions() {
+        let config = PatternConfig {
+            id: "webp".to_string(),
+            hex: "52494646".to_string(),
+            offset: 0,
+            also: vec![PatternCondition {
+                offset: 8,
+                hex: "57454250".to_string(),
+            }],
+        };
+        let pattern = SignaturePattern::parse(&config, "webp")
+            .expect("parse")
+            .expect("pattern");
+        assert!(!pattern.is_literal());
+        assert_eq!(pattern.span(), 12);
+        assert!(pattern.matches_at(b"RIFF\x10\0\0\0WEBPVP8 "));
+        assert!(!pattern.matches_at(b"RIFF\x10\0\0\0WAVEfmt "));
+        assert!(!pattern.matches_at(b"RIFF\x10\0\0\0WEB"));
+
+        let config = PatternConfig {
+            id: "tar".to_string(),
+            hex: "75 73 74 61 72".to_string(),
+            offset: 257,
+            also: Vec::new(),
+        };
+        let pattern = SignaturePattern::parse(&config, "tar")
+            .expect("parse")
+            .expect("pattern");
+        assert_eq!(pattern.search_literal(), (257, &b"ustar"[..]));
+        let mut header = vec![0u8; 512];
+        header[257..262].copy_from_slice(b"ustar");
+        assert!(pattern.matches_at(&header));
+        assert!(!pattern.matches_at(&header[1..]));
+
+        let config = PatternConfig {
+            id: "any".to_string(),
+            hex: "????".to_string(),
+            offset: 0,
+            also: Vec::new(),
+        };
+        assert!(SignaturePattern::parse(&config, "any").is_err());
+    }
+}
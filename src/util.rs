@@ -12,9 +12,11 @@ use anyhow::{Result, anyhow};
 use tracing::info;
 use tracing::{debug, warn};
 
+use crate::carve::bifragment::BifragmentConfig;
 use crate::carve::{self, CarveRegistry};
 use crate::config::Config;
 use crate::metadata::MetadataBackendKind;
+use crate::scanner::pattern::{MaskedBytes, SignaturePattern};
 
 /// Convert CLI metadata backend to internal enum
 pub fn backend_from_cli(backend: crate::cli::MetadataBackend) -> MetadataBackendKind {
@@ -144,6 +146,13 @@ pub fn build_carve_registry(cfg: &Config, dry_run: bool) -> Result<CarveRegistry
     let _ = dry_run; // Currently handled by not creating output dirs
 
     let mut handlers: HashMap<String, Box<dyn carve::CarveHandler>> = HashMap::new();
+    let bifragment = cfg.bifragment.then(|| {
+        BifragmentConfig::new(
+            cfg.bifragment_block_size,
+            cfg.bifragment_max_gap,
+            cfg.bifragment_max_searches,
+        )
+    });
     let allow_quicktime = matches!(cfg.quicktime_mode, crate::config::QuicktimeMode::Mp4);
     let mut mp4_ext = "mp4".to_string();
     let mut has_mp4 = false;
@@ -189,6 +198,7 @@ pub fn build_carve_registry(cfg: &Config, dry_run: bool) -> Result<CarveRegistry
                         ext,
                         file_type.min_size,
                         file_type.max_size,
+                        bifragment.clone(),
                     )),
                 );
             }
@@ -199,6 +209,7 @@ pub fn build_carve_registry(cfg: &Config, dry_run: bool) -> Result<CarveRegistry
                         ext,
                         file_type.min_size,
                         file_type.max_size,
+                        bifragment.clone(),
                     )),
                 );
             }
@@ -241,6 +252,7 @@ pub fn build_carve_registry(cfg: &Config, dry_run: bool) -> Result<CarveRegistry
                         file_type.max_size,
                         file_type.require_eocd,
                         cfg.zip_allowed_kinds.clone(),
+                        bifragment.clone(),
                     )),
                 );
             }
@@ -508,7 +520,7 @@ pub fn build_carve_registry(cfg: &Config, dry_run: bool) -> Result<CarveRegistry
                 );
             }
             "footer" => {
-                let headers = decode_patterns(&file_type.header_patterns, &file_type.id, "header")?;
+                let headers = decode_header_patterns(&file_type.header_patterns, &file_type.id)?;
                 let footers = decode_patterns(&file_type.footer_patterns, &file_type.id, "footer")?;
                 if headers.is_empty() {
                     debug!(
@@ -548,6 +560,19 @@ pub fn build_carve_registry(cfg: &Config, dry_run: bool) -> Result<CarveRegistry
     Ok(CarveRegistry::new(handlers))
 }
 
+fn decode_header_patterns(
+    patterns: &[crate::config::PatternConfig],
+    file_type: &str,
+) -> Result<Vec<SignaturePattern>> {
+    let mut out = Vec::new();
+    for pattern in patterns {
+        let parsed = SignaturePattern::parse(pattern, file_type)
+            .map_err(|e| anyhow!("invalid header pattern for file_type {}: {e}", file_type))?;
+        out.extend(parsed);
+    }
+    Ok(out)
+}
+
 fn decode_patterns(
     patterns: &[crate::config::PatternConfig],
     file_type: &str,
@@ -555,16 +580,23 @@ fn decode_patterns(
 ) -> Result<Vec<Vec<u8>>> {
     let mut out = Vec::new();
     for pattern in patterns {
-        let bytes = hex::decode(pattern.hex.trim()).map_err(|e| {
+        let invalid = |e: String| {
             anyhow!(
                 "invalid {} pattern {} for file_type {}: {e}",
                 kind,
                 pattern.id,
                 file_type
             )
-        })?;
+        };
+        let bytes = MaskedBytes::parse(&pattern.hex).map_err(invalid)?;
+        if !bytes.is_exact() || pattern.offset != 0 || !pattern.also.is_empty() {
+            return Err(invalid(
+                "wildcards, offsets and conditions are only supported in header patterns"
+                    .to_string(),
+            ));
+        }
         if !bytes.is_empty() {
-            out.push(bytes);
+            out.push(bytes.bytes().to_vec());
         }
     }
     Ok(out)
@@ -0,0 +1,741 @@
+use std::collections::VecDeque;
+
+use super::{SPLIT_WINDOW, Splice, SpliceSearch, read_u16_be};
+
+const MARKER_SOI: u8 = 0xD8;
+const MARKER_EOI: u8 = 0xD9;
+const MARKER_SOS: u8 = 0xDA;
+const MARKER_DHT: u8 = 0xC4;
+const MARKER_DRI: u8 = 0xDD;
+const MARKER_DAC: u8 = 0xCC;
+/// Bytes past a trial break decoded before the whole candidate is checked.
+const PROBE_LEN: usize = 16 * 1024;
+
+/// Validate marker structure and, for baseline/extended Huffman images, decode
+/// every entropy-coded block so that truncated or corrupt scans are detected.
+/// Progressive and arithmetic-coded images only get the structural checks.
+pub fn validate(data: &[u8]) -> Result<(), String> {
+    walk(data, &mut DecoderState::default(), None).map(|_| ())
+}
+
+/// Find the gap that turns a baseline JPEG broken inside its entropy-coded
+/// data back into a decodable image. The break is searched for shortly
+/// before the point where decoding fails; each trial resumes the decoder
+/// from the last MCU boundary before the break instead of starting over,
+/// and the markers after the gap must lead to EOI before the candidate is
+/// assembled and decoded whole.
+pub(crate) fn find_splice(data: &[u8], search: &SpliceSearch) -> Option<Splice> {
+    let mut state = DecoderState::default();
+    let mut trace = ScanTrace::new(SPLIT_WINDOW + search.block_size);
+    if walk(data, &mut state, Some(&mut trace)).is_ok() {
+        return None;
+    }
+    let (header, scan_start, failure) = trace.failure?;
+    if failure.eof {
+        return None;
+    }
+    let plan = state.scan_plan(&header).ok()?;
+    let low = failure.pos.saturating_sub(SPLIT_WINDOW).max(scan_start + 1);
+    let mut probe = Vec::with_capacity(SPLIT_WINDOW + PROBE_LEN);
+    for gap in search.gaps() {
+        for split in search.splits(low, failure.pos) {
+            if split + gap >= data.len() {
+                continue;
+            }
+            let Some(resume) = trace.snapshots.iter().rev().find(|s| s.pos <= split) else {
+                continue;
+            };
+            probe.clear();
+            probe.extend_from_slice(&data[resume.pos..split]);
+            let tail = split + gap;
+            probe.extend_from_slice(&data[tail..(tail + PROBE_LEN).min(data.len())]);
+            let start = ScanState { pos: 0, ..*resume };
+            match plan.decode(&probe, start, |_| {}) {
+                Ok(_) => {}
+                Err(err) if err.eof => {}
+                Err(_) => continue,
+            }
+            let Some(end) = structure_end(data, tail) else {
+                continue;
+            };
+            let candidate = Splice {
+                split,
+                gap,
+                len: end - gap,
+            }
+            .join(data);
+            if let Ok(len) = walk(&candidate, &mut DecoderState::default(), None) {
+                return Some(Splice { split, gap, len });
+            }
+        }
+    }
+    None
+}
+
+/// Walk the marker structure, decoding scans, and return the offset just
+/// past EOI. `trace` records decoder snapshots and the first entropy
+/// decoding failure, for `find_splice`.
+fn walk(
+    data: &[u8],
+    state: &mut DecoderState,
+    mut trace: Option<&mut ScanTrace>,
+) -> Result<usize, String> {
+    if data.len() < 4 || data[0] != 0xFF || data[1] != MARKER_SOI {
+        return Err("jpeg missing SOI marker".to_string());
+    }
+
+    let mut pos = 2usize;
+    loop {
+        let (marker, marker_pos) = next_marker(data, pos)
+            .ok_or_else(|| format!("jpeg missing EOI marker (truncated after offset {pos})"))?;
+        pos = marker_pos + 2;
+        match marker {
+            MARKER_EOI => {
+                if state.scans == 0 {
+                    return Err("jpeg has no scan data".to_string());
+                }
+                return Ok(pos);
+            }
+            MARKER_SOI => return Err(format!("jpeg unexpected SOI at offset {marker_pos}")),
+            0xD0..=0xD7 => {
+                return Err(format!(
+                    "jpeg restart marker outside scan at offset {marker_pos}"
+                ));
+            }
+            0x01 => {}
+            _ => {
+                let len = read_u16_be(data, pos)
+                    .ok_or_else(|| format!("jpeg segment truncated at offset {marker_pos}"))?
+                    as usize;
+                if len < 2 {
+                    return Err(format!("jpeg segment length {len} at offset {marker_pos}"));
+                }
+                let body = data.get(pos + 2..pos + len).ok_or_else(|| {
+                    format!("jpeg segment 0x{marker:02X} truncated at offset {marker_pos}")
+                })?;
+                pos += len;
+                match marker {
+                    0xC0..=0xCF
+                        if marker != MARKER_DHT && marker != 0xC8 && marker != MARKER_DAC =>
+                    {
+                        state.parse_frame(marker, body)?;
+                    }
+                    MARKER_DHT => state.parse_dht(body)?,
+                    MARKER_DAC => state.arithmetic = true,
+                    MARKER_DRI => {
+                        state.restart_interval = read_u16_be(body, 0)
+                            .ok_or_else(|| "jpeg DRI segment too short".to_string())?;
+                    }
+                    MARKER_SOS => {
+                        pos = state.process_scan(data, body, pos, trace.as_deref_mut())?;
+                    }
+                    _ => {}
+                }
+            }
+        }
+    }
+}
+
+/// Offset just past EOI when the data from `pos`, inside a scan's
+/// entropy-coded data, runs through well-formed segments to the end of the
+/// image. Scans are skipped, not decoded.
+fn structure_end(data: &[u8], pos: usize) -> Option<usize> {
+    let mut pos = skip_entropy_data(data, pos).ok()?;
+    loop {
+        let (marker, marker_pos) = next_marker(data, pos)?;
+        pos = marker_pos + 2;
+        match marker {
+            MARKER_EOI => return Some(pos),
+            MARKER_SOI | 0xD0..=0xD7 => return None,
+            0x01 => {}
+            _ => {
+                let len = read_u16_be(data, pos)? as usize;
+                if len < 2 {
+                    return None;
+                }
+                pos += len;
+                if marker == MARKER_SOS {
+                    pos = skip_entropy_data(data, pos).ok()?;
+                }
+            }
+        }
+    }
+}
+
+/// Find the next marker at or after `pos`, skipping fill bytes. Returns the
+/// marker code and the offset of its leading 0xFF.
+fn next_marker(data: &[u8], mut pos: usize) -> Option<(u8, usize)> {
+    while pos + 1 < data.len() {
+        if data[pos] != 0xFF {
+            return None;
+        }
+        let code = data[pos + 1];
+        if code == 0xFF {
+            pos += 1;
+            continue;
+        }
+        return Some((code, pos));
+    }
+    None
+}
+
+#[derive(Clone, Copy)]
+struct Component {
+    id: u8,
+    h: u8,
+    v: u8,
+}
+
+#[derive(Default)]
+struct DecoderState {
+    frame_marker: Option<u8>,
+    width: u32,
+    height: u32,
+    components: Vec<Component>,
+    dc_tables: [Option<HuffmanTable>; 4],
+    ac_tables: [Option<HuffmanTable>; 4],
+    restart_interval: u16,
+    arithmetic: bool,
+    scans: usize,
+}
+
+impl DecoderState {
+    fn parse_frame(&mut self, marker: u8, body: &[u8]) -> Result<(), String> {
+        if self.frame_marker.is_some() {
+            return Err("jpeg has multiple frame headers".to_string());
+        }
+        if body.len() < 6 {
+            return Err("jpeg SOF segment too short".to_string());
+        }
+        let height = read_u16_be(body, 1).unwrap_or(0) as u32;
+        let width = read_u16_be(body, 3).unwrap_or(0) as u32;
+        let count = body[5] as usize;
+        if width == 0 {
+            return Err("jpeg frame width is zero".to_string());
+        }
+        if height == 0 {
+            // A zero height defers to a DNL marker; no supported encoder emits it.
+            return Err("jpeg frame height is zero".to_string());
+        }
+        if count == 0 || body.len() < 6 + count * 3 {
+            return Err("jpeg SOF component list truncated".to_string());
+        }
+        let mut components = Vec::with_capacity(count);
+        for idx in 0..count {
+            let base = 6 + idx * 3;
+            let sampling = body[base + 1];
+            let (h, v) = (sampling >> 4, sampling & 0x0F);
+            if !(1..=4).contains(&h) || !(1..=4).contains(&v) {
+                return Err(format!("jpeg component {idx} has invalid sampling factors"));
+            }
+            components.push(Component {
+                id: body[base],
+                h,
+                v,
+            });
+        }
+        self.frame_marker = Some(marker);
+        self.width = width;
+        self.height = height;
+        self.components = components;
+        self.arithmetic |= marker >= 0xC9;
+        Ok(())
+    }
+
+    fn parse_dht(&mut self, body: &[u8]) -> Result<(), String> {
+        let mut pos = 0usize;
+        while pos < body.len() {
+            let info = body[pos];
+            let class = info >> 4;
+            let index = (info & 0x0F) as usize;
+            if class > 1 || index > 3 {
+                return Err("jpeg DHT has invalid table class/index".to_string());
+            }
+            let counts = body
+                .get(pos + 1..pos + 17)
+                .ok_or_else(|| "jpeg DHT truncated".to_string())?;
+            let total: usize = counts.iter().map(|&c| c as usize).sum();
+            let symbols = body
+                .get(pos + 17..pos + 17 + total)
+                .ok_or_else(|| "jpeg DHT truncated".to_string())?;
+            let table = HuffmanTable::build(counts, symbols)?;
+            if class == 0 {
+                self.dc_tables[index] = Some(table);
+            } else {
+                self.ac_tables[index] = Some(table);
+            }
+            pos += 17 + total;
+        }
+        Ok(())
+    }
+
+    /// Validate one scan and return the offset just past its entropy-coded data.
+    fn process_scan(
+        &mut self,
+        data: &[u8],
+        header: &[u8],
+        start: usize,
+        trace: Option<&mut ScanTrace>,
+    ) -> Result<usize, String> {
+        let frame_marker = self
+            .frame_marker
+            .ok_or_else(|| "jpeg scan before frame header".to_string())?;
+        let count = *header.first().ok_or("jpeg SOS segment too short")? as usize;
+        if count == 0 || count > 4 || header.len() < 1 + count * 2 + 3 {
+            return Err("jpeg SOS segment malformed".to_string());
+        }
+        self.scans += 1;
+
+        let huffman_sequential = matches!(frame_marker, 0xC0 | 0xC1) && !self.arithmetic;
+        if !huffman_sequential {
+            self.scan_components(header)?;
+            return skip_entropy_data(data, start);
+        }
+        let plan = self.scan_plan(header)?;
+        let state = ScanState {
+            mcu: 0,
+            pos: start,
+            current: 0,
+            bits_left: 0,
+            expected_rst: 0,
+        };
+        let Some(trace) = trace else {
+            return plan.decode(data, state, |_| {}).map_err(|err| err.reason);
+        };
+        let keep = trace.keep;
+        let result = plan.decode(data, state, |snapshot| {
+            while trace
+                .snapshots
+                .front()
+                .is_some_and(|old| old.pos + keep < snapshot.pos)
+            {
+                trace.snapshots.pop_front();
+            }
+            trace.snapshots.push_back(*snapshot);
+        });
+        result.map_err(|err| {
+            let reason = err.reason.clone();
+            if trace.failure.is_none() {
+                trace.failure = Some((header.to_vec(), start, err));
+            }
+            reason
+        })
+    }
+
+    fn scan_components(&self, header: &[u8]) -> Result<Vec<(Component, usize, usize)>, String> {
+        let count = header[0] as usize;
+        let mut scan_components = Vec::with_capacity(count);
+        for idx in 0..count {
+            let id = header[1 + idx * 2];
+            let tables = header[2 + idx * 2];
+            let component = self
+                .components
+                .iter()
+                .copied()
+                .find(|c| c.id == id)
+                .ok_or_else(|| format!("jpeg scan references unknown component {id}"))?;
+            scan_components.push((component, (tables >> 4) as usize, (tables & 0x0F) as usize));
+        }
+        Ok(scan_components)
+    }
+
+    /// Tables and MCU layout of a baseline scan.
+    fn scan_plan(&self, header: &[u8]) -> Result<ScanPlan<'_>, String> {
+        let scan_components = self.scan_components(header)?;
+        let count = scan_components.len();
+        let spectral_start = header[1 + count * 2];
+        let spectral_end = header[2 + count * 2];
+        let approx = header[3 + count * 2];
+        if spectral_start != 0 || spectral_end != 63 || approx != 0 {
+            return Err("jpeg baseline scan has invalid spectral selection".to_string());
+        }
+
+        let mut blocks = Vec::new();
+        for (component, dc, ac) in &scan_components {
+            let dc_table = self.dc_tables[(*dc).min(3)]
+                .as_ref()
+                .ok_or_else(|| format!("jpeg scan uses undefined DC table {dc}"))?;
+            let ac_table = self.ac_tables[(*ac).min(3)]
+                .as_ref()
+                .ok_or_else(|| format!("jpeg scan uses undefined AC table {ac}"))?;
+            let per_mcu = if count == 1 {
+                1
+            } else {
+                component.h as usize * component.v as usize
+            };
+            blocks.push((dc_table, ac_table, per_mcu));
+        }
+        if blocks.iter().map(|b| b.2).sum::<usize>() > 10 {
+            return Err("jpeg MCU exceeds 10 blocks".to_string());
+        }
+
+        let total_mcus = self.mcu_count(&scan_components.iter().map(|c| c.0).collect::<Vec<_>>());
+        Ok(ScanPlan {
+            blocks,
+            total_mcus,
+            restart: self.restart_interval as u64,
+        })
+    }
+
+    fn mcu_count(&self, scan: &[Component]) -> u64 {
+        let h_max = self.components.iter().map(|c| c.h).max().unwrap_or(1) as u64;
+        let v_max = self.components.iter().map(|c| c.v).max().unwrap_or(1) as u64;
+        let (width, height) = (self.width as u64, self.height as u64);
+        if scan.len() == 1 {
+            let c = scan[0];
+            let comp_w = (width * c.h as u64).div_ceil(h_max);
+            let comp_h = (height * c.v as u64).div_ceil(v_max);
+            comp_w.div_ceil(8) * comp_h.div_ceil(8)
+        } else {
+            width.div_ceil(8 * h_max) * height.div_ceil(8 * v_max)
+        }
+    }
+}
+
+/// Entropy decoder position at an MCU boundary.
+#[derive(Debug, Clone, Copy)]
+struct ScanState {
+    mcu: u64,
+    /// Offset of the first byte not yet (fully or partially) consumed.
+    pos: usize,
+    current: u8,
+    bits_left: u8,
+    expected_rst: u8,
+}
+
+#[derive(Debug)]
+struct ScanError {
+    reason: String,
+    /// Offset the decoder had reached.
+    pos: usize,
+    /// Decoding ran out of data rather than into invalid data.
+    eof: bool,
+}
+
+/// Snapshots kept while decoding for `find_splice`, and where the first
+/// scan that failed to decode broke (its SOS header, data start, error).
+struct ScanTrace {
+    keep: usize,
+    snapshots: VecDeque<ScanState>,
+    failure: Option<(Vec<u8>, usize, ScanError)>,
+}
+
+impl ScanTrace {
+    /// `keep` is how many bytes of snapshots to keep behind the decoder.
+    fn new(keep: usize) -> Self {
+        Self {
+            keep,
+            snapshots: VecDeque::new(),
+            failure: None,
+        }
+    }
+}
+
+struct ScanPlan<'t> {
+    blocks: Vec<(&'t HuffmanTable, &'t HuffmanTable, usize)>,
+    total_mcus: u64,
+    restart: u64,
+}
+
+impl ScanPlan<'_> {
+    /// Decode from `state` to the end of the scan, reporting the decoder
+    /// state at every MCU boundary, and return the offset past the scan.
+    fn decode(
+        &self,
+        data: &[u8],
+        state: ScanState,
+        mut checkpoint: impl FnMut(&ScanState),
+    ) -> Result<usize, ScanError> {
+        let mut reader = BitReader {
+            data,
+            pos: state.pos,
+            current: state.current,
+            bits_left: state.bits_left,
+        };
+        let mut expected_rst = state.expected_rst;
+        for mcu in state.mcu..self.total_mcus {
+            if self.restart > 0 && mcu > 0 && mcu % self.restart == 0 && mcu != state.mcu {
+                let marker_pos = reader.byte_pos();
+                match next_marker(data, marker_pos) {
+                    Some((code, at)) if code == 0xD0 + expected_rst => {
+                        reader = BitReader::new(data, at + 2);
+                        expected_rst = (expected_rst + 1) & 7;
+                    }
+                    Some((code, at)) => {
+                        return Err(ScanError {
+                            reason: format!(
+                                "jpeg expected RST{expected_rst} but found 0xFF{code:02X} at offset {at}"
+                            ),
+                            pos: at,
+                            eof: false,
+                        });
+                    }
+                    None => {
+                        return Err(ScanError {
+                            reason: format!(
+                                "jpeg missing RST{expected_rst} marker at offset {marker_pos}"
+                            ),
+                            pos: marker_pos,
+                            eof: marker_pos + 1 >= data.len(),
+                        });
+                    }
+                }
+            }
+            checkpoint(&ScanState {
+                mcu,
+                pos: reader.pos,
+                current: reader.current,
+                bits_left: reader.bits_left,
+                expected_rst,
+            });
+            for (dc_table, ac_table, per_mcu) in &self.blocks {
+                for _ in 0..*per_mcu {
+                    decode_block(&mut reader, dc_table, ac_table).map_err(|err| ScanError {
+                        reason: format!(
+                            "jpeg entropy data corrupt in MCU {mcu} of {}: {err}",
+                            self.total_mcus
+                        ),
+                        pos: reader.pos,
+                        eof: reader.pos + 1 >= data.len(),
+                    })?;
+                }
+            }
+        }
+        Ok(reader.byte_pos())
+    }
+}
+
+/// Skip entropy-coded data without decoding it, stopping at the first marker
+/// that is neither a stuffed zero nor a restart marker.
+fn skip_entropy_data(data: &[u8], mut pos: usize) -> Result<usize, String> {
+    while pos + 1 < data.len() {
+        if data[pos] == 0xFF {
+            match data[pos + 1] {
+                0x00 | 0xD0..=0xD7 | 0xFF => pos += 1,
+                _ => return Ok(pos),
+            }
+        }
+        pos += 1;
+    }
+    Err("jpeg scan data runs to end of file".to_string())
+}
+
+fn decode_block(
+    reader: &mut BitReader<'_>,
+    dc: &HuffmanTable,
+    ac: &HuffmanTable,
+) -> Result<(), String> {
+    let size = dc.decode(reader)?;
+    if size > 11 {
+        return Err(format!("DC magnitude category {size}"));
+    }
+    reader.skip_bits(size as u32)?;
+    let mut k = 1u32;
+    while k < 64 {
+        let rs = ac.decode(reader)?;
+        let run = (rs >> 4) as u32;
+        let size = (rs & 0x0F) as u32;
+        if size == 0 {
+            if run == 15 {
+                k += 16;
+                continue;
+            }
+            return Ok(());
+        }
+        k += run;
+        if k > 63 {
+            return Err("AC coefficient index past 63".to_string());
+        }
+        reader.skip_bits(size)?;
+        k += 1;
+    }
+    if k > 64 {
+        return Err("AC zero run past end of block".to_string());
+    }
+    Ok(())
+}
+
+struct HuffmanTable {
+    /// Largest code of each length (index 1..=16), or -1 when the length is unused.
+    max_code: [i32; 17],
+    /// Offset into `symbols` for the first code of each length, minus that code.
+    val_offset: [i32; 17],
+    symbols: Vec<u8>,
+}
+
+impl HuffmanTable {
+    fn build(counts: &[u8], symbols: &[u8]) -> Result<Self, String> {
+        let mut max_code = [-1i32; 17];
+        let mut val_offset = [0i32; 17];
+        let mut code = 0i32;
+        let mut index = 0i32;
+        for len in 1..=16 {
+            let count = counts[len - 1] as i32;
+            if count > 0 {
+                val_offset[len] = index - code;
+                code += count;
+                index += count;
+                if code > (1 << len) {
+                    return Err("jpeg DHT code lengths oversubscribed".to_string());
+                }
+                max_code[len] = code - 1;
+            }
+            code <<= 1;
+        }
+        Ok(Self {
+            max_code,
+            val_offset,
+            symbols: symbols.to_vec(),
+        })
+    }
+
+    fn decode(&self, reader: &mut BitReader<'_>) -> Result<u8, String> {
+        let mut code = 0i32;
+        for len in 1..=16 {
+            code = (code << 1) | reader.bit()? as i32;
+            if code <= self.max_code[len] {
+                let idx = (self.val_offset[len] + code) as usize;
+                return self
+                    .symbols
+                    .get(idx)
+                    .copied()
+                    .ok_or_else(|| "huffman symbol out of range".to_string());
+            }
+        }
+        Err("invalid huffman code".to_string())
+    }
+}
+
+struct BitReader<'a> {
+    data: &'a [u8],
+    pos: usize,
+    current: u8,
+    bits_left: u8,
+}
+
+impl<'a> BitReader<'a> {
+    fn new(data: &'a [u8], pos: usize) -> Self {
+        Self {
+            data,
+            pos,
+            current: 0,
+            bits_left: 0,
+        }
+    }
+
+    /// Offset of the first byte not (fully or partially) consumed.
+    fn byte_pos(&self) -> usize {
+        self.pos
+    }
+
+    fn bit(&mut self) -> Result<u8, String> {
+        if self.bits_left == 0 {
+            let byte = *self
+                .data
+                .get(self.pos)
+                .ok_or_else(|| "unexpected end of file".to_string())?;
+            if byte == 0xFF {
+                match self.data.get(self.pos + 1) {
+                    Some(0x00) => self.pos += 2,
+                    Some(code) => {
+                        return Err(format!(
+                            "unexpected marker 0xFF{code:02X} at offset {}",
+                            self.pos
+                        ));
+                    }
+                    None => return Err("unexpected end of file".to_string()),
+                }
+            } else {
+                self.pos += 1;
+            }
+            self.current = byte;
+            self.bits_left = 8;
+        }
+        self.bits_left -= 1;
+        Ok((self.current >> self.bits_left) & 1)
+    }
+
+    fn skip_bits(&mut self, count: u32) -> Result<(), String> {
+        for _ in 0..count {
+            self.bit()?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{find_splice, validate};
+    use crate::validate::{SpliceSearch, noise};
+
+    const GENERATED: &[u8] =
+        include_bytes!("../../tests/golden_image/samples/images/test_generated.jpg");
+
+    /// 8x8 greyscale baseline JPEG with one all-zero block.
+    fn tiny_baseline() -> Vec<u8> {
+        let mut data = vec![0xFF, 0xD8];
+        // DQT
+        data.extend_from_slice(&[0xFF, 0xDB, 0x00, 0x43, 0x00]);
+        data.extend(std::iter::repeat_n(1u8, 64));
+        // SOF0: 8 bits, 8x8, 1 component (id 1, 1x1, tq 0)
+        data.extend_from_slice(&[
+            0xFF, 0xC0, 0x00, 0x0B, 0x08, 0x00, 0x08, 0x00, 0x08, 0x01, 0x01, 0x11, 0x00,
+        ]);
+        // DHT DC0: one code of length 1 -> symbol 0
+        let mut dc = vec![0xFF, 0xC4, 0x00, 0x14, 0x00, 0x01];
+        dc.extend(std::iter::repeat_n(0u8, 15));
+        dc.push(0x00);
+        data.extend(dc);
+        // DHT AC0: one code of length 1 -> symbol 0 (EOB)
+        let mut ac = vec![0xFF, 0xC4, 0x00, 0x14, 0x10, 0x01];
+        ac.extend(std::iter::repeat_n(0u8, 15));
+        ac.push(0x00);
+        data.extend(ac);
+        // SOS
+        data.extend_from_slice(&[0xFF, 0xDA, 0x00, 0x08, 0x01, 0x01, 0x00, 0x00, 0x3F, 0x00]);
+        // DC=0 (bit 0), EOB (bit 0), pad with ones.
+        data.push(0x3F);
+        data.extend_from_slice(&[0xFF, 0xD9]);
+        data
+    }
+
+    #[test]
+    fn accepts_minimal_baseline() {
+        assert_eq!(validate(&tiny_baseline()), Ok(()));
+    }
+
+    #[test]
+    fn rejects_missing_eoi() {
+        let data = tiny_baseline();
+        assert!(validate(&data[..data.len() - 2]).is_err());
+    }
+
+    #[test]
+    fn rejects_truncated_scan() {
+        let mut data = tiny_baseline();
+        // Drop the entropy byte so the scan hits EOI before the block is decoded.
+        let len = data.len();
+        data.remove(len - 3);
+        let err = validate(&data).expect_err("truncated scan");
+        assert!(err.contains("entropy data corrupt"));
+    }
+
+    #[test]
+    fn finds_gap_inside_entropy_data() {
+        assert_eq!(validate(GENERATED), Ok(()));
+        let mut data = GENERATED[..3072].to_vec();
+        data.extend(noise(1024));
+        data.extend_from_slice(&GENERATED[3072..]);
+        assert!(validate(&data).is_err());
+
+        let search = SpliceSearch {
+            base: 0,
+            block_size: 512,
+            max_gap: 4096,
+        };
+        let splice = find_splice(&data, &search).expect("splice");
+        assert_eq!((splice.split, splice.gap), (3072, 1024));
+        assert_eq!(splice.len, GENERATED.len());
+        assert_eq!(splice.join(&data), GENERATED);
+    }
+}
@@ -0,0 +1,193 @@
+//! # Post-carve Validation
+//!
+//! Deep structural validation of carved output files. Carve handlers only
+//! check enough structure to find the end of a file; this stage re-opens the
+//! written file and walks its internal structures (JPEG entropy-coded data,
+//! PNG chunk CRCs, ZIP central directory, PDF xref, SQLite header/pages).
+//!
+//! The JPEG, PNG and ZIP validators can also look for the single gap that
+//! repairs a broken file (`find_splice`), for bifragment carving.
+
+pub mod jpeg;
+pub mod pdf;
+pub mod png;
+pub mod sqlite;
+pub mod zip;
+
+use std::path::Path;
+
+/// Verdict of a deep validation pass over a carved file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ValidationOutcome {
+    /// The file structure is consistent.
+    Passed,
+    /// The file structure is broken; the reason explains the first failure.
+    Failed(String),
+    /// No deep validator exists for this file type.
+    Unsupported,
+}
+
+impl ValidationOutcome {
+    /// Status label recorded in metadata.
+    pub fn status(&self) -> &'static str {
+        match self {
+            ValidationOutcome::Passed => "passed",
+            ValidationOutcome::Failed(_) => "failed",
+            ValidationOutcome::Unsupported => "unsupported",
+        }
+    }
+
+    pub fn reason(&self) -> Option<&str> {
+        match self {
+            ValidationOutcome::Failed(reason) => Some(reason.as_str()),
+            _ => None,
+        }
+    }
+}
+
+/// Validate a carved file on disk according to its (possibly reclassified) file type.
+pub fn validate_file(path: &Path, file_type: &str) -> ValidationOutcome {
+    let result = match file_type {
+        "jpeg" | "jpg" => read_file(path).and_then(|data| jpeg::validate(&data)),
+        "png" => read_file(path).and_then(|data| png::validate(&data)),
+        "zip" | "docx" | "xlsx" | "pptx" | "odt" | "ods" | "odp" | "epub" => {
+            read_file(path).and_then(|data| zip::validate(&data))
+        }
+        "pdf" => read_file(path).and_then(|data| pdf::validate(&data)),
+        "sqlite" => sqlite::validate(path),
+        _ => return ValidationOutcome::Unsupported,
+    };
+    match result {
+        Ok(()) => ValidationOutcome::Passed,
+        Err(reason) => ValidationOutcome::Failed(reason),
+    }
+}
+
+/// How far before the point where decoding fails a fragment break is
+/// looked for; JPEG Huffman and deflate decoding of unrelated data rarely
+/// survive longer.
+pub(crate) const SPLIT_WINDOW: usize = 32 * 1024;
+
+/// Bounds of a bifragment gap search. Fragments begin and end on block
+/// boundaries of the evidence, so candidate break points are offsets whose
+/// evidence position is a multiple of `block_size`, and gaps are whole
+/// blocks up to `max_gap`.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct SpliceSearch {
+    /// Evidence offset of the first byte of the searched data.
+    pub base: u64,
+    pub block_size: usize,
+    pub max_gap: usize,
+}
+
+impl SpliceSearch {
+    /// Block-aligned break points in `low..=high`, latest first.
+    pub(crate) fn splits(&self, low: usize, high: usize) -> impl Iterator<Item = usize> + use<> {
+        let block = self.block_size.max(1) as u64;
+        let misalign = (self.base + high as u64) % block;
+        let latest = (high as u64).checked_sub(misalign);
+        let count = latest
+            .filter(|latest| *latest >= low as u64)
+            .map_or(0, |latest| (latest - low as u64) / block + 1);
+        (0..count).map(move |i| (latest.unwrap_or(0) - i * block) as usize)
+    }
+
+    /// Gap lengths to try, shortest first.
+    pub(crate) fn gaps(&self) -> impl Iterator<Item = usize> + use<> {
+        let block = self.block_size.max(1);
+        (1..=self.max_gap / block).map(move |n| n * block)
+    }
+}
+
+/// A file stored as `data[..split]` followed, after `gap` unrelated bytes,
+/// by the rest of it; `len` is the length of the reassembled file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Splice {
+    pub split: usize,
+    pub gap: usize,
+    pub len: usize,
+}
+
+impl Splice {
+    /// The reassembled file.
+    pub(crate) fn join(&self, data: &[u8]) -> Vec<u8> {
+        let tail = self.split + self.gap;
+        let mut out = Vec::with_capacity(self.len);
+        out.extend_from_slice(&data[..self.split]);
+        out.extend_from_slice(&data[tail..tail + (self.len - self.split)]);
+        out
+    }
+}
+
+#[cfg(test)]
+/// Deterministic filler standing in for an unrelated file in the gap.
+pub(crate) fn noise(len: usize) -> Vec<u8> {
+    let mut seed = 0x2545_F491u32;
+    (0..len)
+        .map(|_| {
+            seed ^= seed << 13;
+            seed ^= seed >> 17;
+            seed ^= seed << 5;
+            seed as u8
+        })
+        .collect()
+}
+
+fn read_file(path: &Path) -> Result<Vec<u8>, String> {
+    std::fs::read(path).map_err(|err| format!("unable to read carved file: {err}"))
+}
+
+pub(crate) fn read_u16_be(data: &[u8], offset: usize) -> Option<u16> {
+    let bytes = data.get(offset..offset + 2)?;
+    Some(u16::from_be_bytes([bytes[0], bytes[1]]))
+}
+
+pub(crate) fn read_u32_be(data: &[u8], offset: usize) -> Option<u32> {
+    let bytes = data.get(offset..offset + 4)?;
+    Some(u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+}
+
+pub(crate) fn read_u16_le(data: &[u8], offset: usize) -> Option<u16> {
+    let bytes = data.get(offset..offset + 2)?;
+    Some(u16::from_le_bytes([bytes[0], bytes[1]]))
+}
+
+pub(crate) fn read_u32_le(data: &[u8], offset: usize) -> Option<u32> {
+    let bytes = data.get(offset..offset + 4)?;
+    Some(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{SpliceSearch, ValidationOutcome, validate_file};
+
+    #[test]
+    fn splice_candidates_are_block_aligned() {
+        let search = SpliceSearch {
+            base: 1000,
+            block_size: 512,
+            max_gap: 1536,
+        };
+        // Evidence offsets 1024 and 1536 fall inside data offsets 0..=600.
+        assert_eq!(search.splits(0, 600).collect::<Vec<_>>(), vec![536, 24]);
+        assert_eq!(search.splits(100, 600).collect::<Vec<_>>(), vec![536]);
+        assert_eq!(search.splits(0, 20).count(), 0);
+        assert_eq!(search.gaps().collect::<Vec<_>>(), vec![512, 1024, 1536]);
+    }
+
+    #[test]
+    fn unknown_types_are_unsupported() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let path = dir.path().join("file.bin");
+        std::fs::write(&path, b"data").expect("write");
+        assert_eq!(validate_file(&path, "wav"), ValidationOutcome::Unsupported);
+    }
+
+    #[test]
+    fn missing_file_fails() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let outcome = validate_file(&dir.path().join("missing.png"), "png");
+        assert_eq!(outcome.status(), "failed");
+        assert!(outcome.reason().unwrap_or("").contains("unable to read"));
+    }
+}
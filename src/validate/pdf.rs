@@ -0,0 +1,319 @@
+use std::io::Read;
+
+use memchr::memmem;
+
+/// Maximum number of `/Prev` hops followed, guarding against xref loops.
+const MAX_XREF_SECTIONS: usize = 64;
+const STARTXREF_WINDOW: usize = 2048;
+
+/// Follow `startxref` to the cross-reference table or stream (and any `/Prev`
+/// sections) and require every in-use entry to point at an `N G obj` header.
+pub fn validate(data: &[u8]) -> Result<(), String> {
+    if !data.starts_with(b"%PDF-") {
+        return Err("pdf header missing".to_string());
+    }
+    let tail_start = data.len().saturating_sub(STARTXREF_WINDOW);
+    let marker = memmem::rfind(&data[tail_start..], b"startxref")
+        .map(|pos| tail_start + pos)
+        .ok_or_else(|| "pdf startxref missing".to_string())?;
+    if memmem::find(&data[marker..], b"%%EOF").is_none() {
+        return Err("pdf %%EOF missing after startxref".to_string());
+    }
+    let (offset, _) = parse_uint(data, skip_ws(data, marker + 9))
+        .ok_or_else(|| "pdf startxref offset unreadable".to_string())?;
+
+    let mut next = Some(offset as usize);
+    let mut sections = 0usize;
+    while let Some(offset) = next {
+        sections += 1;
+        if sections > MAX_XREF_SECTIONS {
+            return Err("pdf xref chain too long".to_string());
+        }
+        if offset >= data.len() {
+            return Err(format!("pdf xref offset {offset} beyond end of file"));
+        }
+        next = if data[offset..].starts_with(b"xref") {
+            check_xref_table(data, offset)?
+        } else {
+            check_xref_stream(data, offset)?
+        };
+    }
+    Ok(())
+}
+
+/// Validate a classic `xref` table and return the `/Prev` offset from its trailer.
+fn check_xref_table(data: &[u8], offset: usize) -> Result<Option<usize>, String> {
+    let mut pos = skip_ws(data, offset + 4);
+    loop {
+        if data[pos..].starts_with(b"trailer") {
+            let dict_end = memmem::find(&data[pos..], b">>")
+                .map(|end| pos + end)
+                .ok_or_else(|| "pdf trailer dictionary unterminated".to_string())?;
+            let trailer = &data[pos..dict_end];
+            return Ok(dict_uint(trailer, b"/Prev").map(|v| v as usize));
+        }
+        let (start, after) = parse_uint(data, pos)
+            .ok_or_else(|| format!("pdf xref subsection header unreadable at offset {pos}"))?;
+        let (count, after) = parse_uint(data, skip_ws(data, after))
+            .ok_or_else(|| format!("pdf xref subsection header unreadable at offset {pos}"))?;
+        pos = skip_ws(data, after);
+        for idx in 0..count {
+            let entry = data
+                .get(pos..pos + 18)
+                .ok_or_else(|| "pdf xref table truncated".to_string())?;
+            let (obj_offset, _) =
+                parse_uint(entry, 0).ok_or_else(|| format!("pdf xref entry at {pos} malformed"))?;
+            let (generation, _) = parse_uint(entry, 11)
+                .ok_or_else(|| format!("pdf xref entry at {pos} malformed"))?;
+            match entry[17] {
+                b'n' => check_object_header(data, obj_offset as usize, start + idx, generation)?,
+                b'f' => {}
+                other => {
+                    return Err(format!(
+                        "pdf xref entry at {pos} has type '{}'",
+                        other as char
+                    ));
+                }
+            }
+            pos = skip_ws(data, pos + 18);
+        }
+    }
+}
+
+/// Validate a cross-reference stream (PDF 1.5+) and return its `/Prev` offset.
+fn check_xref_stream(data: &[u8], offset: usize) -> Result<Option<usize>, String> {
+    let (number, after) = parse_uint(data, offset)
+        .ok_or_else(|| format!("pdf startxref does not point at xref (offset {offset})"))?;
+    let (generation, _) = parse_uint(data, skip_ws(data, after))
+        .ok_or_else(|| format!("pdf startxref does not point at xref (offset {offset})"))?;
+    check_object_header(data, offset, number, generation)?;
+
+    let stream_kw = memmem::find(&data[offset..], b"stream")
+        .map(|pos| offset + pos)
+        .ok_or_else(|| "pdf xref stream keyword missing".to_string())?;
+    let dict = &data[offset..stream_kw];
+    if memmem::find(dict, b"/XRef").is_none() {
+        return Err(format!("pdf object at {offset} is not an xref stream"));
+    }
+    let length = dict_uint(dict, b"/Length")
+        .ok_or_else(|| "pdf xref stream /Length missing".to_string())? as usize;
+    let mut body_start = stream_kw + 6;
+    if data.get(body_start) == Some(&b'\r') {
+        body_start += 1;
+    }
+    if data.get(body_start) == Some(&b'\n') {
+        body_start += 1;
+    }
+    let raw = data
+        .get(body_start..body_start + length)
+        .ok_or_else(|| "pdf xref stream truncated".to_string())?;
+
+    let decoded = if memmem::find(dict, b"/FlateDecode").is_some() {
+        let mut out = Vec::new();
+        flate2::read::ZlibDecoder::new(raw)
+            .read_to_end(&mut out)
+            .map_err(|err| format!("pdf xref stream inflate failed: {err}"))?;
+        out
+    } else if memmem::find(dict, b"/Filter").is_some() {
+        // Other filters are rare for xref streams; keep the structural checks only.
+        return Ok(dict_uint(dict, b"/Prev").map(|v| v as usize));
+    } else {
+        raw.to_vec()
+    };
+
+    let widths = dict_array(dict, b"/W").ok_or_else(|| "pdf xref stream /W missing".to_string())?;
+    if widths.len() != 3 || widths.iter().any(|&w| w > 8) {
+        return Err("pdf xref stream /W malformed".to_string());
+    }
+    let row_len: usize = widths.iter().map(|&w| w as usize).sum();
+    if row_len == 0 {
+        return Err("pdf xref stream /W malformed".to_string());
+    }
+    let rows = match dict_uint(dict, b"/Predictor") {
+        Some(predictor) if predictor >= 10 => unpredict_png(&decoded, row_len)?,
+        _ => decoded,
+    };
+    let index = match dict_array(dict, b"/Index") {
+        Some(index) if index.len().is_multiple_of(2) => index,
+        Some(_) => return Err("pdf xref stream /Index malformed".to_string()),
+        None => vec![
+            0,
+            dict_uint(dict, b"/Size").ok_or_else(|| "pdf xref stream /Size missing".to_string())?,
+        ],
+    };
+
+    let mut row = 0usize;
+    for pair in index.chunks(2) {
+        for idx in 0..pair[1] {
+            let entry = rows
+                .get(row * row_len..(row + 1) * row_len)
+                .ok_or_else(|| "pdf xref stream shorter than /Index".to_string())?;
+            row += 1;
+            let (w0, w1) = (widths[0] as usize, widths[1] as usize);
+            let kind = if w0 == 0 { 1 } else { be_uint(&entry[..w0]) };
+            let field2 = be_uint(&entry[w0..w0 + w1]);
+            let field3 = be_uint(&entry[w0 + w1..]);
+            if kind == 1 {
+                check_object_header(data, field2 as usize, pair[0] + idx, field3)?;
+            }
+        }
+    }
+    Ok(dict_uint(dict, b"/Prev").map(|v| v as usize))
+}
+
+fn check_object_header(
+    data: &[u8],
+    offset: usize,
+    number: u64,
+    generation: u64,
+) -> Result<(), String> {
+    let mismatch = || {
+        format!(
+            "pdf xref entry for object {number} {generation} does not point at its object (offset {offset})"
+        )
+    };
+    let (found_number, after) = parse_uint(data, offset).ok_or_else(mismatch)?;
+    let (found_gen, after) = parse_uint(data, skip_ws(data, after)).ok_or_else(mismatch)?;
+    let after = skip_ws(data, after);
+    if found_number != number || found_gen != generation || !data[after..].starts_with(b"obj") {
+        return Err(mismatch());
+    }
+    Ok(())
+}
+
+/// Reverse PNG row predictors used by xref streams (`/Predictor` 10-15).
+fn unpredict_png(data: &[u8], columns: usize) -> Result<Vec<u8>, String> {
+    let stride = columns + 1;
+    if !data.len().is_multiple_of(stride) {
+        return Err("pdf xref stream predictor rows misaligned".to_string());
+    }
+    let mut out = Vec::with_capacity(data.len() / stride * columns);
+    let mut prev = vec![0u8; columns];
+    for chunk in data.chunks(stride) {
+        let filter = chunk[0];
+        let mut row = chunk[1..].to_vec();
+        for i in 0..columns {
+            let left = if i > 0 { row[i - 1] } else { 0 };
+            let up = prev[i];
+            let up_left = if i > 0 { prev[i - 1] } else { 0 };
+            row[i] = match filter {
+                0 => row[i],
+                1 => row[i].wrapping_add(left),
+                2 => row[i].wrapping_add(up),
+                3 => row[i].wrapping_add(((left as u16 + up as u16) / 2) as u8),
+                4 => row[i].wrapping_add(paeth(left, up, up_left)),
+                _ => return Err(format!("pdf xref stream predictor filter {filter}")),
+            };
+        }
+        out.extend_from_slice(&row);
+        prev = row;
+    }
+    Ok(out)
+}
+
+fn paeth(a: u8, b: u8, c: u8) -> u8 {
+    let p = a as i16 + b as i16 - c as i16;
+    let (pa, pb, pc) = (
+        (p - a as i16).abs(),
+        (p - b as i16).abs(),
+        (p - c as i16).abs(),
+    );
+    if pa <= pb && pa <= pc {
+        a
+    } else if pb <= pc {
+        b
+    } else {
+        c
+    }
+}
+
+fn be_uint(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0u64, |acc, &b| (acc << 8) | b as u64)
+}
+
+fn skip_ws(data: &[u8], mut pos: usize) -> usize {
+    while pos < data.len() && matches!(data[pos], b' ' | b'\t' | b'\r' | b'\n' | b'\x0C' | b'\0') {
+        pos += 1;
+    }
+    pos
+}
+
+fn parse_uint(data: &[u8], pos: usize) -> Option<(u64, usize)> {
+    let digits = data
+        .get(pos..)?
+        .iter()
+        .take_while(|b| b.is_ascii_digit())
+        .count();
+    if digits == 0 || digits > 19 {
+        return None;
+    }
+    let text = std::str::from_utf8(&data[pos..pos + digits]).ok()?;
+    Some((text.parse().ok()?, pos + digits))
+}
+
+/// Integer value following `key` in a dictionary, e.g. `/Size 12`.
+fn dict_uint(dict: &[u8], key: &[u8]) -> Option<u64> {
+    let pos = find_key(dict, key)?;
+    parse_uint(dict, skip_ws(dict, pos + key.len())).map(|(value, _)| value)
+}
+
+/// Integer array following `key`, e.g. `/W [1 2 1]`.
+fn dict_array(dict: &[u8], key: &[u8]) -> Option<Vec<u64>> {
+    let pos = skip_ws(dict, find_key(dict, key)? + key.len());
+    if dict.get(pos) != Some(&b'[') {
+        return None;
+    }
+    let end = pos + dict[pos..].iter().position(|&b| b == b']')?;
+    let mut values = Vec::new();
+    let mut cursor = skip_ws(dict, pos + 1);
+    while cursor < end {
+        let (value, after) = parse_uint(dict, cursor)?;
+        values.push(value);
+        cursor = skip_ws(dict, after);
+    }
+    Some(values)
+}
+
+/// Locate `key` as a whole name (so `/Prev` does not match `/PrevHash`).
+fn find_key(dict: &[u8], key: &[u8]) -> Option<usize> {
+    memmem::find_iter(dict, key).find(|&pos| {
+        dict.get(pos + key.len())
+            .is_none_or(|b| !b.is_ascii_alphanumeric())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::validate;
+
+    fn sample_pdf() -> Vec<u8> {
+        let mut data = b"%PDF-1.4\n".to_vec();
+        let obj1 = data.len();
+        data.extend_from_slice(b"1 0 obj\n<< /Type /Catalog >>\nendobj\n");
+        let xref = data.len();
+        data.extend_from_slice(b"xref\n0 2\n");
+        data.extend_from_slice(b"0000000000 65535 f \n");
+        data.extend_from_slice(format!("{obj1:010} 00000 n \n").as_bytes());
+        data.extend_from_slice(b"trailer\n<< /Size 2 /Root 1 0 R >>\n");
+        data.extend_from_slice(format!("startxref\n{xref}\n%%EOF\n").as_bytes());
+        data
+    }
+
+    #[test]
+    fn accepts_valid_xref_table() {
+        assert_eq!(validate(&sample_pdf()), Ok(()));
+    }
+
+    #[test]
+    fn rejects_bad_object_offset() {
+        let text = String::from_utf8(sample_pdf()).expect("utf8");
+        let broken = text.replace("0000000009 00000 n", "0000000012 00000 n");
+        let err = validate(broken.as_bytes()).expect_err("bad offset");
+        assert!(err.contains("object 1 0"));
+    }
+
+    #[test]
+    fn rejects_missing_startxref() {
+        assert!(validate(b"%PDF-1.4\n1 0 obj\n<<>>\nendobj\n%%EOF\n").is_err());
+    }
+}
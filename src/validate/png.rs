@@ -0,0 +1,266 @@
+use super::{Splice, SpliceSearch, read_u32_be};
+
+const PNG_SIGNATURE: [u8; 8] = [0x89, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A, 0x0A];
+
+/// Walk every chunk, verifying lengths and CRC-32 values, and require the
+/// IHDR → IDAT → IEND ordering.
+pub fn validate(data: &[u8]) -> Result<(), String> {
+    walk(data).map(|_| ())
+}
+
+/// Find the gap that repairs a PNG whose chunk walk breaks after IHDR. The
+/// first chunk with a bad header or CRC holds the break; a trial gap must
+/// put a plausible chunk header right after that chunk's CRC before the
+/// CRC itself is checked, and the chunks after it must run to IEND before
+/// the candidate is assembled.
+pub(crate) fn find_splice(data: &[u8], search: &SpliceSearch) -> Option<Splice> {
+    let start = first_bad_chunk(data)?;
+    let low = start.max(PNG_SIGNATURE.len() + 25);
+    // With an intact header the break lies in the chunk's data or CRC;
+    // otherwise in the header itself.
+    let high = match read_chunk(data, start) {
+        Some(chunk) if chunk.chunk_type.iter().all(u8::is_ascii_alphabetic) => chunk.next,
+        _ => start + 8,
+    }
+    .min(data.len());
+    let splits: Vec<usize> = search.splits(low, high).collect();
+    for gap in search.gaps() {
+        for &split in &splits {
+            if split + gap >= data.len() {
+                continue;
+            }
+            // The chunks after the broken one lie wholly past the gap
+            let Some(end) = plausible_splice(data, start, split, gap)
+                .and_then(|next| chunks_end(data, next + gap))
+            else {
+                continue;
+            };
+            let candidate = Splice {
+                split,
+                gap,
+                len: end - gap,
+            }
+            .join(data);
+            if let Ok(len) = walk(&candidate) {
+                return Some(Splice { split, gap, len });
+            }
+        }
+    }
+    None
+}
+
+/// Offset of the first chunk after IHDR that is malformed, or `None` when
+/// the walk reaches IEND or breaks before IHDR is read.
+fn first_bad_chunk(data: &[u8]) -> Option<usize> {
+    if data.get(..PNG_SIGNATURE.len()) != Some(PNG_SIGNATURE.as_slice()) {
+        return None;
+    }
+    let mut pos = PNG_SIGNATURE.len();
+    let mut index = 0usize;
+    loop {
+        let good = read_chunk(data, pos)
+            .filter(|chunk| chunk.crc_ok && chunk.chunk_type.iter().all(u8::is_ascii_alphabetic));
+        match good {
+            Some(chunk) if index == 0 && &chunk.chunk_type != b"IHDR" => return None,
+            Some(chunk) if &chunk.chunk_type == b"IEND" => return None,
+            Some(chunk) => pos = chunk.next,
+            None if index == 0 => return None,
+            None => return Some(pos),
+        }
+        index += 1;
+    }
+}
+
+/// Cheap test of a trial splice: the chunk at `start` must have a valid
+/// CRC and be followed by a chunk header with a letter type. Returns the
+/// offset of that following chunk in the spliced file.
+fn plausible_splice(data: &[u8], start: usize, split: usize, gap: usize) -> Option<usize> {
+    let at = |pos: usize| if pos < split { pos } else { pos + gap };
+    let byte = |pos: usize| data.get(at(pos)).copied();
+    let word = |pos: usize| -> Option<u32> {
+        Some(u32::from_be_bytes([
+            byte(pos)?,
+            byte(pos + 1)?,
+            byte(pos + 2)?,
+            byte(pos + 3)?,
+        ]))
+    };
+    let crc_pos = start + 8 + word(start)? as usize;
+    let next_type = crc_pos + 8;
+    let next_ok =
+        (next_type..next_type + 4).all(|pos| byte(pos).is_some_and(|b| b.is_ascii_alphabetic()));
+    if !next_ok {
+        return None;
+    }
+    let stored_crc = word(crc_pos)?;
+    let mut hasher = crc32fast::Hasher::new();
+    let body = start + 4..crc_pos;
+    if split > body.start {
+        hasher.update(&data[body.start..split.min(body.end)]);
+    }
+    if split < body.end {
+        hasher.update(&data[at(split.max(body.start))..at(body.end - 1) + 1]);
+    }
+    (stored_crc == hasher.finalize()).then_some(crc_pos + 4)
+}
+
+/// Offset just past IEND when the chunks from `pos` are whole, with letter
+/// types and good CRCs.
+fn chunks_end(data: &[u8], mut pos: usize) -> Option<usize> {
+    loop {
+        let chunk = read_chunk(data, pos)
+            .filter(|chunk| chunk.crc_ok && chunk.chunk_type.iter().all(u8::is_ascii_alphabetic))?;
+        if &chunk.chunk_type == b"IEND" {
+            return Some(chunk.next);
+        }
+        pos = chunk.next;
+    }
+}
+
+/// Walk the chunks and return the offset just past IEND.
+fn walk(data: &[u8]) -> Result<usize, String> {
+    if data.len() < PNG_SIGNATURE.len() || data[..PNG_SIGNATURE.len()] != PNG_SIGNATURE {
+        return Err("png signature mismatch".to_string());
+    }
+
+    let mut pos = PNG_SIGNATURE.len();
+    let mut index = 0usize;
+    let mut seen_idat = false;
+    loop {
+        let chunk = read_chunk(data, pos)
+            .ok_or_else(|| format!("png chunk {index} truncated at offset {pos}"))?;
+        if index == 0 && &chunk.chunk_type != b"IHDR" {
+            return Err("png first chunk is not IHDR".to_string());
+        }
+        if !chunk.chunk_type.iter().all(|b| b.is_ascii_alphabetic()) {
+            return Err(format!(
+                "png chunk {index} has invalid type at offset {pos}"
+            ));
+        }
+        if !chunk.crc_ok {
+            return Err(format!(
+                "png chunk {} ({}) crc mismatch at offset {pos}",
+                index,
+                String::from_utf8_lossy(&chunk.chunk_type)
+            ));
+        }
+        match &chunk.chunk_type {
+            b"IHDR" if chunk.length != 13 => {
+                return Err("png IHDR length is not 13".to_string());
+            }
+            b"IDAT" => seen_idat = true,
+            b"IEND" => {
+                if !seen_idat {
+                    return Err("png has no IDAT chunk".to_string());
+                }
+                return Ok(chunk.next);
+            }
+            _ => {}
+        }
+        pos = chunk.next;
+        index += 1;
+    }
+}
+
+pub(crate) struct PngChunk {
+    pub length: u32,
+    pub chunk_type: [u8; 4],
+    pub crc_ok: bool,
+    /// Offset of the following chunk.
+    pub next: usize,
+}
+
+/// Parse the chunk starting at `pos`, returning `None` when it runs past the data.
+pub(crate) fn read_chunk(data: &[u8], pos: usize) -> Option<PngChunk> {
+    let length = read_u32_be(data, pos)?;
+    let type_start = pos + 4;
+    let data_end = type_start.checked_add(4)?.checked_add(length as usize)?;
+    let stored_crc = read_u32_be(data, data_end)?;
+    let chunk_type = [
+        data[type_start],
+        data[type_start + 1],
+        data[type_start + 2],
+        data[type_start + 3],
+    ];
+    let crc = crc32fast::hash(&data[type_start..data_end]);
+    Some(PngChunk {
+        length,
+        chunk_type,
+        crc_ok: crc == stored_crc,
+        next: data_end + 4,
+    })
+}
+
+#[cfg(test)]
+pub(crate) fn build_chunk(chunk_type: &[u8; 4], payload: &[u8]) -> Vec<u8> {
+    let mut out = Vec::new();
+    out.extend_from_slice(&(payload.len() as u32).to_be_bytes());
+    out.extend_from_slice(chunk_type);
+    out.extend_from_slice(payload);
+    let mut crc_input = chunk_type.to_vec();
+    crc_input.extend_from_slice(payload);
+    out.extend_from_slice(&crc32fast::hash(&crc_input).to_be_bytes());
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{PNG_SIGNATURE, build_chunk, find_splice, validate};
+    use crate::validate::{SpliceSearch, noise};
+
+    fn sample_png() -> Vec<u8> {
+        let mut data = PNG_SIGNATURE.to_vec();
+        data.extend(build_chunk(
+            b"IHDR",
+            &[0, 0, 0, 1, 0, 0, 0, 1, 8, 2, 0, 0, 0],
+        ));
+        data.extend(build_chunk(b"IDAT", &[0x78, 0x9C, 0x63, 0x00, 0x00]));
+        data.extend(build_chunk(b"IEND", &[]));
+        data
+    }
+
+    #[test]
+    fn accepts_valid_png() {
+        assert_eq!(validate(&sample_png()), Ok(()));
+    }
+
+    #[test]
+    fn rejects_crc_mismatch() {
+        let mut data = sample_png();
+        // Corrupt one byte of the IDAT payload.
+        data[8 + 25 + 8] ^= 0xFF;
+        let err = validate(&data).expect_err("crc mismatch");
+        assert!(err.contains("IDAT"));
+        assert!(err.contains("crc"));
+    }
+
+    #[test]
+    fn rejects_truncated_png() {
+        let data = sample_png();
+        assert!(validate(&data[..data.len() - 6]).is_err());
+    }
+
+    #[test]
+    fn finds_gap_inside_idat() {
+        let mut image = PNG_SIGNATURE.to_vec();
+        image.extend(build_chunk(
+            b"IHDR",
+            &[0, 0, 0, 64, 0, 0, 0, 16, 8, 2, 0, 0, 0],
+        ));
+        image.extend(build_chunk(b"IDAT", &noise(3000)));
+        image.extend(build_chunk(b"IEND", &[]));
+        let mut data = image[..2048].to_vec();
+        data.extend(noise(1024));
+        data.extend_from_slice(&image[2048..]);
+        assert!(validate(&data).is_err());
+
+        let search = SpliceSearch {
+            base: 0,
+            block_size: 512,
+            max_gap: 4096,
+        };
+        let splice = find_splice(&data, &search).expect("splice");
+        assert_eq!((splice.split, splice.gap), (2048, 1024));
+        assert_eq!(splice.join(&data), image);
+    }
+}
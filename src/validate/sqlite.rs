@@ -0,0 +1,146 @@
+use std::path::Path;
+
+use rusqlite::{Connection, OpenFlags};
+
+use super::{read_u16_be, read_u32_be};
+
+const SQLITE_HEADER: &[u8; 16] = b"SQLite format 3\0";
+
+/// Check the database header and page geometry, then let SQLite run
+/// `PRAGMA quick_check` over the b-tree pages.
+pub fn validate(path: &Path) -> Result<(), String> {
+    let data = std::fs::read(path).map_err(|err| format!("unable to read carved file: {err}"))?;
+    let wal_mode = check_header(&data)?;
+    if wal_mode {
+        // Read-only connections to WAL databases need a -shm file we do not have.
+        return Ok(());
+    }
+    quick_check(path)
+}
+
+/// Validate the 100-byte header and page 1; returns true for WAL-mode databases.
+fn check_header(data: &[u8]) -> Result<bool, String> {
+    if data.len() < 100 || &data[..SQLITE_HEADER.len()] != SQLITE_HEADER {
+        return Err("sqlite header mismatch".to_string());
+    }
+    let page_size = match read_u16_be(data, 16).unwrap_or(0) {
+        1 => 65536u64,
+        raw => raw as u64,
+    };
+    if !(512..=65536).contains(&page_size) || !page_size.is_power_of_two() {
+        return Err(format!("sqlite page size {page_size} invalid"));
+    }
+    let (write_version, read_version) = (data[18], data[19]);
+    if !matches!(write_version, 1 | 2) || !matches!(read_version, 1 | 2) {
+        return Err("sqlite file format version invalid".to_string());
+    }
+    if data[21] != 64 || data[22] != 32 || data[23] != 32 {
+        return Err("sqlite payload fractions invalid".to_string());
+    }
+    if data[20] as u64 > page_size.saturating_sub(480) {
+        return Err("sqlite reserved space too large".to_string());
+    }
+
+    let file_len = data.len() as u64;
+    if !file_len.is_multiple_of(page_size) {
+        return Err(format!(
+            "sqlite file size {file_len} is not a multiple of page size {page_size}"
+        ));
+    }
+    let change_counter = read_u32_be(data, 24).unwrap_or(0);
+    let valid_for = read_u32_be(data, 92).unwrap_or(0);
+    let page_count = read_u32_be(data, 28).unwrap_or(0) as u64;
+    if page_count > 0 && change_counter == valid_for && page_count * page_size != file_len {
+        return Err(format!(
+            "sqlite page count {page_count} does not match file size {file_len}"
+        ));
+    }
+
+    match data[100] {
+        0x05 | 0x0D => {}
+        other => {
+            return Err(format!(
+                "sqlite page 1 has invalid b-tree type 0x{other:02X}"
+            ));
+        }
+    }
+    Ok(write_version == 2 || read_version == 2)
+}
+
+fn quick_check(path: &Path) -> Result<(), String> {
+    let conn = Connection::open_with_flags(
+        path,
+        OpenFlags::SQLITE_OPEN_READ_ONLY | OpenFlags::SQLITE_OPEN_NO_MUTEX,
+    )
+    .map_err(|err| format!("sqlite open failed: {err}"))?;
+    let mut stmt = conn
+        .prepare("PRAGMA quick_check")
+        .map_err(|err| format!("sqlite quick_check failed: {err}"))?;
+    let mut rows = stmt
+        .query([])
+        .map_err(|err| format!("sqlite quick_check failed: {err}"))?;
+    let mut problems = Vec::new();
+    while let Some(row) = rows
+        .next()
+        .map_err(|err| format!("sqlite quick_check failed: {err}"))?
+    {
+        let message: String = row.get(0).unwrap_or_default();
+        if message != "ok" {
+            problems.push(message);
+        }
+    }
+    if problems.is_empty() {
+        Ok(())
+    } else {
+        Err(format!("sqlite quick_check: {}", problems.join("; ")))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{check_header, validate};
+
+    fn write_db(path: &std::path::Path) {
+        let conn = rusqlite::Connection::open(path).expect("open");
+        conn.execute_batch(
+            "PRAGMA page_size = 512;
+             CREATE TABLE t (id INTEGER PRIMARY KEY, v TEXT);
+             INSERT INTO t (v) VALUES ('a'), ('b');",
+        )
+        .expect("create");
+    }
+
+    #[test]
+    fn accepts_valid_database() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let path = dir.path().join("db.sqlite");
+        write_db(&path);
+        assert_eq!(validate(&path), Ok(()));
+    }
+
+    #[test]
+    fn rejects_truncated_database() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let path = dir.path().join("db.sqlite");
+        write_db(&path);
+        let data = std::fs::read(&path).expect("read");
+        std::fs::write(&path, &data[..data.len() - 100]).expect("write");
+        let err = validate(&path).expect_err("truncated");
+        assert!(err.contains("page size"));
+    }
+
+    #[test]
+    fn allows_reserved_space_down_to_480_usable_bytes() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let path = dir.path().join("db.sqlite");
+        write_db(&path);
+        let mut data = std::fs::read(&path).expect("read");
+        data[20] = 32;
+        assert_eq!(check_header(&data), Ok(false));
+        data[20] = 33;
+        assert_eq!(
+            check_header(&data),
+            Err("sqlite reserved space too large".to_string())
+        );
+    }
+}
@@ -0,0 +1,485 @@
+use flate2::{Decompress, FlushDecompress, Status};
+
+use super::{SPLIT_WINDOW, Splice, SpliceSearch, read_u16_le, read_u32_le};
+
+const EOCD_SIG: &[u8; 4] = b"PK\x05\x06";
+const CDH_SIG: &[u8; 4] = b"PK\x01\x02";
+const LFH_SIG: &[u8; 4] = b"PK\x03\x04";
+const ZIP64_LOCATOR_SIG: &[u8; 4] = b"PK\x06\x07";
+const ZIP64_EOCD_SIG: &[u8; 4] = b"PK\x06\x06";
+pub(crate) const EOCD_MIN_LEN: usize = 22;
+pub(crate) const MAX_COMMENT_LEN: usize = 0xFFFF;
+
+/// Walk EOCD → central directory → local headers and require every entry to
+/// resolve to a local header with a matching name.
+pub fn validate(data: &[u8]) -> Result<(), String> {
+    let eocd_pos =
+        find_eocd(data).ok_or_else(|| "zip end of central directory not found".to_string())?;
+    let (entries, cd_size, cd_offset) = read_directory_bounds(data, eocd_pos)?;
+
+    let cd_end = cd_offset
+        .checked_add(cd_size)
+        .filter(|end| *end <= data.len() as u64)
+        .ok_or_else(|| "zip central directory extends past end of file".to_string())?;
+    let mut pos = cd_offset as usize;
+    let mut seen = 0u64;
+    while seen < entries {
+        if data.get(pos..pos + 4) != Some(CDH_SIG.as_slice()) {
+            return Err(format!(
+                "zip central directory entry {seen} missing signature at offset {pos}"
+            ));
+        }
+        let name_len =
+            read_u16_le(data, pos + 28).ok_or("zip central directory truncated")? as usize;
+        let extra_len =
+            read_u16_le(data, pos + 30).ok_or("zip central directory truncated")? as usize;
+        let comment_len =
+            read_u16_le(data, pos + 32).ok_or("zip central directory truncated")? as usize;
+        let compressed = read_u32_le(data, pos + 20).ok_or("zip central directory truncated")?;
+        let local_offset = read_u32_le(data, pos + 42).ok_or("zip central directory truncated")?;
+        let name = data
+            .get(pos + 46..pos + 46 + name_len)
+            .ok_or("zip central directory name truncated")?;
+        let entry_end = pos + 46 + name_len + extra_len + comment_len;
+        if entry_end as u64 > cd_end {
+            return Err(format!(
+                "zip central directory entry {seen} overruns directory"
+            ));
+        }
+
+        // ZIP64 entries keep the real values in the extra field; only the
+        // local header signature/name are checked for them.
+        let local = local_offset as usize;
+        if data.get(local..local + 4) != Some(LFH_SIG.as_slice()) {
+            return Err(format!(
+                "zip entry {} local header missing at offset {local}",
+                String::from_utf8_lossy(name)
+            ));
+        }
+        let local_name_len =
+            read_u16_le(data, local + 26).ok_or("zip local header truncated")? as usize;
+        let local_extra_len =
+            read_u16_le(data, local + 28).ok_or("zip local header truncated")? as usize;
+        let local_name = data
+            .get(local + 30..local + 30 + local_name_len)
+            .ok_or("zip local header name truncated")?;
+        if local_name != name {
+            return Err(format!(
+                "zip entry {} local header name mismatch",
+                String::from_utf8_lossy(name)
+            ));
+        }
+        if local_offset != u32::MAX && compressed != u32::MAX {
+            let data_end =
+                (local + 30 + local_name_len + local_extra_len) as u64 + compressed as u64;
+            if data_end > cd_offset {
+                return Err(format!(
+                    "zip entry {} data overlaps central directory",
+                    String::from_utf8_lossy(name)
+                ));
+            }
+        }
+
+        pos = entry_end;
+        seen += 1;
+    }
+    Ok(())
+}
+
+/// Find the gap that repairs a ZIP whose second fragment holds the end of
+/// central directory. The gap is the distance between where the EOCD is
+/// found and where its central directory offset says it belongs; local
+/// headers found before or after the gap bound the break, and the CRC of
+/// the entry the break falls in picks the exact block. Every local header
+/// must sit where the directory puts it before a candidate is assembled.
+pub(crate) fn find_splice(data: &[u8], search: &SpliceSearch) -> Option<Splice> {
+    let eocd_pos = find_eocd(data)?;
+    let cd_size = read_u32_le(data, eocd_pos + 12)?;
+    let cd_offset = read_u32_le(data, eocd_pos + 16)?;
+    let comment_len = read_u16_le(data, eocd_pos + 20)? as usize;
+    if cd_size == u32::MAX || cd_offset == u32::MAX {
+        return None;
+    }
+    let joined_eocd = cd_offset as usize + cd_size as usize;
+    let gap = eocd_pos.checked_sub(joined_eocd)?;
+    if gap == 0 || gap > search.max_gap || gap % search.block_size.max(1) != 0 {
+        return None;
+    }
+    let len = joined_eocd + EOCD_MIN_LEN + comment_len;
+
+    // A break before the central directory leaves it whole, right before
+    // the EOCD; otherwise the break is inside it.
+    let cd_start = eocd_pos.checked_sub(cd_size as usize)?;
+    let (mut low, mut high) = (cd_offset as usize, joined_eocd);
+    let mut straddling = None;
+    let mut entries = read_central_directory(&data[cd_start..eocd_pos]).unwrap_or_default();
+    entries.sort_by_key(|entry| entry.local_offset);
+    if !entries.is_empty() {
+        (low, high) = (0, cd_offset as usize);
+        for entry in &entries {
+            let offset = entry.local_offset;
+            if let Some(data_start) = local_data_start(data, offset, &entry.name) {
+                low = data_start;
+                straddling = Some((entry, data_start));
+            } else if local_data_start(data, offset + gap, &entry.name).is_some() {
+                high = offset;
+                break;
+            } else {
+                // The break falls inside this local header
+                low = offset + 1;
+                high = offset + 30 + entry.name.len();
+                straddling = None;
+                break;
+            }
+        }
+    }
+
+    let splits: Vec<usize> = match straddling {
+        Some((entry, data_start)) => {
+            let data_end = data_start + entry.compressed as usize;
+            let inside = entry_splits(
+                data,
+                search,
+                entry,
+                data_start,
+                gap,
+                low,
+                high.min(data_end),
+            );
+            inside
+                .into_iter()
+                .chain(search.splits(data_end.max(low), high))
+                .collect()
+        }
+        None => search.splits(low, high).collect(),
+    };
+    if len + gap > data.len() {
+        return None;
+    }
+    for split in splits {
+        if split + gap >= data.len() || !headers_in_place(data, &entries, split, gap) {
+            continue;
+        }
+        let splice = Splice { split, gap, len };
+        if validate(&splice.join(data)).is_ok() {
+            return Some(splice);
+        }
+    }
+    None
+}
+
+/// Whether the local header of every directory entry wholly before or
+/// after the break is found, for the entry's name, where the spliced file
+/// would have it.
+fn headers_in_place(data: &[u8], entries: &[DirectoryEntry], split: usize, gap: usize) -> bool {
+    entries.iter().all(|entry| {
+        let offset = entry.local_offset;
+        if offset >= split {
+            local_data_start(data, offset + gap, &entry.name).is_some()
+        } else if offset + 30 + entry.name.len() <= split {
+            local_data_start(data, offset, &entry.name).is_some()
+        } else {
+            true
+        }
+    })
+}
+
+/// A central directory record, as far as gap search and member extraction
+/// need it.
+pub(crate) struct DirectoryEntry {
+    pub name: Vec<u8>,
+    pub flags: u16,
+    pub method: u16,
+    /// MS-DOS modification time and date.
+    pub time: u16,
+    pub date: u16,
+    pub crc: u32,
+    pub compressed: u32,
+    pub uncompressed: u32,
+    pub local_offset: usize,
+}
+
+/// Parse the records of a central directory; `None` when one is malformed.
+pub(crate) fn read_central_directory(cd: &[u8]) -> Option<Vec<DirectoryEntry>> {
+    let mut entries = Vec::new();
+    let mut pos = 0usize;
+    while pos < cd.len() {
+        if cd.get(pos..pos + 4) != Some(CDH_SIG.as_slice()) {
+            return None;
+        }
+        let name_len = read_u16_le(cd, pos + 28)? as usize;
+        let extra_len = read_u16_le(cd, pos + 30)? as usize;
+        let comment_len = read_u16_le(cd, pos + 32)? as usize;
+        entries.push(DirectoryEntry {
+            name: cd.get(pos + 46..pos + 46 + name_len)?.to_vec(),
+            flags: read_u16_le(cd, pos + 8)?,
+            method: read_u16_le(cd, pos + 10)?,
+            time: read_u16_le(cd, pos + 12)?,
+            date: read_u16_le(cd, pos + 14)?,
+            crc: read_u32_le(cd, pos + 16)?,
+            compressed: read_u32_le(cd, pos + 20)?,
+            uncompressed: read_u32_le(cd, pos + 24)?,
+            local_offset: read_u32_le(cd, pos + 42)? as usize,
+        });
+        pos += 46 + name_len + extra_len + comment_len;
+    }
+    Some(entries)
+}
+
+/// Parse the local header at `offset` into the fields the central
+/// directory would hold, with the length of the header, name and extra
+/// field.
+pub(crate) fn read_local_header(data: &[u8], offset: usize) -> Option<(DirectoryEntry, usize)> {
+    if data.get(offset..offset + 4) != Some(LFH_SIG.as_slice()) {
+        return None;
+    }
+    let name_len = read_u16_le(data, offset + 26)? as usize;
+    let extra_len = read_u16_le(data, offset + 28)? as usize;
+    let entry = DirectoryEntry {
+        name: data.get(offset + 30..offset + 30 + name_len)?.to_vec(),
+        flags: read_u16_le(data, offset + 6)?,
+        method: read_u16_le(data, offset + 8)?,
+        time: read_u16_le(data, offset + 10)?,
+        date: read_u16_le(data, offset + 12)?,
+        crc: read_u32_le(data, offset + 14)?,
+        compressed: read_u32_le(data, offset + 18)?,
+        uncompressed: read_u32_le(data, offset + 22)?,
+        local_offset: offset,
+    };
+    Some((entry, 30 + name_len + extra_len))
+}
+
+/// Offset of the entry data when a local header for `name` sits at `offset`.
+pub(crate) fn local_data_start(data: &[u8], offset: usize, name: &[u8]) -> Option<usize> {
+    if data.get(offset..offset + 4) != Some(LFH_SIG.as_slice()) {
+        return None;
+    }
+    let name_len = read_u16_le(data, offset + 26)? as usize;
+    let extra_len = read_u16_le(data, offset + 28)? as usize;
+    (data.get(offset + 30..offset + 30 + name_len)? == name)
+        .then_some(offset + 30 + name_len + extra_len)
+}
+
+/// Break points inside the data of the entry the break falls in, for which
+/// the spliced data matches the entry's CRC-32.
+fn entry_splits(
+    data: &[u8],
+    search: &SpliceSearch,
+    entry: &DirectoryEntry,
+    data_start: usize,
+    gap: usize,
+    low: usize,
+    high: usize,
+) -> Vec<usize> {
+    let data_end = data_start + entry.compressed as usize;
+    if data_end + gap > data.len() {
+        return Vec::new();
+    }
+    match entry.method {
+        // Stored: the CRC of each trial is combined from prefix and suffix
+        // CRCs, so every block of the entry is tried in one pass
+        0 => {
+            let splits: Vec<usize> = search.splits(low, high).collect();
+            let mut suffixes = Vec::with_capacity(splits.len());
+            let mut suffix = crc32fast::Hasher::new();
+            let mut suffix_start = data_end;
+            for &split in &splits {
+                let mut hasher = crc32fast::Hasher::new();
+                hasher.update(&data[split + gap..suffix_start + gap]);
+                hasher.combine(&suffix);
+                suffix = hasher;
+                suffix_start = split;
+                suffixes.push(suffix.clone());
+            }
+            let mut prefix = crc32fast::Hasher::new();
+            let mut prefix_end = data_start;
+            let mut found = Vec::new();
+            for (&split, suffix) in splits.iter().zip(&suffixes).rev() {
+                prefix.update(&data[prefix_end..split]);
+                prefix_end = split;
+                let mut whole = prefix.clone();
+                whole.combine(suffix);
+                if whole.finalize() == entry.crc {
+                    found.push(split);
+                }
+            }
+            found.reverse();
+            found
+        }
+        // Deflated: unrelated data soon fails to inflate, so only breaks
+        // shortly before the contiguous inflate fails are tried
+        8 => {
+            let failed_at = data_start + inflate_crc(&data[data_start..data_end], entry).1;
+            let low = low.max(failed_at.saturating_sub(SPLIT_WINDOW));
+            search
+                .splits(low, high.min(failed_at))
+                .filter(|&split| {
+                    let mut compressed = data[data_start..split].to_vec();
+                    compressed.extend_from_slice(&data[split + gap..data_end + gap]);
+                    inflate_crc(&compressed, entry).0
+                })
+                .collect()
+        }
+        _ => Vec::new(),
+    }
+}
+
+/// Inflate raw deflate data; returns whether it matches the entry's size
+/// and CRC-32, and how much input was consumed before it stopped.
+fn inflate_crc(compressed: &[u8], entry: &DirectoryEntry) -> (bool, usize) {
+    let mut inflater = Decompress::new(false);
+    let mut hasher = crc32fast::Hasher::new();
+    let mut out = vec![0u8; 64 * 1024];
+    loop {
+        let in_before = inflater.total_in() as usize;
+        let out_before = inflater.total_out();
+        let status = inflater.decompress(&compressed[in_before..], &mut out, FlushDecompress::None);
+        let produced = (inflater.total_out() - out_before) as usize;
+        hasher.update(&out[..produced]);
+        let consumed = inflater.total_in() as usize;
+        match status {
+            Ok(Status::StreamEnd) => {
+                let ok = inflater.total_out() == entry.uncompressed as u64
+                    && hasher.finalize() == entry.crc;
+                return (ok, consumed);
+            }
+            Ok(_) if consumed > in_before || produced > 0 => {}
+            _ => return (false, consumed),
+        }
+    }
+}
+
+/// Offset of the last end of central directory record in `data`.
+pub(crate) fn find_eocd(data: &[u8]) -> Option<usize> {
+    if data.len() < EOCD_MIN_LEN {
+        return None;
+    }
+    let lower = data.len().saturating_sub(EOCD_MIN_LEN + MAX_COMMENT_LEN);
+    (lower..=data.len() - EOCD_MIN_LEN)
+        .rev()
+        .find(|&pos| &data[pos..pos + 4] == EOCD_SIG)
+}
+
+/// Returns (entry count, central directory size, central directory offset),
+/// following the ZIP64 locator when the classic fields are saturated.
+fn read_directory_bounds(data: &[u8], eocd_pos: usize) -> Result<(u64, u64, u64), String> {
+    let entries = read_u16_le(data, eocd_pos + 10).ok_or("zip EOCD truncated")?;
+    let cd_size = read_u32_le(data, eocd_pos + 12).ok_or("zip EOCD truncated")?;
+    let cd_offset = read_u32_le(data, eocd_pos + 16).ok_or("zip EOCD truncated")?;
+    if entries != u16::MAX && cd_size != u32::MAX && cd_offset != u32::MAX {
+        if entries == 0 {
+            return Err("zip archive has no entries".to_string());
+        }
+        return Ok((entries as u64, cd_size as u64, cd_offset as u64));
+    }
+
+    let locator = eocd_pos
+        .checked_sub(20)
+        .filter(|&pos| &data[pos..pos + 4] == ZIP64_LOCATOR_SIG)
+        .ok_or("zip64 locator missing")?;
+    let zip64_pos = read_u64_le(data, locator + 8).ok_or("zip64 locator truncated")? as usize;
+    if data.get(zip64_pos..zip64_pos + 4) != Some(ZIP64_EOCD_SIG.as_slice()) {
+        return Err("zip64 end of central directory missing".to_string());
+    }
+    let entries = read_u64_le(data, zip64_pos + 32).ok_or("zip64 EOCD truncated")?;
+    let cd_size = read_u64_le(data, zip64_pos + 40).ok_or("zip64 EOCD truncated")?;
+    let cd_offset = read_u64_le(data, zip64_pos + 48).ok_or("zip64 EOCD truncated")?;
+    Ok((entries, cd_size, cd_offset))
+}
+
+fn read_u64_le(data: &[u8], offset: usize) -> Option<u64> {
+    let bytes = data.get(offset..offset + 8)?;
+    Some(u64::from_le_bytes(bytes.try_into().ok()?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{find_splice, validate};
+    use crate::validate::{SpliceSearch, noise};
+
+    /// Archive of stored entries.
+    fn stored_zip(entries: &[(&[u8], &[u8])]) -> Vec<u8> {
+        let mut data = Vec::new();
+        let mut directory = Vec::new();
+        for (name, payload) in entries {
+            let crc = crc32fast::hash(payload);
+            let local_offset = data.len() as u32;
+            data.extend_from_slice(b"PK\x03\x04");
+            data.extend_from_slice(&[20, 0, 0, 0, 0, 0, 0, 0, 0, 0]);
+            data.extend_from_slice(&crc.to_le_bytes());
+            data.extend_from_slice(&(payload.len() as u32).to_le_bytes());
+            data.extend_from_slice(&(payload.len() as u32).to_le_bytes());
+            data.extend_from_slice(&(name.len() as u16).to_le_bytes());
+            data.extend_from_slice(&0u16.to_le_bytes());
+            data.extend_from_slice(name);
+            data.extend_from_slice(payload);
+
+            directory.extend_from_slice(b"PK\x01\x02");
+            directory.extend_from_slice(&[20, 0, 20, 0, 0, 0, 0, 0, 0, 0, 0, 0]);
+            directory.extend_from_slice(&crc.to_le_bytes());
+            directory.extend_from_slice(&(payload.len() as u32).to_le_bytes());
+            directory.extend_from_slice(&(payload.len() as u32).to_le_bytes());
+            directory.extend_from_slice(&(name.len() as u16).to_le_bytes());
+            directory.extend_from_slice(&[0u8; 12]);
+            directory.extend_from_slice(&local_offset.to_le_bytes());
+            directory.extend_from_slice(name);
+        }
+        let cd_offset = data.len() as u32;
+        let cd_size = directory.len() as u32;
+        data.extend(directory);
+
+        let count = (entries.len() as u16).to_le_bytes();
+        data.extend_from_slice(b"PK\x05\x06");
+        data.extend_from_slice(&[0, 0, 0, 0]);
+        data.extend_from_slice(&count);
+        data.extend_from_slice(&count);
+        data.extend_from_slice(&cd_size.to_le_bytes());
+        data.extend_from_slice(&cd_offset.to_le_bytes());
+        data.extend_from_slice(&0u16.to_le_bytes());
+        data
+    }
+
+    fn sample_zip() -> Vec<u8> {
+        stored_zip(&[(b"a.txt", b"hello")])
+    }
+
+    #[test]
+    fn accepts_valid_zip() {
+        assert_eq!(validate(&sample_zip()), Ok(()));
+    }
+
+    #[test]
+    fn rejects_broken_local_header() {
+        let mut data = sample_zip();
+        data[0] = b'X';
+        let err = validate(&data).expect_err("broken local header");
+        assert!(err.contains("local header missing"));
+    }
+
+    #[test]
+    fn rejects_missing_eocd() {
+        let data = sample_zip();
+        assert!(validate(&data[..data.len() - 22]).is_err());
+    }
+
+    #[test]
+    fn finds_gap_inside_stored_entry() {
+        let first = noise(2000);
+        let second: Vec<u8> = noise(3000).into_iter().rev().collect();
+        let archive = stored_zip(&[(b"a.bin", &first), (b"b.bin", &second)]);
+        let mut data = archive[..1024].to_vec();
+        data.extend(noise(1536));
+        data.extend_from_slice(&archive[1024..]);
+        // Whatever follows the archive in the evidence.
+        data.extend(noise(512));
+
+        let search = SpliceSearch {
+            base: 0,
+            block_size: 512,
+            max_gap: 4096,
+        };
+        let splice = find_splice(&data, &search).expect("splice");
+        assert_eq!((splice.split, splice.gap), (1024, 1536));
+        assert_eq!(splice.join(&data), archive);
+    }
+}
@@ -0,0 +1,791 @@
+//! Built-in YARA engine for `--yara-rules`.
+//!
+//! Supports the commonly used subset of the YARA language: rules with tags,
+//! `meta` (ignored) and `strings` sections; text strings with `nocase`,
+//! `wide`, `ascii` and `fullword`; hex strings with wildcards, jumps and
+//! alternatives; regular expressions; and conditions built from `and`, `or`,
+//! `not`, comparisons, arithmetic, `$a`, `#a`, `@a[i]`, `$a at`, `$a in`,
+//! `any`/`all`/`none`/`N of`, `filesize`, `(u)intXX(be)` and references to
+//! earlier rules. Anything else is rejected when the rules are loaded,
+//! rather than evaluated differently from YARA: modules, `include`, `for`
+//! loops, `xor`/`base64` modifiers, and `@a[i]` with an index that is not
+//! a constant within the first [`MAX_STRING_MATCHES`] matches.
+//!
+//! Scan chunks are not files, so rules that depend on the file as a whole
+//! ([`YaraRules::whole_file_rules`]: `filesize`, offsets, integer reads,
+//! match counts, or holding with no string matched) are only evaluated
+//! over carved files; the others are reported for a chunk when they match
+//! the chunk's bytes.
+
+mod parser;
+
+use std::ops::Range;
+use std::path::Path;
+
+use anyhow::{Context, Result, anyhow};
+use regex::bytes::{Regex, RegexSet};
+
+/// Match offsets kept per string and scanned buffer; counts stay exact.
+pub const MAX_STRING_MATCHES: usize = 1000;
+
+/// A rule that matched a scanned buffer.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RuleMatch {
+    pub rule: String,
+    pub tags: Vec<String>,
+    /// Identifier and offset of the earliest match among the rule's strings.
+    pub first_string: Option<(String, usize)>,
+}
+
+/// Compiled rule set, shared by all workers.
+pub struct YaraRules {
+    rules: Vec<Rule>,
+    strings: Vec<StringPattern>,
+    /// Prefilter telling which strings occur at all in a buffer.
+    set: Option<RegexSet>,
+}
+
+impl std::fmt::Debug for YaraRules {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("YaraRules")
+            .field("rules", &self.rules.len())
+            .field("strings", &self.strings.len())
+            .finish()
+    }
+}
+
+struct Rule {
+    name: String,
+    tags: Vec<String>,
+    private: bool,
+    global: bool,
+    strings: Range<usize>,
+    condition: Expr,
+    /// Strings whose earliest match locates the rule: its own and those of
+    /// the rules it references.
+    anchors: Vec<usize>,
+    /// Depends on the file as a whole, so is not evaluated over chunks.
+    whole_file: bool,
+}
+
+struct StringPattern {
+    id: String,
+    regex: Regex,
+    fullword: bool,
+    wide: bool,
+}
+
+#[derive(Debug)]
+enum Expr {
+    Bool(bool),
+    Int(i64),
+    Filesize,
+    StringMatch(usize),
+    StringAt(usize, Box<Expr>),
+    StringIn(usize, Box<Expr>, Box<Expr>),
+    Count(usize),
+    /// `@a[n]`, 1-based.
+    Offset(usize, Box<Expr>),
+    Of(Quantifier, Vec<usize>),
+    ReadInt {
+        size: usize,
+        signed: bool,
+        big_endian: bool,
+        offset: Box<Expr>,
+    },
+    RuleRef(usize),
+    Not(Box<Expr>),
+    And(Vec<Expr>),
+    Or(Vec<Expr>),
+    Cmp(&'static str, Box<Expr>, Box<Expr>),
+    Arith(char, Box<Expr>, Box<Expr>),
+}
+
+#[derive(Debug)]
+enum Quantifier {
+    Any,
+    All,
+    None,
+    Count(Box<Expr>),
+}
+
+impl YaraRules {
+    /// Compile a rule file, or every `.yar`/`.yara` file in a directory.
+    pub fn load(path: &Path) -> Result<Self> {
+        let files = if path.is_dir() {
+            let mut files: Vec<_> = std::fs::read_dir(path)
+                .with_context(|| format!("reading yara rules directory {}", path.display()))?
+                .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+                .filter(|file| {
+                    file.extension()
+                        .is_some_and(|ext| ext == "yar" || ext == "yara")
+                })
+                .collect();
+            files.sort();
+            files
+        } else {
+            vec![path.to_path_buf()]
+        };
+        let mut sources = Vec::with_capacity(files.len());
+        for file in files {
+            let source = std::fs::read_to_string(&file)
+                .with_context(|| format!("reading yara rules {}", file.display()))?;
+            sources.push((file.display().to_string(), source));
+        }
+        let rules = Self::compile_sources(&sources)?;
+        if rules.rules.is_empty() {
+            return Err(anyhow!("no yara rules found in {}", path.display()));
+        }
+        Ok(rules)
+    }
+
+    /// Compile rules from source text.
+    pub fn compile(source: &str) -> Result<Self> {
+        Self::compile_sources(&[("<rules>".to_string(), source.to_string())])
+    }
+
+    fn compile_sources(sources: &[(String, String)]) -> Result<Self> {
+        let mut rules = Vec::new();
+        let mut strings = Vec::new();
+        for (origin, source) in sources {
+            parser::Parser::parse(source, origin, &mut rules, &mut strings)?;
+        }
+        classify(&mut rules, strings.len());
+        let set = if strings.is_empty() {
+            None
+        } else {
+            Some(
+                RegexSet::new(strings.iter().map(|s: &StringPattern| s.regex.as_str()))
+                    .map_err(|e| anyhow!("failed to build yara string set: {e}"))?,
+            )
+        };
+        Ok(Self {
+            rules,
+            strings,
+            set,
+        })
+    }
+
+    pub fn rule_count(&self) -> usize {
+        self.rules.len()
+    }
+
+    /// Names of the rules that depend on the file as a whole and so are
+    /// skipped by [`scan_chunk`](Self::scan_chunk).
+    pub fn whole_file_rules(&self) -> impl Iterator<Item = &str> {
+        self.rules
+            .iter()
+            .filter(|rule| rule.whole_file)
+            .map(|rule| rule.name.as_str())
+    }
+
+    /// Evaluate every rule over a file; private rules are not reported.
+    pub fn scan(&self, data: &[u8]) -> Vec<RuleMatch> {
+        self.evaluate(data, false)
+    }
+
+    /// Evaluate the rules that do not depend on the file as a whole over
+    /// a chunk of the evidence.
+    pub fn scan_chunk(&self, data: &[u8]) -> Vec<RuleMatch> {
+        self.evaluate(data, true)
+    }
+
+    fn evaluate(&self, data: &[u8], chunk: bool) -> Vec<RuleMatch> {
+        let mut matches: Vec<Vec<(usize, usize)>> = vec![Vec::new(); self.strings.len()];
+        let mut counts = vec![0usize; self.strings.len()];
+        if let Some(set) = &self.set {
+            for idx in set.matches(data).iter() {
+                (matches[idx], counts[idx]) = self.strings[idx].find_all(data);
+            }
+        }
+
+        let ctx = Scan {
+            data,
+            matches: &matches,
+            counts: &counts,
+        };
+        let mut results = Vec::with_capacity(self.rules.len());
+        for rule in &self.rules {
+            let matched = !(chunk && rule.whole_file) && ctx.eval_bool(&rule.condition, &results);
+            results.push(matched);
+        }
+        if self
+            .rules
+            .iter()
+            .zip(&results)
+            .any(|(rule, matched)| rule.global && !matched)
+        {
+            return Vec::new();
+        }
+
+        self.rules
+            .iter()
+            .zip(&results)
+            .filter(|(rule, matched)| **matched && !rule.private)
+            .map(|(rule, _)| RuleMatch {
+                rule: rule.name.clone(),
+                tags: rule.tags.clone(),
+                first_string: rule
+                    .anchors
+                    .iter()
+                    .filter_map(|&idx| matches[idx].first().map(|(start, _)| (idx, *start)))
+                    .min_by_key(|(_, start)| *start)
+                    .map(|(idx, start)| (format!("${}", self.strings[idx].id), start)),
+            })
+            .collect()
+    }
+}
+
+impl StringPattern {
+    fn text(id: &str, text: &[u8], nocase: bool, wide: bool, ascii: bool) -> Result<Self> {
+        let encode = |wide: bool| {
+            let mut out = String::new();
+            for &byte in text {
+                if nocase && byte.is_ascii_alphabetic() {
+                    out.push_str(&format!(
+                        "[\\x{:02x}\\x{:02x}]",
+                        byte.to_ascii_lowercase(),
+                        byte.to_ascii_uppercase()
+                    ));
+                } else {
+                    out.push_str(&format!("\\x{byte:02x}"));
+                }
+                if wide {
+                    out.push_str("\\x00");
+                }
+            }
+            out
+        };
+        let body = match (wide, ascii) {
+            (true, true) => format!("(?:{}|{})", encode(false), encode(true)),
+            (true, false) => encode(true),
+            _ => encode(false),
+        };
+        Self::build(id, &body, wide)
+    }
+
+    fn hex(id: &str, body: &str) -> Result<Self> {
+        Self::build(id, &hex_to_regex(body)?, false)
+    }
+
+    fn regex(id: &str, pattern: &str, flags: &str, nocase: bool) -> Result<Self> {
+        let mut prefix = String::new();
+        for flag in flags.chars() {
+            match flag {
+                'i' => prefix.push('i'),
+                's' => prefix.push('s'),
+                _ => return Err(anyhow!("unsupported regular expression flag `{flag}`")),
+            }
+        }
+        if nocase && !prefix.contains('i') {
+            prefix.push('i');
+        }
+        let body = if prefix.is_empty() {
+            pattern.to_string()
+        } else {
+            format!("(?{prefix}){pattern}")
+        };
+        Self::build(id, &body, false)
+    }
+
+    fn build(id: &str, body: &str, wide: bool) -> Result<Self> {
+        let regex = Regex::new(&format!("(?s-u){body}")).map_err(|e| anyhow!("{e}"))?;
+        Ok(Self {
+            id: id.to_string(),
+            regex,
+            fullword: false,
+            wide,
+        })
+    }
+
+    /// Start and length of the first [`MAX_STRING_MATCHES`] matches,
+    /// overlapping ones included, and the number of matches.
+    fn find_all(&self, data: &[u8]) -> (Vec<(usize, usize)>, usize) {
+        let mut out = Vec::new();
+        let mut count = 0;
+        let mut pos = 0;
+        while pos <= data.len() {
+            let Some(found) = self.regex.find_at(data, pos) else {
+                break;
+            };
+            if found.end() > found.start() && (!self.fullword || self.is_word(data, &found)) {
+                if out.len() < MAX_STRING_MATCHES {
+                    out.push((found.start(), found.end() - found.start()));
+                }
+                count += 1;
+            }
+            pos = found.start() + 1;
+        }
+        (out, count)
+    }
+
+    fn is_word(&self, data: &[u8], found: &regex::bytes::Match<'_>) -> bool {
+        let step = if self.wide { 2 } else { 1 };
+        let before = found
+            .start()
+            .checked_sub(step)
+            .map(|idx| data[idx])
+            .filter(u8::is_ascii_alphanumeric);
+        let after = data.get(found.end()).filter(|b| b.is_ascii_alphanumeric());
+        before.is_none() && after.is_none()
+    }
+}
+
+/// Translate a hex string body (`4D 5A ?? [2-4] (01 | 02) ?F`) to a regex.
+fn hex_to_regex(body: &str) -> Result<String> {
+    let chars: Vec<char> = body.chars().filter(|c| !c.is_whitespace()).collect();
+    let mut out = String::new();
+    let mut depth = 0usize;
+    let mut idx = 0;
+    let mut has_byte = false;
+    while idx < chars.len() {
+        match chars[idx] {
+            '(' => {
+                depth += 1;
+                out.push_str("(?:");
+                idx += 1;
+            }
+            ')' => {
+                depth = depth
+                    .checked_sub(1)
+                    .ok_or_else(|| anyhow!("unbalanced `)` in hex string"))?;
+                out.push(')');
+                idx += 1;
+            }
+            '|' => {
+                out.push('|');
+                idx += 1;
+            }
+            '[' => {
+                let end = chars[idx..]
+                    .iter()
+                    .position(|c| *c == ']')
+                    .ok_or_else(|| anyhow!("unterminated jump in hex string"))?;
+                let jump: String = chars[idx + 1..idx + end].iter().collect();
+                let parse = |s: &str| -> Result<Option<u32>> {
+                    if s.is_empty() {
+                        Ok(None)
+                    } else {
+                        s.parse()
+                            .map(Some)
+                            .map_err(|_| anyhow!("invalid jump `[{jump}]`"))
+                    }
+                };
+                let repeat = match jump.split_once('-') {
+                    None => match parse(&jump)? {
+                        Some(count) => format!("{{{count}}}"),
+                        None => return Err(anyhow!("invalid jump `[]`")),
+                    },
+                    Some((low, high)) => match (parse(low)?.unwrap_or(0), parse(high)?) {
+                        (low, Some(high)) if high < low => {
+                            return Err(anyhow!("invalid jump `[{jump}]`"));
+                        }
+                        (low, Some(high)) => format!("{{{low},{high}}}"),
+                        (low, None) => format!("{{{low},}}"),
+                    },
+                };
+                out.push('.');
+                out.push_str(&repeat);
+                idx += end + 1;
+            }
+            _ => {
+                let (Some(&high), Some(&low)) = (chars.get(idx), chars.get(idx + 1)) else {
+                    return Err(anyhow!("odd number of hex digits"));
+                };
+                out.push_str(&hex_byte(high, low)?);
+                has_byte = true;
+                idx += 2;
+            }
+        }
+    }
+    if depth != 0 {
+        return Err(anyhow!("unbalanced `(` in hex string"));
+    }
+    if !has_byte {
+        return Err(anyhow!("empty hex string"));
+    }
+    Ok(out)
+}
+
+fn hex_byte(high: char, low: char) -> Result<String> {
+    let digit = |c: char| {
+        c.to_digit(16)
+            .ok_or_else(|| anyhow!("invalid hex digit `{c}`"))
+    };
+    Ok(match (high, low) {
+        ('?', '?') => ".".to_string(),
+        ('?', low) => {
+            let low = digit(low)?;
+            let class: String = (0..16)
+                .map(|high| format!("\\x{:02x}", high << 4 | low))
+                .collect();
+            format!("[{class}]")
+        }
+        (high, '?') => {
+            let high = digit(high)?;
+            format!("[\\x{:02x}-\\x{:02x}]", high << 4, high << 4 | 0xF)
+        }
+        (high, low) => format!("\\x{:02x}", digit(high)? << 4 | digit(low)?),
+    })
+}
+
+/// Work out which strings locate each rule and which rules depend on the
+/// file as a whole, in rule order so references see their targets.
+fn classify(rules: &mut [Rule], string_count: usize) {
+    let no_matches = vec![Vec::new(); string_count];
+    let no_counts = vec![0; string_count];
+    let nothing = Scan {
+        data: &[],
+        matches: &no_matches,
+        counts: &no_counts,
+    };
+    let mut empty_results = Vec::with_capacity(rules.len());
+    for idx in 0..rules.len() {
+        let (earlier, rest) = rules.split_at_mut(idx);
+        let rule = &mut rest[0];
+        let mut anchors: Vec<usize> = rule.strings.clone().collect();
+        let mut whole_file = false;
+        visit(&rule.condition, &mut |expr| match expr {
+            Expr::Filesize
+            | Expr::Count(_)
+            | Expr::Offset(..)
+            | Expr::StringAt(..)
+            | Expr::StringIn(..)
+            | Expr::ReadInt { .. } => whole_file = true,
+            Expr::RuleRef(target) => {
+                whole_file |= earlier[*target].whole_file;
+                anchors.extend(&earlier[*target].anchors);
+            }
+            _ => {}
+        });
+        // A rule that holds when no string matches would match every chunk
+        let holds_on_nothing = nothing.eval_bool(&rule.condition, &empty_results);
+        empty_results.push(holds_on_nothing);
+        anchors.sort_unstable();
+        anchors.dedup();
+        rule.anchors = anchors;
+        rule.whole_file = whole_file || holds_on_nothing;
+    }
+    // A global rule that cannot be evaluated over a chunk gates them all
+    if rules.iter().any(|rule| rule.global && rule.whole_file) {
+        for rule in rules.iter_mut() {
+            rule.whole_file = true;
+        }
+    }
+}
+
+/// Call `f` on `expr` and every expression inside it.
+fn visit(expr: &Expr, f: &mut impl FnMut(&Expr)) {
+    f(expr);
+    match expr {
+        Expr::StringAt(_, inner) | Expr::Offset(_, inner) | Expr::Not(inner) => visit(inner, f),
+        Expr::ReadInt { offset, .. } => visit(offset, f),
+        Expr::StringIn(_, low, high) | Expr::Cmp(_, low, high) | Expr::Arith(_, low, high) => {
+            visit(low, f);
+            visit(high, f);
+        }
+        Expr::And(terms) | Expr::Or(terms) => terms.iter().for_each(|term| visit(term, f)),
+        Expr::Of(Quantifier::Count(count), _) => visit(count, f),
+        Expr::Bool(_)
+        | Expr::Int(_)
+        | Expr::Filesize
+        | Expr::StringMatch(_)
+        | Expr::Count(_)
+        | Expr::Of(..)
+        | Expr::RuleRef(_) => {}
+    }
+}
+
+/// Evaluation state for one scanned buffer.
+struct Scan<'a> {
+    data: &'a [u8],
+    /// `(start, len)` of the first matches per string.
+    matches: &'a [Vec<(usize, usize)>],
+    /// Number of matches per string.
+    counts: &'a [usize],
+}
+
+impl Scan<'_> {
+    fn eval_bool(&self, expr: &Expr, rules: &[bool]) -> bool {
+        match expr {
+            Expr::Bool(value) => *value,
+            Expr::StringMatch(idx) => !self.matches[*idx].is_empty(),
+            Expr::StringAt(idx, offset) => self
+                .eval_int(offset, rules)
+                .is_some_and(|offset| self.starts(*idx).any(|start| start == offset)),
+            Expr::StringIn(idx, start, end) => {
+                match (self.eval_int(start, rules), self.eval_int(end, rules)) {
+                    (Some(low), Some(high)) => {
+                        self.starts(*idx).any(|start| (low..=high).contains(&start))
+                    }
+                    _ => false,
+                }
+            }
+            Expr::Of(quantifier, set) => {
+                let matched = set
+                    .iter()
+                    .filter(|idx| !self.matches[**idx].is_empty())
+                    .count() as i64;
+                match quantifier {
+                    Quantifier::Any => matched > 0,
+                    Quantifier::All => matched == set.len() as i64,
+                    Quantifier::None => matched == 0,
+                    Quantifier::Count(count) => self
+                        .eval_int(count, rules)
+                        .is_some_and(|count| matched >= count),
+                }
+            }
+            Expr::RuleRef(idx) => rules.get(*idx).copied().unwrap_or(false),
+            Expr::Not(inner) => !self.eval_bool(inner, rules),
+            Expr::And(terms) => terms.iter().all(|term| self.eval_bool(term, rules)),
+            Expr::Or(terms) => terms.iter().any(|term| self.eval_bool(term, rules)),
+            Expr::Cmp(op, left, right) => {
+                match (self.eval_int(left, rules), self.eval_int(right, rules)) {
+                    (Some(left), Some(right)) => match *op {
+                        "==" => left == right,
+                        "!=" => left != right,
+                        "<" => left < right,
+                        "<=" => left <= right,
+                        ">" => left > right,
+                        _ => left >= right,
+                    },
+                    _ => false,
+                }
+            }
+            _ => self.eval_int(expr, rules).is_some_and(|value| value != 0),
+        }
+    }
+
+    /// Integer value of `expr`, `None` when undefined (e.g. reads past the
+    /// end of the data).
+    fn eval_int(&self, expr: &Expr, rules: &[bool]) -> Option<i64> {
+        match expr {
+            Expr::Int(value) => Some(*value),
+            Expr::Filesize => Some(self.data.len() as i64),
+            Expr::Count(idx) => Some(self.counts[*idx] as i64),
+            Expr::Offset(idx, nth) => {
+                let nth = usize::try_from(self.eval_int(nth, rules)?).ok()?;
+                self.starts(*idx).nth(nth.checked_sub(1)?)
+            }
+            Expr::ReadInt {
+                size,
+                signed,
+                big_endian,
+                offset,
+            } => {
+                let offset = usize::try_from(self.eval_int(offset, rules)?).ok()?;
+                let bytes = self.data.get(offset..offset.checked_add(*size)?)?;
+                let mut value: u64 = 0;
+                for idx in 0..*size {
+                    let byte = if *big_endian {
+                        bytes[idx]
+                    } else {
+                        bytes[size - 1 - idx]
+                    };
+                    value = value << 8 | byte as u64;
+                }
+                if *signed {
+                    let shift = 64 - 8 * *size as u32;
+                    Some(((value << shift) as i64) >> shift)
+                } else {
+                    Some(value as i64)
+                }
+            }
+            Expr::Arith(op, left, right) => {
+                let left = self.eval_int(left, rules)?;
+                let right = self.eval_int(right, rules)?;
+                match op {
+                    '+' => left.checked_add(right),
+                    '-' => left.checked_sub(right),
+                    '*' => left.checked_mul(right),
+                    '\\' => left.checked_div(right),
+                    _ => left.checked_rem(right),
+                }
+            }
+            _ => Some(self.eval_bool(expr, rules) as i64),
+        }
+    }
+
+    fn starts(&self, idx: usize) -> impl Iterator<Item = i64> + '_ {
+        self.matches[idx].iter().map(|(start, _)| *start as i64)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names(rules: &YaraRules, data: &[u8]) -> Vec<String> {
+        rules
+            .scan(data)
+            .into_iter()
+            .map(|found| found.rule)
+            .collect()
+    }
+
+    #[test]
+    fn matches_text_hex_and_regex_strings() {
+        let rules = YaraRules::compile(
+            r#"
+            // Marker strings
+            rule text_marker : doc marker {
+                meta:
+                    author = "test"
+                    score = -1
+                strings:
+                    $a = "secret" nocase
+                    $b = "KEY" wide
+                condition:
+                    $a and $b
+            }
+            rule mz_header {
+                strings:
+                    $mz = { 4D 5A ?? [1-3] ( 01 | 02 ) ?F }
+                condition:
+                    $mz at 0 and uint16(0) == 0x5A4D
+            }
+            /* block comment */
+            rule url_like {
+                strings:
+                    $re = /https?:\/\/[a-z]+\.example/i
+                    $word = "cat" fullword
+                condition:
+                    any of them
+            }
+            "#,
+        )
+        .expect("compile");
+        assert_eq!(rules.rule_count(), 3);
+
+        let mut data = b"MZ\x90\x00\x00\x02\x3Fpad SeCrEt K\x00E\x00Y\x00".to_vec();
+        data.extend_from_slice(b" concatenate HTTP://WWW.example");
+        let found = rules.scan(&data);
+        let names: Vec<&str> = found.iter().map(|m| m.rule.as_str()).collect();
+        assert_eq!(names, vec!["text_marker", "mz_header", "url_like"]);
+        assert_eq!(found[0].tags, vec!["doc", "marker"]);
+        assert_eq!(found[0].first_string, Some(("$a".to_string(), 11)));
+
+        // `cat` only inside a longer word, and no URL.
+        assert!(
+            YaraRules::compile("rule r { strings: $w = \"cat\" fullword condition: $w }")
+                .expect("compile")
+                .scan(b"concatenate")
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn evaluates_counts_offsets_and_rule_references() {
+        let rules = YaraRules::compile(
+            r#"
+            private rule has_two {
+                strings:
+                    $x = "ab"
+                condition:
+                    #x == 2 and @x[2] - @x[1] == 4 and $x in (0..2)
+            }
+            rule uses_private {
+                strings:
+                    $y = "zz"
+                    $z1 = "q1"
+                    $z2 = "q2"
+                condition:
+                    has_two and 2 of ($z*) and not $y and filesize < 1KB
+            }
+            rule out_of_bounds {
+                condition:
+                    uint32be(filesize - 2) == 0 or int8(0) == 0x61
+            }
+            "#,
+        )
+        .expect("compile");
+
+        assert_eq!(
+            names(&rules, b"abq1abq2"),
+            vec!["uses_private", "out_of_bounds"]
+        );
+        assert_eq!(names(&rules, b"Xbq1abq2"), Vec::<String>::new());
+    }
+
+    #[test]
+    fn global_rules_gate_every_other_rule() {
+        let rules = YaraRules::compile(
+            r#"
+            global rule small { condition: filesize < 8 }
+            rule any_data { condition: filesize > 0 }
+            "#,
+        )
+        .expect("compile");
+        assert_eq!(names(&rules, b"tiny"), vec!["small", "any_data"]);
+        assert!(rules.scan(b"far too long").is_empty());
+    }
+
+    #[test]
+    fn chunk_scans_skip_rules_that_need_the_whole_file() {
+        let rules = YaraRules::compile(
+            r#"
+            rule marker { strings: $m = "mark" condition: $m }
+            rule refers { condition: marker }
+            rule absent { strings: $m = "mark" condition: not $m }
+            rule counted { strings: $m = "mark" condition: #m > 1 }
+            rule anchored { strings: $m = "mark" condition: $m at 0 }
+            rule sized { condition: filesize > 0 }
+            rule via_sized { strings: $m = "mark" condition: $m and sized }
+            "#,
+        )
+        .expect("compile");
+        assert_eq!(
+            rules.whole_file_rules().collect::<Vec<_>>(),
+            vec!["absent", "counted", "anchored", "sized", "via_sized"]
+        );
+
+        let found = rules.scan_chunk(b"mark mark");
+        let chunk_names: Vec<&str> = found.iter().map(|m| m.rule.as_str()).collect();
+        assert_eq!(chunk_names, vec!["marker", "refers"]);
+        // A rule with no strings of its own is located by those it references
+        assert_eq!(found[1].first_string, Some(("$m".to_string(), 0)));
+        assert_eq!(
+            names(&rules, b"mark mark"),
+            vec![
+                "marker",
+                "refers",
+                "counted",
+                "anchored",
+                "sized",
+                "via_sized"
+            ]
+        );
+
+        // A global rule that needs the whole file gates every rule
+        let gated = YaraRules::compile(
+            "global rule small { condition: filesize < 8 } \
+             rule marker { strings: $m = \"mark\" condition: $m }",
+        )
+        .expect("compile");
+        assert_eq!(gated.whole_file_rules().count(), 2);
+        assert!(gated.scan_chunk(b"mark").is_empty());
+    }
+
+    #[test]
+    fn counts_past_the_kept_matches() {
+        let rules = YaraRules::compile(
+            "rule many { strings: $a = \"a\" condition: #a == 1500 and @a[1000] == 999 }",
+        )
+        .expect("compile");
+        assert_eq!(names(&rules, &[b'a'; 1500]), vec!["many"]);
+    }
+
+    #[test]
+    fn rejects_unsupported_and_invalid_rules() {
+        for source in [
+            "import \"pe\" rule r { condition: true }",
+            "rule r { strings: $a = \"x\" condition: for any i in (1..2): ($a) }",
+            "rule r { strings: $a = { 4D 5 } condition: $a }",
+            "rule r { condition: $missing }",
+            "rule r { condition: true } rule r { condition: true }",
+            "rule r { strings: $a = \"x\" xor condition: $a }",
+        ] {
+            assert!(YaraRules::compile(source).is_err(), "{source}");
+        }
+    }
+}
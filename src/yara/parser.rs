@@ -0,0 +1,945 @@
+//! Lexer and parser for the supported YARA subset.
+
+use anyhow::{Result, anyhow, bail};
+
+use super::{Expr, MAX_STRING_MATCHES, Quantifier, Rule, StringPattern};
+
+#[derive(Debug, Clone, PartialEq)]
+enum Tok {
+    Ident(String),
+    /// `$name`, `$` or a `$prefix*` wildcard in string sets.
+    StrId(String),
+    /// `#name`
+    CountId(String),
+    /// `@name`
+    OffsetId(String),
+    Int(i64),
+    Text(Vec<u8>),
+    Hex(String),
+    Regex(String, String),
+    Sym(&'static str),
+    Eof,
+}
+
+const SYMBOLS: &[&str] = &[
+    "..", "==", "!=", "<=", ">=", "{", "}", "(", ")", "[", "]", ":", "=", ",", "<", ">", "+", "-",
+    "*", "\\", "%",
+];
+
+fn tokenize(src: &str) -> Result<Vec<(Tok, usize)>> {
+    let bytes = src.as_bytes();
+    let mut tokens: Vec<(Tok, usize)> = Vec::new();
+    let mut pos = 0;
+    let mut line = 1;
+    while pos < bytes.len() {
+        let c = bytes[pos];
+        if c == b'\n' {
+            line += 1;
+            pos += 1;
+            continue;
+        }
+        if c.is_ascii_whitespace() {
+            pos += 1;
+            continue;
+        }
+        if bytes[pos..].starts_with(b"//") {
+            while pos < bytes.len() && bytes[pos] != b'\n' {
+                pos += 1;
+            }
+            continue;
+        }
+        if bytes[pos..].starts_with(b"/*") {
+            let end = src[pos + 2..]
+                .find("*/")
+                .ok_or_else(|| anyhow!("line {line}: unterminated comment"))?;
+            line += src[pos..pos + 2 + end].matches('\n').count();
+            pos += end + 4;
+            continue;
+        }
+        let start_line = line;
+        let after_assign = tokens.len() >= 2
+            && tokens[tokens.len() - 1].0 == Tok::Sym("=")
+            && matches!(tokens[tokens.len() - 2].0, Tok::StrId(_));
+        let token = if c == b'"' {
+            let (text, next) = lex_text(bytes, pos + 1, line)?;
+            pos = next;
+            Tok::Text(text)
+        } else if c == b'{' && after_assign {
+            let end = src[pos..]
+                .find('}')
+                .ok_or_else(|| anyhow!("line {line}: unterminated hex string"))?;
+            let body = &src[pos + 1..pos + end];
+            line += body.matches('\n').count();
+            pos += end + 1;
+            Tok::Hex(body.to_string())
+        } else if c == b'/' {
+            let (pattern, next) = lex_regex(src, pos + 1, line)?;
+            let flags_end = bytes[next..]
+                .iter()
+                .position(|b| !b.is_ascii_alphabetic())
+                .map_or(bytes.len(), |len| next + len);
+            pos = flags_end;
+            Tok::Regex(pattern, src[next..flags_end].to_string())
+        } else if matches!(c, b'$' | b'#' | b'@') {
+            let mut end = pos + 1;
+            while end < bytes.len() && (bytes[end].is_ascii_alphanumeric() || bytes[end] == b'_') {
+                end += 1;
+            }
+            if c == b'$' && end < bytes.len() && bytes[end] == b'*' {
+                end += 1;
+            }
+            let name = src[pos + 1..end].to_string();
+            pos = end;
+            match c {
+                b'$' => Tok::StrId(name),
+                b'#' => Tok::CountId(name),
+                _ => Tok::OffsetId(name),
+            }
+        } else if c.is_ascii_digit() {
+            let (value, next) = lex_int(src, pos, line)?;
+            pos = next;
+            Tok::Int(value)
+        } else if c.is_ascii_alphabetic() || c == b'_' {
+            let mut end = pos;
+            while end < bytes.len() && (bytes[end].is_ascii_alphanumeric() || bytes[end] == b'_') {
+                end += 1;
+            }
+            let ident = src[pos..end].to_string();
+            pos = end;
+            Tok::Ident(ident)
+        } else if let Some(sym) = SYMBOLS
+            .iter()
+            .find(|sym| bytes[pos..].starts_with(sym.as_bytes()))
+        {
+            pos += sym.len();
+            Tok::Sym(sym)
+        } else {
+            bail!("line {line}: unexpected character `{}`", c as char);
+        };
+        tokens.push((token, start_line));
+    }
+    tokens.push((Tok::Eof, line));
+    Ok(tokens)
+}
+
+fn lex_text(bytes: &[u8], mut pos: usize, line: usize) -> Result<(Vec<u8>, usize)> {
+    let mut out = Vec::new();
+    loop {
+        match bytes.get(pos) {
+            None | Some(b'\n') => bail!("line {line}: unterminated string"),
+            Some(b'"') => return Ok((out, pos + 1)),
+            Some(b'\\') => {
+                let escaped = match bytes.get(pos + 1) {
+                    Some(b'n') => b'\n',
+                    Some(b'r') => b'\r',
+                    Some(b't') => b'\t',
+                    Some(b'"') => b'"',
+                    Some(b'\\') => b'\\',
+                    Some(b'x') => {
+                        let hex = bytes
+                            .get(pos + 2..pos + 4)
+                            .and_then(|hex| std::str::from_utf8(hex).ok())
+                            .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+                            .ok_or_else(|| anyhow!("line {line}: invalid \\x escape"))?;
+                        out.push(hex);
+                        pos += 4;
+                        continue;
+                    }
+                    _ => bail!("line {line}: invalid escape sequence"),
+                };
+                out.push(escaped);
+                pos += 2;
+            }
+            Some(&byte) => {
+                out.push(byte);
+                pos += 1;
+            }
+        }
+    }
+}
+
+fn lex_regex(src: &str, mut pos: usize, line: usize) -> Result<(String, usize)> {
+    let bytes = src.as_bytes();
+    let mut out = String::new();
+    loop {
+        match bytes.get(pos) {
+            None | Some(b'\n') => bail!("line {line}: unterminated regular expression"),
+            Some(b'/') => return Ok((out, pos + 1)),
+            Some(b'\\') if bytes.get(pos + 1) == Some(&b'/') => {
+                out.push('/');
+                pos += 2;
+            }
+            Some(b'\\') => {
+                let next = src[pos + 1..]
+                    .chars()
+                    .next()
+                    .ok_or_else(|| anyhow!("line {line}: unterminated regular expression"))?;
+                out.push('\\');
+                out.push(next);
+                pos += 1 + next.len_utf8();
+            }
+            Some(_) => {
+                let ch = src[pos..].chars().next().unwrap_or_default();
+                out.push(ch);
+                pos += ch.len_utf8();
+            }
+        }
+    }
+}
+
+fn lex_int(src: &str, pos: usize, line: usize) -> Result<(i64, usize)> {
+    let bytes = src.as_bytes();
+    let (radix, digits_start) = if bytes[pos..].starts_with(b"0x") {
+        (16, pos + 2)
+    } else {
+        (10, pos)
+    };
+    let mut end = digits_start;
+    while end < bytes.len() && (bytes[end] as char).is_digit(radix) {
+        end += 1;
+    }
+    let mut value = i64::from_str_radix(&src[digits_start..end], radix)
+        .map_err(|e| anyhow!("line {line}: invalid number: {e}"))?;
+    if radix == 10 {
+        if bytes[end..].starts_with(b"KB") {
+            value = value.saturating_mul(1024);
+            end += 2;
+        } else if bytes[end..].starts_with(b"MB") {
+            value = value.saturating_mul(1024 * 1024);
+            end += 2;
+        }
+    }
+    Ok((value, end))
+}
+
+/// Parses rule sources into the shared rule and string tables.
+pub(super) struct Parser<'a> {
+    tokens: Vec<(Tok, usize)>,
+    pos: usize,
+    origin: &'a str,
+    rules: &'a mut Vec<Rule>,
+    strings: &'a mut Vec<StringPattern>,
+    /// Identifiers of the strings of the rule being parsed.
+    local: Vec<(String, usize)>,
+}
+
+impl<'a> Parser<'a> {
+    pub(super) fn parse(
+        source: &str,
+        origin: &'a str,
+        rules: &'a mut Vec<Rule>,
+        strings: &'a mut Vec<StringPattern>,
+    ) -> Result<()> {
+        let tokens = tokenize(source).map_err(|e| anyhow!("{origin}: {e}"))?;
+        let mut parser = Parser {
+            tokens,
+            pos: 0,
+            origin,
+            rules,
+            strings,
+            local: Vec::new(),
+        };
+        while parser.peek() != &Tok::Eof {
+            parser.rule()?;
+        }
+        Ok(())
+    }
+
+    fn peek(&self) -> &Tok {
+        &self.tokens[self.pos].0
+    }
+
+    fn peek_at(&self, ahead: usize) -> &Tok {
+        let idx = (self.pos + ahead).min(self.tokens.len() - 1);
+        &self.tokens[idx].0
+    }
+
+    fn next(&mut self) -> Tok {
+        let token = self.tokens[self.pos].0.clone();
+        if self.pos + 1 < self.tokens.len() {
+            self.pos += 1;
+        }
+        token
+    }
+
+    fn error(&self, msg: impl std::fmt::Display) -> anyhow::Error {
+        anyhow!("{}:{}: {msg}", self.origin, self.tokens[self.pos].1)
+    }
+
+    fn is_ident(&self, name: &str) -> bool {
+        matches!(self.peek(), Tok::Ident(ident) if ident == name)
+    }
+
+    fn eat_ident(&mut self, name: &str) -> bool {
+        let found = self.is_ident(name);
+        if found {
+            self.next();
+        }
+        found
+    }
+
+    fn eat_sym(&mut self, sym: &str) -> bool {
+        let found = matches!(self.peek(), Tok::Sym(s) if *s == sym);
+        if found {
+            self.next();
+        }
+        found
+    }
+
+    fn expect_sym(&mut self, sym: &str) -> Result<()> {
+        if self.eat_sym(sym) {
+            Ok(())
+        } else {
+            Err(self.error(format!("expected `{sym}`")))
+        }
+    }
+
+    fn ident(&mut self) -> Result<String> {
+        match self.next() {
+            Tok::Ident(ident) => Ok(ident),
+            _ => Err(self.error("expected identifier")),
+        }
+    }
+
+    fn rule(&mut self) -> Result<()> {
+        if self.is_ident("import") || self.is_ident("include") {
+            return Err(self.error("modules and includes are not supported"));
+        }
+        let mut private = false;
+        let mut global = false;
+        loop {
+            if self.eat_ident("private") {
+                private = true;
+            } else if self.eat_ident("global") {
+                global = true;
+            } else {
+                break;
+            }
+        }
+        if !self.eat_ident("rule") {
+            return Err(self.error("expected `rule`"));
+        }
+        let name = self.ident()?;
+        if self.rules.iter().any(|rule| rule.name == name) {
+            return Err(self.error(format!("duplicate rule `{name}`")));
+        }
+        let mut tags = Vec::new();
+        if self.eat_sym(":") {
+            while let Tok::Ident(tag) = self.peek() {
+                tags.push(tag.clone());
+                self.next();
+            }
+        }
+        self.expect_sym("{")?;
+
+        if self.is_ident("meta") && self.peek_at(1) == &Tok::Sym(":") {
+            self.pos += 2;
+            while matches!(self.peek(), Tok::Ident(_)) && self.peek_at(1) == &Tok::Sym("=") {
+                self.pos += 2;
+                self.eat_sym("-");
+                match self.next() {
+                    Tok::Text(_) | Tok::Int(_) | Tok::Ident(_) => {}
+                    _ => return Err(self.error("invalid meta value")),
+                }
+            }
+        }
+
+        self.local.clear();
+        let first_string = self.strings.len();
+        if self.is_ident("strings") && self.peek_at(1) == &Tok::Sym(":") {
+            self.pos += 2;
+            while let Tok::StrId(id) = self.peek().clone() {
+                self.next();
+                self.expect_sym("=")?;
+                self.string_def(id)?;
+            }
+        }
+        let string_range = first_string..self.strings.len();
+
+        if !(self.eat_ident("condition") && self.eat_sym(":")) {
+            return Err(self.error("expected `condition:`"));
+        }
+        let condition = self.expr()?;
+        self.expect_sym("}")?;
+
+        self.rules.push(Rule {
+            name,
+            tags,
+            private,
+            global,
+            strings: string_range,
+            condition,
+            anchors: Vec::new(),
+            whole_file: false,
+        });
+        Ok(())
+    }
+
+    fn string_def(&mut self, id: String) -> Result<()> {
+        if id.ends_with('*') {
+            return Err(self.error("string identifiers cannot end in `*`"));
+        }
+        if !id.is_empty() && self.local.iter().any(|(name, _)| *name == id) {
+            return Err(self.error(format!("duplicate string `${id}`")));
+        }
+        let value = self.next();
+        let mut modifiers = Vec::new();
+        while let Tok::Ident(modifier) = self.peek() {
+            if matches!(modifier.as_str(), "xor" | "base64" | "base64wide") {
+                return Err(self.error(format!("modifier `{modifier}` is not supported")));
+            }
+            if !matches!(
+                modifier.as_str(),
+                "nocase" | "wide" | "ascii" | "fullword" | "private"
+            ) {
+                break;
+            }
+            modifiers.push(modifier.clone());
+            self.next();
+        }
+        let has = |name: &str| modifiers.iter().any(|m| m == name);
+        let pattern = match value {
+            Tok::Text(text) => {
+                if text.is_empty() {
+                    return Err(self.error("empty string"));
+                }
+                StringPattern::text(&id, &text, has("nocase"), has("wide"), has("ascii"))
+            }
+            Tok::Hex(body) => {
+                if modifiers.iter().any(|m| m != "private") {
+                    return Err(self.error("hex strings take no modifiers"));
+                }
+                StringPattern::hex(&id, &body)
+            }
+            Tok::Regex(pattern, flags) => {
+                if has("wide") {
+                    return Err(self.error("wide regular expressions are not supported"));
+                }
+                StringPattern::regex(&id, &pattern, &flags, has("nocase"))
+            }
+            _ => return Err(self.error("expected string, hex string or regular expression")),
+        };
+        let mut pattern = pattern.map_err(|e| self.error(format!("string ${id}: {e}")))?;
+        pattern.fullword = has("fullword");
+        self.local.push((id, self.strings.len()));
+        self.strings.push(pattern);
+        Ok(())
+    }
+
+    fn string_index(&self, id: &str) -> Result<usize> {
+        self.local
+            .iter()
+            .find(|(name, _)| name == id)
+            .map(|(_, idx)| *idx)
+            .ok_or_else(|| self.error(format!("undefined string `${id}`")))
+    }
+
+    fn expr(&mut self) -> Result<Expr> {
+        let mut terms = vec![self.and_expr()?];
+        while self.eat_ident("or") {
+            terms.push(self.and_expr()?);
+        }
+        Ok(if terms.len() == 1 {
+            terms.remove(0)
+        } else {
+            Expr::Or(terms)
+        })
+    }
+
+    fn and_expr(&mut self) -> Result<Expr> {
+        let mut terms = vec![self.not_expr()?];
+        while self.eat_ident("and") {
+            terms.push(self.not_expr()?);
+        }
+        Ok(if terms.len() == 1 {
+            terms.remove(0)
+        } else {
+            Expr::And(terms)
+        })
+    }
+
+    fn not_expr(&mut self) -> Result<Expr> {
+        if self.eat_ident("not") {
+            return Ok(Expr::Not(Box::new(self.not_expr()?)));
+        }
+        self.comparison()
+    }
+
+    fn comparison(&mut self) -> Result<Expr> {
+        let left = self.additive()?;
+        for op in ["==", "!=", "<=", ">=", "<", ">"] {
+            if self.eat_sym(op) {
+                let right = self.additive()?;
+                return Ok(Expr::Cmp(op, Box::new(left), Box::new(right)));
+            }
+        }
+        Ok(left)
+    }
+
+    fn additive(&mut self) -> Result<Expr> {
+        let mut left = self.multiplicative()?;
+        loop {
+            let op = if self.eat_sym("+") {
+                '+'
+            } else if self.eat_sym("-") {
+                '-'
+            } else {
+                return Ok(left);
+            };
+            let right = self.multiplicative()?;
+            left = Expr::Arith(op, Box::new(left), Box::new(right));
+        }
+    }
+
+    fn multiplicative(&mut self) -> Result<Expr> {
+        let mut left = self.unary()?;
+        loop {
+            let op = if self.eat_sym("*") {
+                '*'
+            } else if self.eat_sym("\\") {
+                '\\'
+            } else if self.eat_sym("%") {
+                '%'
+            } else {
+                return Ok(left);
+            };
+            let right = self.unary()?;
+            left = Expr::Arith(op, Box::new(left), Box::new(right));
+        }
+    }
+
+    fn unary(&mut self) -> Result<Expr> {
+        if self.eat_sym("-") {
+            let operand = self.unary()?;
+            return Ok(Expr::Arith('-', Box::new(Expr::Int(0)), Box::new(operand)));
+        }
+        self.primary()
+    }
+
+    fn primary(&mut self) -> Result<Expr> {
+        match self.next() {
+            Tok::Int(value) => {
+                if self.is_ident("of") {
+                    return self.of(Quantifier::Count(Box::new(Expr::Int(value))));
+                }
+                Ok(Expr::Int(value))
+            }
+            Tok::Sym("(") => {
+                let inner = self.expr()?;
+                self.expect_sym(")")?;
+                Ok(inner)
+            }
+            Tok::StrId(id) => {
+                let idx = self.string_index(&id)?;
+                if self.eat_ident("at") {
+                    let offset = self.additive()?;
+                    return Ok(Expr::StringAt(idx, Box::new(offset)));
+                }
+                if self.eat_ident("in") {
+                    let (start, end) = self.range()?;
+                    return Ok(Expr::StringIn(idx, Box::new(start), Box::new(end)));
+                }
+                Ok(Expr::StringMatch(idx))
+            }
+            Tok::CountId(id) => Ok(Expr::Count(self.string_index(&id)?)),
+            Tok::OffsetId(id) => {
+                let idx = self.string_index(&id)?;
+                let nth = if self.eat_sym("[") {
+                    let nth = self.expr()?;
+                    self.expect_sym("]")?;
+                    nth
+                } else {
+                    Expr::Int(1)
+                };
+                // Only the first matches are kept, so later ones would read
+                // as undefined
+                match nth {
+                    Expr::Int(n) if (1..=MAX_STRING_MATCHES as i64).contains(&n) => {}
+                    _ => {
+                        return Err(self.error(format!(
+                            "`@{id}[i]` needs a constant index from 1 to {MAX_STRING_MATCHES}"
+                        )));
+                    }
+                }
+                Ok(Expr::Offset(idx, Box::new(nth)))
+            }
+            Tok::Ident(ident) => self.ident_expr(ident),
+            _ => Err(self.error("expected expression")),
+        }
+    }
+
+    fn ident_expr(&mut self, ident: String) -> Result<Expr> {
+        match ident.as_str() {
+            "true" => Ok(Expr::Bool(true)),
+            "false" => Ok(Expr::Bool(false)),
+            "filesize" => Ok(Expr::Filesize),
+            "any" => self.of(Quantifier::Any),
+            "all" => self.of(Quantifier::All),
+            "none" => self.of(Quantifier::None),
+            "for" => Err(self.error("`for` loops are not supported")),
+            _ => {
+                if let Some(read) = read_int_function(&ident) {
+                    self.expect_sym("(")?;
+                    let offset = self.expr()?;
+                    self.expect_sym(")")?;
+                    let (size, signed, big_endian) = read;
+                    return Ok(Expr::ReadInt {
+                        size,
+                        signed,
+                        big_endian,
+                        offset: Box::new(offset),
+                    });
+                }
+                self.rules
+                    .iter()
+                    .position(|rule| rule.name == ident)
+                    .map(Expr::RuleRef)
+                    .ok_or_else(|| self.error(format!("unknown identifier `{ident}`")))
+            }
+        }
+    }
+
+    fn range(&mut self) -> Result<(Expr, Expr)> {
+        self.expect_sym("(")?;
+        let start = self.additive()?;
+        self.expect_sym("..")?;
+        let end = self.additive()?;
+        self.expect_sym(")")?;
+        Ok((start, end))
+    }
+
+    fn of(&mut self, quantifier: Quantifier) -> Result<Expr> {
+        if !self.eat_ident("of") {
+            return Err(self.error("expected `of`"));
+        }
+        let mut set = Vec::new();
+        if self.eat_ident("them") {
+            set.extend(self.local.iter().map(|(_, idx)| *idx));
+        } else {
+            self.expect_sym("(")?;
+            loop {
+                let Tok::StrId(id) = self.next() else {
+                    return Err(self.error("expected string identifier"));
+                };
+                match id.strip_suffix('*') {
+                    Some(prefix) => set.extend(
+                        self.local
+                            .iter()
+                            .filter(|(name, _)| name.starts_with(prefix))
+                            .map(|(_, idx)| *idx),
+                    ),
+                    None => set.push(self.string_index(&id)?),
+                }
+                if !self.eat_sym(",") {
+                    break;
+                }
+            }
+            self.expect_sym(")")?;
+        }
+        if set.is_empty() {
+            return Err(self.error("empty string set"));
+        }
+        Ok(Expr::Of(quantifier, set))
+    }
+}
+
+/// `(size, signed, big_endian)` of an `intXX`/`uintXX[be]` function name.
+fn read_int_function(name: &str) -> Option<(usize, bool, bool)> {
+    let (signed, rest) = match name.strip_prefix("uint") {
+        Some(rest) => (false, rest),
+        None => (true, name.strip_prefix("int")?),
+    };
+    let (bits, big_endian) = match rest.strip_suffix("be") {
+        Some(bits) => (bits, true),
+        None => (rest, false),
+    };
+    let size = match bits {
+        "8" => 1,
+        "16" => 2,
+        "32" => 4,
+        _ => return None,
+    };
+    Some((size, signed, big_endian))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(source: &str) -> Result<(Vec<Rule>, Vec<StringPattern>)> {
+        let mut rules = Vec::new();
+        let mut strings = Vec::new();
+        Parser::parse(source, "test.yar", &mut rules, &mut strings)?;
+        Ok((rules, strings))
+    }
+
+    /// Debug form of the last rule's condition.
+    fn condition(source: &str) -> String {
+        let (rules, _) = parse(source).expect("parse");
+        format!("{:?}", rules.last().expect("rule").condition)
+    }
+
+    fn parse_error(source: &str) -> String {
+        match parse(source) {
+            Ok(_) => panic!("parsed: {source}"),
+            Err(err) => err.to_string(),
+        }
+    }
+
+    #[test]
+    fn tokenizes_comments_numbers_and_escapes() {
+        let tokens = tokenize("// line\n/* two\nlines */ 0x1F 2KB 1MB \"a\\x41\\t\\\"\" /a\\/b/is")
+            .expect("tokenize");
+        let kinds: Vec<Tok> = tokens.iter().map(|(tok, _)| tok.clone()).collect();
+        assert_eq!(
+            kinds,
+            vec![
+                Tok::Int(0x1F),
+                Tok::Int(2048),
+                Tok::Int(1024 * 1024),
+                Tok::Text(b"aA\t\"".to_vec()),
+                Tok::Regex("a/b".to_string(), "is".to_string()),
+                Tok::Eof,
+            ]
+        );
+        assert_eq!(tokens[0].1, 3);
+
+        for source in ["\"open", "/* open", "/open", "$a = { 4D", "\"\\q\"", "`"] {
+            assert!(tokenize(source).is_err(), "{source}");
+        }
+    }
+
+    #[test]
+    fn parses_rule_headers_tags_and_meta() {
+        let (rules, strings) = parse(
+            r#"
+            rule first { condition: true }
+            private global rule second : tag_a tag_b {
+                meta:
+                    author = "me"
+                    score = -5
+                    enabled = true
+                strings:
+                    $a = "x"
+                    $b = "y"
+                condition:
+                    first and $a
+            }
+            "#,
+        )
+        .expect("parse");
+        assert_eq!(rules.len(), 2);
+        assert_eq!(rules[1].name, "second");
+        assert!(rules[1].private && rules[1].global);
+        assert!(!rules[0].private && !rules[0].global);
+        assert_eq!(rules[1].tags, vec!["tag_a", "tag_b"]);
+        assert_eq!(rules[1].strings, 0..2);
+        assert_eq!(strings[1].id, "b");
+        assert_eq!(
+            format!("{:?}", rules[1].condition),
+            "And([RuleRef(0), StringMatch(0)])"
+        );
+    }
+
+    #[test]
+    fn parses_string_kinds_and_modifiers() {
+        let (_, strings) = parse(
+            r#"
+            rule r {
+                strings:
+                    $text = "Key" nocase wide ascii fullword private
+                    $hex = { 4D 5A ?? [2-4] ( 01 | 02 ) }
+                    $re = /ab+c/i
+                    $ = "anonymous"
+                condition:
+                    any of them
+            }
+            "#,
+        )
+        .expect("parse");
+        assert_eq!(strings.len(), 4);
+        assert!(strings[0].fullword && strings[0].wide);
+        assert!(strings[0].regex.is_match(b"kEy") && strings[0].regex.is_match(b"K\0e\0Y\0"));
+        assert!(strings[1].regex.is_match(b"MZ\x00\x01\x02\x03\x02"));
+        assert!(strings[2].regex.is_match(b"ABBC"));
+        assert_eq!(strings[3].id, "");
+    }
+
+    #[test]
+    fn parses_operator_precedence() {
+        let strings = "strings: $a = \"a\" $b = \"b\" $c = \"c\"";
+        assert_eq!(
+            condition(&format!(
+                "rule r {{ {strings} condition: $a or $b and not $c }}"
+            )),
+            "Or([StringMatch(0), And([StringMatch(1), Not(StringMatch(2))])])"
+        );
+        assert_eq!(
+            condition("rule r { condition: 1 + 2 * 3 == 7 }"),
+            "Cmp(\"==\", Arith('+', Int(1), Arith('*', Int(2), Int(3))), Int(7))"
+        );
+        assert_eq!(
+            condition("rule r { condition: (7 \\ 2) % 3 != -1 }"),
+            "Cmp(\"!=\", Arith('%', Arith('\\\\', Int(7), Int(2)), Int(3)), \
+             Arith('-', Int(0), Int(1)))"
+        );
+        for op in ["<", "<=", ">", ">="] {
+            assert!(condition(&format!("rule r {{ condition: 1 {op} 2 }}")).contains(op));
+        }
+    }
+
+    #[test]
+    fn parses_string_operators() {
+        let rule = |cond: &str| {
+            condition(&format!(
+                "rule r {{ strings: $a = \"a\" condition: {cond} }}"
+            ))
+        };
+        assert_eq!(
+            rule("$a at 10 + 2"),
+            "StringAt(0, Arith('+', Int(10), Int(2)))"
+        );
+        assert_eq!(rule("$a in (0..filesize)"), "StringIn(0, Int(0), Filesize)");
+        assert_eq!(rule("#a > 1"), "Cmp(\">\", Count(0), Int(1))");
+        assert_eq!(rule("@a == 0"), "Cmp(\"==\", Offset(0, Int(1)), Int(0))");
+        assert_eq!(
+            rule("@a[1000] == 0"),
+            "Cmp(\"==\", Offset(0, Int(1000)), Int(0))"
+        );
+    }
+
+    #[test]
+    fn parses_quantifiers_and_string_sets() {
+        let rule = |cond: &str| {
+            condition(&format!(
+                "rule r {{ strings: $a1 = \"a\" $a2 = \"b\" $b = \"c\" condition: {cond} }}"
+            ))
+        };
+        assert_eq!(rule("any of them"), "Of(Any, [0, 1, 2])");
+        assert_eq!(rule("all of ($a*)"), "Of(All, [0, 1])");
+        assert_eq!(rule("none of ($b, $a2)"), "Of(None, [2, 1])");
+        assert_eq!(rule("2 of them"), "Of(Count(Int(2)), [0, 1, 2])");
+    }
+
+    #[test]
+    fn parses_integer_reads_and_rule_references() {
+        assert_eq!(
+            condition("rule r { condition: uint16be(2) == int8(0) }"),
+            "Cmp(\"==\", ReadInt { size: 2, signed: false, big_endian: true, offset: Int(2) }, \
+             ReadInt { size: 1, signed: true, big_endian: false, offset: Int(0) })"
+        );
+        assert_eq!(
+            condition("rule r { condition: uint32(filesize - 4) }"),
+            "ReadInt { size: 4, signed: false, big_endian: false, \
+             offset: Arith('-', Filesize, Int(4)) }"
+        );
+        assert_eq!(read_int_function("int64"), None);
+        assert_eq!(
+            condition("rule a { condition: false } rule b { condition: not a }"),
+            "Not(RuleRef(0))"
+        );
+    }
+
+    #[test]
+    fn rejects_unsupported_constructs() {
+        for (source, message) in [
+            ("import \"pe\"", "modules and includes"),
+            ("include \"other.yar\"", "modules and includes"),
+            (
+                "rule r { condition: pe.is_dll() }",
+                "unexpected character `.`",
+            ),
+            (
+                "rule r { strings: $a = \"x\" condition: for any i in (1..2): ($a) }",
+                "`for` loops",
+            ),
+            ("rule r { strings: $a = \"x\" xor condition: $a }", "`xor`"),
+            (
+                "rule r { strings: $a = \"x\" base64 condition: $a }",
+                "`base64`",
+            ),
+            (
+                "rule r { strings: $a = \"x\" base64wide condition: $a }",
+                "`base64wide`",
+            ),
+            (
+                "rule r { strings: $a = /x/ wide condition: $a }",
+                "wide regular",
+            ),
+            ("rule r { strings: $a = /x/m condition: $a }", "flag `m`"),
+            (
+                "rule r { strings: $a = { 4D } nocase condition: $a }",
+                "no modifiers",
+            ),
+            (
+                "rule r { strings: $a = \"x\" condition: @a[1001] > 0 }",
+                "`@a[i]` needs a constant index",
+            ),
+            (
+                "rule r { strings: $a = \"x\" condition: @a[0] > 0 }",
+                "`@a[i]` needs a constant index",
+            ),
+            (
+                "rule r { strings: $a = \"x\" condition: @a[#a] > 0 }",
+                "`@a[i]` needs a constant index",
+            ),
+            (
+                "rule r { strings: $a = \"x\" condition: $a matches /x/ }",
+                "expected `}`",
+            ),
+        ] {
+            let err = parse_error(source);
+            assert!(err.contains(message), "{source}: {err}");
+        }
+    }
+
+    #[test]
+    fn rejects_malformed_rules() {
+        for (source, message) in [
+            (
+                "rule r { condition: true } rule r { condition: true }",
+                "duplicate rule",
+            ),
+            (
+                "rule r { condition: later } rule later { condition: true }",
+                "unknown identifier",
+            ),
+            (
+                "rule r { strings: $a = \"x\" $a = \"y\" condition: $a }",
+                "duplicate string",
+            ),
+            (
+                "rule r { strings: $a = \"\" condition: $a }",
+                "empty string",
+            ),
+            (
+                "rule r { strings: $a* = \"x\" condition: $a }",
+                "cannot end in `*`",
+            ),
+            (
+                "rule r { strings: $a = { 4D 5 } condition: $a }",
+                "string $a",
+            ),
+            ("rule r { condition: $missing }", "undefined string"),
+            (
+                "rule r { strings: $a = \"x\" condition: any of ($b*) }",
+                "empty string set",
+            ),
+            (
+                "rule r { strings: $a = \"x\" condition: any ($a) }",
+                "expected `of`",
+            ),
+            ("rule r { strings: $a = \"x\" }", "expected `condition:`"),
+            (
+                "rule r { meta: a = ( condition: true }",
+                "invalid meta value",
+            ),
+            ("r { condition: true }", "expected `rule`"),
+            ("rule r { condition: (true }", "expected `)`"),
+        ] {
+            let err = parse_error(source);
+            assert!(err.contains(message), "{source}: {err}");
+        }
+        assert!(parse_error("rule r {\n\n condition: nope }").starts_with("test.yar:3:"));
+    }
+}
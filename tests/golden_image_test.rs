@@ -102,7 +102,8 @@ fn derive_extension(path: &str) -> String {
 #[cfg(feature = "ewf")]
 fn cli_opts_for_input(path: PathBuf) -> CliOptions {
     CliOptions {
-        input: path,
+        command: None,
+        input: Some(path),
         output: PathBuf::from("./output"),
         config_path: None,
         gpu: false,
@@ -120,11 +121,47 @@ fn cli_opts_for_input(path: PathBuf) -> CliOptions {
         no_scan_emails: false,
         scan_phones: false,
         no_scan_phones: false,
+        scan_ips: false,
+        no_scan_ips: false,
+        scan_credit_cards: false,
+        no_scan_credit_cards: false,
+        scan_crypto_wallets: false,
+        no_scan_crypto_wallets: false,
+        scan_secrets: false,
+        no_scan_secrets: false,
         string_min_len: None,
         scan_entropy: false,
         entropy_window_bytes: None,
         entropy_threshold: None,
         scan_sqlite_pages: false,
+        tolerant_reads: false,
+        no_skip_constant_blocks: false,
+        start_offset: None,
+        end_offset: None,
+        ranges_file: None,
+        unallocated_only: false,
+        attribute_fs_paths: false,
+        sector_size: None,
+        yara_rules: None,
+        yara_carved: false,
+        keywords: None,
+        keyword_context: None,
+        hash_db: None,
+        known_good: Vec::new(),
+        known_bad: Vec::new(),
+        delete_known_good: false,
+        skip_duplicates: false,
+        hardlink_duplicates: false,
+        fuzzy_match: None,
+        fuzzy_threshold: None,
+        tlsh_max_distance: None,
+        bifragment: false,
+        bifragment_block_size: None,
+        bifragment_max_gap: None,
+        bifragment_max_searches: None,
+        recursive_carving: false,
+        recursive_max_depth: None,
+        recursive_max_size: None,
         max_bytes: None,
         max_chunks: None,
         max_files: None,
@@ -134,12 +171,14 @@ fn cli_opts_for_input(path: PathBuf) -> CliOptions {
         resume_from: None,
         evidence_sha256: None,
         compute_evidence_sha256: false,
+        verify_ewf_hashes: false,
         disable_zip: false,
         types: None,
         enable_types: None,
         dry_run: false,
         validate_carved: false,
         remove_invalid: false,
+        partitions: None,
     }
 }
 
@@ -1,12 +1,17 @@
 use std::fs;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
+use std::sync::atomic::AtomicBool;
 
-use swiftbeaver::config;
-use swiftbeaver::evidence::RawFileSource;
+use serde_json::Value;
+use swiftbeaver::config::{self, Config};
+use swiftbeaver::evidence::{EvidenceSource, RawFileSource};
 use swiftbeaver::metadata::{self, MetadataBackendKind};
-use swiftbeaver::pipeline;
-use swiftbeaver::scanner;
+use swiftbeaver::pipeline::{self, CheckpointConfig, PipelineStats};
+use swiftbeaver::scanner::{self, SignatureScanner};
+use swiftbeaver::strings::{self, StringScanner};
 use swiftbeaver::util;
+use tempfile::TempDir;
 
 fn insert_bytes(target: &mut Vec<u8>, offset: usize, data: &[u8]) {
     let end = offset + data.len();
@@ -225,6 +230,138 @@ fn sample_7z() -> Vec<u8> {
     sevenz
 }
 
+/// Image of `len` bytes that is neither blank nor a file signature.
+fn filled_image(len: u32) -> Vec<u8> {
+    (0..len).map(|idx| (idx % 7) as u8 + 1).collect()
+}
+
+/// Default config with the small sample images let through `min_size`.
+fn test_config() -> Config {
+    let mut cfg = config::load_config(None).expect("config").config;
+    cfg.run_id = "test_run".to_string();
+    for ft in cfg.file_types.iter_mut() {
+        if matches!(ft.id.as_str(), "jpeg" | "png" | "gif" | "bmp") {
+            ft.min_size = 16;
+        }
+    }
+    cfg
+}
+
+/// Run the pipeline over `evidence` with 64 KiB chunks and a 64-byte
+/// overlap, writing JSONL metadata under `output`.
+fn run_pipeline(
+    cfg: &Config,
+    evidence: Arc<dyn EvidenceSource>,
+    output: &Path,
+    max_chunks: Option<u64>,
+    checkpoint: Option<CheckpointConfig>,
+) -> PipelineStats {
+    fs::create_dir_all(output).expect("output dir");
+    let meta_sink = metadata::build_sink(
+        MetadataBackendKind::Jsonl,
+        cfg,
+        &cfg.run_id,
+        "0.1.0",
+        "test",
+        Path::new("image.bin"),
+        "",
+        output,
+    )
+    .expect("metadata sink");
+    let sig_scanner: Arc<dyn SignatureScanner> =
+        Arc::from(scanner::build_signature_scanner(cfg, false).expect("scanner"));
+    let string_scanner: Option<Arc<dyn StringScanner>> = cfg
+        .enable_string_scan
+        .then(|| Arc::from(strings::build_string_scanner(cfg, false).expect("string scanner")));
+    let carve_registry = Arc::new(util::build_carve_registry(cfg, false).expect("registry"));
+
+    pipeline::run_pipeline_with_cancel(
+        cfg,
+        evidence,
+        sig_scanner,
+        string_scanner,
+        meta_sink,
+        output,
+        2,
+        64 * 1024,
+        64,
+        None,
+        max_chunks,
+        carve_registry,
+        Arc::new(AtomicBool::new(false)),
+        None,
+        checkpoint,
+    )
+    .expect("pipeline")
+}
+
+/// Records of a JSONL metadata table, in file order.
+fn read_table(output: &Path, table: &str) -> Vec<Value> {
+    let path = output.join("metadata").join(format!("{table}.jsonl"));
+    fs::read_to_string(path)
+        .expect("metadata read")
+        .lines()
+        .map(|line| serde_json::from_str(line).expect("json"))
+        .collect()
+}
+
+/// A finished pipeline run and its scratch directory.
+struct TestRun {
+    dir: TempDir,
+    stats: PipelineStats,
+}
+
+impl TestRun {
+    fn output(&self) -> PathBuf {
+        self.dir.path().join("run")
+    }
+
+    fn table(&self, table: &str) -> Vec<Value> {
+        read_table(&self.output(), table)
+    }
+
+    fn summary(&self) -> Value {
+        self.table("run_summary").remove(0)
+    }
+
+    /// The first carved file record of `file_type`.
+    fn carved(&self, file_type: &str) -> Value {
+        self.table("carved_files")
+            .into_iter()
+            .find(|v| v["file_type"] == file_type)
+            .unwrap_or_else(|| panic!("no {file_type} record"))
+    }
+
+    /// Where a carved file record's file was written.
+    fn carved_path(&self, record: &Value) -> PathBuf {
+        self.output()
+            .join("carved")
+            .join(record["path"].as_str().expect("path"))
+    }
+}
+
+/// Write `image` to a scratch directory and run the pipeline over it.
+/// `configure` adjusts the config and gets the directory for any rule,
+/// list or reference files it needs.
+fn run_image(image: &[u8], configure: impl FnOnce(&mut Config, &Path)) -> TestRun {
+    let dir = tempfile::tempdir().expect("tempdir");
+    let input = dir.path().join("image.bin");
+    fs::write(&input, image).expect("write input");
+    let evidence = RawFileSource::open(&input).expect("evidence");
+    run_source(dir, Arc::new(evidence), configure)
+}
+
+fn run_source(
+    dir: TempDir,
+    evidence: Arc<dyn EvidenceSource>,
+    configure: impl FnOnce(&mut Config, &Path),
+) -> TestRun {
+    let mut cfg = test_config();
+    configure(&mut cfg, dir.path());
+    let stats = run_pipeline(&cfg, evidence, &dir.path().join("run"), None, None);
+    TestRun { dir, stats }
+}
+
 #[test]
 fn integration_carves_basic_formats() {
     let temp_dir = tempfile::tempdir().expect("tempdir");
@@ -336,3 +473,900 @@ fn integration_carves_basic_formats() {
     assert!(types.contains(&"rar".to_string()));
     assert!(types.contains(&"7z".to_string()));
 }
+
+#[test]
+fn integration_validate_carved_records_verdicts() {
+    // sample_png carries zeroed CRCs, so it fails deep validation.
+    let mut image = vec![0u8; 200_000];
+    insert_bytes(&mut image, 1024, &sample_png());
+    insert_bytes(&mut image, 65_536, &sample_docx_zip());
+    insert_bytes(&mut image, 131_072, &sample_gif());
+
+    let run = run_image(&image, |cfg, _| {
+        cfg.validate_carved = true;
+        cfg.remove_invalid = true;
+    });
+
+    assert_eq!(run.stats.validation_pass, 1);
+    assert_eq!(run.stats.validation_fail, 1);
+
+    let png = run.carved("png");
+    assert_eq!(png["validation_status"], "failed");
+    assert!(
+        png["validation_reason"]
+            .as_str()
+            .unwrap_or_default()
+            .contains("crc")
+    );
+    assert!(!run.carved_path(&png).exists());
+    assert_eq!(run.carved("docx")["validation_status"], "passed");
+    assert_eq!(run.carved("gif")["validation_status"], "unsupported");
+}
+
+#[test]
+fn integration_partitions_limit_scan_and_attribute_files() {
+    // MBR with two Linux partitions: sectors 128..256 and 256..512.
+    let mut image = vec![0u8; 262_144];
+    for (slot, (lba, sectors)) in [(128u32, 128u32), (256, 256)].iter().enumerate() {
+        let at = 446 + slot * 16;
+        image[at + 4] = 0x83;
+        image[at + 8..at + 12].copy_from_slice(&lba.to_le_bytes());
+        image[at + 12..at + 16].copy_from_slice(&sectors.to_le_bytes());
+    }
+    image[510] = 0x55;
+    image[511] = 0xAA;
+    insert_bytes(&mut image, 65_536 + 1024, &sample_gif());
+    insert_bytes(&mut image, 131_072 + 512, &sample_jpeg());
+
+    let run = run_image(&image, |cfg, _| cfg.partitions = Some(vec![2]));
+
+    assert_eq!(run.stats.bytes_scanned, 131_072 + 64);
+    assert_eq!(run.stats.files_carved, 1);
+    let jpeg = run.carved("jpeg");
+    assert_eq!(jpeg["partition_index"], 2);
+    assert_eq!(jpeg["partition_type"], "0x83");
+    assert_eq!(jpeg["partition_offset"], 512);
+}
+
+#[test]
+fn integration_skips_constant_blocks() {
+    // Zeroed image with a wiped (0xFF) region and one JPEG between blocks.
+    let mut image = vec![0u8; 262_144];
+    image[131_072..196_608].fill(0xFF);
+    insert_bytes(&mut image, 100_000, &sample_jpeg());
+
+    let run = run_image(&image, |_, _| {});
+
+    // Only the 4 KiB block holding the JPEG is scanned.
+    assert_eq!(run.stats.bytes_skipped, 262_144 - 4096);
+    assert_eq!(run.stats.files_carved, 1);
+
+    let summary = run.summary();
+    assert_eq!(summary["bytes_skipped"], 262_144 - 4096);
+    let ranges: Vec<(u64, u64, u64)> = summary["skipped_ranges"]
+        .as_array()
+        .expect("ranges")
+        .iter()
+        .map(|r| {
+            (
+                r["global_start"].as_u64().unwrap(),
+                r["global_end"].as_u64().unwrap(),
+                r["fill"].as_u64().unwrap(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        ranges,
+        vec![
+            (0, 98_304, 0),
+            (102_400, 131_072, 0),
+            (131_072, 196_608, 255),
+            (196_608, 262_144, 0),
+        ]
+    );
+}
+
+/// In-memory evidence whose reads fail when they touch a bad byte range.
+struct FlakySource {
+    data: Vec<u8>,
+    bad: (u64, u64),
+}
+
+impl EvidenceSource for FlakySource {
+    fn len(&self) -> u64 {
+        self.data.len() as u64
+    }
+
+    fn read_at(
+        &self,
+        offset: u64,
+        buf: &mut [u8],
+    ) -> Result<usize, swiftbeaver::evidence::EvidenceError> {
+        let end = offset + buf.len() as u64;
+        if offset < self.bad.1 && end > self.bad.0 {
+            return Err(swiftbeaver::evidence::EvidenceError::Io(
+                std::io::Error::other("medium error"),
+            ));
+        }
+        let start = (offset as usize).min(self.data.len());
+        let n = buf.len().min(self.data.len() - start);
+        buf[..n].copy_from_slice(&self.data[start..start + n]);
+        Ok(n)
+    }
+}
+
+#[test]
+fn integration_tolerant_reads_record_bad_sectors() {
+    // One JPEG on readable media and one spanning a bad sector.
+    let mut image = filled_image(131_072);
+    insert_bytes(&mut image, 4096, &sample_jpeg());
+    insert_bytes(&mut image, 69_000, &sample_jpeg()[..30]);
+    insert_bytes(&mut image, 70_198, &[0xFF, 0xD9]);
+
+    let flaky = FlakySource {
+        data: image,
+        bad: (69_700, 69_710),
+    };
+    let evidence = swiftbeaver::evidence::TolerantSource::new(Box::new(flaky));
+    let dir = tempfile::tempdir().expect("tempdir");
+    let run = run_source(dir, Arc::new(evidence), |cfg, _| cfg.tolerant_reads = true);
+    assert_eq!(run.stats.files_carved, 2);
+
+    // Sector 136 (69_632..70_144) is the only one that could not be read.
+    let ranges: Vec<(u64, u64)> = run
+        .table("bad_sectors")
+        .iter()
+        .map(|v| {
+            (
+                v["global_start"].as_u64().unwrap(),
+                v["length"].as_u64().unwrap(),
+            )
+        })
+        .collect();
+    assert_eq!(ranges, vec![(69_632, 512)]);
+    assert!(run.output().join("bad_sectors.map").exists());
+
+    let flagged: Vec<u64> = run
+        .table("carved_files")
+        .iter()
+        .filter(|v| {
+            v["errors"]
+                .as_array()
+                .expect("errors")
+                .iter()
+                .any(|e| e.as_str().unwrap_or("").contains("unreadable sectors"))
+        })
+        .map(|v| v["global_start"].as_u64().unwrap())
+        .collect();
+    assert_eq!(flagged, vec![69_000]);
+}
+
+#[test]
+fn integration_ranges_file_limits_scan_and_checkpoints_ranges() {
+    let dir = tempfile::tempdir().expect("tempdir");
+    let input = dir.path().join("image.bin");
+    let mut image = filled_image(262_144);
+    insert_bytes(&mut image, 10_000, &sample_jpeg());
+    insert_bytes(&mut image, 100_000, &sample_jpeg());
+    insert_bytes(&mut image, 200_000, &sample_jpeg());
+    fs::write(&input, &image).expect("write input");
+
+    let ranges_path = dir.path().join("ranges.csv");
+    fs::write(&ranges_path, "start,length\n90000,20000\n190000,30000\n").expect("ranges");
+    let mut cfg = test_config();
+    cfg.ranges_file = Some(ranges_path);
+    cfg.end_offset = Some(210_000);
+
+    let run = |output: &Path, max_chunks: Option<u64>, checkpoint: CheckpointConfig| {
+        let evidence = RawFileSource::open(&input).expect("evidence");
+        run_pipeline(
+            &cfg,
+            Arc::new(evidence),
+            output,
+            max_chunks,
+            Some(checkpoint),
+        )
+    };
+    let carved_starts = |output: &Path| -> Vec<u64> {
+        let mut starts: Vec<u64> = read_table(output, "carved_files")
+            .iter()
+            .map(|v| v["global_start"].as_u64().unwrap())
+            .collect();
+        starts.sort_unstable();
+        starts
+    };
+
+    // Stop after the first range; the checkpoint records it as done.
+    let checkpoint_path = dir.path().join("checkpoint.json");
+    let first_dir = dir.path().join("run1");
+    let stats = run(
+        &first_dir,
+        Some(1),
+        CheckpointConfig {
+            path: checkpoint_path.clone(),
+            resume: None,
+        },
+    );
+    assert_eq!(stats.bytes_scanned, 20_000);
+    assert_eq!(carved_starts(&first_dir), vec![100_000]);
+    let state = swiftbeaver::checkpoint::load_checkpoint(&checkpoint_path).expect("checkpoint");
+    assert_eq!(
+        state.scan_ranges,
+        vec![(90_000, 110_000), (190_000, 210_000)]
+    );
+    assert_eq!(state.completed_ranges, vec![(90_000, 110_000)]);
+
+    // Resuming scans only the remaining range; offsets stay absolute.
+    let second_dir = dir.path().join("run2");
+    let stats = run(
+        &second_dir,
+        None,
+        CheckpointConfig {
+            path: checkpoint_path,
+            resume: Some(state),
+        },
+    );
+    assert_eq!(stats.bytes_scanned, 40_000);
+    assert_eq!(carved_starts(&second_dir), vec![200_000]);
+}
+
+fn set_fat12_entry(fat: &mut [u8], cluster: usize, value: u16) {
+    let at = cluster + cluster / 2;
+    let raw = u16::from_le_bytes([fat[at], fat[at + 1]]);
+    let raw = if cluster % 2 == 1 {
+        (raw & 0x000F) | (value << 4)
+    } else {
+        (raw & 0xF000) | (value & 0x0FFF)
+    };
+    fat[at..at + 2].copy_from_slice(&raw.to_le_bytes());
+}
+
+/// FAT12 volume: 512-byte sectors and clusters, 1 reserved sector, 1 FAT
+/// sector, 16 root entries, 64 sectors; data starts at sector 3 (cluster
+/// 2). `chain` sets FAT entries past the two reserved ones.
+fn fat12_image(chain: &[(usize, u16)]) -> Vec<u8> {
+    let mut image = filled_image(64 * 512);
+    image[..3 * 512].fill(0);
+    image[0] = 0xEB;
+    image[11..13].copy_from_slice(&512u16.to_le_bytes());
+    image[13] = 1;
+    image[14..16].copy_from_slice(&1u16.to_le_bytes());
+    image[16] = 1;
+    image[17..19].copy_from_slice(&16u16.to_le_bytes());
+    image[19..21].copy_from_slice(&64u16.to_le_bytes());
+    image[22..24].copy_from_slice(&1u16.to_le_bytes());
+    image[510] = 0x55;
+    image[511] = 0xAA;
+    let fat = &mut image[512..1024];
+    set_fat12_entry(fat, 0, 0xFF8);
+    set_fat12_entry(fat, 1, 0xFFF);
+    for &(cluster, value) in chain {
+        set_fat12_entry(fat, cluster, value);
+    }
+    image
+}
+
+fn fat12_cluster_offset(cluster: usize) -> usize {
+    (cluster + 1) * 512
+}
+
+#[test]
+fn integration_unallocated_only_scans_free_clusters() {
+    let mut image = fat12_image(&[(2, 0xFFF), (11, 0xFFF)]);
+    // Allocated, free, and straddling the free/allocated boundary.
+    insert_bytes(&mut image, fat12_cluster_offset(2) + 100, &sample_jpeg());
+    insert_bytes(&mut image, fat12_cluster_offset(4) + 40, &sample_jpeg());
+    insert_bytes(&mut image, fat12_cluster_offset(11) - 10, &sample_jpeg());
+
+    let run = run_image(&image, |cfg, _| cfg.unallocated_only = true);
+
+    // System area plus clusters 2 and 11 are never scanned.
+    assert_eq!(run.stats.bytes_scanned, 64 * 512 - 5 * 512);
+    let mut files: Vec<(u64, String)> = run
+        .table("carved_files")
+        .iter()
+        .map(|v| {
+            (
+                v["global_start"].as_u64().unwrap(),
+                v["allocation_status"].as_str().unwrap().to_string(),
+            )
+        })
+        .collect();
+    files.sort();
+    assert_eq!(
+        files,
+        vec![
+            (
+                fat12_cluster_offset(4) as u64 + 40,
+                "unallocated".to_string()
+            ),
+            (
+                fat12_cluster_offset(11) as u64 - 10,
+                "partially_allocated".to_string()
+            ),
+        ]
+    );
+}
+
+#[test]
+fn integration_attribute_fs_paths_maps_offsets_to_live_files() {
+    // `PHOTO.BIN` (1500 bytes) is fragmented over clusters 4, 9 and 10;
+    // cluster 20 belongs to no file.
+    let mut image = fat12_image(&[(4, 9), (9, 10), (10, 0xFFF)]);
+    let entry = &mut image[2 * 512..2 * 512 + 32];
+    entry[..11].copy_from_slice(b"PHOTO   BIN");
+    entry[11] = 0x20;
+    entry[26..28].copy_from_slice(&4u16.to_le_bytes());
+    entry[28..32].copy_from_slice(&1500u32.to_le_bytes());
+    insert_bytes(&mut image, fat12_cluster_offset(9) + 20, &sample_jpeg());
+    insert_bytes(
+        &mut image,
+        fat12_cluster_offset(10) + 8,
+        b"https://example.com/evidence ",
+    );
+    insert_bytes(&mut image, fat12_cluster_offset(20), &sample_jpeg());
+
+    let run = run_image(&image, |cfg, _| {
+        cfg.attribute_fs_paths = true;
+        cfg.enable_string_scan = true;
+    });
+
+    let mut files: Vec<(u64, Value, Value, Value)> = run
+        .table("carved_files")
+        .into_iter()
+        .map(|v| {
+            (
+                v["global_start"].as_u64().unwrap(),
+                v["containing_fs_path"].clone(),
+                v["containing_inode"].clone(),
+                v["offset_in_file"].clone(),
+            )
+        })
+        .collect();
+    files.sort_by_key(|file| file.0);
+    assert_eq!(
+        files,
+        vec![
+            (
+                fat12_cluster_offset(9) as u64 + 20,
+                serde_json::json!("/PHOTO.BIN"),
+                serde_json::json!(4),
+                serde_json::json!(532),
+            ),
+            (
+                fat12_cluster_offset(20) as u64,
+                Value::Null,
+                Value::Null,
+                Value::Null,
+            ),
+        ]
+    );
+
+    let url = run
+        .table("string_artefacts")
+        .into_iter()
+        .find(|v| v["content"].as_str() == Some("https://example.com/evidence"))
+        .expect("url artefact");
+    assert_eq!(url["containing_fs_path"], "/PHOTO.BIN");
+    assert_eq!(url["offset_in_file"], 1032);
+}
+
+#[test]
+fn integration_sector_size_drops_unaligned_hits() {
+    let mut image = filled_image(131_072);
+    insert_bytes(&mut image, 8_192, &sample_bmp());
+    insert_bytes(&mut image, 20_001, &sample_bmp());
+    insert_bytes(&mut image, 40_003, &sample_jpeg());
+
+    let run = run_image(&image, |cfg, _| cfg.sector_size = Some(512));
+
+    // The unaligned BMP is dropped; JPEG opts out of sector alignment.
+    let mut carved: Vec<(String, u64)> = run
+        .table("carved_files")
+        .iter()
+        .map(|v| {
+            (
+                v["file_type"].as_str().unwrap().to_string(),
+                v["global_start"].as_u64().unwrap(),
+            )
+        })
+        .collect();
+    carved.sort();
+    assert_eq!(
+        carved,
+        vec![("bmp".to_string(), 8_192), ("jpeg".to_string(), 40_003)]
+    );
+    assert_eq!(run.summary()["unaligned_hits"]["bmp"], 1);
+}
+
+#[test]
+fn integration_yara_rules_match_chunks_and_carved_files() {
+    let mut image = filled_image(131_072);
+    insert_bytes(&mut image, 10_000, &sample_jpeg());
+    // Straddles the first chunk boundary: reported once, by the first chunk
+    insert_bytes(&mut image, 65_531, b"EVIL_MARKER");
+    insert_bytes(&mut image, 70_000, b"EVIL_MARKER");
+
+    let run = run_image(&image, |cfg, dir| {
+        let rules_path = dir.join("rules.yar");
+        fs::write(
+            &rules_path,
+            r#"
+rule marker : suspicious {
+    strings:
+        $m = "EVIL_MARKER"
+    condition:
+        $m
+}
+
+rule jpeg_magic {
+    strings:
+        $soi = { FF D8 FF }
+    condition:
+        $soi at 0
+}
+"#,
+        )
+        .expect("write rules");
+        cfg.yara_rules = Some(rules_path);
+        cfg.yara_scan_carved = true;
+    });
+
+    let records = run.table("yara_matches");
+    let mut markers: Vec<u64> = records
+        .iter()
+        .filter(|v| v["rule"] == "marker")
+        .map(|v| {
+            assert_eq!(v["tags"], serde_json::json!(["suspicious"]));
+            assert_eq!(v["string_id"], "$m");
+            assert!(v["carved_path"].is_null());
+            v["global_offset"].as_u64().unwrap()
+        })
+        .collect();
+    markers.sort();
+    assert_eq!(markers, vec![65_531, 70_000]);
+
+    let jpeg: Vec<&Value> = records
+        .iter()
+        .filter(|v| v["rule"] == "jpeg_magic")
+        .collect();
+    assert_eq!(jpeg.len(), 1);
+    assert_eq!(jpeg[0]["global_offset"], 10_000);
+    assert!(jpeg[0]["carved_path"].as_str().unwrap().ends_with(".jpg"));
+}
+
+#[test]
+fn integration_custom_artefacts_from_config() {
+    let mut image = filled_image(65_536);
+    insert_bytes(
+        &mut image,
+        1_000,
+        b" pay to GB82 WEST 1234 5698 7654 32 today ",
+    );
+    insert_bytes(&mut image, 2_000, b" bad GB82 WEST 1234 5698 7654 33 typo ");
+    insert_bytes(&mut image, 3_000, b" ref CASE-2024-0042 filed ");
+
+    let run = run_image(&image, |cfg, dir| {
+        let config_path = dir.join("config.yml");
+        let yaml = include_str!("../config/default.yml")
+            .replace("enable_string_scan: false", "enable_string_scan: true")
+            .replace(
+                "custom_artefacts: []",
+                r#"custom_artefacts:
+  - name: "iban"
+    regex: "\\b[A-Z]{2}\\d{2}(?: ?[A-Z0-9]{4}){3,7}(?: ?[A-Z0-9]{1,3})?\\b"
+    validator: iban
+  - name: "case_ref"
+    regex: "CASE-\\d{4}-\\d{4}"
+    encoding: ascii"#,
+            );
+        fs::write(&config_path, yaml).expect("write config");
+        *cfg = config::load_config(Some(&config_path))
+            .expect("config")
+            .config;
+    });
+
+    let mut custom: Vec<(String, String, u64)> = run
+        .table("string_artefacts")
+        .iter()
+        .filter_map(|v| {
+            let name = v["artefact_kind"]["Custom"].as_str()?.to_string();
+            Some((
+                name,
+                v["content"].as_str().unwrap().to_string(),
+                v["global_start"].as_u64().unwrap(),
+            ))
+        })
+        .collect();
+    custom.sort();
+    assert_eq!(
+        custom,
+        vec![
+            ("case_ref".to_string(), "CASE-2024-0042".to_string(), 3_005),
+            (
+                "iban".to_string(),
+                "GB82 WEST 1234 5698 7654 32".to_string(),
+                1_008
+            ),
+        ]
+    );
+}
+
+#[test]
+fn integration_keyword_hits_with_context_and_carved_path() {
+    let wide = |text: &str| -> Vec<u8> { text.encode_utf16().flat_map(u16::to_le_bytes).collect() };
+    let mut image = filled_image(131_072);
+    insert_bytes(&mut image, 10_000, &sample_jpeg());
+    // Straddles the first chunk boundary: reported once, by the first chunk
+    insert_bytes(&mut image, 65_530, &wide("Invoice"));
+    insert_bytes(&mut image, 80_000, b"acct-4521");
+
+    let run = run_image(&image, |cfg, dir| {
+        let keywords_path = dir.join("keywords.txt");
+        fs::write(
+            &keywords_path,
+            "# case 42\nJFIF\ninvoice\ti\nacct-[0-9]{4}\tr\n",
+        )
+        .expect("write keywords");
+        cfg.keywords = Some(keywords_path);
+        cfg.keyword_context_bytes = 4;
+    });
+
+    let mut records = run.table("keyword_hits");
+    records.sort_by_key(|v| v["global_offset"].as_u64());
+    let found: Vec<(&str, &str, u64)> = records
+        .iter()
+        .map(|v| {
+            (
+                v["term"].as_str().unwrap(),
+                v["encoding"].as_str().unwrap(),
+                v["global_offset"].as_u64().unwrap(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        found,
+        vec![
+            ("JFIF", "ascii", 10_004),
+            ("invoice", "utf-16le", 65_530),
+            ("acct-[0-9]{4}", "ascii", 80_000),
+        ]
+    );
+
+    assert!(
+        records[0]["carved_path"]
+            .as_str()
+            .unwrap()
+            .ends_with(".jpg")
+    );
+    assert!(records[1]["carved_path"].is_null());
+
+    // Context past the chunk end is read from the evidence
+    let invoice = &records[1];
+    assert_eq!(invoice["length"], 14);
+    assert_eq!(invoice["context_start"], 65_526);
+    assert_eq!(
+        invoice["context_hex"].as_str().unwrap(),
+        hex::encode(&image[65_526..65_548])
+    );
+}
+
+#[test]
+fn integration_block_hash_matches_merge_across_chunks() {
+    let mut state = 0x2545_f491u32;
+    let reference: Vec<u8> = (0..6 * 4096)
+        .map(|_| {
+            state = state.wrapping_mul(1_103_515_245).wrapping_add(12_345);
+            (state >> 16) as u8
+        })
+        .collect();
+    let mut image = filled_image(131_072);
+    // The whole file crosses the first chunk boundary at 64 KiB
+    insert_bytes(&mut image, 15 * 4096, &reference);
+    insert_bytes(&mut image, 25 * 4096, &reference[2 * 4096..4 * 4096]);
+
+    let run = run_image(&image, |cfg, dir| {
+        let source_dir = dir.join("known");
+        let db_path = dir.join("known.hashdb");
+        fs::create_dir_all(&source_dir).expect("source dir");
+        fs::write(source_dir.join("report.docx"), &reference).expect("write reference");
+        swiftbeaver::hashdb::build(&source_dir, &db_path, 4096).expect("build hash db");
+        cfg.hash_db = Some(db_path);
+    });
+
+    let records = run.table("block_hash_matches");
+    let mut found: Vec<(&str, u64, u64, u64, u64)> = records
+        .iter()
+        .map(|v| {
+            (
+                v["source_file"].as_str().unwrap(),
+                v["global_start"].as_u64().unwrap(),
+                v["length"].as_u64().unwrap(),
+                v["block_index"].as_u64().unwrap(),
+                v["block_count"].as_u64().unwrap(),
+            )
+        })
+        .collect();
+    found.sort_by_key(|&(_, start, ..)| start);
+    assert_eq!(
+        found,
+        vec![
+            ("report.docx", 15 * 4096, 6 * 4096, 0, 6),
+            ("report.docx", 25 * 4096, 2 * 4096, 2, 2),
+        ]
+    );
+}
+
+#[test]
+fn integration_known_good_and_known_bad_hash_sets() {
+    let mut image = vec![0u8; 200_000];
+    insert_bytes(&mut image, 1024, &sample_jpeg());
+    insert_bytes(&mut image, 65_536, &sample_png());
+    insert_bytes(&mut image, 131_072, &sample_gif());
+
+    let run = run_image(&image, |cfg, dir| {
+        // The bad set lists SHA-1 only, which carving does not record
+        let gif_path = dir.join("sample.gif");
+        fs::write(&gif_path, sample_gif()).expect("write gif");
+        let gif_sha1 = swiftbeaver::hashset::sha1_file(&gif_path).expect("sha1");
+        let good_path = dir.join("baseline.txt");
+        let bad_path = dir.join("watchlist.txt");
+        fs::write(
+            &good_path,
+            format!("{:x}  sample.jpg\n", md5::compute(sample_jpeg())),
+        )
+        .expect("write good set");
+        fs::write(&bad_path, format!("{}\n", hex::encode(gif_sha1))).expect("write bad set");
+        cfg.known_good = vec![good_path];
+        cfg.known_bad = vec![bad_path];
+        cfg.delete_known_good = true;
+    });
+
+    let jpeg = run.carved("jpeg");
+    assert_eq!(jpeg["hash_match"], "known_good");
+    assert_eq!(jpeg["hash_set"], "baseline");
+    assert!(!run.carved_path(&jpeg).exists());
+    let gif = run.carved("gif");
+    assert_eq!(gif["hash_match"], "known_bad");
+    assert_eq!(gif["hash_set"], "watchlist");
+    assert!(run.carved_path(&gif).exists());
+    assert!(run.carved("png")["hash_match"].is_null());
+
+    let summary = run.summary();
+    assert_eq!(summary["known_good_files"], 1);
+    assert_eq!(summary["known_bad_files"], 1);
+}
+
+/// Three copies of one JPEG and a PNG, carved with the duplicate flags.
+fn run_duplicates(skip_duplicates: bool, hardlink_duplicates: bool) -> TestRun {
+    let mut image = vec![0u8; 200_000];
+    for offset in [1024, 65_536, 131_072] {
+        insert_bytes(&mut image, offset, &sample_jpeg());
+    }
+    insert_bytes(&mut image, 150_000, &sample_png());
+    run_image(&image, |cfg, _| {
+        cfg.skip_duplicates = skip_duplicates;
+        cfg.hardlink_duplicates = hardlink_duplicates;
+    })
+}
+
+#[test]
+fn integration_skip_duplicates_keeps_first_copy() {
+    let run = run_duplicates(true, false);
+    let records = run.table("carved_files");
+
+    let mut jpegs: Vec<&Value> = records
+        .iter()
+        .filter(|v| v["file_type"] == "jpeg")
+        .collect();
+    jpegs.sort_by_key(|v| v["global_start"].as_u64());
+    let offsets: Vec<u64> = jpegs
+        .iter()
+        .map(|v| v["global_start"].as_u64().unwrap())
+        .collect();
+    assert_eq!(offsets, vec![1024, 65_536, 131_072]);
+
+    let (firsts, dupes): (Vec<_>, Vec<_>) =
+        jpegs.into_iter().partition(|v| v["is_duplicate"] == false);
+    assert_eq!(firsts.len(), 1);
+    assert!(firsts[0]["duplicate_of"].is_null());
+    assert!(run.carved_path(firsts[0]).exists());
+    for dupe in dupes {
+        assert_eq!(dupe["duplicate_of"], firsts[0]["path"]);
+        assert!(!run.carved_path(dupe).exists());
+        assert!(
+            dupe["errors"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .any(|e| e == "removed as duplicate")
+        );
+    }
+
+    assert_eq!(run.carved("png")["is_duplicate"], false);
+}
+
+#[cfg(unix)]
+#[test]
+fn integration_hardlink_duplicates_share_first_copy() {
+    use std::os::unix::fs::MetadataExt;
+
+    let run = run_duplicates(true, true);
+    let records = run.table("carved_files");
+
+    let jpegs: Vec<&Value> = records
+        .iter()
+        .filter(|v| v["file_type"] == "jpeg")
+        .collect();
+    assert_eq!(jpegs.len(), 3);
+    let inodes: Vec<u64> = jpegs
+        .iter()
+        .map(|v| {
+            assert!(v["errors"].as_array().unwrap().is_empty());
+            fs::metadata(run.carved_path(v)).expect("carved file").ino()
+        })
+        .collect();
+    assert!(inodes.iter().all(|&ino| ino == inodes[0]));
+    assert_eq!(
+        jpegs.iter().filter(|v| v["is_duplicate"] == true).count(),
+        2
+    );
+}
+
+#[test]
+fn integration_fuzzy_match_reports_similar_files() {
+    let mut jpeg = sample_jpeg();
+    jpeg.truncate(30);
+    jpeg.extend((0..2000u32).map(|i| (i.wrapping_mul(2_654_435_761) >> 13) as u8 & 0x7f));
+    jpeg.extend_from_slice(&[0xFF, 0xD9]);
+    let mut image = vec![0u8; 64 * 1024];
+    insert_bytes(&mut image, 4096, &jpeg);
+
+    let mut hasher = swiftbeaver::fuzzy::FuzzyHasher::new();
+    hasher.update(&jpeg);
+    let digests = hasher.finish();
+    let ssdeep = digests.ssdeep.expect("ssdeep");
+    let tlsh = digests.tlsh.expect("tlsh");
+
+    let run = run_image(&image, |cfg, dir| {
+        let refs_path = dir.join("refs.txt");
+        fs::write(
+            &refs_path,
+            format!("{ssdeep},\"leaked.jpg\"\n{tlsh}\tleaked.jpg\n"),
+        )
+        .expect("write refs");
+        cfg.fuzzy_match = Some(refs_path);
+    });
+
+    let record = run.carved("jpeg");
+    assert_eq!(record["ssdeep"], ssdeep.as_str());
+    assert_eq!(record["tlsh"], tlsh.as_str());
+
+    let mut matches = run.table("fuzzy_matches");
+    matches.sort_by(|a, b| a["algorithm"].as_str().cmp(&b["algorithm"].as_str()));
+    let algorithms: Vec<&str> = matches
+        .iter()
+        .map(|m| m["algorithm"].as_str().unwrap())
+        .collect();
+    assert_eq!(algorithms, vec!["ssdeep", "tlsh"]);
+    for m in &matches {
+        assert_eq!(m["reference_name"], "leaked.jpg");
+        assert_eq!(m["carved_path"], record["path"]);
+        assert_eq!(m["global_start"], 4096);
+    }
+}
+
+fn golden_sample(path: &str) -> Vec<u8> {
+    fs::read(
+        Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/golden_image/samples")
+            .join(path),
+    )
+    .expect("golden sample")
+}
+
+#[test]
+fn integration_bifragment_reassembles_split_jpeg() {
+    let jpeg = golden_sample("images/test_generated.jpg");
+    // First fragment fills four 512-byte blocks; two blocks of another
+    // file sit between it and the rest of the image.
+    let gap: Vec<u8> = (0..1024u32)
+        .map(|i| (i.wrapping_mul(2_654_435_761) >> 13) as u8 & 0x7f)
+        .collect();
+    let mut image = vec![0u8; 64 * 1024];
+    insert_bytes(&mut image, 4096, &jpeg[..2048]);
+    insert_bytes(&mut image, 6144, &gap);
+    insert_bytes(&mut image, 7168, &jpeg[2048..]);
+
+    let run = run_image(&image, |cfg, _| {
+        cfg.bifragment = true;
+        cfg.bifragment_block_size = 512;
+        cfg.bifragment_max_gap = 8192;
+    });
+
+    let record = run
+        .table("carved_files")
+        .into_iter()
+        .find(|v| v["file_type"] == "jpeg" && v["global_start"] == 4096)
+        .expect("jpeg record");
+    assert_eq!(record["size"], jpeg.len() as u64);
+    assert_eq!(record["global_end"], 7168 + jpeg.len() as u64 - 2048 - 1);
+    assert_eq!(
+        record["fragments"],
+        serde_json::json!([
+            {"start": 4096, "length": 2048},
+            {"start": 7168, "length": jpeg.len() - 2048},
+        ])
+    );
+    assert_eq!(
+        fs::read(run.carved_path(&record)).expect("carved jpeg"),
+        jpeg
+    );
+}
+
+#[test]
+fn integration_recursive_carving_finds_jpeg_in_gzip() {
+    use std::io::Write;
+
+    let jpeg = golden_sample("images/test_generated.jpg");
+    let mut encoder = flate2::GzBuilder::new()
+        .filename("photo.jpg")
+        .write(Vec::new(), flate2::Compression::default());
+    encoder.write_all(&jpeg).expect("write");
+    let gzip = encoder.finish().expect("gzip");
+    let mut image = vec![0u8; 64 * 1024];
+    insert_bytes(&mut image, 1024, &gzip);
+
+    let run = run_image(&image, |cfg, _| cfg.recursive_carving = true);
+
+    let container = run.carved("gzip");
+    assert_eq!(container["depth"], 0);
+    let record = run.carved("jpeg");
+    assert_eq!(record["depth"], 1);
+    assert_eq!(record["parent_path"], container["path"]);
+    assert_eq!(record["parent_id"], "photo.jpg");
+    assert_eq!(record["global_start"], container["global_start"]);
+    assert_eq!(record["offset_in_parent"], 0);
+    assert_eq!(record["size"], jpeg.len() as u64);
+    assert_eq!(
+        fs::read(run.carved_path(&record)).expect("carved jpeg"),
+        jpeg
+    );
+}
+
+#[test]
+fn integration_lists_tar_members_in_archive_entries() {
+    let mut image = vec![0u8; 64 * 1024];
+    insert_bytes(&mut image, 4096, &golden_sample("archives/test.tar"));
+
+    let run = run_image(&image, |_, _| {});
+
+    let entries = run.table("archive_entries");
+    assert_eq!(entries.len(), 1);
+    assert_eq!(entries[0]["carved_path"], run.carved("tar")["path"]);
+    assert_eq!(entries[0]["name"], "tarfile.txt");
+    assert_eq!(entries[0]["uncompressed_size"], 29);
+    assert_eq!(entries[0]["encrypted"], false);
+}
+
+#[test]
+fn integration_flags_encrypted_zip() {
+    let mut zip = sample_docx_zip();
+    let cd_offset = 30 + "word/document.xml".len() + 1;
+    // Encrypted flag in the local header and the central directory
+    zip[6] = 0x01;
+    zip[cd_offset + 8] = 0x01;
+    let mut image = vec![0u8; 64 * 1024];
+    insert_bytes(&mut image, 4096, &zip);
+    insert_bytes(&mut image, 32 * 1024, &sample_pdf());
+
+    let run = run_image(&image, |_, _| {});
+
+    let docx = run.carved("docx");
+    assert_eq!(docx["encryption"], "full");
+    assert_eq!(docx["encryption_algorithm"], "zipcrypto");
+    let pdf = run.carved("pdf");
+    assert_eq!(pdf["encryption"], "none");
+    assert!(pdf["encryption_algorithm"].is_null());
+    assert_eq!(run.summary()["encrypted_files"], 1);
+}
@@ -2,10 +2,13 @@ use std::fs::File;
 use std::path::PathBuf;
 
 use parquet::file::reader::{FileReader, SerializedFileReader};
+use parquet::record::Field;
 
 use swiftbeaver::carve::CarvedFile;
 use swiftbeaver::config;
-use swiftbeaver::metadata::{self, EntropyRegion, MetadataBackendKind, RunSummary};
+use swiftbeaver::metadata::{
+    self, EntropyRegion, KeywordHit, MetadataBackendKind, RunSummary, SkippedRange,
+};
 use swiftbeaver::parsers::browser::{
     BrowserCookieRecord, BrowserDownloadRecord, BrowserHistoryRecord,
 };
@@ -46,6 +49,7 @@ fn parquet_writes_expected_files() {
         truncated: false,
         errors: Vec::new(),
         pattern_id: Some("jpeg_soi".to_string()),
+        ..Default::default()
     };
     sink.record_file(&file).expect("record file");
 
@@ -54,11 +58,43 @@ fn parquet_writes_expected_files() {
         artefact_kind: ArtefactKind::Url,
         content: "https://example.com/path?q=1".to_string(),
         encoding: "ascii".to_string(),
+        subtype: None,
         global_start: 100,
         global_end: 123,
+        containing_fs_path: None,
+        containing_inode: None,
+        offset_in_file: None,
     };
     sink.record_string(&artefact).expect("record url");
 
+    let custom = StringArtefact {
+        run_id: "run_001".to_string(),
+        artefact_kind: ArtefactKind::Custom("case_ref".to_string()),
+        content: "CASE-2024-0042".to_string(),
+        encoding: "ascii".to_string(),
+        subtype: None,
+        global_start: 200,
+        global_end: 213,
+        containing_fs_path: None,
+        containing_inode: None,
+        offset_in_file: None,
+    };
+    sink.record_string(&custom).expect("record custom");
+
+    let card = StringArtefact {
+        run_id: "run_001".to_string(),
+        artefact_kind: ArtefactKind::CreditCard,
+        content: "4111111111111111".to_string(),
+        encoding: "ascii".to_string(),
+        subtype: Some("visa".to_string()),
+        global_start: 300,
+        global_end: 316,
+        containing_fs_path: None,
+        containing_inode: None,
+        offset_in_file: None,
+    };
+    sink.record_string(&card).expect("record card");
+
     let visit_time = chrono::DateTime::from_timestamp(1_600_000_000, 0).map(|dt| dt.naive_utc());
     let record = BrowserHistoryRecord {
         run_id: "run_001".to_string(),
@@ -69,6 +105,9 @@ fn parquet_writes_expected_files() {
         visit_time,
         visit_source: Some("typed".to_string()),
         source_file: PathBuf::from("carved/history.sqlite"),
+        containing_fs_path: None,
+        containing_inode: None,
+        offset_in_file: None,
     };
     sink.record_history(&record).expect("record history");
 
@@ -86,6 +125,9 @@ fn parquet_writes_expected_files() {
         is_secure: Some(true),
         is_http_only: Some(true),
         source_file: PathBuf::from("carved/Cookies"),
+        containing_fs_path: None,
+        containing_inode: None,
+        offset_in_file: None,
     };
     sink.record_cookie(&cookie).expect("record cookie");
 
@@ -100,6 +142,9 @@ fn parquet_writes_expected_files() {
         total_bytes: Some(123),
         state: Some("1".to_string()),
         source_file: PathBuf::from("carved/History"),
+        containing_fs_path: None,
+        containing_inode: None,
+        offset_in_file: None,
     };
     sink.record_download(&download).expect("record download");
     let summary = RunSummary {
@@ -110,6 +155,16 @@ fn parquet_writes_expected_files() {
         files_carved: 1,
         string_spans: 3,
         artefacts_extracted: 4,
+        bytes_skipped: 4096,
+        skipped_ranges: vec![SkippedRange {
+            global_start: 4096,
+            global_end: 8192,
+            fill: 0,
+        }],
+        unaligned_hits: [("bmp".to_string(), 3)].into_iter().collect(),
+        known_good_files: 0,
+        known_bad_files: 0,
+        encrypted_files: 0,
     };
     sink.record_run_summary(&summary).expect("record summary");
     let entropy = EntropyRegion {
@@ -132,6 +187,8 @@ fn parquet_writes_expected_files() {
     let downloads_path = parquet_dir.join("browser_downloads.parquet");
     let summary_path = parquet_dir.join("run_summary.parquet");
     let entropy_path = parquet_dir.join("entropy_regions.parquet");
+    let custom_path = parquet_dir.join("artefacts_custom.parquet");
+    let cards_path = parquet_dir.join("artefacts_credit_cards.parquet");
 
     assert!(files_path.exists());
     assert!(urls_path.exists());
@@ -148,6 +205,8 @@ fn parquet_writes_expected_files() {
     assert_eq!(count_rows(&downloads_path), 1);
     assert_eq!(count_rows(&summary_path), 1);
     assert_eq!(count_rows(&entropy_path), 1);
+    assert_eq!(count_rows(&custom_path), 1);
+    assert_eq!(count_rows(&cards_path), 1);
 
     assert_has_column(&files_path, "evidence_sha256");
     assert_has_column(&urls_path, "evidence_sha256");
@@ -155,8 +214,84 @@ fn parquet_writes_expected_files() {
     assert_has_column(&cookies_path, "evidence_sha256");
     assert_has_column(&downloads_path, "evidence_sha256");
     assert_has_column(&summary_path, "evidence_sha256");
+    assert_has_column(&summary_path, "skipped_ranges");
+    assert_has_column(&summary_path, "unaligned_hits");
     assert_has_column(&entropy_path, "evidence_sha256");
     assert_has_column(&entropy_path, "entropy");
+    assert_has_column(&custom_path, "name");
+    assert_has_column(&cards_path, "card_number");
+    assert_has_column(&cards_path, "brand");
+}
+
+#[test]
+fn parquet_attributes_keyword_hits() {
+    let tmp = tempfile::tempdir().expect("tempdir");
+    let run_output_dir = tmp.path().join("run");
+    std::fs::create_dir_all(&run_output_dir).expect("run dir");
+    let loaded = config::load_config(None).expect("config");
+    let mut cfg = loaded.config;
+    cfg.parquet_row_group_size = 2;
+
+    let sink = metadata::build_sink(
+        MetadataBackendKind::Parquet,
+        &cfg,
+        "run_001",
+        "0.1.0",
+        &loaded.config_hash,
+        &PathBuf::from("evidence.dd"),
+        "",
+        &run_output_dir,
+    )
+    .expect("parquet sink");
+    for offset in [10, 200, 20, 300, 30] {
+        let hit = KeywordHit {
+            run_id: "run_001".to_string(),
+            term: "secret".to_string(),
+            encoding: "ascii".to_string(),
+            global_offset: offset,
+            length: 6,
+            context_start: offset,
+            context_hex: "736563726574".to_string(),
+            context_text: "secret".to_string(),
+            carved_path: None,
+        };
+        sink.record_keyword_hit(&hit).expect("record hit");
+    }
+    sink.attribute_keyword_hits(&|offset| (offset < 100).then(|| "carved/a.jpg".to_string()))
+        .expect("attribute hits");
+    sink.flush().expect("flush");
+    drop(sink);
+
+    let path = run_output_dir.join("parquet").join("keyword_hits.parquet");
+    let reader = SerializedFileReader::new(File::open(&path).expect("open")).expect("reader");
+    let rows: Vec<(i64, Option<String>)> = reader
+        .get_row_iter(None)
+        .expect("row iter")
+        .map(|row| {
+            let row = row.expect("row");
+            let mut offset = 0;
+            let mut carved_path = None;
+            for (name, field) in row.get_column_iter() {
+                match (name.as_str(), field) {
+                    ("global_offset", Field::Long(value)) => offset = *value,
+                    ("carved_path", Field::Str(value)) => carved_path = Some(value.clone()),
+                    _ => {}
+                }
+            }
+            (offset, carved_path)
+        })
+        .collect();
+    let attributed = Some("carved/a.jpg".to_string());
+    assert_eq!(
+        rows,
+        vec![
+            (10, attributed.clone()),
+            (200, None),
+            (20, attributed.clone()),
+            (300, None),
+            (30, attributed),
+        ]
+    );
 }
 
 fn count_rows(path: &PathBuf) -> usize {